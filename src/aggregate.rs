@@ -1,33 +1,89 @@
-use crate::{crossdev, InodeFilter, WalkOptions, WalkResult};
+use crate::{
+    crossdev, csv, get_entry_or_panic, json, prometheus,
+    traverse::{Traversal, Tree, TreeIndex},
+    DirEntry, InodeFilter, Progress, Threshold, WalkOptions, WalkResult, INTERRUPTED,
+};
 use anyhow::Result;
 use colored::{Color, Colorize};
 use filesize::PathExt;
-use std::time::Duration;
+use petgraph::Direction;
 use std::{
     borrow::Cow,
+    collections::HashMap,
     io,
-    path::Path,
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
-    },
-    thread,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
 };
 
-/// Aggregate the given `paths` and write information about them to `out` in a human-readable format.
-/// If `compute_total` is set, it will write an additional line with the total size across all given `paths`.
-/// If `sort_by_size_in_bytes` is set, we will sort all sizes (ascending) before outputting them.
+/// Return the size of `entry` on the device with `device_id`, or `None` if it couldn't be read.
+/// Directories have no size of their own, and neither do entries which were seen before according to `inodes`.
+/// If inodes are counted, the size of each entry is the one inode it takes, directories included.
+fn size_of_entry(
+    entry: &DirEntry,
+    walk_options: &WalkOptions,
+    inodes: &mut InodeFilter,
+    device_id: u64,
+) -> Option<u128> {
+    let count_inodes = walk_options.count_inodes;
+    match entry.client_state {
+        Some(Ok(ref m))
+            if (count_inodes || !m.is_dir())
+                && (walk_options.count_hard_links || inodes.add(m))
+                && (walk_options.cross_filesystems || crossdev::is_same_device(device_id, m)) =>
+        {
+            if count_inodes {
+                Some(1)
+            } else if walk_options.apparent_size {
+                Some(m.len())
+            } else {
+                entry.path().size_on_disk_fast(m).ok()
+            }
+        }
+        Some(Ok(_)) => Some(0),
+        Some(Err(_)) => None,
+        None if count_inodes => Some(1),
+        None => Some(0), // ignore directory
+    }
+    .map(u128::from)
+}
+
+/// What [`aggregate()`] writes about the paths it was given
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AggregateOptions {
+    /// If set, it will write an additional line with the total size across all given paths.
+    pub compute_total: bool,
+    /// If set, we will sort all sizes (ascending) before outputting them.
+    pub sort_by_size_in_bytes: bool,
+    /// If set, each path is preceded by the directories within it down to that depth, similar to `du`, with their
+    /// full path. They are sorted like the paths, or listed before their parent directory if
+    /// `sort_by_size_in_bytes` is unset.
+    pub max_depth: Option<usize>,
+    /// If set, only paths and directories whose size it includes are written, but all are counted in the total.
+    pub threshold: Option<Threshold>,
+}
+
+/// Aggregate the given `paths` and write information about them to `out` in a human-readable format, as
+/// configured by `options`. If `err` is set, a line telling how far the walk got is kept up to date on it while
+/// walking, and cleared before anything is written to `out`.
+/// If the walk is [interrupted][crate::INTERRUPTED], the path being walked and the total are marked as incomplete, and
+/// all following paths are left out.
 pub fn aggregate(
     mut out: impl io::Write,
-    err: Option<impl io::Write + Send + 'static>,
+    err: Option<impl io::Write>,
     walk_options: WalkOptions,
-    compute_total: bool,
-    sort_by_size_in_bytes: bool,
+    options: AggregateOptions,
     paths: impl IntoIterator<Item = impl AsRef<Path>>,
 ) -> Result<(WalkResult, Statistics)> {
+    let AggregateOptions {
+        compute_total,
+        sort_by_size_in_bytes,
+        max_depth,
+        threshold,
+    } = options;
+    let is_shown = |num_bytes| threshold.is_none_or(|t| t.includes(num_bytes));
     let mut res = WalkResult::default();
     let mut stats = Statistics {
-        smallest_file_in_bytes: u128::max_value(),
+        smallest_file_in_bytes: u128::MAX,
         ..Default::default()
     };
     let mut total = 0;
@@ -35,98 +91,121 @@ pub fn aggregate(
     let mut aggregates = Vec::new();
     let mut inodes = InodeFilter::default();
     let paths: Vec<_> = paths.into_iter().collect();
-    let shared_count = Arc::new(AtomicU64::new(0));
-
-    if let Some(mut err) = err {
-        thread::spawn({
-            let shared_count = Arc::clone(&shared_count);
-            move || {
-                thread::sleep(Duration::from_secs(1));
-                loop {
-                    thread::sleep(Duration::from_millis(100));
-                    write!(
-                        err,
-                        "Enumerating {} entries\r",
-                        shared_count.load(Ordering::Acquire)
-                    )
-                    .ok();
-                }
-            }
-        });
-    }
+    let parallelism = walk_options.parallelism()?;
+    let mut progress = err.map(|err| Progress::new(err, walk_options.byte_format));
 
+    let mut is_incomplete = false;
     for path in paths.into_iter() {
+        if is_incomplete {
+            break;
+        }
         num_roots += 1;
         let mut num_bytes = 0u128;
         let mut num_errors = 0u64;
+        let mut directories = HashMap::<PathBuf, u128>::new();
         let device_id = crossdev::init(path.as_ref())?;
-        for entry in walk_options.iter_from_path(path.as_ref()) {
+        for entry in walk_options.iter_from_path(path.as_ref(), device_id, parallelism.clone()) {
+            if INTERRUPTED.load(Ordering::Relaxed) {
+                is_incomplete = true;
+                break;
+            }
             stats.entries_traversed += 1;
-            shared_count.fetch_add(1, Ordering::Relaxed);
             match entry {
                 Ok(entry) => {
-                    let file_size = match entry.client_state {
-                        Some(Ok(ref m))
-                            if !m.is_dir()
-                                && (walk_options.count_hard_links || inodes.add(m))
-                                && (walk_options.cross_filesystems
-                                    || crossdev::is_same_device(device_id, m)) =>
-                        {
-                            if walk_options.apparent_size {
-                                m.len()
-                            } else {
-                                entry.path().size_on_disk_fast(m).unwrap_or_else(|_| {
-                                    num_errors += 1;
-                                    0
-                                })
-                            }
-                        }
-                        Some(Ok(_)) => 0,
-                        Some(Err(_)) => {
+                    let file_size = size_of_entry(&entry, &walk_options, &mut inodes, device_id)
+                        .unwrap_or_else(|| {
                             num_errors += 1;
                             0
-                        }
-                        None => 0, // ignore directory
-                    } as u128;
+                        });
                     stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(file_size);
                     stats.smallest_file_in_bytes = stats.smallest_file_in_bytes.min(file_size);
                     num_bytes += file_size;
+                    if let Some(progress) = progress.as_mut() {
+                        progress.update(
+                            stats.entries_traversed,
+                            Some(total + num_bytes),
+                            &entry.parent_path,
+                        );
+                    }
+                    if let Some(max_depth) = max_depth {
+                        if entry.file_type.is_dir() && (1..=max_depth).contains(&entry.depth) {
+                            // Their own size, like the inode they take, is part of theirs
+                            directories.insert(entry.path(), file_size);
+                        }
+                        // The parent is at `depth - 1`, and its ancestors are yielded in order of decreasing depth
+                        let parent_depth = entry.depth.saturating_sub(1);
+                        for dir in entry
+                            .parent_path
+                            .ancestors()
+                            .skip(parent_depth.saturating_sub(max_depth))
+                            .take(parent_depth.min(max_depth))
+                        {
+                            if let Some(size) = directories.get_mut(dir) {
+                                *size += file_size;
+                            }
+                        }
+                    }
                 }
                 Err(_) => num_errors += 1,
             }
         }
 
+        let mut directories: Vec<_> = directories
+            .into_iter()
+            .filter(|&(_, num_bytes)| is_shown(num_bytes))
+            .collect();
+        directories.sort_by(|(l, _), (r, _)| children_first(l, r));
         if sort_by_size_in_bytes {
-            aggregates.push((path.as_ref().to_owned(), num_bytes, num_errors));
-        } else {
-            output_colored_path(
-                &mut out,
-                &walk_options,
-                &path,
+            directories.sort_by_key(|&(_, num_bytes)| num_bytes);
+            aggregates.push((
+                path.as_ref().to_owned(),
                 num_bytes,
                 num_errors,
-                path_color_of(&path),
-            )?;
+                is_incomplete,
+                directories,
+            ));
+        } else {
+            if let Some(progress) = progress.as_mut() {
+                progress.clear();
+            }
+            output_directories(&mut out, &walk_options, directories, is_incomplete)?;
+            if is_shown(num_bytes) {
+                output_colored_path(
+                    &mut out,
+                    &walk_options,
+                    &path,
+                    num_bytes,
+                    num_errors,
+                    is_incomplete,
+                    path_color_of(&path),
+                )?;
+            }
         }
+        stats.path_sizes.push((path.as_ref().to_owned(), num_bytes));
         total += num_bytes;
         res.num_errors += num_errors;
     }
 
+    drop(progress);
     if stats.entries_traversed == 0 {
         stats.smallest_file_in_bytes = 0;
     }
 
     if sort_by_size_in_bytes {
-        aggregates.sort_by_key(|&(_, num_bytes, _)| num_bytes);
-        for (path, num_bytes, num_errors) in aggregates.into_iter() {
-            output_colored_path(
-                &mut out,
-                &walk_options,
-                &path,
-                num_bytes,
-                num_errors,
-                path_color_of(&path),
-            )?;
+        aggregates.sort_by_key(|&(_, num_bytes, _, _, _)| num_bytes);
+        for (path, num_bytes, num_errors, is_incomplete, directories) in aggregates.into_iter() {
+            output_directories(&mut out, &walk_options, directories, is_incomplete)?;
+            if is_shown(num_bytes) {
+                output_colored_path(
+                    &mut out,
+                    &walk_options,
+                    &path,
+                    num_bytes,
+                    num_errors,
+                    is_incomplete,
+                    path_color_of(&path),
+                )?;
+            }
         }
     }
 
@@ -137,12 +216,648 @@ pub fn aggregate(
             Path::new("total"),
             total,
             res.num_errors,
+            is_incomplete,
             None,
         )?;
     }
     Ok((res, stats))
 }
 
+/// Aggregate the given `paths` and write a JSON document with the tree of all scanned entries to `out`.
+///
+/// Each entry is an object with its `path`, `size` in bytes, the amount of `entries` within it, whether its
+/// meta-data could not be read (`error`), and its `children` sorted by name, if there are any.
+/// If `sort_by_size_in_bytes` is set, the given `paths` are sorted by their size (ascending), just like in
+/// [`aggregate()`]. If `max_depth` is set, the children of entries at that depth are left out.
+/// If the walk was [interrupted][crate::INTERRUPTED], the document contains `"incomplete":true`.
+pub fn aggregate_json(
+    mut out: impl io::Write,
+    mut err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    sort_by_size_in_bytes: bool,
+    max_depth: Option<usize>,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<(WalkResult, Statistics)> {
+    let paths = paths.into_iter().map(|p| p.as_ref().to_owned()).collect();
+    let traversal = Traversal::from_walk(walk_options, paths, |t| {
+        if let Some(err) = err.as_mut() {
+            write!(err, "Enumerating {} entries\r", t.entries_traversed).ok();
+        }
+        Ok(false)
+    })?;
+    let tree = &traversal.tree;
+    let stats = statistics_of(&traversal);
+
+    let mut roots: Vec<_> = tree
+        .neighbors_directed(traversal.root_index, Direction::Outgoing)
+        .collect();
+    // Neighbors are yielded in reverse order of insertion
+    roots.reverse();
+    if sort_by_size_in_bytes {
+        roots.sort_by_key(|&idx| get_entry_or_panic(tree, idx).size);
+    }
+
+    write!(
+        out,
+        "{{\"total_bytes\":{},\"entries_traversed\":{},\"io_errors\":{},",
+        traversal.total_bytes.unwrap_or(0),
+        traversal.entries_traversed,
+        traversal.io_errors
+    )?;
+    if traversal.is_incomplete {
+        out.write_all(b"\"incomplete\":true,")?;
+    }
+    out.write_all(b"\"roots\":[")?;
+    for (ridx, root) in roots.into_iter().enumerate() {
+        if ridx != 0 {
+            out.write_all(b",")?;
+        }
+        output_json_entries(&mut out, tree, root, max_depth)?;
+    }
+    out.write_all(b"]}\n")?;
+
+    Ok((
+        WalkResult {
+            num_errors: traversal.io_errors,
+        },
+        stats,
+    ))
+}
+
+/// Walk the given `paths` and write one JSON object per entry to `out` as soon as its size is known, each on a line
+/// of its own.
+///
+/// Each object has the `path` of the entry, its `size` in bytes, its `depth` below the path it was found in, and its
+/// `type`, one of `file`, `dir`, `symlink` or `other`. An `error` is set if it couldn't be read. Directories are
+/// written after all of their entries, with the sum of their sizes, and paths after all directories within them.
+/// If the walk is [interrupted][crate::INTERRUPTED], no further entries are written.
+pub fn aggregate_ndjson(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<(WalkResult, Statistics)> {
+    let mut res = WalkResult::default();
+    let mut stats = Statistics {
+        smallest_file_in_bytes: u128::MAX,
+        ..Default::default()
+    };
+    let mut inodes = InodeFilter::default();
+    let parallelism = walk_options.parallelism()?;
+
+    /// A directory whose entries are still being walked
+    struct OpenDirectory {
+        path: PathBuf,
+        depth: usize,
+        size: u128,
+        has_error: bool,
+    }
+    fn close(out: &mut impl io::Write, open: &mut Vec<OpenDirectory>) -> io::Result<()> {
+        let dir = open.pop().expect("a directory to close");
+        if let Some(parent) = open.last_mut() {
+            parent.size += dir.size;
+        }
+        output_ndjson_entry(out, &dir.path, dir.size, dir.depth, "dir", dir.has_error)
+    }
+
+    'walk: for path in paths {
+        let device_id = crossdev::init(path.as_ref())?;
+        let mut open = Vec::<OpenDirectory>::new();
+        let mut path_size = 0;
+        for entry in walk_options.iter_from_path(path.as_ref(), device_id, parallelism.clone()) {
+            if INTERRUPTED.load(Ordering::Relaxed) {
+                break 'walk;
+            }
+            stats.entries_traversed += 1;
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    res.num_errors += 1;
+                    match open.last_mut() {
+                        Some(dir) => dir.has_error = true,
+                        None => {
+                            let path = err.path().unwrap_or_else(|| path.as_ref());
+                            output_ndjson_entry(&mut out, path, 0, 0, "other", true)?;
+                        }
+                    }
+                    continue;
+                }
+            };
+            while open.last().is_some_and(|dir| dir.depth >= entry.depth) {
+                close(&mut out, &mut open)?;
+            }
+            let entry_path = if entry.depth == 0 {
+                path.as_ref().to_owned()
+            } else {
+                entry.path()
+            };
+            let size = size_of_entry(&entry, &walk_options, &mut inodes, device_id);
+            if size.is_none() {
+                res.num_errors += 1;
+            }
+            let size = size.unwrap_or(0);
+            path_size += size;
+            if entry.file_type.is_dir() {
+                // Its own size, like the inode it takes, is added to its parent along with those of its entries
+                open.push(OpenDirectory {
+                    path: entry_path,
+                    depth: entry.depth,
+                    size,
+                    has_error: false,
+                });
+                continue;
+            }
+            if let Some(parent) = open.last_mut() {
+                parent.size += size;
+            }
+            stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(size);
+            stats.smallest_file_in_bytes = stats.smallest_file_in_bytes.min(size);
+            let kind = if entry.file_type.is_file() {
+                "file"
+            } else if entry.file_type.is_symlink() {
+                "symlink"
+            } else {
+                "other"
+            };
+            let has_error = matches!(entry.client_state, Some(Err(_)));
+            output_ndjson_entry(&mut out, &entry_path, size, entry.depth, kind, has_error)?;
+        }
+        while !open.is_empty() {
+            close(&mut out, &mut open)?;
+        }
+        stats.path_sizes.push((path.as_ref().to_owned(), path_size));
+    }
+
+    if stats.smallest_file_in_bytes == u128::MAX {
+        stats.smallest_file_in_bytes = 0;
+    }
+    Ok((res, stats))
+}
+
+fn output_ndjson_entry(
+    out: &mut impl io::Write,
+    path: &Path,
+    size: u128,
+    depth: usize,
+    kind: &str,
+    has_error: bool,
+) -> io::Result<()> {
+    out.write_all(b"{\"path\":")?;
+    json::write_str(out, &path.to_string_lossy())?;
+    write!(
+        out,
+        ",\"size\":{},\"depth\":{},\"type\":\"{}\"",
+        size, depth, kind
+    )?;
+    if has_error {
+        out.write_all(b",\"error\":true")?;
+    }
+    out.write_all(b"}\n")
+}
+
+fn statistics_of(traversal: &Traversal) -> Statistics {
+    let tree = &traversal.tree;
+    let mut stats = Statistics {
+        entries_traversed: traversal.entries_traversed,
+        path_sizes: tree
+            .neighbors_directed(traversal.root_index, Direction::Outgoing)
+            .map(|idx| {
+                let entry = get_entry_or_panic(tree, idx);
+                (tree.names[entry.name].to_path_buf(), u128::from(entry.size))
+            })
+            .collect(),
+        ..Default::default()
+    };
+    // Neighbors are yielded in reverse order of insertion
+    stats.path_sizes.reverse();
+    let files = tree
+        .node_indices()
+        .filter(|&idx| idx != traversal.root_index)
+        .map(|idx| get_entry_or_panic(tree, idx))
+        .filter(|entry| entry.entry_count == 0);
+    for (num_files, file) in files.enumerate() {
+        let size = u128::from(file.size);
+        stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(size);
+        stats.smallest_file_in_bytes = if num_files == 0 {
+            size
+        } else {
+            stats.smallest_file_in_bytes.min(size)
+        };
+    }
+    stats
+}
+
+/// Aggregate the given `paths` and write a table to `out`, with a header followed by one row per path with its
+/// `path`, `size` in bytes, the amount of `entries` within it and the amount of IO `errors` encountered in it.
+///
+/// Fields are separated by `delimiter`, like `b','` for CSV or `b'\t'` for tab-separated values, and quoted as
+/// described in RFC 4180 if needed. Rows are chosen and ordered just like the lines of [`aggregate()`].
+/// Only the totals of the rows are kept while walking, see `walk_totals()`.
+pub fn aggregate_table(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    options: AggregateOptions,
+    delimiter: u8,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<(WalkResult, Statistics)> {
+    let (mut roots, res, stats) = walk_totals(&walk_options, options.max_depth, err, paths)?;
+    let is_shown = |size| options.threshold.is_none_or(|t| t.includes(size));
+    if options.sort_by_size_in_bytes {
+        roots.sort_by_key(|root| root.totals.size);
+    }
+
+    let header = ["path", "size", "entries", "errors"].join(&(delimiter as char).to_string());
+    writeln!(out, "{}", header)?;
+    let mut total = PathTotals::new(PathBuf::from("total"));
+    for root in &roots {
+        total.size += root.totals.size;
+        total.entry_count += root.totals.entry_count + 1;
+    }
+    total.num_errors = res.num_errors;
+    let num_roots = roots.len();
+    for WalkedPath {
+        totals,
+        mut directories,
+    } in roots
+    {
+        directories.retain(|dir| is_shown(dir.size));
+        directories.sort_by(|l, r| children_first(&l.path, &r.path));
+        if options.sort_by_size_in_bytes {
+            directories.sort_by_key(|dir| dir.size);
+        }
+        for dir in &directories {
+            output_row(&mut out, delimiter, dir)?;
+        }
+        if is_shown(totals.size) {
+            output_row(&mut out, delimiter, &totals)?;
+        }
+    }
+    if num_roots > 1 && options.compute_total {
+        output_row(&mut out, delimiter, &total)?;
+    }
+    Ok((res, stats))
+}
+
+/// Aggregate the given `paths` and write their size, amount of entries and errors to `out` as gauges in the text
+/// exposition format of Prometheus, to be read by the textfile collector of the node exporter for instance.
+///
+/// Each path is a sample with a `path` label, followed by the directories within it down to `max_depth` of
+/// `options`, if they are included by its `threshold`. Paths are written in the order they were given in, and
+/// directories in the order of [`aggregate_table()`] without sorting. There is no total.
+pub fn aggregate_prometheus(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    options: AggregateOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<(WalkResult, Statistics)> {
+    let (roots, res, stats) = walk_totals(&walk_options, options.max_depth, err, paths)?;
+    let is_shown = |size| options.threshold.is_none_or(|t| t.includes(size));
+
+    let mut samples = Vec::new();
+    for WalkedPath {
+        totals,
+        mut directories,
+    } in roots
+    {
+        directories.sort_by(|l, r| children_first(&l.path, &r.path));
+        samples.push(totals);
+        samples.extend(directories);
+    }
+    samples.retain(|sample| is_shown(sample.size));
+
+    let metrics = [
+        (
+            "dua_path_bytes",
+            "The size of a path in bytes, as disk usage or apparent size.",
+        ),
+        ("dua_path_entries", "The amount of entries within a path."),
+        (
+            "dua_path_errors",
+            "The amount of entries within a path which couldn't be read.",
+        ),
+    ];
+    for (metric, (name, help)) in metrics.iter().enumerate() {
+        writeln!(out, "# HELP {} {}", name, help)?;
+        writeln!(out, "# TYPE {} gauge", name)?;
+        for sample in &samples {
+            let value = match metric {
+                0 => sample.size,
+                1 => sample.entry_count as u128,
+                _ => sample.num_errors as u128,
+            };
+            write!(out, "{}{{path=", name)?;
+            prometheus::write_label_value(&mut out, &sample.path.to_string_lossy())?;
+            writeln!(out, "}} {}", value)?;
+        }
+    }
+    Ok((res, stats))
+}
+
+/// The size of a path or a directory within it, along with the amount of entries and IO errors within it
+struct PathTotals {
+    path: PathBuf,
+    size: u128,
+    entry_count: u64,
+    num_errors: u64,
+}
+
+impl PathTotals {
+    fn new(path: PathBuf) -> Self {
+        PathTotals {
+            path,
+            size: 0,
+            entry_count: 0,
+            num_errors: 0,
+        }
+    }
+}
+
+/// One of the paths [`walk_totals()`] walked, along with the directories within it down to the maximum depth
+struct WalkedPath {
+    totals: PathTotals,
+    directories: Vec<PathTotals>,
+}
+
+/// Walk `paths` and return the totals of each of them, along with those of the directories within them down to
+/// `max_depth`. If `err` is set, a line telling how far the walk got is kept up to date on it, and cleared when done.
+///
+/// Instead of a tree of all entries, only the running totals of the directories being walked are kept, one per level
+/// of depth, so the memory needed grows with the depth of the directories and the amount of those that are returned,
+/// not with the amount of entries. If the walk is [interrupted][crate::INTERRUPTED], all following paths are left out.
+fn walk_totals(
+    walk_options: &WalkOptions,
+    max_depth: Option<usize>,
+    err: Option<impl io::Write>,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<(Vec<WalkedPath>, WalkResult, Statistics)> {
+    let mut res = WalkResult::default();
+    let mut stats = Statistics {
+        smallest_file_in_bytes: u128::MAX,
+        ..Default::default()
+    };
+    fn count_file(stats: &mut Statistics, size: u128) {
+        stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(size);
+        stats.smallest_file_in_bytes = stats.smallest_file_in_bytes.min(size);
+    }
+    let mut inodes = InodeFilter::default();
+    let parallelism = walk_options.parallelism()?;
+    let mut progress = err.map(|err| Progress::new(err, walk_options.byte_format));
+    let mut total_bytes = 0;
+    let mut walked = Vec::new();
+
+    /// A directory whose entries are still being walked
+    struct OpenDirectory {
+        totals: PathTotals,
+        depth: usize,
+    }
+    /// Add the totals of the innermost directory to its parent, and keep them if it's not deeper than `max_depth`.
+    fn close(
+        open: &mut Vec<OpenDirectory>,
+        directories: &mut Vec<PathTotals>,
+        max_depth: Option<usize>,
+        stats: &mut Statistics,
+    ) -> Option<PathTotals> {
+        let dir = open.pop().expect("a directory to close");
+        if dir.totals.entry_count == 0 {
+            // Empty directories count as files without size, like in a tree of entries
+            stats.smallest_file_in_bytes = 0;
+        }
+        match open.last_mut() {
+            Some(parent) => {
+                parent.totals.size += dir.totals.size;
+                parent.totals.entry_count += dir.totals.entry_count;
+                parent.totals.num_errors += dir.totals.num_errors;
+                if max_depth.is_some_and(|max_depth| dir.depth <= max_depth) {
+                    directories.push(dir.totals);
+                }
+                None
+            }
+            None => Some(dir.totals),
+        }
+    }
+
+    'walk: for path in paths {
+        let path = path.as_ref();
+        let device_id = crossdev::init(path)?;
+        let mut open = Vec::<OpenDirectory>::new();
+        let mut root = None::<PathTotals>;
+        let mut directories = Vec::new();
+        for entry in walk_options.iter_from_path(path, device_id, parallelism.clone()) {
+            stats.entries_traversed += 1;
+            let is_interrupted = INTERRUPTED.load(Ordering::Relaxed);
+            match entry {
+                Ok(entry) => {
+                    while open.last().is_some_and(|dir| dir.depth >= entry.depth) {
+                        root = root.or(close(&mut open, &mut directories, max_depth, &mut stats));
+                    }
+                    let size = size_of_entry(&entry, walk_options, &mut inodes, device_id);
+                    let num_errors = size.is_none() as u64;
+                    let size = size.unwrap_or(0);
+                    res.num_errors += num_errors;
+                    total_bytes += size;
+                    let is_dir = entry.file_type.is_dir() || entry.read_children_path.is_some();
+                    if !is_dir {
+                        count_file(&mut stats, size);
+                    }
+                    match open.last_mut() {
+                        Some(parent) => {
+                            // The own size of directories, like the inode they take, is added with their entries
+                            if !is_dir {
+                                parent.totals.size += size;
+                            }
+                            parent.totals.entry_count += 1;
+                            parent.totals.num_errors += num_errors;
+                        }
+                        None if !is_dir => {
+                            root = Some(PathTotals {
+                                size,
+                                num_errors,
+                                ..PathTotals::new(path.to_owned())
+                            })
+                        }
+                        None => {}
+                    }
+                    if is_dir {
+                        open.push(OpenDirectory {
+                            totals: PathTotals {
+                                size,
+                                ..PathTotals::new(if entry.depth == 0 {
+                                    path.to_owned()
+                                } else {
+                                    entry.path()
+                                })
+                            },
+                            depth: entry.depth,
+                        });
+                    }
+                    if let Some(progress) = progress.as_mut() {
+                        progress.update(
+                            stats.entries_traversed,
+                            Some(total_bytes),
+                            &entry.parent_path,
+                        );
+                    }
+                }
+                Err(err) => {
+                    res.num_errors += 1;
+                    let err_path = err.path().unwrap_or(path);
+                    let dir = open
+                        .iter()
+                        .rposition(|dir| err_path.starts_with(&dir.totals.path))
+                        .or_else(|| open.len().checked_sub(1));
+                    match dir {
+                        Some(dir) => open[dir].totals.num_errors += 1,
+                        // The path itself couldn't be read
+                        None => {
+                            count_file(&mut stats, 0);
+                            let root = root.get_or_insert_with(|| PathTotals::new(path.to_owned()));
+                            root.num_errors += 1;
+                        }
+                    }
+                }
+            }
+            if is_interrupted {
+                break;
+            }
+        }
+        while !open.is_empty() {
+            root = root.or(close(&mut open, &mut directories, max_depth, &mut stats));
+        }
+        if let Some(totals) = root {
+            stats.path_sizes.push((totals.path.clone(), totals.size));
+            walked.push(WalkedPath {
+                totals,
+                directories,
+            });
+        }
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            break 'walk;
+        }
+    }
+
+    if let Some(progress) = progress.as_mut() {
+        progress.clear();
+    }
+    if stats.smallest_file_in_bytes == u128::MAX {
+        stats.smallest_file_in_bytes = 0;
+    }
+    Ok((walked, res, stats))
+}
+
+fn output_row(out: &mut impl io::Write, delimiter: u8, totals: &PathTotals) -> io::Result<()> {
+    csv::write_field(out, &totals.path.to_string_lossy(), delimiter)?;
+    writeln!(
+        out,
+        "{d}{}{d}{}{d}{}",
+        totals.size,
+        totals.entry_count,
+        totals.num_errors,
+        d = delimiter as char
+    )
+}
+
+/// Write the entry at `node_idx` and all entries below it up to `max_depth`, each with its children sorted by name.
+///
+/// Entries still to write are kept on a stack of their own, so trees of any depth can be written.
+fn output_json_entries(
+    out: &mut impl io::Write,
+    tree: &Tree,
+    node_idx: TreeIndex,
+    max_depth: Option<usize>,
+) -> io::Result<()> {
+    // Entries along with whether they are the first of their siblings, and `None` to end the directory whose
+    // children were written last
+    let mut stack = vec![Some((node_idx, true))];
+    // The names of the directories containing the next entry
+    let mut open: Vec<&Path> = Vec::new();
+    while let Some(next) = stack.pop() {
+        let (idx, is_first) = match next {
+            Some(next) => next,
+            None => {
+                out.write_all(b"]}")?;
+                open.pop();
+                continue;
+            }
+        };
+        if !is_first {
+            out.write_all(b",")?;
+        }
+        let entry = get_entry_or_panic(tree, idx);
+        let path: PathBuf = open
+            .iter()
+            .copied()
+            .chain(std::iter::once(&tree.names[entry.name]))
+            .collect();
+        out.write_all(b"{\"path\":")?;
+        json::write_str(out, &path.to_string_lossy())?;
+        write!(
+            out,
+            ",\"size\":{},\"entries\":{},\"error\":{}",
+            entry.size, entry.entry_count, entry.metadata_io_error
+        )?;
+
+        let mut children: Vec<_> = tree.neighbors_directed(idx, Direction::Outgoing).collect();
+        if max_depth == Some(open.len()) {
+            children.clear();
+        }
+        if children.is_empty() {
+            out.write_all(b"}")?;
+            continue;
+        }
+        // Sorted in reverse, to be popped in order
+        children.sort_by(|&l, &r| tree.name_of(r).cmp(tree.name_of(l)));
+        out.write_all(b",\"children\":[")?;
+        open.push(&tree.names[entry.name]);
+        stack.push(None);
+        let last = children.len() - 1;
+        stack.extend(
+            children
+                .into_iter()
+                .enumerate()
+                .map(|(pos, child)| Some((child, pos == last))),
+        );
+    }
+    Ok(())
+}
+
+/// Order paths by their components, but each directory after all of its children, like `du` does.
+fn children_first(l: &Path, r: &Path) -> std::cmp::Ordering {
+    use std::cmp::Ordering::*;
+    let (mut l, mut r) = (l.components(), r.components());
+    loop {
+        match (l.next(), r.next()) {
+            (Some(l), Some(r)) => match l.cmp(&r) {
+                Equal => continue,
+                ordering => return ordering,
+            },
+            (Some(_), None) => return Less,
+            (None, Some(_)) => return Greater,
+            (None, None) => return Equal,
+        }
+    }
+}
+
+fn output_directories(
+    out: &mut impl io::Write,
+    options: &WalkOptions,
+    directories: Vec<(PathBuf, u128)>,
+    is_incomplete: bool,
+) -> io::Result<()> {
+    for (path, num_bytes) in directories {
+        output_colored_path(
+            out,
+            options,
+            path,
+            num_bytes,
+            0,
+            is_incomplete,
+            Some(Color::Cyan),
+        )?;
+    }
+    Ok(())
+}
+
 fn path_color_of(path: impl AsRef<Path>) -> Option<Color> {
     if path.as_ref().is_file() {
         None
@@ -157,11 +872,12 @@ fn output_colored_path(
     path: impl AsRef<Path>,
     num_bytes: u128,
     num_errors: u64,
+    is_incomplete: bool,
     path_color: Option<colored::Color>,
 ) -> std::result::Result<(), io::Error> {
     writeln!(
         out,
-        "{:>byte_column_width$} {}{}",
+        "{:>byte_column_width$} {}{}{}",
         options
             .byte_format
             .display(num_bytes)
@@ -175,6 +891,11 @@ fn output_colored_path(
                 None => path.normal(),
             }
         },
+        if is_incomplete {
+            " (incomplete)".red().to_string()
+        } else {
+            String::new()
+        },
         if num_errors == 0 {
             Cow::Borrowed("")
         } else {
@@ -197,4 +918,273 @@ pub struct Statistics {
     pub smallest_file_in_bytes: u128,
     /// The size of the largest file encountered in bytes
     pub largest_file_in_bytes: u128,
+    /// The size of each input path in bytes, in the order they were walked in
+    pub path_sizes: Vec<(PathBuf, u128)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ByteFormat, TraversalSorting};
+
+    #[test]
+    fn it_lists_directories_down_to_the_max_depth_before_their_root() -> Result<()> {
+        let mut out = Vec::new();
+        aggregate(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            AggregateOptions {
+                compute_total: true,
+                sort_by_size_in_bytes: true,
+                max_depth: Some(2),
+                threshold: None,
+            },
+            Some(Path::new("tests/fixtures/sample-02")),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", out);
+        for (line, (size, path)) in lines.iter().zip(&[
+            ("0 b", "sample-02/dir/empty-dir"),
+            ("1024 b", "sample-02/dir/sub"),
+            ("1283 b", "sample-02/dir"),
+            ("1540 b", "sample-02"),
+        ]) {
+            assert!(line.contains(size) && line.ends_with(path), "{}", line);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn directories_count_their_own_inode_along_with_those_of_their_entries() -> Result<()> {
+        let mut out = Vec::new();
+        aggregate(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .count_inodes(true)
+                .byte_format(ByteFormat::Inodes),
+            AggregateOptions {
+                compute_total: false,
+                sort_by_size_in_bytes: true,
+                max_depth: Some(1),
+                threshold: None,
+            },
+            Some(Path::new("tests/fixtures/sample-02")),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "7 tests/fixtures/sample-02/dir",
+                "10 tests/fixtures/sample-02"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_out_paths_the_threshold_excludes() -> Result<()> {
+        let walk = |threshold: &str| -> Result<Vec<String>> {
+            let mut out = Vec::new();
+            aggregate(
+                &mut out,
+                None::<io::Sink>,
+                WalkOptions::default()
+                    .threads(1)
+                    .byte_format(ByteFormat::Bytes)
+                    .apparent_size(true),
+                AggregateOptions {
+                    compute_total: true,
+                    sort_by_size_in_bytes: true,
+                    max_depth: Some(2),
+                    threshold: Some(threshold.parse().map_err(anyhow::Error::msg)?),
+                },
+                ["tests/fixtures/sample-02/a", "tests/fixtures/sample-02/dir"].iter(),
+            )?;
+            Ok(String::from_utf8(out)?
+                .lines()
+                .map(|l| l.trim_start().to_owned())
+                .collect())
+        };
+        assert_eq!(
+            walk("1024")?,
+            vec![
+                "1024 b tests/fixtures/sample-02/dir/sub",
+                "1283 b tests/fixtures/sample-02/dir",
+                "1539 b total"
+            ]
+        );
+        assert_eq!(
+            walk("-256")?,
+            vec![
+                "256 b tests/fixtures/sample-02/a",
+                "0 b tests/fixtures/sample-02/dir/empty-dir",
+                "1539 b total"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_a_row_per_path_with_a_header() -> Result<()> {
+        let mut out = Vec::new();
+        aggregate_table(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default().threads(1).apparent_size(true),
+            AggregateOptions {
+                compute_total: true,
+                sort_by_size_in_bytes: true,
+                max_depth: Some(1),
+                threshold: None,
+            },
+            b',',
+            ["tests/fixtures/sample-02/dir", "tests/fixtures/sample-02/a"].iter(),
+        )?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "path,size,entries,errors
+tests/fixtures/sample-02/a,256,0,0
+tests/fixtures/sample-02/dir/empty-dir,0,1,0
+tests/fixtures/sample-02/dir/sub,1024,1,0
+tests/fixtures/sample-02/dir,1283,6,0
+total,1539,8,0
+"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_totals_of_directories_down_to_the_max_depth_are_kept() -> Result<()> {
+        let walk = |max_depth| {
+            walk_totals(
+                &WalkOptions::default()
+                    .threads(1)
+                    .apparent_size(true)
+                    .sorting(TraversalSorting::AlphabeticalByFileName),
+                max_depth,
+                None::<io::Sink>,
+                ["tests/fixtures/sample-02", "tests/fixtures/sample-02/a"].iter(),
+            )
+        };
+        let (roots, res, stats) = walk(None)?;
+        assert_eq!(res.num_errors, 0);
+        assert_eq!(stats.entries_traversed, 11);
+        let totals: Vec<_> = roots
+            .iter()
+            .map(|root| (root.totals.size, root.totals.entry_count))
+            .collect();
+        assert_eq!(totals, vec![(1540, 9), (256, 0)]);
+        assert!(roots.iter().all(|root| root.directories.is_empty()));
+
+        let (roots, _, _) = walk(Some(1))?;
+        let directories: Vec<_> = roots[0]
+            .directories
+            .iter()
+            .map(|dir| (dir.path.to_string_lossy().into_owned(), dir.size))
+            .collect();
+        assert_eq!(
+            directories,
+            vec![("tests/fixtures/sample-02/dir".to_owned(), 1283)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_gauges_per_metric_for_prometheus() -> Result<()> {
+        let mut out = Vec::new();
+        aggregate_prometheus(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default().threads(1).apparent_size(true),
+            AggregateOptions {
+                max_depth: Some(1),
+                threshold: Some(Threshold::AtLeast(1)),
+                ..Default::default()
+            },
+            ["tests/fixtures/sample-02/dir", "tests/fixtures/sample-02/a"].iter(),
+        )?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            r#"# HELP dua_path_bytes The size of a path in bytes, as disk usage or apparent size.
+# TYPE dua_path_bytes gauge
+dua_path_bytes{path="tests/fixtures/sample-02/dir"} 1283
+dua_path_bytes{path="tests/fixtures/sample-02/dir/sub"} 1024
+dua_path_bytes{path="tests/fixtures/sample-02/a"} 256
+# HELP dua_path_entries The amount of entries within a path.
+# TYPE dua_path_entries gauge
+dua_path_entries{path="tests/fixtures/sample-02/dir"} 6
+dua_path_entries{path="tests/fixtures/sample-02/dir/sub"} 1
+dua_path_entries{path="tests/fixtures/sample-02/a"} 0
+# HELP dua_path_errors The amount of entries within a path which couldn't be read.
+# TYPE dua_path_errors gauge
+dua_path_errors{path="tests/fixtures/sample-02/dir"} 0
+dua_path_errors{path="tests/fixtures/sample-02/dir/sub"} 0
+dua_path_errors{path="tests/fixtures/sample-02/a"} 0
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_directories_after_their_entries_as_ndjson() -> Result<()> {
+        let mut out = Vec::new();
+        let (res, stats) = aggregate_ndjson(
+            &mut out,
+            WalkOptions::default()
+                .threads(1)
+                .apparent_size(true)
+                .sorting(TraversalSorting::AlphabeticalByFileName),
+            ["tests/fixtures/sample-02/dir", "tests/fixtures/sample-02/a"].iter(),
+        )?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            r#"{"path":"tests/fixtures/sample-02/dir/c","size":257,"depth":1,"type":"file"}
+{"path":"tests/fixtures/sample-02/dir/d","size":2,"depth":1,"type":"file"}
+{"path":"tests/fixtures/sample-02/dir/empty-dir/.gitkeep","size":0,"depth":2,"type":"file"}
+{"path":"tests/fixtures/sample-02/dir/empty-dir","size":0,"depth":1,"type":"dir"}
+{"path":"tests/fixtures/sample-02/dir/sub/e","size":1024,"depth":2,"type":"file"}
+{"path":"tests/fixtures/sample-02/dir/sub","size":1024,"depth":1,"type":"dir"}
+{"path":"tests/fixtures/sample-02/dir","size":1283,"depth":0,"type":"dir"}
+{"path":"tests/fixtures/sample-02/a","size":256,"depth":0,"type":"file"}
+"#
+        );
+        assert_eq!(res.num_errors, 0);
+        assert_eq!(stats.entries_traversed, 8);
+        assert_eq!(stats.largest_file_in_bytes, 1024);
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_the_tree_of_entries_as_json() -> Result<()> {
+        let mut out = Vec::new();
+        let (res, stats) = aggregate_json(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default().threads(1).apparent_size(true),
+            true,
+            None,
+            Some(Path::new("tests/fixtures/sample-02").join("dir")),
+        )?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            r#"{"total_bytes":1283,"entries_traversed":7,"io_errors":0,"roots":[{"path":"tests/fixtures/sample-02/dir","size":1283,"entries":6,"error":false,"children":[{"path":"tests/fixtures/sample-02/dir/c","size":257,"entries":0,"error":false},{"path":"tests/fixtures/sample-02/dir/d","size":2,"entries":0,"error":false},{"path":"tests/fixtures/sample-02/dir/empty-dir","size":0,"entries":1,"error":false,"children":[{"path":"tests/fixtures/sample-02/dir/empty-dir/.gitkeep","size":0,"entries":0,"error":false}]},{"path":"tests/fixtures/sample-02/dir/sub","size":1024,"entries":1,"error":false,"children":[{"path":"tests/fixtures/sample-02/dir/sub/e","size":1024,"entries":0,"error":false}]}]}]}
+"#
+        );
+        assert_eq!(res.num_errors, 0);
+        assert_eq!(stats.entries_traversed, 7);
+        assert_eq!(stats.largest_file_in_bytes, 1024);
+        assert_eq!(stats.smallest_file_in_bytes, 0);
+        Ok(())
+    }
 }
@@ -0,0 +1,346 @@
+//! Listing what's inside archives without unpacking them, to show their contents as part of a traversal.
+use crate::{filesystem::MemoryFilesystem, gzip};
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+};
+
+/// The endings of the names of archives whose contents can be listed
+pub const EXTENSIONS: &[&str] = &[".tar", ".tar.gz", ".tgz", ".zip"];
+
+/// Return true if `name` looks like the name of an archive whose contents can be listed.
+pub fn is_archive(name: &Path) -> bool {
+    let name = name.to_string_lossy().to_lowercase();
+    EXTENSIONS.iter().any(|extension| name.ends_with(extension))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// Return the entries within the archive at `path` as filesystem, with `path` as directory containing them. Files
+/// take up their uncompressed size.
+///
+/// Compressed tar archives have to be decompressed from start to end to see all of their entries.
+pub fn contents(path: &Path) -> io::Result<MemoryFilesystem> {
+    let mut contents = MemoryFilesystem::default().block_size(1).dir(path);
+    let mut add = |name: &[u8], size: Option<u64>| {
+        // Leading slashes and '..' can't lead out of the archive
+        let relative: PathBuf = crate::ncdu::path_from_bytes(name.to_owned())
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+        if relative.as_os_str().is_empty() {
+            return;
+        }
+        let entry = path.join(relative);
+        contents = match size {
+            Some(size) => std::mem::take(&mut contents).file(entry, size),
+            None => std::mem::take(&mut contents).dir(entry),
+        };
+    };
+    let name = path.to_string_lossy().to_lowercase();
+    let file = File::open(path)?;
+    if name.ends_with(".zip") {
+        read_zip(file, &mut add)?;
+    } else if name.ends_with(".tar") {
+        read_tar(BufReader::new(file), &mut add)?;
+    } else {
+        read_tar(gzip::Decoder::new(BufReader::new(file))?, &mut add)?;
+    }
+    Ok(contents)
+}
+
+/// Something to read an archive from, which may be able to skip over content without reading it
+trait Skip: Read {
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut self.take(n), &mut io::sink())?;
+        if skipped < n {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+}
+
+impl Skip for BufReader<File> {
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        let n = n.try_into().map_err(|_| invalid("Entry too large"))?;
+        self.seek_relative(n)
+    }
+}
+
+impl<R: Read> Skip for gzip::Decoder<R> {}
+
+const BLOCK_SIZE: u64 = 512;
+
+/// Return the number in the tar header `field`, stored as octal text or, if its first bit is set, as big-endian
+/// binary number.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |n, b| (n << 8) | u64::from(*b)));
+    }
+    let text = std::str::from_utf8(field).map_err(|_| invalid("Invalid number in tar header"))?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid("Invalid number in tar header"))
+}
+
+/// Return `field` up to its first null byte.
+fn tar_text(field: &[u8]) -> &[u8] {
+    field.split(|b| *b == 0).next().unwrap_or(field)
+}
+
+/// Call `add` with the name and size of each entry of the tar archive read from `input`, or `None` as size if it's
+/// a directory. Links and special files have no size.
+fn read_tar(mut input: impl Skip, add: &mut impl FnMut(&[u8], Option<u64>)) -> io::Result<()> {
+    let mut header = [0; BLOCK_SIZE as usize];
+    // Long names and sizes of the next entry, given by GNU and pax headers
+    let mut long_name: Option<Vec<u8>> = None;
+    let mut pax_size: Option<u64> = None;
+    loop {
+        match input.read_exact(&mut header) {
+            Ok(()) => {}
+            // Some writers leave out the blocks marking the end
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err),
+        }
+        if header.iter().all(|b| *b == 0) {
+            return Ok(());
+        }
+        let size = tar_number(&header[124..136])?;
+        let padded_size = size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+        let kind = header[156];
+        match kind {
+            b'L' | b'x' => {
+                let len = size.try_into().map_err(|_| invalid("Header too large"))?;
+                let mut data = vec![0; len];
+                input.read_exact(&mut data)?;
+                input.skip(padded_size - size)?;
+                if kind == b'L' {
+                    long_name = Some(tar_text(&data).to_owned());
+                } else {
+                    for (key, value) in pax_records(&data) {
+                        match key {
+                            b"path" => long_name = Some(value.to_owned()),
+                            b"size" => {
+                                pax_size = std::str::from_utf8(value)
+                                    .ok()
+                                    .and_then(|size| size.parse().ok())
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                continue;
+            }
+            b'g' | b'K' => {
+                input.skip(padded_size)?;
+                continue;
+            }
+            _ => {}
+        }
+        let name = match long_name.take() {
+            Some(name) => name,
+            None => {
+                let (name, prefix) = (tar_text(&header[..100]), tar_text(&header[345..500]));
+                if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                    [prefix, b"/", name].concat()
+                } else {
+                    name.to_owned()
+                }
+            }
+        };
+        let size = pax_size.take().unwrap_or(size);
+        match kind {
+            b'5' => add(&name, None),
+            b'0' | b'\0' | b'7' => add(&name, Some(size)),
+            _ => add(&name, Some(0)),
+        }
+        // Only regular files have content, even if the size of links says otherwise
+        if matches!(kind, b'0' | b'\0' | b'7') {
+            input.skip(size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE)?;
+        } else if !matches!(kind, b'1' | b'2') {
+            input.skip(padded_size)?;
+        }
+    }
+}
+
+/// Return the keys and values of the records of a pax extended header, formatted like `<length> <key>=<value>\n`.
+fn pax_records(mut data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut records = Vec::new();
+    while let Some(space) = data.iter().position(|b| *b == b' ') {
+        let len: usize = match std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|len| len.parse().ok())
+        {
+            Some(len) if len > space && len <= data.len() => len,
+            _ => break,
+        };
+        let record = &data[space + 1..len - 1];
+        if let Some(eq) = record.iter().position(|b| *b == b'=') {
+            records.push((&record[..eq], &record[eq + 1..]));
+        }
+        data = &data[len..];
+    }
+    records
+}
+
+fn u16_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(data[pos..pos + 4].try_into().expect("4 bytes"))
+}
+fn u64_at(data: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(data[pos..pos + 8].try_into().expect("8 bytes"))
+}
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+
+/// Call `add` with the name and uncompressed size of each entry of the zip archive `file`, or `None` as size if
+/// it's a directory. Only the central directory at the end of the file is read.
+fn read_zip(mut file: File, add: &mut impl FnMut(&[u8], Option<u64>)) -> io::Result<()> {
+    let len = file.seek(SeekFrom::End(0))?;
+    // The end of the central directory is followed by a comment of up to 64KiB
+    let tail_len = len.min(22 + 0xffff);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|pos| u32_at(&tail, *pos) == END_OF_CENTRAL_DIRECTORY)
+        .ok_or_else(|| invalid("Not a zip archive"))?;
+    let (mut entries, mut directory_len, mut directory_offset) = (
+        u64::from(u16_at(&tail, end + 10)),
+        u64::from(u32_at(&tail, end + 12)),
+        u64::from(u32_at(&tail, end + 16)),
+    );
+    if end >= 20 && u32_at(&tail, end - 20) == ZIP64_LOCATOR {
+        file.seek(SeekFrom::Start(u64_at(&tail, end - 20 + 8)))?;
+        let mut end64 = [0; 56];
+        file.read_exact(&mut end64)?;
+        if u32_at(&end64, 0) != ZIP64_END_OF_CENTRAL_DIRECTORY {
+            return Err(invalid("Invalid zip64 end of central directory"));
+        }
+        entries = u64_at(&end64, 32);
+        directory_len = u64_at(&end64, 40);
+        directory_offset = u64_at(&end64, 48);
+    }
+
+    file.seek(SeekFrom::Start(directory_offset))?;
+    let mut directory = Vec::new();
+    file.take(directory_len).read_to_end(&mut directory)?;
+    let mut pos = 0;
+    for _ in 0..entries {
+        if pos + 46 > directory.len() || u32_at(&directory, pos) != CENTRAL_DIRECTORY_HEADER {
+            return Err(invalid("Invalid zip central directory"));
+        }
+        let mut size = u64::from(u32_at(&directory, pos + 24));
+        let name_len = usize::from(u16_at(&directory, pos + 28));
+        let extra_len = usize::from(u16_at(&directory, pos + 30));
+        let comment_len = usize::from(u16_at(&directory, pos + 32));
+        let name_end = pos + 46 + name_len;
+        if name_end + extra_len + comment_len > directory.len() {
+            return Err(invalid("Invalid zip central directory"));
+        }
+        let name = &directory[pos + 46..name_end];
+        if size == u64::from(u32::MAX) {
+            // The real size is the first value of the zip64 extra field
+            let mut extra = &directory[name_end..name_end + extra_len];
+            while extra.len() >= 4 {
+                let (id, len) = (u16_at(extra, 0), usize::from(u16_at(extra, 2)));
+                if id == 1 && len >= 8 && extra.len() >= 12 {
+                    size = u64_at(extra, 4);
+                    break;
+                }
+                extra = &extra[(4 + len).min(extra.len())..];
+            }
+        }
+        if name.ends_with(b"/") {
+            add(name, None);
+        } else {
+            add(name, Some(size));
+        }
+        pos = name_end + extra_len + comment_len;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filesystem::Filesystem, testing::TempDir};
+    use std::{fs, process::Command};
+
+    fn listing(path: &Path) -> io::Result<Vec<(usize, String, Option<u64>)>> {
+        let mut contents = contents(path)?;
+        let entries = contents
+            .walk(path)?
+            .skip(1)
+            .map(|entry| {
+                let entry = entry.expect("no errors");
+                (
+                    entry.depth,
+                    entry.name.to_string_lossy().into_owned(),
+                    entry.size.map(|size| size.apparent),
+                )
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    #[test]
+    fn entries_of_tar_and_zip_archives_are_listed_with_their_uncompressed_size(
+    ) -> anyhow::Result<()> {
+        let root = TempDir::new("archive")?;
+        let long_name = "a-name-longer-than-a-hundred-characters-".repeat(3);
+        fs::create_dir_all(root.join("content/dir/empty"))?;
+        fs::write(root.join("content/dir/big"), vec![b'x'; 100_000])?;
+        fs::write(root.join("content").join(&long_name), b"small")?;
+
+        let expected = vec![
+            (1, long_name.clone(), Some(5)),
+            (1, "dir".to_owned(), None),
+            (2, "big".to_owned(), Some(100_000)),
+            (2, "empty".to_owned(), None),
+        ];
+        for (archive, command) in [
+            ("content.tar", vec!["tar", "cf", "../content.tar", "."]),
+            (
+                "content.tar.gz",
+                vec!["tar", "czf", "../content.tar.gz", "."],
+            ),
+            (
+                "content.tgz",
+                vec!["tar", "--format=pax", "-czf", "../content.tgz", "."],
+            ),
+            ("content.zip", vec!["zip", "-qr", "../content.zip", "."]),
+        ] {
+            let status = match Command::new(command[0])
+                .args(&command[1..])
+                .current_dir(root.join("content"))
+                .status()
+            {
+                Ok(status) => status,
+                Err(_) => continue, // the archiver isn't installed
+            };
+            assert!(status.success(), "{} was created", archive);
+            let archive = root.join(archive);
+            assert!(is_archive(&archive));
+            assert_eq!(listing(&archive)?, expected, "{}", archive.display());
+        }
+
+        fs::write(root.join("broken.zip"), b"not a zip")?;
+        assert!(contents(&root.join("broken.zip")).is_err());
+        Ok(())
+    }
+}
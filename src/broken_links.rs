@@ -0,0 +1,103 @@
+use crate::{walk::Walk, WalkOptions, WalkResult};
+use anyhow::Result;
+use colored::Colorize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Walk the given `paths` and write all symbolic links whose target doesn't exist to `out`, along with the target they
+/// point to, in the order of their paths.
+///
+/// Links pointing to themselves or to each other in a loop are broken as well.
+/// If `err` is set, it shows the entries seen so far while walking, and is cleared before the links are written.
+/// If the walk is [interrupted][crate::INTERRUPTED], the links found so far are written along with a note that more
+/// may be missing.
+pub fn broken_links(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut links: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut walk = Walk::new(&walk_options, err)?;
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, _| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            if !entry.path_is_symlink() {
+                return None;
+            }
+            let path = entry.path();
+            if path.exists() {
+                return None;
+            }
+            match fs::read_link(&path) {
+                Ok(target) => links.push((path, target)),
+                Err(_) => res.num_errors += 1,
+            }
+            None
+        })?;
+    }
+    walk.clear_progress();
+
+    links.sort();
+    writeln!(
+        out,
+        "{} broken {}",
+        links.len().to_string().as_str().red(),
+        if links.len() == 1 { "link" } else { "links" }
+    )?;
+    for (path, target) in links {
+        writeln!(out, "  {} -> {}", path.display(), target.display())?;
+    }
+    if walk.is_incomplete {
+        writeln!(out, "{}", "(incomplete, more may be missing)".red())?;
+    }
+    Ok(res)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn it_lists_links_whose_target_does_not_exist() -> Result<()> {
+        let root = TempDir::new("broken-links")?;
+        fs::create_dir_all(root.join("dir"))?;
+        fs::write(root.join("file"), b"content")?;
+        symlink("file", root.join("alive"))?;
+        symlink("../gone", root.join("dir/dead"))?;
+        symlink("loop", root.join("loop"))?;
+
+        let mut out = Vec::new();
+        let res = broken_links(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            vec![&root],
+        )?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(
+            out,
+            format!(
+                "2 broken links\n  {r}/dir/dead -> ../gone\n  {r}/loop -> loop\n",
+                r = root.display()
+            )
+        );
+        assert_eq!(res.num_errors, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    by_type::{output_total, output_usage, size_of_file},
+    top_files::LargestFiles,
+    walk::Walk,
+    InodeFilter, Usage, WalkOptions, WalkResult,
+};
+use anyhow::Result;
+use colored::Colorize;
+use std::{
+    io,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The upper bound of the age of files in each bucket in days, along with its description
+const BUCKETS: &[(u64, &str)] = &[
+    (30, "less than 30 days"),
+    (180, "30 to 180 days"),
+    (365, "180 days to 1 year"),
+    (u64::MAX, "more than 1 year"),
+];
+
+/// Which time of a file its age is computed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgeOf {
+    /// The time the file was last modified
+    Modification,
+    /// The time the file was last read, which some filesystems don't keep up to date
+    Access,
+}
+
+/// Walk the given `paths` and write the amount of bytes in files of each age to `out`, the youngest first, followed
+/// by the `num_files` largest files which are older than `stale_after_days`.
+///
+/// Files whose time is unknown are left out, and files from the future are as young as can be.
+/// If `err` is set, it shows the entries seen and the bytes of files put into an age so far while walking, and is
+/// cleared before the ages are written. If the walk is [interrupted][crate::INTERRUPTED], the files seen so far are
+/// written, followed by a note.
+pub fn by_age(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    age_of: AgeOf,
+    stale_after_days: u64,
+    num_files: usize,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut inodes = InodeFilter::default();
+    let mut walk = Walk::new(&walk_options, err)?;
+    let mut buckets = [Usage::default(); BUCKETS.len()];
+    let mut stale_files = LargestFiles::new(num_files);
+    let now = SystemTime::now();
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, device_id| {
+            let entry = match entry {
+                Ok(entry) if entry.file_type.is_dir() => return None,
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            let time = match &entry.client_state {
+                Some(Ok(m)) => match age_of {
+                    AgeOf::Modification => m.modified(),
+                    AgeOf::Access => m.accessed(),
+                }
+                .ok(),
+                _ => None,
+            };
+            let size = match size_of_file(&entry, &walk_options, &mut inodes, device_id) {
+                Some(Some(size)) => size,
+                Some(None) => return None,
+                None => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            let time = match time {
+                Some(time) => time,
+                None => return None,
+            };
+            let age_in_days =
+                now.duration_since(time).unwrap_or_default().as_secs() / DAY.as_secs();
+            let bucket = BUCKETS
+                .iter()
+                .position(|&(max_days, _)| age_in_days < max_days)
+                .expect("the last bucket to take any age");
+            buckets[bucket].size += size;
+            buckets[bucket].files += 1;
+            if age_in_days >= stale_after_days {
+                stale_files.push(size, entry.path(), Some(time));
+            }
+            Some(size)
+        })?;
+    }
+    walk.clear_progress();
+
+    let total = buckets.iter().map(|b| b.size).sum();
+    for (usage, (_, description)) in buckets.iter().zip(BUCKETS) {
+        output_usage(&mut out, &walk_options, description, *usage, total)?;
+    }
+    output_total(&mut out, &walk_options, total)?;
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "Largest files not {} in {} days",
+            match age_of {
+                AgeOf::Modification => "modified",
+                AgeOf::Access => "accessed",
+            },
+            stale_after_days
+        )
+        .cyan()
+    )?;
+    stale_files.write(&mut out, &walk_options)?;
+    if walk.is_incomplete {
+        writeln!(out, "{}", "(incomplete, sizes are too small)".red())?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+    use std::fs;
+
+    #[test]
+    fn it_buckets_files_by_age_and_lists_stale_ones() -> Result<()> {
+        let root = TempDir::new("by-age")?;
+        let now = SystemTime::now();
+        for (name, size, age_in_days) in &[("new", 1, 0), ("recent", 20, 100), ("old", 300, 400)] {
+            let path = root.join(name);
+            fs::write(&path, vec![0; *size])?;
+            fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(now - DAY * *age_in_days)?;
+        }
+
+        let mut out = Vec::new();
+        by_age(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            AgeOf::Modification,
+            90,
+            1,
+            Some(&root),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            &lines[..6],
+            &[
+                "1 b 0.3% 1 file less than 30 days",
+                "20 b 6.2% 1 file 30 to 180 days",
+                "0 b 0.0% 0 files 180 days to 1 year",
+                "300 b 93.5% 1 file more than 1 year",
+                "321 b total",
+                "Largest files not modified in 90 days",
+            ]
+        );
+        assert_eq!(
+            lines.len(),
+            7,
+            "only the requested amount of files is listed"
+        );
+        assert!(lines[6].starts_with("300 b ") && lines[6].ends_with("old"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_the_inodes_of_each_age() -> Result<()> {
+        let root = TempDir::new("by-age-inodes")?;
+        let now = SystemTime::now();
+        for (name, age_in_days) in &[("new", 0), ("newer", 1), ("old", 400)] {
+            let path = root.join(name);
+            fs::write(&path, "content")?;
+            fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(now - DAY * *age_in_days)?;
+        }
+
+        let mut out = Vec::new();
+        by_age(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Inodes)
+                .count_inodes(true),
+            AgeOf::Modification,
+            90,
+            1,
+            Some(&root),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            &lines[..5],
+            &[
+                "2 66.7% 2 files less than 30 days",
+                "0 0.0% 0 files 30 to 180 days",
+                "0 0.0% 0 files 180 days to 1 year",
+                "1 33.3% 1 file more than 1 year",
+                "3 total",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    by_type::{output_table, size_of_file},
+    walk::Walk,
+    InodeFilter, Usage, WalkOptions, WalkResult,
+};
+use anyhow::Result;
+use colored::Colorize;
+use std::{collections::HashMap, fs, io, os::unix::fs::MetadataExt, path::Path};
+
+/// Parse the names of the ids in a file formatted like `/etc/passwd` or `/etc/group`, with the name in the first
+/// and the id in the third field of each line.
+pub fn names_by_id(content: &str) -> HashMap<u32, String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_owned()))
+        })
+        .collect()
+}
+
+fn names_in(path: &str) -> HashMap<u32, String> {
+    fs::read_to_string(path)
+        .map(|content| names_by_id(&content))
+        .unwrap_or_default()
+}
+
+/// Walk the given `paths` and write a table of the amount of bytes owned by each user to `out`, the largest first,
+/// followed by a table for each group.
+///
+/// Names are looked up in `/etc/passwd` and `/etc/group`, and ids without a name there are written as they are.
+/// If `err` is set, it shows the entries seen and the bytes of files summed up so far while walking, and is cleared
+/// before the tables. If the walk is [interrupted][crate::INTERRUPTED], the files seen so far are written, followed
+/// by a note.
+pub fn by_owner(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut inodes = InodeFilter::default();
+    let mut walk = Walk::new(&walk_options, err)?;
+    let mut by_user = HashMap::<u32, Usage>::new();
+    let mut by_group = HashMap::<u32, Usage>::new();
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, device_id| {
+            let entry = match entry {
+                Ok(entry) if entry.file_type.is_dir() => return None,
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            let (uid, gid) = match &entry.client_state {
+                Some(Ok(m)) => (m.uid(), m.gid()),
+                _ => (0, 0),
+            };
+            match size_of_file(&entry, &walk_options, &mut inodes, device_id) {
+                Some(Some(size)) => {
+                    for usage in [
+                        by_user.entry(uid).or_default(),
+                        by_group.entry(gid).or_default(),
+                    ] {
+                        usage.size += size;
+                        usage.files += 1;
+                    }
+                    return Some(size);
+                }
+                Some(None) => {}
+                None => res.num_errors += 1,
+            }
+            None
+        })?;
+    }
+    walk.clear_progress();
+
+    let named = |usage: HashMap<u32, Usage>, names: HashMap<u32, String>| {
+        usage
+            .into_iter()
+            .map(|(id, usage)| {
+                let name = names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+                (name, usage)
+            })
+            .collect()
+    };
+    writeln!(out, "{}", "Users".cyan())?;
+    output_table(
+        &mut out,
+        &walk_options,
+        named(by_user, names_in("/etc/passwd")),
+    )?;
+    writeln!(out, "{}", "Groups".cyan())?;
+    output_table(
+        &mut out,
+        &walk_options,
+        named(by_group, names_in("/etc/group")),
+    )?;
+    if walk.is_incomplete {
+        writeln!(out, "{}", "(incomplete, sizes are too small)".red())?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ByteFormat;
+
+    #[test]
+    fn names_are_parsed_from_passwd_and_group_files() {
+        let names = names_by_id(
+            "# comment\nroot:x:0:0:root:/root:/bin/bash\nnobody:x:65534:65534::/:/sbin/nologin\nbroken\n",
+        );
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[&0], "root");
+        assert_eq!(names[&65534], "nobody");
+        assert_eq!(names_by_id("wheel:x:10:alice,bob\n")[&10], "wheel");
+    }
+
+    #[test]
+    fn it_sums_up_sizes_by_user_and_group() -> Result<()> {
+        let mut out = Vec::new();
+        by_owner(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            Some("tests/fixtures/sample-02"),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        // All fixtures belong to whoever checked them out
+        assert_eq!(lines.len(), 6, "{}", out);
+        assert_eq!(lines[0], "Users");
+        assert!(
+            lines[1].starts_with("1540 b 100.0% 6 files "),
+            "{}",
+            lines[1]
+        );
+        assert_eq!(lines[2], "1540 b total");
+        assert_eq!(lines[3], "Groups");
+        assert!(
+            lines[4].starts_with("1540 b 100.0% 6 files "),
+            "{}",
+            lines[4]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{crossdev, walk::Walk, DirEntry, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use colored::Colorize;
+use filesize::PathExt;
+use std::{collections::HashMap, io, path::Path};
+
+/// The type of files without an extension
+pub const NO_EXTENSION: &str = "(no extension)";
+
+/// The amount of bytes and files of one kind, like a type of file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub size: u128,
+    pub files: u64,
+}
+
+/// The type of the file at `path`, which is its extension in lower case with a leading dot, like `.mp4`, or
+/// [`NO_EXTENSION`]. Hidden files like `.bashrc` have no extension.
+pub fn file_type_of(path: &Path) -> String {
+    match path.extension() {
+        Some(extension) => format!(".{}", extension.to_string_lossy().to_lowercase()),
+        None => NO_EXTENSION.into(),
+    }
+}
+
+/// Walk the given `paths` and write a table of the amount of bytes used by each type of file to `out`, the largest
+/// first, with their share of the total and their amount of files. See [`file_type_of()`] for what the type is.
+///
+/// If `per_path` is set, a table is written for each path, preceded by it, instead of one table for all of them.
+/// If `err` is set, it shows the entries seen and the bytes of files summed up so far while walking, and is cleared
+/// before each table. If the walk is [interrupted][crate::INTERRUPTED], the files seen so far are written, followed
+/// by a note.
+pub fn by_type(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    per_path: bool,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut inodes = InodeFilter::default();
+    let mut walk = Walk::new(&walk_options, err)?;
+    let mut usage = HashMap::<String, Usage>::new();
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, device_id| {
+            match entry {
+                Ok(entry) if !entry.file_type.is_dir() => {
+                    match size_of_file(&entry, &walk_options, &mut inodes, device_id) {
+                        Some(Some(size)) => {
+                            let usage = usage.entry(file_type_of(&entry.path())).or_default();
+                            usage.size += size;
+                            usage.files += 1;
+                            return Some(size);
+                        }
+                        Some(None) => {}
+                        None => res.num_errors += 1,
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => res.num_errors += 1,
+            }
+            None
+        })?;
+        if per_path {
+            walk.clear_progress();
+            writeln!(out, "{}", path.as_ref().display().to_string().cyan())?;
+            output_table(&mut out, &walk_options, std::mem::take(&mut usage))?;
+        }
+        if walk.is_incomplete {
+            break;
+        }
+    }
+
+    walk.clear_progress();
+    if !per_path {
+        output_table(&mut out, &walk_options, usage)?;
+    }
+    if walk.is_incomplete {
+        writeln!(out, "{}", "(incomplete, sizes are too small)".red())?;
+    }
+    Ok(res)
+}
+
+/// Return the size of the file `entry`, `Some(None)` if it's not counted, or `None` if it couldn't be read. The size is
+/// one if [inodes are counted][WalkOptions::count_inodes].
+pub(crate) fn size_of_file(
+    entry: &DirEntry,
+    walk_options: &WalkOptions,
+    inodes: &mut InodeFilter,
+    device_id: u64,
+) -> Option<Option<u128>> {
+    let m = match entry.client_state {
+        Some(Ok(ref m))
+            if (walk_options.count_hard_links || inodes.add(m))
+                && (walk_options.cross_filesystems || crossdev::is_same_device(device_id, m)) =>
+        {
+            m
+        }
+        Some(Ok(_)) | None => return Some(None),
+        Some(Err(_)) => return None,
+    };
+    let size = if walk_options.count_inodes {
+        1
+    } else if walk_options.apparent_size {
+        m.len()
+    } else {
+        entry.path().size_on_disk_fast(m).ok()?
+    };
+    Some(Some(size as u128))
+}
+
+/// Write a line for each kind of `usage` to `out`, the largest first, followed by the total.
+pub(crate) fn output_table(
+    out: &mut impl io::Write,
+    walk_options: &WalkOptions,
+    usage: HashMap<String, Usage>,
+) -> io::Result<()> {
+    let total: u128 = usage.values().map(|u| u.size).sum();
+    let mut usage: Vec<_> = usage.into_iter().collect();
+    usage.sort_by(|(ln, l), (rn, r)| r.size.cmp(&l.size).then_with(|| ln.cmp(rn)));
+    for (kind, usage) in usage {
+        output_usage(out, walk_options, &kind, usage, total)?;
+    }
+    output_total(out, walk_options, total)
+}
+
+/// Write the size of `usage` to `out`, along with its share of `total`, its amount of files and its `kind`.
+pub(crate) fn output_usage(
+    out: &mut impl io::Write,
+    walk_options: &WalkOptions,
+    kind: &str,
+    Usage { size, files }: Usage,
+    total: u128,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>byte_column_width$} {:>5.1}% {:>8} file{} {}",
+        walk_options
+            .byte_format
+            .display(size)
+            .to_string()
+            .as_str()
+            .green(),
+        if total == 0 {
+            0.0
+        } else {
+            size as f64 * 100.0 / total as f64
+        },
+        files,
+        if files == 1 { " " } else { "s" },
+        kind,
+        byte_column_width = walk_options.byte_format.width()
+    )
+}
+
+pub(crate) fn output_total(
+    out: &mut impl io::Write,
+    walk_options: &WalkOptions,
+    total: u128,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>byte_column_width$} total",
+        walk_options
+            .byte_format
+            .display(total)
+            .to_string()
+            .as_str()
+            .green(),
+        byte_column_width = walk_options.byte_format.width()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ByteFormat;
+
+    #[test]
+    fn it_sums_up_sizes_by_extension() -> Result<()> {
+        let mut out = Vec::new();
+        by_type(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            false,
+            Some("tests/fixtures/sample-01"),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "1000000 b 79.4% 1 file .1mb",
+                "256000 b 20.3% 1 file .256kb",
+                "1256 b 0.1% 3 files (no extension)",
+                "1024 b 0.1% 1 file .kb",
+                "666 b 0.1% 1 file .666",
+                "123 b 0.0% 1 file .b",
+                "1 b 0.0% 1 file .lnk",
+                "0 b 0.0% 1 file .empty",
+                "1259070 b total"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_the_inodes_of_each_extension() -> Result<()> {
+        let mut out = Vec::new();
+        by_type(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Inodes)
+                .count_inodes(true),
+            false,
+            Some("tests/fixtures/sample-01"),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(lines[0], "3 30.0% 3 files (no extension)");
+        assert_eq!(lines.last().map(String::as_str), Some("10 total"));
+        Ok(())
+    }
+
+    #[test]
+    fn file_types_are_lower_case_extensions() {
+        assert_eq!(file_type_of(Path::new("dir/movie.MP4")), ".mp4");
+        assert_eq!(file_type_of(Path::new("archive.tar.gz")), ".gz");
+        assert_eq!(file_type_of(Path::new(".bashrc")), NO_EXTENSION);
+        assert_eq!(file_type_of(Path::new("Makefile")), NO_EXTENSION);
+    }
+}
@@ -0,0 +1,263 @@
+//! Finding directories whose contents are disposable, like build outputs, dependencies and caches, to delete them.
+use crate::{
+    get_entry_or_panic,
+    traverse::{EntryData, Traversal, Tree, TreeIndex},
+    WalkOptions, WalkResult, INTERRUPTED,
+};
+use anyhow::Result;
+use colored::Colorize;
+use petgraph::Direction;
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+/// The paths of the caches of browsers below the home directory
+const BROWSER_CACHES: &[&str] = &[
+    ".cache/mozilla",
+    ".cache/google-chrome",
+    ".cache/chromium",
+    ".cache/BraveSoftware",
+    "Library/Caches/Firefox",
+    "Library/Caches/Google/Chrome",
+    "Library/Caches/com.apple.Safari",
+];
+
+/// A directory whose contents are disposable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Junk {
+    pub path: PathBuf,
+    /// What the directory holds, like `node modules`
+    pub kind: &'static str,
+    /// The size of the directory in bytes, as apparent size or disk usage like the scan was made
+    pub size: u128,
+}
+
+/// What to do with the disposable directories that were found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deletion {
+    /// Only list them
+    None,
+    /// Ask for each of them whether to delete it
+    Ask,
+    /// Delete all of them without asking
+    All,
+}
+
+/// Return what the directory `entry` at `path` holds if its contents are disposable, or `None` if they aren't known
+/// to be.
+///
+/// Cargo's `target` directories are only recognized next to a `Cargo.toml` file, and `caches` only within `.gradle`.
+pub fn junk_kind(path: &Path, entry: &EntryData) -> Option<&'static str> {
+    if !entry.is_dir || entry.is_symlink {
+        return None;
+    }
+    let name = path.file_name()?;
+    let parent_name = path.parent().and_then(Path::file_name);
+    Some(match name.to_str() {
+        Some("target") if path.with_file_name("Cargo.toml").is_file() => "cargo build output",
+        Some("node_modules") => "node modules",
+        Some("__pycache__") => "python bytecode",
+        Some("caches") if parent_name == Some(OsStr::new(".gradle")) => "gradle caches",
+        _ if BROWSER_CACHES.iter().any(|cache| path.ends_with(cache)) => "browser cache",
+        _ if entry.is_cache_dir => "tagged cache",
+        _ => return None,
+    })
+}
+
+/// Return all disposable directories in `traversal`, the largest first. Directories within them aren't listed again.
+pub fn find_junk(traversal: &Traversal) -> Vec<Junk> {
+    let tree = &traversal.tree;
+    let mut junk = Vec::new();
+    // The names of the entries below the root are the input paths
+    let mut stack: Vec<(TreeIndex, PathBuf)> = children_of(tree, traversal.root_index)
+        .map(|idx| (idx, tree.name_of(idx).to_path_buf()))
+        .collect();
+    while let Some((idx, path)) = stack.pop() {
+        let entry = get_entry_or_panic(tree, idx);
+        if let Some(kind) = junk_kind(&path, entry) {
+            junk.push(Junk {
+                path,
+                kind,
+                size: entry.size as u128,
+            });
+            continue;
+        }
+        stack.extend(children_of(tree, idx).map(|child| (child, path.join(tree.name_of(child)))));
+    }
+    junk.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    junk
+}
+
+fn children_of(tree: &Tree, idx: TreeIndex) -> impl Iterator<Item = TreeIndex> + '_ {
+    tree.neighbors_directed(idx, Direction::Outgoing)
+}
+
+/// Walk the given `paths` and write all disposable directories among them to `out`, the largest first, each with its
+/// size, what it holds and its path, followed by the space they take together. Then delete them as `deletion` says.
+///
+/// When asking, a line is read from `answers` for each directory: `y` deletes it, `a` deletes it along with all
+/// remaining ones, `q` stops asking, and everything else keeps it. Directories which can't be deleted are counted as
+/// errors. If the walk is [interrupted][crate::INTERRUPTED], the directories found so far are written but none of
+/// them is deleted.
+pub fn clean(
+    mut out: impl io::Write,
+    mut err: Option<impl io::Write>,
+    mut answers: impl BufRead,
+    walk_options: WalkOptions,
+    deletion: Deletion,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let byte_format = walk_options.byte_format;
+    let paths = paths.into_iter().map(|p| p.as_ref().to_owned()).collect();
+    let traversal = Traversal::from_walk(walk_options, paths, |t| {
+        if let Some(err) = err.as_mut() {
+            write!(err, "Enumerating {} entries\r", t.entries_traversed).ok();
+        }
+        Ok(false)
+    })?;
+    let mut res = WalkResult {
+        num_errors: traversal.io_errors,
+    };
+    let junk = find_junk(&traversal);
+
+    for Junk { path, kind, size } in &junk {
+        writeln!(
+            out,
+            "{:>byte_column_width$} {:<18} {}",
+            byte_format.display(*size).to_string().as_str().green(),
+            kind,
+            path.display(),
+            byte_column_width = byte_format.width()
+        )?;
+    }
+    writeln!(
+        out,
+        "{} disposable {}, {} reclaimable",
+        junk.len(),
+        if junk.len() == 1 {
+            "directory"
+        } else {
+            "directories"
+        },
+        byte_format.display(junk.iter().map(|j| j.size).sum())
+    )?;
+    if traversal.is_incomplete {
+        writeln!(out, "{}", "(incomplete, more may be missing)".red())?;
+        return Ok(res);
+    }
+
+    let mut delete_all = deletion == Deletion::All;
+    let mut reclaimed = 0;
+    for Junk { path, size, .. } in junk {
+        if deletion == Deletion::None || INTERRUPTED.load(Ordering::Relaxed) {
+            break;
+        }
+        if !delete_all {
+            write!(
+                out,
+                "Delete {} ({})? [y/N/a/q] ",
+                path.display(),
+                byte_format.display(size)
+            )?;
+            out.flush()?;
+            let mut answer = String::new();
+            if answers.read_line(&mut answer)? == 0 {
+                writeln!(out)?;
+                break;
+            }
+            match answer.trim() {
+                "y" | "Y" => {}
+                "a" | "A" => delete_all = true,
+                "q" | "Q" => break,
+                _ => continue,
+            }
+        }
+        match fs::remove_dir_all(&path) {
+            Ok(()) => reclaimed += size,
+            Err(e) => {
+                res.num_errors += 1;
+                writeln!(out, "Could not delete {}: {}", path.display(), e)?;
+            }
+        }
+    }
+    if deletion != Deletion::None {
+        writeln!(
+            out,
+            "{} reclaimed",
+            byte_format.display(reclaimed).to_string().as_str().green()
+        )?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+
+    #[test]
+    fn disposable_directories_are_listed_and_deleted_as_answered() -> Result<()> {
+        let root = TempDir::new("clean")?;
+        let write = |path: &str, len: usize| -> io::Result<()> {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().expect("a parent"))?;
+            fs::write(path, vec![0; len])
+        };
+        write("crate/Cargo.toml", 1)?;
+        write("crate/target/debug/dua", 400)?;
+        write("web/node_modules/left-pad/node_modules/index.js", 300)?;
+        write("lib/__pycache__/lib.pyc", 200)?;
+        write("home/.gradle/caches/jar", 100)?;
+        write("other/target/kept", 1000)?;
+
+        let scan = |answers: &[u8], deletion| -> Result<String> {
+            let mut out = Vec::new();
+            clean(
+                &mut out,
+                None::<io::Sink>,
+                answers,
+                WalkOptions::default()
+                    .threads(1)
+                    .byte_format(ByteFormat::Bytes)
+                    .apparent_size(true),
+                deletion,
+                vec![&root],
+            )?;
+            Ok(String::from_utf8(out)?)
+        };
+
+        let r = root.display();
+        let listing = format!(
+            "       400 b cargo build output {r}/crate/target
+       300 b node modules       {r}/web/node_modules
+       200 b python bytecode    {r}/lib/__pycache__
+       100 b gradle caches      {r}/home/.gradle/caches
+4 disposable directories, 1000 b reclaimable
+",
+            r = r
+        );
+        assert_eq!(scan(b"", Deletion::None)?, listing);
+
+        assert_eq!(
+            scan(b"n\ny\nq\n", Deletion::Ask)?,
+            format!(
+                "{listing}Delete {r}/crate/target (400 b)? [y/N/a/q] \
+                 Delete {r}/web/node_modules (300 b)? [y/N/a/q] \
+                 Delete {r}/lib/__pycache__ (200 b)? [y/N/a/q] 300 b reclaimed\n",
+                listing = listing,
+                r = r
+            )
+        );
+        assert!(!root.join("web/node_modules").exists());
+        assert!(root.join("crate/target").exists() && root.join("lib/__pycache__").exists());
+
+        scan(b"", Deletion::All)?;
+        assert!(!root.join("crate/target").exists() && !root.join("home/.gradle/caches").exists());
+        assert!(root.join("other/target/kept").exists() && root.join("crate/Cargo.toml").exists());
+        Ok(())
+    }
+}
@@ -1,16 +1,58 @@
-use crate::traverse::{EntryData, Tree, TreeIndex};
-use byte_unit::{n_gb_bytes, n_gib_bytes, n_mb_bytes, n_mib_bytes, ByteUnit};
-use std::{fmt, path::Path};
+use crate::{
+    crossdev,
+    gitignore::{GitignoreMode, GitignoreState},
+    traverse::{EntryData, Traversal, Tree, TreeIndex},
+    Glob,
+};
+use byte_unit::ByteUnit;
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{self, BufRead, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{atomic::AtomicBool, Arc, Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Set once the user asked to stop, like with Ctrl+C, to end all walks early with the entries seen so far.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Format `time` as `YYYY-MM-DDThh:mm:ss` in UTC. Times before the epoch are formatted as the epoch.
+pub fn utc_date_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = ((secs / 86400) as i64, secs % 86400);
+    // Convert days since the epoch into a date of the proleptic Gregorian calendar,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
 
 pub fn get_entry_or_panic(tree: &Tree, node_idx: TreeIndex) -> &EntryData {
     tree.node_weight(node_idx)
         .expect("node should always be retrievable with valid index")
 }
 
-pub(crate) fn get_size_or_panic(tree: &Tree, node_idx: TreeIndex) -> u128 {
-    get_entry_or_panic(tree, node_idx).size
-}
-
 /// Specifies a way to format bytes
 #[derive(Clone, Copy)]
 pub enum ByteFormat {
@@ -20,6 +62,8 @@ pub enum ByteFormat {
     Binary,
     /// raw bytes, without additional formatting
     Bytes,
+    /// the exact amount of bytes without a unit, for scripts
+    Raw,
     /// only gigabytes without smart-unit
     GB,
     /// only gibibytes without smart-unit
@@ -28,16 +72,35 @@ pub enum ByteFormat {
     MB,
     /// only mebibytes without smart-unit
     MiB,
+    /// only kilobytes without smart-unit
+    KB,
+    /// only kibibytes without smart-unit
+    KiB,
+    /// only terabytes without smart-unit
+    TB,
+    /// only tebibytes without smart-unit
+    TiB,
+    /// amounts of inodes instead of bytes, as counted with [`WalkOptions::count_inodes`]
+    Inodes,
 }
 
 impl ByteFormat {
     pub fn width(self) -> usize {
         use ByteFormat::*;
-        match self {
+        let width = match self {
             Metric | Binary => 10,
-            Bytes => 12,
+            Bytes | Inodes => 12,
+            Raw | KiB | KB => 14,
             MiB | MB => 12,
             _ => 10,
+        };
+        match self {
+            Bytes | Inodes | KiB | KB | MiB | MB
+                if number_format().thousands_separator.is_some() =>
+            {
+                width + 3
+            }
+            _ => width,
         }
     }
     pub fn total_width(self) -> usize {
@@ -46,9 +109,10 @@ impl ByteFormat {
 
         self.width()
             + match self {
-                Binary | MiB | GiB => 3,
-                Metric | MB | GB => 2,
+                Binary | KiB | MiB | GiB | TiB => 3,
+                Metric | KB | MB | GB | TB => 2,
                 Bytes => 1,
+                Raw | Inodes => 0,
             }
             + THE_SPACE_BETWEEN_UNIT_AND_NUMBER
     }
@@ -71,19 +135,23 @@ impl fmt::Display for ByteFormatDisplay {
         use ByteFormat::*;
 
         let format = match self.format {
-            Bytes => return write!(f, "{} b", self.bytes),
+            Bytes => return write!(f, "{} b", format_number(self.bytes)),
+            Inodes => return f.write_str(&format_number(self.bytes)),
+            Raw => return write!(f, "{}", self.bytes),
             Binary => (true, None),
             Metric => (false, None),
-            GB => (false, Some((n_gb_bytes!(1), ByteUnit::GB))),
-            GiB => (false, Some((n_gib_bytes!(1), ByteUnit::GiB))),
-            MB => (false, Some((n_mb_bytes!(1), ByteUnit::MB))),
-            MiB => (false, Some((n_mib_bytes!(1), ByteUnit::MiB))),
+            GB => (false, Some(ByteUnit::GB)),
+            GiB => (false, Some(ByteUnit::GiB)),
+            MB => (false, Some(ByteUnit::MB)),
+            MiB => (false, Some(ByteUnit::MiB)),
+            KB => (false, Some(ByteUnit::KB)),
+            KiB => (false, Some(ByteUnit::KiB)),
+            TB => (false, Some(ByteUnit::TB)),
+            TiB => (false, Some(ByteUnit::TiB)),
         };
 
         let b = match format {
-            (_, Some((divisor, unit))) => Byte::from_unit(self.bytes as f64 / divisor as f64, unit)
-                .expect("byte count > 0")
-                .get_adjusted_unit(unit),
+            (_, Some(unit)) => Byte::from_bytes(self.bytes).get_adjusted_unit(unit),
             (binary, None) => Byte::from_bytes(self.bytes).get_appropriate_unit(binary),
         }
         .format(2);
@@ -92,7 +160,7 @@ impl fmt::Display for ByteFormatDisplay {
             (Some(bytes), Some(unit)) => write!(
                 f,
                 "{} {:>unit_width$}",
-                bytes,
+                number_format().apply(bytes),
                 unit,
                 unit_width = match self.format {
                     Binary => 3,
@@ -105,6 +173,203 @@ impl fmt::Display for ByteFormatDisplay {
     }
 }
 
+/// How numbers are shown, with their digits grouped and decimals separated like `1,234.5` or `1.234,5`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Written between each group of three digits before the decimals, if set
+    pub thousands_separator: Option<char>,
+    /// Written before the decimals
+    pub decimal_separator: char,
+}
+
+static NUMBER_FORMAT: RwLock<NumberFormat> = RwLock::new(NumberFormat::PLAIN);
+
+/// Use `format` for all numbers shown by [`ByteFormat::display()`] and [`format_number()`] from now on. Like the
+/// colors of the output, it applies to the whole process.
+pub fn set_number_format(format: NumberFormat) {
+    *NUMBER_FORMAT.write().expect("not poisoned") = format;
+}
+
+/// The way numbers are shown, as set with [`set_number_format()`].
+pub fn number_format() -> NumberFormat {
+    *NUMBER_FORMAT.read().expect("not poisoned")
+}
+
+/// Show `n` in the [current number format][number_format()].
+pub fn format_number(n: impl fmt::Display) -> String {
+    number_format().apply(&n.to_string())
+}
+
+impl NumberFormat {
+    /// Digits without separators and decimals after a `.`, like `1234.5`
+    pub const PLAIN: NumberFormat = NumberFormat {
+        thousands_separator: None,
+        decimal_separator: '.',
+    };
+
+    /// The format of the locale in `LC_ALL`, `LC_NUMERIC` or `LANG`, whichever is set first.
+    pub fn from_locale() -> NumberFormat {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .find(|locale| !locale.is_empty())
+            .map(|locale| NumberFormat::of_locale(&locale.to_string_lossy()))
+            .unwrap_or(NumberFormat::PLAIN)
+    }
+
+    /// The format of `locale`, like `de_DE.UTF-8`, as far as its language and territory tell.
+    fn of_locale(locale: &str) -> NumberFormat {
+        let locale = locale.split(['.', '@']).next().unwrap_or("");
+        let (language, territory) = locale.split_once('_').unwrap_or((locale, ""));
+        let (thousands_separator, decimal_separator) = match (language, territory) {
+            ("" | "C" | "POSIX", _) => return NumberFormat::PLAIN,
+            ("de" | "fr" | "it" | "rm", "CH") | (_, "LI") => ('\'', '.'),
+            (
+                "da" | "de" | "el" | "es" | "hr" | "id" | "it" | "nl" | "pt" | "ro" | "sl" | "sr"
+                | "tr" | "vi",
+                _,
+            ) => ('.', ','),
+            (
+                "bg" | "cs" | "et" | "fi" | "fr" | "hu" | "lt" | "lv" | "nb" | "nn" | "no" | "pl"
+                | "ru" | "sk" | "sv" | "uk",
+                _,
+            ) => (' ', ','),
+            _ => (',', '.'),
+        };
+        NumberFormat {
+            thousands_separator: Some(thousands_separator),
+            decimal_separator,
+        }
+    }
+
+    /// Return `number`, made of digits and optional decimals after a `.`, with the separators of this format.
+    pub fn apply(self, number: &str) -> String {
+        let (integer, decimals) = match number.split_once('.') {
+            Some((integer, decimals)) => (integer, Some(decimals)),
+            None => (number, None),
+        };
+        let (sign, digits) = match integer.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", integer),
+        };
+        let mut out = String::with_capacity(number.len() + digits.len() / 3 * 3);
+        out.push_str(sign);
+        for (pos, digit) in digits.chars().enumerate() {
+            match self.thousands_separator {
+                Some(separator) if pos != 0 && (digits.len() - pos) % 3 == 0 => out.push(separator),
+                _ => {}
+            }
+            out.push(digit);
+        }
+        if let Some(decimals) = decimals {
+            out.push(self.decimal_separator);
+            out.push_str(decimals);
+        }
+        out
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    /// Parse `plain`, `locale`, or the decimal separator, `.` or `,`, optionally preceded by the thousands separator,
+    /// one of `,`, `.`, `'`, `_` or a space, like `,.` for `1,234.5` or `.,` for `1.234,5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid number format {:?}, expected 'plain', 'locale' or separators like ',.' or '.,'",
+                s
+            )
+        };
+        let (thousands_separator, decimal_separator) = match s {
+            "plain" => return Ok(NumberFormat::PLAIN),
+            "locale" => return Ok(NumberFormat::from_locale()),
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next(), chars.next()) {
+                    (Some(decimal), None, None) => (None, decimal),
+                    (Some(thousands), Some(decimal), None) => (Some(thousands), decimal),
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        if !matches!(decimal_separator, '.' | ',')
+            || thousands_separator.is_some_and(|thousands| {
+                thousands == decimal_separator || !matches!(thousands, ',' | '.' | '\'' | '_' | ' ')
+            })
+        {
+            return Err(invalid());
+        }
+        Ok(NumberFormat {
+            thousands_separator,
+            decimal_separator,
+        })
+    }
+}
+
+/// A size entries are compared with to decide if they are shown, like the threshold of GNU `du`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Threshold {
+    /// Only entries at least as large as the given amount of bytes are shown
+    AtLeast(u128),
+    /// Only entries at most as large as the given amount of bytes are shown
+    AtMost(u128),
+}
+
+impl Threshold {
+    /// Return true if an entry of `size` bytes is shown.
+    pub fn includes(&self, size: u128) -> bool {
+        match *self {
+            Threshold::AtLeast(threshold) => size >= threshold,
+            Threshold::AtMost(threshold) => size <= threshold,
+        }
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    /// Parse a size like `100`, `10K`, `1.5MB` or `2GiB`, which is negative to set an upper bound.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (size, threshold): (_, fn(u128) -> Threshold) = match s.trim().strip_prefix('-') {
+            Some(size) => (size, Threshold::AtMost),
+            None => (s, Threshold::AtLeast),
+        };
+        byte_unit::Byte::from_str(size)
+            .map(|size| threshold(size.get_bytes()))
+            .map_err(|err| format!("Invalid size {:?}: {}", s, err))
+    }
+}
+
+/// A size the input paths must not exceed, to let scripts fail if they do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeLimit {
+    pub bytes: u128,
+    /// If set, each input path is compared with the limit, otherwise the total of all of them is.
+    pub per_path: bool,
+}
+
+impl SizeLimit {
+    /// Return the paths among `sizes` of input paths which are larger than the limit, along with their size. If it
+    /// isn't `per_path`, it's a path named `total` with the sum of all sizes, if it's larger.
+    pub fn exceeded_by(&self, sizes: &[(PathBuf, u128)]) -> Vec<(PathBuf, u128)> {
+        if self.per_path {
+            sizes
+                .iter()
+                .filter(|(_, size)| *size > self.bytes)
+                .cloned()
+                .collect()
+        } else {
+            let total = sizes.iter().map(|(_, size)| size).sum();
+            if total > self.bytes {
+                vec![(PathBuf::from("total"), total)]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
 /// Identify the kind of sorting to apply during filesystem iteration
 #[derive(Clone)]
 pub enum TraversalSorting {
@@ -112,45 +377,368 @@ pub enum TraversalSorting {
     AlphabeticalByFileName,
 }
 
+/// How symbolic links are treated during a traversal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymlinkMode {
+    /// Symbolic links are entries of their own, with the size of the link itself
+    Never,
+    /// Input paths which are symbolic links are walked like what they point to, all others are entries of their own
+    InputPaths,
+    /// All symbolic links are walked like what they point to. Directories are walked only once, no matter how many
+    /// links lead to them, which also stops links from looping back to their parents.
+    Always,
+}
+
 /// Configures a filesystem walk, including output and formatting options.
 #[derive(Clone)]
 pub struct WalkOptions {
-    /// The amount of threads to use. Refer to [`WalkDir::num_threads()`](https://docs.rs/jwalk/0.4.0/jwalk/struct.WalkDir.html#method.num_threads)
-    /// for more information.
+    /// The amount of threads to use. 0 uses one thread per logical processor, 1 walks on the calling thread.
+    /// All threads are shared by all input paths of a walk and work-steal directories from each other.
+    /// Entries are always yielded in depth-first order, so the amount of threads doesn't affect the resulting tree.
     pub threads: usize,
     pub byte_format: ByteFormat,
     pub count_hard_links: bool,
     pub apparent_size: bool,
     pub sorting: TraversalSorting,
     pub cross_filesystems: bool,
+    /// Entries matching any of these patterns are skipped entirely, along with all of their children.
+    pub exclude: Vec<Glob>,
+    /// Whether entries are skipped depending on the `.gitignore` files in the walked directories and their parents.
+    pub gitignore: GitignoreMode,
+    /// If set, directories tagged as cache with a [`CACHEDIR_TAG`] file are skipped entirely.
+    pub skip_cache_dirs: bool,
+    /// Whether symbolic links are walked like what they point to.
+    pub symlinks: SymlinkMode,
+    /// If set, the contents of archives with one of the [`EXTENSIONS`][crate::archive::EXTENSIONS] are listed as
+    /// entries within them when building a [`Traversal`]. They are marked as being in an archive, and don't add to
+    /// the size of the archive or its parents.
+    pub archives: bool,
+    /// If set, the size of each file and directory is the inode it uses instead of its bytes, so sizes are amounts of
+    /// inodes. Like bytes, hard-linked files are counted once unless `count_hard_links` is set.
+    pub count_inodes: bool,
+    /// If set, a [`Traversal`] keeps no more than about this amount of entries in memory, by collapsing the smallest
+    /// files of each directory into one entry standing for all of them whenever there are more.
+    pub max_entries: Option<usize>,
+    /// If set, virtual filesystems like `/proc` and `/sys` are walked like all others. Otherwise, their mount points
+    /// below the walked paths are yielded, but not descended into, as their files would add sizes which don't take
+    /// any space. See [`virtual_mount_points()`][crate::devices::virtual_mount_points()] for which these are.
+    pub include_virtual: bool,
 }
 
-type WalkDir = jwalk::WalkDirGeneric<((), Option<Result<std::fs::Metadata, jwalk::Error>>)>;
+type ClientState = (
+    GitignoreState,
+    Option<Result<std::fs::Metadata, jwalk::Error>>,
+);
+type WalkDir = jwalk::WalkDirGeneric<ClientState>;
+/// An entry yielded by a walk. Its metadata is only read for entries that aren't directories.
+pub(crate) type DirEntry = jwalk::DirEntry<ClientState>;
+
+/// One thread per logical processor, metric byte formatting, disk usage with hard links counted once, crossing
+/// filesystems except virtual ones and following only input paths which are symbolic links, nothing excluded and
+/// archives not looked into.
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            threads: 0,
+            byte_format: ByteFormat::Metric,
+            count_hard_links: false,
+            apparent_size: false,
+            sorting: TraversalSorting::None,
+            cross_filesystems: true,
+            exclude: Vec::new(),
+            gitignore: GitignoreMode::Disregard,
+            skip_cache_dirs: false,
+            symlinks: SymlinkMode::InputPaths,
+            archives: false,
+            count_inodes: false,
+            max_entries: None,
+            include_virtual: false,
+        }
+    }
+}
 
+/// Builder methods, to change the [defaults][WalkOptions::default()] like
+/// `WalkOptions::default().threads(1).apparent_size(true)`.
 impl WalkOptions {
-    pub(crate) fn iter_from_path(&self, path: &Path) -> WalkDir {
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+    pub fn byte_format(mut self, byte_format: ByteFormat) -> Self {
+        self.byte_format = byte_format;
+        self
+    }
+    pub fn count_hard_links(mut self, count_hard_links: bool) -> Self {
+        self.count_hard_links = count_hard_links;
+        self
+    }
+    pub fn apparent_size(mut self, apparent_size: bool) -> Self {
+        self.apparent_size = apparent_size;
+        self
+    }
+    pub fn sorting(mut self, sorting: TraversalSorting) -> Self {
+        self.sorting = sorting;
+        self
+    }
+    pub fn cross_filesystems(mut self, cross_filesystems: bool) -> Self {
+        self.cross_filesystems = cross_filesystems;
+        self
+    }
+    /// Add `glob` to the patterns of entries to skip.
+    pub fn exclude(mut self, glob: Glob) -> Self {
+        self.exclude.push(glob);
+        self
+    }
+    pub fn gitignore(mut self, gitignore: GitignoreMode) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+    pub fn skip_cache_dirs(mut self, skip_cache_dirs: bool) -> Self {
+        self.skip_cache_dirs = skip_cache_dirs;
+        self
+    }
+    pub fn symlinks(mut self, symlinks: SymlinkMode) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+    pub fn archives(mut self, archives: bool) -> Self {
+        self.archives = archives;
+        self
+    }
+    pub fn count_inodes(mut self, count_inodes: bool) -> Self {
+        self.count_inodes = count_inodes;
+        self
+    }
+    pub fn max_entries(mut self, max_entries: Option<usize>) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+    pub fn include_virtual(mut self, include_virtual: bool) -> Self {
+        self.include_virtual = include_virtual;
+        self
+    }
+
+    /// Walk all `paths` and return the tree of their entries.
+    ///
+    /// Use [`Traversal::from_walk()`] to be called back while walking, to show progress or to stop early.
+    pub fn traverse(
+        self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> anyhow::Result<Traversal> {
+        let paths = paths.into_iter().map(|p| p.as_ref().to_owned()).collect();
+        Traversal::from_walk(self, paths, |_| Ok(false))
+    }
+}
+
+impl WalkOptions {
+    /// Create the parallelism to use for all input paths of a single walk.
+    ///
+    /// The thread pool, if any, is owned by the returned value and torn down once the walk is done, instead of
+    /// lingering like rayon's global pool would.
+    pub(crate) fn parallelism(&self) -> io::Result<jwalk::Parallelism> {
+        Ok(match self.threads {
+            1 => jwalk::Parallelism::Serial,
+            n => jwalk::Parallelism::RayonExistingPool(Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(n)
+                    .build()
+                    .map_err(io::Error::other)?,
+            )),
+        })
+    }
+
+    /// Walk `path`, which resides on the device with `root_device_id`.
+    /// Unless `cross_filesystems` is set, directories on other devices are yielded, but not descended into, and
+    /// neither are the mount points of virtual filesystems unless `include_virtual` is set.
+    pub(crate) fn iter_from_path(
+        &self,
+        path: &Path,
+        root_device_id: u64,
+        parallelism: jwalk::Parallelism,
+    ) -> WalkDir {
+        let cross_filesystems = self.cross_filesystems;
+        let exclude = self.exclude.clone();
+        let gitignore = self.gitignore;
+        let skip_cache_dirs = self.skip_cache_dirs;
+        let symlinks = self.symlinks;
+        let visited_dirs = Arc::new(Mutex::new(HashSet::new()));
+        let virtual_mount_points = if self.include_virtual {
+            HashSet::new()
+        } else {
+            virtual_mount_points_below(path)
+        };
         WalkDir::new(path)
-            .follow_links(false)
+            .follow_links(symlinks == SymlinkMode::Always)
             .sort(match self.sorting {
                 TraversalSorting::None => false,
                 TraversalSorting::AlphabeticalByFileName => true,
             })
             .skip_hidden(false)
-            .process_read_dir(|_, _, _, dir_entry_results| {
+            .root_read_dir_state(match gitignore {
+                GitignoreMode::Disregard => GitignoreState::default(),
+                _ => GitignoreState::for_root(path),
+            })
+            .process_read_dir(move |depth, parent, ignores, dir_entry_results| {
+                if !exclude.is_empty() {
+                    dir_entry_results.retain(|dir_entry_result| match dir_entry_result {
+                        Ok(dir_entry) => !exclude
+                            .iter()
+                            .any(|glob| glob.matches(parent, &dir_entry.file_name)),
+                        Err(_) => true,
+                    });
+                }
+                if skip_cache_dirs {
+                    dir_entry_results.retain(|dir_entry_result| match dir_entry_result {
+                        Ok(dir_entry) => {
+                            !(dir_entry.file_type.is_dir()
+                                && is_cache_dir(&parent.join(&dir_entry.file_name)))
+                        }
+                        Err(_) => true,
+                    });
+                }
+                // The root itself is always walked, as it was asked for
+                if gitignore != GitignoreMode::Disregard && depth.is_some() && !ignores.is_ignored {
+                    if gitignore == GitignoreMode::OnlyIgnored && ignores.is_ignored(parent, true) {
+                        ignores.is_ignored = true;
+                    } else {
+                        ignores.enter(parent);
+                        dir_entry_results.retain(|dir_entry_result| match dir_entry_result {
+                            Ok(dir_entry) => {
+                                let is_dir = dir_entry.file_type.is_dir();
+                                let is_ignored =
+                                    ignores.is_ignored(&parent.join(&dir_entry.file_name), is_dir);
+                                match gitignore {
+                                    GitignoreMode::OnlyIgnored => is_ignored || is_dir,
+                                    _ => !is_ignored,
+                                }
+                            }
+                            Err(_) => true,
+                        });
+                    }
+                }
+                if !virtual_mount_points.is_empty() {
+                    for dir_entry in dir_entry_results.iter_mut().flatten() {
+                        if dir_entry.file_type.is_dir()
+                            && virtual_mount_points.contains(&parent.join(&dir_entry.file_name))
+                        {
+                            dir_entry.read_children_path = None;
+                        }
+                    }
+                }
                 dir_entry_results.iter_mut().for_each(|dir_entry_result| {
                     if let Ok(dir_entry) = dir_entry_result {
+                        // Input paths pointing to directories are always walked unless we say otherwise
+                        if depth.is_none() && dir_entry.file_type.is_symlink() {
+                            match symlinks {
+                                SymlinkMode::Never => dir_entry.read_children_path = None,
+                                _ => {
+                                    if let Ok(m) = std::fs::metadata(dir_entry.path()) {
+                                        if !m.is_dir() {
+                                            dir_entry.client_state = Some(Ok(m));
+                                        }
+                                        return;
+                                    }
+                                }
+                            }
+                        }
                         if dir_entry.file_type.is_file() || dir_entry.file_type().is_symlink() {
                             dir_entry.client_state = Some(dir_entry.metadata());
+                        } else if dir_entry.file_type.is_dir()
+                            && (symlinks == SymlinkMode::Always || !cross_filesystems)
+                        {
+                            // The type of the directory entry tells directories apart without a stat() call, and
+                            // they need at most one to see if they were visited already or are on another device.
+                            if let Ok(m) = dir_entry.metadata() {
+                                let is_visited = symlinks == SymlinkMode::Always
+                                    && dev_inode(&m).is_some_and(|dev_inode| {
+                                        !visited_dirs
+                                            .lock()
+                                            .expect("not poisoned")
+                                            .insert(dev_inode)
+                                    });
+                                if is_visited
+                                    || (!cross_filesystems
+                                        && !crossdev::is_same_device(root_device_id, &m))
+                                {
+                                    dir_entry.read_children_path = None;
+                                }
+                            }
                         }
                     }
                 })
             })
-            .parallelism(match self.threads {
-                0 => jwalk::Parallelism::RayonDefaultPool,
-                1 => jwalk::Parallelism::Serial,
-                _ => jwalk::Parallelism::RayonNewPool(self.threads),
-            })
+            .parallelism(parallelism)
+    }
+}
+
+/// Return the mount points of virtual filesystems below `path`, which isn't one of them itself, starting with `path`
+/// like the paths of the entries of its walk.
+fn virtual_mount_points_below(path: &Path) -> HashSet<PathBuf> {
+    let mount_points = crate::devices::virtual_mount_points();
+    let canonical = match path.canonicalize() {
+        Ok(canonical) if !mount_points.is_empty() => canonical,
+        _ => return HashSet::new(),
+    };
+    mount_points
+        .iter()
+        .filter_map(|mount_point| mount_point.strip_prefix(&canonical).ok())
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .map(|relative| path.join(relative))
+        .collect()
+}
+
+/// Return the device and inode of the entry with the given metadata, which identify it no matter which path leads
+/// to it.
+#[cfg(unix)]
+fn dev_inode(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn dev_inode(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// The name of the file marking its directory as cache, see <https://bford.info/cachedir/>
+pub const CACHEDIR_TAG: &str = "CACHEDIR.TAG";
+const CACHEDIR_TAG_SIGNATURE: &[u8] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+/// Return true if the file at `path` is a valid cache directory tag, which starts with a signature.
+pub fn is_cache_dir_tag(path: &Path) -> bool {
+    let mut signature = [0; CACHEDIR_TAG_SIGNATURE.len()];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut signature))
+        .is_ok()
+        && signature == CACHEDIR_TAG_SIGNATURE
+}
+
+/// Return true if the directory at `path` was tagged as cache, whose contents can be recreated.
+pub fn is_cache_dir(path: &Path) -> bool {
+    is_cache_dir_tag(&path.join(CACHEDIR_TAG))
+}
+
+/// Read paths from `read`, each followed by `separator`, like `b'\0'` for the output of `find -print0`.
+/// Empty paths are skipped, and the last path doesn't need a separator.
+pub fn read_paths(read: impl Read, separator: u8) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for bytes in io::BufReader::new(read).split(separator) {
+        let bytes = bytes?;
+        if bytes.is_empty() {
+            continue;
+        }
+        #[cfg(unix)]
+        let path =
+            PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes));
+        #[cfg(not(unix))]
+        let path = PathBuf::from(
+            String::from_utf8(bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        );
+        paths.push(path);
     }
+    Ok(paths)
 }
 
 /// Information we gather during a filesystem walk
@@ -169,3 +757,240 @@ impl WalkResult {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::fs;
+
+    #[test]
+    fn numbers_are_shown_with_the_separators_of_their_format() {
+        let format = |s: &str| s.parse::<NumberFormat>().map_err(|_| ());
+        assert_eq!(
+            format(",.").map(|f| f.apply("1234567.89")).as_deref(),
+            Ok("1,234,567.89")
+        );
+        assert_eq!(
+            format(".,").map(|f| f.apply("-123456")).as_deref(),
+            Ok("-123.456")
+        );
+        assert_eq!(
+            format(" ,").map(|f| f.apply("999.5")).as_deref(),
+            Ok("999,5")
+        );
+        assert_eq!(
+            format(",").map(|f| f.apply("12345.6")).as_deref(),
+            Ok("12345,6")
+        );
+        assert_eq!(format("plain"), Ok(NumberFormat::PLAIN));
+        assert_eq!(format(",,"), Err(()));
+        assert_eq!(format("x."), Err(()));
+        assert_eq!(format(",.,"), Err(()));
+
+        let of_locale = |locale| {
+            let format = NumberFormat::of_locale(locale);
+            (format.thousands_separator, format.decimal_separator)
+        };
+        assert_eq!(of_locale("de_DE.UTF-8"), (Some('.'), ','));
+        assert_eq!(of_locale("de_CH.UTF-8"), (Some('\''), '.'));
+        assert_eq!(of_locale("fr_FR@euro"), (Some(' '), ','));
+        assert_eq!(of_locale("en_US.UTF-8"), (Some(','), '.'));
+        assert_eq!(of_locale("C.UTF-8"), (None, '.'));
+    }
+
+    #[test]
+    fn sizes_in_a_single_unit_have_the_same_precision_whatever_their_magnitude() {
+        let display = |format: ByteFormat, bytes| format.display(bytes).to_string();
+        assert_eq!(display(ByteFormat::KB, 1), "0.00 KB");
+        assert_eq!(display(ByteFormat::KB, 1_234_567_890), "1234567.89 KB");
+        assert_eq!(display(ByteFormat::KiB, 1536), "1.50 KiB");
+        assert_eq!(display(ByteFormat::TB, 1_500_000_000), "0.00 TB");
+        assert_eq!(display(ByteFormat::TB, 2_500_000_000_000_000), "2500.00 TB");
+        assert_eq!(display(ByteFormat::TiB, 1 << 40), "1.00 TiB");
+        for format in &[
+            ByteFormat::KiB,
+            ByteFormat::KB,
+            ByteFormat::TB,
+            ByteFormat::TiB,
+        ] {
+            assert!(display(*format, 98_765_432_109_876).len() <= format.total_width());
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn virtual_filesystems_below_walked_paths_are_found_as_they_are_walked() {
+        let proc = Path::new("/proc");
+        if !crate::devices::virtual_mount_points()
+            .iter()
+            .any(|p| p == proc)
+        {
+            return;
+        }
+        assert!(virtual_mount_points_below(Path::new("/")).contains(proc));
+        assert!(
+            virtual_mount_points_below(Path::new("/proc/..")).contains(Path::new("/proc/../proc"))
+        );
+        assert!(
+            !virtual_mount_points_below(proc).contains(proc),
+            "walked paths are walked, whatever they are"
+        );
+    }
+
+    #[test]
+    fn paths_are_read_up_to_each_separator() -> io::Result<()> {
+        assert_eq!(
+            read_paths(&b"a\0b c\0\0d/e"[..], b'\0')?,
+            vec![
+                PathBuf::from("a"),
+                PathBuf::from("b c"),
+                PathBuf::from("d/e")
+            ]
+        );
+        assert_eq!(
+            read_paths(&b"a\nb\0c\n"[..], b'\n')?,
+            vec![PathBuf::from("a"), PathBuf::from("b\0c")]
+        );
+        assert!(read_paths(&b""[..], b'\n')?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn thresholds_are_parsed_as_lower_or_upper_bounds() {
+        assert_eq!("100".parse(), Ok(Threshold::AtLeast(100)));
+        assert_eq!("10K".parse(), Ok(Threshold::AtLeast(10_000)));
+        assert_eq!("-1MiB".parse(), Ok(Threshold::AtMost(1024 * 1024)));
+        assert!("lots".parse::<Threshold>().is_err());
+        assert!(Threshold::AtLeast(10).includes(10) && !Threshold::AtLeast(10).includes(9));
+        assert!(Threshold::AtMost(10).includes(10) && !Threshold::AtMost(10).includes(11));
+    }
+
+    #[test]
+    fn size_limits_apply_to_each_path_or_their_total() {
+        let sizes = vec![(PathBuf::from("a"), 10), (PathBuf::from("b"), 20)];
+        let limit = |bytes, per_path| SizeLimit { bytes, per_path }.exceeded_by(&sizes);
+        assert_eq!(limit(10, true), vec![(PathBuf::from("b"), 20)]);
+        assert_eq!(limit(20, true), vec![]);
+        assert_eq!(limit(20, false), vec![(PathBuf::from("total"), 30)]);
+        assert_eq!(limit(30, false), vec![]);
+    }
+
+    #[test]
+    fn cache_dirs_are_flagged_or_skipped() -> anyhow::Result<()> {
+        let root = TempDir::new("cachedir")?;
+        for dir in &["cache/data", "fake-cache", "src"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        fs::write(
+            root.join("cache").join(CACHEDIR_TAG),
+            "Signature: 8a477f597d28d172789f06886806bc55\n# a cache\n",
+        )?;
+        fs::write(
+            root.join("fake-cache").join(CACHEDIR_TAG),
+            "Signature: none",
+        )?;
+        fs::write(root.join("cache/data/blob"), "cached")?;
+
+        let walk = |skip_cache_dirs| {
+            Traversal::from_walk(
+                WalkOptions::default()
+                    .threads(1)
+                    .apparent_size(true)
+                    .skip_cache_dirs(skip_cache_dirs),
+                vec![root.to_path_buf()],
+                |_| Ok(false),
+            )
+        };
+        let cache_dirs = |t: &Traversal| -> Vec<String> {
+            let mut names: Vec<_> = t
+                .tree
+                .node_indices()
+                .filter(|idx| t.tree[*idx].is_cache_dir)
+                .map(|idx| t.tree.name_of(idx).to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        let has_entry = |t: &Traversal, name: &str| {
+            t.tree
+                .node_indices()
+                .any(|idx| t.tree.name_of(idx) == Path::new(name))
+        };
+
+        let t = walk(false)?;
+        assert_eq!(cache_dirs(&t), vec!["cache"]);
+        assert!(has_entry(&t, "blob"));
+
+        let t = walk(true)?;
+        assert!(cache_dirs(&t).is_empty());
+        assert!(!has_entry(&t, "cache") && !has_entry(&t, "blob"));
+        assert!(has_entry(&t, "fake-cache"), "the signature is required");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_followed_only_when_asked_to_and_without_looping() -> anyhow::Result<()> {
+        use std::os::unix::fs::symlink;
+        let root = TempDir::new("symlinks")?;
+        fs::create_dir_all(root.join("tree/data"))?;
+        fs::write(root.join("tree/data/blob"), "0123456789")?;
+        symlink("data", root.join("tree/alias"))?;
+        symlink("data/blob", root.join("tree/blob-link"))?;
+        symlink("..", root.join("tree/data/parent"))?;
+        symlink("tree", root.join("tree-link"))?;
+
+        let walk = |symlinks, input: &str| {
+            Traversal::from_walk(
+                WalkOptions::default()
+                    .threads(1)
+                    .apparent_size(true)
+                    .symlinks(symlinks),
+                vec![root.join(input)],
+                |_| Ok(false),
+            )
+        };
+        let entry = |t: &Traversal, name: &str| {
+            t.tree
+                .node_indices()
+                .find(|idx| t.tree.name_of(*idx) == Path::new(name))
+                .map(|idx| t.tree[idx].clone())
+        };
+        let link_size = |target: &str| target.len() as u128;
+
+        let t = walk(SymlinkMode::Never, "tree")?;
+        let alias = entry(&t, "alias").expect("links are entries");
+        assert!(alias.is_symlink && !alias.is_dir);
+        assert_eq!(
+            u128::from(alias.size),
+            link_size("data"),
+            "links have their own size"
+        );
+        assert_eq!(
+            t.total_bytes,
+            Some(10 + link_size("data") + link_size("data/blob") + link_size(".."))
+        );
+        let t = walk(SymlinkMode::Never, "tree-link")?;
+        assert_eq!(t.entries_traversed, 1, "input paths aren't followed either");
+
+        let t = walk(SymlinkMode::InputPaths, "tree-link")?;
+        assert!(entry(&t, "data").is_some(), "input paths are followed");
+        assert!(
+            entry(&t, "alias").expect("link").size < 10,
+            "links within are not"
+        );
+
+        let t = walk(SymlinkMode::Always, "tree")?;
+        assert!(
+            entry(&t, "blob-link").expect("link").size == 10,
+            "links count what they point to"
+        );
+        assert_eq!(
+            t.total_bytes,
+            Some(20),
+            "the directory linked twice and the parent are walked only once"
+        );
+        Ok(())
+    }
+}
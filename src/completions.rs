@@ -0,0 +1,463 @@
+//! Completion scripts for shells, generated from the definition of the command-line so they always know all
+//! subcommands, their options and the values options like `--format` take.
+use crate::options::Shell;
+use clap::{App, ArgSettings};
+use std::io;
+
+/// A subcommand, or the command itself if it has no names
+struct Command<'a> {
+    /// The name of the subcommand followed by its aliases
+    names: Vec<&'a str>,
+    about: String,
+    options: Vec<Opt<'a>>,
+}
+
+/// An option of a command, which has a short or a long name or both
+struct Opt<'a> {
+    name: &'a str,
+    short: Option<char>,
+    long: Option<&'a str>,
+    about: String,
+    takes_value: bool,
+    /// If set, the option may be given more than once
+    is_repeatable: bool,
+    /// The values the option takes, or none if it takes a path or anything else
+    values: &'a [&'a str],
+}
+
+impl<'a> Opt<'a> {
+    /// The short and long form of the option as given on the command-line, like `-f` and `--format`
+    fn flags(&self) -> Vec<String> {
+        self.short
+            .map(|short| format!("-{}", short))
+            .into_iter()
+            .chain(self.long.map(|long| format!("--{}", long)))
+            .collect()
+    }
+}
+
+fn command_of<'a>(app: &'a App<'_>, names: Vec<&'a str>) -> Command<'a> {
+    Command {
+        names,
+        about: first_sentence(app.get_about()),
+        options: app
+            .get_arguments()
+            .filter(|arg| arg.get_short().is_some() || arg.get_long().is_some())
+            .map(|arg| Opt {
+                name: arg.get_name(),
+                short: arg.get_short(),
+                long: arg.get_long(),
+                about: first_sentence(arg.get_about()),
+                takes_value: arg.is_set(ArgSettings::TakesValue),
+                is_repeatable: arg.is_set(ArgSettings::MultipleOccurrences),
+                values: arg.get_possible_values().unwrap_or(&[]),
+            })
+            .collect(),
+    }
+}
+
+/// The first sentence of `help`, without the period ending it
+fn first_sentence(help: Option<&str>) -> String {
+    let help = help.unwrap_or("").trim();
+    let sentence = help.split(". ").next().unwrap_or(help);
+    sentence.trim_end_matches('.').to_owned()
+}
+
+/// Write a script completing the subcommands and options of `app` in `shell` to `out`.
+pub fn write(mut out: impl io::Write, shell: Shell, app: &App<'_>) -> io::Result<()> {
+    let bin = app.get_name();
+    let top = command_of(app, Vec::new());
+    let subcommands: Vec<_> = app
+        .get_subcommands()
+        .map(|sub| {
+            let mut names: Vec<_> = std::iter::once(sub.get_name())
+                .chain(sub.get_all_aliases())
+                .collect();
+            // Visible aliases are listed among all aliases as well
+            names.dedup();
+            command_of(sub, names)
+        })
+        .collect();
+    match shell {
+        Shell::Bash => write_bash(&mut out, bin, &top, &subcommands),
+        Shell::Zsh => write_zsh(&mut out, bin, &top, &subcommands),
+        Shell::Fish => write_fish(&mut out, bin, &top, &subcommands),
+        Shell::Powershell => write_powershell(&mut out, bin, &top, &subcommands),
+    }
+}
+
+fn write_bash(
+    out: &mut impl io::Write,
+    bin: &str,
+    top: &Command<'_>,
+    subcommands: &[Command<'_>],
+) -> io::Result<()> {
+    let function = format!("_{}", bin.replace('-', "_"));
+    writeln!(out, "{}() {{", function)?;
+    writeln!(out, "    local cur prev command word opts")?;
+    writeln!(out, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(out, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(out, "    command=\"\"")?;
+    writeln!(
+        out,
+        "    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do"
+    )?;
+    writeln!(out, "        case \"$word\" in")?;
+    for sub in subcommands {
+        writeln!(
+            out,
+            "            {}) command={}; break ;;",
+            sub.names.join("|"),
+            sub.names[0]
+        )?;
+    }
+    writeln!(out, "        esac")?;
+    writeln!(out, "    done")?;
+    writeln!(out, "    case \"$command:$prev\" in")?;
+    for command in std::iter::once(top).chain(subcommands) {
+        let name = command.names.first().copied().unwrap_or("");
+        for opt in command.options.iter().filter(|opt| opt.takes_value) {
+            let patterns: Vec<_> = opt
+                .flags()
+                .iter()
+                .map(|flag| format!("\"{}:{}\"", name, flag))
+                .collect();
+            let reply = if opt.values.is_empty() {
+                "compgen -f -- \"$cur\"".to_owned()
+            } else {
+                format!("compgen -W \"{}\" -- \"$cur\"", opt.values.join(" "))
+            };
+            writeln!(
+                out,
+                "        {}) COMPREPLY=($({})); return ;;",
+                patterns.join("|"),
+                reply
+            )?;
+        }
+    }
+    writeln!(out, "    esac")?;
+    writeln!(out, "    case \"$command\" in")?;
+    for command in std::iter::once(top).chain(subcommands) {
+        let mut words: Vec<String> = command.options.iter().flat_map(Opt::flags).collect();
+        if command.names.is_empty() {
+            words.extend(
+                subcommands
+                    .iter()
+                    .flat_map(|sub| sub.names.iter())
+                    .map(|name| name.to_string()),
+            );
+        }
+        writeln!(
+            out,
+            "        \"{}\") opts=\"{}\" ;;",
+            command.names.first().copied().unwrap_or(""),
+            words.join(" ")
+        )?;
+    }
+    writeln!(out, "    esac")?;
+    writeln!(out, "    COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))")?;
+    writeln!(out, "    if [[ \"$cur\" != -* ]]; then")?;
+    writeln!(out, "        COMPREPLY+=($(compgen -f -- \"$cur\"))")?;
+    writeln!(out, "    fi")?;
+    writeln!(out, "}}")?;
+    writeln!(out, "complete -F {} -o filenames {}", function, bin)
+}
+
+/// Escape `s` to be used within the brackets of a single-quoted specification of `_arguments`.
+fn zsh_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_specs(command: &Command<'_>) -> Vec<String> {
+    let mut specs = Vec::new();
+    for opt in &command.options {
+        let value = if !opt.takes_value {
+            String::new()
+        } else if opt.values.is_empty() {
+            format!(":{}:_files", opt.name)
+        } else {
+            format!(":{}:({})", opt.name, opt.values.join(" "))
+        };
+        for flag in opt.flags() {
+            let suffix = match (opt.takes_value, flag.starts_with("--")) {
+                (false, _) => "",
+                (true, true) => "=",
+                (true, false) => "+",
+            };
+            specs.push(format!(
+                "'{}{}{}[{}]{}'",
+                if opt.is_repeatable { "*" } else { "" },
+                flag,
+                suffix,
+                zsh_escape(&opt.about),
+                value
+            ));
+        }
+    }
+    specs
+}
+
+fn write_zsh(
+    out: &mut impl io::Write,
+    bin: &str,
+    top: &Command<'_>,
+    subcommands: &[Command<'_>],
+) -> io::Result<()> {
+    let function = format!("_{}", bin.replace('-', "_"));
+    writeln!(out, "#compdef {}", bin)?;
+    writeln!(out)?;
+    writeln!(out, "{}_commands() {{", function)?;
+    writeln!(out, "    local commands; commands=(")?;
+    for sub in subcommands {
+        for name in &sub.names {
+            writeln!(out, "        '{}:{}'", name, zsh_escape(&sub.about))?;
+        }
+    }
+    writeln!(out, "    )")?;
+    writeln!(out, "    _describe 'command' commands")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "{}() {{", function)?;
+    writeln!(out, "    local context state line")?;
+    writeln!(out, "    _arguments -C -s \\")?;
+    for spec in zsh_specs(top) {
+        writeln!(out, "        {} \\", spec)?;
+    }
+    writeln!(out, "        '*::arg:->args'")?;
+    writeln!(out, "    case $state in")?;
+    writeln!(out, "        args)")?;
+    writeln!(out, "            if (( CURRENT == 1 )); then")?;
+    writeln!(
+        out,
+        "                _alternative 'commands:command:{}_commands' 'files:input path:_files'",
+        function
+    )?;
+    writeln!(out, "                return")?;
+    writeln!(out, "            fi")?;
+    writeln!(out, "            case $words[1] in")?;
+    for sub in subcommands {
+        writeln!(out, "                ({})", sub.names.join("|"))?;
+        writeln!(out, "                    _arguments -s \\")?;
+        for spec in zsh_specs(sub) {
+            writeln!(out, "                        {} \\", spec)?;
+        }
+        writeln!(out, "                        '*:input path:_files'")?;
+        writeln!(out, "                    ;;")?;
+    }
+    writeln!(out, "                (*)")?;
+    writeln!(out, "                    _files")?;
+    writeln!(out, "                    ;;")?;
+    writeln!(out, "            esac")?;
+    writeln!(out, "            ;;")?;
+    writeln!(out, "    esac")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "{} \"$@\"", function)
+}
+
+fn fish_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn write_fish(
+    out: &mut impl io::Write,
+    bin: &str,
+    top: &Command<'_>,
+    subcommands: &[Command<'_>],
+) -> io::Result<()> {
+    for command in std::iter::once(top).chain(subcommands) {
+        let condition = if command.names.is_empty() {
+            "__fish_use_subcommand".to_owned()
+        } else {
+            format!("__fish_seen_subcommand_from {}", command.names.join(" "))
+        };
+        for opt in &command.options {
+            write!(out, "complete -c {} -n '{}'", bin, condition)?;
+            if let Some(short) = opt.short {
+                write!(out, " -s {}", short)?;
+            }
+            if let Some(long) = opt.long {
+                write!(out, " -l {}", long)?;
+            }
+            if opt.takes_value {
+                if opt.values.is_empty() {
+                    write!(out, " -r")?;
+                } else {
+                    write!(out, " -x -a '{}'", opt.values.join(" "))?;
+                }
+            }
+            writeln!(out, " -d '{}'", fish_escape(&opt.about))?;
+        }
+    }
+    for sub in subcommands {
+        for name in &sub.names {
+            writeln!(
+                out,
+                "complete -c {} -n '__fish_use_subcommand' -f -a '{}' -d '{}'",
+                bin,
+                name,
+                fish_escape(&sub.about)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn powershell_escape(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+fn write_powershell(
+    out: &mut impl io::Write,
+    bin: &str,
+    top: &Command<'_>,
+    subcommands: &[Command<'_>],
+) -> io::Result<()> {
+    let quoted = |words: &mut dyn Iterator<Item = String>| {
+        words
+            .map(|word| format!("'{}'", powershell_escape(&word)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    writeln!(out, "using namespace System.Management.Automation")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Register-ArgumentCompleter -Native -CommandName '{}' -ScriptBlock {{",
+        bin
+    )?;
+    writeln!(
+        out,
+        "    param($wordToComplete, $commandAst, $cursorPosition)"
+    )?;
+    writeln!(
+        out,
+        "    $elements = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})"
+    )?;
+    writeln!(out, "    if ($wordToComplete) {{")?;
+    writeln!(
+        out,
+        "        $elements = @($elements | Select-Object -SkipLast 1)"
+    )?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "    $previous = $elements | Select-Object -Last 1")?;
+    writeln!(out, "    $command = ''")?;
+    writeln!(out, "    foreach ($element in $elements) {{")?;
+    writeln!(out, "        switch ($element) {{")?;
+    for sub in subcommands {
+        writeln!(
+            out,
+            "            {{ $_ -in {} }} {{ $command = '{}' }}",
+            quoted(&mut sub.names.iter().map(|name| name.to_string())),
+            sub.names[0]
+        )?;
+    }
+    writeln!(out, "        }}")?;
+    writeln!(out, "        if ($command) {{ break }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(
+        out,
+        "    $completions = switch (\"${{command}}:${{previous}}\") {{"
+    )?;
+    for command in std::iter::once(top).chain(subcommands) {
+        let name = command.names.first().copied().unwrap_or("");
+        for opt in command
+            .options
+            .iter()
+            .filter(|opt| opt.takes_value && !opt.values.is_empty())
+        {
+            writeln!(
+                out,
+                "        {{ $_ -in {} }} {{ {}; break }}",
+                quoted(
+                    &mut opt
+                        .flags()
+                        .into_iter()
+                        .map(|flag| format!("{}:{}", name, flag))
+                ),
+                quoted(&mut opt.values.iter().map(|value| value.to_string()))
+            )?;
+        }
+    }
+    writeln!(out, "        default {{")?;
+    writeln!(out, "            switch ($command) {{")?;
+    for command in std::iter::once(top).chain(subcommands) {
+        let mut words: Vec<String> = command.options.iter().flat_map(Opt::flags).collect();
+        if command.names.is_empty() {
+            words.extend(
+                subcommands
+                    .iter()
+                    .flat_map(|sub| sub.names.iter())
+                    .map(|name| name.to_string()),
+            );
+        }
+        writeln!(
+            out,
+            "                '{}' {{ {} }}",
+            command.names.first().copied().unwrap_or(""),
+            quoted(&mut words.into_iter())
+        )?;
+    }
+    writeln!(out, "            }}")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(
+        out,
+        "    $completions | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{"
+    )?;
+    writeln!(
+        out,
+        "        [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_)"
+    )?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::Args;
+    use clap::IntoApp;
+
+    fn script(shell: Shell) -> String {
+        let mut out = Vec::new();
+        write(&mut out, shell, &Args::into_app()).expect("writing to memory works");
+        String::from_utf8(out).expect("only UTF-8")
+    }
+
+    #[test]
+    fn all_subcommands_and_values_of_options_are_completed() {
+        let bash = script(Shell::Bash);
+        assert!(bash.contains("            aggregate|a) command=aggregate; break ;;"));
+        assert!(bash.contains(
+            "        \"aggregate:--format\") COMPREPLY=($(compgen -W \"human json csv tsv ndjson prometheus\" -- \"$cur\")); return ;;"
+        ));
+        assert!(bash.contains("complete -F _dua -o filenames dua"));
+
+        let zsh = script(Shell::Zsh);
+        assert!(zsh.starts_with("#compdef dua\n"));
+        assert!(zsh.contains(":format:(human json csv tsv ndjson prometheus)'"));
+
+        let fish = script(Shell::Fish);
+        assert!(fish.contains(
+            "complete -c dua -n '__fish_seen_subcommand_from aggregate a' -l format -x -a 'human json csv tsv ndjson prometheus'"
+        ));
+
+        let powershell = script(Shell::Powershell);
+        assert!(powershell.contains("Register-ArgumentCompleter -Native -CommandName 'dua'"));
+
+        for subcommand in Args::into_app().get_subcommands() {
+            for script in &[&bash, &zsh, &fish, &powershell] {
+                assert!(
+                    script.contains(subcommand.get_name()),
+                    "{} is completed",
+                    subcommand.get_name()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! Reading the configuration file, which is written in a subset of [TOML](https://toml.io).
+//!
+//! Supported are tables like `[themes.mine]`, keys with strings, integers, booleans and arrays of these as values,
+//! and comments. Everything in a table is read at once, there is no support for dotted keys or inline tables.
+use crate::options::{parse_size, ByteFormat};
+use anyhow::{anyhow, bail, Context, Result};
+use std::{collections::BTreeMap, convert::TryFrom, fs, io, path::PathBuf};
+
+/// A value of a key in the configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Return the string this value is, or an error mentioning `key` if it's something else.
+    pub fn as_str(&self, key: &str) -> Result<&str> {
+        match self {
+            Value::String(s) => Ok(s),
+            _ => bail!("'{}' must be a string", key),
+        }
+    }
+
+    /// Return the boolean this value is, or an error mentioning `key` if it's something else.
+    pub fn as_bool(&self, key: &str) -> Result<bool> {
+        match self {
+            Value::Boolean(b) => Ok(*b),
+            _ => bail!("'{}' must be true or false", key),
+        }
+    }
+
+    /// Return the strings in the array this value is, or an error mentioning `key` if it's something else.
+    pub fn as_strs(&self, key: &str) -> Result<Vec<&str>> {
+        match self {
+            Value::Array(values) => values.iter().map(|v| v.as_str(key)).collect(),
+            _ => bail!("'{}' must be an array of strings", key),
+        }
+    }
+}
+
+/// The keys of a table along with their value
+pub type Table = BTreeMap<String, Value>;
+
+/// The content of the configuration file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// All tables by their name, with the keys before the first table in the one named ""
+    pub tables: BTreeMap<String, Table>,
+}
+
+impl Config {
+    #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+    /// Return the names of all tables within the table called `parent`, like `mine` in `[themes.mine]`.
+    pub fn subtables<'a>(&'a self, parent: &'a str) -> impl Iterator<Item = (&'a str, &'a Table)> {
+        self.tables.iter().filter_map(move |(name, table)| {
+            name.strip_prefix(parent)
+                .and_then(|name| name.strip_prefix('.'))
+                .map(|name| (name, table))
+        })
+    }
+}
+
+/// The settings at the top of the configuration file, used unless they are given on the command-line.
+///
+/// Their keys are named like the long command-line flags, with '_' instead of '-'.
+#[derive(Debug, Default, PartialEq)]
+pub struct Settings {
+    pub threads: Option<usize>,
+    pub format: Option<ByteFormat>,
+    pub number_format: Option<dua::NumberFormat>,
+    pub apparent_size: bool,
+    pub count_hard_links: bool,
+    pub stay_on_filesystem: bool,
+    pub include_virtual: bool,
+    /// Patterns of entries to skip, in addition to those given on the command-line
+    pub exclude: Vec<dua::Glob>,
+    pub respect_gitignore: bool,
+    pub only_ignored: bool,
+    pub skip_cache_dirs: bool,
+    pub follow_symlinks: bool,
+    pub dereference_args: bool,
+    pub no_dereference: bool,
+    /// The name of the theme of interactive mode
+    pub theme: Option<String>,
+    /// The way entries are sorted when interactive mode starts
+    pub sort: Option<String>,
+    /// The size above which deletions in interactive mode must be confirmed by typing the amount of entries
+    pub confirm_deletions_above: Option<u128>,
+    /// The columns of interactive mode in the order to show them in, with optional widths like `size:12`
+    pub columns: Option<Vec<String>>,
+}
+
+impl Settings {
+    /// Read the settings from the top of `config`, failing on keys and tables it doesn't know.
+    pub fn from_config(config: &Config) -> Result<Settings> {
+        if let Some(name) = config.tables.keys().find(|name| {
+            !name.is_empty()
+                && *name != "keys"
+                && !name.starts_with("themes.")
+                && !name.starts_with("actions.")
+        }) {
+            bail!("Unknown table '[{}]'", name);
+        }
+        let mut settings = Settings::default();
+        for (key, value) in config.tables.get("").into_iter().flatten() {
+            match key.as_str() {
+                "threads" => {
+                    settings.threads = Some(match value {
+                        Value::Integer(n) => usize::try_from(*n)
+                            .map_err(|_| anyhow!("'threads' must not be negative"))?,
+                        _ => bail!("'threads' must be a number"),
+                    })
+                }
+                "format" => {
+                    settings.format = Some(
+                        value
+                            .as_str(key)?
+                            .parse()
+                            .map_err(|err| anyhow!("{}", err))?,
+                    )
+                }
+                "number_format" => {
+                    settings.number_format = Some(
+                        value
+                            .as_str(key)?
+                            .parse()
+                            .map_err(|err| anyhow!("{}", err))?,
+                    )
+                }
+                "apparent_size" => settings.apparent_size = value.as_bool(key)?,
+                "count_hard_links" => settings.count_hard_links = value.as_bool(key)?,
+                "stay_on_filesystem" => settings.stay_on_filesystem = value.as_bool(key)?,
+                "include_virtual" => settings.include_virtual = value.as_bool(key)?,
+                "exclude" => {
+                    settings.exclude = value
+                        .as_strs(key)?
+                        .into_iter()
+                        .map(|pattern| pattern.parse().map_err(|err| anyhow!("{}", err)))
+                        .collect::<Result<_>>()?
+                }
+                "respect_gitignore" => settings.respect_gitignore = value.as_bool(key)?,
+                "only_ignored" => settings.only_ignored = value.as_bool(key)?,
+                "skip_cache_dirs" => settings.skip_cache_dirs = value.as_bool(key)?,
+                "follow_symlinks" => settings.follow_symlinks = value.as_bool(key)?,
+                "dereference_args" => settings.dereference_args = value.as_bool(key)?,
+                "no_dereference" => settings.no_dereference = value.as_bool(key)?,
+                "theme" => settings.theme = Some(value.as_str(key)?.to_owned()),
+                "sort" => settings.sort = Some(value.as_str(key)?.to_owned()),
+                "columns" => {
+                    settings.columns = Some(
+                        value
+                            .as_strs(key)?
+                            .into_iter()
+                            .map(ToOwned::to_owned)
+                            .collect(),
+                    )
+                }
+                "confirm_deletions_above" => {
+                    settings.confirm_deletions_above =
+                        Some(parse_size(value.as_str(key)?).map_err(|err| anyhow!("{}", err))?)
+                }
+                _ => bail!("Unknown setting '{}'", key),
+            }
+        }
+        if settings.respect_gitignore && settings.only_ignored {
+            bail!("'respect_gitignore' and 'only_ignored' can't be used together");
+        }
+        if settings.no_dereference && (settings.dereference_args || settings.follow_symlinks) {
+            bail!("'no_dereference' can't be used with 'dereference_args' or 'follow_symlinks'");
+        }
+        Ok(settings)
+    }
+}
+
+/// The path of the configuration file, in `$XDG_CONFIG_HOME/dua` or `~/.config/dua`.
+pub fn path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("dua").join("config.toml"))
+}
+
+/// Read the configuration file, which is empty if there is none.
+pub fn load() -> Result<Config> {
+    let path = match path() {
+        Some(path) => path,
+        None => return Ok(Config::default()),
+    };
+    match fs::read_to_string(&path) {
+        Ok(content) => parse(&content)
+            .with_context(|| format!("Could not read configuration from '{}'", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(anyhow::Error::from(err))
+            .with_context(|| format!("Could not read configuration from '{}'", path.display())),
+    }
+}
+
+/// Parse the configuration in `content`.
+pub fn parse(content: &str) -> Result<Config> {
+    let mut config = Config::default();
+    let mut table = String::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let res = if let Some(name) = line.strip_prefix('[') {
+            name.strip_suffix(']')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    table = name.to_owned();
+                    config.tables.entry(table.clone()).or_default();
+                })
+                .ok_or_else(|| anyhow!("invalid table header"))
+        } else {
+            parse_key_value(line).and_then(|(key, value)| {
+                match config
+                    .tables
+                    .entry(table.clone())
+                    .or_default()
+                    .insert(key.to_owned(), value)
+                {
+                    Some(_) => Err(anyhow!("'{}' is set more than once", key)),
+                    None => Ok(()),
+                }
+            })
+        };
+        res.map_err(|err| anyhow!("line {}: {}", line_number + 1, err))?;
+    }
+    Ok(config)
+}
+
+/// Return `line` without the comment at its end, if it has one outside of a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (pos, c) in line.char_indices() {
+        match c {
+            '\\' if in_string => escaped = !escaped,
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..pos],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+fn parse_key_value(line: &str) -> Result<(&str, Value)> {
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected 'key = value'"))?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("invalid key '{}'", key);
+    }
+    let (value, rest) = parse_value(value.trim())?;
+    if !rest.trim().is_empty() {
+        bail!("unexpected '{}' after the value of '{}'", rest.trim(), key);
+    }
+    Ok((key, value))
+}
+
+/// Parse the value at the start of `input`, and return it along with the rest of the input.
+fn parse_value(input: &str) -> Result<(Value, &str)> {
+    if let Some(rest) = input.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[pos + 1..])),
+                '\\' => value.push(match chars.next() {
+                    Some((_, 'n')) => '\n',
+                    Some((_, 't')) => '\t',
+                    Some((_, c @ '"')) | Some((_, c @ '\\')) => c,
+                    _ => bail!("invalid escape sequence in string"),
+                }),
+                c => value.push(c),
+            }
+        }
+        bail!("unterminated string")
+    } else if let Some(mut rest) = input.strip_prefix('[') {
+        let mut values = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(rest) = rest.strip_prefix(']') {
+                return Ok((Value::Array(values), rest));
+            }
+            let (value, after_value) = parse_value(rest)?;
+            values.push(value);
+            rest = after_value.trim_start();
+            match rest.strip_prefix(',') {
+                Some(after_comma) => rest = after_comma,
+                None if rest.starts_with(']') => {}
+                None => bail!("expected ',' or ']' in array"),
+            }
+        }
+    } else {
+        let end = input
+            .find(|c: char| c == ',' || c == ']' || c.is_whitespace())
+            .unwrap_or(input.len());
+        let (token, rest) = input.split_at(end);
+        let value = match token {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            _ => Value::Integer(
+                token
+                    .replace('_', "")
+                    .parse()
+                    .map_err(|_| anyhow!("invalid value '{}'", token))?,
+            ),
+        };
+        Ok((value, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::String(s.into())
+    }
+
+    #[test]
+    fn it_parses_tables_with_keys_and_values() {
+        let config = parse(
+            r##"
+            # a comment
+            top = "level" # trailing comment
+            [themes.mine]
+            base = "light"
+            quoted = "a \"#\" sign\\"
+            numbers = [1, -2, 3_000]
+            colors = [ "red", "blue", ]
+
+            [other]
+            enabled = true
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.tables[""]["top"], string("level"));
+        let mine = &config.tables["themes.mine"];
+        assert_eq!(mine["base"], string("light"));
+        assert_eq!(mine["quoted"], string("a \"#\" sign\\"));
+        assert_eq!(
+            mine["numbers"],
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(-2),
+                Value::Integer(3000)
+            ])
+        );
+        assert_eq!(
+            mine["colors"].as_strs("colors").unwrap(),
+            vec!["red", "blue"]
+        );
+        assert_eq!(config.tables["other"]["enabled"], Value::Boolean(true));
+        #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+        assert_eq!(
+            config
+                .subtables("themes")
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            vec!["mine"]
+        );
+    }
+
+    #[test]
+    fn settings_are_read_from_the_top_of_the_configuration() {
+        let settings = |content: &str| parse(content).and_then(|c| Settings::from_config(&c));
+        assert_eq!(
+            settings(
+                r#"
+                threads = 2
+                format = "binary"
+                apparent_size = true
+                exclude = ["*.log", "*/node_modules"]
+                only_ignored = true
+                theme = "light"
+                sort = "name"
+                columns = ["mtime", "size:12", "name"]
+                confirm_deletions_above = "10GB"
+                [themes.light]
+                size = "red"
+                "#
+            )
+            .unwrap(),
+            Settings {
+                threads: Some(2),
+                format: Some(ByteFormat::Binary),
+                apparent_size: true,
+                exclude: vec!["*.log".parse().unwrap(), "*/node_modules".parse().unwrap()],
+                only_ignored: true,
+                theme: Some("light".into()),
+                sort: Some("name".into()),
+                columns: Some(vec!["mtime".into(), "size:12".into(), "name".into()]),
+                confirm_deletions_above: Some(10_000_000_000),
+                ..Default::default()
+            }
+        );
+        assert_eq!(settings("").unwrap(), Settings::default());
+
+        for (input, expected) in [
+            ("treads = 1", "Unknown setting 'treads'"),
+            ("[colors]", "Unknown table '[colors]'"),
+            ("threads = -1", "'threads' must not be negative"),
+            ("format = \"pb\"", "Invalid byte format: \"pb\""),
+            ("apparent_size = 1", "'apparent_size' must be true or false"),
+            (
+                "confirm_deletions_above = \"big\"",
+                "Invalid size \"big\": The character 'b' is not a number.",
+            ),
+            (
+                "exclude = \"*.log\"",
+                "'exclude' must be an array of strings",
+            ),
+            (
+                "respect_gitignore = true\nonly_ignored = true",
+                "'respect_gitignore' and 'only_ignored' can't be used together",
+            ),
+            (
+                "dereference_args = true\nno_dereference = true",
+                "'no_dereference' can't be used with 'dereference_args' or 'follow_symlinks'",
+            ),
+        ] {
+            assert_eq!(settings(input).unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn it_reports_errors_with_their_line() {
+        for (input, expected) in [
+            ("key", "line 1: expected 'key = value'"),
+            ("\n[themes", "line 2: invalid table header"),
+            ("a = 1\na = 2", "line 2: 'a' is set more than once"),
+            ("a = \"open", "line 1: unterminated string"),
+            ("a = yes", "line 1: invalid value 'yes'"),
+            ("a = [1 2]", "line 1: expected ',' or ']' in array"),
+            ("a = 1 2", "line 1: unexpected '2' after the value of 'a'"),
+            ("a b = 1", "line 1: invalid key 'a b'"),
+        ] {
+            assert_eq!(parse(input).unwrap_err().to_string(), expected);
+        }
+    }
+}
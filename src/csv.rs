@@ -0,0 +1,36 @@
+use std::io;
+
+/// Write `field` to `out`, surrounded by quotes if it contains `delimiter`, quotes or line breaks,
+/// with quotes in it doubled as described in RFC 4180.
+pub(crate) fn write_field(out: &mut impl io::Write, field: &str, delimiter: u8) -> io::Result<()> {
+    let needs_quotes = field
+        .bytes()
+        .any(|b| b == delimiter || b == b'"' || b == b'\n' || b == b'\r');
+    if !needs_quotes {
+        return out.write_all(field.as_bytes());
+    }
+    out.write_all(b"\"")?;
+    out.write_all(field.replace('"', "\"\"").as_bytes())?;
+    out.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(s: &str, delimiter: u8) -> String {
+        let mut out = Vec::new();
+        write_field(&mut out, s, delimiter).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn fields_are_only_quoted_if_needed() {
+        assert_eq!(field("dir/a b", b','), "dir/a b");
+        assert_eq!(field("a,b", b','), "\"a,b\"");
+        assert_eq!(field("a,b", b'\t'), "a,b");
+        assert_eq!(field("a\tb", b'\t'), "\"a\tb\"");
+        assert_eq!(field("say \"hi\"", b','), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines", b','), "\"two\nlines\"");
+    }
+}
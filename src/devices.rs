@@ -0,0 +1,343 @@
+//! Listing mounted filesystems along with their size and free space, as reported by `df`.
+use crate::ByteFormat;
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A mounted filesystem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// The device or the name of the filesystem, like `/dev/sda1` or `tmpfs`
+    pub filesystem: String,
+    pub mount_point: PathBuf,
+    /// The size of the filesystem in bytes
+    pub total: u128,
+    /// The bytes in use
+    pub used: u128,
+    /// The bytes which can still be written by users other than root
+    pub available: u128,
+}
+
+impl Device {
+    /// The share of the space that is used, as `df` computes it, leaving out the space reserved for root.
+    pub fn used_fraction(&self) -> f64 {
+        match self.used + self.available {
+            0 => 0.0,
+            usable => self.used as f64 / usable as f64,
+        }
+    }
+}
+
+/// The types of filesystems whose files are made up by the kernel, with sizes that don't take any space, like those in
+/// `/proc` whose sizes would count many times the memory of the machine.
+pub const VIRTUAL_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Directories whose `tmpfs` mounts hold the state of the running system rather than files of users.
+const RUNTIME_DIRS: &[&str] = &["/dev", "/proc", "/run", "/sys"];
+
+/// Return the mount points of all [virtual filesystems][VIRTUAL_FILESYSTEMS] and of `tmpfs` in [`RUNTIME_DIRS`],
+/// like `/proc`, `/sys`, `/dev` and `/run`, as listed in `/proc/mounts`. There are none on systems other than Linux.
+pub fn virtual_mount_points() -> Vec<PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read("/proc/mounts")
+            .map(|mounts| parse_virtual_mount_points(&mounts))
+            .unwrap_or_default()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Vec::new()
+    }
+}
+
+/// Parse the mount points of virtual filesystems from `mounts` in the format of `/proc/mounts`, a line per mount
+/// with the device, mount point and filesystem type, followed by options. Spaces and other whitespace in mount points
+/// are escaped as octal numbers, like `\040`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_virtual_mount_points(mounts: &[u8]) -> Vec<PathBuf> {
+    mounts
+        .split(|b| *b == b'\n')
+        .filter_map(|line| {
+            let mut fields = line
+                .split(|b| b.is_ascii_whitespace())
+                .filter(|field| !field.is_empty());
+            let (mount_point, fstype) = (unescape_octal(fields.nth(1)?), fields.next()?);
+            let is_virtual = VIRTUAL_FILESYSTEMS.iter().any(|v| v.as_bytes() == fstype)
+                || (fstype == b"tmpfs"
+                    && RUNTIME_DIRS
+                        .iter()
+                        .any(|dir| mount_point.starts_with(Path::new(dir))));
+            Some(mount_point).filter(|_| is_virtual)
+        })
+        .collect()
+}
+
+/// Replace escapes like `\040` with the byte of their octal number.
+fn unescape_octal(field: &[u8]) -> PathBuf {
+    let mut out = Vec::with_capacity(field.len());
+    let mut i = 0;
+    while i < field.len() {
+        let digits = field
+            .get(i + 1..i + 4)
+            .filter(|digits| field[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)));
+        match digits {
+            Some(digits) => {
+                out.push(
+                    digits
+                        .iter()
+                        .fold(0u8, |n, d| n.wrapping_mul(8) + (d - b'0')),
+                );
+                i += 4;
+            }
+            None => {
+                out.push(field[i]);
+                i += 1;
+            }
+        }
+    }
+    crate::ncdu::path_from_bytes(out)
+}
+
+/// Return all mounted filesystems which have space to use, in the order `df` lists them.
+///
+/// Filesystems without any blocks, like `proc` or `sysfs`, are left out.
+pub fn mounted_devices() -> Result<Vec<Device>> {
+    mounted(false)
+}
+
+/// Return all mounted filesystems like [`mounted_devices()`], with amounts of inodes instead of bytes if
+/// `count_inodes` is set.
+fn mounted(count_inodes: bool) -> Result<Vec<Device>> {
+    Ok(parse_df(&run_df(count_inodes)?, count_inodes)
+        .into_iter()
+        .filter(|device| device.total != 0)
+        .collect())
+}
+
+/// Return the device among `devices` which `path` is on, the one mounted deepest above it. `path` must be absolute and
+/// free of symlinks to find the right one.
+pub fn device_containing<'a>(devices: &'a [Device], path: &Path) -> Option<&'a Device> {
+    devices
+        .iter()
+        .filter(|device| path.starts_with(&device.mount_point))
+        .max_by_key(|device| device.mount_point.components().count())
+}
+
+/// Write a line for each mounted filesystem to `out`, with its size, used and available space, the share that is used
+/// and its mount point. With [`ByteFormat::Inodes`], these are its amounts of inodes instead, to see which filesystem
+/// runs out of them. Filesystems without a fixed amount of inodes, like `btrfs`, are left out then.
+pub fn devices(mut out: impl io::Write, byte_format: ByteFormat) -> Result<()> {
+    let count_inodes = matches!(byte_format, ByteFormat::Inodes);
+    let devices = mounted(count_inodes)?;
+    let width = byte_format.width();
+    writeln!(
+        out,
+        "{:>width$} {:>width$} {:>width$} {:>4} MOUNTED ON",
+        if count_inodes { "INODES" } else { "SIZE" },
+        "USED",
+        "FREE",
+        "USE%",
+        width = width
+    )?;
+    for device in devices {
+        writeln!(
+            out,
+            "{:>width$} {:>width$} {:>width$} {:>3.0}% {}",
+            byte_format.display(device.total).to_string(),
+            byte_format.display(device.used).to_string(),
+            byte_format
+                .display(device.available)
+                .to_string()
+                .as_str()
+                .green(),
+            device.used_fraction() * 100.0,
+            device.mount_point.display(),
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+/// Run `df` in its portable output format with sizes in kilobytes, or amounts of inodes if `count_inodes` is set.
+fn run_df(count_inodes: bool) -> Result<String> {
+    let output = Command::new("df")
+        .arg(if count_inodes { "-Pi" } else { "-Pk" })
+        .output()
+        .context("Could not run 'df'")?;
+    if !output.status.success() && output.stdout.is_empty() {
+        return Err(anyhow!(
+            "'df' failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse the output of `df -Pk`, a header followed by a line per filesystem with its name, its size, the used and
+/// available space in kilobytes, the share that is used and its mount point, which may contain spaces.
+/// With `inodes` set, it's the output of `df -Pi` instead, which has amounts of inodes in place of kilobytes.
+fn parse_df(output: &str, inodes: bool) -> Vec<Device> {
+    let unit = if inodes { 1 } else { 1024 };
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (filesystem, mut rest) = split_field(line)?;
+            let mut amounts = [0u128; 3];
+            for amount in amounts.iter_mut() {
+                let (field, tail) = split_field(rest)?;
+                *amount = field.parse::<u128>().ok()? * unit;
+                rest = tail;
+            }
+            let (_capacity, mount_point) = split_field(rest)?;
+            let [total, used, available] = amounts;
+            Some(Device {
+                filesystem: filesystem.to_owned(),
+                // The mount point is the rest of the line, spaces included
+                mount_point: PathBuf::from(mount_point.trim_start()),
+                total,
+                used,
+                available,
+            })
+        })
+        .collect()
+}
+
+/// Split the first whitespace-separated field off `s`, returning it along with the rest.
+fn split_field(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    Some(s.split_at(end)).filter(|(field, _)| !field.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filesystems_are_parsed_from_the_portable_output_of_df() {
+        let output = "\
+Filesystem     1024-blocks     Used Available Capacity Mounted on
+/dev/vda         264212084 13614560  82442848      15% /
+proc                     0        0         0       -  /proc
+/dev/sdb1             1000      250       750      25% /media/USB Stick
+";
+        let devices = parse_df(output, false);
+        assert_eq!(
+            devices,
+            vec![
+                Device {
+                    filesystem: "/dev/vda".into(),
+                    mount_point: "/".into(),
+                    total: 264212084 * 1024,
+                    used: 13614560 * 1024,
+                    available: 82442848 * 1024,
+                },
+                Device {
+                    filesystem: "proc".into(),
+                    mount_point: "/proc".into(),
+                    total: 0,
+                    used: 0,
+                    available: 0,
+                },
+                Device {
+                    filesystem: "/dev/sdb1".into(),
+                    mount_point: "/media/USB Stick".into(),
+                    total: 1000 * 1024,
+                    used: 250 * 1024,
+                    available: 750 * 1024,
+                },
+            ]
+        );
+        assert_eq!(devices[2].used_fraction(), 0.25);
+        assert_eq!(devices[1].used_fraction(), 0.0);
+
+        let on = |path: &str| device_containing(&devices, Path::new(path)).map(|d| &d.filesystem);
+        assert_eq!(
+            on("/media/USB Stick/photos").map(String::as_str),
+            Some("/dev/sdb1")
+        );
+        assert_eq!(on("/media/USB").map(String::as_str), Some("/dev/vda"));
+        assert_eq!(on("relative"), None);
+    }
+
+    #[test]
+    fn inodes_of_filesystems_are_parsed_from_the_portable_output_of_df() {
+        let output = "\
+Filesystem       Inodes  IUsed    IFree IUse% Mounted on
+/dev/vda       16777216 524288 16252928    4% /
+proc                  0      0        0     - /proc
+";
+        let devices = parse_df(output, true);
+        assert_eq!(
+            devices[0],
+            Device {
+                filesystem: "/dev/vda".into(),
+                mount_point: "/".into(),
+                total: 16777216,
+                used: 524288,
+                available: 16252928,
+            }
+        );
+        assert_eq!(devices[0].used_fraction(), 1.0 / 32.0);
+        assert_eq!(devices[1].total, 0);
+    }
+
+    #[test]
+    fn virtual_filesystems_are_parsed_from_the_list_of_mounts() {
+        let mounts = b"\
+/dev/vda / ext4 rw,relatime 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+cgroup2 /sys/fs/cgroup cgroup2 rw,nosuid,nodev,noexec,relatime 0 0
+udev /dev devtmpfs rw,nosuid,relatime 0 0
+tmpfs /run tmpfs rw,nosuid,nodev,size=1630344k,mode=755 0 0
+tmpfs /run/user/1000 tmpfs rw,nosuid,nodev,relatime 0 0
+tmpfs /tmp tmpfs rw,nosuid,nodev 0 0
+/dev/sdb1 /media/USB\\040Stick vfat rw 0 0
+binfmt_misc /media/odd\\040name binfmt_misc rw 0 0
+";
+        assert_eq!(
+            parse_virtual_mount_points(mounts),
+            [
+                "/proc",
+                "/sys",
+                "/sys/fs/cgroup",
+                "/dev",
+                "/run",
+                "/run/user/1000",
+                "/media/odd name"
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{
+    get_entry_or_panic,
+    traverse::{Traversal, TreeIndex},
+    utc_date_time, ByteFormat, WalkOptions, WalkResult, INTERRUPTED,
+};
+use anyhow::Result;
+use colored::{ColoredString, Colorize};
+use petgraph::Direction;
+use std::{
+    collections::{BTreeSet, HashMap},
+    io,
+    path::PathBuf,
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How the size of a directory changed from one traversal to another
+#[derive(Debug, PartialEq, Eq)]
+pub struct SizeChange {
+    pub path: PathBuf,
+    /// The size before, or `None` if the directory was added
+    pub before: Option<u128>,
+    /// The size after, or `None` if the directory was removed
+    pub after: Option<u128>,
+}
+
+impl SizeChange {
+    /// The amount of bytes the directory grew by, negative if it shrank.
+    pub fn delta(&self) -> i128 {
+        self.after.unwrap_or(0) as i128 - self.before.unwrap_or(0) as i128
+    }
+}
+
+/// Compare the directories of `before` and `after` by their path, and return those whose size changed,
+/// the largest change first, no matter if it grew or shrank.
+///
+/// The top-level entries are compared as well, even if they are files. Directories which were added or removed are
+/// listed even if they are empty.
+pub fn size_changes(before: &Traversal, after: &Traversal) -> Vec<SizeChange> {
+    let (before, after) = (directory_sizes(before), directory_sizes(after));
+    let paths: BTreeSet<_> = before.keys().chain(after.keys()).collect();
+    let mut changes: Vec<_> = paths
+        .into_iter()
+        .map(|path| SizeChange {
+            path: path.clone(),
+            before: before.get(path).copied(),
+            after: after.get(path).copied(),
+        })
+        .filter(|change| change.before != change.after)
+        .collect();
+    // Sorting is stable, which keeps changes of the same amount in order of their path
+    changes.sort_by_key(|change| std::cmp::Reverse(change.delta().unsigned_abs()));
+    changes
+}
+
+fn directory_sizes(traversal: &Traversal) -> HashMap<PathBuf, u128> {
+    let tree = &traversal.tree;
+    let mut sizes = HashMap::new();
+    let mut to_visit: Vec<(TreeIndex, PathBuf)> = tree
+        .neighbors_directed(traversal.root_index, Direction::Outgoing)
+        .map(|idx| (idx, tree.name_of(idx).to_path_buf()))
+        .collect();
+    while let Some((node_idx, path)) = to_visit.pop() {
+        for child in tree.neighbors_directed(node_idx, Direction::Outgoing) {
+            let entry = get_entry_or_panic(tree, child);
+            if entry.is_dir {
+                to_visit.push((child, path.join(&tree.names[entry.name])));
+            }
+        }
+        sizes.insert(path, get_entry_or_panic(tree, node_idx).size.into());
+    }
+    sizes
+}
+
+/// Write the `num_entries` directories whose size changed the most from `before` to `after` to `out`, along with
+/// their size in both, followed by the change of the total size.
+pub fn diff(
+    mut out: impl io::Write,
+    walk_options: &WalkOptions,
+    before: &Traversal,
+    after: &Traversal,
+    num_entries: usize,
+) -> Result<()> {
+    let format = walk_options.byte_format;
+    for change in size_changes(before, after).into_iter().take(num_entries) {
+        write_change(&mut out, format, &change)?;
+    }
+    let total = |t: &Traversal| u128::from(get_entry_or_panic(&t.tree, t.root_index).size);
+    let (before, after) = (total(before), total(after));
+    writeln!(
+        out,
+        "{} {} → {} total",
+        signed(format, after as i128 - before as i128),
+        size(format, Some(before)),
+        size(format, Some(after))
+    )?;
+    Ok(())
+}
+
+/// Write a line with the amount `change` grew or shrank by, the size before and after, and the path.
+fn write_change(
+    out: &mut impl io::Write,
+    format: ByteFormat,
+    change: &SizeChange,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} {} → {} {}{}",
+        signed(format, change.delta()),
+        size(format, change.before),
+        size(format, change.after),
+        change.path.display(),
+        match (change.before, change.after) {
+            (None, _) => " (new)",
+            (_, None) => " (gone)",
+            _ => "",
+        }
+    )
+}
+
+/// Format `delta` with its sign, in red if it grew and in green otherwise.
+fn signed(format: ByteFormat, delta: i128) -> ColoredString {
+    let text = format!(
+        "{}{}",
+        if delta < 0 { "-" } else { "+" },
+        format.display(delta.unsigned_abs())
+    );
+    let text = format!("{:>width$}", text, width = format.width() + 1);
+    if delta > 0 {
+        text.red()
+    } else {
+        text.green()
+    }
+}
+
+/// Format `size`, or `-` if there is none.
+fn size(format: ByteFormat, size: Option<u128>) -> String {
+    match size {
+        Some(size) => format!(
+            "{:>width$}",
+            format.display(size).to_string(),
+            width = format.width()
+        ),
+        None => format!("{:>width$}", "-", width = format.width()),
+    }
+}
+
+/// Return the directories which grew by more than `threshold` bytes from `before` to `after`, leaving out those which
+/// only did because a directory within them did, the largest growth first.
+///
+/// Added directories count with all of their size, but the top-level entries aren't compared if they are files.
+pub fn grown_beyond(before: &Traversal, after: &Traversal, threshold: u128) -> Vec<SizeChange> {
+    let grown: Vec<_> = size_changes(before, after)
+        .into_iter()
+        .filter(|change| change.delta() > 0 && change.delta().unsigned_abs() > threshold)
+        .collect();
+    let is_ancestor = |change: &SizeChange| {
+        grown
+            .iter()
+            .any(|other| other.path != change.path && other.path.starts_with(&change.path))
+    };
+    let deepest: Vec<_> = grown
+        .iter()
+        .filter(|change| !is_ancestor(change))
+        .map(|change| change.path.clone())
+        .collect();
+    grown
+        .into_iter()
+        .filter(|change| deepest.contains(&change.path))
+        .collect()
+}
+
+/// Write the directories which grew by more than `threshold` bytes from `before` to `after` to `out`, as found by
+/// [`grown_beyond()`], and return how many there are. Nothing is written if there are none.
+pub fn check(
+    mut out: impl io::Write,
+    walk_options: &WalkOptions,
+    before: &Traversal,
+    after: &Traversal,
+    threshold: u128,
+) -> Result<usize> {
+    let format = walk_options.byte_format;
+    let grown = grown_beyond(before, after, threshold);
+    if !grown.is_empty() {
+        writeln!(
+            out,
+            "{} {} grew by more than {}",
+            grown.len(),
+            if grown.len() == 1 {
+                "directory"
+            } else {
+                "directories"
+            },
+            format.display(threshold)
+        )?;
+    }
+    for change in &grown {
+        write_change(&mut out, format, change)?;
+    }
+    Ok(grown.len())
+}
+
+/// Scan `paths` again and again, waiting `interval` from the start of one scan to the next, and write the
+/// `num_entries` directories whose size changed the most since the previous scan to `out` after each one, like
+/// [`diff()`] does.
+///
+/// It only returns once [interrupted][crate::INTERRUPTED], with the result of the last complete scan.
+pub fn watch(
+    mut out: impl io::Write,
+    walk_options: WalkOptions,
+    paths: Vec<PathBuf>,
+    interval: Duration,
+    num_entries: usize,
+) -> Result<WalkResult> {
+    let scan = || Traversal::from_walk(walk_options.clone(), paths.clone(), |_| Ok(false));
+    let mut started = Instant::now();
+    let mut previous = scan()?;
+    if previous.is_incomplete {
+        return Ok(WalkResult::default());
+    }
+    writeln!(
+        out,
+        "{} {} in total, scanning again every {}s",
+        utc_date_time(SystemTime::now()),
+        walk_options.byte_format.display(
+            get_entry_or_panic(&previous.tree, previous.root_index)
+                .size
+                .into()
+        ),
+        interval.as_secs()
+    )?;
+    loop {
+        while started.elapsed() < interval {
+            if INTERRUPTED.load(Ordering::Relaxed) {
+                return Ok(WalkResult {
+                    num_errors: previous.io_errors,
+                });
+            }
+            thread::sleep(Duration::from_millis(100).min(interval));
+        }
+        started = Instant::now();
+        let current = scan()?;
+        if current.is_incomplete {
+            return Ok(WalkResult {
+                num_errors: previous.io_errors,
+            });
+        }
+        writeln!(out, "\n{}", utc_date_time(SystemTime::now()))?;
+        diff(&mut out, &walk_options, &previous, &current, num_entries)?;
+        out.flush()?;
+        previous = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn walk_options() -> WalkOptions {
+        WalkOptions::default()
+            .threads(1)
+            .byte_format(ByteFormat::Bytes)
+            .apparent_size(true)
+    }
+
+    fn walk(paths: &[&str]) -> Result<Traversal> {
+        Traversal::from_walk(
+            walk_options(),
+            paths.iter().map(PathBuf::from).collect(),
+            |_| Ok(false),
+        )
+    }
+
+    #[test]
+    fn it_lists_directories_by_the_amount_their_size_changed() -> Result<()> {
+        let before = walk(&["tests/fixtures/sample-01/dir", "tests/fixtures/sample-02"])?;
+        let mut after = walk(&[
+            "tests/fixtures/sample-01/dir",
+            "tests/fixtures/sample-01/z123.b",
+        ])?;
+        // pretend the 256kb file shrank by 1000 bytes
+        for idx in after.tree.node_indices().collect::<Vec<_>>() {
+            let name = after.tree.name_of(idx);
+            if name == Path::new("sub") || name == Path::new("tests/fixtures/sample-01/dir") {
+                after.tree[idx].size -= 1000;
+            }
+        }
+
+        let changes: Vec<_> = size_changes(&before, &after)
+            .into_iter()
+            .map(|c| (c.path.to_string_lossy().into_owned(), c.delta()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("tests/fixtures/sample-02".to_owned(), -1540),
+                ("tests/fixtures/sample-02/dir".into(), -1283),
+                ("tests/fixtures/sample-02/dir/sub".into(), -1024),
+                ("tests/fixtures/sample-01/dir".into(), -1000),
+                ("tests/fixtures/sample-01/dir/sub".into(), -1000),
+                ("tests/fixtures/sample-01/z123.b".into(), 123),
+                ("tests/fixtures/sample-02/dir/empty-dir".into(), 0),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_deepest_directories_growing_beyond_the_threshold_are_listed() -> Result<()> {
+        let before = walk(&["tests/fixtures/sample-01"])?;
+        let mut after = walk(&["tests/fixtures/sample-01"])?;
+        // pretend 'sub' grew by 2000 bytes, and 'dir' by another 500 of its own
+        for idx in after.tree.node_indices().collect::<Vec<_>>() {
+            let name = after.tree.name_of(idx);
+            if name == Path::new("sub") {
+                after.tree[idx].size += 2000;
+            } else if name == Path::new("dir") || name == Path::new("tests/fixtures/sample-01") {
+                after.tree[idx].size += 2500;
+            }
+        }
+
+        let grown = |threshold| {
+            grown_beyond(&before, &after, threshold)
+                .into_iter()
+                .map(|c| (c.path.to_string_lossy().into_owned(), c.delta()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            grown(1000),
+            vec![("tests/fixtures/sample-01/dir/sub".to_owned(), 2000)]
+        );
+        assert_eq!(
+            grown(2000),
+            vec![("tests/fixtures/sample-01/dir".to_owned(), 2500)],
+            "'sub' isn't beyond the threshold anymore, but its parent is"
+        );
+        assert_eq!(grown(2500), vec![]);
+
+        let mut out = Vec::new();
+        assert_eq!(check(&mut out, &walk_options(), &before, &after, 5000)?, 0);
+        assert!(
+            out.is_empty(),
+            "nothing is written if nothing grew too much"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,757 @@
+//! Attributing the space taken by Docker to its images, containers and volumes, by reading the metadata it keeps in
+//! its data directory when using the `overlay2` storage driver.
+use crate::{
+    filesystem::{Filesystem, FsEntry, LocalFilesystem, Walk},
+    json,
+    remote::Listing,
+    traverse::{Traversal, TraversalError},
+    WalkOptions, WalkResult,
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The directory Docker keeps images, containers and volumes in by default
+pub const DOCKER_ROOT: &str = "/var/lib/docker";
+
+/// A layer of an image, the files it adds or changes over the layers below it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    /// The digest of the layer's content, like `sha256:3c3e…`
+    pub diff_id: String,
+    /// The identifier of the layer along with all layers below it
+    pub chain_id: String,
+    /// The directory with the files of the layer
+    pub dir: PathBuf,
+}
+
+/// An image, made of layers from the bottom to the top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub id: String,
+    /// A tag of the image like `ubuntu:22.04`, or its shortened id if it has none
+    pub name: String,
+    pub layers: Vec<Layer>,
+}
+
+/// A container, with the files it changed over its image and the data Docker keeps about it, like its logs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    pub id: String,
+    pub name: String,
+    /// The id of the image the container was created from
+    pub image_id: String,
+    /// The directory with the files the container added or changed, if it has one
+    pub layer: Option<PathBuf>,
+    /// The directory with the configuration and logs of the container
+    pub dir: PathBuf,
+}
+
+/// What Docker keeps in its data directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Docker {
+    /// All images, ordered by name
+    pub images: Vec<Image>,
+    /// All containers, ordered by name
+    pub containers: Vec<Container>,
+    /// The directories of all volumes, ordered by name
+    pub volumes: Vec<PathBuf>,
+}
+
+impl Docker {
+    /// Read the images, containers and volumes of the Docker data directory at `root`, like [`DOCKER_ROOT`].
+    pub fn read(root: &Path) -> Result<Docker> {
+        let image_dir = root.join("image/overlay2");
+        if !image_dir.is_dir() {
+            anyhow::bail!(
+                "'{}' is not a Docker data directory using the overlay2 storage driver",
+                root.display()
+            );
+        }
+        let layerdb = image_dir.join("layerdb/sha256");
+        let read_id = |path: PathBuf| -> Option<String> {
+            fs::read_to_string(path).ok().map(|id| id.trim().to_owned())
+        };
+        // Layers are found by their content and the layer below them
+        let mut layers = HashMap::new();
+        for entry in read_dir_sorted(&layerdb)? {
+            let chain_id = format!(
+                "sha256:{}",
+                entry.file_name().unwrap_or_default().to_string_lossy()
+            );
+            let (diff_id, cache_id) =
+                match (read_id(entry.join("diff")), read_id(entry.join("cache-id"))) {
+                    (Some(diff_id), Some(cache_id)) => (diff_id, cache_id),
+                    _ => continue,
+                };
+            let layer = Layer {
+                diff_id: diff_id.clone(),
+                chain_id,
+                dir: root.join("overlay2").join(cache_id).join("diff"),
+            };
+            layers.insert((read_id(entry.join("parent")), diff_id), layer);
+        }
+
+        let mut names = HashMap::<String, String>::new();
+        if let Ok(content) = fs::read(image_dir.join("repositories.json")) {
+            for (reference, id) in read_repositories(&content).with_context(|| {
+                format!(
+                    "Could not read '{}'",
+                    image_dir.join("repositories.json").display()
+                )
+            })? {
+                // Tags are easier to recognize than digests
+                let is_better = match names.get(&id) {
+                    Some(name) => name.contains('@') && !reference.contains('@'),
+                    None => true,
+                };
+                if is_better {
+                    names.insert(id, reference);
+                }
+            }
+        }
+        let mut images = Vec::new();
+        for entry in read_dir_sorted(&image_dir.join("imagedb/content/sha256"))? {
+            let hex = entry
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            let id = format!("sha256:{}", hex);
+            let diff_ids = read_diff_ids(&fs::read(&entry)?)
+                .with_context(|| format!("Could not read '{}'", entry.display()))?;
+            let mut parent = None;
+            let mut image_layers = Vec::new();
+            for diff_id in diff_ids {
+                match layers.get(&(parent.clone(), diff_id)) {
+                    Some(layer) => {
+                        parent = Some(layer.chain_id.clone());
+                        image_layers.push(layer.clone());
+                    }
+                    None => break, // still being pulled, or removed
+                }
+            }
+            images.push(Image {
+                name: names
+                    .remove(&id)
+                    .unwrap_or_else(|| short_id(&id).to_owned()),
+                id,
+                layers: image_layers,
+            });
+        }
+        images.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut containers = Vec::new();
+        for dir in read_dir_sorted(&root.join("containers")).unwrap_or_default() {
+            let id = dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            let (name, image_id) = match fs::read(dir.join("config.v2.json")) {
+                Ok(config) => read_container_config(&config).with_context(|| {
+                    format!("Could not read the configuration in '{}'", dir.display())
+                })?,
+                Err(_) => continue,
+            };
+            containers.push(Container {
+                name: match name.trim_start_matches('/') {
+                    "" => short_id(&id).to_owned(),
+                    name => name.to_owned(),
+                },
+                image_id,
+                layer: read_id(image_dir.join("layerdb/mounts").join(&id).join("mount-id"))
+                    .map(|mount_id| root.join("overlay2").join(mount_id).join("diff")),
+                dir,
+                id,
+            });
+        }
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let volumes = read_dir_sorted(&root.join("volumes"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .collect();
+        Ok(Docker {
+            images,
+            containers,
+            volumes,
+        })
+    }
+}
+
+fn read_dir_sorted(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// The first 12 hexadecimal digits of `id`, like Docker shows them
+fn short_id(id: &str) -> &str {
+    let hex = id.strip_prefix("sha256:").unwrap_or(id);
+    hex.get(..12).unwrap_or(hex)
+}
+
+/// Read all keys of the object `r` is at, calling `value` with each key to read or skip its value.
+fn read_object(
+    r: &mut json::Reader,
+    mut value: impl FnMut(&mut json::Reader, &[u8]) -> Result<()>,
+) -> Result<()> {
+    r.expect(b'{')?;
+    if r.next_if(b'}') {
+        return Ok(());
+    }
+    loop {
+        let key = r.string()?;
+        r.expect(b':')?;
+        value(r, &key)?;
+        if !r.has_next_item(b'}')? {
+            return Ok(());
+        }
+    }
+}
+
+fn string(r: &mut json::Reader) -> Result<String> {
+    Ok(String::from_utf8_lossy(&r.string()?).into_owned())
+}
+
+/// Read the image references like `ubuntu:22.04` of `repositories.json`, along with the ids of their images.
+fn read_repositories(content: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut r = json::Reader::new(content);
+    let mut references = Vec::new();
+    read_object(&mut r, |r, key| match key {
+        b"Repositories" => read_object(r, |r, _repository| {
+            read_object(r, |r, reference| {
+                references.push((String::from_utf8_lossy(reference).into_owned(), string(r)?));
+                Ok(())
+            })
+        }),
+        _ => r.skip_value(),
+    })?;
+    Ok(references)
+}
+
+/// Read the digests of the layers of an image from its configuration, from the bottom to the top.
+fn read_diff_ids(config: &[u8]) -> Result<Vec<String>> {
+    let mut r = json::Reader::new(config);
+    let mut diff_ids = Vec::new();
+    read_object(&mut r, |r, key| match key {
+        b"rootfs" => read_object(r, |r, key| match key {
+            b"diff_ids" => {
+                r.expect(b'[')?;
+                if !r.next_if(b']') {
+                    loop {
+                        diff_ids.push(string(r)?);
+                        if !r.has_next_item(b']')? {
+                            break;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            _ => r.skip_value(),
+        }),
+        _ => r.skip_value(),
+    })?;
+    Ok(diff_ids)
+}
+
+/// Read the name of a container and the id of its image from its configuration.
+fn read_container_config(config: &[u8]) -> Result<(String, String)> {
+    let mut r = json::Reader::new(config);
+    let (mut name, mut image_id) = (String::new(), String::new());
+    read_object(&mut r, |r, key| match key {
+        b"Name" => string(r).map(|s| name = s),
+        b"Image" => string(r).map(|s| image_id = s),
+        _ => r.skip_value(),
+    })?;
+    Ok((name, image_id))
+}
+
+/// The images, containers and volumes of Docker as directories, walked from [`DockerFilesystem::PATHS`].
+///
+/// Each image contains its layers, named by their shortened digest, with the files they add or change. Layers shared
+/// by several images are only walked within the first of them, and are empty elsewhere. Each container contains the
+/// files it changed as `layer`, and its configuration and logs as `data`.
+pub struct DockerFilesystem {
+    docker: Docker,
+    local: LocalFilesystem,
+}
+
+/// A part of a walk
+enum Step {
+    /// A directory without a counterpart on disk
+    Dir { depth: usize, name: PathBuf },
+    /// A directory on disk, whose entries are walked below it
+    Walk {
+        depth: usize,
+        name: PathBuf,
+        dir: PathBuf,
+    },
+}
+
+impl DockerFilesystem {
+    /// The paths to walk to see all of Docker
+    pub const PATHS: [&'static str; 3] = ["images", "containers", "volumes"];
+
+    /// Walk the directories of `docker` with `walk_options`.
+    pub fn new(docker: Docker, walk_options: WalkOptions) -> io::Result<Self> {
+        Ok(DockerFilesystem {
+            docker,
+            local: LocalFilesystem::new(walk_options)?,
+        })
+    }
+
+    /// The name of `layer` within an image, or what it's named if it's shared with the image called `walked_in`.
+    pub fn layer_name(layer: &Layer, walked_in: Option<&str>) -> PathBuf {
+        match walked_in {
+            None => format!("layer {}", short_id(&layer.diff_id)).into(),
+            Some(image) => format!("layer {} (in {})", short_id(&layer.diff_id), image).into(),
+        }
+    }
+
+    fn steps(&self, path: &Path) -> io::Result<Vec<Step>> {
+        let name = path.to_owned();
+        let mut steps = vec![Step::Dir { depth: 0, name }];
+        match path.to_str() {
+            Some("images") => {
+                let mut walked_in = HashMap::<&str, &str>::new();
+                for image in &self.docker.images {
+                    steps.push(Step::Dir {
+                        depth: 1,
+                        name: image.name.clone().into(),
+                    });
+                    for layer in &image.layers {
+                        let name = DockerFilesystem::layer_name(
+                            layer,
+                            walked_in.get(layer.chain_id.as_str()).copied(),
+                        );
+                        steps.push(match walked_in.get(layer.chain_id.as_str()) {
+                            Some(_) => Step::Dir { depth: 2, name },
+                            None => Step::Walk {
+                                depth: 2,
+                                name,
+                                dir: layer.dir.clone(),
+                            },
+                        });
+                        walked_in.entry(&layer.chain_id).or_insert(&image.name);
+                    }
+                }
+            }
+            Some("containers") => {
+                for container in &self.docker.containers {
+                    steps.push(Step::Dir {
+                        depth: 1,
+                        name: container.name.clone().into(),
+                    });
+                    if let Some(layer) = &container.layer {
+                        steps.push(Step::Walk {
+                            depth: 2,
+                            name: "layer".into(),
+                            dir: layer.clone(),
+                        });
+                    }
+                    steps.push(Step::Walk {
+                        depth: 2,
+                        name: "data".into(),
+                        dir: container.dir.clone(),
+                    });
+                }
+            }
+            Some("volumes") => {
+                for volume in &self.docker.volumes {
+                    steps.push(Step::Walk {
+                        depth: 1,
+                        name: volume.file_name().unwrap_or_default().into(),
+                        dir: volume.clone(),
+                    });
+                }
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Docker has no '{}'", path.display()),
+                ))
+            }
+        }
+        Ok(steps)
+    }
+}
+
+impl Filesystem for DockerFilesystem {
+    fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+        let steps = self.steps(path)?;
+        let local = &mut self.local;
+        Ok(Box::new(steps.into_iter().flat_map(move |step| {
+            match step {
+                Step::Dir { depth, name } => vec![Ok(Listing::entry(depth, name, true))],
+                // Entries are collected, as the walk of a directory can't outlive the step it's done in
+                Step::Walk { depth, name, dir } => match local.walk(&dir) {
+                    Ok(walk) => walk
+                        .map(|entry| {
+                            entry.map(|entry| match entry.depth {
+                                0 => FsEntry {
+                                    depth,
+                                    name: name.clone(),
+                                    ..entry
+                                },
+                                _ => FsEntry {
+                                    depth: depth + entry.depth,
+                                    ..entry
+                                },
+                            })
+                        })
+                        .collect(),
+                    Err(err) => {
+                        let mut entry = Listing::entry(depth, name, true);
+                        entry.metadata_error = Some(TraversalError::from_io_error(&err, dir));
+                        vec![Ok(entry)]
+                    }
+                },
+            }
+        })))
+    }
+}
+
+/// The sizes of all layers of an image, counting shared layers in each image they are in
+struct ImageSize {
+    total: u128,
+    /// The size of layers not used by any other image
+    unique: u128,
+}
+
+/// Walk all images, containers and volumes of the Docker data directory at `root`, and write a line for each of them
+/// to `out` with the space it takes.
+///
+/// Images are listed with the size of all of their layers, and the size of the layers no other image uses, which is
+/// what removing the image frees. Containers are listed with the size of the files they changed along with their
+/// logs. A line with the space taken by all of them together follows.
+///
+/// If `err` is set, the amount of entries seen so far is written to it while walking.
+pub fn docker(
+    mut out: impl io::Write,
+    mut err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    root: &Path,
+) -> Result<WalkResult> {
+    let byte_format = walk_options.byte_format;
+    let docker = Docker::read(root)?;
+    let apparent_size = walk_options.apparent_size;
+    let mut filesystem = DockerFilesystem::new(docker.clone(), walk_options)?;
+    let traversal = Traversal::from_filesystem(
+        &mut filesystem,
+        apparent_size,
+        DockerFilesystem::PATHS.iter().map(PathBuf::from).collect(),
+        |t| {
+            if let Some(err) = err.as_mut() {
+                write!(err, "Enumerating {} entries\r", t.entries_traversed).ok();
+            }
+            Ok(false)
+        },
+    )?;
+    let tree = &traversal.tree;
+    // Names are matched as a whole, as those of images may contain slashes, like `ghcr.io/owner/image`
+    let find = |names: &[&Path]| {
+        names.iter().try_fold(traversal.root_index, |parent, name| {
+            traversal
+                .children(parent)
+                .into_iter()
+                .find(|idx| tree.name_of(*idx) == *name)
+        })
+    };
+    let size_of = |names: &[&Path]| -> u128 { find(names).map_or(0, |idx| tree[idx].size as u128) };
+
+    let mut layer_sizes = HashMap::new();
+    let mut users = HashMap::<&str, usize>::new();
+    for image in &docker.images {
+        for layer in &image.layers {
+            *users.entry(&layer.chain_id).or_default() += 1;
+            let name = DockerFilesystem::layer_name(layer, None);
+            let path = [Path::new("images"), Path::new(&image.name), &name];
+            if find(&path).is_some() {
+                layer_sizes
+                    .entry(&layer.chain_id)
+                    .or_insert_with(|| size_of(&path));
+            }
+        }
+    }
+    let image_sizes: BTreeMap<&str, ImageSize> = docker
+        .images
+        .iter()
+        .map(|image| {
+            let size_of_layers = |unique_only: bool| -> u128 {
+                image
+                    .layers
+                    .iter()
+                    .filter(|layer| !unique_only || users[layer.chain_id.as_str()] == 1)
+                    .map(|layer| layer_sizes.get(&layer.chain_id).copied().unwrap_or(0))
+                    .sum()
+            };
+            (
+                image.name.as_str(),
+                ImageSize {
+                    total: size_of_layers(false),
+                    unique: size_of_layers(true),
+                },
+            )
+        })
+        .collect();
+
+    let width = byte_format.width();
+    writeln!(
+        out,
+        "{:>width$} {:>width$} IMAGE",
+        "SIZE",
+        "UNIQUE",
+        width = width
+    )?;
+    for image in &docker.images {
+        let size = &image_sizes[image.name.as_str()];
+        writeln!(
+            out,
+            "{:>width$} {:>width$} {}",
+            byte_format.display(size.total).to_string(),
+            byte_format
+                .display(size.unique)
+                .to_string()
+                .as_str()
+                .green(),
+            image.name,
+            width = width
+        )?;
+    }
+    writeln!(out, "{:>width$} CONTAINER", "SIZE", width = width)?;
+    for container in &docker.containers {
+        let path = [Path::new("containers"), Path::new(&container.name)];
+        let image = docker
+            .images
+            .iter()
+            .find(|image| image.id == container.image_id)
+            .map_or_else(
+                || short_id(&container.image_id),
+                |image| image.name.as_str(),
+            );
+        writeln!(
+            out,
+            "{:>width$} {} ({})",
+            byte_format
+                .display(size_of(&path))
+                .to_string()
+                .as_str()
+                .green(),
+            container.name,
+            image,
+            width = width
+        )?;
+    }
+    writeln!(out, "{:>width$} VOLUME", "SIZE", width = width)?;
+    for volume in &docker.volumes {
+        let path = [
+            Path::new("volumes"),
+            Path::new(volume.file_name().unwrap_or_default()),
+        ];
+        writeln!(
+            out,
+            "{:>width$} {}",
+            byte_format
+                .display(size_of(&path))
+                .to_string()
+                .as_str()
+                .green(),
+            volume.file_name().unwrap_or_default().to_string_lossy(),
+            width = width
+        )?;
+    }
+    writeln!(
+        out,
+        "{} images, {} containers and {} volumes taking {}",
+        docker.images.len(),
+        docker.containers.len(),
+        docker.volumes.len(),
+        byte_format.display(
+            DockerFilesystem::PATHS
+                .iter()
+                .map(|p| size_of(&[Path::new(p)]))
+                .sum()
+        )
+    )?;
+    if traversal.is_incomplete {
+        writeln!(out, "{}", "(incomplete, more may be missing)".red())?;
+    }
+    Ok(WalkResult {
+        num_errors: traversal.io_errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+
+    /// Write the files Docker keeps for images with `layers`, each a digest and the size of its only file, and
+    /// containers with a name, an image and the size of its only changed file.
+    fn docker_root(
+        root: &Path,
+        images: &[(&str, &[(&str, usize)])],
+        containers: &[(&str, &str, usize)],
+    ) -> io::Result<()> {
+        let image_dir = root.join("image/overlay2");
+        let mut repositories = Vec::new();
+        for (image_idx, (name, layers)) in images.iter().enumerate() {
+            let image_hex = format!("{:064}", image_idx + 1);
+            let diff_ids: Vec<_> = layers
+                .iter()
+                .map(|(hex, _)| format!("\"sha256:{}\"", hex))
+                .collect();
+            fs::create_dir_all(image_dir.join("imagedb/content/sha256"))?;
+            fs::write(
+                image_dir.join("imagedb/content/sha256").join(&image_hex),
+                format!(
+                    r#"{{"architecture":"amd64","config":{{"Env":["A=1"]}},"rootfs":{{"type":"layers","diff_ids":[{}]}}}}"#,
+                    diff_ids.join(",")
+                ),
+            )?;
+            repositories.push(format!(
+                r#""{name}":{{"{name}":"sha256:{hex}"}}"#,
+                name = name,
+                hex = image_hex
+            ));
+            // Chains are named after the layers they consist of, which is good enough to be unique here
+            let mut parent: Option<String> = None;
+            for (hex, size) in layers.iter() {
+                let chain = match &parent {
+                    Some(parent) => format!("{}-{}", parent, hex),
+                    None => hex.to_string(),
+                };
+                let dir = image_dir.join("layerdb/sha256").join(&chain);
+                fs::create_dir_all(&dir)?;
+                fs::write(dir.join("diff"), format!("sha256:{}", hex))?;
+                fs::write(dir.join("cache-id"), format!("cache-{}", chain))?;
+                if let Some(parent) = &parent {
+                    fs::write(dir.join("parent"), format!("sha256:{}", parent))?;
+                }
+                let layer_dir = root
+                    .join("overlay2")
+                    .join(format!("cache-{}", chain))
+                    .join("diff");
+                fs::create_dir_all(&layer_dir)?;
+                fs::write(layer_dir.join("file"), vec![0; *size])?;
+                parent = Some(chain);
+            }
+        }
+        fs::write(
+            image_dir.join("repositories.json"),
+            format!(r#"{{"Repositories":{{{}}}}}"#, repositories.join(",")),
+        )?;
+        for (idx, (name, image, size)) in containers.iter().enumerate() {
+            let id = format!("c{:063}", idx);
+            let image_idx = images
+                .iter()
+                .position(|(n, _)| n == image)
+                .expect("known image");
+            let dir = root.join("containers").join(&id);
+            fs::create_dir_all(&dir)?;
+            fs::write(
+                dir.join("config.v2.json"),
+                format!(
+                    r#"{{"ID":"{}","Image":"sha256:{:064}","Name":"/{}"}}"#,
+                    id,
+                    image_idx + 1,
+                    name
+                ),
+            )?;
+            let mounts = image_dir.join("layerdb/mounts").join(&id);
+            fs::create_dir_all(&mounts)?;
+            fs::write(mounts.join("mount-id"), format!("mount-{}", idx))?;
+            let layer_dir = root
+                .join("overlay2")
+                .join(format!("mount-{}", idx))
+                .join("diff");
+            fs::create_dir_all(&layer_dir)?;
+            fs::write(layer_dir.join("changed"), vec![0; *size])?;
+        }
+        fs::create_dir_all(root.join("volumes/data/_data"))?;
+        fs::write(root.join("volumes/data/_data/db"), vec![0; 7000])?;
+        Ok(())
+    }
+
+    #[test]
+    fn images_are_listed_with_the_size_of_their_layers_and_of_those_no_other_image_uses(
+    ) -> Result<()> {
+        let root = TempDir::new("docker")?;
+        let (base, app, other) = ("a".repeat(64), "b".repeat(64), "c".repeat(64));
+        docker_root(
+            &root,
+            &[
+                ("app:1.0", &[(&base, 1000), (&app, 200)]),
+                ("base:latest", &[(&base, 1000)]),
+                ("other:2", &[(&other, 30)]),
+            ],
+            &[("web", "app:1.0", 5)],
+        )?;
+
+        let docker = Docker::read(&root)?;
+        assert_eq!(
+            docker
+                .images
+                .iter()
+                .map(|i| (i.name.as_str(), i.layers.len()))
+                .collect::<Vec<_>>(),
+            vec![("app:1.0", 2), ("base:latest", 1), ("other:2", 1)]
+        );
+        assert_eq!(docker.containers[0].name, "web");
+
+        let config = root
+            .join("containers")
+            .join(&docker.containers[0].id)
+            .join("config.v2.json");
+        let config_size = fs::metadata(config)?.len();
+        let mut out = Vec::new();
+        let walk_options = WalkOptions::default()
+            .threads(1)
+            .apparent_size(true)
+            .byte_format(ByteFormat::Bytes);
+        super::docker(&mut out, None::<io::Stderr>, walk_options.clone(), &root)?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "SIZE UNIQUE IMAGE",
+                "1200 b 200 b app:1.0",
+                "1000 b 0 b base:latest",
+                "30 b 30 b other:2",
+                "SIZE CONTAINER",
+                &format!("{} b web (app:1.0)", 5 + config_size),
+                "SIZE VOLUME",
+                "7000 b data",
+                &format!(
+                    "3 images, 1 containers and 1 volumes taking {} b",
+                    1230 + 5 + config_size + 7000
+                ),
+            ]
+        );
+
+        // Layers shared with an earlier image are shown there, but not walked again
+        let mut filesystem = DockerFilesystem::new(docker, walk_options)?;
+        let traversal =
+            Traversal::from_filesystem(&mut filesystem, true, vec!["images".into()], |_| {
+                Ok(false)
+            })?;
+        let shared =
+            Path::new("images/base:latest").join(format!("layer {} (in app:1.0)", &base[..12]));
+        let shared = traversal.find(&shared).expect("the shared layer");
+        assert_eq!(traversal.children(shared).len(), 0);
+        Ok(())
+    }
+}
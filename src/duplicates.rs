@@ -0,0 +1,305 @@
+use crate::{crossdev, walk::Walk, InodeFilter, WalkOptions, WalkResult, INTERRUPTED};
+use anyhow::Result;
+use colored::Colorize;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fs,
+    hash::{BuildHasher, Hasher},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+/// The amount of bytes at the start of each file which are compared before all of their content is.
+const PREFIX_LEN: u64 = 4096;
+
+/// Files with the same content.
+#[derive(Debug)]
+pub struct DuplicateSet<T> {
+    /// The length of each of the files in bytes
+    pub size: u64,
+    /// The paths of the files along with the value given for them, sorted by path
+    pub files: Vec<(PathBuf, T)>,
+}
+
+impl<T> DuplicateSet<T> {
+    /// The amount of bytes freed by keeping only one of the files.
+    pub fn reclaimable_bytes(&self) -> u128 {
+        self.size as u128 * (self.files.len() as u128 - 1)
+    }
+}
+
+/// Find the `files` which have the same content, given their length in bytes, their path, and a value to associate
+/// with each of them, like an index.
+///
+/// Files are grouped by their length first, then by a hash of their first bytes, and finally by a hash of their
+/// whole content, so only files that may be duplicates are read completely. Empty files and all entries that aren't
+/// regular files, like symbolic links, are ignored. Files that can't be read are passed to `on_error`.
+///
+/// The returned sets are sorted by the amount of bytes they waste, the largest first. Once
+/// [interrupted][crate::INTERRUPTED], no more files are read and only the sets found so far are returned.
+pub fn find_duplicates<T>(
+    files: impl IntoIterator<Item = (u64, PathBuf, T)>,
+    mut on_error: impl FnMut(&Path, io::Error),
+) -> Vec<DuplicateSet<T>> {
+    let mut by_size = HashMap::<_, Vec<_>>::new();
+    for (size, path, value) in files {
+        if size > 0 {
+            by_size.entry(size).or_default().push((path, value));
+        }
+    }
+
+    let hasher = ContentHasher::default();
+    let mut sets = Vec::new();
+    'sizes: for (size, files) in by_size.into_iter() {
+        if files.len() < 2 {
+            continue;
+        }
+        let mut candidates = vec![files];
+        for limit in &[Some(PREFIX_LEN), None] {
+            if limit.is_none() && size <= PREFIX_LEN {
+                break;
+            }
+            let mut groups = Vec::new();
+            for files in candidates {
+                let mut by_hash = HashMap::<_, Vec<_>>::new();
+                for (path, value) in files {
+                    if INTERRUPTED.load(Ordering::Relaxed) {
+                        break 'sizes;
+                    }
+                    match hasher.hash(&path, *limit) {
+                        Ok(Some(hash)) => by_hash.entry(hash).or_default().push((path, value)),
+                        Ok(None) => {}
+                        Err(err) => on_error(&path, err),
+                    }
+                }
+                groups.extend(by_hash.into_values());
+            }
+            candidates = groups.into_iter().filter(|files| files.len() > 1).collect();
+        }
+        sets.extend(candidates.into_iter().map(|mut files| {
+            files.sort_by(|(l, _), (r, _)| l.cmp(r));
+            DuplicateSet { size, files }
+        }));
+    }
+    sets.sort_by(|l, r| {
+        r.reclaimable_bytes()
+            .cmp(&l.reclaimable_bytes())
+            .then_with(|| l.files[0].0.cmp(&r.files[0].0))
+    });
+    sets
+}
+
+/// Hashes file content with two independently keyed hashers, to make collisions of different content negligible.
+#[derive(Default)]
+struct ContentHasher([RandomState; 2]);
+
+impl ContentHasher {
+    /// Hash the first `limit` bytes of the file at `path`, or all of them, or return `None` if it's not a regular file.
+    fn hash(&self, path: &Path, limit: Option<u64>) -> io::Result<Option<(u64, u64)>> {
+        if !fs::symlink_metadata(path)?.is_file() {
+            return Ok(None);
+        }
+        let mut content = fs::File::open(path)?.take(limit.unwrap_or(u64::MAX));
+        let mut hashers = [self.0[0].build_hasher(), self.0[1].build_hasher()];
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let bytes_read = match content.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            for hasher in hashers.iter_mut() {
+                hasher.write(&buf[..bytes_read]);
+            }
+        }
+        Ok(Some((hashers[0].finish(), hashers[1].finish())))
+    }
+}
+
+/// Walk the given `paths` and write all sets of files with the same content to `out`, the ones wasting the most
+/// space first, followed by the amount of bytes that could be freed by keeping only one file of each set.
+///
+/// Hard links to the same file are not duplicates of each other and are only counted once. If `err` is set, it shows
+/// the entries seen and the bytes of files to compare while walking, and is cleared before the files are read.
+/// If the walk is [interrupted][crate::INTERRUPTED], the sets found so far are written along with a note that more
+/// may be missing.
+pub fn duplicates(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut files = Vec::new();
+    let mut inodes = InodeFilter::default();
+    let mut walk = Walk::new(&walk_options, err)?;
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, device_id| {
+            match entry {
+                Ok(entry) => match entry.client_state {
+                    Some(Ok(ref m))
+                        if m.is_file()
+                            && inodes.add(m)
+                            && (walk_options.cross_filesystems
+                                || crossdev::is_same_device(device_id, m)) =>
+                    {
+                        files.push((m.len(), entry.path(), ()));
+                        return Some(m.len() as u128);
+                    }
+                    Some(Ok(_)) | None => {}
+                    Some(Err(_)) => res.num_errors += 1,
+                },
+                Err(_) => res.num_errors += 1,
+            }
+            None
+        })?;
+    }
+    walk.clear_progress();
+
+    let sets = find_duplicates(files, |_, _| res.num_errors += 1);
+    let byte_format = walk_options.byte_format;
+    let mut reclaimable_bytes = 0;
+    for set in &sets {
+        writeln!(
+            out,
+            "{:>byte_column_width$} × {} ({} reclaimable)",
+            byte_format
+                .display(set.size as u128)
+                .to_string()
+                .as_str()
+                .green(),
+            set.files.len(),
+            byte_format.display(set.reclaimable_bytes()),
+            byte_column_width = byte_format.width()
+        )?;
+        for (path, _) in &set.files {
+            writeln!(out, "  {}", path.display())?;
+        }
+        reclaimable_bytes += set.reclaimable_bytes();
+    }
+    writeln!(
+        out,
+        "{} reclaimable in {} set{} of duplicates{}",
+        byte_format
+            .display(reclaimable_bytes)
+            .to_string()
+            .as_str()
+            .green(),
+        sets.len(),
+        if sets.len() == 1 { "" } else { "s" },
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            " (incomplete)".red().to_string()
+        } else {
+            String::new()
+        }
+    )?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+
+    fn write_files(name: &str, files: &[(&str, &[u8])]) -> io::Result<TempDir> {
+        let root = TempDir::new(&format!("duplicates-{}", name))?;
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().expect("parent"))?;
+            fs::write(path, content)?;
+        }
+        Ok(root)
+    }
+
+    #[test]
+    fn it_groups_files_by_content_and_sorts_sets_by_reclaimable_bytes() -> io::Result<()> {
+        let same_prefix_a = [vec![1u8; PREFIX_LEN as usize], vec![2]].concat();
+        let same_prefix_b = [vec![1u8; PREFIX_LEN as usize], vec![3]].concat();
+        let root = write_files(
+            "find",
+            &[
+                ("a", b"hello"),
+                ("sub/a", b"hello"),
+                ("b", b"world"),
+                ("c", b"hi"),
+                ("sub/c", b"hi"),
+                ("sub/deeper/c", b"hi"),
+                ("empty", b""),
+                ("empty-too", b""),
+                ("long-a", &same_prefix_a),
+                ("long-a-again", &same_prefix_a),
+                ("long-b", &same_prefix_b),
+            ],
+        )?;
+        let files = [
+            "a",
+            "sub/a",
+            "b",
+            "c",
+            "sub/c",
+            "sub/deeper/c",
+            "empty",
+            "empty-too",
+            "long-a",
+            "long-a-again",
+            "long-b",
+        ]
+        .iter()
+        .map(|name| {
+            let path = root.join(name);
+            (fs::metadata(&path).expect("file").len(), path, *name)
+        });
+        let mut num_errors = 0;
+        let sets = find_duplicates(files, |_, _| num_errors += 1);
+        let sets: Vec<_> = sets
+            .iter()
+            .map(|set| {
+                (
+                    set.size,
+                    set.files.iter().map(|(_, name)| *name).collect::<Vec<_>>(),
+                    set.reclaimable_bytes(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sets,
+            vec![
+                (4097, vec!["long-a", "long-a-again"], 4097),
+                (5, vec!["a", "sub/a"], 5),
+                (2, vec!["c", "sub/c", "sub/deeper/c"], 4),
+            ]
+        );
+        assert_eq!(num_errors, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_duplicate_sets_and_the_reclaimable_bytes() -> Result<()> {
+        let root = write_files(
+            "write",
+            &[("a", b"hello"), ("sub/a", b"hello"), ("b", b"world")],
+        )?;
+        let mut out = Vec::new();
+        let res = duplicates(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            Some(&root),
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("5 b") && lines[0].ends_with("× 2 (5 b reclaimable)"));
+        assert_eq!(lines[1], format!("  {}", root.join("a").display()));
+        assert_eq!(lines[2], format!("  {}", root.join("sub/a").display()));
+        assert!(lines[3].ends_with("reclaimable in 1 set of duplicates"));
+        assert_eq!(res.num_errors, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{crossdev, walk::Walk, WalkOptions, WalkResult};
+use anyhow::Result;
+use colored::Colorize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Walk the given `paths` and write all empty directories to `out`, followed by all files without content, each in
+/// the order of their paths.
+///
+/// Directories are empty if there is nothing in them at all, including entries left out of the walk, like those
+/// excluded or on other filesystems.
+/// If `err` is set, it shows the entries seen so far while walking, and is cleared before the lists are written.
+/// If the walk is [interrupted][crate::INTERRUPTED], the entries found so far are written along with a note that more
+/// may be missing.
+pub fn empties(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut directories: Vec<PathBuf> = Vec::new();
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut walk = Walk::new(&walk_options, err)?;
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, device_id| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            // Directories on other filesystems aren't descended into, and aren't looked at either
+            if entry.file_type.is_dir() {
+                if entry.read_children_path.is_some() {
+                    let path = entry.path();
+                    match fs::read_dir(&path).map(|mut dir| dir.next().is_none()) {
+                        Ok(true) => directories.push(path),
+                        Ok(false) => {}
+                        Err(_) => res.num_errors += 1,
+                    }
+                }
+                return None;
+            }
+            match entry.client_state {
+                Some(Ok(ref m))
+                    if m.is_file()
+                        && m.len() == 0
+                        && (walk_options.cross_filesystems
+                            || crossdev::is_same_device(device_id, m)) =>
+                {
+                    files.push(entry.path())
+                }
+                Some(Ok(_)) | None => {}
+                Some(Err(_)) => res.num_errors += 1,
+            }
+            None
+        })?;
+    }
+    walk.clear_progress();
+
+    directories.sort();
+    files.sort();
+    for (singular, plural, paths) in [
+        ("empty directory", "empty directories", &directories),
+        ("empty file", "empty files", &files),
+    ] {
+        writeln!(
+            out,
+            "{} {}",
+            paths.len().to_string().as_str().green(),
+            if paths.len() == 1 { singular } else { plural }
+        )?;
+        for path in paths {
+            writeln!(out, "  {}", path.display())?;
+        }
+    }
+    if walk.is_incomplete {
+        writeln!(out, "{}", "(incomplete, more may be missing)".red())?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+
+    #[test]
+    fn it_lists_empty_directories_and_files_without_content() -> Result<()> {
+        let root = TempDir::new("empties")?;
+        fs::create_dir_all(root.join("full/empty"))?;
+        fs::create_dir_all(root.join("other-empty"))?;
+        fs::write(root.join("full/content"), b"content")?;
+        fs::write(root.join("full/nothing"), b"")?;
+
+        let mut out = Vec::new();
+        let res = empties(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            vec![&root],
+        )?;
+        let out = String::from_utf8(out)?;
+        let r = root.display();
+        assert_eq!(
+            out,
+            format!(
+                "2 empty directories\n  {r}/full/empty\n  {r}/other-empty\n1 empty file\n  {r}/full/nothing\n",
+                r = r
+            )
+        );
+        assert_eq!(res.num_errors, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+//! Sources of entries to walk, like the local filesystem, which [traversals][crate::traverse::Traversal] build their
+//! tree from.
+use crate::{crossdev, traverse::TraversalError, InodeFilter, WalkOptions};
+use filesize::PathExt;
+use std::{
+    collections::BTreeMap,
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The sizes of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntrySize {
+    /// The amount of bytes of its content
+    pub apparent: u64,
+    /// The amount of bytes it takes on disk, or why that isn't known
+    pub disk_usage: Result<u64, TraversalError>,
+}
+
+/// An entry yielded by a walk of a [`Filesystem`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsEntry {
+    /// How far the entry is below the walked path, which is at depth 0
+    pub depth: usize,
+    /// The walked path itself at depth 0, and the name of the entry within its directory otherwise
+    pub name: PathBuf,
+    /// If set, the entry is a directory whose entries follow it, one level deeper
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    /// If set, the entry is a symbolic link whose target doesn't exist
+    pub is_broken_link: bool,
+    /// If set, the entry is a valid [`CACHEDIR_TAG`][crate::CACHEDIR_TAG] file
+    pub is_cache_dir_tag: bool,
+    /// If set, the entry is an archive to look into, see [`WalkOptions::archives`]
+    pub is_archive: bool,
+    /// The time the entry was last modified at, if known
+    pub mtime: Option<SystemTime>,
+    /// The sizes of the entry, or `None` if it doesn't count, like directories, files seen before through another
+    /// hard link, or files on other filesystems
+    pub size: Option<EntrySize>,
+    /// Set if the meta-data of the entry couldn't be obtained
+    pub metadata_error: Option<TraversalError>,
+}
+
+/// The entries yielded by a walk, or the errors of those which couldn't be read at all
+pub type Walk<'a> = Box<dyn Iterator<Item = Result<FsEntry, TraversalError>> + 'a>;
+
+/// A source of entries, walked one path at a time.
+pub trait Filesystem {
+    /// Walk `path` depth-first, yielding it first and each directory right before the entries within it.
+    ///
+    /// All walks of the same filesystem belong to a single traversal, so files seen by a previous walk through
+    /// another hard link may not count again.
+    fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>>;
+}
+
+/// The filesystems of the operating system, walked as configured by [`WalkOptions`].
+pub struct LocalFilesystem {
+    walk_options: WalkOptions,
+    parallelism: jwalk::Parallelism,
+    inodes: InodeFilter,
+}
+
+impl LocalFilesystem {
+    /// Create a filesystem to walk with `walk_options`, along with the threads to do so.
+    pub fn new(walk_options: WalkOptions) -> io::Result<Self> {
+        Ok(LocalFilesystem {
+            parallelism: walk_options.parallelism()?,
+            walk_options,
+            inodes: InodeFilter::default(),
+        })
+    }
+}
+
+#[cfg(not(windows))]
+fn size_on_disk(_parent: &Path, name: &Path, meta: &Metadata) -> io::Result<u64> {
+    name.size_on_disk_fast(meta)
+}
+#[cfg(windows)]
+fn size_on_disk(parent: &Path, name: &Path, meta: &Metadata) -> io::Result<u64> {
+    parent.join(name).size_on_disk_fast(meta)
+}
+
+impl Filesystem for LocalFilesystem {
+    fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+        let device_id = crossdev::init(path)?;
+        let LocalFilesystem {
+            walk_options,
+            parallelism,
+            inodes,
+        } = self;
+        let path = path.to_owned();
+        let entries = walk_options.iter_from_path(&path, device_id, parallelism.clone());
+        let (count_hard_links, cross_filesystems, archives, count_inodes) = (
+            walk_options.count_hard_links,
+            walk_options.cross_filesystems,
+            walk_options.archives,
+            walk_options.count_inodes,
+        );
+        // Each entry takes one inode, whichever size is shown
+        let one_inode = || EntrySize {
+            apparent: 1,
+            disk_usage: Ok(1),
+        };
+        Ok(Box::new(entries.into_iter().map(move |entry| {
+            let entry = entry.map_err(|err| TraversalError::from_walk_error(&err, &path))?;
+            let (name, entry_path) = if entry.depth < 1 {
+                (path.clone(), path.clone())
+            } else {
+                (
+                    PathBuf::from(&entry.file_name),
+                    entry.parent_path.join(&entry.file_name),
+                )
+            };
+            let is_symlink = entry.path_is_symlink();
+            let mut fs_entry = FsEntry {
+                depth: entry.depth,
+                // Input paths linking to directories are walked, but their type is still the link's
+                is_dir: entry.file_type.is_dir() || entry.read_children_path.is_some(),
+                is_symlink,
+                is_broken_link: is_symlink && !entry_path.exists(),
+                is_cache_dir_tag: false,
+                is_archive: false,
+                mtime: None,
+                size: None,
+                metadata_error: None,
+                name,
+            };
+            fs_entry.is_cache_dir_tag = entry.depth > 0
+                && !fs_entry.is_dir
+                && *fs_entry.name == *Path::new(crate::CACHEDIR_TAG)
+                && crate::is_cache_dir_tag(&entry_path);
+            fs_entry.is_archive = archives
+                && !fs_entry.is_dir
+                && !is_symlink
+                && crate::archive::is_archive(&fs_entry.name);
+            match &entry.client_state {
+                Some(Ok(m)) => {
+                    fs_entry.mtime = m.modified().ok();
+                    if (count_inodes || !m.is_dir())
+                        && (count_hard_links || inodes.add(m))
+                        && (cross_filesystems || crossdev::is_same_device(device_id, m))
+                    {
+                        fs_entry.size = Some(match count_inodes {
+                            true => one_inode(),
+                            false => EntrySize {
+                                apparent: m.len(),
+                                disk_usage: size_on_disk(&entry.parent_path, &fs_entry.name, m)
+                                    .map_err(|err| {
+                                        TraversalError::from_io_error(&err, entry_path.clone())
+                                    }),
+                            },
+                        });
+                    }
+                }
+                Some(Err(err)) => {
+                    fs_entry.metadata_error =
+                        Some(TraversalError::from_walk_error(err, &entry_path));
+                    fs_entry.size = Some(one_inode()).filter(|_| count_inodes);
+                }
+                None if count_inodes => fs_entry.size = Some(one_inode()),
+                None => {} // a directory
+            }
+            Ok(fs_entry)
+        })))
+    }
+}
+
+/// A filesystem kept in memory, to walk made-up trees of files and directories without touching the disk.
+///
+/// Entries within a directory are walked in the order of their names. Files take up their size rounded up to
+/// whole blocks on disk, and have no modification time.
+#[derive(Debug, Clone)]
+pub struct MemoryFilesystem {
+    /// The size of each file, or `None` for directories, by path.
+    /// Paths are ordered by their components, which puts all entries within a directory right after it.
+    entries: BTreeMap<PathBuf, Option<u64>>,
+    block_size: u64,
+}
+
+/// An empty filesystem with blocks of [`MemoryFilesystem::BLOCK_SIZE`] bytes
+impl Default for MemoryFilesystem {
+    fn default() -> Self {
+        MemoryFilesystem {
+            entries: BTreeMap::new(),
+            block_size: Self::BLOCK_SIZE,
+        }
+    }
+}
+
+impl MemoryFilesystem {
+    /// The unit in which files take up space on disk by default
+    pub const BLOCK_SIZE: u64 = 4096;
+
+    /// Set the unit in which files take up space on disk. With 1, they take up exactly their size.
+    pub fn block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Add a file at `path` with `size` bytes of content, along with all directories leading to it.
+    pub fn file(mut self, path: impl AsRef<Path>, size: u64) -> Self {
+        self.add_parents(path.as_ref());
+        self.entries.insert(path.as_ref().to_owned(), Some(size));
+        self
+    }
+
+    /// Add an empty directory at `path`, along with all directories leading to it.
+    pub fn dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.add_parents(path.as_ref());
+        self.entries.insert(path.as_ref().to_owned(), None);
+        self
+    }
+
+    fn add_parents(&mut self, path: &Path) {
+        for parent in path.ancestors().skip(1) {
+            if parent.as_os_str().is_empty() {
+                break;
+            }
+            self.entries.insert(parent.to_owned(), None);
+        }
+    }
+}
+
+impl Filesystem for MemoryFilesystem {
+    fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+        if !self.entries.contains_key(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' doesn't exist", path.display()),
+            ));
+        }
+        let root = path.to_owned();
+        let root_depth = root.components().count();
+        let block_size = self.block_size;
+        Ok(Box::new(
+            self.entries
+                .range(root.clone()..)
+                .take_while(move |(path, _)| path.starts_with(&root))
+                .map(move |(path, size)| {
+                    let depth = path.components().count() - root_depth;
+                    Ok(FsEntry {
+                        depth,
+                        name: match depth {
+                            0 => path.clone(),
+                            _ => PathBuf::from(path.file_name().expect("a name below the root")),
+                        },
+                        is_dir: size.is_none(),
+                        is_symlink: false,
+                        is_broken_link: false,
+                        is_cache_dir_tag: false,
+                        is_archive: false,
+                        mtime: None,
+                        size: size.map(|size| EntrySize {
+                            apparent: size,
+                            disk_usage: Ok(size.div_ceil(block_size) * block_size),
+                        }),
+                        metadata_error: None,
+                    })
+                }),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn made_up_entries_are_walked_depth_first_in_the_order_of_their_names() -> io::Result<()> {
+        let mut filesystem = MemoryFilesystem::default()
+            .file("root/a.txt", 5000)
+            .file("root/a/b", 1)
+            .dir("root/c");
+        let entries: Vec<_> = filesystem
+            .walk(Path::new("root"))?
+            .map(|entry| {
+                let entry = entry.expect("no errors");
+                (
+                    entry.depth,
+                    entry.name,
+                    entry.size.map(|size| (size.apparent, size.disk_usage)),
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (0, PathBuf::from("root"), None),
+                (1, PathBuf::from("a"), None),
+                (2, PathBuf::from("b"), Some((1, Ok(4096)))),
+                (1, PathBuf::from("a.txt"), Some((5000, Ok(8192)))),
+                (1, PathBuf::from("c"), None),
+            ]
+        );
+        assert!(filesystem.walk(Path::new("missing")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+//! Matching entries against the patterns of `.gitignore` files, see `man gitignore`.
+use crate::Glob;
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// How entries which are ignored by git are treated during a traversal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitignoreMode {
+    /// Ignore files have no effect
+    Disregard,
+    /// Skip ignored entries along with all of their children
+    Respect,
+    /// Skip all files which are not ignored, to see what could be deleted without losing anything tracked.
+    /// Directories are kept, as they may contain ignored entries.
+    OnlyIgnored,
+}
+
+/// Return true if `path` is ignored by the `.gitignore` files of its parent directories, up to the root of the
+/// git repository it's in.
+pub fn is_ignored_by_git(path: &Path) -> bool {
+    GitignoreState::for_root(path).is_ignored(path, path.is_dir())
+}
+
+#[derive(Debug)]
+enum Segment {
+    /// `**`, any amount of directories
+    AnyDirs,
+    Name(Glob),
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// The path components to match, relative to the directory of the file the rule is from
+    segments: Vec<Segment>,
+    /// If set, the rule re-includes entries that previous rules ignored
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        // Patterns with a separator before the end are relative to their file, all others match at any depth
+        let is_anchored = pattern.contains('/');
+        let mut segments = Vec::new();
+        if !is_anchored {
+            segments.push(Segment::AnyDirs);
+        }
+        for segment in pattern.split('/').filter(|s| !s.is_empty()) {
+            segments.push(match segment {
+                "**" => Segment::AnyDirs,
+                name => Segment::Name(name.parse().ok()?),
+            });
+        }
+        if segments.iter().all(|s| matches!(s, Segment::AnyDirs)) {
+            return None;
+        }
+        Some(Rule {
+            segments,
+            negated,
+            dir_only,
+        })
+    }
+
+    fn matches(&self, components: &[&OsStr], is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && matches_segments(&self.segments, components)
+    }
+}
+
+fn matches_segments(segments: &[Segment], components: &[&OsStr]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((Segment::AnyDirs, rest)) => {
+            (0..=components.len()).any(|skip| matches_segments(rest, &components[skip..]))
+        }
+        Some((Segment::Name(glob), rest)) => match components.split_first() {
+            Some((name, components)) => {
+                glob.matches(Path::new(""), name) && matches_segments(rest, components)
+            }
+            None => false,
+        },
+    }
+}
+
+/// The rules of one ignore file
+#[derive(Debug)]
+struct IgnoreFile {
+    /// The directory the rules apply to, as seen by the walk
+    dir: PathBuf,
+    /// Prepended to paths relative to `dir` before matching, if the file is in a parent directory of the walk
+    prefix: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    fn read(path: &Path, dir: &Path, prefix: PathBuf) -> Option<IgnoreFile> {
+        let content = fs::read_to_string(path).ok()?;
+        let rules: Vec<_> = content.lines().filter_map(Rule::parse).collect();
+        if rules.is_empty() {
+            return None;
+        }
+        Some(IgnoreFile {
+            dir: dir.to_owned(),
+            prefix,
+            rules,
+        })
+    }
+
+    /// Return `Some(true)` if `path` is ignored, `Some(false)` if it was re-included, or `None` if no rule matches.
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = self.prefix.join(path.strip_prefix(&self.dir).ok()?);
+        let components: Vec<_> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        if components.is_empty() {
+            return None;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&components, is_dir))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// The ignore files applying to a directory during a walk, handed down from each directory to its children.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GitignoreState {
+    /// The ignore files in order of increasing precedence
+    files: Vec<Arc<IgnoreFile>>,
+    /// If set, the directory is ignored itself, which is all that's needed to know about its entries
+    pub(crate) is_ignored: bool,
+}
+
+impl GitignoreState {
+    /// Create the state for walking `root`, with the ignore files of its parent directories up to the root of the
+    /// git repository it's in, if there is one.
+    pub(crate) fn for_root(root: &Path) -> GitignoreState {
+        let mut state = GitignoreState::default();
+        let absolute_root = match fs::canonicalize(root) {
+            Ok(root) => root,
+            Err(_) => return state,
+        };
+        let ancestors: Vec<_> = absolute_root.ancestors().skip(1).collect();
+        let repository_root = match ancestors.iter().position(|dir| dir.join(".git").exists()) {
+            Some(pos) => pos,
+            None => return state,
+        };
+        for dir in ancestors[..=repository_root].iter().rev() {
+            let prefix = absolute_root
+                .strip_prefix(dir)
+                .expect("ancestors are prefixes")
+                .to_owned();
+            state.push(&dir.join(".git/info/exclude"), root, prefix.clone());
+            state.push(&dir.join(".gitignore"), root, prefix);
+        }
+        state
+    }
+
+    /// Add the ignore files of `dir`, which is about to be walked.
+    pub(crate) fn enter(&mut self, dir: &Path) {
+        self.push(&dir.join(".git/info/exclude"), dir, PathBuf::new());
+        self.push(&dir.join(".gitignore"), dir, PathBuf::new());
+    }
+
+    fn push(&mut self, path: &Path, dir: &Path, prefix: PathBuf) {
+        if let Some(file) = IgnoreFile::read(path, dir, prefix) {
+            self.files.push(Arc::new(file));
+        }
+    }
+
+    /// Return true if the entry at `path` is ignored by the rules of the ignore files seen so far.
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|file| file.matched(path, is_dir))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn ignore_file(dir: &str, content: &str) -> IgnoreFile {
+        IgnoreFile {
+            dir: dir.into(),
+            prefix: PathBuf::new(),
+            rules: content.lines().filter_map(Rule::parse).collect(),
+        }
+    }
+
+    #[test]
+    fn it_matches_names_at_any_depth_and_anchored_paths_relative_to_the_file() {
+        let file = ignore_file(
+            "repo",
+            "# build output\n/target\n*.log\n!keep.log\nnode_modules/\ndocs/*.html\na/**/z\n\\#hash\n",
+        );
+        let matched = |path: &str, is_dir: bool| file.matched(Path::new(path), is_dir);
+        assert_eq!(matched("repo/target", true), Some(true));
+        assert_eq!(
+            matched("repo/sub/target", true),
+            None,
+            "a leading slash anchors the pattern"
+        );
+        assert_eq!(matched("repo/sub/deep/debug.log", false), Some(true));
+        assert_eq!(matched("repo/sub/keep.log", false), Some(false));
+        assert_eq!(matched("repo/web/node_modules", true), Some(true));
+        assert_eq!(
+            matched("repo/web/node_modules", false),
+            None,
+            "a trailing slash only matches directories"
+        );
+        assert_eq!(matched("repo/docs/index.html", false), Some(true));
+        assert_eq!(
+            matched("repo/docs/api/index.html", false),
+            None,
+            "stars don't match separators"
+        );
+        assert_eq!(matched("repo/a/z", false), Some(true));
+        assert_eq!(matched("repo/a/b/c/z", false), Some(true));
+        assert_eq!(matched("repo/#hash", false), Some(true));
+        assert_eq!(matched("elsewhere/debug.log", false), None);
+    }
+
+    #[test]
+    fn later_files_take_precedence_and_parents_are_matched_with_a_prefix() {
+        let parent = IgnoreFile {
+            dir: "walked".into(),
+            prefix: "sub".into(),
+            rules: ["/sub/generated", "*.tmp"]
+                .iter()
+                .filter_map(|l| Rule::parse(l))
+                .collect(),
+        };
+        let state = GitignoreState {
+            files: vec![
+                Arc::new(parent),
+                Arc::new(ignore_file("walked/dir", "!important.tmp")),
+            ],
+            is_ignored: false,
+        };
+        assert!(state.is_ignored(Path::new("walked/generated"), true));
+        assert!(state.is_ignored(Path::new("walked/dir/scratch.tmp"), false));
+        assert!(!state.is_ignored(Path::new("walked/dir/important.tmp"), false));
+        assert!(!state.is_ignored(Path::new("walked/dir/main.rs"), false));
+    }
+
+    #[test]
+    fn walks_skip_ignored_entries_or_everything_else() -> anyhow::Result<()> {
+        use crate::{traverse::Traversal, WalkOptions};
+
+        let repo = TempDir::new("gitignore")?;
+        for dir in &[".git/info", "src", "target/debug", "logs"] {
+            fs::create_dir_all(repo.join(dir))?;
+        }
+        fs::write(repo.join(".git/info/exclude"), "*.swp\n")?;
+        fs::write(repo.join(".gitignore"), "/target\n*.log\n")?;
+        fs::write(repo.join("logs/.gitignore"), "!keep.log\n")?;
+        for file in &[
+            "src/main.rs",
+            "src/main.rs.swp",
+            "target/debug/dua",
+            "logs/debug.log",
+            "logs/keep.log",
+        ] {
+            fs::write(repo.join(file), "content")?;
+        }
+
+        let names_with = |gitignore, root: &Path| -> anyhow::Result<Vec<String>> {
+            let walk_options = WalkOptions::default()
+                .threads(1)
+                .apparent_size(true)
+                .gitignore(gitignore);
+            let t = Traversal::from_walk(walk_options, vec![root.to_owned()], |_| Ok(false))?;
+            let mut names: Vec<_> = t
+                .tree
+                .node_indices()
+                .filter(|&idx| idx != t.root_index)
+                .filter(|&idx| !t.tree[idx].is_dir)
+                .map(|idx| t.tree.name_of(idx).to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            Ok(names)
+        };
+        let files = names_with(GitignoreMode::Respect, &repo)?;
+        assert!(
+            !files
+                .iter()
+                .any(|f| f == "dua" || f == "debug.log" || f == "main.rs.swp"),
+            "{:?}",
+            files
+        );
+        assert!(files.iter().any(|f| f == "main.rs") && files.iter().any(|f| f == "keep.log"));
+        assert_eq!(
+            names_with(GitignoreMode::OnlyIgnored, &repo)?,
+            vec!["debug.log", "dua", "main.rs.swp"]
+        );
+        assert_eq!(
+            names_with(GitignoreMode::Respect, &repo.join("logs"))?,
+            vec![".gitignore", "keep.log"],
+            "ignore files of parent directories apply as well"
+        );
+        assert!(is_ignored_by_git(&repo.join("target")));
+        assert!(!is_ignored_by_git(&repo.join("src")));
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::{ffi::OsStr, path::Path, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyChars,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::AnyChar => true,
+            Token::AnyChars => false,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(from, to)| (*from..=*to).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+/// A shell-like glob pattern, used to exclude entries from a traversal.
+///
+/// * `*` matches any amount of characters, including none
+/// * `?` matches exactly one character
+/// * `[abc]`, `[a-z]` and `[!abc]` match one character (not) in the given set
+///
+/// Patterns without a `/` are matched against file names only, like `.cache` or `*.log`. All others are matched against
+/// the whole path of the entry, where `*` also matches path separators, like `*/node_modules`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+    matches_path: bool,
+}
+
+impl Glob {
+    /// Returns true if the entry named `name` in directory `parent` matches this pattern.
+    pub fn matches(&self, parent: &Path, name: &OsStr) -> bool {
+        if self.matches_path {
+            self.matches_str(&parent.join(name).to_string_lossy())
+        } else {
+            self.matches_str(&name.to_string_lossy())
+        }
+    }
+
+    /// Returns true if `path` matches this pattern.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        match (self.matches_path, path.file_name()) {
+            (false, Some(name)) => self.matches_str(&name.to_string_lossy()),
+            _ => self.matches_str(&path.to_string_lossy()),
+        }
+    }
+
+    fn matches_str(&self, input: &str) -> bool {
+        let input: Vec<char> = input.chars().collect();
+        let (mut t, mut i) = (0, 0);
+        let mut backtrack = None::<(usize, usize)>;
+        while i < input.len() {
+            match self.tokens.get(t) {
+                Some(Token::AnyChars) => {
+                    backtrack = Some((t, i));
+                    t += 1;
+                    continue;
+                }
+                Some(token) if token.matches(input[i]) => {
+                    t += 1;
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star_t, star_i)) => {
+                    backtrack = Some((star_t, star_i + 1));
+                    t = star_t + 1;
+                    i = star_i + 1;
+                }
+                None => return false,
+            }
+        }
+        self.tokens[t..].iter().all(|t| *t == Token::AnyChars)
+    }
+}
+
+impl FromStr for Glob {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = match s.trim_end_matches('/') {
+            "" => s,
+            trimmed => trimmed,
+        };
+        if pattern.is_empty() {
+            return Err("Glob patterns must not be empty".into());
+        }
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '*' => {
+                    if tokens.last() == Some(&Token::AnyChars) {
+                        continue;
+                    }
+                    Token::AnyChars
+                }
+                '?' => Token::AnyChar,
+                '[' => {
+                    let negated = chars.next_if(|c| *c == '!' || *c == '^').is_some();
+                    let mut ranges = Vec::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') if !ranges.is_empty() => break,
+                            Some(from) => {
+                                let to = if chars.peek() == Some(&'-') {
+                                    chars.next();
+                                    match chars.next() {
+                                        Some(']') => {
+                                            ranges.push((from, from));
+                                            ranges.push(('-', '-'));
+                                            break;
+                                        }
+                                        Some(to) => to,
+                                        None => break,
+                                    }
+                                } else {
+                                    from
+                                };
+                                ranges.push((from, to));
+                            }
+                            None => {
+                                return Err(format!("Unclosed character class in glob {:?}", s))
+                            }
+                        }
+                    }
+                    Token::Class { negated, ranges }
+                }
+                c => Token::Char(c),
+            });
+        }
+        Ok(Glob {
+            tokens,
+            matches_path: pattern.contains('/'),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, parent: &str, name: &str) -> bool {
+        pattern
+            .parse::<Glob>()
+            .unwrap()
+            .matches(Path::new(parent), OsStr::new(name))
+    }
+
+    #[test]
+    fn it_matches_file_names_without_separator() {
+        assert!(matches(".cache", "/home/user", ".cache"));
+        assert!(matches("*.log", "/var/log", "syslog.log"));
+        assert!(matches("*", "a", ""));
+        assert!(matches("node_modules/", "project", "node_modules"));
+        assert!(
+            !matches("*.log", "/var/log", "syslog.log.1"),
+            "patterns are anchored on both ends"
+        );
+        assert!(matches("a?c", "", "abc"));
+        assert!(!matches("a?c", "", "ac"));
+        assert!(matches("a*b*c", "", "axxbyybzzc"));
+        assert!(!matches("a*b*c", "", "axxbyybzz"));
+    }
+
+    #[test]
+    fn it_matches_whole_paths_with_separator() {
+        assert!(matches("*/node_modules", "project/sub", "node_modules"));
+        assert!(matches("/tmp/*", "/tmp/a", "b"), "stars match separators");
+        assert!(!matches("*/node_modules", "project", "node_modules.txt"));
+    }
+
+    #[test]
+    fn it_matches_character_classes() {
+        assert!(matches("[abc].o", "", "b.o"));
+        assert!(!matches("[abc].o", "", "d.o"));
+        assert!(matches("[!abc].o", "", "d.o"));
+        assert!(matches("file[0-9]", "", "file7"));
+        assert!(matches("[]]", "", "]"), "a leading ] is a literal");
+        assert!(matches("[a-]", "", "-"), "a trailing - is a literal");
+    }
+
+    #[test]
+    fn it_rejects_invalid_patterns() {
+        assert!("[abc".parse::<Glob>().is_err());
+        assert!("".parse::<Glob>().is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Decompression of gzip files, to look into compressed archives without unpacking them to disk.
+//!
+//! Only what's needed to read the content of a gzip file once from start to end is implemented: DEFLATE blocks are
+//! decoded with one lookup table per Huffman code, and checksums aren't verified.
+use std::io::{self, Read};
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order in which the lengths of the code lengths code are stored in dynamic blocks
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+const END_OF_BLOCK: u16 = 256;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// A canonical Huffman code, decoded with a table indexed by the next bits of the input
+struct Huffman {
+    /// The symbol and the length of its code, for all possible values of the next `max_len` bits
+    table: Vec<(u16, u8)>,
+    max_len: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let max_len = lengths.iter().copied().max().unwrap_or(0) as u32;
+        let mut count = [0u32; 16];
+        for &len in lengths {
+            count[len as usize] += 1;
+        }
+        count[0] = 0;
+        let mut next_code = [0u32; 16];
+        let mut code = 0;
+        for len in 1..16 {
+            code = (code + count[len - 1]) << 1;
+            next_code[len] = code;
+        }
+        let mut table = vec![(0, 0); 1 << max_len];
+        for (symbol, &len) in lengths.iter().enumerate().filter(|(_, len)| **len > 0) {
+            let code = next_code[len as usize];
+            next_code[len as usize] += 1;
+            if code >= 1 << len {
+                return Err(invalid("Invalid Huffman code lengths"));
+            }
+            // Codes are stored starting with their most significant bit, which is read first
+            let reversed = code.reverse_bits() >> (32 - len as u32);
+            for idx in (reversed as usize..table.len()).step_by(1 << len) {
+                table[idx] = (symbol as u16, len);
+            }
+        }
+        Ok(Huffman { table, max_len })
+    }
+
+    fn fixed() -> (Huffman, Huffman) {
+        let mut lengths = [8; 288];
+        lengths[144..256].iter_mut().for_each(|len| *len = 9);
+        lengths[256..280].iter_mut().for_each(|len| *len = 7);
+        (
+            Huffman::new(&lengths).expect("valid fixed code"),
+            Huffman::new(&[5; 30]).expect("valid fixed code"),
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    BlockStart,
+    Stored,
+    Codes,
+    Done,
+}
+
+/// The decompressed content of a gzip stream.
+pub struct Decoder<R> {
+    input: R,
+    bits: u64,
+    bit_count: u32,
+    /// The last bytes written, which matches refer to
+    window: Box<[u8]>,
+    window_pos: usize,
+    total_out: u64,
+    mode: Mode,
+    is_last_block: bool,
+    stored_left: u16,
+    codes: Option<(Huffman, Huffman)>,
+    copy_len: usize,
+    copy_distance: usize,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Read the gzip header from `input`, which should be buffered, and return the decoder of the content following
+    /// it.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0; 10];
+        input.read_exact(&mut header)?;
+        if header[..3] != [0x1f, 0x8b, 8] {
+            return Err(invalid("Not a gzip file"));
+        }
+        let flags = header[3];
+        let skip_zero_terminated = |input: &mut R| -> io::Result<()> {
+            let mut byte = [1];
+            while byte[0] != 0 {
+                input.read_exact(&mut byte)?;
+            }
+            Ok(())
+        };
+        if flags & 0x04 != 0 {
+            let mut len = [0; 2];
+            input.read_exact(&mut len)?;
+            io::copy(
+                &mut (&mut input).take(u16::from_le_bytes(len).into()),
+                &mut io::sink(),
+            )?;
+        }
+        if flags & 0x08 != 0 {
+            skip_zero_terminated(&mut input)?; // the file name
+        }
+        if flags & 0x10 != 0 {
+            skip_zero_terminated(&mut input)?; // the comment
+        }
+        if flags & 0x02 != 0 {
+            input.read_exact(&mut [0; 2])?; // the header checksum
+        }
+        Ok(Decoder {
+            input,
+            bits: 0,
+            bit_count: 0,
+            window: vec![0; WINDOW_SIZE].into_boxed_slice(),
+            window_pos: 0,
+            total_out: 0,
+            mode: Mode::BlockStart,
+            is_last_block: false,
+            stored_left: 0,
+            codes: None,
+            copy_len: 0,
+            copy_distance: 0,
+        })
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut byte = [0];
+        while self.bit_count <= 56 {
+            if self.input.read(&mut byte)? == 0 {
+                break;
+            }
+            self.bits |= u64::from(byte[0]) << self.bit_count;
+            self.bit_count += 8;
+        }
+        Ok(())
+    }
+
+    fn read_bits(&mut self, n: u32) -> io::Result<u32> {
+        if self.bit_count < n {
+            self.fill()?;
+            if self.bit_count < n {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        let value = (self.bits & ((1 << n) - 1)) as u32;
+        self.bits >>= n;
+        self.bit_count -= n;
+        Ok(value)
+    }
+
+    fn decode(&mut self, code: &Huffman) -> io::Result<u16> {
+        if self.bit_count < code.max_len {
+            self.fill()?;
+        }
+        let (symbol, len) = code.table[(self.bits & ((1 << code.max_len) - 1)) as usize];
+        if len == 0 || u32::from(len) > self.bit_count {
+            return Err(invalid("Invalid Huffman code"));
+        }
+        self.bits >>= len;
+        self.bit_count -= u32::from(len);
+        Ok(symbol)
+    }
+
+    fn start_block(&mut self) -> io::Result<()> {
+        self.is_last_block = self.read_bits(1)? == 1;
+        match self.read_bits(2)? {
+            0 => {
+                let unaligned = self.bit_count % 8;
+                self.bits >>= unaligned;
+                self.bit_count -= unaligned;
+                let len = self.read_bits(16)? as u16;
+                if self.read_bits(16)? as u16 != !len {
+                    return Err(invalid("Invalid length of stored block"));
+                }
+                self.stored_left = len;
+                self.mode = Mode::Stored;
+            }
+            1 => {
+                self.codes = Some(Huffman::fixed());
+                self.mode = Mode::Codes;
+            }
+            2 => {
+                self.codes = Some(self.dynamic_codes()?);
+                self.mode = Mode::Codes;
+            }
+            _ => return Err(invalid("Invalid block type")),
+        }
+        Ok(())
+    }
+
+    fn dynamic_codes(&mut self) -> io::Result<(Huffman, Huffman)> {
+        let literals = self.read_bits(5)? as usize + 257;
+        let distances = self.read_bits(5)? as usize + 1;
+        let code_lengths = self.read_bits(4)? as usize + 4;
+        let mut lengths = [0; 19];
+        for &idx in &CODE_LENGTH_ORDER[..code_lengths] {
+            lengths[idx] = self.read_bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&lengths)?;
+        let mut lengths = Vec::with_capacity(literals + distances);
+        while lengths.len() < literals + distances {
+            let (len, repeat) = match self.decode(&code_length_code)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 => (
+                    *lengths
+                        .last()
+                        .ok_or_else(|| invalid("Repeated length without a previous one"))?,
+                    3 + self.read_bits(2)?,
+                ),
+                17 => (0, 3 + self.read_bits(3)?),
+                _ => (0, 11 + self.read_bits(7)?),
+            };
+            lengths.extend(std::iter::repeat_n(len, repeat as usize));
+        }
+        if lengths.len() > literals + distances || lengths[END_OF_BLOCK as usize] == 0 {
+            return Err(invalid("Invalid code lengths"));
+        }
+        Ok((
+            Huffman::new(&lengths[..literals])?,
+            Huffman::new(&lengths[literals..])?,
+        ))
+    }
+
+    fn emit(&mut self, byte: u8, buf: &mut [u8], written: &mut usize) {
+        self.window[self.window_pos] = byte;
+        self.window_pos = (self.window_pos + 1) % WINDOW_SIZE;
+        self.total_out += 1;
+        buf[*written] = byte;
+        *written += 1;
+    }
+
+    /// Decode the next symbol of a compressed block, and return true if it's the end of the block.
+    fn next_symbol(&mut self, buf: &mut [u8], written: &mut usize) -> io::Result<bool> {
+        let (literals, distances) = self.codes.take().expect("codes of the current block");
+        let symbol = self.decode(&literals);
+        let res = match symbol? {
+            END_OF_BLOCK => Ok(true),
+            byte @ 0..=255 => {
+                self.emit(byte as u8, buf, written);
+                Ok(false)
+            }
+            symbol => {
+                let idx = (symbol - 257) as usize;
+                if idx >= LENGTH_BASE.len() {
+                    return Err(invalid("Invalid length"));
+                }
+                let len = LENGTH_BASE[idx] as usize
+                    + self.read_bits(LENGTH_EXTRA_BITS[idx].into())? as usize;
+                let idx = self.decode(&distances)? as usize;
+                if idx >= DISTANCE_BASE.len() {
+                    return Err(invalid("Invalid distance"));
+                }
+                let distance = DISTANCE_BASE[idx] as usize
+                    + self.read_bits(DISTANCE_EXTRA_BITS[idx].into())? as usize;
+                if distance as u64 > self.total_out {
+                    return Err(invalid("Distance beyond the start of the stream"));
+                }
+                self.copy_len = len;
+                self.copy_distance = distance;
+                Ok(false)
+            }
+        };
+        self.codes = Some((literals, distances));
+        res
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if self.copy_len > 0 {
+                let byte =
+                    self.window[(self.window_pos + WINDOW_SIZE - self.copy_distance) % WINDOW_SIZE];
+                self.emit(byte, buf, &mut written);
+                self.copy_len -= 1;
+                continue;
+            }
+            match self.mode {
+                Mode::Done => break,
+                Mode::BlockStart if self.is_last_block => self.mode = Mode::Done,
+                Mode::BlockStart => self.start_block()?,
+                Mode::Stored if self.stored_left == 0 => self.mode = Mode::BlockStart,
+                Mode::Stored => {
+                    let byte = self.read_bits(8)? as u8;
+                    self.emit(byte, buf, &mut written);
+                    self.stored_left -= 1;
+                }
+                Mode::Codes => {
+                    if self.next_symbol(buf, &mut written)? {
+                        self.codes = None;
+                        self.mode = Mode::BlockStart;
+                    }
+                }
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    fn gzip(content: &[u8]) -> io::Result<Vec<u8>> {
+        let mut child = Command::new("gzip")
+            .arg("-c")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("piped");
+        let content = content.to_owned();
+        let writer = std::thread::spawn(move || io::Write::write_all(&mut stdin, &content));
+        let output = child.wait_with_output()?;
+        writer.join().expect("no panic")?;
+        Ok(output.stdout)
+    }
+
+    #[test]
+    fn content_compressed_by_gzip_is_restored() -> io::Result<()> {
+        if Command::new("gzip").arg("--version").output().is_err() {
+            return Ok(()); // nothing to compress with
+        }
+        let mut content = Vec::new();
+        for i in 0..100_000u32 {
+            content.extend_from_slice(format!("line {} of {}\n", i % 977, i * 7919).as_bytes());
+        }
+        let mut state = 1u32;
+        let noise: Vec<u8> = (0..50_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        for (content, what) in [
+            (&content[..], "text"),
+            (&noise[..], "noise, which is stored as is"),
+            (&[][..], "nothing"),
+            (&[7; 70_000][..], "a repeated byte"),
+        ] {
+            let mut decompressed = Vec::new();
+            Decoder::new(&gzip(content)?[..])?.read_to_end(&mut decompressed)?;
+            assert!(decompressed == content, "{} is restored", what);
+        }
+        assert!(Decoder::new(&b"not gzip"[..]).is_err());
+        Ok(())
+    }
+}
@@ -29,7 +29,7 @@ impl InodeFilter {
     }
 
     #[cfg(not(any(unix, windows)))]
-    pub fn add(&mut self, metadata: &std::fs::Metadata) -> bool {
+    pub fn add(&mut self, _metadata: &std::fs::Metadata) -> bool {
         true
     }
 
@@ -75,4 +75,21 @@ mod tests {
         assert!(inodes.add_dev_inode((1, 1), 1));
         assert!(inodes.add_dev_inode((1, 1), 1));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_filters_hard_links_of_real_files() -> std::io::Result<()> {
+        let dir = crate::testing::TempDir::new("inodefilter")?;
+        let (file, link) = (dir.join("file"), dir.join("link"));
+        std::fs::write(&file, b"content")?;
+        std::fs::hard_link(&file, &link)?;
+
+        let mut inodes = InodeFilter::default();
+        let first = inodes.add(&file.metadata()?);
+        let second = inodes.add(&link.metadata()?);
+
+        assert!(first, "the first link seen is counted");
+        assert!(!second, "all other links to the same inode are not");
+        Ok(())
+    }
 }
@@ -1,24 +1,58 @@
+use crate::interactive::{widgets::Theme, Columns};
 use dua::{ByteFormat, WalkOptions};
 use std::fmt;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub enum ByteVisualization {
     Percentage,
     Bar,
     LongBar,
+    #[default]
     PercentageAndBar,
 }
 
+/// What the share of entries shown as percentage and bar is of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PercentageBase {
+    /// The total size of the directory shown
+    #[default]
+    Directory,
+    /// The total size of all entries that were scanned
+    Scan,
+    /// The size of the filesystem the directory shown is on
+    Filesystem,
+}
+
+impl PercentageBase {
+    /// Switch from the directory to the scan and the filesystem, and back to the directory.
+    pub fn cycle(&mut self) {
+        use PercentageBase::*;
+        *self = match self {
+            Directory => Scan,
+            Scan => Filesystem,
+            Filesystem => Directory,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            PercentageBase::Directory => "directory",
+            PercentageBase::Scan => "scan",
+            PercentageBase::Filesystem => "filesystem",
+        }
+    }
+}
+
 pub struct DisplayByteVisualization {
     format: ByteVisualization,
     percentage: f32,
+    bar_width: usize,
 }
 
-impl Default for ByteVisualization {
-    fn default() -> Self {
-        ByteVisualization::PercentageAndBar
-    }
-}
+/// The default width of bars, in characters
+pub const DEFAULT_BAR_WIDTH: usize = 10;
+/// The range of widths bars can be configured to have, in characters
+pub const BAR_WIDTHS: std::ops::RangeInclusive<usize> = 2..=40;
 
 impl ByteVisualization {
     pub fn cycle(&mut self) {
@@ -30,10 +64,11 @@ impl ByteVisualization {
             Percentage => Bar,
         }
     }
-    pub fn display(self, percentage: f32) -> DisplayByteVisualization {
+    pub fn display(self, percentage: f32, bar_width: usize) -> DisplayByteVisualization {
         DisplayByteVisualization {
             format: self,
             percentage,
+            bar_width,
         }
     }
 }
@@ -41,23 +76,26 @@ impl ByteVisualization {
 impl fmt::Display for DisplayByteVisualization {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         use ByteVisualization::*;
-        let Self { format, percentage } = self;
+        let Self {
+            format,
+            percentage,
+            bar_width,
+        } = self;
 
         let percentage = if percentage.is_nan() {
             0.0
         } else {
             *percentage
         };
-        const BAR_SIZE: usize = 10;
         match format {
             Percentage => Self::make_percentage(f, percentage),
             PercentageAndBar => {
                 Self::make_percentage(f, percentage)?;
                 f.write_str(" ")?;
-                Self::make_bar(f, percentage, BAR_SIZE)
+                Self::make_bar(f, percentage, *bar_width)
             }
-            Bar => Self::make_bar(f, percentage, BAR_SIZE),
-            LongBar => Self::make_bar(f, percentage, 19),
+            Bar => Self::make_bar(f, percentage, *bar_width),
+            LongBar => Self::make_bar(f, percentage, bar_width * 2 - 1),
         }
     }
 }
@@ -96,7 +134,8 @@ impl DisplayByteVisualization {
         Ok(())
     }
     fn make_percentage(f: &mut fmt::Formatter, percentage: f32) -> Result<(), fmt::Error> {
-        write!(f, " {:>5.01}% ", percentage * 100.0)
+        let percentage = dua::format_number(format!("{:.1}", percentage * 100.0));
+        write!(f, " {:>5}% ", percentage)
     }
 }
 
@@ -105,6 +144,46 @@ impl DisplayByteVisualization {
 pub struct DisplayOptions {
     pub byte_format: ByteFormat,
     pub byte_vis: ByteVisualization,
+    /// The columns shown before the names of entries
+    pub columns: Columns,
+    /// What the share of entries is of
+    pub percentage_base: PercentageBase,
+    /// If set, show the free space of the filesystem shown and how long the scan took in the footer
+    pub show_footer_stats: bool,
+    /// The width of bars in characters, long bars are about twice as wide
+    pub bar_width: usize,
+    /// The colors to draw with
+    pub theme: Theme,
+}
+
+impl DisplayOptions {
+    /// Make bars `delta` characters wider, or narrower if negative, within the bounds of [`BAR_WIDTHS`].
+    pub fn resize_bar(&mut self, delta: isize) {
+        self.bar_width = (self.bar_width as isize + delta)
+            .clamp(*BAR_WIDTHS.start() as isize, *BAR_WIDTHS.end() as isize)
+            as usize;
+    }
+
+    /// Switch to the next unit of sizes, going from metric to binary units, bytes, gigabytes and gibibytes.
+    ///
+    /// Formats which aren't part of the cycle continue with metric units, and amounts of inodes have no other unit.
+    pub fn cycle_byte_format(&mut self) {
+        use ByteFormat::*;
+        self.byte_format = match self.byte_format {
+            Metric => Binary,
+            Binary => Bytes,
+            Bytes => KB,
+            KB => KiB,
+            KiB => MB,
+            MB => MiB,
+            MiB => GB,
+            GB => GiB,
+            GiB => TB,
+            TB => TiB,
+            TiB | Raw => Metric,
+            Inodes => Inodes,
+        }
+    }
 }
 
 impl From<WalkOptions> for DisplayOptions {
@@ -112,6 +191,11 @@ impl From<WalkOptions> for DisplayOptions {
         DisplayOptions {
             byte_format,
             byte_vis: ByteVisualization::default(),
+            columns: Columns::default(),
+            percentage_base: PercentageBase::default(),
+            show_footer_stats: false,
+            bar_width: DEFAULT_BAR_WIDTH,
+            theme: Theme::default(),
         }
     }
 }
@@ -0,0 +1,281 @@
+//! Keeping the last complete scan of some input paths, to show it right away the next time they are browsed.
+//!
+//! Caches are kept in `$XDG_CACHE_HOME/dua` or `~/.cache/dua`, one per set of input paths and options which affect the
+//! entries that are seen. Each starts with [`HEADER`] and the time the scan started at in seconds since the UNIX epoch
+//! on a line of their own, followed by the scan as snapshot.
+//!
+//! Directories changed since then are found by their time of last modification, which changes when entries are
+//! created, removed or renamed in them, but not when files within them are written to.
+use super::session::{fnv1a, hash_of_input_paths};
+use anyhow::{anyhow, Context, Result};
+use dua::{
+    traverse::{Traversal, TreeIndex},
+    WalkOptions,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The first line of every cache file
+const HEADER: &str = "dua-cache 1";
+/// Caches older than this aren't used, as files written to since then would be shown with their old size
+pub const MAX_CACHE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The file the scan of some input paths is cached in
+#[derive(Debug, Clone)]
+pub struct Cache {
+    pub path: PathBuf,
+}
+
+impl Cache {
+    /// Return the cache of `input_paths` walked with `walk_options`, or `None` if there is no directory to keep it in.
+    pub fn of(input_paths: &[PathBuf], walk_options: &WalkOptions) -> Option<Cache> {
+        let dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(Self::in_dir(&dir.join("dua"), input_paths, walk_options))
+    }
+
+    /// Return the cache in `dir`, named after a hash of the canonical `input_paths` and the options which change the
+    /// entries that are seen. Sizes don't matter, as snapshots keep both of them.
+    fn in_dir(dir: &Path, input_paths: &[PathBuf], walk_options: &WalkOptions) -> Cache {
+        let options = format!(
+            "{} {} {:?} {} {:?} {:?} {} {} {:?} {}",
+            walk_options.cross_filesystems,
+            walk_options.count_hard_links,
+            walk_options.gitignore,
+            walk_options.skip_cache_dirs,
+            walk_options.symlinks,
+            walk_options.exclude,
+            walk_options.archives,
+            walk_options.count_inodes,
+            walk_options.max_entries,
+            walk_options.include_virtual
+        );
+        let hash = fnv1a(hash_of_input_paths(input_paths), options.bytes());
+        Cache {
+            path: dir.join(format!("{:016x}.cache", hash)),
+        }
+    }
+
+    /// Read the cached scan along with the time it was made at, or return `None` if there is none younger than
+    /// [`MAX_CACHE_AGE`].
+    pub fn load(&self, walk_options: &WalkOptions) -> Result<Option<(Traversal, SystemTime)>> {
+        let context = || format!("Could not load cached scan '{}'", self.path.display());
+        let content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(anyhow::Error::from(err)).with_context(context),
+        };
+        let (scanned_at, snapshot) = parse_header(&content).with_context(context)?;
+        if SystemTime::now()
+            .duration_since(scanned_at)
+            .is_ok_and(|age| age > MAX_CACHE_AGE)
+        {
+            return Ok(None);
+        }
+        let traversal = dua::snapshot::load(snapshot, walk_options).with_context(context)?;
+        Ok(Some((traversal, scanned_at)))
+    }
+
+    /// Save `traversal`, whose walk started at `scanned_at`, replacing the scan that was cached before.
+    pub fn save(&self, traversal: &Traversal, scanned_at: SystemTime) -> Result<()> {
+        let context = || format!("Could not cache scan in '{}'", self.path.display());
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).with_context(context)?;
+        }
+        // A cache that is only partly written must not replace the previous one
+        let written = self.path.with_extension("cache.partial");
+        let mut out = io::BufWriter::new(fs::File::create(&written).with_context(context)?);
+        io::Write::write_all(
+            &mut out,
+            format!(
+                "{}\n{}\n",
+                HEADER,
+                scanned_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            )
+            .as_bytes(),
+        )
+        .with_context(context)?;
+        dua::snapshot::save(out, traversal).with_context(context)?;
+        fs::rename(&written, &self.path).with_context(context)
+    }
+}
+
+/// Return the time the scan in `content` was made at, along with the snapshot following it.
+fn parse_header(content: &[u8]) -> Result<(SystemTime, &[u8])> {
+    let mut lines = content.splitn(3, |b| *b == b'\n');
+    if lines.next() != Some(HEADER.as_bytes()) {
+        return Err(anyhow!("Not a cache of this version of dua"));
+    }
+    let secs: u64 = lines
+        .next()
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| anyhow!("Invalid time of the scan"))?;
+    let snapshot = lines.next().ok_or_else(|| anyhow!("The scan is missing"))?;
+    Ok((UNIX_EPOCH + Duration::from_secs(secs), snapshot))
+}
+
+/// Directories which changed since a scan was cached
+#[derive(Debug)]
+pub struct StaleDirectories {
+    pub directories: Vec<(TreeIndex, PathBuf)>,
+    /// The time they were looked for at, which the scan is up to date with once they are rescanned
+    pub checked_at: SystemTime,
+}
+
+/// Another walk of a directory which changed since the scan was cached
+#[derive(Debug)]
+pub struct Rescan {
+    pub index: TreeIndex,
+    pub path: PathBuf,
+    pub scan: Result<Traversal, String>,
+}
+
+/// What the thread looking for changed directories or rescanning them tells about them
+#[derive(Debug)]
+pub enum RefreshUpdate {
+    Stale(StaleDirectories),
+    Rescanned(Box<Rescan>),
+}
+
+/// Changed directories being rescanned on another thread
+#[derive(Debug)]
+pub struct Refresh {
+    pub num_directories: usize,
+    pub num_rescanned: usize,
+    /// The rescans which are done but not yet shown, as dialogs referred to entries they might remove
+    pub pending: Vec<Rescan>,
+    /// The reason the last directory that couldn't be rescanned failed for
+    pub error: Option<String>,
+    pub checked_at: SystemTime,
+}
+
+impl Refresh {
+    pub fn new(stale: &StaleDirectories) -> Self {
+        Refresh {
+            num_directories: stale.directories.len(),
+            num_rescanned: 0,
+            pending: Vec::new(),
+            error: None,
+            checked_at: stale.checked_at,
+        }
+    }
+}
+
+/// Return those of `directories`, given parents first, which were modified after `since`, leaving out the ones within
+/// others that were, as rescanning those covers them.
+pub fn find_stale_directories(
+    directories: Vec<(TreeIndex, PathBuf)>,
+    since: SystemTime,
+) -> StaleDirectories {
+    let checked_at = SystemTime::now();
+    let mut stale: Vec<(TreeIndex, PathBuf)> = Vec::new();
+    for (index, path) in directories {
+        if stale.iter().any(|(_, parent)| path.starts_with(parent)) {
+            continue;
+        }
+        let is_changed = fs::symlink_metadata(&path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|mtime| mtime > since);
+        if is_changed {
+            stale.push((index, path));
+        }
+    }
+    StaleDirectories {
+        directories: stale,
+        checked_at,
+    }
+}
+
+/// Walk all `directories` again one after another, and tell `send` about each of them until it returns false.
+pub fn rescan_all(
+    directories: Vec<(TreeIndex, PathBuf)>,
+    walk_options: WalkOptions,
+    mut send: impl FnMut(RefreshUpdate) -> bool,
+) {
+    for (index, path) in directories {
+        let scan = Traversal::from_walk(walk_options.clone(), vec![path.clone()], |_| Ok(false))
+            .map_err(|err| err.to_string());
+        if !send(RefreshUpdate::Rescanned(Box::new(Rescan {
+            index,
+            path,
+            scan,
+        }))) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn walk_options() -> WalkOptions {
+        WalkOptions::default().threads(1).apparent_size(true)
+    }
+
+    #[test]
+    fn scans_are_cached_with_the_time_they_were_made_at() -> Result<()> {
+        let dir = TempDir::new("cache-round-trip")?;
+        let inputs = vec![PathBuf::from("tests/fixtures/sample-02")];
+        let cache = Cache::in_dir(&dir, &inputs, &walk_options());
+        assert!(cache.load(&walk_options())?.is_none());
+
+        let traversal = Traversal::from_walk(walk_options(), inputs.clone(), |_| Ok(false))?;
+        let scanned_at = UNIX_EPOCH
+            + Duration::from_secs(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+        cache.save(&traversal, scanned_at)?;
+        let (loaded, loaded_scanned_at) = cache.load(&walk_options())?.expect("a cached scan");
+        assert_eq!(loaded_scanned_at, scanned_at);
+        assert_eq!(loaded.entries_traversed, traversal.entries_traversed);
+        assert_eq!(loaded.total_bytes, traversal.total_bytes);
+
+        cache.save(&traversal, scanned_at - MAX_CACHE_AGE * 2)?;
+        assert!(cache.load(&walk_options())?.is_none(), "it's too old");
+        fs::remove_file(&cache.path)?;
+
+        let other = Cache::in_dir(
+            &dir,
+            &inputs,
+            &WalkOptions {
+                skip_cache_dirs: true,
+                ..walk_options()
+            },
+        );
+        assert_ne!(cache.path, other.path, "options seeing other entries");
+        let same = Cache::in_dir(
+            &dir,
+            &inputs,
+            &WalkOptions {
+                apparent_size: false,
+                ..walk_options()
+            },
+        );
+        assert_eq!(cache.path, same.path, "both sizes are cached");
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_outermost_changed_directories_are_stale() {
+        let root = PathBuf::from("tests/fixtures/sample-02");
+        let directories = vec![
+            (TreeIndex::new(1), root.clone()),
+            (TreeIndex::new(2), root.join("dir")),
+            (TreeIndex::new(3), root.join("dir").join("sub")),
+        ];
+        let stale = find_stale_directories(directories.clone(), UNIX_EPOCH);
+        assert_eq!(stale.directories, directories[..1]);
+
+        let stale = find_stale_directories(directories, SystemTime::now() + MAX_CACHE_AGE);
+        assert!(stale.directories.is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Copying text to the clipboard, with the tools of the platform or through the terminal.
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// How text ended up in the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Copied {
+    /// By the clipboard tool with the given name, like `pbcopy` or `xclip`
+    Tool(&'static str),
+    /// By an OSC 52 escape sequence, which terminals supporting it put into the clipboard of the machine they run on
+    Terminal,
+}
+
+/// Copy `text` to the clipboard with the first tool of the platform that works, or with an OSC 52 escape sequence
+/// written to `terminal` if there is none.
+///
+/// In SSH sessions the escape sequence is always used, as the tools would only reach the clipboard of the remote
+/// machine, if any.
+pub fn copy(text: &str, mut terminal: impl io::Write) -> io::Result<Copied> {
+    let is_remote = ["SSH_CONNECTION", "SSH_TTY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some());
+    if !is_remote {
+        for (program, args) in tools() {
+            if copy_with(program, args, text).is_ok() {
+                return Ok(Copied::Tool(program));
+            }
+        }
+    }
+    terminal.write_all(osc52(text).as_bytes())?;
+    terminal.flush()?;
+    Ok(Copied::Terminal)
+}
+
+/// The programs which copy their standard input to the clipboard along with their arguments, in order of preference.
+fn tools() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", &[])]
+    } else if cfg!(windows) {
+        vec![("clip", &[])]
+    } else {
+        let mut tools: Vec<(_, &[_])> = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            tools.push(("wl-copy", &[]));
+        }
+        if std::env::var_os("DISPLAY").is_some() {
+            tools.push(("xclip", &["-selection", "clipboard"]));
+            tools.push(("xsel", &["--clipboard", "--input"]));
+        }
+        tools
+    }
+}
+
+fn copy_with(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} failed with {}",
+            program, status
+        )))
+    }
+}
+
+/// The escape sequence which asks the terminal to put `text` into the clipboard.
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_sent_to_the_terminal_base64_encoded() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("/tmp/ä b".as_bytes()), "L3RtcC/DpCBi");
+        assert_eq!(osc52("foo"), "\x1b]52;c;Zm9v\x07");
+    }
+}
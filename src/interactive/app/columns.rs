@@ -0,0 +1,218 @@
+//! The columns shown before the names of entries, in the order and with the widths the configuration gives them.
+use std::fmt;
+
+/// A column of the entries pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// The size entries are sorted by
+    Size,
+    /// The size that isn't sorted by, like disk usage next to the apparent size
+    OtherSize,
+    /// The amount of entries within directories
+    Count,
+    /// How long ago entries were last modified
+    MTime,
+    /// The share of the total size as percentage, bar or both, as cycled through with the byte visualization
+    Bar,
+}
+
+impl Column {
+    const ALL: [Column; 5] = [
+        Column::Size,
+        Column::OtherSize,
+        Column::Count,
+        Column::MTime,
+        Column::Bar,
+    ];
+
+    /// The name of the column in the configuration file
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Size => "size",
+            Column::OtherSize => "other-size",
+            Column::Count => "count",
+            Column::MTime => "mtime",
+            Column::Bar => "bar",
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A column along with whether it is shown and its width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub column: Column,
+    pub shown: bool,
+    /// The width in characters, or `None` to use the width that fits the values of the column
+    pub width: Option<usize>,
+}
+
+/// All columns in the order they are drawn in, followed by the names of entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns([ColumnSpec; 5]);
+
+impl Default for Columns {
+    /// The size and the bar, with the other columns hidden in between them
+    fn default() -> Self {
+        Columns(Column::ALL.map(|column| ColumnSpec {
+            column,
+            shown: matches!(column, Column::Size | Column::Bar),
+            width: None,
+        }))
+    }
+}
+
+impl Columns {
+    /// Parse the columns of the configuration file, given in the order to draw them in, like `["mtime", "size:12",
+    /// "bar", "name"]`. A width may follow the name of a column after a `:`. Columns that aren't given are hidden until
+    /// they are toggled on, and are drawn after the others. `name` is always drawn last.
+    pub fn from_names(names: &[&str]) -> Result<Columns, String> {
+        let mut listed: Vec<ColumnSpec> = Vec::new();
+        for (pos, spec) in names.iter().enumerate() {
+            let (name, width) = match spec.split_once(':') {
+                Some((name, width)) => (
+                    name,
+                    Some(
+                        width
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|w| *w > 0)
+                            .ok_or_else(|| format!("Invalid width of column {:?}", spec))?,
+                    ),
+                ),
+                None => (*spec, None),
+            };
+            if name == "name" {
+                if width.is_some() {
+                    return Err("The name column takes the rest of the line".into());
+                }
+                if pos + 1 != names.len() {
+                    return Err("The name column must be the last one".into());
+                }
+                continue;
+            }
+            let column = Column::ALL
+                .iter()
+                .copied()
+                .find(|column| column.name() == name)
+                .ok_or_else(|| {
+                    format!(
+                        "{:?} is not one of size, other-size, count, mtime, bar or name",
+                        name
+                    )
+                })?;
+            if listed.iter().any(|spec| spec.column == column) {
+                return Err(format!("The {} column is given more than once", column));
+            }
+            listed.push(ColumnSpec {
+                column,
+                shown: true,
+                width,
+            });
+        }
+        let hidden = Column::ALL
+            .iter()
+            .filter(|column| listed.iter().all(|spec| spec.column != **column))
+            .map(|column| ColumnSpec {
+                column: *column,
+                shown: false,
+                width: None,
+            });
+        listed.extend(hidden.collect::<Vec<_>>());
+        let mut columns = Columns::default();
+        columns.0.copy_from_slice(&listed);
+        Ok(columns)
+    }
+
+    /// The columns in the order they are drawn in, including hidden ones.
+    pub fn iter(&self) -> impl Iterator<Item = &ColumnSpec> {
+        self.0.iter()
+    }
+
+    pub fn is_shown(&self, column: Column) -> bool {
+        self.spec(column).shown
+    }
+
+    /// Show `column` if it is hidden, or hide it.
+    pub fn toggle(&mut self, column: Column) {
+        let spec = self.spec_mut(column);
+        spec.shown = !spec.shown;
+    }
+
+    /// The width configured for `column`, if any.
+    pub fn width(&self, column: Column) -> Option<usize> {
+        self.spec(column).width
+    }
+
+    fn spec(&self, column: Column) -> &ColumnSpec {
+        self.0
+            .iter()
+            .find(|spec| spec.column == column)
+            .expect("all columns to be present")
+    }
+
+    fn spec_mut(&mut self, column: Column) -> &mut ColumnSpec {
+        self.0
+            .iter_mut()
+            .find(|spec| spec.column == column)
+            .expect("all columns to be present")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_configured_in_order_with_optional_widths() {
+        let columns = Columns::from_names(&["mtime", "size:12", "bar", "name"]).unwrap();
+        assert_eq!(
+            columns
+                .iter()
+                .map(|spec| (spec.column.name(), spec.shown, spec.width))
+                .collect::<Vec<_>>(),
+            vec![
+                ("mtime", true, None),
+                ("size", true, Some(12)),
+                ("bar", true, None),
+                ("other-size", false, None),
+                ("count", false, None),
+            ]
+        );
+        assert_eq!(
+            Columns::from_names(&[])
+                .unwrap()
+                .iter()
+                .filter(|s| s.shown)
+                .count(),
+            0
+        );
+
+        for (names, expected) in [
+            (
+                &["age"][..],
+                "\"age\" is not one of size, other-size, count, mtime, bar or name",
+            ),
+            (&["size", "size"], "The size column is given more than once"),
+            (&["name", "size"], "The name column must be the last one"),
+            (&["name:20"], "The name column takes the rest of the line"),
+            (&["size:0"], "Invalid width of column \"size:0\""),
+        ] {
+            assert_eq!(Columns::from_names(names).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn columns_can_be_toggled() {
+        let mut columns = Columns::default();
+        assert!(columns.is_shown(Column::Size) && !columns.is_shown(Column::Count));
+        columns.toggle(Column::Count);
+        columns.toggle(Column::Size);
+        assert!(!columns.is_shown(Column::Size) && columns.is_shown(Column::Count));
+    }
+}
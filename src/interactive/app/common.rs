@@ -1,31 +1,267 @@
 use crate::interactive::path_of;
 use dua::traverse::{EntryData, Tree, TreeIndex};
 use itertools::Itertools;
-use petgraph::Direction;
+use petgraph::{visit::Dfs, Direction};
+use std::{cmp::Ordering, path::Path, str::FromStr};
 use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Default)]
 pub enum SortMode {
+    #[default]
     SizeDescending,
     SizeAscending,
+    CountDescending,
+    CountAscending,
+    NameAscending,
+    NameDescending,
+    MTimeDescending,
+    MTimeAscending,
 }
 
 impl SortMode {
     pub fn toggle_size(&mut self) {
         use SortMode::*;
         *self = match self {
-            SizeAscending => SizeDescending,
             SizeDescending => SizeAscending,
+            _ => SizeDescending,
+        }
+    }
+    pub fn toggle_count(&mut self) {
+        use SortMode::*;
+        *self = match self {
+            CountDescending => CountAscending,
+            _ => CountDescending,
+        }
+    }
+    pub fn toggle_name(&mut self) {
+        use SortMode::*;
+        *self = match self {
+            NameAscending => NameDescending,
+            _ => NameAscending,
+        }
+    }
+    pub fn toggle_mtime(&mut self) {
+        use SortMode::*;
+        *self = match self {
+            MTimeDescending => MTimeAscending,
+            _ => MTimeDescending,
+        }
+    }
+    /// The name of the sorting, which [`FromStr`] turns back into it.
+    pub fn name(self) -> &'static str {
+        use SortMode::*;
+        match self {
+            SizeDescending => "size-descending",
+            SizeAscending => "size-ascending",
+            CountDescending => "count-descending",
+            CountAscending => "count-ascending",
+            NameAscending => "name-ascending",
+            NameDescending => "name-descending",
+            MTimeDescending => "mtime-descending",
+            MTimeAscending => "mtime-ascending",
+        }
+    }
+    pub fn description(&self) -> &'static str {
+        use SortMode::*;
+        match self {
+            SizeDescending => "by size, descending",
+            SizeAscending => "by size, ascending",
+            CountDescending => "by entry count, descending",
+            CountAscending => "by entry count, ascending",
+            NameAscending => "by name, ascending",
+            NameDescending => "by name, descending",
+            MTimeDescending => "by modification time, newest first",
+            MTimeAscending => "by modification time, oldest first",
         }
     }
 }
 
-impl Default for SortMode {
-    fn default() -> Self {
-        SortMode::SizeDescending
+impl FromStr for SortMode {
+    type Err = String;
+
+    /// Parse the name of a sorting as used in the configuration file, like `size` or `name-descending`, where the
+    /// direction defaults to the one its key in interactive mode starts with.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use SortMode::*;
+        Ok(match s {
+            "size" | "size-descending" => SizeDescending,
+            "size-ascending" => SizeAscending,
+            "count" | "count-descending" => CountDescending,
+            "count-ascending" => CountAscending,
+            "name" | "name-ascending" => NameAscending,
+            "name-descending" => NameDescending,
+            "mtime" | "mtime-descending" => MTimeDescending,
+            "mtime-ascending" => MTimeAscending,
+            _ => {
+                return Err(format!(
+                    "{:?} is not one of size, count, name or mtime, optionally followed by -ascending or -descending",
+                    s
+                ))
+            }
+        })
     }
 }
 
+/// A filter for the entries of the current directory by a part of their name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    /// If set, keys are used to edit the query
+    pub is_editing: bool,
+}
+
+impl Search {
+    /// Return true if `name` contains the query, ignoring case unless the query contains uppercase characters.
+    pub fn matches(&self, name: &str) -> bool {
+        if self.query.chars().any(char::is_uppercase) {
+            name.contains(&self.query)
+        } else {
+            name.to_lowercase().contains(&self.query.to_lowercase())
+        }
+    }
+}
+
+/// A glob pattern being typed to mark all entries matching it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarkPattern {
+    pub query: String,
+    /// If set, entries within all directories below the current one are marked too
+    pub recursive: bool,
+}
+
+/// Which entries of a directory are shown. Entries that aren't still count towards the size of their directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryFilter {
+    /// If set, entries whose name starts with a dot aren't shown
+    pub hide_hidden: bool,
+    /// Entries smaller than this many bytes aren't shown
+    pub min_size: u64,
+    /// The kind of entries shown
+    pub kind: EntryKind,
+}
+
+/// Which kinds of entries are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EntryKind {
+    #[default]
+    All,
+    FilesOnly,
+    DirectoriesOnly,
+    /// Empty directories and files without content, along with the directories containing them
+    Empty,
+    /// Symbolic links whose target doesn't exist, along with the directories containing them
+    BrokenLinks,
+}
+
+impl EntryKind {
+    pub fn cycle(&mut self) {
+        *self = match self {
+            EntryKind::All => EntryKind::FilesOnly,
+            EntryKind::FilesOnly => EntryKind::DirectoriesOnly,
+            EntryKind::DirectoriesOnly => EntryKind::Empty,
+            EntryKind::Empty => EntryKind::BrokenLinks,
+            EntryKind::BrokenLinks => EntryKind::All,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            EntryKind::All => "files and directories",
+            EntryKind::FilesOnly => "files only",
+            EntryKind::DirectoriesOnly => "directories only",
+            EntryKind::Empty => "empty entries",
+            EntryKind::BrokenLinks => "broken links",
+        }
+    }
+
+    fn matches(self, tree: &Tree, index: TreeIndex, entry: &EntryData) -> bool {
+        match self {
+            EntryKind::All => true,
+            EntryKind::FilesOnly => !entry.is_dir,
+            EntryKind::DirectoriesOnly => entry.is_dir,
+            EntryKind::Empty => is_or_contains(tree, index, entry, is_empty),
+            EntryKind::BrokenLinks => {
+                is_or_contains(tree, index, entry, |entry| entry.is_broken_link)
+            }
+        }
+    }
+}
+
+/// The minimum sizes of entries cycled through, along with their label
+pub const MIN_SIZES: [(u64, &str); 4] = [
+    (0, "all"),
+    (1_000_000, "1 MB"),
+    (100_000_000, "100 MB"),
+    (1_000_000_000, "1 GB"),
+];
+
+impl EntryFilter {
+    /// Return true if `entry` at `index` of `tree` is to be shown.
+    pub fn matches(&self, tree: &Tree, index: TreeIndex, entry: &EntryData) -> bool {
+        !(self.hide_hidden && is_hidden(&tree.names[entry.name]))
+            && entry.size >= self.min_size
+            && self.kind.matches(tree, index, entry)
+    }
+
+    /// Show only entries of the next larger minimum size, or all of them after the largest one.
+    pub fn cycle_min_size(&mut self) {
+        let pos = MIN_SIZES
+            .iter()
+            .position(|(size, _)| *size == self.min_size)
+            .unwrap_or(0);
+        self.min_size = MIN_SIZES[(pos + 1) % MIN_SIZES.len()].0;
+    }
+
+    /// The label of the minimum size, like `1 MB`, or `all` if there is none.
+    pub fn min_size_label(&self) -> &'static str {
+        MIN_SIZES
+            .iter()
+            .find(|(size, _)| *size == self.min_size)
+            .map_or("custom", |(_, label)| label)
+    }
+}
+
+/// Return the amount of entries within `node_idx` that `filter` doesn't show, along with their total size.
+pub fn filtered_out(tree: &Tree, node_idx: TreeIndex, filter: EntryFilter) -> (usize, u128) {
+    tree.neighbors_directed(node_idx, Direction::Outgoing)
+        .filter_map(|idx| tree.node_weight(idx).map(|entry| (idx, entry)))
+        .filter(|(idx, entry)| !filter.matches(tree, *idx, entry))
+        .fold((0, 0), |(count, size), (_, entry)| {
+            (count + 1, size + u128::from(entry.size))
+        })
+}
+
+/// Return true if `entry` at `index` of `tree` matches `predicate`, or is a directory with an entry within it that
+/// does.
+fn is_or_contains(
+    tree: &Tree,
+    index: TreeIndex,
+    entry: &EntryData,
+    predicate: impl Fn(&EntryData) -> bool,
+) -> bool {
+    let graph = &**tree;
+    let mut within = Dfs::new(graph, index);
+    predicate(entry)
+        || (entry.is_dir
+            && std::iter::from_fn(|| within.next(graph))
+                .any(|idx| tree.node_weight(idx).is_some_and(&predicate)))
+}
+
+/// Return true if `entry` is a directory without any entries that were seen, or a file without content.
+fn is_empty(entry: &EntryData) -> bool {
+    if entry.is_dir {
+        entry.entry_count == 0
+    } else {
+        !entry.is_symlink && entry.size == 0 && entry.alternate_size == 0
+    }
+}
+
+/// Return true if the last component of `name` starts with a dot, like `.git`. `.` and `..` are never hidden.
+fn is_hidden(name: &Path) -> bool {
+    name.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
 pub struct EntryDataBundle {
     pub index: TreeIndex,
     pub data: EntryData,
@@ -33,28 +269,82 @@ pub struct EntryDataBundle {
     pub exists: bool,
 }
 
-pub fn sorted_entries(tree: &Tree, node_idx: TreeIndex, sorting: SortMode) -> Vec<EntryDataBundle> {
+pub fn sorted_entries(
+    tree: &Tree,
+    node_idx: TreeIndex,
+    sorting: SortMode,
+    filter: EntryFilter,
+) -> Vec<EntryDataBundle> {
     use SortMode::*;
     tree.neighbors_directed(node_idx, Direction::Outgoing)
         .filter_map(|idx| {
-            tree.node_weight(idx).map(|w| {
-                let p = path_of(tree, idx);
-                let pm = p.symlink_metadata();
-                EntryDataBundle {
-                    index: idx,
-                    data: w.clone(),
-                    exists: pm.is_ok(),
-                    is_dir: pm.ok().map_or(false, |m| m.is_dir()),
-                }
-            })
+            tree.node_weight(idx)
+                .filter(|w| filter.matches(tree, idx, w))
+                .map(|w| {
+                    let p = path_of(tree, idx);
+                    let pm = p.symlink_metadata();
+                    EntryDataBundle {
+                        index: idx,
+                        data: w.clone(),
+                        exists: w.is_in_archive || w.is_collapsed || pm.is_ok(),
+                        is_dir: w.is_dir,
+                    }
+                })
         })
         .sorted_by(|l, r| match sorting {
             SizeDescending => r.data.size.cmp(&l.data.size),
             SizeAscending => l.data.size.cmp(&r.data.size),
+            CountDescending => r.data.entry_count.cmp(&l.data.entry_count),
+            CountAscending => l.data.entry_count.cmp(&r.data.entry_count),
+            NameAscending => natural_cmp(&tree.names[l.data.name], &tree.names[r.data.name]),
+            NameDescending => natural_cmp(&tree.names[r.data.name], &tree.names[l.data.name]),
+            MTimeDescending => r.data.mtime.cmp(&l.data.mtime),
+            MTimeAscending => l.data.mtime.cmp(&r.data.mtime),
         })
         .collect()
 }
 
+/// Compare names like humans do, ignoring case and with numbers in them compared by value, so `file2` comes
+/// before `file10`.
+pub fn natural_cmp(l: &Path, r: &Path) -> Ordering {
+    let (l, r) = (l.to_string_lossy(), r.to_string_lossy());
+    let (mut lc, mut rc) = (l.chars().peekable(), r.chars().peekable());
+    loop {
+        match (lc.peek().copied(), rc.peek().copied()) {
+            (None, None) => return l.cmp(&r),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) if a.is_ascii_digit() && b.is_ascii_digit() => {
+                let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let (a, b) = (number(&mut lc), number(&mut rc));
+                let (a_trimmed, b_trimmed) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+                match a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed))
+                {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                }
+            }
+            (Some(a), Some(b)) => {
+                match a.to_lowercase().cmp(b.to_lowercase()) {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                }
+                lc.next();
+                rc.next();
+            }
+        }
+    }
+}
+
 pub fn fit_string_graphemes_with_ellipsis(
     s: impl Into<String>,
     path_graphemes_count: usize,
@@ -91,6 +381,43 @@ pub fn fit_string_graphemes_with_ellipsis(
 mod tests {
     use super::*;
 
+    #[test]
+    fn natural_cmp_orders_numbers_by_value_and_ignores_case() {
+        let mut names: Vec<_> = ["file10", "File2", "file1", "a", "file02b", "file2a", "B"]
+            .iter()
+            .map(Path::new)
+            .collect();
+        names.sort_by(|l, r| natural_cmp(l, r));
+        assert_eq!(
+            names,
+            ["a", "B", "file1", "File2", "file2a", "file02b", "file10"]
+                .iter()
+                .map(Path::new)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sort_modes_are_parsed_with_the_direction_of_their_key_by_default() {
+        assert_eq!("size".parse(), Ok(SortMode::SizeDescending));
+        assert_eq!("name".parse(), Ok(SortMode::NameAscending));
+        assert_eq!("mtime-ascending".parse(), Ok(SortMode::MTimeAscending));
+        assert!("largest".parse::<SortMode>().is_err());
+    }
+
+    #[test]
+    fn search_is_case_sensitive_only_with_uppercase_characters() {
+        let search = |query: &str| Search {
+            query: query.into(),
+            is_editing: false,
+        };
+        assert!(search("readme").matches("README.md"));
+        assert!(search("Read").matches("Readme"));
+        assert!(!search("Read").matches("readme"));
+        assert!(search("").matches("anything"));
+        assert!(!search("x").matches("readme"));
+    }
+
     #[test]
     fn fit_string_inputs() {
         assert_eq!(
@@ -0,0 +1,485 @@
+//! Deleting entries on another thread, to keep the interface responsive while large directories are deleted and to
+//! allow aborting it.
+use crate::{
+    interactive::{app::trash, DeletionMode},
+    path::absolute,
+};
+use dua::traverse::TreeIndex;
+use filesize::PathExt;
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// How long to wait before telling about the progress again, to not slow down the deletion with redraws.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An entry to delete
+#[derive(Debug, Clone)]
+pub struct DeletionItem {
+    pub index: TreeIndex,
+    pub path: PathBuf,
+    /// The amount of entries it contains, including itself
+    pub num_entries: u64,
+    /// Its size as shown, to tell how much was freed when moving it to the trash
+    pub size: u128,
+}
+
+/// How far a deletion got
+#[derive(Debug, Clone, Default)]
+pub struct DeletionProgress {
+    pub entries_removed: u64,
+    pub bytes_freed: u128,
+    /// The path that was removed last
+    pub path: PathBuf,
+}
+
+/// What the thread deleting entries tells about them
+#[derive(Debug, Clone)]
+pub enum DeletionUpdate {
+    Progress(DeletionProgress),
+    /// The entry at `index` was dealt with, and might still exist in part if there were errors or the deletion was
+    /// aborted. `message` tells why it couldn't be deleted, if it's set.
+    Finished {
+        index: TreeIndex,
+        num_errors: usize,
+        message: Option<String>,
+    },
+    /// All entries were dealt with, or the deletion was aborted
+    Done {
+        aborted: bool,
+    },
+}
+
+/// A deletion in progress, shown on top of all other panes until it's done.
+pub struct Deletion {
+    pub mode: DeletionMode,
+    pub progress: DeletionProgress,
+    /// The amount of entries to remove, to tell how far the deletion got
+    pub total_entries: u64,
+    pub started: Instant,
+    /// The amount of entries removed from the tree so far
+    pub entries_deleted: usize,
+    /// The reason the last entry that couldn't be deleted failed for
+    pub error: Option<String>,
+    abort: Arc<AtomicBool>,
+}
+
+impl Deletion {
+    pub fn new(mode: DeletionMode, items: &[DeletionItem]) -> Self {
+        Deletion {
+            mode,
+            progress: DeletionProgress::default(),
+            total_entries: items.iter().map(|item| item.num_entries).sum(),
+            started: Instant::now(),
+            entries_deleted: 0,
+            error: None,
+            abort: Default::default(),
+        }
+    }
+
+    /// Ask the thread deleting entries to stop as soon as possible.
+    pub fn abort(&self) {
+        self.abort.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_aborting(&self) -> bool {
+        self.abort.load(Ordering::Relaxed)
+    }
+
+    /// A handle to [`abort()`][Deletion::abort()] to pass to [`delete_entries()`].
+    pub fn abort_flag(&self) -> Arc<AtomicBool> {
+        self.abort.clone()
+    }
+}
+
+/// Delete all `items` one after another in the given `mode`, and tell `send` how far it got until it returns false.
+///
+/// Bytes freed are counted as `apparent_size` or as disk usage, to match the sizes that are shown. Once `abort` is
+/// set, no more entries are removed.
+pub fn delete_entries(
+    items: Vec<DeletionItem>,
+    mode: DeletionMode,
+    apparent_size: bool,
+    abort: &AtomicBool,
+    mut send: impl FnMut(DeletionUpdate) -> bool,
+) {
+    let mut progress = DeletionProgress::default();
+    let mut sent_at = Instant::now();
+    for item in items {
+        if abort.load(Ordering::Relaxed) {
+            break;
+        }
+        let res = match mode {
+            DeletionMode::Permanently => {
+                let mut is_disconnected = false;
+                let res = delete_directory_recursively(
+                    item.path.clone(),
+                    apparent_size,
+                    abort,
+                    |path, bytes| {
+                        progress.entries_removed += 1;
+                        progress.bytes_freed += bytes as u128;
+                        if sent_at.elapsed() >= PROGRESS_INTERVAL {
+                            sent_at = Instant::now();
+                            progress.path = path.to_owned();
+                            is_disconnected |= !send(DeletionUpdate::Progress(progress.clone()));
+                        }
+                    },
+                );
+                if is_disconnected {
+                    return;
+                }
+                res.map_err(|num_errors| {
+                    (
+                        num_errors,
+                        format!(
+                            "Could not delete '{}': {} error{}",
+                            item.path.display(),
+                            num_errors,
+                            if num_errors > 1 { "s" } else { "" }
+                        ),
+                    )
+                })
+            }
+            DeletionMode::ToTrash => trash::move_to_trash(&item.path)
+                .map(|()| {
+                    progress.entries_removed += item.num_entries;
+                    progress.bytes_freed += item.size;
+                })
+                .map_err(|err| {
+                    (
+                        1,
+                        format!(
+                            "Could not move '{}' to the trash: {}",
+                            item.path.display(),
+                            err
+                        ),
+                    )
+                }),
+        };
+        progress.path = item.path;
+        let (num_errors, message) = match res {
+            Ok(()) => (0, None),
+            Err((num_errors, message)) => (num_errors, Some(message)),
+        };
+        if !send(DeletionUpdate::Progress(progress.clone()))
+            || !send(DeletionUpdate::Finished {
+                index: item.index,
+                num_errors,
+                message,
+            })
+        {
+            return;
+        }
+    }
+    send(DeletionUpdate::Done {
+        aborted: abort.load(Ordering::Relaxed),
+    });
+}
+
+/// Tell what `path` is if it must not be deleted without typing it: an input path as indicated by `is_input`, the root
+/// directory, the home directory or a mount point.
+pub fn protection_of(path: &Path, is_input: bool) -> Option<&'static str> {
+    if is_input {
+        return Some("an input path");
+    }
+    // Symlinks are deleted themselves, and must not be taken for what they point to
+    let path = absolute(path).unwrap_or_else(|_| path.to_owned());
+    let parent = match path.parent() {
+        Some(parent) => parent,
+        None => return Some("the root directory"),
+    };
+    let home = std::env::var_os("HOME").and_then(|home| Path::new(&home).canonicalize().ok());
+    if home.as_deref() == Some(path.as_path()) {
+        return Some("the home directory");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let (Ok(meta), Ok(parent_meta)) = (path.symlink_metadata(), parent.metadata()) {
+            if meta.is_dir() && meta.dev() != parent_meta.dev() {
+                return Some("a mount point");
+            }
+        }
+    }
+    None
+}
+
+/// Directories deeper than this below the entry to delete are moved right into it before their entries are deleted,
+/// to keep paths shorter than what the operating system allows for, with a name to spare.
+const MAX_PATH_LEN: usize = 512;
+
+/// A directory whose entries are being deleted
+struct OpenDir {
+    /// The names of the entries still to delete
+    names: Vec<OsString>,
+    metadata: Option<fs::Metadata>,
+    /// The path of the directory containing it, if it was moved away from there
+    moved_from: Option<PathBuf>,
+}
+
+// TODO: could use jwalk for this
+// see https://github.com/Byron/dua-cli/issues/43
+/// Remove `path` and everything in it, calling `on_removed` with each removed path and the bytes it freed, until
+/// `abort` is set. Return the amount of entries which couldn't be removed.
+///
+/// Directories being emptied are kept on a stack of their own, and are moved up into `path` once their own path gets
+/// too long, so trees of any depth can be removed. Aborting may leave such directories behind.
+fn delete_directory_recursively(
+    path: PathBuf,
+    apparent_size: bool,
+    abort: &AtomicBool,
+    mut on_removed: impl FnMut(&Path, u64),
+) -> Result<(), usize> {
+    let size_of = |path: &Path, m: &fs::Metadata| {
+        if apparent_size {
+            m.len()
+        } else {
+            path.size_on_disk_fast(m).unwrap_or_default()
+        }
+    };
+    let root = path.clone();
+    let mut path = path;
+    let mut open: Vec<OpenDir> = Vec::new();
+    let mut num_moved = 0;
+    let mut num_errors = 0;
+    loop {
+        if abort.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let metadata = path.symlink_metadata().ok();
+        let assume_file_to_try_deletion = false;
+        let is_dir = metadata
+            .as_ref()
+            .map(|m| m.is_dir())
+            .unwrap_or(assume_file_to_try_deletion);
+        if is_dir {
+            let moved_from = if !open.is_empty() && path.as_os_str().len() > MAX_PATH_LEN {
+                let moved = loop {
+                    num_moved += 1;
+                    let moved = root.join(format!(".dua-deleting-{}", num_moved));
+                    if moved.symlink_metadata().is_err() {
+                        break moved;
+                    }
+                };
+                match fs::rename(&path, &moved) {
+                    Ok(()) => Some(std::mem::replace(&mut path, moved)),
+                    Err(_) => None,
+                }
+            } else {
+                None
+            };
+            match fs::read_dir(&path) {
+                Ok(iterator) => {
+                    let mut names = Vec::new();
+                    for entry in iterator {
+                        match entry.map_err(io_err_to_usize) {
+                            Ok(entry) => names.push(entry.file_name()),
+                            Err(c) => num_errors += c,
+                        }
+                    }
+                    open.push(OpenDir {
+                        names,
+                        metadata,
+                        moved_from,
+                    });
+                }
+                Err(_) => {
+                    num_errors += 1;
+                    leave(&mut path, moved_from);
+                }
+            }
+        } else {
+            // files and symlinks alike, the latter are never followed. On Windows, symlinks to directories and
+            // junctions are directories themselves, and can only be removed as such.
+            let is_symlink = metadata
+                .as_ref()
+                .is_some_and(|m| m.file_type().is_symlink());
+            match fs::remove_file(&path).or_else(|err| {
+                if is_symlink {
+                    fs::remove_dir(&path).map_err(|_| err)
+                } else {
+                    Err(err)
+                }
+            }) {
+                Ok(()) => on_removed(
+                    &path,
+                    metadata.map(|m| size_of(&path, &m)).unwrap_or_default(),
+                ),
+                Err(err) => num_errors += io_err_to_usize(err),
+            }
+            if open.is_empty() {
+                break;
+            }
+            path.pop();
+        }
+
+        // Remove all directories which are empty now, up to the one with entries left to delete
+        while let Some(dir) = open.last_mut() {
+            if let Some(name) = dir.names.pop() {
+                path.push(name);
+                break;
+            }
+            let dir = open.pop().expect("the directory we just looked at");
+            if abort.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            match fs::remove_dir(&path).or_else(|_| fs::remove_file(&path)) {
+                Ok(()) => on_removed(
+                    &path,
+                    dir.metadata.map(|m| size_of(&path, &m)).unwrap_or_default(),
+                ),
+                Err(err) => num_errors += io_err_to_usize(err),
+            }
+            if open.is_empty() {
+                break;
+            }
+            leave(&mut path, dir.moved_from);
+        }
+        if open.is_empty() {
+            break;
+        }
+    }
+
+    if num_errors == 0 {
+        Ok(())
+    } else {
+        Err(num_errors)
+    }
+}
+
+/// Make `path` the path of the directory containing it, which it was moved away from if `moved_from` is set.
+fn leave(path: &mut PathBuf, moved_from: Option<PathBuf>) {
+    if let Some(moved_from) = moved_from {
+        *path = moved_from;
+    }
+    path.pop();
+}
+
+fn io_err_to_usize(err: io::Error) -> usize {
+    if err.kind() == io::ErrorKind::NotFound {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn input_paths_the_root_and_the_home_directory_are_protected() {
+        let fixture = Path::new("tests/fixtures/sample-02/dir");
+        assert_eq!(protection_of(fixture, true), Some("an input path"));
+        assert_eq!(protection_of(fixture, false), None);
+        assert_eq!(
+            protection_of(Path::new("/"), false),
+            Some("the root directory")
+        );
+        if let Some(home) = std::env::var_os("HOME").filter(|home| Path::new(home).is_dir()) {
+            assert_eq!(
+                protection_of(Path::new(&home), false),
+                Some("the home directory")
+            );
+        }
+    }
+
+    #[test]
+    fn nothing_is_deleted_once_aborted() {
+        let dir = TempDir::new("deletion-abort").unwrap();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("file"), b"content").unwrap();
+        let item = |index| DeletionItem {
+            index: TreeIndex::new(index),
+            path: dir.to_path_buf(),
+            num_entries: 3,
+            size: 7,
+        };
+
+        let mut updates = Vec::new();
+        delete_entries(
+            vec![item(1)],
+            DeletionMode::Permanently,
+            true,
+            &AtomicBool::new(true),
+            |update| {
+                updates.push(update);
+                true
+            },
+        );
+        assert!(matches!(
+            updates.as_slice(),
+            [DeletionUpdate::Done { aborted: true }]
+        ));
+        assert!(dir.join("sub").join("file").is_file());
+
+        updates.clear();
+        delete_entries(
+            vec![item(1), item(2)],
+            DeletionMode::Permanently,
+            true,
+            &AtomicBool::new(false),
+            |update| {
+                updates.push(update);
+                true
+            },
+        );
+        assert!(!dir.exists());
+        match updates.as_slice() {
+            [DeletionUpdate::Progress(progress), DeletionUpdate::Finished {
+                num_errors: 0,
+                message: None,
+                ..
+            }, DeletionUpdate::Progress(_), DeletionUpdate::Finished {
+                num_errors: 0,
+                message: None,
+                ..
+            }, DeletionUpdate::Done { aborted: false }] => {
+                assert_eq!(progress.entries_removed, 3);
+                assert!(progress.bytes_freed >= 7, "directories may take space too");
+            }
+            updates => panic!("unexpected updates: {:?}", updates),
+        }
+    }
+
+    #[test]
+    fn trees_of_any_depth_are_deleted() -> std::io::Result<()> {
+        const DEPTH: usize = 100_000;
+        const CHAIN: usize = 200;
+        let dir = TempDir::new("deletion-depth")?;
+        // Paths can't be that long, so the tree is built from the bottom up, by moving it into a new chain of
+        // directories over and over
+        let chain = |name: &str| -> std::io::Result<PathBuf> {
+            let mut path = dir.join(name);
+            path.extend(std::iter::repeat_n("d", CHAIN - 1));
+            fs::create_dir_all(&path)?;
+            Ok(path)
+        };
+        fs::write(chain("tree")?.join("file"), b"content")?;
+        for _ in 1..DEPTH / CHAIN {
+            fs::rename(dir.join("tree"), chain("next")?.join("d"))?;
+            fs::rename(dir.join("next"), dir.join("tree"))?;
+        }
+
+        let mut num_removed = 0;
+        let res = delete_directory_recursively(
+            dir.join("tree"),
+            true,
+            &AtomicBool::new(false),
+            |_, _| num_removed += 1,
+        );
+        assert_eq!(res, Ok(()));
+        assert_eq!(num_removed, DEPTH + 1, "all directories and the file");
+        assert!(!dir.join("tree").exists());
+        Ok(())
+    }
+}
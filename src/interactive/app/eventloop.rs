@@ -1,47 +1,100 @@
 use crate::interactive::{
-    sorted_entries,
-    widgets::{MainWindow, MainWindowProps},
-    ByteVisualization, CursorDirection, CursorMode, DisplayOptions, EntryDataBundle, MarkEntryMode,
-    SortMode,
+    input_channel, sorted_entries,
+    widgets::{ErrorPane, MainWindow, MainWindowProps, MessagesPane, PathFinder, Theme, Treemap},
+    Action, ByteVisualization, Column, Columns, CursorDirection, CursorMode, Deletion,
+    DisplayOptions, EntryDataBundle, EntryFilter, Event, KeyMap, MarkEntryMode, MarkPattern,
+    Message, MessageLog, Refresh, Search, SortMode, StaleDirectories, BAR_WIDTHS,
 };
 use anyhow::Result;
-use crosstermion::input::{key_input_channel, Key};
+use crosstermion::input::Key;
 use dua::{
+    filesystem::{Filesystem, LocalFilesystem},
     traverse::{Traversal, TreeIndex},
-    WalkOptions, WalkResult,
+    ByteFormat, Device, WalkOptions, WalkResult,
+};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::mpsc::{Receiver, SyncSender},
+    time::{Duration, Instant, SystemTime},
 };
-use std::{collections::BTreeMap, path::PathBuf};
 use tui::backend::Backend;
 use tui_react::Terminal;
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum FocussedPane {
+    #[default]
     Main,
     Help,
     Mark,
 }
 
-impl Default for FocussedPane {
-    fn default() -> Self {
-        FocussedPane::Main
-    }
-}
-
 #[derive(Default)]
 pub struct AppState {
     pub root: TreeIndex,
     pub selected: Option<TreeIndex>,
     pub entries: Vec<EntryDataBundle>,
     pub sorting: SortMode,
-    pub message: Option<String>,
+    /// Which entries of the current directory are shown
+    pub filter: EntryFilter,
+    /// The message shown in the footer until the next key is pressed
+    pub message: Option<Message>,
+    /// All messages shown so far, to look at them again
+    pub messages: MessageLog,
     pub focussed: FocussedPane,
     pub bookmarks: BTreeMap<TreeIndex, TreeIndex>,
     pub is_scanning: bool,
+    /// The time the walk of the traversal started at, to show its progress while scanning
+    pub scan_started: Option<Instant>,
+    /// How long the walk of the traversal took, unless it wasn't walked but loaded
+    pub scan_duration: Option<Duration>,
+    /// If set, the traversal was loaded from a file and its entries might not exist on this machine.
+    pub is_loaded: bool,
+    /// If set, the traversal is of another machine or of what Docker keeps, so none of its entries exist at their path.
+    pub is_remote: bool,
+    /// If set, only entries of the current directory matching it are shown
+    pub search: Option<Search>,
+    /// If set, keys are used to type a pattern of entries to mark
+    pub mark_pattern: Option<MarkPattern>,
+    /// The last key if it was 'g', which waits for the next one to tell whether it starts 'gg' to move to the top like
+    /// in vim, or cycles the byte visualization on its own.
+    pub pending_prefix: Option<Key>,
+    /// The byte format before 'B' switched to exact byte counts, to restore it when it's pressed again
+    pub byte_format_before_raw: Option<ByteFormat>,
+    /// The time and entry of the last click with the mouse, to detect double-clicks
+    pub last_click: Option<(Instant, TreeIndex)>,
+    /// The options of the walk that produced the traversal, to walk parts of it again
+    pub walk_options: Option<WalkOptions>,
+    /// The actions of the keys pressed while the entries are focussed
+    pub keymap: KeyMap,
+    /// If set, directories are polled for entries created or removed in them, and rescanned if there are
+    pub poll_changes: bool,
+    /// The time changes on disk were last looked for, to only rescan directories changed since then
+    pub changes_checked_at: Option<SystemTime>,
+    /// The deletion in progress, if there is one
+    pub deletion: Option<Deletion>,
+    /// Where to send events from other threads, like the one deleting entries, if they can be received
+    pub event_sender: Option<SyncSender<Event>>,
+    /// If set, deleting entries larger than this needs the amount of entries in them to be typed
+    pub confirm_deletions_above: Option<u128>,
+    /// The time the scan was made at if it's one from the cache
+    pub cached_at: Option<SystemTime>,
+    /// The directories changed since the cached scan was made, once they were looked for
+    pub stale_directories: Option<StaleDirectories>,
+    /// The rescans of changed directories in progress, if any
+    pub refresh: Option<Refresh>,
+    /// If set, a device picked in the devices pane is shown instead of the input paths, and neither the session nor
+    /// the cache of the input paths are saved
+    pub scans_device: bool,
+    /// The mounted filesystems, once they were looked up to show the free space of the one shown
+    pub devices: Option<Vec<Device>>,
 }
 
 pub enum ProcessingResult {
     Finished(WalkResult),
     ExitRequested(WalkResult),
+    /// The user asked to stop scanning and browse the entries seen so far
+    StopScanningRequested,
 }
 
 impl AppState {
@@ -56,9 +109,9 @@ impl AppState {
         B: Backend,
     {
         let props = MainWindowProps {
-            traversal: &traversal,
+            traversal,
             display,
-            state: &self,
+            state: self,
         };
         draw_window(window, props, terminal)
     }
@@ -69,7 +122,7 @@ impl AppState {
         traversal: &mut Traversal,
         display: &mut DisplayOptions,
         terminal: &mut Terminal<B>,
-        keys: impl Iterator<Item = Key>,
+        events: impl Iterator<Item = impl Into<Event>>,
     ) -> Result<ProcessingResult>
     where
         B: Backend,
@@ -78,19 +131,129 @@ impl AppState {
         use FocussedPane::*;
 
         self.draw(window, traversal, *display, terminal)?;
-        for key in keys {
+        for event in events {
+            let event = event.into();
+            let event = match event {
+                Event::Tick => {
+                    if self.rescan_changed_directories(window, traversal) {
+                        self.draw(window, traversal, *display, terminal)?;
+                    }
+                    continue;
+                }
+                Event::Deletion(update) => {
+                    self.process_deletion_update(update, window, traversal);
+                    self.draw(window, traversal, *display, terminal)?;
+                    continue;
+                }
+                Event::Refresh(update) => {
+                    self.process_refresh_update(update, window, traversal);
+                    self.draw(window, traversal, *display, terminal)?;
+                    continue;
+                }
+                event => event,
+            };
             self.reset_message();
+            self.apply_rescans(window, traversal);
+            let key = match event {
+                Event::Key(key) => key,
+                Event::Mouse(event) => {
+                    if self.deletion.is_none() {
+                        self.process_mouse_event(event, window, traversal);
+                        self.draw(window, traversal, *display, terminal)?;
+                    }
+                    continue;
+                }
+                Event::Tick | Event::Deletion(_) | Event::Refresh(_) => continue,
+            };
+            let is_after_g = self.pending_prefix.take().is_some();
+            if is_after_g
+                && !(matches!(self.focussed, Main)
+                    && matches!(
+                        self.keymap.action(key),
+                        Some(Action::CycleByteVisualization)
+                    ))
+            {
+                display.byte_vis.cycle();
+            }
+            if let Some(deletion) = &self.deletion {
+                if matches!(key, Esc | Char('q') | Ctrl('c')) {
+                    deletion.abort();
+                    self.draw(window, traversal, *display, terminal)?;
+                }
+                continue;
+            }
+            if window.delete_confirmation.is_some() && !matches!(key, Ctrl('c')) {
+                self.confirm_deletion(key, window, traversal);
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if window.error_pane.is_some() && !matches!(key, Ctrl('c')) {
+                window.error_pane = window
+                    .error_pane
+                    .take()
+                    .and_then(|pane| pane.process_events(key, traversal.errors.len()));
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if window.messages_pane.is_some() && !matches!(key, Ctrl('c')) {
+                window.messages_pane = window
+                    .messages_pane
+                    .take()
+                    .and_then(|pane| pane.process_events(key, self.messages.len()));
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if window.duplicates_pane.is_some() && !matches!(key, Ctrl('c')) {
+                self.dispatch_to_duplicates_pane(key, window, traversal);
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if window.devices_pane.is_some() && !matches!(key, Ctrl('c')) {
+                if let Some(mount_point) = self.dispatch_to_devices_pane(key, window) {
+                    self.show_progress(format!("Scanning '{}'…", mount_point.display()));
+                    self.draw(window, traversal, *display, terminal)?;
+                    self.scan_device(mount_point, window, traversal);
+                }
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if window.path_finder.is_some() && !matches!(key, Ctrl('c')) {
+                self.dispatch_to_path_finder(key, window, traversal);
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if self.mark_pattern.is_some() && !matches!(key, Ctrl('c')) {
+                self.edit_mark_pattern(key, window, traversal);
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            if self.search.as_ref().is_some_and(|s| s.is_editing) && !matches!(key, Ctrl('c')) {
+                self.edit_search(key, traversal);
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
             match key {
-                Char('?') => self.toggle_help_pane(window),
-                Char('\t') => {
-                    self.cycle_focus(window);
+                Ctrl('c') if self.is_scanning => {
+                    return Ok(ProcessingResult::StopScanningRequested)
                 }
                 Ctrl('c') => {
                     return Ok(ProcessingResult::ExitRequested(WalkResult {
                         num_errors: traversal.io_errors,
                     }))
                 }
-                Char('q') | Esc => match self.focussed {
+                Esc if matches!(self.focussed, Main) && self.search.is_some() => {
+                    self.clear_search(traversal);
+                    self.draw(window, traversal, *display, terminal)?;
+                    continue;
+                }
+                _ => {}
+            }
+            match self.keymap.action(key) {
+                Some(Action::ToggleHelp) => self.toggle_help_pane(window),
+                Some(Action::CycleFocus) => {
+                    self.cycle_focus(window);
+                }
+                Some(Action::Quit) => match self.focussed {
                     Main => {
                         return Ok(ProcessingResult::ExitRequested(WalkResult {
                             num_errors: traversal.io_errors,
@@ -106,9 +269,7 @@ impl AppState {
             }
 
             match self.focussed {
-                FocussedPane::Mark => {
-                    self.dispatch_to_mark_pane(key, window, traversal, *display, terminal)
-                }
+                FocussedPane::Mark => self.dispatch_to_mark_pane(key, window, traversal),
                 FocussedPane::Help => {
                     window
                         .help_pane
@@ -116,41 +277,117 @@ impl AppState {
                         .expect("help pane")
                         .process_events(key);
                 }
-                FocussedPane::Main => match key {
-                    Char('O') => self.open_that(traversal),
-                    Char(' ') => self.mark_entry(
+                FocussedPane::Main => match self.keymap.action(key) {
+                    Some(Action::Search) => self.start_search(),
+                    Some(Action::FindPath) => window.path_finder = Some(PathFinder::new(traversal)),
+                    Some(Action::ShowErrors) => window.error_pane = Some(ErrorPane::default()),
+                    Some(Action::ShowMessages) => {
+                        window.messages_pane = Some(MessagesPane::default())
+                    }
+                    Some(Action::Open) => self.open_that(traversal, terminal),
+                    Some(Action::Shell) => self.spawn_shell(traversal, terminal),
+                    Some(Action::CopyPath) => self.copy_path_to_clipboard(traversal),
+                    Some(Action::ToggleMark) => self.mark_entry(
                         CursorMode::KeepPosition,
                         MarkEntryMode::Toggle,
                         window,
                         traversal,
                     ),
-                    Char('d') => self.mark_entry(
+                    Some(Action::MarkAndAdvance) => self.mark_entry(
                         CursorMode::Advance,
                         MarkEntryMode::Toggle,
                         window,
                         traversal,
                     ),
-                    Char('x') => self.mark_entry(
+                    Some(Action::Delete) => {
+                        self.request_deletion(window, traversal, display.byte_format)
+                    }
+                    Some(Action::MarkForDeletion) => self.mark_entry(
                         CursorMode::Advance,
                         MarkEntryMode::MarkForDeletion,
                         window,
                         traversal,
                     ),
-                    Char('u') | Char('h') | Backspace | Left => {
-                        self.exit_node_with_traversal(traversal)
+                    Some(Action::MarkByPattern) => self.mark_pattern = Some(MarkPattern::default()),
+                    Some(Action::InvertMarks) => self.invert_marks(window, traversal),
+                    Some(Action::ClearMarks) => self.clear_marks(window),
+                    Some(Action::ExitDirectory) => self.exit_node_with_traversal(traversal),
+                    Some(Action::EnterDirectory) => self.enter_node_with_traversal(traversal),
+                    Some(Action::ToTop) => self.change_entry_selection(CursorDirection::ToTop),
+                    Some(Action::ToBottom) => {
+                        self.change_entry_selection(CursorDirection::ToBottom)
+                    }
+                    Some(Action::ToLargest) => self.select_largest(),
+                    Some(Action::NextSibling) => self.show_sibling(true, traversal),
+                    Some(Action::PreviousSibling) => self.show_sibling(false, traversal),
+                    Some(Action::PageUp) => self.change_entry_selection(CursorDirection::PageUp),
+                    Some(Action::Up) => self.change_entry_selection(CursorDirection::Up),
+                    Some(Action::Down) => self.change_entry_selection(CursorDirection::Down),
+                    Some(Action::PageDown) => {
+                        self.change_entry_selection(CursorDirection::PageDown)
+                    }
+                    Some(Action::SortBySize) => self.cycle_sorting(traversal),
+                    Some(Action::SortByCount) => self.cycle_count_sorting(traversal),
+                    Some(Action::SortByName) => self.cycle_name_sorting(traversal),
+                    Some(Action::SortByMTime) => self.cycle_mtime_sorting(traversal),
+                    Some(Action::ToggleEntryCount) => display.columns.toggle(Column::Count),
+                    Some(Action::ToggleMTime) => display.columns.toggle(Column::MTime),
+                    Some(Action::ToggleOtherSize) => display.columns.toggle(Column::OtherSize),
+                    Some(Action::ToggleFooterStats) => self.toggle_footer_stats(display),
+                    Some(Action::ToggleHidden) => self.toggle_hidden(traversal),
+                    Some(Action::CycleMinSize) => self.cycle_min_size(traversal),
+                    Some(Action::CycleEntryKind) => self.cycle_entry_kind(traversal),
+                    Some(Action::CyclePercentageBase) => {
+                        self.cycle_percentage_base(display, traversal)
+                    }
+                    Some(Action::ToggleTreemap) => {
+                        window.treemap = match window.treemap {
+                            Some(_) => None,
+                            None => Some(Treemap::default()),
+                        }
+                    }
+                    Some(Action::CycleByteVisualization) if is_after_g => {
+                        self.change_entry_selection(CursorDirection::ToTop)
+                    }
+                    Some(Action::CycleByteVisualization) => self.pending_prefix = Some(key),
+                    Some(Action::WidenBars) => display.resize_bar(1),
+                    Some(Action::NarrowBars) => display.resize_bar(-1),
+                    Some(Action::ToggleApparentSize) => {
+                        self.toggle_apparent_size(window, traversal)
+                    }
+                    Some(Action::ToggleRawBytes) => match self.byte_format_before_raw.take() {
+                        Some(byte_format) => display.byte_format = byte_format,
+                        None => {
+                            self.byte_format_before_raw = Some(display.byte_format);
+                            display.byte_format = ByteFormat::Raw;
+                        }
+                    },
+                    Some(Action::CycleByteFormat) => {
+                        if let Some(byte_format) = self.byte_format_before_raw.take() {
+                            display.byte_format = byte_format;
+                        }
+                        display.cycle_byte_format()
                     }
-                    Char('o') | Char('l') | Char('\n') | Right => {
-                        self.enter_node_with_traversal(traversal)
+                    Some(Action::Rescan) => {
+                        self.show_progress("Rescanning…");
+                        self.draw(window, traversal, *display, terminal)?;
+                        self.rescan(window, traversal);
+                    }
+                    Some(Action::RescanChanged) => self.rescan_stale_directories(window, traversal),
+                    Some(Action::FindDuplicates) => {
+                        self.show_progress("Looking for duplicates…");
+                        self.draw(window, traversal, *display, terminal)?;
+                        self.find_duplicates(window, traversal);
+                    }
+                    Some(Action::ShowDevices) => self.show_devices(window),
+                    Some(Action::Quit | Action::CycleFocus | Action::ToggleHelp) => {}
+                    None => {
+                        if let Char(c @ '1'..='9') = key {
+                            self.jump_to_breadcrumb(c as usize - '0' as usize, traversal)
+                        } else if let Some(action) = self.keymap.external_action(key).cloned() {
+                            self.run_external_action(&action, window, traversal, terminal)
+                        }
                     }
-                    Char('H') => self.change_entry_selection(CursorDirection::ToTop),
-                    Char('G') => self.change_entry_selection(CursorDirection::ToBottom),
-                    Ctrl('u') | PageUp => self.change_entry_selection(CursorDirection::PageUp),
-                    Char('k') | Up => self.change_entry_selection(CursorDirection::Up),
-                    Char('j') | Down => self.change_entry_selection(CursorDirection::Down),
-                    Ctrl('d') | PageDown => self.change_entry_selection(CursorDirection::PageDown),
-                    Char('s') => self.cycle_sorting(traversal),
-                    Char('g') => display.byte_vis.cycle(),
-                    _ => {}
                 },
             };
             self.draw(window, traversal, *display, terminal)?;
@@ -175,6 +412,23 @@ where
     Ok(())
 }
 
+/// How often directories are checked for changes while polling for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What interactive mode starts with, as chosen by the user
+#[derive(Default, Clone)]
+pub struct Preferences {
+    pub theme: Theme,
+    pub sorting: SortMode,
+    pub keymap: KeyMap,
+    /// If set, directories are polled for entries created or removed in them, and rescanned if there are
+    pub poll_changes: bool,
+    /// If set, deleting entries larger than this needs the amount of entries in them to be typed
+    pub confirm_deletions_above: Option<u128>,
+    /// The columns shown before the names of entries
+    pub columns: Columns,
+}
+
 /// State and methods representing the interactive disk usage analyser for the terminal
 pub struct TerminalApp {
     pub traversal: Traversal,
@@ -183,7 +437,7 @@ pub struct TerminalApp {
     pub window: MainWindow,
 }
 
-type KeyboardInputAndApp = (std::sync::mpsc::Receiver<Key>, TerminalApp);
+type KeyboardInputAndApp = (std::sync::mpsc::Receiver<Event>, TerminalApp);
 
 impl TerminalApp {
     pub fn refresh_view<B>(&mut self, terminal: &mut Terminal<B>)
@@ -204,7 +458,7 @@ impl TerminalApp {
     pub fn process_events<B>(
         &mut self,
         terminal: &mut Terminal<B>,
-        keys: impl Iterator<Item = Key>,
+        events: impl Iterator<Item = impl Into<Event>>,
     ) -> Result<WalkResult>
     where
         B: Backend,
@@ -214,15 +468,43 @@ impl TerminalApp {
             &mut self.traversal,
             &mut self.display,
             terminal,
-            keys,
+            events,
         )? {
             ProcessingResult::Finished(res) | ProcessingResult::ExitRequested(res) => Ok(res),
+            ProcessingResult::StopScanningRequested => Ok(WalkResult {
+                num_errors: self.traversal.io_errors,
+            }),
         }
     }
 
     pub fn initialize<B>(
         terminal: &mut Terminal<B>,
         options: WalkOptions,
+        preferences: Preferences,
+        input_paths: Vec<PathBuf>,
+        mode: Interaction,
+    ) -> Result<Option<KeyboardInputAndApp>>
+    where
+        B: Backend,
+    {
+        let mut filesystem = LocalFilesystem::new(options.clone())?;
+        Self::initialize_from_filesystem(
+            terminal,
+            options,
+            preferences,
+            &mut filesystem,
+            input_paths,
+            mode,
+        )
+    }
+
+    /// Like [`initialize()`][TerminalApp::initialize()], but walk the `input_paths` of `filesystem`, like one of
+    /// another machine.
+    pub fn initialize_from_filesystem<B>(
+        terminal: &mut Terminal<B>,
+        options: WalkOptions,
+        preferences: Preferences,
+        filesystem: &mut dyn Filesystem,
         input_paths: Vec<PathBuf>,
         mode: Interaction,
     ) -> Result<Option<KeyboardInputAndApp>>
@@ -231,94 +513,115 @@ impl TerminalApp {
     {
         terminal.hide_cursor()?;
         terminal.clear()?;
-        let mut display: DisplayOptions = options.clone().into();
-        display.byte_vis = ByteVisualization::PercentageAndBar;
+        let mut display = display_options(&options, &preferences);
         let mut window = MainWindow::default();
-        let keys_rx = match mode {
-            Interaction::None => {
-                let (_, keys_rx) = std::sync::mpsc::channel();
-                keys_rx
-            }
-            Interaction::Full => key_input_channel(),
-        };
+        let poll_changes = preferences.poll_changes;
+        let confirm_deletions_above = preferences.confirm_deletions_above;
+        let (event_sender, events_rx) = mode.input_channel(poll_changes.then_some(POLL_INTERVAL));
 
-        let fetch_buffered_key_events = || {
-            let mut keys = Vec::new();
-            while let Ok(key) = keys_rx.try_recv() {
-                keys.push(key);
+        let fetch_buffered_events = || {
+            let mut events = Vec::new();
+            while let Ok(event) = events_rx.try_recv() {
+                events.push(event);
             }
-            keys
+            events
         };
 
         let mut state = None::<AppState>;
         let mut received_events = false;
-        let traversal = Traversal::from_walk(options, input_paths, |traversal| {
-            let s = match state.as_mut() {
-                Some(s) => {
-                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting);
-                    if !received_events {
-                        s.selected = s.entries.get(0).map(|b| b.index);
-                    }
-                    s
-                }
-                None => {
-                    state = Some({
-                        let sorting = Default::default();
-                        let entries =
-                            sorted_entries(&traversal.tree, traversal.root_index, sorting);
-                        AppState {
-                            root: traversal.root_index,
-                            sorting,
-                            selected: entries.get(0).map(|b| b.index),
-                            entries,
-                            is_scanning: true,
-                            ..Default::default()
+        let mut exit_requested = false;
+        let walk_options = options.clone();
+        let scan_started = Instant::now();
+        let changes_checked_at = SystemTime::now();
+        let traversal = Traversal::from_filesystem_with_options(
+            filesystem,
+            &options,
+            input_paths,
+            |traversal| {
+                let s = match state.as_mut() {
+                    Some(s) => {
+                        s.refresh_entries(traversal);
+                        if !received_events {
+                            s.selected = s.entries.first().map(|b| b.index);
                         }
-                    });
-                    state.as_mut().expect("state to be present, we just set it")
-                }
-            };
-            s.reset_message(); // force "scanning" to appear
-            let events = fetch_buffered_key_events();
-            received_events |= !events.is_empty();
+                        s
+                    }
+                    None => {
+                        state = Some({
+                            let sorting = preferences.sorting;
+                            let entries = sorted_entries(
+                                &traversal.tree,
+                                traversal.root_index,
+                                sorting,
+                                EntryFilter::default(),
+                            );
+                            AppState {
+                                root: traversal.root_index,
+                                sorting,
+                                selected: entries.first().map(|b| b.index),
+                                entries,
+                                is_scanning: true,
+                                scan_started: Some(scan_started),
+                                keymap: preferences.keymap.clone(),
+                                ..Default::default()
+                            }
+                        });
+                        state.as_mut().expect("state to be present, we just set it")
+                    }
+                };
+                s.reset_message();
+                let events = fetch_buffered_events();
+                received_events |= !events.is_empty();
 
-            let should_exit = match s.process_events(
-                &mut window,
-                traversal,
-                &mut display,
-                terminal,
-                events.into_iter(),
-            )? {
-                ProcessingResult::ExitRequested(_) => true,
-                ProcessingResult::Finished(_) => false,
-            };
-            Ok(should_exit)
-        })?;
-        let traversal = match traversal {
-            Some(t) => t,
-            None => return Ok(None),
-        };
+                let should_stop = match s.process_events(
+                    &mut window,
+                    traversal,
+                    &mut display,
+                    terminal,
+                    events.into_iter(),
+                )? {
+                    ProcessingResult::ExitRequested(_) => {
+                        exit_requested = true;
+                        true
+                    }
+                    ProcessingResult::StopScanningRequested => true,
+                    ProcessingResult::Finished(_) => false,
+                };
+                Ok(should_stop)
+            },
+        )?;
+        if exit_requested {
+            return Ok(None);
+        }
 
-        Ok(Some((keys_rx, {
+        Ok(Some((events_rx, {
             let mut app = TerminalApp {
                 state: {
                     let mut s = state.unwrap_or_else(|| {
-                        let sorting = Default::default();
+                        let sorting = preferences.sorting;
                         let root = traversal.root_index;
-                        let entries = sorted_entries(&traversal.tree, root, sorting);
+                        let entries =
+                            sorted_entries(&traversal.tree, root, sorting, EntryFilter::default());
                         AppState {
                             root,
                             entries,
                             sorting,
+                            keymap: preferences.keymap,
                             ..Default::default()
                         }
                     });
                     s.is_scanning = false;
-                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting);
+                    s.scan_duration = Some(scan_started.elapsed());
+                    s.walk_options = Some(walk_options);
+                    s.poll_changes = poll_changes;
+                    s.changes_checked_at = Some(changes_checked_at);
+                    s.event_sender = event_sender;
+                    s.confirm_deletions_above = confirm_deletions_above;
+                    s.refresh_entries(&traversal);
                     s.selected = if received_events {
-                        s.selected.or_else(|| s.entries.get(0).map(|b| b.index))
+                        s.selected.or_else(|| s.entries.first().map(|b| b.index))
                     } else {
-                        s.entries.get(0).map(|b| b.index)
+                        s.entries.first().map(|b| b.index)
                     };
                     s
                 },
@@ -327,9 +630,69 @@ impl TerminalApp {
                 window,
             };
             app.refresh_view(terminal);
+            if app.traversal.collapsed_entries > 0 {
+                app.state.warn(format!(
+                    "{} of the smallest files were collapsed to keep no more than {} entries",
+                    app.traversal.collapsed_entries,
+                    options.max_entries.unwrap_or_default()
+                ));
+            }
             app
         })))
     }
+
+    /// Like [`initialize()`][TerminalApp::initialize()], but browse the given `traversal` instead of walking the
+    /// filesystem, like one that was loaded from a file.
+    pub fn initialize_from_traversal<B>(
+        terminal: &mut Terminal<B>,
+        options: WalkOptions,
+        preferences: Preferences,
+        traversal: Traversal,
+        mode: Interaction,
+    ) -> Result<KeyboardInputAndApp>
+    where
+        B: Backend,
+    {
+        terminal.hide_cursor()?;
+        terminal.clear()?;
+        let sorting = preferences.sorting;
+        let root = traversal.root_index;
+        let entries = sorted_entries(&traversal.tree, root, sorting, EntryFilter::default());
+        let poll_changes = preferences.poll_changes;
+        let (event_sender, events_rx) = mode.input_channel(poll_changes.then_some(POLL_INTERVAL));
+        let display = display_options(&options, &preferences);
+        let mut app = TerminalApp {
+            state: AppState {
+                root,
+                sorting,
+                selected: entries.first().map(|b| b.index),
+                entries,
+                is_loaded: true,
+                walk_options: Some(options.clone()),
+                keymap: preferences.keymap,
+                poll_changes,
+                event_sender,
+                confirm_deletions_above: preferences.confirm_deletions_above,
+                ..Default::default()
+            },
+            display,
+            traversal,
+            window: MainWindow::default(),
+        };
+        app.refresh_view(terminal);
+        Ok((events_rx, app))
+    }
+}
+
+fn display_options(options: &WalkOptions, preferences: &Preferences) -> DisplayOptions {
+    let mut display: DisplayOptions = options.clone().into();
+    display.byte_vis = ByteVisualization::PercentageAndBar;
+    display.theme = preferences.theme;
+    display.columns = preferences.columns;
+    if let Some(width) = preferences.columns.width(Column::Bar) {
+        display.bar_width = width.clamp(*BAR_WIDTHS.start(), *BAR_WIDTHS.end());
+    }
+    display
 }
 
 pub enum Interaction {
@@ -337,3 +700,22 @@ pub enum Interaction {
     #[allow(dead_code)]
     None,
 }
+
+impl Interaction {
+    /// Return a receiver of events along with a way to send events to it, if anything can be received.
+    fn input_channel(
+        &self,
+        tick_interval: Option<Duration>,
+    ) -> (Option<SyncSender<Event>>, Receiver<Event>) {
+        match self {
+            Interaction::None => {
+                let (_, events_rx) = std::sync::mpsc::sync_channel(0);
+                (None, events_rx)
+            }
+            Interaction::Full => {
+                let (event_sender, events_rx) = input_channel(tick_interval);
+                (Some(event_sender), events_rx)
+            }
+        }
+    }
+}
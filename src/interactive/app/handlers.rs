@@ -1,23 +1,64 @@
-use crate::interactive::{
-    app::FocussedPane::*,
-    path_of, sorted_entries,
-    widgets::{HelpPane, MainWindow, MarkMode, MarkPane},
-    AppState, DisplayOptions, EntryDataBundle,
+use crate::{
+    interactive::{
+        app::{
+            clipboard::{self, Copied},
+            FocussedPane::*,
+        },
+        delete_entries, find_stale_directories, path_of, protection_of, rescan_all, sorted_entries,
+        widgets::{
+            breadcrumbs_of, format_age, DeleteConfirmation, DevicesPane, DuplicatesPane, HelpPane,
+            MainWindow, MarkMode, MarkPane, RequiredInput,
+        },
+        with_suspended_terminal, Action, AppState, Deletion, DeletionItem, DeletionUpdate,
+        DisplayOptions, EntryDataBundle, Event, ExternalAction, MarkPattern, Message, MouseEvent,
+        MouseEventKind, PercentageBase, Refresh, RefreshUpdate, Rescan, Search, Severity,
+        TRASH_SUPPORTED,
+    },
+    path::absolute,
 };
 use crosstermion::input::Key;
-use dua::traverse::{Traversal, TreeIndex};
+use dua::{
+    traverse::{Name, Traversal, Tree, TreeIndex},
+    ByteFormat, Device, Glob, WalkOptions,
+};
 use itertools::Itertools;
 use petgraph::{visit::Bfs, Direction};
-use std::{fs, io, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
 use tui::backend::Backend;
 use tui_react::Terminal;
 
+/// Times of modification come from a coarser clock than the current time, and some filesystems only store seconds, so
+/// directories changed just before a check may look older than it.
+const MTIME_RESOLUTION: Duration = Duration::from_secs(1);
+
+/// How long ago the scan cached at `cached_at` was made, like `3h`.
+fn cached_age(cached_at: SystemTime) -> String {
+    format_age(
+        SystemTime::now()
+            .duration_since(cached_at)
+            .unwrap_or_default(),
+    )
+}
+
 #[derive(Copy, Clone)]
 pub enum CursorMode {
     Advance,
     KeepPosition,
 }
 
+/// The way entries are deleted
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeletionMode {
+    /// Remove entries from disk, they can't be restored
+    Permanently,
+    /// Move entries to the trash, from where they can be restored
+    ToTrash,
+}
+
 #[derive(Copy, Clone)]
 pub enum MarkEntryMode {
     Toggle,
@@ -48,9 +89,132 @@ impl CursorDirection {
 }
 
 impl AppState {
-    pub fn open_that(&self, traversal: &Traversal) {
-        if let Some(idx) = self.selected {
-            open::that(path_of(&traversal.tree, idx)).ok();
+    /// Open the selected entry with the program associated with it, giving it the terminal until it exits.
+    pub fn open_that<B>(&mut self, traversal: &Traversal, terminal: &mut Terminal<B>)
+    where
+        B: Backend,
+    {
+        let path = match self.selected {
+            Some(idx) => path_of(&traversal.tree, idx),
+            None => return,
+        };
+        match with_suspended_terminal(terminal, || open::that(&path)) {
+            Ok(Ok(status)) if status.success() => self.message = None,
+            Ok(Ok(status)) => self.report_error(format!(
+                "Could not open '{}': the opener exited with {}",
+                path.display(),
+                status
+            )),
+            Ok(Err(err)) | Err(err) => {
+                self.report_error(format!("Could not open '{}': {}", path.display(), err))
+            }
+        }
+    }
+
+    /// Run the shell of the user in the directory that is shown, giving it the terminal until it exits.
+    pub fn spawn_shell<B>(&mut self, traversal: &Traversal, terminal: &mut Terminal<B>)
+    where
+        B: Backend,
+    {
+        let dir = match path_of(&traversal.tree, self.root) {
+            dir if dir.as_os_str().is_empty() => PathBuf::from("."),
+            dir => dir,
+        };
+        if !dir.is_dir() {
+            self.warn(format!("'{}' is not a directory here", dir.display()));
+            return;
+        }
+        let shell = std::env::var_os(if cfg!(windows) { "COMSPEC" } else { "SHELL" })
+            .unwrap_or_else(|| (if cfg!(windows) { "cmd" } else { "sh" }).into());
+        let res = with_suspended_terminal(terminal, || {
+            std::process::Command::new(&shell)
+                .current_dir(&dir)
+                .status()
+        });
+        if let Ok(Err(err)) | Err(err) = res {
+            self.report_error(format!(
+                "Could not run '{}': {}",
+                Path::new(&shell).display(),
+                err
+            ));
+        }
+    }
+
+    /// Run the command of `action` with the paths of all marked entries, or the selected one if there are none, giving
+    /// it the terminal until it exits.
+    pub fn run_external_action<B>(
+        &mut self,
+        action: &ExternalAction,
+        window: &MainWindow,
+        traversal: &Traversal,
+        terminal: &mut Terminal<B>,
+    ) where
+        B: Backend,
+    {
+        if self.is_loaded {
+            self.warn(format!(
+                "'{}' can't be run on entries of a loaded scan",
+                action.name
+            ));
+            return;
+        }
+        let paths: Vec<_> = match window.mark_pane.as_ref() {
+            Some(pane) => pane
+                .marked_sorted_by_index()
+                .into_iter()
+                .map(|(_, mark)| mark.path.clone())
+                .collect(),
+            None => self
+                .selected
+                .map(|idx| path_of(&traversal.tree, idx))
+                .into_iter()
+                .collect(),
+        };
+        if paths.is_empty() {
+            return;
+        }
+        let (program, args) = action.command_for(&paths);
+        let res = with_suspended_terminal(terminal, || {
+            std::process::Command::new(&program).args(&args).status()
+        });
+        match res {
+            Ok(Ok(status)) if status.success() => self.inform(format!(
+                "'{}' finished with {} entr{}",
+                action.name,
+                paths.len(),
+                if paths.len() == 1 { "y" } else { "ies" }
+            )),
+            Ok(Ok(status)) => {
+                self.report_error(format!("'{}' failed with {}", action.name, status))
+            }
+            Ok(Err(err)) | Err(err) => self.report_error(format!(
+                "Could not run '{}': {}",
+                Path::new(&program).display(),
+                err
+            )),
+        }
+    }
+
+    /// Copy the absolute path of the selected entry to the clipboard.
+    pub fn copy_path_to_clipboard(&mut self, traversal: &Traversal) {
+        let path = match self.selected {
+            Some(idx) => path_of(&traversal.tree, idx),
+            None => return,
+        };
+        let path = absolute(&path).unwrap_or(path);
+        match clipboard::copy(&path.to_string_lossy(), io::stderr()) {
+            Ok(Copied::Tool(_)) => {
+                self.inform(format!("Copied '{}' to the clipboard", path.display()))
+            }
+            Ok(Copied::Terminal) => self.inform(format!(
+                "Sent '{}' to the clipboard of the terminal",
+                path.display()
+            )),
+            Err(err) => self.report_error(format!(
+                "Could not copy '{}' to the clipboard: {}",
+                path.display(),
+                err
+            )),
         }
     }
 
@@ -70,7 +234,7 @@ impl AppState {
             .map(|parent_idx| {
                 (
                     parent_idx,
-                    sorted_entries(&traversal.tree, parent_idx, self.sorting),
+                    sorted_entries(&traversal.tree, parent_idx, self.sorting, self.filter),
                 )
             })
     }
@@ -80,13 +244,14 @@ impl AppState {
             Some((parent_idx, entries)) => {
                 self.root = parent_idx;
                 self.entries = entries;
+                self.search = None;
                 self.selected = self
                     .bookmarks
                     .get(&parent_idx)
                     .copied()
-                    .or_else(|| self.entries.get(0).map(|b| b.index));
+                    .or_else(|| self.entries.first().map(|b| b.index));
             }
-            None => self.message = Some("Top level reached".into()),
+            None => self.inform("Top level reached"),
         }
     }
 
@@ -97,7 +262,12 @@ impl AppState {
         self.selected.map(|previously_selected| {
             (
                 previously_selected,
-                sorted_entries(&traversal.tree, previously_selected, self.sorting),
+                sorted_entries(
+                    &traversal.tree,
+                    previously_selected,
+                    self.sorting,
+                    self.filter,
+                ),
             )
         })
     }
@@ -125,8 +295,9 @@ impl AppState {
                     self.root = previously_selected;
                     self.selected = Some(b.index);
                     self.entries = new_entries;
+                    self.search = None;
                 }
-                None => self.message = Some("Entry is a file or an empty directory".into()),
+                None => self.inform("Entry is a file or an empty directory"),
             }
         }
     }
@@ -151,19 +322,858 @@ impl AppState {
         }
     }
 
-    pub fn cycle_sorting(&mut self, traversal: &Traversal) {
-        self.sorting.toggle_size();
-        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting);
+    /// Select the largest of the entries shown, whichever way they are sorted.
+    pub fn select_largest(&mut self) {
+        if let Some(largest) = self.entries.iter().max_by_key(|b| b.data.size) {
+            self.selected = Some(largest.index);
+            self.bookmarks.insert(self.root, largest.index);
+        }
     }
 
-    pub fn reset_message(&mut self) {
+    /// Show the entries of the next directory in the parent of the one shown, or the previous one if `forward` isn't
+    /// set, in the order they are sorted in. Files and empty directories are skipped.
+    pub fn show_sibling(&mut self, forward: bool, traversal: &Traversal) {
+        let parent = match traversal
+            .tree
+            .neighbors_directed(self.root, Direction::Incoming)
+            .next()
+        {
+            Some(parent) => parent,
+            None => {
+                self.inform("The top level has no siblings");
+                return;
+            }
+        };
+        let siblings = sorted_entries(&traversal.tree, parent, self.sorting, self.filter);
+        let position = match siblings.iter().position(|b| b.index == self.root) {
+            Some(position) => position,
+            None => return,
+        };
+        let has_entries = |b: &&EntryDataBundle| {
+            traversal
+                .tree
+                .neighbors_directed(b.index, Direction::Outgoing)
+                .next()
+                .is_some()
+        };
+        let sibling = if forward {
+            siblings[position + 1..].iter().find(has_entries)
+        } else {
+            siblings[..position].iter().rev().find(has_entries)
+        };
+        match sibling {
+            Some(sibling) => {
+                let sibling = sibling.index;
+                self.bookmarks.insert(parent, sibling);
+                self.set_root(sibling, traversal);
+                self.selected = self
+                    .bookmarks
+                    .get(&sibling)
+                    .copied()
+                    .or_else(|| self.entries.first().map(|b| b.index));
+            }
+            None => self.inform(if forward {
+                "This is the last directory"
+            } else {
+                "This is the first directory"
+            }),
+        }
+    }
+
+    pub fn dispatch_to_path_finder(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let finder = match window.path_finder.take() {
+            Some(finder) => finder,
+            None => return,
+        };
+        match finder.process_events(key) {
+            Some((finder, None)) => window.path_finder = Some(finder),
+            Some((_, Some(index))) => self.jump_to(index, traversal),
+            None => {}
+        }
+    }
+
+    /// Select entries with clicks and scroll through them with the mouse wheel, enter directories with double-clicks,
+    /// and jump to parent directories by clicking their name in the title.
+    pub fn process_mouse_event(
+        &mut self,
+        event: MouseEvent,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+        // Dialogs and overlays are used with the keyboard
+        if window.delete_confirmation.is_some()
+            || window.path_finder.is_some()
+            || window.error_pane.is_some()
+            || window.duplicates_pane.is_some()
+            || window.help_pane.is_some()
+            || self.search.as_ref().is_some_and(|s| s.is_editing)
+            || self.mark_pattern.is_some()
+        {
+            return;
+        }
+        let MouseEvent { kind, column, row } = event;
+        if kind == MouseEventKind::Click {
+            if let Some(ancestor) = window.breadcrumbs.segment_at(column, row) {
+                self.jump_to_ancestor(ancestor, traversal);
+                return;
+            }
+        }
+        let area = match &window.treemap {
+            Some(treemap) => treemap.area,
+            None => window.entries_pane.area,
+        };
+        if column < area.x
+            || column >= area.x + area.width
+            || row < area.y
+            || row >= area.y + area.height
+        {
+            return;
+        }
+        match kind {
+            MouseEventKind::ScrollUp => self.change_entry_selection(CursorDirection::Up),
+            MouseEventKind::ScrollDown => self.change_entry_selection(CursorDirection::Down),
+            MouseEventKind::Click => {
+                if matches!(self.focussed, Mark) {
+                    self.cycle_focus(window);
+                }
+                if row == area.y {
+                    if let Some(ancestor) = window.entries_pane.ancestor_at_title_column(
+                        column,
+                        &traversal.tree,
+                        self.root,
+                    ) {
+                        self.jump_to_ancestor(ancestor, traversal);
+                    }
+                    return;
+                }
+                if window.treemap.is_none() {
+                    match window.entries_pane.sorting_at(column, row) {
+                        Some(Action::SortBySize) => return self.cycle_sorting(traversal),
+                        Some(Action::SortByCount) => return self.cycle_count_sorting(traversal),
+                        Some(Action::SortByName) => return self.cycle_name_sorting(traversal),
+                        Some(Action::SortByMTime) => return self.cycle_mtime_sorting(traversal),
+                        _ => {}
+                    }
+                }
+                let index = match &window.treemap {
+                    Some(treemap) => treemap.entry_at(column, row),
+                    None => window
+                        .entries_pane
+                        .entry_at_row(row)
+                        .and_then(|pos| self.entries.get(pos))
+                        .map(|entry| entry.index),
+                };
+                let index = match index {
+                    Some(index) => index,
+                    None => return,
+                };
+                self.selected = Some(index);
+                self.bookmarks.insert(self.root, index);
+                match self.last_click.take() {
+                    Some((time, clicked))
+                        if clicked == index && time.elapsed() < DOUBLE_CLICK_INTERVAL =>
+                    {
+                        self.enter_node_with_traversal(traversal)
+                    }
+                    _ => self.last_click = Some((Instant::now(), index)),
+                }
+            }
+        }
+    }
+
+    /// Show the directory of the breadcrumb with the given `number`, counting from 1 at the root of the traversal.
+    pub fn jump_to_breadcrumb(&mut self, number: usize, traversal: &Traversal) {
+        if let Some(&ancestor) = breadcrumbs_of(&traversal.tree, self.root).get(number - 1) {
+            self.jump_to_ancestor(ancestor, traversal);
+        }
+    }
+
+    /// Show the entries of `ancestor` of the current directory, selecting the one containing it.
+    pub fn jump_to_ancestor(&mut self, ancestor: TreeIndex, traversal: &Traversal) {
+        let mut child = self.root;
+        while let Some(parent) = traversal
+            .tree
+            .neighbors_directed(child, Direction::Incoming)
+            .next()
+        {
+            if parent == ancestor {
+                self.set_root(ancestor, traversal);
+                self.selected = Some(child);
+                self.bookmarks.insert(ancestor, child);
+                return;
+            }
+            child = parent;
+        }
+    }
+
+    /// Show the directory `index` is in, or its entries if it's a directory that has some, and select it.
+    pub fn jump_to(&mut self, index: TreeIndex, traversal: &Traversal) {
+        let has_children = traversal
+            .tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .next()
+            .is_some();
+        let (root, selected) = if has_children {
+            (index, None)
+        } else {
+            match traversal
+                .tree
+                .neighbors_directed(index, Direction::Incoming)
+                .next()
+            {
+                Some(parent) => (parent, Some(index)),
+                None => return,
+            }
+        };
+        self.set_root(root, traversal);
+        self.selected = selected
+            .or_else(|| self.bookmarks.get(&root).copied())
+            .filter(|selected| self.entries.iter().any(|e| e.index == *selected))
+            .or_else(|| self.entries.first().map(|e| e.index));
+        if let Some(selected) = self.selected {
+            self.bookmarks.insert(self.root, selected);
+        }
+    }
+
+    pub fn toggle_apparent_size(&mut self, window: &mut MainWindow, traversal: &mut Traversal) {
+        if self.is_scanning {
+            self.warn("Sizes can be toggled once scanning is done");
+            return;
+        }
+        traversal.toggle_apparent_size();
+        self.refresh_entries(traversal);
+        if let Some(pane) = window.mark_pane.as_mut() {
+            pane.refresh_sizes(&traversal.tree);
+        }
+    }
+
+    /// Walk the directory that is shown again to pick up changes made outside of `dua`, keeping the selection.
+    ///
+    /// At the top level, all directories that were given as input are walked again.
+    pub fn rescan(&mut self, window: &mut MainWindow, traversal: &mut Traversal) {
+        let walk_options = match (&self.walk_options, self.is_scanning) {
+            (Some(walk_options), false) => walk_options.clone(),
+            _ => {
+                self.warn("Directories can be rescanned once scanning is done");
+                return;
+            }
+        };
+        let directories: Vec<_> = if self.root == traversal.root_index {
+            traversal
+                .tree
+                .neighbors_directed(self.root, Direction::Outgoing)
+                .filter(|idx| traversal.tree[*idx].is_dir)
+                .collect()
+        } else {
+            vec![self.root]
+        };
+        self.message = None;
+        self.rescan_directories(directories, &walk_options, window, traversal);
+    }
+
+    /// Rescan the directories below the one that is shown whose time of last modification changed since the last
+    /// check, to pick up entries created or removed in them while polling for changes. Return true if any was.
+    ///
+    /// Only up to [`MAX_CHECKED_DIRECTORIES`] are checked each time, closest to the directory that is shown first.
+    pub fn rescan_changed_directories(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) -> bool {
+        const MAX_CHECKED_DIRECTORIES: usize = 1000;
+        let walk_options = match &self.walk_options {
+            Some(walk_options) if self.poll_changes && !self.is_scanning && !self.is_loaded => {
+                walk_options.clone()
+            }
+            _ => return false,
+        };
+        if self.has_dialog_referring_to_entries(window) {
+            return false;
+        }
+        let checked_at = match self.changes_checked_at.replace(SystemTime::now()) {
+            Some(checked_at) => checked_at - MTIME_RESOLUTION,
+            None => return false,
+        };
+
+        let tree = &traversal.tree;
+        let mut changed = Vec::new();
+        let mut num_checked = 0;
+        let mut to_check = std::collections::VecDeque::from(vec![self.root]);
+        while let Some(index) = to_check.pop_front() {
+            if num_checked == MAX_CHECKED_DIRECTORIES {
+                break;
+            }
+            if index != traversal.root_index {
+                num_checked += 1;
+                let is_changed = fs::symlink_metadata(path_of(tree, index))
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|mtime| mtime > checked_at);
+                if is_changed {
+                    // Rescanning it covers all directories within
+                    changed.push(index);
+                    continue;
+                }
+            }
+            to_check.extend(
+                tree.neighbors_directed(index, Direction::Outgoing)
+                    .filter(|idx| tree[*idx].is_dir),
+            );
+        }
+        if changed.is_empty() {
+            return false;
+        }
+        self.rescan_directories(changed, &walk_options, window, traversal);
+        true
+    }
+
+    /// Look for the directories changed since the cached scan that is shown was made, on another thread if it can tell
+    /// about them.
+    pub fn look_for_stale_directories(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let cached_at = match self.cached_at {
+            Some(cached_at) => cached_at,
+            None => return,
+        };
+        let tree = &traversal.tree;
+        let mut directories = Vec::new();
+        let mut bfs = Bfs::new(&**tree, traversal.root_index);
+        while let Some(index) = bfs.next(&**tree) {
+            if index != traversal.root_index && tree[index].is_dir {
+                directories.push((index, path_of(tree, index)));
+            }
+        }
+        let since = cached_at - MTIME_RESOLUTION;
+        self.show_progress(format!(
+            "Showing the scan cached {} ago, looking for changes…",
+            cached_age(cached_at)
+        ));
+        match self.event_sender.clone() {
+            Some(events) => {
+                std::thread::spawn(move || {
+                    let stale = find_stale_directories(directories, since);
+                    events
+                        .send(Event::Refresh(RefreshUpdate::Stale(stale)))
+                        .ok();
+                });
+            }
+            None => {
+                let stale = find_stale_directories(directories, since);
+                self.process_refresh_update(RefreshUpdate::Stale(stale), window, traversal);
+            }
+        }
+    }
+
+    /// Rescan the directories changed since the cached scan was made on another thread, or right away if it couldn't
+    /// tell about them.
+    pub fn rescan_stale_directories(&mut self, window: &mut MainWindow, traversal: &mut Traversal) {
+        let message = match (&mut self.stale_directories, &self.walk_options) {
+            _ if self.refresh.is_some() => "Changed directories are being rescanned already",
+            (None, _) | (_, None) if self.cached_at.is_none() => {
+                "Changed directories are only looked for in scans from the cache"
+            }
+            (None, _) | (_, None) => "Changed directories are still being looked for",
+            (Some(stale), _) if stale.directories.is_empty() => {
+                "No directories changed since the scan was cached"
+            }
+            (Some(stale), Some(walk_options)) => {
+                self.refresh = Some(Refresh::new(stale));
+                let directories = std::mem::take(&mut stale.directories);
+                let walk_options = walk_options.clone();
+                match self.event_sender.clone() {
+                    Some(events) => {
+                        std::thread::spawn(move || {
+                            rescan_all(directories, walk_options, |update| {
+                                events.send(Event::Refresh(update)).is_ok()
+                            })
+                        });
+                    }
+                    None => {
+                        let mut updates = Vec::new();
+                        rescan_all(directories, walk_options, |update| {
+                            updates.push(update);
+                            true
+                        });
+                        for update in updates {
+                            self.process_refresh_update(update, window, traversal);
+                        }
+                        return;
+                    }
+                }
+                "Rescanning changed directories…"
+            }
+        };
+        self.inform(message);
+    }
+
+    /// Tell about the directories that changed since the scan was cached, or show those that were rescanned.
+    pub fn process_refresh_update(
+        &mut self,
+        update: RefreshUpdate,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        match update {
+            RefreshUpdate::Stale(stale) => {
+                let age = self.cached_at.map(cached_age).unwrap_or_default();
+                let message = match stale.directories.len() {
+                    0 => {
+                        self.changes_checked_at = Some(stale.checked_at);
+                        format!(
+                            "Showing the scan cached {} ago, no directories changed since",
+                            age
+                        )
+                    }
+                    n => format!(
+                        "Showing the scan cached {} ago, {} director{} changed since, press {} to rescan {}",
+                        age,
+                        n,
+                        if n == 1 { "y" } else { "ies" },
+                        self.keymap.hint(Action::RescanChanged),
+                        if n == 1 { "it" } else { "them" }
+                    ),
+                };
+                self.inform(message);
+                self.stale_directories = Some(stale);
+            }
+            RefreshUpdate::Rescanned(rescan) => {
+                if let Some(refresh) = self.refresh.as_mut() {
+                    refresh.pending.push(*rescan);
+                }
+                self.apply_rescans(window, traversal);
+            }
+        }
+    }
+
+    /// Show the changed directories that were rescanned, unless dialogs referring to entries are open.
+    pub fn apply_rescans(&mut self, window: &mut MainWindow, traversal: &mut Traversal) {
+        if self.has_dialog_referring_to_entries(window) {
+            return;
+        }
+        let mut refresh = match self.refresh.take() {
+            Some(refresh) if !refresh.pending.is_empty() => refresh,
+            refresh => {
+                self.refresh = refresh;
+                return;
+            }
+        };
+        let selected_name = self
+            .selected
+            .and_then(|idx| traversal.tree.node_weight(idx))
+            .map(|e| e.name);
+        for Rescan { index, path, scan } in std::mem::take(&mut refresh.pending) {
+            refresh.num_rescanned += 1;
+            // The directory might have been deleted since, and its index reused
+            let is_same_directory =
+                traversal.tree.contains_node(index) && path_of(&traversal.tree, index) == path;
+            if !is_same_directory {
+                continue;
+            }
+            let res = scan.and_then(|scan| {
+                traversal
+                    .replace_directory(index, &path, scan)
+                    .map_err(|err| err.to_string())
+            });
+            match res {
+                Ok(()) => self.recompute_sizes_recursively(index, traversal),
+                Err(err) => {
+                    refresh.error = Some(format!("Could not rescan '{}': {}", path.display(), err))
+                }
+            }
+        }
+        self.refresh_after_rescan(selected_name, window, traversal);
+
+        if refresh.num_rescanned < refresh.num_directories {
+            self.show_progress(format!(
+                "Rescanning changed directories, {} of {} done…",
+                refresh.num_rescanned, refresh.num_directories
+            ));
+            self.refresh = Some(refresh);
+            return;
+        }
+        self.changes_checked_at = Some(refresh.checked_at);
+        let num_directories = refresh.num_directories;
+        match refresh.error {
+            Some(error) => self.report_error(error),
+            None => self.inform(format!(
+                "Rescanned {} changed director{}",
+                num_directories,
+                if num_directories == 1 { "y" } else { "ies" }
+            )),
+        }
+    }
+
+    fn rescan_directories(
+        &mut self,
+        directories: Vec<TreeIndex>,
+        walk_options: &WalkOptions,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let selected_name = self
+            .selected
+            .and_then(|idx| traversal.tree.node_weight(idx))
+            .map(|e| e.name);
+
+        for index in directories {
+            let path = path_of(&traversal.tree, index);
+            if let Err(err) = traversal.rescan(index, path.clone(), walk_options.clone()) {
+                self.report_error(format!("Could not rescan '{}': {}", path.display(), err));
+            }
+            self.recompute_sizes_recursively(index, traversal);
+        }
+        self.refresh_after_rescan(selected_name, window, traversal);
+    }
+
+    /// Dialogs refer to entries which may be gone after a rescan, which must not happen while they are shown.
+    fn has_dialog_referring_to_entries(&self, window: &MainWindow) -> bool {
+        self.deletion.is_some()
+            || window.delete_confirmation.is_some()
+            || window.duplicates_pane.is_some()
+            || window.path_finder.is_some()
+    }
+
+    /// Forget about entries that are gone after rescanning directories, show the sizes they have now and select the
+    /// entry named `selected_name` again.
+    fn refresh_after_rescan(
+        &mut self,
+        selected_name: Option<Name>,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let tree = &traversal.tree;
+        self.bookmarks
+            .retain(|dir, selected| tree.contains_node(*dir) && tree.contains_node(*selected));
+        window.mark_pane = window
+            .mark_pane
+            .take()
+            .and_then(|pane| pane.forget_deleted(tree));
+        if let Some(pane) = window.mark_pane.as_mut() {
+            pane.refresh_sizes(tree);
+        }
+        self.refresh_entries(traversal);
+        self.selected = selected_name
+            .and_then(|name| self.entries.iter().find(|e| e.data.name == name))
+            .or_else(|| self.entries.first())
+            .map(|e| e.index);
+    }
+
+    /// Show the sets of files with the same content within the directory that is shown, reading files as needed.
+    pub fn find_duplicates(&mut self, window: &mut MainWindow, traversal: &Traversal) {
         if self.is_scanning {
-            self.message = Some("-> scanning <-".into());
+            self.warn("Duplicates can be searched once scanning is done");
+            return;
+        }
+        let tree = &traversal.tree;
+        let mut files = Vec::new();
+        let mut bfs = Bfs::new(&**tree, self.root);
+        while let Some(index) = bfs.next(&**tree) {
+            let entry = &tree[index];
+            if !entry.is_dir && entry.entry_count == 0 {
+                let size = if traversal.apparent_size {
+                    entry.size
+                } else {
+                    entry.alternate_size
+                };
+                files.push((size, path_of(tree, index), index));
+            }
+        }
+        let mut num_errors = 0;
+        let sets = dua::find_duplicates(files, |_, _| num_errors += 1);
+        match (sets.is_empty(), num_errors) {
+            (true, 0) => self.inform("No duplicates found"),
+            (true, n) => self.warn(format!("No duplicates found, {} files couldn't be read", n)),
+            (false, 0) => self.message = None,
+            (false, n) => self.warn(format!("{} files couldn't be read", n)),
+        }
+        if !sets.is_empty() {
+            window.duplicates_pane = Some(DuplicatesPane::new(sets));
+        }
+    }
+
+    pub fn dispatch_to_duplicates_pane(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let pane = match window.duplicates_pane.take() {
+            Some(pane) => pane,
+            None => return,
+        };
+        match pane.process_events(key) {
+            Some((pane, None)) => window.duplicates_pane = Some(pane),
+            Some((_, Some(index))) => self.jump_to(index, traversal),
+            None => {}
+        }
+    }
+
+    /// Show the mounted filesystems, to pick one to scan instead of the input paths.
+    pub fn show_devices(&mut self, window: &mut MainWindow) {
+        if self.is_scanning || self.walk_options.is_none() {
+            self.warn("Devices can be scanned once scanning is done");
+            return;
+        }
+        match dua::mounted_devices() {
+            Ok(devices) if devices.is_empty() => self.inform("No mounted filesystems were found"),
+            Ok(devices) => window.devices_pane = Some(DevicesPane::new(devices)),
+            Err(err) => self.report_error(format!("{:#}", err)),
+        }
+    }
+
+    /// Pass `key` to the devices pane, and return the mount point of the device to scan if one was picked.
+    pub fn dispatch_to_devices_pane(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+    ) -> Option<PathBuf> {
+        match window.devices_pane.take()?.process_events(key) {
+            Some((pane, None)) => {
+                window.devices_pane = Some(pane);
+                None
+            }
+            Some((_, mount_point)) => mount_point,
+            None => None,
+        }
+    }
+
+    /// Replace the traversal with a scan of the device mounted at `mount_point`, and show it from the top.
+    pub fn scan_device(
+        &mut self,
+        mount_point: PathBuf,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let walk_options = match &self.walk_options {
+            Some(walk_options) => walk_options.clone(),
+            None => return,
+        };
+        let scan_started = Instant::now();
+        let scan =
+            match Traversal::from_walk(walk_options, vec![mount_point.clone()], |_| Ok(false)) {
+                Ok(scan) => scan,
+                Err(err) => {
+                    self.report_error(format!(
+                        "Could not scan '{}': {}",
+                        mount_point.display(),
+                        err
+                    ));
+                    return;
+                }
+            };
+        *traversal = scan;
+        self.scan_duration = Some(scan_started.elapsed());
+        self.scans_device = true;
+        self.is_loaded = false;
+        self.cached_at = None;
+        self.stale_directories = None;
+        self.refresh = None;
+        self.changes_checked_at = Some(SystemTime::now());
+        self.bookmarks.clear();
+        self.search = None;
+        window.mark_pane = None;
+        window.entries_pane = Default::default();
+        self.root = traversal.root_index;
+        self.refresh_entries(traversal);
+        self.selected = self.entries.first().map(|e| e.index);
+        self.message = None;
+    }
+
+    /// Show or hide entries whose name starts with a dot, keeping the selected entry if it's still shown.
+    pub fn toggle_hidden(&mut self, traversal: &Traversal) {
+        self.filter.hide_hidden = !self.filter.hide_hidden;
+        self.refresh_filtered_entries(traversal);
+        self.inform(if self.filter.hide_hidden {
+            "Entries starting with a dot are hidden"
         } else {
-            self.message = None;
+            "Entries starting with a dot are shown"
+        });
+    }
+
+    /// Only show entries of the next larger minimum size, or all of them again.
+    pub fn cycle_min_size(&mut self, traversal: &Traversal) {
+        self.filter.cycle_min_size();
+        self.refresh_filtered_entries(traversal);
+        self.inform(match self.filter.min_size {
+            0 => "Showing entries of any size".to_owned(),
+            _ => format!(
+                "Showing entries of at least {}",
+                self.filter.min_size_label()
+            ),
+        });
+    }
+
+    /// Only show files, only directories, or both of them again.
+    pub fn cycle_entry_kind(&mut self, traversal: &Traversal) {
+        self.filter.kind.cycle();
+        self.refresh_filtered_entries(traversal);
+        self.inform(format!("Showing {}", self.filter.kind.description()));
+    }
+
+    /// Obtain the entries anew after the filter changed, keeping the selected entry if it's still shown.
+    fn refresh_filtered_entries(&mut self, traversal: &Traversal) {
+        let selected = self.selected;
+        self.refresh_entries(traversal);
+        self.selected = selected
+            .filter(|idx| self.entries.iter().any(|e| e.index == *idx))
+            .or_else(|| self.entries.first().map(|e| e.index));
+    }
+
+    /// Show or hide the free space of the filesystem and the duration of the scan in the footer, looking up the mounted
+    /// filesystems the first time.
+    pub fn toggle_footer_stats(&mut self, display: &mut DisplayOptions) {
+        display.show_footer_stats = !display.show_footer_stats;
+        if display.show_footer_stats {
+            self.look_up_devices();
+        }
+    }
+
+    /// Show percentages of the next base, skipping the filesystem if the one of the directory shown isn't known, like
+    /// for traversals loaded from a file.
+    pub fn cycle_percentage_base(&mut self, display: &mut DisplayOptions, traversal: &Traversal) {
+        display.percentage_base.cycle();
+        if display.percentage_base == PercentageBase::Filesystem {
+            self.look_up_devices();
+            if self.device_of_root(&traversal.tree).is_none() {
+                display.percentage_base.cycle();
+            }
+        }
+        self.inform(format!(
+            "Percentages are of the {}",
+            display.percentage_base.description()
+        ));
+    }
+
+    /// Look up the mounted filesystems unless that was done already, or the traversal was loaded from a file.
+    fn look_up_devices(&mut self) {
+        if self.devices.is_none() && !self.is_loaded {
+            self.devices = Some(dua::mounted_devices().unwrap_or_else(|err| {
+                self.report_error(format!("{:#}", err));
+                Vec::new()
+            }));
         }
     }
 
+    /// Return the filesystem the directory shown is on, if the mounted filesystems were looked up.
+    pub fn device_of_root(&self, tree: &Tree) -> Option<&Device> {
+        let devices = self.devices.as_ref()?;
+        let mut path = path_of(tree, self.root);
+        if path.as_os_str().is_empty() {
+            // Above the input paths, which are likely on the same filesystem
+            let first = tree
+                .neighbors_directed(self.root, Direction::Outgoing)
+                .next()?;
+            path = path_of(tree, first);
+        }
+        dua::device_containing(devices, &path.canonicalize().ok()?)
+    }
+
+    pub fn cycle_sorting(&mut self, traversal: &Traversal) {
+        self.sorting.toggle_size();
+        self.refresh_entries(traversal);
+    }
+
+    pub fn cycle_count_sorting(&mut self, traversal: &Traversal) {
+        self.sorting.toggle_count();
+        self.refresh_entries(traversal);
+    }
+
+    pub fn cycle_name_sorting(&mut self, traversal: &Traversal) {
+        self.sorting.toggle_name();
+        self.refresh_entries(traversal);
+    }
+
+    pub fn cycle_mtime_sorting(&mut self, traversal: &Traversal) {
+        self.sorting.toggle_mtime();
+        self.refresh_entries(traversal);
+    }
+
+    /// Obtain the entries of the current directory anew, keeping only those matching the search.
+    pub fn refresh_entries(&mut self, traversal: &Traversal) {
+        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting, self.filter);
+        if let Some(search) = &self.search {
+            self.entries
+                .retain(|e| search.matches(&traversal.tree.names[e.data.name].to_string_lossy()));
+        }
+    }
+
+    pub fn start_search(&mut self) {
+        self.search.get_or_insert_with(Search::default).is_editing = true;
+    }
+
+    pub fn edit_search(&mut self, key: Key, traversal: &Traversal) {
+        use crosstermion::input::Key::*;
+        let search = match self.search.as_mut() {
+            Some(search) => search,
+            None => return,
+        };
+        match key {
+            Esc => return self.clear_search(traversal),
+            Char('\n') => {
+                if search.query.is_empty() {
+                    return self.clear_search(traversal);
+                }
+                search.is_editing = false;
+                self.selected = self.entries.first().map(|e| e.index);
+                return;
+            }
+            Backspace => {
+                search.query.pop();
+            }
+            Up => return self.change_entry_selection(CursorDirection::Up),
+            Down => return self.change_entry_selection(CursorDirection::Down),
+            Char(c) if !c.is_control() => search.query.push(c),
+            _ => return,
+        }
+        self.refresh_entries(traversal);
+        self.selected = self.entries.first().map(|e| e.index);
+    }
+
+    pub fn clear_search(&mut self, traversal: &Traversal) {
+        self.search = None;
+        let selected = self.selected;
+        self.refresh_entries(traversal);
+        self.selected = selected.or_else(|| self.entries.first().map(|e| e.index));
+    }
+
+    pub fn reset_message(&mut self) {
+        self.message = None;
+    }
+
+    /// Tell how an action went in the footer, and log it.
+    pub fn inform(&mut self, text: impl Into<String>) {
+        self.show_message(Message::new(text, Severity::Info));
+    }
+
+    /// Tell why an action wasn't done in the footer, and log it.
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.show_message(Message::new(text, Severity::Warning));
+    }
+
+    /// Tell why an action failed in the footer, and log it.
+    pub fn report_error(&mut self, text: impl Into<String>) {
+        self.show_message(Message::new(text, Severity::Error));
+    }
+
+    /// Tell what is being done in the footer while it takes a while, without logging it as it will be followed by
+    /// how it went.
+    pub fn show_progress(&mut self, text: impl Into<String>) {
+        self.message = Some(Message::new(text, Severity::Info));
+    }
+
+    fn show_message(&mut self, message: Message) {
+        self.messages.push(message.clone());
+        self.message = Some(message);
+    }
+
     pub fn toggle_help_pane(&mut self, window: &mut MainWindow) {
         self.focussed = match self.focussed {
             Main | Mark => {
@@ -180,54 +1190,56 @@ impl AppState {
         if let Some(p) = window.mark_pane.as_mut() {
             p.set_focus(false)
         };
-        self.focussed = match (self.focussed, &window.help_pane, &mut window.mark_pane) {
-            (Main, Some(_), _) => Help,
-            (Help, _, Some(ref mut pane)) => {
-                pane.set_focus(true);
-                Mark
-            }
-            (Help, _, None) => Main,
-            (Mark, _, _) => Main,
-            (Main, None, None) => Main,
-            (Main, None, Some(ref mut pane)) => {
+        self.focussed = match (self.focussed, &mut window.mark_pane) {
+            // the help is shown on top of all other panes, and keeps the focus until it's closed
+            (Help, _) => Help,
+            (Mark, _) => Main,
+            (Main, None) => Main,
+            (Main, Some(ref mut pane)) => {
                 pane.set_focus(true);
                 Mark
             }
         };
     }
 
-    pub fn dispatch_to_mark_pane<B>(
+    pub fn dispatch_to_mark_pane(
         &mut self,
         key: Key,
         window: &mut MainWindow,
         traversal: &mut Traversal,
-        display: DisplayOptions,
-        terminal: &mut Terminal<B>,
-    ) where
-        B: Backend,
-    {
+    ) {
         let res = window.mark_pane.take().and_then(|p| p.process_events(key));
         window.mark_pane = match res {
             Some((pane, mode)) => match mode {
-                Some(MarkMode::Delete) => {
-                    self.message = Some("Deleting entries...".to_string());
-                    let mut entries_deleted = 0;
-                    let res = pane.iterate_deletable_items(|mut pane, entry_to_delete| {
-                        window.mark_pane = Some(pane);
-                        self.draw(window, traversal, display, terminal).ok();
-                        pane = window.mark_pane.take().expect("option to be filled");
-                        match self.delete_entry(entry_to_delete, traversal) {
-                            Ok(ed) => {
-                                entries_deleted += ed;
-                                self.message =
-                                    Some(format!("Deleted {} entries...", entries_deleted));
-                                Ok(pane)
-                            }
-                            Err(c) => Err((pane, c)),
-                        }
-                    });
-                    self.message = None;
-                    res
+                Some(MarkMode::Delete(_)) if self.is_loaded => {
+                    self.warn("Entries of a loaded scan can't be deleted");
+                    Some(pane)
+                }
+                Some(MarkMode::Delete(_))
+                    if self.has_protected_or_large_marks(&pane, traversal) =>
+                {
+                    self.warn(
+                        "Protected or large entries must be deleted on their own, with confirmation",
+                    );
+                    Some(pane)
+                }
+                Some(MarkMode::Delete(mode)) => {
+                    let tree = &traversal.tree;
+                    let items = pane
+                        .marked_sorted_by_index()
+                        .into_iter()
+                        .filter_map(|(index, mark)| {
+                            tree.node_weight(*index).map(|entry| DeletionItem {
+                                index: *index,
+                                path: mark.path.clone(),
+                                num_entries: u64::from(entry.entry_count) + 1,
+                                size: entry.size.into(),
+                            })
+                        })
+                        .collect();
+                    window.mark_pane = Some(pane);
+                    self.start_deletion(items, mode, window, traversal);
+                    window.mark_pane.take()
                 }
                 None => Some(pane),
             },
@@ -238,58 +1250,288 @@ impl AppState {
         }
     }
 
-    pub fn delete_entry(
+    /// Return true if any marked entry is protected or larger than deletions can be without typing.
+    fn has_protected_or_large_marks(&self, pane: &MarkPane, traversal: &Traversal) -> bool {
+        let tree = &traversal.tree;
+        pane.marked().iter().any(|(index, mark)| {
+            let is_input = tree.neighbors_directed(*index, Direction::Incoming).next()
+                == Some(traversal.root_index);
+            protection_of(&mark.path, is_input).is_some()
+                || self
+                    .confirm_deletions_above
+                    .is_some_and(|limit| mark.size > limit)
+        })
+    }
+
+    /// Ask for confirmation to delete the selected entry, which must be typed if it's protected or large.
+    pub fn request_deletion(
         &mut self,
-        index: TreeIndex,
-        traversal: &mut Traversal,
-    ) -> Result<usize, usize> {
-        let mut entries_deleted = 0;
-        if let Some(_entry) = traversal.tree.node_weight(index) {
-            let path_to_delete = path_of(&traversal.tree, index);
-            delete_directory_recursively(path_to_delete)?;
-            let parent_idx = traversal
+        window: &mut MainWindow,
+        traversal: &Traversal,
+        byte_format: ByteFormat,
+    ) {
+        if self.is_loaded {
+            self.warn("Entries of a loaded scan can't be deleted");
+            return;
+        }
+        if self.is_scanning {
+            self.warn("Entries can be deleted once scanning is done");
+            return;
+        }
+        let selected = self
+            .selected
+            .and_then(|selected| self.entries.iter().find(|e| e.index == selected));
+        if let Some(entry) = selected {
+            let path = path_of(&traversal.tree, entry.index);
+            let is_input = traversal
                 .tree
-                .neighbors_directed(index, Direction::Incoming)
+                .neighbors_directed(entry.index, Direction::Incoming)
                 .next()
-                .expect("us being unable to delete the root index");
-            let mut bfs = Bfs::new(&traversal.tree, index);
-            while let Some(nx) = bfs.next(&traversal.tree) {
-                traversal.tree.remove_node(nx);
-                traversal.entries_traversed -= 1;
-                entries_deleted += 1;
-            }
-            self.entries = sorted_entries(&traversal.tree, self.root, self.sorting);
-            if traversal.tree.node_weight(self.root).is_none() {
-                self.set_root(traversal.root_index, traversal);
-            }
-            if self
-                .selected
-                .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
-                .is_none()
-            {
-                self.selected = self.entries.get(0).map(|e| e.index);
+                == Some(traversal.root_index);
+            let required_input = match protection_of(&path, is_input) {
+                Some(what) => Some(RequiredInput::new(
+                    format!("It's {}, type its path", what),
+                    path.to_string_lossy().into_owned(),
+                )),
+                None => match self.confirm_deletions_above {
+                    Some(limit) if u128::from(entry.data.size) > limit => Some(RequiredInput::new(
+                        format!(
+                            "It's larger than {}, type the amount of entries",
+                            byte_format.display(limit)
+                        ),
+                        (entry.data.entry_count + 1).to_string(),
+                    )),
+                    _ => None,
+                },
+            };
+            window.delete_confirmation = Some(DeleteConfirmation {
+                index: entry.index,
+                path,
+                size: entry.data.size.into(),
+                entry_count: entry.data.entry_count.into(),
+                is_dir: entry.is_dir,
+                required_input,
+            });
+        }
+    }
+
+    /// Delete the entry awaiting confirmation if `key` confirms it, or cancel the deletion otherwise.
+    pub fn confirm_deletion(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let mut confirmation = match window.delete_confirmation.take() {
+            Some(confirmation) => confirmation,
+            None => return,
+        };
+        let mode = match (confirmation.required_input.as_mut(), key) {
+            (Some(required), Key::Char('\n')) if required.is_complete() => {
+                DeletionMode::Permanently
+            }
+            (Some(required), Key::Ctrl('t')) if required.is_complete() && TRASH_SUPPORTED => {
+                DeletionMode::ToTrash
+            }
+            (Some(_), Key::Esc) => {
+                self.inform("Deletion cancelled");
+                return;
+            }
+            (Some(required), key) => {
+                match key {
+                    Key::Backspace => {
+                        required.typed.pop();
+                    }
+                    Key::Char(c) if !c.is_control() => required.typed.push(c),
+                    Key::Char('\n') | Key::Ctrl('t')
+                        if matches!(key, Key::Char('\n')) || TRASH_SUPPORTED =>
+                    {
+                        self.warn(format!("{} first", required.reason))
+                    }
+                    _ => {}
+                }
+                window.delete_confirmation = Some(confirmation);
+                return;
+            }
+            (None, Key::Char('y') | Key::Char('Y')) => DeletionMode::Permanently,
+            (None, Key::Char('t') | Key::Char('T')) if TRASH_SUPPORTED => DeletionMode::ToTrash,
+            (None, _) => {
+                self.inform("Deletion cancelled");
+                return;
+            }
+        };
+        let item = DeletionItem {
+            index: confirmation.index,
+            path: confirmation.path,
+            num_entries: confirmation.entry_count + 1,
+            size: confirmation.size,
+        };
+        self.start_deletion(vec![item], mode, window, traversal);
+    }
+
+    /// Delete `items` on another thread, whose progress is shown until it's done, or right away if it couldn't tell
+    /// about it.
+    fn start_deletion(
+        &mut self,
+        items: Vec<DeletionItem>,
+        mode: DeletionMode,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let deletion = Deletion::new(mode, &items);
+        let abort = deletion.abort_flag();
+        let apparent_size = traversal.apparent_size;
+        self.deletion = Some(deletion);
+        match self.event_sender.clone() {
+            Some(events) => {
+                std::thread::spawn(move || {
+                    delete_entries(items, mode, apparent_size, &abort, |update| {
+                        events.send(Event::Deletion(update)).is_ok()
+                    })
+                });
+            }
+            None => {
+                let mut updates = Vec::new();
+                delete_entries(items, mode, apparent_size, &abort, |update| {
+                    updates.push(update);
+                    true
+                });
+                for update in updates {
+                    self.process_deletion_update(update, window, traversal);
+                }
             }
-            self.recompute_sizes_recursively(parent_idx, traversal);
         }
-        Ok(entries_deleted)
+    }
+
+    /// Show how far the deletion got, and remove the entries it's done with from the tree.
+    pub fn process_deletion_update(
+        &mut self,
+        update: DeletionUpdate,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let mut deletion = match self.deletion.take() {
+            Some(deletion) => deletion,
+            None => return,
+        };
+        match update {
+            DeletionUpdate::Progress(progress) => deletion.progress = progress,
+            DeletionUpdate::Finished {
+                index,
+                num_errors,
+                message,
+            } => {
+                if message.is_some() {
+                    deletion.error = message;
+                }
+                // Entries within others deleted before are gone already
+                if traversal.tree.contains_node(index) {
+                    let path = path_of(&traversal.tree, index);
+                    if path.symlink_metadata().is_err() {
+                        deletion.entries_deleted += self.remove_entry(index, traversal);
+                        window.mark_pane = window
+                            .mark_pane
+                            .take()
+                            .and_then(|pane| pane.forget_deleted(&traversal.tree));
+                    } else {
+                        if traversal.tree[index].is_dir {
+                            // Show what's left of it
+                            if let Some(walk_options) = self.walk_options.clone() {
+                                self.rescan_directories(
+                                    vec![index],
+                                    &walk_options,
+                                    window,
+                                    traversal,
+                                );
+                            }
+                        }
+                        if let Some(pane) = window.mark_pane.as_mut() {
+                            pane.set_errors_during_deletion(index, num_errors);
+                        }
+                    }
+                }
+            }
+            DeletionUpdate::Done { aborted } => {
+                let entries_deleted = deletion.entries_deleted;
+                match (aborted, deletion.error, deletion.mode) {
+                    (true, _, _) => self.warn(format!(
+                        "Deletion aborted after deleting {} entries",
+                        entries_deleted
+                    )),
+                    (false, Some(error), _) => self.report_error(error),
+                    (false, None, DeletionMode::Permanently) => {
+                        self.inform(format!("Deleted {} entries", entries_deleted))
+                    }
+                    (false, None, DeletionMode::ToTrash) => {
+                        self.inform(format!("Moved {} entries to the trash", entries_deleted))
+                    }
+                }
+                if window.mark_pane.is_none() && matches!(self.focussed, Mark) {
+                    self.focussed = Main;
+                }
+                return;
+            }
+        }
+        self.deletion = Some(deletion);
+    }
+
+    /// Remove the entry at `index` and all entries within it from the tree, and return how many there were.
+    fn remove_entry(&mut self, index: TreeIndex, traversal: &mut Traversal) -> usize {
+        let mut entries_deleted = 0;
+        let parent_idx = traversal
+            .tree
+            .neighbors_directed(index, Direction::Incoming)
+            .next()
+            .expect("us being unable to delete the root index");
+        let mut bfs = Bfs::new(&*traversal.tree, index);
+        while let Some(nx) = bfs.next(&*traversal.tree) {
+            traversal.tree.remove_node(nx);
+            traversal.entries_traversed -= 1;
+            entries_deleted += 1;
+        }
+        self.refresh_entries(traversal);
+        if traversal.tree.node_weight(self.root).is_none() {
+            self.set_root(traversal.root_index, traversal);
+        }
+        if self
+            .selected
+            .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
+            .is_none()
+        {
+            self.selected = self.entries.first().map(|e| e.index);
+        }
+        self.recompute_sizes_recursively(parent_idx, traversal);
+        entries_deleted
     }
 
     fn set_root(&mut self, root: TreeIndex, traversal: &Traversal) {
         self.root = root;
-        self.entries = sorted_entries(&traversal.tree, root, self.sorting);
+        self.search = None;
+        self.entries = sorted_entries(&traversal.tree, root, self.sorting, self.filter);
     }
 
     fn recompute_sizes_recursively(&mut self, mut index: TreeIndex, traversal: &mut Traversal) {
         loop {
-            traversal
-                .tree
-                .node_weight_mut(index)
-                .expect("valid index")
-                .size = traversal
+            let (size, alternate_size, entry_count, mtime) = traversal
                 .tree
                 .neighbors_directed(index, Direction::Outgoing)
-                .filter_map(|idx| traversal.tree.node_weight(idx).map(|w| w.size))
-                .sum();
+                .filter_map(|idx| traversal.tree.node_weight(idx))
+                .fold(
+                    (0, 0, 0, None),
+                    |(size, alternate_size, entry_count, mtime), w| {
+                        (
+                            size + w.size,
+                            alternate_size + w.alternate_size,
+                            entry_count + w.entry_count + 1,
+                            w.mtime.max(mtime),
+                        )
+                    },
+                );
+            let entry = traversal.tree.node_weight_mut(index).expect("valid index");
+            entry.size = size;
+            entry.alternate_size = alternate_size;
+            entry.entry_count = entry_count;
+            entry.mtime = mtime;
             match traversal
                 .tree
                 .neighbors_directed(index, Direction::Incoming)
@@ -302,7 +1544,7 @@ impl AppState {
         traversal.total_bytes = traversal
             .tree
             .node_weight(traversal.root_index)
-            .map(|w| w.size);
+            .map(|w| w.size.into());
     }
 
     pub fn mark_entry(
@@ -313,6 +1555,14 @@ impl AppState {
         traversal: &Traversal,
     ) {
         if let Some(index) = self.selected {
+            if traversal.tree[index].is_in_archive {
+                self.inform("Entries within archives can't be deleted on their own");
+                return;
+            }
+            if traversal.tree[index].is_collapsed {
+                self.inform("Collapsed files can only be deleted along with their directory");
+                return;
+            }
             let is_dir = self
                 .entries
                 .iter()
@@ -334,69 +1584,100 @@ impl AppState {
             self.change_entry_selection(CursorDirection::Down)
         }
     }
-}
 
-fn into_error_count(res: Result<(), io::Error>) -> usize {
-    match res.map_err(io_err_to_usize) {
-        Ok(_) => 0,
-        Err(c) => c,
-    }
-}
-
-fn io_err_to_usize(err: io::Error) -> usize {
-    if err.kind() == io::ErrorKind::NotFound {
-        0
-    } else {
-        1
+    /// Type the pattern of entries to mark with `key`, and mark all entries matching it once it's entered.
+    pub fn edit_mark_pattern(&mut self, key: Key, window: &mut MainWindow, traversal: &Traversal) {
+        use crosstermion::input::Key::*;
+        let pattern = match self.mark_pattern.as_mut() {
+            Some(pattern) => pattern,
+            None => return,
+        };
+        match key {
+            Esc => self.mark_pattern = None,
+            Char('\n') => {
+                let pattern = self.mark_pattern.take().expect("pattern to be typed");
+                if !pattern.query.is_empty() {
+                    self.mark_matching(&pattern, window, traversal);
+                }
+            }
+            Char('\t') => pattern.recursive = !pattern.recursive,
+            Backspace => {
+                pattern.query.pop();
+            }
+            Char(c) if !c.is_control() => pattern.query.push(c),
+            _ => {}
+        }
     }
-}
 
-// TODO: could use jwalk for this
-// see https://github.com/Byron/dua-cli/issues/43
-fn delete_directory_recursively(path: PathBuf) -> Result<(), usize> {
-    let mut files_or_dirs = vec![path];
-    let mut dirs = Vec::new();
-    let mut num_errors = 0;
-    while let Some(path) = files_or_dirs.pop() {
-        let assume_symlink_to_try_deletion = true;
-        let is_symlink = path
-            .symlink_metadata()
-            .map(|m| m.file_type().is_symlink())
-            .unwrap_or(assume_symlink_to_try_deletion);
-        if is_symlink {
-            // do not follow symlinks
-            num_errors += into_error_count(fs::remove_file(&path));
-            continue;
-        }
-        match fs::read_dir(&path) {
-            Ok(iterator) => {
-                dirs.push(path);
-                for entry in iterator {
-                    match entry.map_err(io_err_to_usize) {
-                        Ok(entry) => files_or_dirs.push(entry.path()),
-                        Err(c) => num_errors += c,
-                    }
-                }
+    /// Mark all entries that are shown and match `pattern`, or all matching entries below the current directory if
+    /// it's recursive. Entries within matching directories aren't marked on their own, as they go along with them.
+    fn mark_matching(
+        &mut self,
+        pattern: &MarkPattern,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let glob: Glob = match pattern.query.parse() {
+            Ok(glob) => glob,
+            Err(err) => {
+                self.report_error(err);
+                return;
             }
-            Err(ref e) if e.kind() == io::ErrorKind::Other => {
-                // assume file, save IOps
-                num_errors += into_error_count(fs::remove_file(path));
+        };
+        let tree = &traversal.tree;
+        let mut matching = Vec::new();
+        let mut to_check: Vec<_> = self.entries.iter().map(|e| e.index).collect();
+        while let Some(index) = to_check.pop() {
+            let entry = &tree[index];
+            if entry.is_in_archive || entry.is_collapsed {
                 continue;
             }
-            Err(_) => {
-                num_errors += 1;
-                continue;
+            if glob.matches_path(&path_of(tree, index)) {
+                matching.push((index, entry.is_dir));
+            } else if pattern.recursive && entry.is_dir {
+                to_check.extend(tree.neighbors_directed(index, Direction::Outgoing));
             }
-        };
+        }
+        matching.sort();
+
+        self.inform(format!(
+            "Marked {} entr{} matching '{}'",
+            matching.len(),
+            if matching.len() == 1 { "y" } else { "ies" },
+            pattern.query
+        ));
+        for (index, is_dir) in matching {
+            window.mark_pane = window
+                .mark_pane
+                .take()
+                .unwrap_or_default()
+                .toggle_index(index, tree, is_dir, false);
+        }
     }
 
-    for dir in dirs.into_iter().rev() {
-        num_errors += into_error_count(fs::remove_dir(&dir).or_else(|_| fs::remove_file(dir)));
+    /// Mark all entries that are shown which aren't marked yet, and unmark all others.
+    pub fn invert_marks(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| !e.data.is_in_archive && !e.data.is_collapsed)
+        {
+            window.mark_pane = window.mark_pane.take().unwrap_or_default().toggle_index(
+                entry.index,
+                &traversal.tree,
+                entry.is_dir,
+                true,
+            );
+        }
+        if window.mark_pane.is_none() && matches!(self.focussed, Mark) {
+            self.focussed = Main;
+        }
     }
 
-    if num_errors == 0 {
-        Ok(())
-    } else {
-        Err(num_errors)
+    pub fn clear_marks(&mut self, window: &mut MainWindow) {
+        window.mark_pane = None;
+        if matches!(self.focussed, Mark) {
+            self.focussed = Main;
+        }
     }
 }
@@ -0,0 +1,229 @@
+use crate::interactive::{DeletionUpdate, RefreshUpdate};
+use crosstermion::input::Key;
+use std::{
+    io,
+    sync::mpsc::{Receiver, SyncSender},
+    time::Duration,
+};
+use tui::backend::Backend;
+use tui_react::Terminal;
+
+/// Something the user did, with the keyboard or the mouse.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "tui-crossplatform"), allow(dead_code))]
+pub enum Event {
+    Key(Key),
+    Mouse(MouseEvent),
+    /// Time passed, to look for changes on disk while polling for changes
+    Tick,
+    /// The thread deleting entries got further
+    Deletion(DeletionUpdate),
+    /// The thread looking for directories changed since the scan was cached, or rescanning them, got further
+    Refresh(RefreshUpdate),
+}
+
+impl From<Key> for Event {
+    fn from(key: Key) -> Self {
+        Event::Key(key)
+    }
+}
+
+/// A mouse event at the given cell of the terminal, counted from the top-left.
+#[derive(Debug, Clone, Copy)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub column: u16,
+    pub row: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "tui-crossplatform"), allow(dead_code))]
+pub enum MouseEventKind {
+    /// The left mouse button was pressed
+    Click,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// Send a [tick][Event::Tick] to `event_send` every `interval` on another thread, until it's disconnected.
+fn send_ticks(event_send: SyncSender<Event>, interval: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        if event_send.send(Event::Tick).is_err() {
+            break;
+        }
+    });
+}
+
+/// Return a receiver of user input, read on another thread to avoid blocking the main thread, along with a
+/// [tick][Event::Tick] every `tick_interval` if it's set, and a sender for other threads to send events to it.
+#[cfg(not(feature = "tui-crossplatform"))]
+pub fn input_channel(tick_interval: Option<Duration>) -> (SyncSender<Event>, Receiver<Event>) {
+    let keys = crosstermion::input::key_input_channel();
+    let (event_send, event_receive) = std::sync::mpsc::sync_channel(0);
+    if let Some(interval) = tick_interval {
+        send_ticks(event_send.clone(), interval);
+    }
+    let sender = event_send.clone();
+    std::thread::spawn(move || {
+        for key in keys {
+            if event_send.send(Event::Key(key)).is_err() {
+                break;
+            }
+        }
+    });
+    (sender, event_receive)
+}
+
+/// Return a receiver of user input, read on another thread to avoid blocking the main thread, along with a
+/// [tick][Event::Tick] every `tick_interval` if it's set, and a sender for other threads to send events to it.
+///
+/// Mouse events are only received if the `mouse` feature is enabled and [`enable_mouse_capture()`] was called.
+/// No input is read while the terminal is suspended, to leave it to the programs run in the meantime.
+#[cfg(feature = "tui-crossplatform")]
+pub fn input_channel(tick_interval: Option<Duration>) -> (SyncSender<Event>, Receiver<Event>) {
+    use crosstermion::crossterm::event::{self, MouseButton};
+    use std::{convert::TryFrom, sync::atomic::Ordering};
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+    let (event_send, event_receive) = std::sync::mpsc::sync_channel(0);
+    if let Some(interval) = tick_interval {
+        send_ticks(event_send.clone(), interval);
+    }
+    let sender = event_send.clone();
+    std::thread::spawn(move || -> io::Result<()> {
+        loop {
+            if suspension::IS_SUSPENDED.load(Ordering::SeqCst) {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            match event::poll(POLL_INTERVAL) {
+                Ok(true) if !suspension::IS_SUSPENDED.load(Ordering::SeqCst) => {}
+                Ok(_) => continue,
+                Err(err) => return Err(suspension::into_io_error(err)),
+            }
+            let event = match event::read() {
+                Ok(event::Event::Key(key)) => match Key::try_from(key) {
+                    Ok(key) => Event::Key(key),
+                    Err(_) => continue,
+                },
+                Ok(event::Event::Mouse(mouse)) => Event::Mouse(MouseEvent {
+                    kind: match mouse.kind {
+                        event::MouseEventKind::Down(MouseButton::Left) => MouseEventKind::Click,
+                        event::MouseEventKind::ScrollUp => MouseEventKind::ScrollUp,
+                        event::MouseEventKind::ScrollDown => MouseEventKind::ScrollDown,
+                        _ => continue,
+                    },
+                    column: mouse.column,
+                    row: mouse.row,
+                }),
+                Ok(event::Event::Resize(..)) => continue,
+                Err(crosstermion::crossterm::ErrorKind::IoError(err))
+                    if err.kind() == io::ErrorKind::Interrupted =>
+                {
+                    continue
+                }
+                Err(err) => return Err(suspension::into_io_error(err)),
+            };
+            if event_send.send(event).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    });
+    (sender, event_receive)
+}
+
+/// Make the terminal send mouse events, which does nothing unless the `mouse` feature is enabled.
+pub fn enable_mouse_capture() -> io::Result<()> {
+    #[cfg(feature = "mouse")]
+    crosstermion::crossterm::execute!(
+        io::stderr(),
+        crosstermion::crossterm::event::EnableMouseCapture
+    )
+    .map_err(suspension::into_io_error)?;
+    Ok(())
+}
+
+/// Undo [`enable_mouse_capture()`].
+pub fn disable_mouse_capture() -> io::Result<()> {
+    #[cfg(feature = "mouse")]
+    crosstermion::crossterm::execute!(
+        io::stderr(),
+        crosstermion::crossterm::event::DisableMouseCapture
+    )
+    .map_err(suspension::into_io_error)?;
+    Ok(())
+}
+
+/// Leave the alternate screen, raw mode and mouse capture of the terminal without anything to restore them, like when
+/// panicking, to make whatever is written next readable.
+///
+/// With the `tui-unix` backend the terminal stays in raw mode, which can only be left by the one who entered it.
+pub fn restore_terminal() {
+    #[cfg(feature = "tui-crossplatform")]
+    suspension::suspend().ok();
+    #[cfg(not(feature = "tui-crossplatform"))]
+    {
+        use crosstermion::termion::{cursor, screen};
+        use std::io::Write;
+        write!(io::stderr(), "{}{}", screen::ToMainScreen, cursor::Show).ok();
+    }
+}
+
+/// Leave the alternate screen and raw mode of the terminal while calling `f`, to let it run programs that use the
+/// terminal, and restore both afterwards, redrawing everything with the next frame.
+///
+/// With the `tui-unix` backend the terminal stays as it is, which is enough for programs with their own window.
+pub fn with_suspended_terminal<B, T>(
+    terminal: &mut Terminal<B>,
+    f: impl FnOnce() -> T,
+) -> io::Result<T>
+where
+    B: Backend,
+{
+    #[cfg(feature = "tui-crossplatform")]
+    suspension::suspend()?;
+    let res = f();
+    #[cfg(feature = "tui-crossplatform")]
+    suspension::resume()?;
+    let area = terminal.size()?;
+    terminal.resize(area)?;
+    terminal.hide_cursor()?;
+    Ok(res)
+}
+
+#[cfg(feature = "tui-crossplatform")]
+mod suspension {
+    use crosstermion::crossterm::{cursor, execute, terminal, ErrorKind};
+    use std::{
+        io,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    /// If set, the input thread doesn't read from the terminal
+    pub static IS_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+    pub fn suspend() -> io::Result<()> {
+        IS_SUSPENDED.store(true, Ordering::SeqCst);
+        super::disable_mouse_capture()?;
+        execute!(io::stderr(), terminal::LeaveAlternateScreen, cursor::Show)
+            .map_err(into_io_error)?;
+        terminal::disable_raw_mode().map_err(into_io_error)
+    }
+
+    pub fn resume() -> io::Result<()> {
+        terminal::enable_raw_mode().map_err(into_io_error)?;
+        execute!(io::stderr(), terminal::EnterAlternateScreen).map_err(into_io_error)?;
+        super::enable_mouse_capture()?;
+        IS_SUSPENDED.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn into_io_error(err: ErrorKind) -> io::Error {
+        match err {
+            ErrorKind::IoError(err) => err,
+            err => io::Error::other(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,833 @@
+use crate::{
+    config::{Config, Table, Value},
+    interactive::TRASH_SUPPORTED,
+};
+use anyhow::{anyhow, bail, Result};
+use crosstermion::input::Key;
+use std::{collections::BTreeMap, ffi::OsString, path::PathBuf};
+
+/// Something the user can do with a key while the entries are focussed, or to control panes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    CycleFocus,
+    ToggleHelp,
+    Down,
+    Up,
+    EnterDirectory,
+    ExitDirectory,
+    PageDown,
+    PageUp,
+    ToTop,
+    ToBottom,
+    ToLargest,
+    NextSibling,
+    PreviousSibling,
+    FindPath,
+    Search,
+    SortBySize,
+    SortByCount,
+    SortByName,
+    SortByMTime,
+    ToggleEntryCount,
+    ToggleMTime,
+    ToggleOtherSize,
+    ToggleFooterStats,
+    ToggleHidden,
+    CycleMinSize,
+    CycleEntryKind,
+    ToggleTreemap,
+    CycleByteVisualization,
+    CyclePercentageBase,
+    NarrowBars,
+    WidenBars,
+    ToggleApparentSize,
+    ToggleRawBytes,
+    CycleByteFormat,
+    ShowErrors,
+    ShowMessages,
+    Open,
+    Shell,
+    CopyPath,
+    Rescan,
+    RescanChanged,
+    FindDuplicates,
+    ShowDevices,
+    MarkAndAdvance,
+    Delete,
+    MarkForDeletion,
+    ToggleMark,
+    MarkByPattern,
+    InvertMarks,
+    ClearMarks,
+}
+
+/// The groups actions are listed in by the help, along with their title
+pub const ACTION_GROUPS: &[(&str, &[Action])] = {
+    use Action::*;
+    &[
+        ("Keys for pane control", &[Quit, CycleFocus, ToggleHelp]),
+        (
+            "Keys for Navigation",
+            &[
+                Down,
+                Up,
+                EnterDirectory,
+                ExitDirectory,
+                PageDown,
+                PageUp,
+                ToTop,
+                ToBottom,
+                ToLargest,
+                NextSibling,
+                PreviousSibling,
+                FindPath,
+                Search,
+            ],
+        ),
+        (
+            "Keys for display",
+            &[
+                SortBySize,
+                SortByCount,
+                SortByName,
+                SortByMTime,
+                ToggleEntryCount,
+                ToggleMTime,
+                ToggleOtherSize,
+                ToggleFooterStats,
+                ToggleHidden,
+                CycleMinSize,
+                CycleEntryKind,
+                ToggleTreemap,
+                CycleByteVisualization,
+                CyclePercentageBase,
+                NarrowBars,
+                WidenBars,
+                ToggleApparentSize,
+                ToggleRawBytes,
+                CycleByteFormat,
+                ShowErrors,
+                ShowMessages,
+            ],
+        ),
+        (
+            "Keys for entry operations",
+            &[
+                Open,
+                Shell,
+                CopyPath,
+                Rescan,
+                RescanChanged,
+                FindDuplicates,
+                ShowDevices,
+                MarkAndAdvance,
+                Delete,
+                MarkForDeletion,
+                ToggleMark,
+                MarkByPattern,
+                InvertMarks,
+                ClearMarks,
+            ],
+        ),
+    ]
+};
+
+impl Action {
+    /// The name of the action in the `[keys]` table of the configuration
+    pub fn name(self) -> &'static str {
+        use Action::*;
+        match self {
+            Quit => "quit",
+            CycleFocus => "cycle-focus",
+            ToggleHelp => "toggle-help",
+            Down => "down",
+            Up => "up",
+            EnterDirectory => "enter-directory",
+            ExitDirectory => "exit-directory",
+            PageDown => "page-down",
+            PageUp => "page-up",
+            ToTop => "to-top",
+            ToBottom => "to-bottom",
+            ToLargest => "to-largest",
+            NextSibling => "next-sibling",
+            PreviousSibling => "previous-sibling",
+            FindPath => "find-path",
+            Search => "search",
+            SortBySize => "sort-by-size",
+            SortByCount => "sort-by-count",
+            SortByName => "sort-by-name",
+            SortByMTime => "sort-by-mtime",
+            ToggleEntryCount => "toggle-entry-count",
+            ToggleMTime => "toggle-mtime",
+            ToggleOtherSize => "toggle-other-size",
+            ToggleFooterStats => "toggle-footer-stats",
+            ToggleHidden => "toggle-hidden",
+            CycleMinSize => "cycle-min-size",
+            CycleEntryKind => "cycle-entry-kind",
+            ToggleTreemap => "toggle-treemap",
+            CycleByteVisualization => "cycle-byte-visualization",
+            CyclePercentageBase => "cycle-percentage-base",
+            NarrowBars => "narrow-bars",
+            WidenBars => "widen-bars",
+            ToggleApparentSize => "toggle-apparent-size",
+            ToggleRawBytes => "toggle-raw-bytes",
+            CycleByteFormat => "cycle-byte-format",
+            ShowErrors => "show-errors",
+            ShowMessages => "show-messages",
+            Open => "open",
+            Shell => "shell",
+            CopyPath => "copy-path",
+            Rescan => "rescan",
+            RescanChanged => "rescan-changed",
+            FindDuplicates => "find-duplicates",
+            ShowDevices => "show-devices",
+            MarkAndAdvance => "mark-and-advance",
+            Delete => "delete",
+            MarkForDeletion => "mark-for-deletion",
+            ToggleMark => "toggle-mark",
+            MarkByPattern => "mark-by-pattern",
+            InvertMarks => "invert-marks",
+            ClearMarks => "clear-marks",
+        }
+    }
+
+    fn default_keys(self) -> &'static [Key] {
+        use crosstermion::input::Key::{Backspace, Char, Ctrl, End, Esc, Home, Left, Right};
+        use Action::*;
+        match self {
+            Quit => &[Char('q'), Esc],
+            CycleFocus => &[Char('\t')],
+            ToggleHelp => &[Char('?')],
+            Down => &[Char('j'), Key::Down],
+            Up => &[Char('k'), Key::Up],
+            EnterDirectory => &[Char('o'), Char('l'), Char('\n'), Right],
+            ExitDirectory => &[Char('u'), Char('h'), Left, Backspace],
+            PageDown => &[Ctrl('d'), Key::PageDown],
+            PageUp => &[Ctrl('u'), Key::PageUp],
+            ToTop => &[Char('H'), Home],
+            ToBottom => &[Char('G'), End],
+            ToLargest => &[Char('L')],
+            NextSibling => &[Char('J')],
+            PreviousSibling => &[Char('K')],
+            FindPath => &[Char('f')],
+            Search => &[Char('/')],
+            SortBySize => &[Char('s')],
+            SortByCount => &[Char('C')],
+            SortByName => &[Char('n')],
+            SortByMTime => &[Char('M')],
+            ToggleEntryCount => &[Char('c')],
+            ToggleMTime => &[Char('t')],
+            ToggleOtherSize => &[Char('A')],
+            ToggleFooterStats => &[Char('F')],
+            ToggleHidden => &[Char('.')],
+            CycleMinSize => &[Char('z')],
+            CycleEntryKind => &[Char('v')],
+            ToggleTreemap => &[Char('T')],
+            CycleByteVisualization => &[Char('g')],
+            CyclePercentageBase => &[Char('p')],
+            NarrowBars => &[Char('<')],
+            WidenBars => &[Char('>')],
+            ToggleApparentSize => &[Char('a')],
+            ToggleRawBytes => &[Char('B')],
+            CycleByteFormat => &[Char('U')],
+            ShowErrors => &[Char('e')],
+            ShowMessages => &[Char('E')],
+            Open => &[Char('O')],
+            Shell => &[Char('b')],
+            CopyPath => &[Char('y')],
+            Rescan => &[Char('r')],
+            RescanChanged => &[Char('R')],
+            FindDuplicates => &[Char('D')],
+            ShowDevices => &[Char('V')],
+            MarkAndAdvance => &[Char('m')],
+            Delete => &[Char('d')],
+            MarkForDeletion => &[Char('x')],
+            ToggleMark => &[Char(' ')],
+            MarkByPattern => &[Char('*')],
+            InvertMarks => &[Char('i')],
+            ClearMarks => &[Char('X')],
+        }
+    }
+
+    /// What the action does, in one or two lines
+    pub fn description(self) -> (&'static str, Option<&'static str>) {
+        use Action::*;
+        match self {
+            Quit => (
+                "Close the current pane. Closes the program if no",
+                Some("pane is open"),
+            ),
+            CycleFocus => ("Cycle between the entries and the mark pane", None),
+            ToggleHelp => ("Show or hide this help", None),
+            Down => ("move down an entry", None),
+            Up => ("move up an entry", None),
+            EnterDirectory => ("descent into the selected directory", None),
+            ExitDirectory => ("ascent one level into the parent directory", None),
+            PageDown => ("move down 10 entries at once", None),
+            PageUp => ("move up 10 entries at once", None),
+            ToTop => ("Move to the top of the entries list", None),
+            ToBottom => ("Move to the bottom of the entries list", None),
+            ToLargest => ("Move to the largest of the entries shown", None),
+            NextSibling => (
+                "Show the next directory next to this one, in the",
+                Some("order of the parent directory"),
+            ),
+            PreviousSibling => ("Show the previous directory next to this one", None),
+            FindPath => (
+                "Find any entry by typing parts of its path and jump",
+                Some("to it with <enter>"),
+            ),
+            Search => (
+                "Filter the entries by name while typing, enter",
+                Some("selects the first match and <ESC> clears the filter"),
+            ),
+            SortBySize => ("toggle sort by size ascending/descending", None),
+            SortByCount => ("toggle sort by entry count ascending/descending", None),
+            SortByName => ("toggle sort by name ascending/descending", None),
+            SortByMTime => ("toggle sort by modification time newest/oldest first", None),
+            ToggleEntryCount => ("show or hide the entry count of directories", None),
+            ToggleMTime => (
+                "show or hide how long ago entries were modified, which",
+                Some("is the latest modification within directories"),
+            ),
+            ToggleOtherSize => (
+                "show or hide disk usage next to the apparent size, or the",
+                Some("other way around, to see what compression or sparse files save"),
+            ),
+            ToggleFooterStats => (
+                "show or hide the free space of the filesystem and how",
+                Some("long the scan took in the footer"),
+            ),
+            ToggleHidden => (
+                "show or hide entries whose name starts with a dot,",
+                Some("which still count towards the size of directories"),
+            ),
+            CycleMinSize => (
+                "only show entries of at least 1 MB, 100 MB or 1 GB,",
+                Some("or all of them again"),
+            ),
+            CycleEntryKind => (
+                "only show files, only directories, empty entries or broken",
+                Some("links and the directories containing them, or all again"),
+            ),
+            ToggleTreemap => (
+                "show the entries as treemap, with an area proportional",
+                Some("to their size, or as list again"),
+            ),
+            CycleByteVisualization => (
+                "cycle through percentage display and bar options",
+                Some("with the next key, pressing it twice moves to the top"),
+            ),
+            CyclePercentageBase => (
+                "show percentages of the directory, of all scanned",
+                Some("entries or of the size of the filesystem"),
+            ),
+            NarrowBars => ("make bars narrower", None),
+            WidenBars => ("make bars wider", None),
+            ToggleApparentSize => ("toggle between apparent size and disk usage", None),
+            ToggleRawBytes => ("toggle exact byte counts without a unit", None),
+            CycleByteFormat => (
+                "cycle the unit of sizes through metric and binary units,",
+                Some("bytes, and each single unit from KB to TiB"),
+            ),
+            ShowErrors => (
+                "show the IO errors of the scan, like directories",
+                Some("which couldn't be read and are missing from sizes"),
+            ),
+            ShowMessages => (
+                "show the messages shown so far, like errors of",
+                Some("actions and completed deletions"),
+            ),
+            Open => (
+                "Open the entry with the associated program, which",
+                Some("gets the terminal until it exits"),
+            ),
+            Shell => (
+                "Run your shell in the directory that is shown, until",
+                Some("it exits. Press r afterwards to see changes."),
+            ),
+            CopyPath => (
+                "Copy the absolute path of the selected entry to the clipboard",
+                None,
+            ),
+            Rescan => (
+                "Rescan the directory that is shown to see changes",
+                Some("made outside of dua"),
+            ),
+            RescanChanged => (
+                "Rescan the directories changed since the cached scan was made",
+                Some("when browsing a scan from the cache"),
+            ),
+            FindDuplicates => (
+                "List files with the same content in the directory",
+                Some("that is shown, and jump to one with <enter>"),
+            ),
+            ShowDevices => (
+                "List the mounted filesystems with their free space,",
+                Some("and scan one instead with <enter>"),
+            ),
+            MarkAndAdvance => (
+                "Toggle the currently selected entry and move down",
+                Some("This used to be on 'd', which now deletes"),
+            ),
+            Delete => (
+                "Delete the currently selected entry after confirmation",
+                Some("or move it to the trash").filter(|_| TRASH_SUPPORTED),
+            ),
+            MarkForDeletion => (
+                "Mark for the currently selected entry for deletion and move down",
+                None,
+            ),
+            ToggleMark => ("Toggle the currently selected entry", None),
+            MarkByPattern => (
+                "Mark all entries matching a glob pattern like *.log,",
+                Some("<tab> marks within all directories below too"),
+            ),
+            InvertMarks => ("Toggle the marks of all entries that are shown", None),
+            ClearMarks => ("Unmark all entries", None),
+        }
+    }
+}
+
+/// A command of the configuration to run on the marked entries, defined in a table like `[actions.backup]`.
+#[derive(Debug, Clone)]
+pub struct ExternalAction {
+    /// The name of its table, like `backup`
+    pub name: String,
+    pub keys: Vec<Key>,
+    /// The program to run followed by its arguments, where an argument `{}` stands for all paths
+    pub command: Vec<String>,
+    /// What it does, for the help
+    pub description: Option<String>,
+}
+
+impl ExternalAction {
+    /// Read the action called `name` from its `table`, which has the `key` or keys to run it with and its `command`,
+    /// as array of the program and its arguments, along with an optional `description`.
+    fn from_table(name: &str, table: &Table) -> Result<ExternalAction> {
+        let mut action = ExternalAction {
+            name: name.to_owned(),
+            keys: Vec::new(),
+            command: Vec::new(),
+            description: None,
+        };
+        for (key, value) in table {
+            match key.as_str() {
+                "key" => {
+                    action.keys = match value {
+                        Value::String(name) => vec![name.as_str()],
+                        _ => value.as_strs(key)?,
+                    }
+                    .into_iter()
+                    .map(parse_key)
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| anyhow!("{}", err))?
+                }
+                "command" => {
+                    action.command = value.as_strs(key)?.into_iter().map(Into::into).collect()
+                }
+                "description" => action.description = Some(value.as_str(key)?.to_owned()),
+                _ => bail!("Unknown key '{}' of action '{}'", key, name),
+            }
+        }
+        if action.command.is_empty() {
+            bail!("Action '{}' needs a 'command' to run", name);
+        }
+        Ok(action)
+    }
+
+    /// Return the program to run and its arguments, with `{}` replaced by all `paths`, which are appended if there
+    /// is no `{}`.
+    pub fn command_for(&self, paths: &[PathBuf]) -> (OsString, Vec<OsString>) {
+        let (program, args) = self.command.split_first().expect("a command");
+        let mut with_paths = Vec::new();
+        let mut has_placeholder = false;
+        for arg in args {
+            if arg == "{}" {
+                has_placeholder = true;
+                with_paths.extend(paths.iter().map(|p| p.clone().into_os_string()));
+            } else {
+                with_paths.push(arg.into());
+            }
+        }
+        if !has_placeholder {
+            with_paths.extend(paths.iter().map(|p| p.clone().into_os_string()));
+        }
+        (program.into(), with_paths)
+    }
+}
+
+/// The keys bound to each action.
+#[derive(Clone)]
+pub struct KeyMap {
+    keys: BTreeMap<Action, Vec<Key>>,
+    /// The action of each key, by the name of the key
+    actions: BTreeMap<String, Action>,
+    external: Vec<ExternalAction>,
+    /// The position of the external action of each key in `external`, by the name of the key
+    external_actions: BTreeMap<String, usize>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let keys = ACTION_GROUPS
+            .iter()
+            .flat_map(|(_, actions)| actions.iter())
+            .map(|action| (*action, action.default_keys().to_vec()))
+            .collect();
+        KeyMap::new(keys, Vec::new()).expect("default keys don't conflict")
+    }
+}
+
+impl KeyMap {
+    fn new(keys: BTreeMap<Action, Vec<Key>>, external: Vec<ExternalAction>) -> Result<KeyMap> {
+        let mut actions = BTreeMap::new();
+        for (action, keys) in &keys {
+            for key in keys {
+                let name = key_name(*key);
+                if is_reserved(*key) {
+                    bail!("'{}' can't be bound to '{}'", name, action.name());
+                }
+                if let Some(other) = actions.insert(name.clone(), *action) {
+                    bail!(
+                        "'{}' is bound to both '{}' and '{}'",
+                        name,
+                        other.name(),
+                        action.name()
+                    );
+                }
+            }
+        }
+        let mut external_actions = BTreeMap::new();
+        for (pos, action) in external.iter().enumerate() {
+            for key in &action.keys {
+                let name = key_name(*key);
+                if is_reserved(*key) {
+                    bail!("'{}' can't be bound to '{}'", name, action.name);
+                }
+                if let Some(other) = actions.get(&name).map(|other| other.name()).or_else(|| {
+                    external_actions
+                        .get(&name)
+                        .map(|other: &usize| external[*other].name.as_str())
+                }) {
+                    bail!(
+                        "'{}' is bound to both '{}' and '{}'",
+                        name,
+                        other,
+                        action.name
+                    );
+                }
+                external_actions.insert(name, pos);
+            }
+        }
+        Ok(KeyMap {
+            keys,
+            actions,
+            external,
+            external_actions,
+        })
+    }
+
+    /// Return the default keys, with the actions in the `[keys]` table of `config` bound to the keys given there
+    /// instead.
+    ///
+    /// Each action can be bound to a single key or an array of them, named like `j`, `ctrl-d`, `alt-x`, `enter`,
+    /// `space` or `pagedown`. External actions of `[actions.<name>]` tables are bound the same way. It's an error if a
+    /// key ends up being bound to more than one action.
+    pub fn from_config(config: &Config) -> Result<KeyMap> {
+        let mut keys = KeyMap::default().keys;
+        for (name, value) in config.tables.get("keys").into_iter().flatten() {
+            let action = keys
+                .keys()
+                .find(|action| action.name() == name)
+                .copied()
+                .ok_or_else(|| anyhow!("Unknown action '{}'", name))?;
+            let names = match value {
+                Value::String(name) => vec![name.as_str()],
+                _ => value.as_strs(name)?,
+            };
+            keys.insert(
+                action,
+                names
+                    .into_iter()
+                    .map(parse_key)
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| anyhow!("{}", err))?,
+            );
+        }
+        let external = config
+            .subtables("actions")
+            .map(|(name, table)| ExternalAction::from_table(name, table))
+            .collect::<Result<_>>()?;
+        KeyMap::new(keys, external)
+    }
+
+    /// Return the action bound to `key`, if there is one.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.actions.get(&key_name(key)).copied()
+    }
+
+    /// Return the external action bound to `key`, if there is one.
+    pub fn external_action(&self, key: Key) -> Option<&ExternalAction> {
+        self.external_actions
+            .get(&key_name(key))
+            .map(|pos| &self.external[*pos])
+    }
+
+    /// Return all external actions of the configuration.
+    pub fn external_actions(&self) -> &[ExternalAction] {
+        &self.external
+    }
+
+    /// Return all keys bound to `action`.
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.keys
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return a short name of the first key bound to `action` for hints in the border of panes, or `-` if there is
+    /// none.
+    pub fn hint(&self, action: Action) -> String {
+        match self.keys(action).first() {
+            Some(Key::Ctrl(c)) => format!("CTRL+{}", c),
+            Some(key) => key_name(*key),
+            None => "-".into(),
+        }
+    }
+}
+
+/// Return true if `key` can't be bound to any action as it's always used for something else.
+fn is_reserved(key: Key) -> bool {
+    use crosstermion::input::Key::*;
+    matches!(key, Ctrl('c') | Char('1'..='9'))
+}
+
+/// Parse a key by the name it has in the configuration, like `j`, `ctrl-d` or `pagedown`.
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    use crosstermion::input::Key::*;
+    let single_char = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(c) = single_char(name) {
+        return Ok(Char(c));
+    }
+    let lowercase = name.to_lowercase();
+    let modified = |prefix: &str| {
+        lowercase
+            .strip_prefix(prefix)
+            .and_then(|_| single_char(&name[prefix.len()..]))
+    };
+    if let Some(c) = modified("ctrl-") {
+        return Ok(Ctrl(c.to_ascii_lowercase()));
+    }
+    if let Some(c) = modified("alt-") {
+        return Ok(Alt(c));
+    }
+    Ok(match lowercase.as_str() {
+        "space" => Char(' '),
+        "enter" => Char('\n'),
+        "tab" => Char('\t'),
+        "backtab" => BackTab,
+        "esc" => Esc,
+        "backspace" => Backspace,
+        "delete" => Delete,
+        "insert" => Insert,
+        "left" => Left,
+        "right" => Right,
+        "up" => Up,
+        "down" => Down,
+        "home" => Home,
+        "end" => End,
+        "pageup" => PageUp,
+        "pagedown" => PageDown,
+        f => match f.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            Some(n) if (1..=12).contains(&n) => F(n),
+            _ => return Err(format!("Invalid key {:?}", name)),
+        },
+    })
+}
+
+/// The name of `key` in the configuration, which [`parse_key()`] turns back into it.
+pub fn key_name(key: Key) -> String {
+    use crosstermion::input::Key::*;
+    match key {
+        Char(' ') => "space".into(),
+        Char('\n') => "enter".into(),
+        Char('\t') => "tab".into(),
+        Char(c) => c.to_string(),
+        Ctrl(c) => format!("ctrl-{}", c),
+        Alt(c) => format!("alt-{}", c),
+        BackTab => "backtab".into(),
+        Esc => "esc".into(),
+        Backspace => "backspace".into(),
+        Delete => "delete".into(),
+        Insert => "insert".into(),
+        Left => "left".into(),
+        Right => "right".into(),
+        Up => "up".into(),
+        Down => "down".into(),
+        Home => "home".into(),
+        End => "end".into(),
+        PageUp => "pageup".into(),
+        PageDown => "pagedown".into(),
+        F(n) => format!("f{}", n),
+        Null => "null".into(),
+    }
+}
+
+/// A label of `key` for the help, like `j`, `Ctrl + d` or `<Page Down>`.
+pub fn key_label(key: Key) -> String {
+    use crosstermion::input::Key::*;
+    match key {
+        Char(' ') => "<space>".into(),
+        Char('\n') => "<enter>".into(),
+        Char('\t') => "<tab>".into(),
+        Char(c) => c.to_string(),
+        Ctrl(c) => format!("Ctrl + {}", c),
+        Alt(c) => format!("Alt + {}", c),
+        Esc => "<ESC>".into(),
+        Home => "<Home>".into(),
+        End => "<End>".into(),
+        PageUp => "<Page Up>".into(),
+        PageDown => "<Page Down>".into(),
+        BackTab => "<Shift + tab>".into(),
+        F(n) => format!("<F{}>", n),
+        other => format!("<{}>", key_name(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse;
+
+    #[test]
+    fn keys_are_parsed_by_their_name() {
+        for name in [
+            "j", "G", "ctrl-d", "alt-x", "space", "enter", "pagedown", "f5", "esc",
+        ] {
+            assert_eq!(key_name(parse_key(name).unwrap()), name);
+        }
+        assert_eq!(key_name(parse_key("Ctrl-D").unwrap()), "ctrl-d");
+        assert_eq!(key_name(parse_key("PageUp").unwrap()), "pageup");
+        assert!(parse_key("ctrl-").is_err());
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("hyper-x").is_err());
+    }
+
+    #[test]
+    fn actions_can_be_bound_to_other_keys() {
+        let keymap = |content: &str| KeyMap::from_config(&parse(content).unwrap());
+        let keys = keymap(
+            r#"
+            [keys]
+            down = ["n", "down"]
+            sort-by-name = "N"
+            quit = "ctrl-q"
+            open = []
+            "#,
+        )
+        .unwrap();
+        assert_eq!(keys.action(Key::Char('n')), Some(Action::Down));
+        assert_eq!(keys.action(Key::Down), Some(Action::Down));
+        assert_eq!(keys.action(Key::Char('j')), None, "defaults are replaced");
+        assert_eq!(keys.action(Key::Char('N')), Some(Action::SortByName));
+        assert_eq!(keys.action(Key::Ctrl('q')), Some(Action::Quit));
+        assert_eq!(keys.action(Key::Esc), None);
+        assert_eq!(keys.hint(Action::Quit), "CTRL+q");
+        assert_eq!(keys.hint(Action::Open), "-");
+        assert_eq!(
+            KeyMap::default().action(Key::Char('?')),
+            Some(Action::ToggleHelp)
+        );
+        let keys = keymap("[keys]\nmark-and-advance = \"d\"\ndelete = \"alt-d\"").unwrap();
+        assert_eq!(
+            keys.action(Key::Char('d')),
+            Some(Action::MarkAndAdvance),
+            "marking can be bound to 'd' again"
+        );
+
+        for (input, expected) in [
+            (
+                "[keys]\ndown = \"k\"",
+                "'k' is bound to both 'down' and 'up'",
+            ),
+            ("[keys]\nfly = \"f\"", "Unknown action 'fly'"),
+            (
+                "[keys]\nquit = \"ctrl-c\"",
+                "'ctrl-c' can't be bound to 'quit'",
+            ),
+            ("[keys]\nquit = \"3\"", "'3' can't be bound to 'quit'"),
+            ("[keys]\nquit = \"hyper-q\"", "Invalid key \"hyper-q\""),
+            ("[keys]\nquit = 1", "'quit' must be an array of strings"),
+        ] {
+            assert_eq!(keymap(input).err().unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn external_actions_are_bound_to_keys_and_get_the_paths() {
+        let keymap = |content: &str| KeyMap::from_config(&parse(content).unwrap());
+        let keys = keymap(
+            r#"
+            [actions.backup]
+            key = "S"
+            command = ["rsync", "-a", "{}", "/mnt/backup/"]
+            description = "Copy to the backup drive"
+            [actions.pack]
+            key = ["P", "alt-p"]
+            command = ["tar", "czf", "marked.tgz"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(keys.external_actions().len(), 2);
+        let backup = keys.external_action(Key::Char('S')).unwrap();
+        assert_eq!(backup.name, "backup");
+        assert_eq!(
+            backup.command_for(&[PathBuf::from("a"), PathBuf::from("b c")]),
+            (
+                "rsync".into(),
+                vec!["-a".into(), "a".into(), "b c".into(), "/mnt/backup/".into()]
+            )
+        );
+        let pack = keys.external_action(Key::Alt('p')).unwrap();
+        assert_eq!(
+            pack.command_for(&[PathBuf::from("a")]),
+            (
+                "tar".into(),
+                vec!["czf".into(), "marked.tgz".into(), "a".into()]
+            ),
+            "paths are appended without placeholder"
+        );
+        assert!(keys.external_action(Key::Char('j')).is_none());
+
+        for (input, expected) in [
+            (
+                "[actions.list]\nkey = \"k\"\ncommand = [\"ls\"]",
+                "'k' is bound to both 'up' and 'list'",
+            ),
+            (
+                "[actions.a]\nkey = \"Z\"\ncommand = [\"true\"]\n[actions.b]\nkey = \"Z\"\ncommand = [\"true\"]",
+                "'Z' is bound to both 'a' and 'b'",
+            ),
+            ("[actions.a]\nkey = \"Z\"", "Action 'a' needs a 'command' to run"),
+            (
+                "[actions.a]\nkey = \"ctrl-c\"\ncommand = [\"true\"]",
+                "'ctrl-c' can't be bound to 'a'",
+            ),
+            (
+                "[actions.a]\nkeys = \"Z\"\ncommand = [\"true\"]",
+                "Unknown key 'keys' of action 'a'",
+            ),
+        ] {
+            assert_eq!(keymap(input).err().unwrap().to_string(), expected);
+        }
+    }
+}
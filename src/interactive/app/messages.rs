@@ -0,0 +1,99 @@
+//! Messages telling how actions went, shown in the footer until the next key is pressed and kept in a log to look at
+//! later.
+use std::{collections::VecDeque, ops::Deref, time::SystemTime};
+
+/// The most messages kept in the log, older ones are dropped
+pub const MAX_LOGGED_MESSAGES: usize = 500;
+
+/// How much a message matters, which decides how it is styled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something was done as asked, or there is something to know
+    Info,
+    /// Something wasn't done, as it can't be done right now or needs to be done differently
+    Warning,
+    /// Something failed
+    Error,
+}
+
+impl Severity {
+    /// The label of the severity in the log
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub text: String,
+    pub severity: Severity,
+    /// The time the message was shown at
+    pub at: SystemTime,
+}
+
+impl Message {
+    pub fn new(text: impl Into<String>, severity: Severity) -> Self {
+        Message {
+            text: text.into(),
+            severity,
+            at: SystemTime::now(),
+        }
+    }
+}
+
+impl Deref for Message {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+/// The messages shown so far, oldest first
+#[derive(Debug, Default)]
+pub struct MessageLog(VecDeque<Message>);
+
+impl MessageLog {
+    /// Add `message`, dropping the oldest one if there are [`MAX_LOGGED_MESSAGES`] already.
+    pub fn push(&mut self, message: Message) {
+        if self.0.len() == MAX_LOGGED_MESSAGES {
+            self.0.pop_front();
+        }
+        self.0.push_back(message);
+    }
+
+    /// The messages with the latest one first
+    pub fn latest_first(&self) -> impl Iterator<Item = &Message> {
+        self.0.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_log_keeps_the_latest_messages() {
+        let mut log = MessageLog::default();
+        for n in 0..MAX_LOGGED_MESSAGES + 2 {
+            log.push(Message::new(n.to_string(), Severity::Info));
+        }
+        assert_eq!(log.len(), MAX_LOGGED_MESSAGES);
+        assert_eq!(
+            log.latest_first().map(|m| &**m).take(2).collect::<Vec<_>>(),
+            vec![
+                (MAX_LOGGED_MESSAGES + 1).to_string(),
+                MAX_LOGGED_MESSAGES.to_string()
+            ]
+        );
+        assert_eq!(log.latest_first().last().map(|m| &**m), Some("2"));
+    }
+}
@@ -1,12 +1,29 @@
 mod bytevis;
+mod cache;
+mod clipboard;
+mod columns;
 mod common;
+mod deletion;
 mod eventloop;
 mod handlers;
+mod input;
+mod keymap;
+mod messages;
+mod session;
+mod trash;
 
 pub use bytevis::*;
+pub use cache::*;
+pub use columns::*;
 pub use common::*;
+pub use deletion::*;
 pub use eventloop::*;
 pub use handlers::*;
+pub use input::*;
+pub use keymap::*;
+pub use messages::*;
+pub use session::*;
+pub use trash::TRASH_SUPPORTED;
 
 #[cfg(test)]
 mod tests;
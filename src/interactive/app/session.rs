@@ -0,0 +1,342 @@
+//! Saving what interactive mode shows when quitting, to resume cleaning up where it was left off.
+//!
+//! Sessions are kept in `$XDG_STATE_HOME/dua/sessions` or `~/.local/state/dua/sessions`, one per set of input paths.
+//! Each is a text file starting with [`HEADER`], followed by a line per setting like `sort name-ascending` or
+//! `mark /home/user/Downloads/big.iso`. Paths are absolute to be found again from any working directory. The scan may
+//! be kept next to it as snapshot, to browse it again without scanning.
+use crate::interactive::{SortMode, TerminalApp};
+use anyhow::{anyhow, bail, Context, Result};
+use dua::{
+    traverse::{Traversal, TreeIndex},
+    WalkOptions,
+};
+use petgraph::Direction;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The first line of every session file
+const HEADER: &str = "dua-session 1";
+
+/// What was shown when quitting
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// The directory that was shown, or `None` if it were the input paths
+    pub root: Option<PathBuf>,
+    pub selected: Option<PathBuf>,
+    pub sorting: SortMode,
+    pub marked: Vec<PathBuf>,
+}
+
+impl Session {
+    /// Remember what `app` shows.
+    pub fn capture(app: &TerminalApp) -> Session {
+        let paths = AbsolutePaths::new(&app.traversal);
+        Session {
+            root: Some(app.state.root)
+                .filter(|root| *root != app.traversal.root_index)
+                .map(|root| paths.path_of(root)),
+            selected: app.state.selected.map(|idx| paths.path_of(idx)),
+            sorting: app.state.sorting,
+            marked: app
+                .window
+                .mark_pane
+                .as_ref()
+                .map(|pane| {
+                    pane.marked_sorted_by_index()
+                        .into_iter()
+                        .map(|(idx, _)| paths.path_of(*idx))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Show what this session showed in `app`, as far as its entries still exist, and return the amount of those
+    /// which don't.
+    pub fn restore(&self, app: &mut TerminalApp) -> usize {
+        let paths = AbsolutePaths::new(&app.traversal);
+        let tree = &app.traversal.tree;
+        let mut num_missing = 0;
+        let mut index_of = |path: &Path| {
+            let idx = paths.index_of(path);
+            num_missing += idx.is_none() as usize;
+            idx
+        };
+
+        let state = &mut app.state;
+        state.sorting = self.sorting;
+        state.root = match &self.root {
+            Some(root) => index_of(root)
+                .filter(|idx| tree[*idx].is_dir)
+                .unwrap_or(app.traversal.root_index),
+            None => app.traversal.root_index,
+        };
+        state.refresh_entries(&app.traversal);
+        state.selected = self
+            .selected
+            .as_deref()
+            .and_then(&mut index_of)
+            .filter(|idx| state.entries.iter().any(|e| e.index == *idx))
+            .or_else(|| state.entries.first().map(|e| e.index));
+        app.window.mark_pane = None;
+        for path in &self.marked {
+            if let Some(idx) = index_of(path) {
+                app.window.mark_pane = app
+                    .window
+                    .mark_pane
+                    .take()
+                    .unwrap_or_default()
+                    .toggle_index(idx, tree, tree[idx].is_dir, false);
+            }
+        }
+        num_missing
+    }
+
+    fn write(&self, mut out: impl io::Write) -> io::Result<()> {
+        writeln!(out, "{}", HEADER)?;
+        if let Some(root) = &self.root {
+            writeln!(out, "root {}", escape(root))?;
+        }
+        if let Some(selected) = &self.selected {
+            writeln!(out, "selected {}", escape(selected))?;
+        }
+        writeln!(out, "sort {}", self.sorting.name())?;
+        for path in &self.marked {
+            writeln!(out, "mark {}", escape(path))?;
+        }
+        out.flush()
+    }
+
+    fn parse(content: &str) -> Result<Session> {
+        let mut lines = content.lines();
+        if lines.next() != Some(HEADER) {
+            bail!("Not a session of this version of dua");
+        }
+        let mut session = Session::default();
+        for line in lines {
+            let (key, value) = line
+                .split_once(' ')
+                .ok_or_else(|| anyhow!("Invalid line {:?}", line))?;
+            match key {
+                "root" => session.root = Some(unescape(value)),
+                "selected" => session.selected = Some(unescape(value)),
+                "sort" => session.sorting = value.parse().map_err(|err| anyhow!("{}", err))?,
+                "mark" => session.marked.push(unescape(value)),
+                _ => bail!("Unknown setting '{}'", key),
+            }
+        }
+        Ok(session)
+    }
+}
+
+/// The files the session of some input paths is kept in
+#[derive(Debug, Clone)]
+pub struct SessionFiles {
+    pub session: PathBuf,
+    /// The scan of the session, to browse it again without scanning
+    pub snapshot: PathBuf,
+}
+
+impl SessionFiles {
+    /// Return the files of the session of `input_paths`, or `None` if there is no directory to keep them in.
+    pub fn of(input_paths: &[PathBuf]) -> Option<SessionFiles> {
+        let dir = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state"))
+            })?;
+        Some(Self::in_dir(&dir.join("dua").join("sessions"), input_paths))
+    }
+
+    /// Return the files of the session of `input_paths` in `dir`, named after a hash of their canonical paths.
+    fn in_dir(dir: &Path, input_paths: &[PathBuf]) -> SessionFiles {
+        let hash = hash_of_input_paths(input_paths);
+        SessionFiles {
+            session: dir.join(format!("{:016x}.session", hash)),
+            snapshot: dir.join(format!("{:016x}.snapshot", hash)),
+        }
+    }
+
+    /// Read the session, or return `None` if there is none.
+    pub fn load(&self) -> Result<Option<Session>> {
+        match fs::read_to_string(&self.session) {
+            Ok(content) => Session::parse(&content)
+                .map(Some)
+                .with_context(|| format!("Could not read session '{}'", self.session.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(anyhow::Error::from(err))
+                .with_context(|| format!("Could not read session '{}'", self.session.display())),
+        }
+    }
+
+    /// Read the scan saved with the session, or return `None` if there is none.
+    pub fn load_snapshot(&self, walk_options: &WalkOptions) -> Result<Option<Traversal>> {
+        match fs::read(&self.snapshot) {
+            Ok(content) => dua::snapshot::load(&content, walk_options)
+                .map(Some)
+                .with_context(|| format!("Could not load scan '{}'", self.snapshot.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(anyhow::Error::from(err))
+                .with_context(|| format!("Could not load scan '{}'", self.snapshot.display())),
+        }
+    }
+
+    /// Save what `app` shows, along with its scan if `with_snapshot` is set and it's complete.
+    pub fn save(&self, app: &TerminalApp, with_snapshot: bool) -> Result<()> {
+        let context = || format!("Could not save session '{}'", self.session.display());
+        if let Some(dir) = self.session.parent() {
+            fs::create_dir_all(dir).with_context(context)?;
+        }
+        Session::capture(app)
+            .write(io::BufWriter::new(
+                fs::File::create(&self.session).with_context(context)?,
+            ))
+            .with_context(context)?;
+        if with_snapshot && !app.traversal.is_incomplete {
+            dua::snapshot::save(
+                io::BufWriter::new(fs::File::create(&self.snapshot).with_context(context)?),
+                &app.traversal,
+            )
+            .with_context(context)?;
+        }
+        Ok(())
+    }
+}
+
+/// A hash of the canonical `input_paths`, to name the files kept for them.
+pub(super) fn hash_of_input_paths(input_paths: &[PathBuf]) -> u64 {
+    input_paths.iter().fold(FNV_OFFSET_BASIS, |hash, path| {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+        fnv1a(hash, path.to_string_lossy().bytes().chain(Some(0)))
+    })
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continue `hash` with `bytes` using FNV-1a, as the hashers of the standard library may change between releases.
+pub(super) fn fnv1a(hash: u64, bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(hash, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Absolute paths of entries, made of the canonical path of the input path they are in followed by the names of the
+/// entries below it.
+struct AbsolutePaths<'a> {
+    traversal: &'a Traversal,
+    inputs: Vec<(TreeIndex, PathBuf)>,
+}
+
+impl<'a> AbsolutePaths<'a> {
+    fn new(traversal: &'a Traversal) -> Self {
+        let inputs = traversal
+            .tree
+            .neighbors_directed(traversal.root_index, Direction::Outgoing)
+            .map(|idx| {
+                let path = traversal.tree.name_of(idx);
+                (idx, path.canonicalize().unwrap_or_else(|_| path.to_owned()))
+            })
+            .collect();
+        AbsolutePaths { traversal, inputs }
+    }
+
+    fn path_of(&self, mut idx: TreeIndex) -> PathBuf {
+        let tree = &self.traversal.tree;
+        let mut names = Vec::new();
+        loop {
+            if let Some((_, input)) = self.inputs.iter().find(|(input, _)| *input == idx) {
+                return names
+                    .iter()
+                    .rev()
+                    .fold(input.clone(), |path, name| path.join(name));
+            }
+            names.push(tree.name_of(idx));
+            match tree.neighbors_directed(idx, Direction::Incoming).next() {
+                Some(parent) => idx = parent,
+                None => return names.iter().rev().collect(),
+            }
+        }
+    }
+
+    fn index_of(&self, path: &Path) -> Option<TreeIndex> {
+        let tree = &self.traversal.tree;
+        self.inputs.iter().find_map(|(input_idx, input)| {
+            let below = path.strip_prefix(input).ok()?;
+            below.components().try_fold(*input_idx, |idx, name| {
+                let name = tree.names.get(Path::new(name.as_os_str()))?;
+                tree.neighbors_directed(idx, Direction::Outgoing)
+                    .find(|child| tree[*child].name == name)
+            })
+        })
+    }
+}
+
+fn escape(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> PathBuf {
+    let mut path = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                path.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                path.push('\\');
+                chars.next();
+            }
+            (c, _) => path.push(c),
+        }
+    }
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_are_written_and_parsed_with_escaped_paths() -> Result<()> {
+        let session = Session {
+            root: Some("/home/user/dir".into()),
+            selected: Some("/home/user/dir/new\nline".into()),
+            sorting: SortMode::NameDescending,
+            marked: vec!["/home/user/dir/back\\slash".into(), "/home/user/a".into()],
+        };
+        let mut out = Vec::new();
+        session.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out.clone())?,
+            "dua-session 1\nroot /home/user/dir\nselected /home/user/dir/new\\nline\n\
+             sort name-descending\nmark /home/user/dir/back\\\\slash\nmark /home/user/a\n"
+        );
+        assert_eq!(Session::parse(std::str::from_utf8(&out)?)?, session);
+        assert_eq!(
+            Session::parse("dua-session 1\n")?,
+            Session::default(),
+            "all lines are optional"
+        );
+        assert!(Session::parse("dua-session 2\n").is_err());
+        assert!(Session::parse("dua-session 1\nzoom 2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn session_files_are_named_after_the_input_paths() {
+        let dir = Path::new("sessions");
+        let a = SessionFiles::in_dir(dir, &[PathBuf::from("src")]);
+        let b = SessionFiles::in_dir(dir, &[std::env::current_dir().unwrap().join("src")]);
+        assert_eq!(a.session, b.session, "paths are made canonical");
+        assert_eq!(a.snapshot.with_extension("session"), a.session);
+        let c = SessionFiles::in_dir(dir, &[PathBuf::from("src"), PathBuf::from("tests")]);
+        assert_ne!(a.session, c.session);
+    }
+}
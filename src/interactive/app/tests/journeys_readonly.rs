@@ -5,12 +5,14 @@ use std::ffi::OsString;
 use crate::interactive::{
     app::tests::{
         utils::{
-            fixture_str, index_by_name, initialized_app_and_terminal_from_fixture, into_keys,
-            node_by_index, node_by_name,
+            fixture_str, index_by_name, initialized_app_and_terminal_from_filesystem,
+            initialized_app_and_terminal_from_fixture,
+            initialized_app_and_terminal_from_fixture_with_options, into_keys, node_by_index,
+            node_by_name,
         },
         FIXTURE_PATH,
     },
-    SortMode,
+    Column, EntryKind, FocussedPane, SortMode,
 };
 
 #[test]
@@ -44,7 +46,10 @@ fn simple_user_journey_read_only() -> Result<()> {
 
         let first_selected_path = OsString::from(format!("{}/{}", FIXTURE_PATH, long_root));
         assert_eq!(
-            node_by_name(&app, &first_selected_path).name,
+            app.traversal
+                .tree
+                .name_of(index_by_name(&app, &first_selected_path))
+                .as_os_str(),
             first_selected_path,
             "the roots are always listed with the given (possibly long) names",
         );
@@ -163,12 +168,12 @@ fn simple_user_journey_read_only() -> Result<()> {
         }
     }
 
-    // Deletion
+    // Marking for deletion
     {
-        // when hitting the 'd' key (also move cursor back to start)
+        // when hitting the 'm' key (also move cursor back to start)
         app.process_events(&mut terminal, into_keys(b"k".iter()))?;
         let previously_selected_index = *app.state.selected.as_ref().unwrap();
-        app.process_events(&mut terminal, into_keys(b"d".iter()))?;
+        app.process_events(&mut terminal, into_keys(b"m".iter()))?;
         {
             assert_eq!(
                 Some(1),
@@ -176,9 +181,10 @@ fn simple_user_journey_read_only() -> Result<()> {
                 "it marks only a single node",
             );
             assert!(
-                app.window.mark_pane.as_ref().map_or(false, |p| p
-                    .marked()
-                    .contains_key(&previously_selected_index)),
+                app.window
+                    .mark_pane
+                    .as_ref()
+                    .is_some_and(|p| p.marked().contains_key(&previously_selected_index)),
                 "it marks the selected node"
             );
             assert_eq!(
@@ -188,9 +194,9 @@ fn simple_user_journey_read_only() -> Result<()> {
             );
         }
 
-        // when hitting the 'd' key again
+        // when hitting the 'm' key again
         {
-            app.process_events(&mut terminal, into_keys(b"d".iter()))?;
+            app.process_events(&mut terminal, into_keys(b"m".iter()))?;
 
             assert_eq!(
                 Some(2),
@@ -205,9 +211,9 @@ fn simple_user_journey_read_only() -> Result<()> {
             );
         }
 
-        // when hitting the 'd' key once again
+        // when hitting the 'm' key once again
         {
-            app.process_events(&mut terminal, into_keys(b"d".iter()))?;
+            app.process_events(&mut terminal, into_keys(b"m".iter()))?;
 
             assert_eq!(
                 Some(1),
@@ -216,9 +222,10 @@ fn simple_user_journey_read_only() -> Result<()> {
             );
 
             assert!(
-                app.window.mark_pane.as_ref().map_or(false, |p| p
-                    .marked()
-                    .contains_key(&previously_selected_index)),
+                app.window
+                    .mark_pane
+                    .as_ref()
+                    .is_some_and(|p| p.marked().contains_key(&previously_selected_index)),
                 "it leaves the first selected entry marked"
             );
         }
@@ -274,3 +281,1680 @@ fn simple_user_journey_read_only() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn it_toggles_between_apparent_size_and_disk_usage() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-02"])?;
+    let apparent_size = app.traversal.total_bytes;
+    assert_eq!(apparent_size, Some(1540), "it starts with apparent sizes");
+
+    // when hitting the a key
+    app.process_events(&mut terminal, into_keys(b"a".iter()))?;
+    assert!(!app.traversal.apparent_size, "it shows the disk usage");
+    assert_eq!(
+        app.traversal.total_bytes,
+        Some(
+            app.state
+                .entries
+                .iter()
+                .map(|e| u128::from(e.data.size))
+                .sum()
+        ),
+        "the total matches the sum of the disk usage of all entries"
+    );
+
+    // when hitting the a key again
+    app.process_events(&mut terminal, into_keys(b"a".iter()))?;
+    assert!(app.traversal.apparent_size);
+    assert_eq!(
+        app.traversal.total_bytes, apparent_size,
+        "it shows apparent sizes again"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_counts_entries_and_sorts_by_count() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    assert_eq!(
+        node_by_name(&app, fixture_str("sample-01")).entry_count,
+        13,
+        "directories know about all entries within them, recursively"
+    );
+
+    // when entering the root and hitting the C key
+    app.process_events(&mut terminal, into_keys(b"oC".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::CountDescending);
+    assert_eq!(
+        node_by_index(&app, app.state.entries[0].index),
+        node_by_name(&app, "dir"),
+        "the directory with most entries comes first"
+    );
+    assert_eq!(node_by_name(&app, "dir").entry_count, 7);
+
+    // when hitting the C key again
+    app.process_events(&mut terminal, into_keys(b"C".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::CountAscending);
+
+    // when hitting the s key
+    app.process_events(&mut terminal, into_keys(b"s".iter()))?;
+    assert_eq!(
+        app.state.sorting,
+        SortMode::SizeDescending,
+        "it goes back to sorting by size"
+    );
+    Ok(())
+}
+
+#[test]
+fn inodes_can_be_counted_instead_of_bytes() -> Result<()> {
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_fixture_with_options(&["sample-01"], |options| {
+            options.count_inodes = true;
+            options.byte_format = dua::ByteFormat::Inodes;
+        })?;
+    assert_eq!(
+        app.traversal.total_bytes,
+        Some(14),
+        "the input directory takes one inode along with each of its 13 entries"
+    );
+    assert_eq!(
+        node_by_name(&app, "dir").size,
+        8,
+        "directories count their own inode"
+    );
+
+    // when entering the root
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(
+        node_by_index(&app, app.state.entries[0].index),
+        node_by_name(&app, "dir"),
+        "the entry using the most inodes comes first"
+    );
+    let screen = terminal
+        .backend
+        .buffer()
+        .content
+        .iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect::<String>();
+    assert!(screen.contains("Total inodes: 14"), "{}", screen);
+    Ok(())
+}
+
+#[test]
+fn the_smallest_files_are_collapsed_above_the_maximum_of_entries() -> Result<()> {
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_fixture_with_options(&["sample-01"], |options| {
+            options.max_entries = Some(12);
+        })?;
+    assert!(app.traversal.tree.node_count() <= 12);
+    assert!(
+        app.state
+            .message
+            .as_deref()
+            .is_some_and(|m| m.ends_with("files were collapsed to keep no more than 12 entries")),
+        "{:?}",
+        app.state.message.as_deref()
+    );
+    assert_eq!(
+        node_by_name(&app, fixture_str("sample-01")).entry_count,
+        13,
+        "collapsed files are still counted"
+    );
+
+    // when entering the root and selecting the collapsed files
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    let collapsed = app
+        .state
+        .entries
+        .iter()
+        .find(|e| e.data.is_collapsed)
+        .expect("collapsed files in the root")
+        .index;
+    let name = app
+        .traversal
+        .tree
+        .name_of(collapsed)
+        .to_string_lossy()
+        .into_owned();
+    assert!(
+        name.starts_with("(… ") && name.contains("more entries"),
+        "{}",
+        name
+    );
+    app.state.selected = Some(collapsed);
+
+    // they can't be marked
+    app.process_events(&mut terminal, into_keys(b" ".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Collapsed files can only be deleted along with their directory")
+    );
+    assert!(app.window.mark_pane.is_none());
+    Ok(())
+}
+
+#[test]
+fn it_shows_the_other_size_next_to_the_one_sizes_are_sorted_by() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        buf.content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect::<String>()
+    };
+    for index in app.traversal.tree.node_indices().collect::<Vec<_>>() {
+        app.traversal.tree[index].alternate_size = 123_456_789;
+    }
+
+    // when entering the root
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(!app.display.columns.is_shown(Column::OtherSize));
+    assert!(
+        !screen(&terminal).contains("123.46 MB"),
+        "the other size is hidden by default"
+    );
+
+    // when hitting the A key
+    app.process_events(&mut terminal, into_keys(b"A".iter()))?;
+    assert!(app.display.columns.is_shown(Column::OtherSize));
+    assert!(
+        screen(&terminal).contains("123.46 MB"),
+        "it shows the other size of entries"
+    );
+
+    // when hitting the A key again
+    app.process_events(&mut terminal, into_keys(b"A".iter()))?;
+    assert!(
+        !screen(&terminal).contains("123.46 MB"),
+        "it's hidden again"
+    );
+    Ok(())
+}
+
+#[test]
+fn the_position_of_the_selected_entry_is_shown_below_the_entries() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let bottom_border = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, buf.area.height - 2).symbol.as_str())
+            .collect::<String>()
+    };
+
+    // when entering the root and moving down
+    app.process_events(&mut terminal, into_keys(b"oj".iter()))?;
+    let count = app.state.entries.len();
+    assert!(
+        bottom_border(&terminal).contains(&format!(" 2/{} ", count)),
+        "{}",
+        bottom_border(&terminal)
+    );
+
+    // when moving to the bottom
+    app.process_events(&mut terminal, into_keys(b"G".iter()))?;
+    assert!(bottom_border(&terminal).contains(&format!(" {0}/{0} ", count)));
+    Ok(())
+}
+
+#[test]
+fn the_footer_can_show_the_free_space_and_how_long_the_scan_took() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(140, 20))?;
+    let footer = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, buf.area.height - 1).symbol.as_str())
+            .collect::<String>()
+    };
+    assert!(app.state.scan_duration.is_some(), "the scan was timed");
+    app.state.devices = Some(vec![dua::Device {
+        filesystem: "/dev/vda".into(),
+        mount_point: "/".into(),
+        total: 2_000_000,
+        used: 1_500_000,
+        available: 500_000,
+    }]);
+
+    // when drawing the app
+    app.process_events(&mut terminal, into_keys(b"".iter()))?;
+    assert!(
+        !footer(&terminal).contains("Free"),
+        "the stats are hidden by default"
+    );
+
+    // when hitting the F key
+    app.process_events(&mut terminal, into_keys(b"F".iter()))?;
+    assert!(app.display.show_footer_stats);
+    let text = footer(&terminal);
+    assert!(text.contains("Free: 500.00 KB of 2.00 MB"), "{}", text);
+    assert!(text.contains("Scanned in "), "{}", text);
+    assert!(text.contains(" entries/s)"), "{}", text);
+
+    // when entering a directory
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(
+        footer(&terminal).contains("Free: 500.00 KB"),
+        "the filesystem of the directory shown is looked up"
+    );
+
+    // when hitting the F key again
+    app.process_events(&mut terminal, into_keys(b"F".iter()))?;
+    assert!(!footer(&terminal).contains("Free"), "they are hidden again");
+    Ok(())
+}
+
+#[test]
+fn it_shows_how_long_ago_entries_were_modified_and_sorts_by_it() -> Result<()> {
+    use std::time::{Duration, SystemTime};
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        buf.content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect::<String>()
+    };
+    let day = Duration::from_secs(24 * 60 * 60);
+    let now = SystemTime::now();
+    let dir = index_by_name(&app, "dir");
+    for index in app.traversal.tree.node_indices().collect::<Vec<_>>() {
+        let age = if index == dir { day * 3 } else { day * 800 };
+        app.traversal.tree[index].mtime = Some((now - age).into());
+    }
+
+    // when entering the root and hitting the t key
+    app.process_events(&mut terminal, into_keys(b"ot".iter()))?;
+    assert!(app.display.columns.is_shown(Column::MTime));
+    assert!(
+        screen(&terminal).contains("  3d  |"),
+        "it shows the age of 'dir'"
+    );
+    assert!(
+        screen(&terminal).contains("  2y  |"),
+        "it shows the age of other entries"
+    );
+
+    // when hitting the M key
+    app.process_events(&mut terminal, into_keys(b"M".iter()))?;
+    assert_eq!(
+        app.state.entries[0].index, dir,
+        "the entry modified last comes first"
+    );
+
+    // when hitting the M key again
+    app.process_events(&mut terminal, into_keys(b"M".iter()))?;
+    assert_eq!(
+        app.state.entries.last().map(|b| b.index),
+        Some(dir),
+        "it comes last"
+    );
+
+    // when hitting the t key again
+    app.process_events(&mut terminal, into_keys(b"t".iter()))?;
+    assert!(
+        !screen(&terminal).contains("  3d  |"),
+        "the ages are hidden"
+    );
+    Ok(())
+}
+
+#[test]
+fn going_back_to_a_directory_restores_its_selection_and_scroll_position() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    // Two entries fit
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(40, 8))?;
+    let dir = index_by_name(&app, "dir");
+
+    // when sorting by name, moving to the bottom and up again to 'dir'
+    app.process_events(&mut terminal, into_keys(b"onGk".iter()))?;
+    assert_eq!(app.state.selected, Some(dir));
+    let offset = app.window.entries_pane.list.offset;
+    assert_eq!(offset, 4, "'dir' is at the top of the view");
+
+    // when entering and leaving 'dir'
+    app.process_events(&mut terminal, into_keys(b"ou".iter()))?;
+    assert_eq!(app.state.selected, Some(dir), "it is selected again");
+    assert_eq!(
+        app.window.entries_pane.list.offset, offset,
+        "the view is scrolled like before"
+    );
+    Ok(())
+}
+
+#[test]
+fn the_largest_entry_and_sibling_directories_can_be_jumped_to() -> Result<()> {
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_fixture(&["sample-01", "sample-02"])?;
+    let input = |name: &str| {
+        app.state
+            .entries
+            .iter()
+            .find(|b| app.traversal.tree.names[b.data.name].ends_with(name))
+            .map(|b| b.index)
+            .expect("an input path")
+    };
+    let (sample_01, sample_02) = (input("sample-01"), input("sample-02"));
+
+    // when sorting by name, moving to the bottom and hitting L
+    app.process_events(&mut terminal, into_keys(b"nG".iter()))?;
+    assert_eq!(app.state.selected, Some(sample_02));
+    app.process_events(&mut terminal, into_keys(b"L".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        Some(sample_01),
+        "it selects the largest"
+    );
+
+    // when entering sample-01 and hitting J
+    app.process_events(&mut terminal, into_keys(b"oJ".iter()))?;
+    assert_eq!(app.state.root, sample_02, "it shows the next directory");
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.first().map(|b| b.index)
+    );
+
+    // when hitting J again
+    app.process_events(&mut terminal, into_keys(b"J".iter()))?;
+    assert_eq!(app.state.root, sample_02, "there is no next one");
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("This is the last directory")
+    );
+
+    // when hitting K and going up
+    app.process_events(&mut terminal, into_keys(b"Ku".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        Some(sample_01),
+        "the directory that was shown last is selected"
+    );
+    Ok(())
+}
+
+#[test]
+fn the_footer_shows_the_amount_and_size_of_marked_entries() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(120, 20))?;
+    let footer = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, buf.area.height - 1).symbol.as_str())
+            .collect::<String>()
+    };
+    let format = app.display.byte_format;
+
+    // when entering the root
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(!footer(&terminal).contains("marked"), "nothing is marked");
+
+    // when marking 'dir' and an entry within it
+    let dir = index_by_name(&app, "dir");
+    assert_eq!(app.state.selected, Some(dir));
+    app.process_events(&mut terminal, into_keys(b" o ".iter()))?;
+    let dir_size = format
+        .display(node_by_index(&app, dir).size.into())
+        .to_string();
+    let text = footer(&terminal);
+    assert!(
+        text.contains(&format!("2 marked, {}", dir_size.trim())),
+        "the entry within 'dir' is counted once: {}",
+        text
+    );
+    Ok(())
+}
+
+#[test]
+fn the_header_row_shows_the_sorting_and_changes_it_when_clicked() -> Result<()> {
+    use crate::interactive::{Event, MouseEvent, MouseEventKind};
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 20))?;
+    let header = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, 3).symbol.clone())
+            .collect::<Vec<_>>()
+    };
+    let click = |column| {
+        vec![Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Click,
+            column,
+            row: 3,
+        })]
+        .into_iter()
+    };
+
+    // when entering the root
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    let labels = header(&terminal).concat();
+    assert!(labels.contains("Size▼"), "{}", labels);
+    assert!(
+        labels.contains("Share") && labels.contains(" Name"),
+        "{}",
+        labels
+    );
+
+    // when clicking the label of the names
+    let name_column = header(&terminal)
+        .windows(4)
+        .position(|w| w.concat() == "Name")
+        .expect("a name label") as u16;
+    app.process_events(&mut terminal, click(name_column))?;
+    assert_eq!(app.state.sorting, SortMode::NameAscending);
+    let labels = header(&terminal).concat();
+    assert!(
+        labels.contains("Name▲") && !labels.contains("Size▼"),
+        "{}",
+        labels
+    );
+
+    // when clicking it again
+    app.process_events(&mut terminal, click(name_column))?;
+    assert_eq!(app.state.sorting, SortMode::NameDescending);
+    assert!(header(&terminal).concat().contains("Name▼"));
+
+    // when clicking the label of the sizes
+    app.process_events(&mut terminal, click(3))?;
+    assert_eq!(app.state.sorting, SortMode::SizeDescending);
+    Ok(())
+}
+
+#[test]
+fn percentages_can_be_of_the_directory_the_scan_or_the_filesystem() -> Result<()> {
+    use crate::interactive::PercentageBase;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        buf.content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect::<String>()
+    };
+    let total = app.traversal.total_bytes.expect("a finished traversal");
+    app.state.devices = Some(vec![dua::Device {
+        filesystem: "/dev/vda".into(),
+        mount_point: "/".into(),
+        total: total * 1000,
+        used: total,
+        available: total * 999,
+    }]);
+
+    // when showing the input path, which has all entries
+    app.process_events(&mut terminal, into_keys(b"".iter()))?;
+    assert_eq!(app.display.percentage_base, PercentageBase::Directory);
+    assert!(screen(&terminal).contains("100.0%"));
+
+    // when hitting p
+    app.process_events(&mut terminal, into_keys(b"p".iter()))?;
+    assert_eq!(app.display.percentage_base, PercentageBase::Scan);
+    assert!(
+        screen(&terminal).contains("100.0%"),
+        "the input path holds the whole scan"
+    );
+
+    // when hitting p again
+    app.process_events(&mut terminal, into_keys(b"p".iter()))?;
+    assert_eq!(app.display.percentage_base, PercentageBase::Filesystem);
+    assert!(
+        screen(&terminal).contains("  0.1%"),
+        "the scan is a thousandth of the filesystem"
+    );
+
+    // when hitting p a third time
+    app.process_events(&mut terminal, into_keys(b"p".iter()))?;
+    assert_eq!(app.display.percentage_base, PercentageBase::Directory);
+
+    // when the filesystem isn't known
+    app.state.devices = Some(Vec::new());
+    app.process_events(&mut terminal, into_keys(b"pp".iter()))?;
+    assert_eq!(
+        app.display.percentage_base,
+        PercentageBase::Directory,
+        "it is skipped"
+    );
+    Ok(())
+}
+
+#[test]
+fn columns_are_drawn_in_the_configured_order_and_width() -> Result<()> {
+    use crate::interactive::Columns;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-02"])?;
+    let first_entry = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, 4).symbol.as_str())
+            .collect::<String>()
+    };
+    let size = app
+        .display
+        .byte_format
+        .display(app.state.entries[0].data.size.into())
+        .to_string();
+
+    // when the bar comes first and the size is wider
+    app.display.columns = Columns::from_names(&["bar", "size:12", "name"]).expect("valid");
+    app.process_events(&mut terminal, into_keys(b"".iter()))?;
+    let line = first_entry(&terminal);
+    let bar = line.find('|').expect("a bar");
+    let size_at = line.find(size.trim()).expect("the size");
+    assert!(bar < size_at, "the size follows the bar: {}", line);
+    assert!(
+        line.contains(&format!("| {:>12}", size.trim())),
+        "the size is 12 characters wide: {}",
+        line
+    );
+
+    // when hitting the c key to show a column that isn't configured
+    app.process_events(&mut terminal, into_keys(b"c".iter()))?;
+    assert!(app.display.columns.is_shown(Column::Count));
+    Ok(())
+}
+
+#[test]
+fn symlinks_are_shown_with_their_target() -> Result<()> {
+    use tui::style::Modifier;
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 20))?;
+
+    // when entering the root
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(node_by_name(&app, "c.lnk").is_symlink);
+    assert!(!node_by_name(&app, "a").is_symlink);
+    let buf = terminal.backend.buffer();
+    let row = (0..buf.area.height)
+        .map(|y| {
+            (0..buf.area.width)
+                .map(|x| buf.get(x, y).symbol.as_str())
+                .collect::<String>()
+        })
+        .position(|line| line.contains(" c.lnk -> a "))
+        .expect("the link is followed by its target") as u16;
+    let name_column = (0..buf.area.width)
+        .find(|x| buf.get(*x, row).symbol == "c")
+        .expect("the name");
+    assert!(
+        buf.get(name_column, row)
+            .modifier
+            .contains(Modifier::ITALIC),
+        "links are drawn in italics"
+    );
+    Ok(())
+}
+
+#[test]
+fn multiple_roots_are_listed_under_their_common_top() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01", "sample-02/dir"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 20))?;
+
+    app.process_events(&mut terminal, into_keys(b"".iter()))?;
+    let buf = terminal.backend.buffer();
+    let screen: Vec<String> = (0..buf.area.height)
+        .map(|y| {
+            (0..buf.area.width)
+                .map(|x| buf.get(x, y).symbol.as_str())
+                .collect()
+        })
+        .collect();
+    assert!(
+        screen
+            .iter()
+            .any(|line| line.contains(" input paths (2 items) ")),
+        "the top is labelled by what it contains rather than the current directory"
+    );
+    for root in &["sample-01", "sample-02/dir"] {
+        assert!(
+            screen.iter().any(|line| line.contains(&fixture_str(root))),
+            "each root is listed with its full path"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn the_help_shows_keys_and_display_options_on_top_of_all_panes() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 20))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .map(|x| buf.get(x, y).symbol.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // when sorting by count, marking an entry and opening the help
+    app.process_events(&mut terminal, into_keys(b"C ?".iter()))?;
+    assert_eq!(app.state.focussed, FocussedPane::Help);
+    assert!(
+        screen(&terminal).contains(":  by entry count"),
+        "it shows the current display options"
+    );
+
+    // when hitting tab
+    app.process_events(&mut terminal, into_keys(b"\t".iter()))?;
+    assert_eq!(
+        app.state.focussed,
+        FocussedPane::Help,
+        "the help keeps the focus until it's closed"
+    );
+
+    // when scrolling down and closing the help
+    app.process_events(&mut terminal, into_keys(b"G?".iter()))?;
+    assert!(app.window.help_pane.is_none());
+    assert_eq!(app.state.focussed, FocussedPane::Main);
+    assert!(!screen(&terminal).contains("Current display options"));
+    Ok(())
+}
+
+#[test]
+fn it_filters_entries_by_name_while_typing() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| {
+                app.traversal.tree.names[e.data.name]
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // when entering the root and searching for 'B', which is case-sensitive
+    app.process_events(&mut terminal, into_keys(b"o/B".iter()))?;
+    assert!(
+        app.state.entries.is_empty(),
+        "no name contains an uppercase B"
+    );
+    assert_eq!(app.state.selected, None);
+
+    // when correcting the search to be case-insensitive
+    app.process_events(
+        &mut terminal,
+        vec![crosstermion::input::Key::Backspace].into_iter(),
+    )?;
+    app.process_events(&mut terminal, into_keys(b"b".iter()))?;
+    assert_eq!(names(&app), vec!["z123.b", "b.empty"], "sorted by size");
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "z123.b")),
+        "the first match is selected"
+    );
+
+    // when confirming the search and moving down
+    app.process_events(&mut terminal, into_keys(b"\nj".iter()))?;
+    assert_eq!(app.state.search.as_ref().map(|s| s.is_editing), Some(false));
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "b.empty")),
+        "keys are used for navigation again, within the matches"
+    );
+
+    // when clearing the search
+    app.process_events(
+        &mut terminal,
+        vec![crosstermion::input::Key::Esc].into_iter(),
+    )?;
+    assert!(app.state.search.is_none());
+    assert_eq!(app.state.entries.len(), 6, "all entries are shown again");
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "b.empty")),
+        "the selection is kept"
+    );
+    Ok(())
+}
+
+#[test]
+fn entries_starting_with_a_dot_can_be_hidden() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| {
+                app.traversal.tree.names[e.data.name]
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+    let root_size = |app: &crate::interactive::TerminalApp| node_by_index(app, app.state.root).size;
+
+    // when entering the root and selecting the hidden file
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    let size = root_size(&app);
+    app.state.selected = Some(index_by_name(&app, ".hidden.666"));
+
+    // when hiding entries starting with a dot
+    app.process_events(&mut terminal, into_keys(b".".iter()))?;
+    assert!(!names(&app).contains(&".hidden.666".to_owned()));
+    assert_eq!(names(&app).len(), 5);
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.first().map(|e| e.index),
+        "the first entry is selected instead"
+    );
+    assert_eq!(root_size(&app), size, "they still count");
+
+    // when entering a directory and going back up, they stay hidden
+    app.process_events(&mut terminal, into_keys(b"uo".iter()))?;
+    assert_eq!(names(&app).len(), 5);
+
+    // when showing them again
+    app.process_events(&mut terminal, into_keys(b".".iter()))?;
+    assert_eq!(names(&app).len(), 6);
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Entries starting with a dot are shown")
+    );
+    Ok(())
+}
+
+#[test]
+fn small_entries_can_be_hidden_and_are_summed_up_below_the_list() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(80, 20))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .map(|x| buf.get(x, y).symbol.clone())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // when entering the root and showing only entries of at least 1 MB
+    app.process_events(&mut terminal, into_keys(b"oz".iter()))?;
+    assert_eq!(app.state.entries.len(), 1, "only 'dir' is large enough");
+    assert_eq!(app.state.selected, Some(index_by_name(&app, "dir")));
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Showing entries of at least 1 MB")
+    );
+    let text = screen(&terminal);
+    assert!(text.contains("└ 1/1 ─ 5 hidden, 1.05 KB ─"), "{}", text);
+
+    // when showing only entries of at least 100 MB
+    app.process_events(&mut terminal, into_keys(b"z".iter()))?;
+    assert!(app.state.entries.is_empty());
+    assert_eq!(app.state.selected, None);
+    let text = screen(&terminal);
+    assert!(text.contains("└ 6 hidden, "), "{}", text);
+
+    // when cycling through the larger sizes back to all entries
+    app.process_events(&mut terminal, into_keys(b"zz".iter()))?;
+    assert_eq!(app.state.entries.len(), 6);
+    assert_eq!(app.state.filter.min_size, 0);
+    let text = screen(&terminal);
+    assert!(!text.contains("hidden,"), "{}", text);
+    Ok(())
+}
+
+#[test]
+fn only_files_or_only_directories_can_be_shown() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let kinds = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| e.is_dir)
+            .collect::<Vec<_>>()
+    };
+
+    // when entering the root and showing files only
+    app.process_events(&mut terminal, into_keys(b"ov".iter()))?;
+    assert_eq!(kinds(&app), vec![false; 5]);
+    assert_eq!(app.state.message.as_deref(), Some("Showing files only"));
+
+    // when showing directories only
+    app.process_events(&mut terminal, into_keys(b"v".iter()))?;
+    assert_eq!(kinds(&app), vec![true]);
+    assert_eq!(app.state.selected, Some(index_by_name(&app, "dir")));
+
+    // when entering it, the filter still applies
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(kinds(&app).iter().all(|is_dir| *is_dir));
+
+    // when cycling through the other kinds until both are shown again
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    while app.state.filter.kind != EntryKind::All {
+        app.process_events(&mut terminal, into_keys(b"v".iter()))?;
+    }
+    assert_eq!(app.state.entries.len(), 6);
+    Ok(())
+}
+
+#[test]
+fn empty_entries_and_the_directories_containing_them_can_be_shown() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        let mut names: Vec<_> = app
+            .state
+            .entries
+            .iter()
+            .filter_map(|e| app.traversal.tree.names[e.data.name].file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+
+    // when entering the root and showing empty entries
+    app.process_events(&mut terminal, into_keys(b"ovvv".iter()))?;
+    assert_eq!(app.state.message.as_deref(), Some("Showing empty entries"));
+    assert_eq!(names(&app), vec!["b.empty", "dir"]);
+
+    // when following the directory down to the file without content
+    app.state.selected = Some(index_by_name(&app, "dir"));
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(names(&app), vec!["empty-dir"]);
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(names(&app), vec![".gitkeep"]);
+    Ok(())
+}
+
+#[test]
+fn the_path_finder_jumps_to_any_entry() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    // when opening the path finder and typing parts of a deep path
+    app.process_events(&mut terminal, into_keys(b"fsub/dsa".iter()))?;
+    let finder = app.window.path_finder.as_ref().expect("the finder is open");
+    assert!(
+        finder
+            .matches()
+            .next()
+            .is_some_and(|path| path.ends_with("dir/sub/dir-sub-a.256kb")),
+        "the best match comes first"
+    );
+
+    // when choosing the best match, a file
+    app.process_events(&mut terminal, into_keys(b"\n".iter()))?;
+    assert!(app.window.path_finder.is_none());
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "sub"),
+        "it shows its parent"
+    );
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "dir-sub-a.256kb")),
+        "and selects it"
+    );
+
+    // when choosing a directory
+    app.process_events(&mut terminal, into_keys(b"fempty-dir\n".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "empty-dir"),
+        "it shows the entries of the directory"
+    );
+
+    // when cancelling
+    app.process_events(&mut terminal, into_keys(b"fa".iter()))?;
+    app.process_events(
+        &mut terminal,
+        vec![crosstermion::input::Key::Esc].into_iter(),
+    )?;
+    assert!(app.window.path_finder.is_none());
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "empty-dir"),
+        "nothing changes"
+    );
+    Ok(())
+}
+
+#[test]
+fn it_sorts_by_name_in_natural_order() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| {
+                app.traversal.tree.names[e.data.name]
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // when entering the root and hitting the n key
+    app.process_events(&mut terminal, into_keys(b"on".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::NameAscending);
+    assert_eq!(
+        names(&app),
+        vec![".hidden.666", "a", "b.empty", "c.lnk", "dir", "z123.b"]
+    );
+
+    // when hitting the n key again
+    app.process_events(&mut terminal, into_keys(b"n".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::NameDescending);
+    assert_eq!(names(&app)[0], "z123.b");
+    Ok(())
+}
+
+#[test]
+fn bars_can_be_made_wider_and_narrower_within_bounds() -> Result<()> {
+    use crate::interactive::ByteVisualization;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let bar = |app: &crate::interactive::TerminalApp| {
+        format!(
+            "{}",
+            ByteVisualization::Bar.display(0.5, app.display.bar_width)
+        )
+    };
+    assert_eq!(bar(&app), "█████     ", "it starts with 10 characters");
+
+    // when hitting the > key
+    app.process_events(&mut terminal, into_keys(b">>".iter()))?;
+    assert_eq!(bar(&app), "██████      ");
+
+    // when hitting the < key many times
+    app.process_events(&mut terminal, into_keys([b'<'; 20].iter()))?;
+    assert_eq!(
+        app.display.bar_width, 2,
+        "bars don't get narrower than that"
+    );
+    assert_eq!(bar(&app), "█ ");
+    Ok(())
+}
+
+#[test]
+fn exact_byte_counts_can_be_toggled() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-02"])?;
+    let size = |app: &crate::interactive::TerminalApp| {
+        format!("{}", app.display.byte_format.display(1540))
+    };
+    let size_before = size(&app);
+
+    // when hitting B
+    app.process_events(&mut terminal, into_keys(b"B".iter()))?;
+    assert_eq!(size(&app), "1540", "sizes are shown as exact bytes");
+
+    // when hitting it again
+    app.process_events(&mut terminal, into_keys(b"B".iter()))?;
+    assert_eq!(size(&app), size_before, "the previous format is restored");
+    Ok(())
+}
+
+#[test]
+fn the_unit_of_sizes_can_be_cycled() -> Result<()> {
+    use dua::ByteFormat;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-02"])?;
+    let footer = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, buf.area.height - 1).symbol.as_str())
+            .collect::<String>()
+    };
+    let total = app.traversal.total_bytes.expect("a finished traversal");
+
+    // when hitting U repeatedly
+    for expected in &[
+        ByteFormat::Binary,
+        ByteFormat::Bytes,
+        ByteFormat::KB,
+        ByteFormat::KiB,
+        ByteFormat::MB,
+        ByteFormat::MiB,
+        ByteFormat::GB,
+        ByteFormat::GiB,
+        ByteFormat::TB,
+        ByteFormat::TiB,
+        ByteFormat::Metric,
+    ] {
+        app.process_events(&mut terminal, into_keys(b"U".iter()))?;
+        let expected = expected.display(total).to_string();
+        assert_eq!(app.display.byte_format.display(total).to_string(), expected);
+        assert!(
+            footer(&terminal).contains(expected.trim()),
+            "the footer shows the total in the new unit"
+        );
+    }
+
+    // when showing exact bytes and hitting U
+    app.process_events(&mut terminal, into_keys(b"BU".iter()))?;
+    assert_eq!(
+        app.display.byte_format.display(total).to_string(),
+        ByteFormat::Binary.display(total).to_string(),
+        "it continues with the unit shown before the exact bytes"
+    );
+    Ok(())
+}
+
+#[test]
+fn vim_keys_move_to_the_top_with_gg_while_g_cycles_the_bars() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let bar = |app: &crate::interactive::TerminalApp| {
+        format!(
+            "{}",
+            app.display.byte_vis.display(0.5, app.display.bar_width)
+        )
+    };
+    let bar_before = bar(&app);
+
+    // when entering the root, moving down with j and up with gg
+    app.process_events(&mut terminal, into_keys(b"ljjgg".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.first().map(|e| e.index),
+        "it moves to the top"
+    );
+    assert_eq!(bar(&app), bar_before, "the bars look the same");
+
+    // when hitting g once
+    app.process_events(&mut terminal, into_keys(b"jg".iter()))?;
+    assert_eq!(bar(&app), bar_before, "nothing changes until the next key");
+
+    // when hitting another key after it
+    app.process_events(&mut terminal, into_keys(b"k".iter()))?;
+    assert_ne!(bar(&app), bar_before, "the bars are cycled");
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.first().map(|e| e.index),
+        "the other key is handled as well"
+    );
+
+    // when going back up with h and down with G
+    app.process_events(&mut terminal, into_keys(b"hG".iter()))?;
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.last().map(|e| e.index)
+    );
+    Ok(())
+}
+
+#[test]
+fn the_mouse_selects_enters_and_scrolls_through_entries() -> Result<()> {
+    use crate::interactive::{Event, MouseEvent, MouseEventKind};
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mouse = |kind, column, row| Event::Mouse(MouseEvent { kind, column, row });
+
+    // when entering sample-01/dir and scrolling down with the wheel
+    app.process_events(&mut terminal, into_keys(b"ll".iter()))?;
+    app.process_events(
+        &mut terminal,
+        vec![mouse(MouseEventKind::ScrollDown, 10, 5)].into_iter(),
+    )?;
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.get(1).map(|e| e.index),
+        "it moves the selection down"
+    );
+
+    // when clicking the fourth entry, below the title and the header row of the entries pane
+    let first_row = app.window.entries_pane.area.y + 2;
+    app.process_events(
+        &mut terminal,
+        vec![mouse(MouseEventKind::Click, 10, first_row + 3)].into_iter(),
+    )?;
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.get(3).map(|e| e.index),
+        "it selects the clicked entry"
+    );
+
+    // when double-clicking the 'sub' directory
+    let sub = index_by_name(&app, "sub");
+    let sub_row = first_row
+        + app
+            .state
+            .entries
+            .iter()
+            .position(|e| e.index == sub)
+            .unwrap() as u16;
+    let double_click = vec![
+        mouse(MouseEventKind::Click, 10, sub_row),
+        mouse(MouseEventKind::Click, 10, sub_row),
+    ];
+    app.process_events(&mut terminal, double_click.into_iter())?;
+    assert_eq!(app.state.root, sub, "it enters the directory");
+
+    // when clicking the 'sample-01' part of the path in the title
+    app.process_events(
+        &mut terminal,
+        vec![mouse(MouseEventKind::Click, 2 + 20, first_row - 2)].into_iter(),
+    )?;
+    assert_eq!(
+        app.traversal.tree.name_of(app.state.root).as_os_str(),
+        OsString::from(format!("{}/sample-01", FIXTURE_PATH)),
+        "it shows the entries of the clicked parent directory"
+    );
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "dir")),
+        "it selects the directory that was shown before"
+    );
+
+    // when clicking below the last entry
+    app.process_events(
+        &mut terminal,
+        vec![mouse(MouseEventKind::Click, 10, 15)].into_iter(),
+    )?;
+    assert_eq!(
+        app.state.selected,
+        Some(index_by_name(&app, "dir")),
+        "the selection stays"
+    );
+    Ok(())
+}
+
+#[test]
+fn the_treemap_shows_entries_by_size_and_enters_the_selected_one() -> Result<()> {
+    use crate::interactive::{Event, MouseEvent, MouseEventKind};
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    // when entering sample-01 and hitting T
+    app.process_events(&mut terminal, into_keys(b"lT".iter()))?;
+    let treemap = app.window.treemap.as_ref().expect("the treemap is shown");
+    let dir = index_by_name(&app, "dir");
+    let (largest, cell) = treemap.cells[0];
+    assert_eq!(largest, dir, "the largest entry comes first");
+    assert!(
+        cell.width * cell.height > treemap.area.width * treemap.area.height / 2,
+        "and takes most of the space"
+    );
+
+    // when clicking the cell of 'dir' twice
+    let click = || {
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Click,
+            column: cell.x,
+            row: cell.y,
+        })
+    };
+    app.process_events(&mut terminal, vec![click(), click()].into_iter())?;
+    assert_eq!(app.state.root, dir, "it enters the directory");
+
+    // when going back up, selecting 'dir' and hitting enter
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(app.state.selected, Some(dir));
+    app.process_events(&mut terminal, into_keys(b"\n".iter()))?;
+    assert_eq!(app.state.root, dir, "it enters the selected directory");
+
+    // when hitting T again
+    app.process_events(&mut terminal, into_keys(b"T".iter()))?;
+    assert!(app.window.treemap.is_none(), "the entries are listed again");
+    Ok(())
+}
+
+#[test]
+fn the_monochrome_theme_draws_without_colors() -> Result<()> {
+    use crate::interactive::widgets::Theme;
+    use tui::style::Color;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let colors = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        buf.content
+            .iter()
+            .flat_map(|cell| vec![cell.fg, cell.bg])
+            .filter(|color| *color != Color::Reset)
+            .count()
+    };
+    assert!(colors(&terminal) > 0, "the default theme has colors");
+
+    // when switching to the monochrome theme, marking an entry and showing the treemap
+    app.display.theme = Theme::MONOCHROME;
+    app.process_events(&mut terminal, into_keys(b" ".iter()))?;
+    assert_eq!(colors(&terminal), 0, "there are no colors in the entries");
+    app.process_events(&mut terminal, into_keys(b"T".iter()))?;
+    assert_eq!(colors(&terminal), 0, "there are no colors in the treemap");
+    Ok(())
+}
+
+#[test]
+fn breadcrumbs_jump_to_parent_directories_by_number_or_click() -> Result<()> {
+    use crate::interactive::{Event, MouseEvent, MouseEventKind};
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let breadcrumbs = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.width)
+            .map(|x| buf.get(x, 1).symbol.clone())
+            .collect::<String>()
+    };
+
+    // when entering sample-01/dir/sub
+    app.process_events(&mut terminal, into_keys(b"ll".iter()))?;
+    let sub = index_by_name(&app, "sub");
+    app.state.selected = Some(sub);
+    app.process_events(&mut terminal, into_keys(b"l".iter()))?;
+    assert_eq!(app.state.root, sub);
+    assert_eq!(
+        breadcrumbs(&terminal).trim_end(),
+        " … › 3 dir › 4 sub",
+        "it shows the path below the header, leaving out what doesn't fit"
+    );
+
+    // when pressing the number of the 'dir' segment
+    app.process_events(&mut terminal, into_keys(b"3".iter()))?;
+    assert_eq!(app.state.root, index_by_name(&app, "dir"));
+    assert_eq!(
+        app.state.selected,
+        Some(sub),
+        "it selects where it came from"
+    );
+
+    // when pressing the number of a directory that doesn't exist
+    app.process_events(&mut terminal, into_keys(b"9".iter()))?;
+    assert_eq!(
+        app.state.root,
+        index_by_name(&app, "dir"),
+        "nothing happens"
+    );
+
+    // when clicking the 'sample-01' segment
+    let column = breadcrumbs(&terminal)
+        .chars()
+        .position(|c| c == '2')
+        .expect("second segment") as u16;
+    app.process_events(
+        &mut terminal,
+        vec![Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Click,
+            column,
+            row: 1,
+        })]
+        .into_iter(),
+    )?;
+    assert_eq!(
+        app.traversal.tree.name_of(app.state.root).as_os_str(),
+        OsString::from(format!("{}/sample-01", FIXTURE_PATH))
+    );
+
+    // when pressing the number of the root of the traversal
+    app.process_events(&mut terminal, into_keys(b"1".iter()))?;
+    assert_eq!(app.state.root, app.traversal.root_index);
+    Ok(())
+}
+
+#[test]
+fn the_error_pane_lists_paths_that_could_not_be_read() -> Result<()> {
+    use dua::traverse::TraversalError;
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 20))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .map(|x| buf.get(x, y).symbol.clone())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    app.traversal.errors = vec![TraversalError {
+        path: "sample-01/secret".into(),
+        kind: std::io::ErrorKind::PermissionDenied,
+        message: "Permission denied".into(),
+    }];
+    app.traversal.io_errors = 2;
+
+    // when pressing e
+    app.process_events(&mut terminal, into_keys(b"e".iter()))?;
+    let text = screen(&terminal);
+    assert!(text.contains("IO errors (1 of 2 with"), "{}", text);
+    assert!(
+        text.contains("PermissionDenied sample-01/secret"),
+        "{}",
+        text
+    );
+    assert!(text.contains("2 IO errors"), "the footer shows the count");
+
+    // when moving in it and closing it with q
+    app.process_events(&mut terminal, into_keys(b"jq".iter()))?;
+    assert!(app.window.error_pane.is_none());
+    assert_eq!(
+        app.state.selected,
+        app.state.entries.first().map(|e| e.index),
+        "keys don't reach the entries while the pane is open"
+    );
+    Ok(())
+}
+
+#[test]
+fn messages_are_styled_by_severity_and_kept_in_a_log() -> Result<()> {
+    use crate::interactive::{widgets::Theme, Severity};
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 20))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .map(|x| buf.get(x, y).symbol.clone())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    app.state.is_loaded = true;
+
+    // when leaving the top level and trying to delete an entry of a loaded scan
+    app.process_events(&mut terminal, into_keys(b"ud".iter()))?;
+    let message = app.state.message.clone().expect("a message");
+    assert_eq!(&*message, "Entries of a loaded scan can't be deleted");
+    assert_eq!(message.severity, Severity::Warning);
+    let buf = terminal.backend.buffer();
+    let footer = buf.area.height - 1;
+    let row: Vec<_> = (0..buf.area.width)
+        .map(|x| buf.get(x, footer).symbol.as_str())
+        .collect();
+    let x = (0..row.len())
+        .find(|x| row[*x..].concat().starts_with("Entries of"))
+        .expect("the message in the footer") as u16;
+    assert_eq!(buf.get(x, footer).fg, Theme::DARK.warning);
+
+    // when showing the messages
+    app.process_events(&mut terminal, into_keys(b"E".iter()))?;
+    assert_eq!(app.state.message, None, "it's gone from the footer");
+    let text = screen(&terminal);
+    assert!(text.contains("Messages (2)"), "{}", text);
+    let lines: Vec<_> = text.lines().filter(|l| l.contains(" ago ")).collect();
+    assert_eq!(lines.len(), 2, "{}", text);
+    assert!(
+        lines[0].contains("warning Entries of a loaded scan can't be deleted"),
+        "the latest comes first: {}",
+        text
+    );
+    assert!(lines[1].contains("info    Top level reached"), "{}", text);
+
+    // when closing it again
+    app.process_events(&mut terminal, into_keys(b"jE".iter()))?;
+    assert!(app.window.messages_pane.is_none());
+    assert_eq!(app.state.messages.len(), 2, "the log is kept");
+    Ok(())
+}
+
+#[test]
+fn duplicates_are_listed_and_can_be_jumped_to() -> Result<()> {
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_fixture(&["sample-01", "sample-02"])?;
+
+    // when pressing D at the top level
+    app.process_events(&mut terminal, into_keys(b"D".iter()))?;
+    let sets: Vec<_> = app
+        .window
+        .duplicates_pane
+        .as_ref()
+        .expect("duplicates were found")
+        .sets()
+        .iter()
+        .map(|set| {
+            set.files
+                .iter()
+                .map(|(_, index)| app.traversal.tree.name_of(*index).as_os_str().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        sets,
+        vec![
+            vec![OsString::from("dir-a.kb"), OsString::from("e")],
+            vec![OsString::from("a"), OsString::from("a")],
+        ],
+        "the sets wasting the most space come first"
+    );
+
+    // when moving to the second file of the first set and choosing it
+    app.process_events(&mut terminal, into_keys(b"jj\n".iter()))?;
+    assert!(app.window.duplicates_pane.is_none());
+    assert_eq!(
+        app.traversal
+            .tree
+            .name_of(app.state.selected.expect("a selection"))
+            .as_os_str(),
+        OsString::from("e"),
+        "the chosen file is selected in its directory"
+    );
+    assert_eq!(
+        app.traversal.tree.name_of(app.state.root).as_os_str(),
+        OsString::from("sub")
+    );
+    Ok(())
+}
+
+#[test]
+fn a_device_can_be_picked_to_scan_it_instead() -> Result<()> {
+    use crate::interactive::widgets::DevicesPane;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let device = |mount_point: String| dua::Device {
+        filesystem: "/dev/test".into(),
+        mount_point: mount_point.into(),
+        total: 1000,
+        used: 250,
+        available: 750,
+    };
+    app.window.devices_pane = Some(DevicesPane::new(vec![
+        device(fixture_str("sample-01")),
+        device(fixture_str("sample-02")),
+    ]));
+
+    // when moving to the second device and picking it
+    app.process_events(&mut terminal, into_keys(b"j\n".iter()))?;
+    assert!(app.window.devices_pane.is_none());
+    assert!(
+        app.state.scans_device,
+        "the session of the input paths isn't saved"
+    );
+    assert_eq!(app.state.root, app.traversal.root_index);
+    assert_eq!(
+        app.state
+            .entries
+            .iter()
+            .map(|e| app.traversal.tree.names[e.data.name].as_os_str().to_owned())
+            .collect::<Vec<_>>(),
+        vec![OsString::from(fixture_str("sample-02"))],
+        "the device is shown instead of the input paths"
+    );
+
+    // when opening the pane again and closing it
+    app.window.devices_pane = Some(DevicesPane::new(vec![device(fixture_str("sample-01"))]));
+    app.process_events(&mut terminal, into_keys(b"q".iter()))?;
+    assert!(app.window.devices_pane.is_none());
+    assert_eq!(
+        app.state.entries.len(),
+        1,
+        "nothing is scanned without picking a device"
+    );
+    Ok(())
+}
+
+#[test]
+fn rebound_keys_trigger_their_action_and_are_listed_in_the_help() -> Result<()> {
+    use crate::interactive::KeyMap;
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 70))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .map(|x| buf.get(x, y).symbol.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let config = crate::config::parse(
+        "[keys]\nenter-directory = \"n\"\nsort-by-name = \"N\"\ntoggle-help = \"F1\"",
+    )?;
+    app.state.keymap = KeyMap::from_config(&config)?;
+    let root = app.state.root;
+
+    // when pressing the keys the actions used to be bound to
+    app.process_events(&mut terminal, into_keys(b"l?".iter()))?;
+    assert_eq!(app.state.root, root, "they do nothing");
+    assert_eq!(app.state.sorting, SortMode::SizeDescending);
+    assert!(app.window.help_pane.is_none());
+
+    // when pressing the keys they are bound to now
+    app.process_events(&mut terminal, into_keys(b"nN".iter()))?;
+    assert_ne!(app.state.root, root, "it enters the directory");
+    assert_eq!(app.state.sorting, SortMode::NameAscending);
+    app.process_events(
+        &mut terminal,
+        std::iter::once(crosstermion::input::Key::F(1)),
+    )?;
+    assert_eq!(app.state.focussed, FocussedPane::Help);
+    let help = screen(&terminal);
+    assert!(
+        help.contains("n => descent into the selected directory"),
+        "the help lists them"
+    );
+    assert!(help.contains("N => toggle sort by name"));
+    Ok(())
+}
+
+#[test]
+fn entries_are_marked_by_pattern_inverted_and_cleared() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let marked_names = |app: &crate::interactive::TerminalApp| {
+        let mut names: Vec<_> = app
+            .window
+            .mark_pane
+            .as_ref()
+            .map(|pane| {
+                pane.marked()
+                    .keys()
+                    .map(|&index| {
+                        app.traversal
+                            .tree
+                            .name_of(index)
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    };
+
+    // when entering the root and marking a pattern only found in 'dir'
+    app.process_events(&mut terminal, into_keys(b"o*dir-*".iter()))?;
+    assert!(app.state.mark_pattern.is_some(), "it's typed");
+    app.process_events(&mut terminal, into_keys(b"\n".iter()))?;
+    assert!(app.state.mark_pattern.is_none());
+    assert!(app.window.mark_pane.is_none(), "only shown entries match");
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Marked 0 entries matching 'dir-*'")
+    );
+
+    // when marking it within all directories below
+    app.process_events(&mut terminal, into_keys(b"*dir-*\t\n".iter()))?;
+    assert_eq!(
+        marked_names(&app),
+        vec!["dir-a.1mb", "dir-a.kb", "dir-sub-a.256kb"]
+    );
+
+    // when clearing marks and marking entries of the root only
+    app.process_events(&mut terminal, into_keys(b"X".iter()))?;
+    assert!(app.window.mark_pane.is_none());
+    app.process_events(&mut terminal, into_keys(b"**b*\n".iter()))?;
+    assert_eq!(marked_names(&app), vec!["b.empty", "z123.b"]);
+
+    // when inverting the marks
+    app.process_events(&mut terminal, into_keys(b"i".iter()))?;
+    assert_eq!(marked_names(&app), vec![".hidden.666", "a", "c.lnk", "dir"]);
+    Ok(())
+}
+
+#[test]
+fn sessions_restore_the_view_of_another_app() -> Result<()> {
+    use crate::interactive::Session;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    // when entering 'dir', sorting by name and marking its second entry
+    app.process_events(&mut terminal, into_keys(b"ooj ".iter()))?;
+    app.state.sorting = SortMode::NameDescending;
+    let session = Session::capture(&app);
+    assert_eq!(
+        session.root.as_ref().and_then(|p| p.file_name()),
+        Some(std::ffi::OsStr::new("dir"))
+    );
+    assert!(session.root.as_ref().is_some_and(|p| p.is_absolute()));
+    assert_eq!(session.marked.len(), 1);
+
+    // when restoring it in an app which was just started
+    let (_, mut restored) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    assert_eq!(session.restore(&mut restored), 0, "all entries are found");
+    assert_eq!(restored.state.root, index_by_name(&restored, "dir"));
+    assert_eq!(restored.state.sorting, SortMode::NameDescending);
+    assert_eq!(
+        restored
+            .state
+            .entries
+            .first()
+            .map(|e| app.traversal.tree.names[e.data.name]
+                .to_string_lossy()
+                .into_owned()),
+        Some("sub".to_string()),
+        "the entries are sorted as before"
+    );
+    assert_eq!(
+        restored
+            .traversal
+            .tree
+            .name_of(restored.state.selected.unwrap()),
+        app.traversal.tree.name_of(app.state.selected.unwrap())
+    );
+    let marked = |app: &crate::interactive::TerminalApp| {
+        app.window.mark_pane.as_ref().map(|p| {
+            p.marked()
+                .values()
+                .map(|m| m.path.clone())
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(marked(&restored), marked(&app));
+
+    // when restoring a session with entries that don't exist anymore
+    let mut session = session;
+    session.marked.push("/does/not/exist".into());
+    assert_eq!(session.restore(&mut restored), 1);
+    Ok(())
+}
+
+#[test]
+fn made_up_trees_can_be_browsed_without_touching_the_disk() -> Result<()> {
+    let mut filesystem = dua::filesystem::MemoryFilesystem::default()
+        .file("data/images/disk.img", 5_000_000_000_000)
+        .file("data/notes.txt", 1)
+        .dir("data/empty");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_filesystem(&mut filesystem, &["data"])?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| {
+                app.traversal.tree.names[e.data.name]
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&app), vec!["data"]);
+    assert_eq!(node_by_name(&app, "data").size, 5_000_000_000_001);
+    assert_eq!(node_by_name(&app, "data").entry_count, 4);
+
+    // when entering the input path, the largest entry comes first
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(names(&app), vec!["images", "notes.txt", "empty"]);
+    Ok(())
+}
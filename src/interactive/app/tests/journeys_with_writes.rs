@@ -1,16 +1,21 @@
 use crate::interactive::app::tests::utils::{
-    initialized_app_and_terminal_from_paths, into_keys, WritableFixture,
+    debug, initialized_app_and_terminal_from_ncdu_export, initialized_app_and_terminal_from_paths,
+    initialized_app_and_terminal_with_closure, into_keys, node_by_index, node_by_name, portable,
+    WritableFixture,
 };
+use crate::interactive::Event;
+use crate::testing::TempDir;
 use anyhow::Result;
 use pretty_assertions::assert_eq;
 
 #[test]
 fn basic_user_journey_with_deletion() -> Result<()> {
     let fixture = WritableFixture::from("sample-02");
-    let (mut terminal, mut app) = initialized_app_and_terminal_from_paths(&[fixture.root.clone()])?;
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
 
     // With a selection of items
-    app.process_events(&mut terminal, into_keys(b"doddd".iter()))?;
+    app.process_events(&mut terminal, into_keys(b"mommm".iter()))?;
 
     assert_eq!(
         app.window.mark_pane.as_ref().map(|p| p.marked().len()),
@@ -33,6 +38,27 @@ fn basic_user_journey_with_deletion() -> Result<()> {
         ]
         .into_iter(),
     )?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Protected or large entries must be deleted on their own, with confirmation"),
+        "the input path is protected"
+    );
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(4),
+        "nothing was deleted"
+    );
+
+    // When unmarking the input path, marked first, and deleting again
+    app.process_events(
+        &mut terminal,
+        vec![
+            crosstermion::input::Key::Char('g'),
+            crosstermion::input::Key::Char('x'),
+            crosstermion::input::Key::Ctrl('r'),
+        ]
+        .into_iter(),
+    )?;
     assert_eq!(
         app.window.mark_pane.is_none(),
         true,
@@ -40,13 +66,585 @@ fn basic_user_journey_with_deletion() -> Result<()> {
     );
     assert_eq!(app.state.selected, None, "nothing is left to be selected");
     assert_eq!(
-        app.state.root, app.traversal.root_index,
-        "the only root left is the top-level"
+        fixture.as_ref().read_dir()?.count(),
+        0,
+        "all entries of the directory should have been deleted",
+    );
+    Ok(())
+}
+
+#[test]
+fn deleting_protected_or_large_entries_needs_typing_to_confirm() -> Result<()> {
+    let fixture = WritableFixture::from("sample-02");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.confirm_deletions_above = Some(1000);
+    let root = fixture.as_ref().to_string_lossy().into_owned();
+
+    // When asking to delete the input path and pressing 'y'
+    app.process_events(&mut terminal, into_keys(b"dy\n".iter()))?;
+    assert_eq!(
+        app.window
+            .delete_confirmation
+            .as_ref()
+            .and_then(|c| c.required_input.as_ref())
+            .map(|r| (r.reason.as_str(), r.expected.as_str(), r.typed.as_str())),
+        Some(("It's an input path, type its path", root.as_str(), "y")),
+        "its path must be typed instead"
+    );
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("It's an input path, type its path first")
+    );
+    assert!(fixture.as_ref().is_dir());
+
+    // When cancelling and asking to delete 'dir', which is larger than the limit
+    app.process_events(
+        &mut terminal,
+        vec![crosstermion::input::Key::Esc].into_iter(),
+    )?;
+    assert!(app.window.delete_confirmation.is_none());
+    app.process_events(&mut terminal, into_keys(b"od".iter()))?;
+    assert_eq!(
+        app.window
+            .delete_confirmation
+            .as_ref()
+            .and_then(|c| c.required_input.as_ref())
+            .map(|r| r.expected.as_str()),
+        Some("6"),
+        "the amount of entries must be typed"
+    );
+    app.process_events(&mut terminal, into_keys(b"7\n".iter()))?;
+    assert!(fixture.as_ref().join("dir").is_dir(), "the input is wrong");
+    app.process_events(
+        &mut terminal,
+        vec![crosstermion::input::Key::Backspace].into_iter(),
+    )?;
+    app.process_events(&mut terminal, into_keys(b"6\n".iter()))?;
+    assert!(app.window.delete_confirmation.is_none());
+    assert_eq!(app.state.message.as_deref(), Some("Deleted 6 entries"));
+    assert!(!fixture.as_ref().join("dir").exists());
+    Ok(())
+}
+
+#[test]
+fn deleting_some_marked_entries_updates_the_sizes_of_all_ancestors() -> Result<()> {
+    let fixture = WritableFixture::from("sample-02");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let entries_before = app.traversal.entries_traversed;
+
+    // When marking 'dir/sub' and 'dir/c', the two biggest entries in 'dir', and deleting them
+    app.process_events(&mut terminal, into_keys(b"oo j ".iter()))?;
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(2)
+    );
+    app.process_events(
+        &mut terminal,
+        vec![
+            crosstermion::input::Key::Char('\t'),
+            crosstermion::input::Key::Ctrl('r'),
+        ]
+        .into_iter(),
+    )?;
+
+    assert!(
+        app.window.mark_pane.is_none(),
+        "all marked entries are gone"
+    );
+    assert!(!fixture.as_ref().join("dir").join("sub").exists());
+    assert!(!fixture.as_ref().join("dir").join("c").exists());
+    assert!(fixture.as_ref().join("dir").join("d").is_file());
+
+    let dir = node_by_name(&app, "dir");
+    assert_eq!(
+        (dir.size, dir.entry_count),
+        (2, 2),
+        "d and empty-dir are left"
+    );
+    let root = node_by_index(&app, app.traversal.root_index);
+    assert_eq!(
+        (root.size, root.entry_count),
+        (2 + 256 + 1, 6),
+        "the fixture, a, b, and dir with its entries are left"
+    );
+    assert_eq!(app.traversal.total_bytes, Some(259));
+    assert_eq!(
+        app.traversal.entries_traversed,
+        entries_before - 3,
+        "sub, sub/e and c were removed"
+    );
+    Ok(())
+}
+
+#[test]
+fn deleting_the_selected_entry_after_confirmation() -> Result<()> {
+    let fixture = WritableFixture::from("sample-02");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let dir = fixture.as_ref().join("dir");
+
+    // When asking to delete the biggest entry, 'dir', and cancelling
+    app.process_events(&mut terminal, into_keys(b"od".iter()))?;
+    assert_eq!(
+        app.window
+            .delete_confirmation
+            .as_ref()
+            .map(|c| (c.path.clone(), c.size, c.entry_count)),
+        Some((dir.clone(), 1283, 5)),
+        "it asks for confirmation first"
+    );
+    app.process_events(&mut terminal, into_keys(b"q".iter()))?;
+    assert!(app.window.delete_confirmation.is_none());
+    assert_eq!(app.state.message.as_deref(), Some("Deletion cancelled"));
+    assert!(dir.is_dir(), "nothing was deleted");
+
+    // When confirming the deletion
+    app.process_events(&mut terminal, into_keys(b"dy".iter()))?;
+    assert!(app.window.delete_confirmation.is_none());
+    assert_eq!(app.state.message.as_deref(), Some("Deleted 6 entries"));
+    assert!(!dir.exists(), "the directory was deleted");
+    assert_eq!(
+        app.traversal.total_bytes,
+        Some(256 + 1),
+        "freed bytes are subtracted from the total"
+    );
+    assert_eq!(
+        node_by_index(&app, app.traversal.root_index).entry_count,
+        3,
+        "the fixture, a and b are left"
+    );
+    assert_eq!(
+        app.state.entries.len(),
+        2,
+        "the entries of the current directory are updated"
+    );
+    Ok(())
+}
+
+#[test]
+fn deleting_on_another_thread_shows_its_progress_until_it_is_done() -> Result<()> {
+    let fixture = WritableFixture::from("sample-02");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let (event_sender, events) = std::sync::mpsc::sync_channel(0);
+    app.state.event_sender = Some(event_sender);
+    let dir = fixture.as_ref().join("dir");
+
+    // When confirming the deletion of 'dir'
+    app.process_events(&mut terminal, into_keys(b"ody".iter()))?;
+    assert!(
+        app.state.deletion.is_some(),
+        "the progress is shown while deleting"
+    );
+    app.process_events(&mut terminal, into_keys(b"j".iter()))?;
+    assert!(app.state.deletion.is_some(), "keys are ignored meanwhile");
+
+    // and receiving what the deleting thread tells until it's done
+    while app.state.deletion.is_some() {
+        let event = events.recv()?;
+        app.process_events(&mut terminal, std::iter::once(event))?;
+    }
+    assert_eq!(app.state.message.as_deref(), Some("Deleted 6 entries"));
+    assert!(!dir.exists(), "the directory was deleted");
+    assert_eq!(app.traversal.total_bytes, Some(256 + 1));
+    assert_eq!(app.state.entries.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn loaded_scans_can_be_browsed_but_not_deleted_from() -> Result<()> {
+    let fixture = WritableFixture::from("sample-02");
+    let (_, scanned) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_ncdu_export(&fixture.root)?;
+
+    assert_eq!(
+        debug(portable(&app.traversal.tree)),
+        debug(portable(&scanned.traversal.tree)),
+        "the loaded scan has the same entries and sizes as the original one"
+    );
+    assert_eq!(
+        app.traversal.entries_traversed,
+        scanned.traversal.entries_traversed
+    );
+
+    // When marking entries and trying to delete them
+    app.process_events(&mut terminal, into_keys(b"om".iter()))?;
+    app.process_events(
+        &mut terminal,
+        vec![
+            crosstermion::input::Key::Char('\t'),
+            crosstermion::input::Key::Ctrl('r'),
+        ]
+        .into_iter(),
+    )?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Entries of a loaded scan can't be deleted")
+    );
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1),
+        "the marked entry is still there"
     );
     assert_eq!(
         fixture.as_ref().is_dir(),
-        false,
-        "the directory should have been deleted",
+        true,
+        "the directory must still exist"
+    );
+
+    // When running an external action on them
+    let config = crate::config::parse("[actions.remove]\nkey = \"Y\"\ncommand = [\"rm\", \"-r\"]")?;
+    app.state.keymap = crate::interactive::KeyMap::from_config(&config)?;
+    app.process_events(&mut terminal, into_keys(b"\tY".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("'remove' can't be run on entries of a loaded scan")
+    );
+    assert!(fixture.as_ref().join("dir").is_dir());
+    Ok(())
+}
+
+#[test]
+fn it_sorts_by_modification_time_with_directories_as_new_as_their_newest_entry() -> Result<()> {
+    let fixture = WritableFixture::from("sample-02");
+    let now = std::time::SystemTime::now();
+    for (path, age_in_days) in [
+        ("a", 3),
+        ("b", 2),
+        ("dir/c", 5),
+        ("dir/d", 5),
+        ("dir/sub/e", 1),
+    ] {
+        std::fs::File::options()
+            .write(true)
+            .open(fixture.as_ref().join(path))?
+            .set_modified(now - std::time::Duration::from_secs(age_in_days * 24 * 60 * 60))?;
+    }
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| {
+                app.traversal.tree.names[e.data.name]
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // when entering the fixture and hitting the M key
+    app.process_events(&mut terminal, into_keys(b"oM".iter()))?;
+    assert_eq!(names(&app), vec!["dir", "b", "a"], "newest first");
+    assert_eq!(
+        node_by_name(&app, "dir").mtime,
+        node_by_name(&app, "e").mtime,
+        "directories are as new as their newest entry"
+    );
+
+    // when hitting the M key again
+    app.process_events(&mut terminal, into_keys(b"M".iter()))?;
+    assert_eq!(names(&app), vec!["a", "b", "dir"], "oldest first");
+    Ok(())
+}
+
+#[test]
+fn polling_picks_up_entries_created_and_removed_outside_of_dua() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.poll_changes = true;
+    let total_before = app.traversal.total_bytes;
+
+    // when entering the input path and changing 'dir' within it while time passes
+    app.process_events(&mut terminal, into_keys(b"l".iter()))?;
+    std::fs::write(fixture.as_ref().join("dir").join("new.5000b"), [0; 5000])?;
+    std::fs::remove_file(fixture.as_ref().join("dir").join("1000bytes"))?;
+    app.process_events(&mut terminal, vec![Event::Tick].into_iter())?;
+
+    let names: Vec<_> = app
+        .traversal
+        .tree
+        .neighbors_directed(
+            app.state
+                .entries
+                .iter()
+                .find(|e| app.traversal.tree.names[e.data.name].to_str() == Some("dir"))
+                .map(|e| e.index)
+                .expect("dir"),
+            petgraph::Direction::Outgoing,
+        )
+        .map(|idx| {
+            app.traversal
+                .tree
+                .name_of(idx)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    assert!(names.contains(&"new.5000b".to_string()), "{:?}", names);
+    assert!(!names.contains(&"1000bytes".to_string()), "{:?}", names);
+    assert!(
+        app.traversal.total_bytes > total_before,
+        "the total is updated"
+    );
+    Ok(())
+}
+
+#[test]
+fn rescanning_picks_up_changes_made_outside_of_dua() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let size_before = node_by_name(&app, "dir").size;
+    let total_before = app.traversal.total_bytes;
+
+    // when entering 'dir', changing it and rescanning
+    app.process_events(&mut terminal, into_keys(b"l".iter()))?;
+    let dir = app
+        .state
+        .entries
+        .iter()
+        .find(|e| app.traversal.tree.names[e.data.name].to_str() == Some("dir"))
+        .map(|e| e.index)
+        .expect("dir");
+    app.state.selected = Some(dir);
+    app.process_events(&mut terminal, into_keys(b"lj".iter()))?;
+    let selected_name = app
+        .traversal
+        .tree
+        .name_of(app.state.selected.expect("selection"))
+        .to_path_buf();
+    std::fs::write(fixture.as_ref().join("dir").join("new.5000b"), [0; 5000])?;
+    std::fs::remove_file(fixture.as_ref().join("dir").join("1000bytes"))?;
+    app.process_events(&mut terminal, into_keys(b"r".iter()))?;
+
+    let names: Vec<_> = app
+        .state
+        .entries
+        .iter()
+        .map(|e| {
+            app.traversal.tree.names[e.data.name]
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    assert!(names.contains(&"new.5000b".to_string()), "{:?}", names);
+    assert!(!names.contains(&"1000bytes".to_string()), "{:?}", names);
+    assert_eq!(
+        app.traversal
+            .tree
+            .name_of(app.state.selected.expect("selection")),
+        selected_name,
+        "the selection is kept"
+    );
+    assert!(
+        node_by_name(&app, "dir").size > size_before,
+        "the size of the directory is updated"
+    );
+    assert!(
+        app.traversal.total_bytes > total_before,
+        "the total is updated"
+    );
+    assert_eq!(
+        app.traversal.tree.node_count() as u64,
+        app.traversal.entries_traversed + 1,
+        "only the root isn't counted as entry"
+    );
+    Ok(())
+}
+
+#[test]
+fn directories_changed_since_the_scan_was_cached_can_be_rescanned() -> Result<()> {
+    use std::time::{Duration, SystemTime};
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    // when asking to rescan changed directories of a scan that isn't cached
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Changed directories are only looked for in scans from the cache")
+    );
+
+    // when nothing changed since the scan was cached
+    app.state.cached_at = Some(SystemTime::now() + Duration::from_secs(60));
+    app.state
+        .look_for_stale_directories(&mut app.window, &mut app.traversal);
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Showing the scan cached 0s ago, no directories changed since")
+    );
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("No directories changed since the scan was cached")
+    );
+
+    // when the fixture, which was just created, changed since the scan was cached
+    std::fs::write(fixture.as_ref().join("dir").join("new.5000b"), [0; 5000])?;
+    std::fs::remove_file(fixture.as_ref().join("dir").join("1000bytes"))?;
+    app.state.cached_at = Some(SystemTime::now() - Duration::from_secs(2 * 60 * 60));
+    app.state.changes_checked_at = None;
+    app.state
+        .look_for_stale_directories(&mut app.window, &mut app.traversal);
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Showing the scan cached 2h ago, 1 directory changed since, press R to rescan it"),
+        "only the outermost changed directory is rescanned"
+    );
+    let total_before = app.traversal.total_bytes;
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Rescanned 1 changed directory")
+    );
+    assert!(app.state.changes_checked_at.is_some());
+    assert!(
+        app.traversal.total_bytes > total_before,
+        "the total is updated"
+    );
+    let names: Vec<_> = app
+        .traversal
+        .tree
+        .node_indices()
+        .map(|idx| {
+            app.traversal
+                .tree
+                .name_of(idx)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    assert!(names.contains(&"new.5000b".to_string()), "{:?}", names);
+    assert!(!names.contains(&"1000bytes".to_string()), "{:?}", names);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn broken_links_and_the_directories_containing_them_can_be_shown() -> Result<()> {
+    use std::os::unix::fs::symlink;
+    let root = TempDir::new("broken-links-journey")?;
+    std::fs::create_dir_all(root.join("dir/sub"))?;
+    std::fs::create_dir_all(root.join("other"))?;
+    std::fs::write(root.join("file"), b"content")?;
+    symlink("file", root.join("alive"))?;
+    symlink("../gone", root.join("dir/sub/dead"))?;
+
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&root.root))?;
+    let names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| {
+                app.traversal.tree.names[e.data.name]
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // when entering the root and cycling to broken links
+    app.process_events(&mut terminal, into_keys(b"ovvvv".iter()))?;
+    assert_eq!(app.state.message.as_deref(), Some("Showing broken links"));
+    assert_eq!(names(&app), vec!["dir"]);
+
+    // when following the directory down to the link
+    app.process_events(&mut terminal, into_keys(b"oo".iter()))?;
+    assert_eq!(names(&app), vec!["dead"]);
+    let dead = app.state.entries[0].index;
+    assert!(node_by_index(&app, dead).is_broken_link);
+    Ok(())
+}
+
+#[test]
+fn repositories_show_the_size_of_their_git_directory_and_working_tree() -> Result<()> {
+    let root = TempDir::new("repos-journey")?;
+    std::fs::create_dir_all(root.join("repo/.git"))?;
+    std::fs::write(root.join("repo/.git/HEAD"), [0; 300])?;
+    std::fs::write(root.join("repo/README"), [0; 100])?;
+
+    let (_, mut app) = initialized_app_and_terminal_from_paths(std::slice::from_ref(&root.root))?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 10))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        buf.content
+            .iter()
+            .map(|c| c.symbol.as_str())
+            .collect::<String>()
+    };
+
+    // when entering the root, the repository is annotated
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(
+        screen(&terminal).contains("/repo  [git: .git 300  B, working tree 100  B]"),
+        "{}",
+        screen(&terminal)
+    );
+    Ok(())
+}
+
+#[test]
+fn archives_can_be_browsed_but_their_entries_not_marked() -> Result<()> {
+    let root = TempDir::new("archives-journey")?;
+    std::fs::create_dir_all(root.join("content/dir"))?;
+    std::fs::write(root.join("content/dir/file"), [0; 3000])?;
+    let status = match std::process::Command::new("tar")
+        .args(["cf", "../content.tar", "dir"])
+        .current_dir(root.join("content"))
+        .status()
+    {
+        Ok(status) => status,
+        Err(_) => return Ok(()), // tar isn't installed
+    };
+    assert!(status.success());
+    std::fs::remove_dir_all(root.join("content"))?;
+
+    let (_, mut app) = initialized_app_and_terminal_with_closure(
+        std::slice::from_ref(&root),
+        |p| p.to_owned(),
+        |options| options.archives = true,
+    )?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(100, 10))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        let buf = terminal.backend.buffer();
+        buf.content
+            .iter()
+            .map(|c| c.symbol.as_str())
+            .collect::<String>()
+    };
+
+    // when entering the root, the archive is annotated with the size of its contents
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(
+        screen(&terminal).contains(" content.tar  [archive, 3.00 KB unpacked]"),
+        "{}",
+        screen(&terminal)
+    );
+
+    // the archive can be entered, but what's inside can't be marked
+    app.process_events(&mut terminal, into_keys(b"oo".iter()))?;
+    assert_eq!(node_by_name(&app, "file").size, 3000);
+    assert!(node_by_name(&app, "file").is_in_archive);
+    app.process_events(&mut terminal, into_keys(b" ".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Entries within archives can't be deleted on their own")
+    );
+    assert!(app.window.mark_pane.is_none());
+
+    // the archive itself can be marked
+    app.process_events(&mut terminal, into_keys(b"uu ".iter()))?;
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1)
     );
     Ok(())
 }
@@ -1,5 +1,7 @@
 use crate::interactive::app::tests::utils::{
-    debug, initialized_app_and_terminal_from_fixture, sample_01_tree, sample_02_tree,
+    debug, initialized_app_and_terminal_from_fixture,
+    initialized_app_and_terminal_from_fixture_with_options, portable, sample_01_tree,
+    sample_02_tree,
 };
 use anyhow::Result;
 use pretty_assertions::assert_eq;
@@ -10,8 +12,8 @@ fn it_can_handle_ending_traversal_reaching_top_but_skipping_levels() -> Result<(
     let expected_tree = sample_01_tree();
 
     assert_eq!(
-        debug(app.traversal.tree),
-        debug(expected_tree),
+        debug(portable(&app.traversal.tree)),
+        debug(portable(&expected_tree)),
         "filesystem graph is stable and matches the directory structure"
     );
     Ok(())
@@ -23,9 +25,23 @@ fn it_can_handle_ending_traversal_without_reaching_the_top() -> Result<()> {
     let expected_tree = sample_02_tree();
 
     assert_eq!(
-        debug(app.traversal.tree),
-        debug(expected_tree),
+        debug(portable(&app.traversal.tree)),
+        debug(portable(&expected_tree)),
         "filesystem graph is stable and matches the directory structure"
     );
     Ok(())
 }
+
+#[test]
+fn it_skips_excluded_entries_along_with_their_children() -> Result<()> {
+    let (_, app) = initialized_app_and_terminal_from_fixture_with_options(&["sample-02"], |o| {
+        o.exclude = vec!["dir".parse().unwrap()]
+    })?;
+
+    assert_eq!(
+        app.traversal.entries_traversed, 3,
+        "only the root and its files 'a' and 'b' are seen"
+    );
+    assert_eq!(app.traversal.total_bytes, Some(257));
+    Ok(())
+}
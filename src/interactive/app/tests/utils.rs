@@ -1,11 +1,12 @@
 use anyhow::{Context, Error, Result};
 use dua::{
-    traverse::{EntryData, Tree, TreeIndex},
-    ByteFormat, TraversalSorting, WalkOptions,
+    filesystem::Filesystem,
+    traverse::{EntryData, Traversal, Tree, TreeIndex},
+    TraversalSorting, WalkOptions,
 };
 use itertools::Itertools;
 use jwalk::{DirEntry, WalkDir};
-use petgraph::prelude::NodeIndex;
+use petgraph::prelude::{NodeIndex, StableGraph};
 use std::{
     env::temp_dir,
     ffi::OsStr,
@@ -17,7 +18,7 @@ use std::{
 use tui::backend::TestBackend;
 use tui_react::Terminal;
 
-use crate::interactive::{app::tests::FIXTURE_PATH, Interaction, TerminalApp};
+use crate::interactive::{app::tests::FIXTURE_PATH, Interaction, Preferences, TerminalApp};
 
 pub fn into_keys<'a>(
     bytes: impl Iterator<Item = &'a u8> + 'a,
@@ -30,13 +31,13 @@ pub fn node_by_index(app: &TerminalApp, id: TreeIndex) -> &EntryData {
 }
 
 pub fn node_by_name(app: &TerminalApp, name: impl AsRef<OsStr>) -> &EntryData {
-    node_by_index(app, index_by_name(&app, name))
+    node_by_index(app, index_by_name(app, name))
 }
 
 pub fn index_by_name_and_size(
     app: &TerminalApp,
     name: impl AsRef<OsStr>,
-    size: Option<u128>,
+    size: Option<u64>,
 ) -> TreeIndex {
     let name = name.as_ref();
     let t: Vec<_> = app
@@ -45,7 +46,9 @@ pub fn index_by_name_and_size(
         .node_indices()
         .map(|idx| (idx, node_by_index(app, idx)))
         .filter_map(|(idx, e)| {
-            if e.name == name && size.map(|s| s == e.size).unwrap_or(true) {
+            if app.traversal.tree.names[e.name].as_os_str() == name
+                && size.map(|s| s == e.size).unwrap_or(true)
+            {
                 Some(idx)
             } else {
                 None
@@ -97,9 +100,7 @@ fn delete_recursive(path: impl AsRef<Path>) -> Result<()> {
                 .sorted_by_key(|p| p.components().count())
                 .rev()
                 .map(|d| {
-                    remove_dir(d)
-                        .with_context(|| format!("Could not delete '{}'", d.display()))
-                        .map_err(Error::from)
+                    remove_dir(d).with_context(|| format!("Could not delete '{}'", d.display()))
                 }),
         )
         .collect::<Result<_, _>>()
@@ -135,7 +136,7 @@ fn copy_recursive(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Er
 
 impl From<&'static str> for WritableFixture {
     fn from(fixture_name: &str) -> Self {
-        const TEMP_TLD_DIRNAME: &'static str = "dua-unit";
+        const TEMP_TLD_DIRNAME: &str = "dua-unit";
 
         let src = fixture(fixture_name);
         let dst = temp_dir().join(TEMP_TLD_DIRNAME);
@@ -164,21 +165,18 @@ pub fn fixture_str(p: impl AsRef<Path>) -> String {
 pub fn initialized_app_and_terminal_with_closure(
     fixture_paths: &[impl AsRef<Path>],
     mut convert: impl FnMut(&Path) -> PathBuf,
+    adjust_options: impl FnOnce(&mut WalkOptions),
 ) -> Result<(Terminal<TestBackend>, TerminalApp), Error> {
     let mut terminal = new_test_terminal()?;
     std::env::set_current_dir(Path::new(env!("CARGO_MANIFEST_DIR")))?;
 
     let input_paths = fixture_paths.iter().map(|c| convert(c.as_ref())).collect();
+    let mut options = walk_options();
+    adjust_options(&mut options);
     let app = TerminalApp::initialize(
         &mut terminal,
-        WalkOptions {
-            threads: 1,
-            byte_format: ByteFormat::Metric,
-            apparent_size: true,
-            count_hard_links: false,
-            sorting: TraversalSorting::AlphabeticalByFileName,
-            cross_filesystems: false,
-        },
+        options,
+        Preferences::default(),
         input_paths,
         Interaction::None,
     )?
@@ -189,6 +187,57 @@ pub fn initialized_app_and_terminal_with_closure(
     ))
 }
 
+fn walk_options() -> WalkOptions {
+    WalkOptions::default()
+        .threads(1)
+        .apparent_size(true)
+        .sorting(TraversalSorting::AlphabeticalByFileName)
+        .cross_filesystems(false)
+}
+
+/// Scan `path`, export it in the ncdu format and browse what was loaded from the export.
+pub fn initialized_app_and_terminal_from_ncdu_export(
+    path: &Path,
+) -> Result<(Terminal<TestBackend>, TerminalApp), Error> {
+    let mut terminal = new_test_terminal()?;
+    std::env::set_current_dir(Path::new(env!("CARGO_MANIFEST_DIR")))?;
+
+    let traversal = Traversal::from_walk(walk_options(), vec![path.to_owned()], |_| Ok(false))?;
+    let mut export = Vec::new();
+    dua::ncdu::export(&mut export, &traversal)?;
+    let traversal = dua::ncdu::import(&export, &walk_options())?;
+    let (_, app) = TerminalApp::initialize_from_traversal(
+        &mut terminal,
+        walk_options(),
+        Preferences::default(),
+        traversal,
+        Interaction::None,
+    )?;
+    Ok((terminal, app))
+}
+
+/// Browse the given `paths` of `filesystem`, like a made-up tree of a [`MemoryFilesystem`][dua::filesystem::MemoryFilesystem].
+pub fn initialized_app_and_terminal_from_filesystem(
+    filesystem: &mut dyn Filesystem,
+    paths: &[&str],
+) -> Result<(Terminal<TestBackend>, TerminalApp), Error> {
+    let mut terminal = new_test_terminal()?;
+    let traversal = Traversal::from_filesystem(
+        filesystem,
+        true,
+        paths.iter().map(PathBuf::from).collect(),
+        |_| Ok(false),
+    )?;
+    let (_, app) = TerminalApp::initialize_from_traversal(
+        &mut terminal,
+        walk_options(),
+        Preferences::default(),
+        traversal,
+        Interaction::None,
+    )?;
+    Ok((terminal, app))
+}
+
 pub fn new_test_terminal() -> std::io::Result<Terminal<TestBackend>> {
     Terminal::new(TestBackend::new(40, 20))
 }
@@ -199,13 +248,20 @@ pub fn initialized_app_and_terminal_from_paths(
     fn to_path_buf(p: &Path) -> PathBuf {
         p.to_path_buf()
     }
-    initialized_app_and_terminal_with_closure(fixture_paths, to_path_buf)
+    initialized_app_and_terminal_with_closure(fixture_paths, to_path_buf, |_| {})
 }
 
 pub fn initialized_app_and_terminal_from_fixture(
     fixture_paths: &[&str],
 ) -> Result<(Terminal<TestBackend>, TerminalApp), Error> {
-    initialized_app_and_terminal_with_closure(fixture_paths, |p| fixture(p))
+    initialized_app_and_terminal_from_fixture_with_options(fixture_paths, |_| {})
+}
+
+pub fn initialized_app_and_terminal_from_fixture_with_options(
+    fixture_paths: &[&str],
+    adjust_options: impl FnOnce(&mut WalkOptions),
+) -> Result<(Terminal<TestBackend>, TerminalApp), Error> {
+    initialized_app_and_terminal_with_closure(fixture_paths, |p| fixture(p), adjust_options)
 }
 
 pub fn sample_01_tree() -> Tree {
@@ -284,12 +340,13 @@ pub fn sample_02_tree() -> Tree {
 
 pub fn make_add_node<'a>(
     t: &'a mut Tree,
-) -> impl FnMut(&str, u128, Option<NodeIndex>) -> NodeIndex + 'a {
+) -> impl FnMut(&str, u64, Option<NodeIndex>) -> NodeIndex + 'a {
     move |name, size, maybe_from_idx| {
+        let name = t.names.intern(Path::new(name));
         let n = t.add_node(EntryData {
-            name: PathBuf::from(name),
+            name,
             size,
-            metadata_io_error: false,
+            ..Default::default()
         });
         if let Some(from) = maybe_from_idx {
             t.add_edge(from, n, ());
@@ -298,6 +355,21 @@ pub fn make_add_node<'a>(
     }
 }
 
+/// Drop everything from `tree` that depends on the filesystem the fixtures are checked out on, like disk usage, and
+/// the order names were first seen in, keeping the name, size and error of each entry.
+pub fn portable(tree: &Tree) -> StableGraph<(PathBuf, u64, bool), ()> {
+    tree.map(
+        |_, e| {
+            (
+                tree.names[e.name].to_path_buf(),
+                e.size,
+                e.metadata_io_error,
+            )
+        },
+        |_, e| *e,
+    )
+}
+
 pub fn debug(item: impl fmt::Debug) -> String {
     format!("{:?}", item)
 }
@@ -0,0 +1,204 @@
+//! Moving entries to the trash of the desktop environment, which allows to restore them later.
+#[cfg(unix)]
+use crate::path::absolute;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Whether entries can be moved to the trash on this platform. Without it, the keys to do so are neither shown nor
+/// handled.
+pub const TRASH_SUPPORTED: bool = cfg!(unix);
+
+/// Move `path` to the trash, without following it if it's a symlink.
+///
+/// On Linux and other unixes, the home trash of the [freedesktop.org Trash specification] is used,
+/// which only works for entries on the same filesystem.
+///
+/// [freedesktop.org Trash specification]: https://specifications.freedesktop.org/trash-spec/trashspec-latest.html
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find the home trash"))?;
+    file_name(path)?;
+    freedesktop::move_to_trash(&absolute(path)?, &data_home.join("Trash"))
+}
+
+/// Move `path` to the trash of the current user, without following it if it's a symlink.
+///
+/// Only the entry is moved to `~/.Trash`, without recording where it came from, so the Finder can't put it back.
+/// Entries need to be moved back by hand.
+#[cfg(target_os = "macos")]
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    let trash = std::env::var_os("HOME")
+        .map(|home| Path::new(&home).join(".Trash"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find the trash"))?;
+    file_name(path)?;
+    let path = absolute(path)?;
+    let name = file_name(&path)?;
+    for attempt in 0.. {
+        let mut trashed_name = name.to_owned();
+        if attempt > 0 {
+            trashed_name.push(format!(" {}", attempt));
+        }
+        let destination = trash.join(trashed_name);
+        if destination.symlink_metadata().is_err() {
+            return rename(&path, &destination);
+        }
+    }
+    unreachable!("there are more names to try than can ever be in the trash")
+}
+
+/// Fail, as the Recycle Bin of Windows can only be used through its shell API.
+#[cfg(not(unix))]
+pub fn move_to_trash(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Moving entries to the trash isn't supported on this platform",
+    ))
+}
+
+#[cfg(unix)]
+fn file_name(path: &Path) -> io::Result<&std::ffi::OsStr> {
+    path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' can't be moved to the trash", path.display()),
+        )
+    })
+}
+
+#[cfg(unix)]
+fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::rename(from, to).map_err(|err| match err.kind() {
+        io::ErrorKind::CrossesDevices => io::Error::new(
+            err.kind(),
+            format!(
+                "'{}' is on another filesystem than the trash in '{}'",
+                from.display(),
+                to.parent().unwrap_or(to).display()
+            ),
+        ),
+        _ => err,
+    })
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod freedesktop {
+    use std::{
+        fs,
+        io::{self, Write},
+        os::unix::ffi::OsStrExt,
+        path::Path,
+        time::SystemTime,
+    };
+
+    /// Move the absolute `path` into the `trash` directory, along with the information needed to restore it.
+    pub fn move_to_trash(path: &Path, trash: &Path) -> io::Result<()> {
+        let (files, info) = (trash.join("files"), trash.join("info"));
+        fs::create_dir_all(&files)?;
+        fs::create_dir_all(&info)?;
+
+        let name = super::file_name(path)?;
+        for attempt in 0.. {
+            let mut trashed_name = name.to_owned();
+            if attempt > 0 {
+                trashed_name.push(format!(".{}", attempt));
+            }
+            let mut info_name = trashed_name.clone();
+            info_name.push(".trashinfo");
+            let info_path = info.join(info_name);
+            // The info file is created first and exclusively to reserve the name, as per the specification
+            let mut info_file = match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            };
+            return write!(
+                info_file,
+                "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                percent_encoded(path),
+                deletion_date(SystemTime::now())
+            )
+            .and_then(|_| super::rename(path, &files.join(trashed_name)))
+            .inspect_err(|_| {
+                fs::remove_file(&info_path).ok();
+            });
+        }
+        unreachable!("there are more names to try than can ever be in the trash")
+    }
+
+    pub(super) fn percent_encoded(path: &Path) -> String {
+        let mut out = String::new();
+        for byte in path.as_os_str().as_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                    out.push(*byte as char)
+                }
+                _ => out.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        out
+    }
+
+    /// The time of deletion as `YYYY-MM-DDThh:mm:ss`. It's in UTC, as the local timezone isn't known to us.
+    pub(super) fn deletion_date(time: SystemTime) -> String {
+        dua::utc_date_time(time)
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::freedesktop::*;
+    use std::{
+        fs,
+        path::Path,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    #[test]
+    fn it_formats_deletion_dates_and_encodes_paths() {
+        assert_eq!(deletion_date(UNIX_EPOCH), "1970-01-01T00:00:00");
+        assert_eq!(
+            deletion_date(UNIX_EPOCH + Duration::from_secs(951825600)),
+            "2000-02-29T12:00:00"
+        );
+        assert_eq!(
+            deletion_date(UNIX_EPOCH + Duration::from_secs(1623200000)),
+            "2021-06-09T00:53:20"
+        );
+        assert_eq!(
+            percent_encoded(Path::new("/home/a b/ü%.txt")),
+            "/home/a%20b/%C3%BC%25.txt"
+        );
+    }
+
+    #[test]
+    fn it_moves_entries_into_the_trash_with_unique_names() -> std::io::Result<()> {
+        let root = crate::testing::TempDir::new("trash")?;
+        let trash = root.join("Trash");
+        let dir = root.join("dir");
+        for _ in 0..2 {
+            fs::create_dir_all(dir.join("sub"))?;
+            fs::write(dir.join("sub").join("file"), b"content")?;
+            move_to_trash(&dir, &trash)?;
+            assert!(!dir.exists());
+        }
+
+        assert!(trash.join("files/dir/sub/file").is_file());
+        assert!(trash.join("files/dir.1/sub/file").is_file());
+        let info = fs::read_to_string(trash.join("info/dir.1.trashinfo"))?;
+        assert!(info.starts_with(&format!(
+            "[Trash Info]\nPath={}\nDeletionDate=",
+            dir.display()
+        )));
+        Ok(())
+    }
+}
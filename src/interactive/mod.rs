@@ -4,29 +4,17 @@ pub use app::*;
 pub mod widgets;
 
 mod utils {
-    use dua::{
-        get_entry_or_panic,
-        traverse::{Tree, TreeIndex},
-    };
-    use std::path::PathBuf;
+    pub use dua::traverse::path_of;
+    use dua::traverse::{Tree, TreeIndex};
 
-    pub fn path_of(tree: &Tree, mut node_idx: TreeIndex) -> PathBuf {
-        const THE_ROOT: usize = 1;
-        let mut entries = Vec::new();
-
-        while let Some(parent_idx) = tree.neighbors_directed(node_idx, petgraph::Incoming).next() {
-            entries.push(get_entry_or_panic(tree, node_idx));
-            node_idx = parent_idx;
-        }
-        entries.push(get_entry_or_panic(tree, node_idx));
-        entries
-            .iter()
-            .rev()
-            .skip(THE_ROOT)
-            .fold(PathBuf::new(), |mut acc, entry| {
-                acc.push(&entry.name);
-                acc
-            })
+    /// Return a label for `top`, the node above all input paths, unless they are all in the current directory,
+    /// which it stands for then.
+    pub fn top_label(tree: &Tree, top: TreeIndex) -> Option<&'static str> {
+        let is_in_current_dir = tree
+            .neighbors_directed(top, petgraph::Outgoing)
+            .map(|idx| tree.name_of(idx))
+            .all(|name| name.is_relative() && name.components().count() == 1);
+        (!is_in_current_dir).then_some("input paths")
     }
 }
-pub use utils::path_of;
+pub use utils::{path_of, top_label};
@@ -0,0 +1,132 @@
+use crate::interactive::{top_label, widgets::Theme};
+use dua::traverse::{Tree, TreeIndex};
+use petgraph::Direction;
+use std::{borrow::Borrow, ops::Range};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Paragraph, Widget},
+};
+use tui_react::util::block_width;
+
+const SEPARATOR: &str = " › ";
+const ELLIPSIS: &str = "…";
+
+/// A line with the path from the root of the traversal to the directory that is shown, with numbered segments.
+#[derive(Default)]
+pub struct Breadcrumbs {
+    /// The segments as drawn last, along with the columns they occupy
+    segments: Vec<(TreeIndex, Range<u16>)>,
+    area: Rect,
+}
+
+pub struct BreadcrumbsProps<'a> {
+    pub tree: &'a Tree,
+    pub root: TreeIndex,
+    pub theme: Theme,
+}
+
+/// Return all directories from the root of the `tree` down to `root`, both inclusive.
+pub fn breadcrumbs_of(tree: &Tree, root: TreeIndex) -> Vec<TreeIndex> {
+    let mut path = vec![root];
+    let mut node_idx = root;
+    while let Some(parent) = tree
+        .neighbors_directed(node_idx, Direction::Incoming)
+        .next()
+    {
+        path.push(parent);
+        node_idx = parent;
+    }
+    path.reverse();
+    path
+}
+
+impl Breadcrumbs {
+    /// Return the directory whose segment was drawn at the given terminal cell, if there is one.
+    pub fn segment_at(&self, column: u16, row: u16) -> Option<TreeIndex> {
+        if row != self.area.y {
+            return None;
+        }
+        self.segments
+            .iter()
+            .find(|(_, columns)| columns.contains(&column))
+            .map(|(index, _)| *index)
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<BreadcrumbsProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let BreadcrumbsProps { tree, root, theme } = props.borrow();
+        let path = breadcrumbs_of(tree, *root);
+        let labels: Vec<_> = path
+            .iter()
+            .enumerate()
+            .map(|(pos, &index)| {
+                let name = match tree.neighbors_directed(index, Direction::Incoming).next() {
+                    Some(_) => tree.name_of(index).to_string_lossy().into_owned(),
+                    None => top_label(tree, index).unwrap_or(".").to_owned(),
+                };
+                let number = match pos + 1 {
+                    n @ 1..=9 => format!("{} ", n),
+                    _ => String::new(),
+                };
+                (index, number, name)
+            })
+            .collect();
+
+        // Leave out the segments closest to the root until the rest fits
+        let width_of = |labels: &[(TreeIndex, String, String)]| -> u16 {
+            labels
+                .iter()
+                .map(|(_, number, name)| block_width(number) + block_width(name))
+                .sum::<u16>()
+                + block_width(SEPARATOR) * labels.len().saturating_sub(1) as u16
+                + 1
+        };
+        let ellipsis_width = block_width(ELLIPSIS) + block_width(SEPARATOR);
+        let mut first = 0;
+        while first + 1 < labels.len()
+            && width_of(&labels[first..]) + if first > 0 { ellipsis_width } else { 0 } > area.width
+        {
+            first += 1;
+        }
+
+        let number_style = Style::default()
+            .fg(theme.highlight)
+            .add_modifier(Modifier::BOLD);
+        let separator_style = Style::default().fg(theme.border);
+        let mut spans = vec![Span::raw(" ")];
+        if first > 0 {
+            spans.push(Span::styled(ELLIPSIS, separator_style));
+            spans.push(Span::styled(SEPARATOR, separator_style));
+        }
+        let mut column = area.x + spans.iter().map(|s| block_width(&s.content)).sum::<u16>();
+        self.segments.clear();
+        let last = labels.len() - 1;
+        for (pos, (index, number, name)) in labels.into_iter().enumerate().skip(first) {
+            if pos > first {
+                spans.push(Span::styled(SEPARATOR, separator_style));
+                column += block_width(SEPARATOR);
+            }
+            let name_style = if pos == last {
+                Style::default()
+                    .fg(theme.directory)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.directory)
+            };
+            let start = column;
+            column += block_width(&number) + block_width(&name);
+            spans.push(Span::styled(number, number_style));
+            spans.push(Span::styled(name, name_style));
+            self.segments.push((index, start..column));
+        }
+        self.area = area;
+        Paragraph::new(Text::from(Spans::from(spans))).render(area, buf);
+    }
+}
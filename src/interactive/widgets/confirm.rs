@@ -0,0 +1,233 @@
+use crate::interactive::{
+    fit_string_graphemes_with_ellipsis, widgets::Theme, Deletion, DeletionMode, TRASH_SUPPORTED,
+};
+use dua::{traverse::TreeIndex, ByteFormat};
+use std::{borrow::Borrow, path::PathBuf};
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A modal dialog asking whether an entry should really be deleted.
+pub struct DeleteConfirmation {
+    pub index: TreeIndex,
+    pub path: PathBuf,
+    pub size: u128,
+    pub entry_count: u64,
+    pub is_dir: bool,
+    /// If set, the entry is protected or large, and a single key doesn't suffice to confirm its deletion
+    pub required_input: Option<RequiredInput>,
+}
+
+/// Something to type to confirm the deletion of an entry.
+pub struct RequiredInput {
+    /// Why it must be typed, like "It's the home directory, type its path"
+    pub reason: String,
+    pub expected: String,
+    pub typed: String,
+}
+
+impl RequiredInput {
+    pub fn new(reason: String, expected: String) -> Self {
+        RequiredInput {
+            reason,
+            expected,
+            typed: String::new(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.typed == self.expected
+    }
+}
+
+pub struct DeleteConfirmationProps {
+    pub format: ByteFormat,
+    pub theme: Theme,
+}
+
+impl DeleteConfirmation {
+    pub fn render(
+        &self,
+        props: impl Borrow<DeleteConfirmationProps>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let DeleteConfirmationProps { format, theme } = props.borrow();
+        let DeleteConfirmation {
+            path,
+            size,
+            entry_count,
+            is_dir,
+            required_input,
+            ..
+        } = self;
+
+        let what = if *is_dir {
+            format!(
+                "{} in {} entr{} will be deleted.",
+                format.display(*size),
+                entry_count + 1,
+                if *entry_count == 0 { "y" } else { "ies" }
+            )
+        } else {
+            format!("{} will be deleted.", format.display(*size))
+        };
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Spans::from(Span::styled(path.to_string_lossy().into_owned(), bold)),
+            Spans::from(""),
+            Spans::from(what),
+            Spans::from(""),
+        ];
+        match required_input {
+            Some(required) => lines.extend(vec![
+                Spans::from(format!("{}, to confirm:", required.reason)),
+                Spans::from(vec![
+                    Span::styled(
+                        required.typed.clone(),
+                        bold.fg(if required.is_complete() {
+                            theme.accent
+                        } else {
+                            theme.danger
+                        }),
+                    ),
+                    Span::raw("_"),
+                ]),
+                Spans::from(""),
+                Spans::from(choices(("Enter", "Ctrl + t"), theme.accent)),
+                Spans::from(vec![
+                    Span::styled("Esc", bold.fg(theme.accent)),
+                    Span::raw(" = cancel"),
+                ]),
+            ]),
+            None => lines.extend(vec![
+                Spans::from(choices(("y", "t"), theme.accent)),
+                Spans::from(vec![
+                    Span::styled("any other key", bold.fg(theme.accent)),
+                    Span::raw(" = cancel"),
+                ]),
+            ]),
+        }
+        let height = lines.len() as u16 + 3;
+        let text = Text::from(lines);
+
+        let area = centered(area, 64, height);
+        Clear.render(area, buf);
+        Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Delete? ")
+                    .borders(Borders::ALL)
+                    .border_style(bold.fg(theme.danger)),
+            )
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+pub struct DeletionProgressProps<'a> {
+    pub deletion: &'a Deletion,
+    pub format: ByteFormat,
+    pub theme: Theme,
+}
+
+/// A modal dialog telling how far a deletion got, until it's done.
+pub struct DeletionProgress;
+
+impl DeletionProgress {
+    pub fn render<'a>(
+        &self,
+        props: impl Borrow<DeletionProgressProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let DeletionProgressProps {
+            deletion,
+            format,
+            theme,
+        } = props.borrow();
+        let progress = &deletion.progress;
+
+        let area = centered(area, 64, 8);
+        let path = progress.path.to_string_lossy();
+        let path_width = area.width.saturating_sub(2) as usize;
+        let (path, _) = fit_string_graphemes_with_ellipsis(
+            path.as_ref(),
+            path.graphemes(true).count(),
+            path_width,
+        );
+        let percentage = match deletion.total_entries {
+            0 => 100,
+            total => (progress.entries_removed * 100 / total).min(100),
+        };
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let text = Text::from(vec![
+            Spans::from(Span::styled(path, bold)),
+            Spans::from(""),
+            Spans::from(format!(
+                "{} of {} entries ({}%), {} freed in {}s",
+                progress.entries_removed,
+                deletion.total_entries,
+                percentage,
+                format.display(progress.bytes_freed),
+                deletion.started.elapsed().as_secs()
+            )),
+            Spans::from(""),
+            if deletion.is_aborting() {
+                Spans::from("Aborting…")
+            } else {
+                Spans::from(vec![
+                    Span::styled("Esc", bold.fg(theme.accent)),
+                    Span::raw(" = abort"),
+                ])
+            },
+        ]);
+
+        Clear.render(area, buf);
+        Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(match deletion.mode {
+                        DeletionMode::Permanently => " Deleting… ",
+                        DeletionMode::ToTrash => " Moving to the trash… ",
+                    })
+                    .borders(Borders::ALL)
+                    .border_style(bold.fg(theme.danger)),
+            )
+            .alignment(Alignment::Center)
+            .render(area, buf);
+    }
+}
+
+/// The keys to delete permanently and, where it's supported, to move to the trash, along with what they do.
+fn choices((delete, trash): (&'static str, &'static str), accent: Color) -> Vec<Span<'static>> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut spans = vec![
+        Span::styled(delete, bold.fg(accent)),
+        Span::raw(" = delete permanently"),
+    ];
+    if TRASH_SUPPORTED {
+        spans.extend(vec![
+            Span::raw(" | "),
+            Span::styled(trash, bold.fg(accent)),
+            Span::raw(" = move to trash"),
+        ]);
+    }
+    spans
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let (width, height) = (width.min(area.width), height.min(area.height));
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,109 @@
+use crate::interactive::{widgets::Theme, CursorDirection};
+use crosstermion::{input::Key, input::Key::*};
+use dua::{ByteFormat, Device};
+use std::{borrow::Borrow, path::PathBuf};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Clear, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// An overlay listing the mounted filesystems with their size and free space, to pick one to scan.
+pub struct DevicesPane {
+    devices: Vec<Device>,
+    selected: usize,
+    list: List,
+}
+
+pub struct DevicesPaneProps {
+    pub format: ByteFormat,
+    pub border_style: Style,
+    pub theme: Theme,
+}
+
+impl DevicesPane {
+    pub fn new(devices: Vec<Device>) -> Self {
+        DevicesPane {
+            devices,
+            selected: 0,
+            list: Default::default(),
+        }
+    }
+
+    /// Handle `key`, returning `None` if the pane was closed, along with the mount point to scan if one was chosen.
+    pub fn process_events(mut self, key: Key) -> Option<(Self, Option<PathBuf>)> {
+        let direction = match key {
+            Esc | Char('q') | Char('V') => return None,
+            Char('\n') | Char('o') | Char('l') | Right => {
+                let device = self.devices.get(self.selected)?;
+                let mount_point = device.mount_point.clone();
+                return Some((self, Some(mount_point)));
+            }
+            Char('H') | Char('g') | Home => CursorDirection::ToTop,
+            Char('G') | End => CursorDirection::ToBottom,
+            Ctrl('u') | PageUp => CursorDirection::PageUp,
+            Char('k') | Up => CursorDirection::Up,
+            Char('j') | Down => CursorDirection::Down,
+            Ctrl('d') | PageDown => CursorDirection::PageDown,
+            _ => return Some((self, None)),
+        };
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(self.devices.len().saturating_sub(1));
+        Some((self, None))
+    }
+
+    pub fn render(&mut self, props: impl Borrow<DevicesPaneProps>, area: Rect, buf: &mut Buffer) {
+        let DevicesPaneProps {
+            format,
+            border_style,
+            theme,
+        } = props.borrow();
+        let title = format!(" Devices ({}), <enter> scans one ", self.devices.len());
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner_area = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let selected = self.selected;
+        let width = inner_area.width as usize;
+        let byte_column_width = format.width();
+        let lines = self.devices.iter().enumerate().map(|(idx, device)| {
+            let style = if idx == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            let sizes = format!(
+                " {:>w$} free of {:>w$} {:>3.0}% used ",
+                format.display(device.available).to_string(),
+                format.display(device.total).to_string(),
+                device.used_fraction() * 100.0,
+                w = byte_column_width
+            );
+            let name = format!("{} ({})", device.mount_point.display(), device.filesystem);
+            vec![
+                Span::styled(sizes, style.fg(theme.size)),
+                Span::styled(
+                    format!(
+                        "{:<width$}",
+                        name,
+                        width = width.saturating_sub(byte_column_width * 2 + 21)
+                    ),
+                    style,
+                ),
+            ]
+        });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, lines, inner_area, buf);
+    }
+}
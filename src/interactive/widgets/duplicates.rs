@@ -0,0 +1,138 @@
+use crate::interactive::{widgets::Theme, CursorDirection};
+use crosstermion::{input::Key, input::Key::*};
+use dua::{traverse::TreeIndex, ByteFormat, DuplicateSet};
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Clear, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// An overlay listing sets of files with the same content, each followed by its files, to jump to one of them.
+pub struct DuplicatesPane {
+    sets: Vec<DuplicateSet<TreeIndex>>,
+    /// The set and the file within it of each line, or `None` for the line describing the set
+    lines: Vec<(usize, Option<usize>)>,
+    selected: usize,
+    list: List,
+}
+
+pub struct DuplicatesPaneProps {
+    pub format: ByteFormat,
+    pub border_style: Style,
+    pub theme: Theme,
+}
+
+impl DuplicatesPane {
+    pub fn new(sets: Vec<DuplicateSet<TreeIndex>>) -> Self {
+        let lines = sets
+            .iter()
+            .enumerate()
+            .flat_map(|(set_idx, set)| {
+                std::iter::once((set_idx, None))
+                    .chain((0..set.files.len()).map(move |file_idx| (set_idx, Some(file_idx))))
+            })
+            .collect();
+        DuplicatesPane {
+            sets,
+            lines,
+            selected: 0,
+            list: Default::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn sets(&self) -> &[DuplicateSet<TreeIndex>] {
+        &self.sets
+    }
+
+    /// Handle `key`, returning `None` if the pane was closed, along with the entry to jump to if one was chosen.
+    ///
+    /// Choosing the line of a set chooses its first file.
+    pub fn process_events(mut self, key: Key) -> Option<(Self, Option<TreeIndex>)> {
+        let direction = match key {
+            Esc | Char('q') | Char('D') => return None,
+            Char('\n') | Char('o') | Char('l') | Right => {
+                let (set_idx, file_idx) = *self.lines.get(self.selected)?;
+                let index = self.sets[set_idx].files[file_idx.unwrap_or(0)].1;
+                return Some((self, Some(index)));
+            }
+            Char('H') | Char('g') | Home => CursorDirection::ToTop,
+            Char('G') | End => CursorDirection::ToBottom,
+            Ctrl('u') | PageUp => CursorDirection::PageUp,
+            Char('k') | Up => CursorDirection::Up,
+            Char('j') | Down => CursorDirection::Down,
+            Ctrl('d') | PageDown => CursorDirection::PageDown,
+            _ => return Some((self, None)),
+        };
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(self.lines.len().saturating_sub(1));
+        Some((self, None))
+    }
+
+    pub fn render(
+        &mut self,
+        props: impl Borrow<DuplicatesPaneProps>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let DuplicatesPaneProps {
+            format,
+            border_style,
+            theme,
+        } = props.borrow();
+        let title = format!(
+            " Duplicates ({} set{}, {} reclaimable) ",
+            self.sets.len(),
+            if self.sets.len() == 1 { "" } else { "s" },
+            format.display(self.sets.iter().map(|s| s.reclaimable_bytes()).sum())
+        );
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner_area = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let (sets, selected) = (&self.sets, self.selected);
+        let width = inner_area.width as usize;
+        let lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(idx, &(set_idx, file_idx))| {
+                let style = if idx == selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                let set = &sets[set_idx];
+                let (text, style) = match file_idx {
+                    None => (
+                        format!(
+                            " {} × {} ({} reclaimable)",
+                            format.display(set.size as u128),
+                            set.files.len(),
+                            format.display(set.reclaimable_bytes())
+                        ),
+                        style.fg(theme.accent).add_modifier(Modifier::BOLD),
+                    ),
+                    Some(file_idx) => (format!("   {}", set.files[file_idx].0.display()), style),
+                };
+                vec![Span::styled(
+                    format!("{:<width$}", text, width = width),
+                    style,
+                )]
+            });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, lines, inner_area, buf);
+    }
+}
@@ -1,17 +1,23 @@
 use crate::interactive::{
-    path_of,
+    path_of, top_label,
     widgets::{entry_color, EntryMarkMap},
-    DisplayOptions, EntryDataBundle,
+    Action, Column, DisplayOptions, EntryDataBundle, KeyMap, PercentageBase, SortMode,
 };
 use dua::traverse::{Tree, TreeIndex};
 use itertools::Itertools;
-use std::{borrow::Borrow, path::Path};
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    ops::Range,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 use tui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Span,
-    widgets::{Block, Borders},
+    widgets::{Block, Borders, Widget},
 };
 use tui_react::util::rect::line_bound;
 use tui_react::{
@@ -20,6 +26,27 @@ use tui_react::{
     List, ListProps,
 };
 
+/// The width of the entry count column, enough to show counts up to a billion
+const ENTRY_COUNT_WIDTH: usize = 11;
+/// The width of the modification time column, enough for ages like `364d`
+const MTIME_WIDTH: usize = 4;
+
+/// Format `age` compactly in the largest unit it has at least one of, like `3d` or `2y`.
+pub fn format_age(age: Duration) -> String {
+    const UNITS: &[(u64, &str)] = &[
+        (365 * 24 * 60 * 60, "y"),
+        (24 * 60 * 60, "d"),
+        (60 * 60, "h"),
+        (60, "m"),
+    ];
+    let secs = age.as_secs();
+    UNITS
+        .iter()
+        .find(|(unit, _)| secs >= *unit)
+        .map(|(unit, suffix)| format!("{}{}", secs / unit, suffix))
+        .unwrap_or_else(|| format!("{}s", secs))
+}
+
 pub struct EntriesProps<'a> {
     pub tree: &'a Tree,
     pub root: TreeIndex,
@@ -29,14 +56,75 @@ pub struct EntriesProps<'a> {
     pub marked: Option<&'a EntryMarkMap>,
     pub border_style: Style,
     pub is_focussed: bool,
+    pub keymap: &'a KeyMap,
+    pub sorting: SortMode,
+    /// The amount of entries the filter doesn't show along with their total size, if entries are filtered
+    pub filtered_out: Option<(usize, u128)>,
+    /// The size percentages are of, or `None` for the total of the entries of the directory
+    pub percentage_total: Option<u128>,
+    /// If set, the entries are of another machine or of Docker, and are not expected to exist at their path
+    pub is_remote: bool,
 }
 
 #[derive(Default)]
 pub struct Entries {
     pub list: List,
+    /// The area the entries were last drawn in, to know which entry the mouse points at
+    pub area: Rect,
+    /// The columns of the header row, along with the sorting clicking them toggles
+    pub header: Vec<(Range<u16>, Action)>,
+    /// The directory whose entries were drawn last
+    root: Option<TreeIndex>,
+    /// The first entry in view of the directories that were shown, to scroll back to it when showing them again
+    offsets: HashMap<TreeIndex, usize>,
 }
 
 impl Entries {
+    /// Return the entry drawn in the given `row` of the terminal, if there is one.
+    pub fn entry_at_row(&self, row: u16) -> Option<usize> {
+        // Below the title and the header row
+        let first_row = self.area.y + 2;
+        (row >= first_row && row + 1 < self.area.y + self.area.height)
+            .then(|| self.list.offset + (row - first_row) as usize)
+    }
+
+    /// Return the action toggling the sorting by the column of the header row drawn in the given `row` and `column`
+    /// of the terminal, if there is one.
+    pub fn sorting_at(&self, column: u16, row: u16) -> Option<Action> {
+        if row != self.area.y + 1 {
+            return None;
+        }
+        self.header
+            .iter()
+            .find(|(columns, _)| columns.contains(&column))
+            .map(|(_, action)| *action)
+    }
+
+    /// Return `root` or an ancestor of it whose path ends in the given `column` of the title, if there is one.
+    pub fn ancestor_at_title_column(
+        &self,
+        column: u16,
+        tree: &Tree,
+        root: TreeIndex,
+    ) -> Option<TreeIndex> {
+        // the title is drawn after the border, with a leading space
+        let offset = column.checked_sub(self.area.x + 2)? as usize;
+        let mut ancestor = None;
+        let mut node_idx = root;
+        loop {
+            match path_of(tree, node_idx).to_string_lossy().chars().count() {
+                0 => break,
+                len if len > offset => ancestor = Some(node_idx),
+                _ => break,
+            }
+            match tree.neighbors_directed(node_idx, petgraph::Incoming).next() {
+                Some(parent) => node_idx = parent,
+                None => break,
+            }
+        }
+        ancestor
+    }
+
     pub fn render<'a>(
         &mut self,
         props: impl Borrow<EntriesProps<'a>>,
@@ -52,7 +140,19 @@ impl Entries {
             marked,
             border_style,
             is_focussed,
+            keymap,
+            sorting,
+            filtered_out,
+            percentage_total,
+            is_remote,
         } = props.borrow();
+        self.area = area;
+        if self.root != Some(*root) {
+            if let Some(previous_root) = self.root.replace(*root) {
+                self.offsets.insert(previous_root, self.list.offset);
+            }
+            self.list.offset = self.offsets.get(root).copied().unwrap_or(0);
+        }
         let list = &mut self.list;
 
         let is_top = |node_idx| {
@@ -61,12 +161,23 @@ impl Entries {
                 .is_none()
         };
 
-        let total: u128 = entries.iter().map(|b| b.data.size).sum();
+        // Entries which are filtered out still take their share
+        let total: u128 = percentage_total.unwrap_or_else(|| {
+            entries
+                .iter()
+                .map(|b| u128::from(b.data.size))
+                .sum::<u128>()
+                + filtered_out.map_or(0, |(_, size)| size)
+        });
+        let now = SystemTime::now();
         let title = match path_of(tree, *root).to_string_lossy().to_string() {
-            ref p if p.is_empty() => Path::new(".")
-                .canonicalize()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|_| String::from(".")),
+            ref p if p.is_empty() => match top_label(tree, *root) {
+                Some(label) => label.to_owned(),
+                None => Path::new(".")
+                    .canonicalize()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| String::from(".")),
+            },
             p => p,
         };
         let title = format!(
@@ -90,8 +201,21 @@ impl Entries {
                 .unwrap_or(0)
         });
 
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+        let header_area = Rect {
+            height: inner_area.height.min(1),
+            ..inner_area
+        };
+        let list_area = Rect {
+            y: inner_area.y + header_area.height,
+            height: inner_area.height - header_area.height,
+            ..inner_area
+        };
+        self.header = render_header(display, *sorting, header_area, buf);
+
         let props = ListProps {
-            block: Some(block),
+            block: None,
             entry_in_view,
         };
         let lines = entries.iter().map(
@@ -113,18 +237,17 @@ impl Entries {
                 if *is_focussed & is_selected {
                     style.add_modifier.insert(Modifier::BOLD);
                 }
+                let is_marked = marked.map(|m| m.contains_key(node_idx)).unwrap_or(false);
 
-                let bytes = Span::styled(
-                    format!(
-                        "{:>byte_column_width$}",
-                        display.byte_format.display(w.size).to_string(), // we would have to impl alignment/padding ourselves otherwise...
-                        byte_column_width = display.byte_format.width()
-                    ),
+                // Colors alone don't show up on every terminal
+                let mark = Span::styled(
+                    if is_marked { "*" } else { " " },
                     Style {
-                        fg: Color::Green.into(),
+                        fg: display.theme.marked.into(),
                         ..style
                     },
                 );
+
                 let fraction = w.size as f32 / total as f32;
                 let should_avoid_showing_a_big_reversed_bar = fraction > 0.9;
                 let local_style = if should_avoid_showing_a_big_reversed_bar {
@@ -133,42 +256,205 @@ impl Entries {
                     style
                 };
 
-                let left_bar = Span::styled(" |", local_style);
-                let percentage = Span::styled(
-                    format!("{}", display.byte_vis.display(fraction)),
-                    local_style,
-                );
-                let right_bar = Span::styled("| ", local_style);
-
+                // Links are followed by their target, to tell why they are empty or what they were counted as
+                let target = if w.is_symlink {
+                    std::fs::read_link(path_of(tree, *node_idx))
+                        .map(|target| format!(" -> {}", target.display()))
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                // Repositories are followed by how much of them is history, and how much is checked out
+                let repo = match dua::git_dir_of(tree, *node_idx) {
+                    Some(git_idx) => {
+                        let git_size = tree[git_idx].size;
+                        format!(
+                            "  [git: .git {}, working tree {}]",
+                            display.byte_format.display(git_size as u128),
+                            display
+                                .byte_format
+                                .display(w.size.saturating_sub(git_size) as u128)
+                        )
+                    }
+                    None => String::new(),
+                };
+                // Archives that were looked into are followed by how much their contents take unpacked
+                let archive = if !w.is_dir && tree.neighbors(*node_idx).next().is_some() {
+                    let unpacked: u128 = tree
+                        .neighbors(*node_idx)
+                        .map(|idx| tree[idx].size as u128)
+                        .sum();
+                    format!(
+                        "  [archive, {} unpacked]",
+                        display.byte_format.display(unpacked)
+                    )
+                } else {
+                    String::new()
+                };
                 let name = Span::styled(
                     fill_background_to_right(
                         format!(
-                            "{prefix}{}",
-                            w.name.to_string_lossy(),
-                            prefix = if *is_dir && !is_top(*root) { "/" } else { " " }
+                            "{prefix}{}{target}{repo}{archive}",
+                            tree.names[w.name].to_string_lossy(),
+                            prefix = if *is_dir && !is_top(*root) { "/" } else { " " },
+                            target = target,
+                            repo = repo,
+                            archive = archive
                         ),
                         area.width,
                     ),
                     {
-                        let is_marked = marked.map(|m| m.contains_key(node_idx)).unwrap_or(false);
-                        let fg = if !exists {
-                            // non-existing - always red!
-                            Some(Color::Red)
+                        let fg = if !exists && !*is_remote {
+                            // non-existing - always in the color of errors!
+                            display.theme.error.into()
+                        } else if w.is_cache_dir && !is_marked {
+                            display.theme.cache_dir.into()
+                        } else if w.is_broken_link && !is_marked {
+                            display.theme.broken_link.into()
+                        } else if w.is_symlink && !is_marked {
+                            display.theme.symlink.into()
                         } else {
-                            entry_color(style.fg, !*is_dir, is_marked)
+                            entry_color(&display.theme, !*is_dir, is_marked)
+                        };
+                        let style = if w.is_broken_link {
+                            // Crossed out to tell them apart without colors
+                            style.add_modifier(Modifier::ITALIC | Modifier::CROSSED_OUT)
+                        } else if w.is_symlink {
+                            style.add_modifier(Modifier::ITALIC)
+                        } else if w.is_in_archive || w.is_collapsed {
+                            // They can't be deleted, unlike everything else
+                            style.add_modifier(Modifier::DIM)
+                        } else {
+                            style
                         };
                         Style { fg, ..style }
                     },
                 );
-                vec![bytes, left_bar, percentage, right_bar, name]
+                let columns = display
+                    .columns
+                    .iter()
+                    .filter(|spec| spec.shown)
+                    .flat_map(|spec| {
+                        let byte_column_width =
+                            spec.width.unwrap_or_else(|| display.byte_format.width());
+                        match spec.column {
+                            Column::Size => vec![Span::styled(
+                                format!(
+                                    "{:>byte_column_width$}",
+                                    display.byte_format.display(w.size.into()).to_string(), // we would have to impl alignment/padding ourselves otherwise...
+                                    byte_column_width = byte_column_width
+                                ),
+                                Style {
+                                    fg: display.theme.size.into(),
+                                    ..style
+                                },
+                            )],
+                            Column::OtherSize => vec![Span::styled(
+                                format!(
+                                    " {:>byte_column_width$}",
+                                    display
+                                        .byte_format
+                                        .display(w.alternate_size.into())
+                                        .to_string(),
+                                    byte_column_width = byte_column_width
+                                ),
+                                style,
+                            )],
+                            Column::Count => vec![Span::styled(
+                                format!(
+                                    "{:>width$} ",
+                                    if *is_dir {
+                                        dua::format_number(w.entry_count)
+                                    } else {
+                                        String::new()
+                                    },
+                                    width = spec.width.unwrap_or(ENTRY_COUNT_WIDTH)
+                                ),
+                                style,
+                            )],
+                            Column::MTime => vec![Span::styled(
+                                format!(
+                                    "{:>width$} ",
+                                    w.mtime
+                                        .map(|mtime| format_age(
+                                            now.duration_since(mtime.into()).unwrap_or_default()
+                                        ))
+                                        .unwrap_or_default(),
+                                    width = spec.width.unwrap_or(MTIME_WIDTH)
+                                ),
+                                style,
+                            )],
+                            Column::Bar => vec![
+                                Span::styled(" |", local_style),
+                                Span::styled(
+                                    format!(
+                                        "{}",
+                                        display.byte_vis.display(fraction, display.bar_width)
+                                    ),
+                                    local_style,
+                                ),
+                                Span::styled("| ", local_style),
+                            ],
+                        }
+                    });
+                std::iter::once(mark)
+                    .chain(columns)
+                    .chain(std::iter::once(name))
+                    .collect::<Vec<_>>()
             },
         );
 
-        list.render(props, lines, area, buf);
+        list.render(props, lines, list_area, buf);
+
+        // Where the entries in view are among all of them, on the right border
+        let rows = list_area.height;
+        if let Some((start, size)) = scrollbar_thumb(rows as usize, list.offset, entries.len()) {
+            for row in start..start + size {
+                buf.get_mut(area.right() - 1, list_area.y + row as u16)
+                    .set_symbol("┃")
+                    .set_style(*border_style);
+            }
+        }
+        if area.height > 1 {
+            let mut bound = line_bound(
+                Rect {
+                    x: area.x + 1,
+                    width: area.width.saturating_sub(2),
+                    ..area
+                },
+                area.height as usize - 1,
+            );
+            let position = entry_in_view
+                .filter(|_| !entries.is_empty())
+                .map(|position| format!(" {}/{} ", position + 1, entries.len()));
+            let filtered_out = filtered_out.map(|(count, size)| {
+                format!(" {} hidden, {} ", count, display.byte_format.display(size))
+            });
+            for text in position.iter().chain(filtered_out.iter()) {
+                let width = block_width(text);
+                if width > bound.width {
+                    break;
+                }
+                draw_text_nowrap_fn(bound, buf, text, |_, _, _| *border_style);
+                bound = Rect {
+                    x: bound.x + width + 1,
+                    width: bound.width - width.saturating_add(1).min(bound.width),
+                    ..bound
+                };
+            }
+        }
 
         if *is_focussed {
-            let help_text = " . = o|.. = u ── ⇊ = CTRL+d|↓ = j|⇈ = CTRL+u|↑ = k ";
-            let help_text_block_width = block_width(help_text);
+            let help_text = format!(
+                " . = {}|.. = {} ── ⇊ = {}|↓ = {}|⇈ = {}|↑ = {} ",
+                keymap.hint(Action::EnterDirectory),
+                keymap.hint(Action::ExitDirectory),
+                keymap.hint(Action::PageDown),
+                keymap.hint(Action::Down),
+                keymap.hint(Action::PageUp),
+                keymap.hint(Action::Up)
+            );
+            let help_text_block_width = block_width(&help_text);
             let bound = Rect {
                 width: area.width.saturating_sub(1),
                 ..area
@@ -177,21 +463,163 @@ impl Entries {
                 draw_text_nowrap_fn(
                     rect::snap_to_right(bound, help_text_block_width),
                     buf,
-                    help_text,
+                    &help_text,
                     |_, _, _| Style::default(),
                 );
             }
             let bound = line_bound(bound, bound.height.saturating_sub(1) as usize);
-            let help_text = " mark-move = d | mark-toggle = space ";
-            let help_text_block_width = block_width(help_text);
+            let help_text = format!(
+                " mark-move = {} | mark-toggle = {} | delete = {} ",
+                keymap.hint(Action::MarkAndAdvance),
+                keymap.hint(Action::ToggleMark),
+                keymap.hint(Action::Delete)
+            );
+            let help_text_block_width = block_width(&help_text);
             if help_text_block_width <= bound.width {
                 draw_text_nowrap_fn(
                     rect::snap_to_right(bound, help_text_block_width),
                     buf,
-                    help_text,
+                    &help_text,
                     |_, _, _| Style::default(),
                 );
             }
         }
     }
 }
+
+/// Draw a label above each shown column, marking the one entries are sorted by with the direction, and return
+/// the columns of the labels clicking which toggles a sorting.
+fn render_header(
+    display: &DisplayOptions,
+    sorting: SortMode,
+    area: Rect,
+    buf: &mut Buffer,
+) -> Vec<(Range<u16>, Action)> {
+    let label = |name: &str, action: Option<Action>| {
+        let indicator = action.and_then(|action| sort_indicator(sorting, action));
+        format!("{}{}", name, indicator.unwrap_or_default())
+    };
+    // The mark of entries comes first
+    let mut columns = vec![(" ".to_owned(), None)];
+    for spec in display.columns.iter().filter(|spec| spec.shown) {
+        let byte_column_width = spec.width.unwrap_or_else(|| display.byte_format.width());
+        columns.push(match spec.column {
+            Column::Size => {
+                let action = Some(Action::SortBySize);
+                let text = format!("{:>w$}", label("Size", action), w = byte_column_width);
+                (text, action)
+            }
+            Column::OtherSize => (format!(" {:>w$}", "Other", w = byte_column_width), None),
+            Column::Count => {
+                let action = Some(Action::SortByCount);
+                let width = spec.width.unwrap_or(ENTRY_COUNT_WIDTH);
+                (
+                    format!("{:>w$} ", label("Entries", action), w = width),
+                    action,
+                )
+            }
+            Column::MTime => {
+                let action = Some(Action::SortByMTime);
+                let width = spec.width.unwrap_or(MTIME_WIDTH);
+                (format!("{:>w$} ", label("Age", action), w = width), action)
+            }
+            Column::Bar => {
+                let width =
+                    block_width(&display.byte_vis.display(0.0, display.bar_width).to_string())
+                        as usize;
+                let text = match display.percentage_base {
+                    PercentageBase::Directory => "Share",
+                    PercentageBase::Scan => "Share of scan",
+                    PercentageBase::Filesystem => "Share of filesystem",
+                };
+                (format!("  {:<w$.w$}  ", text, w = width), None)
+            }
+        });
+    }
+    let action = Some(Action::SortByName);
+    columns.push((format!(" {}", label("Name", action)), action));
+
+    let style = Style::default().add_modifier(Modifier::BOLD);
+    let mut x = area.x;
+    let mut clickable = Vec::new();
+    for (text, action) in columns {
+        let width = block_width(&text);
+        let column_area = Rect {
+            x,
+            width: width.min(area.right().saturating_sub(x)),
+            ..area
+        };
+        if column_area.width == 0 || area.height == 0 {
+            break;
+        }
+        draw_text_nowrap_fn(column_area, buf, &text, |_, _, _| style);
+        if let Some(action) = action {
+            clickable.push((x..x + column_area.width, action));
+        }
+        x += column_area.width;
+    }
+    clickable
+}
+
+/// The arrow to put behind the label of the column `action` sorts by, if entries are sorted by it.
+fn sort_indicator(sorting: SortMode, action: Action) -> Option<&'static str> {
+    use SortMode::*;
+    let descending = match (action, sorting) {
+        (Action::SortBySize, SizeDescending)
+        | (Action::SortByCount, CountDescending)
+        | (Action::SortByName, NameDescending)
+        | (Action::SortByMTime, MTimeAscending) => true,
+        (Action::SortBySize, SizeAscending)
+        | (Action::SortByCount, CountAscending)
+        | (Action::SortByName, NameAscending)
+        | (Action::SortByMTime, MTimeDescending) => false,
+        _ => return None,
+    };
+    Some(if descending { "▼" } else { "▲" })
+}
+
+/// Return the first row and the amount of rows of the thumb of a scrollbar `rows` high, for a list of `len` entries
+/// showing those from `offset` on, or `None` if they all fit.
+fn scrollbar_thumb(rows: usize, offset: usize, len: usize) -> Option<(usize, usize)> {
+    if len <= rows || rows == 0 {
+        return None;
+    }
+    let size = (rows * rows / len).max(1);
+    let start = offset.min(len - rows) * (rows - size) / (len - rows);
+    Some((start, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_scrollbar_thumb_is_as_high_as_the_share_of_entries_in_view() {
+        assert_eq!(scrollbar_thumb(10, 0, 10), None, "all entries fit");
+        assert_eq!(scrollbar_thumb(0, 0, 10), None);
+        assert_eq!(scrollbar_thumb(10, 0, 20), Some((0, 5)));
+        assert_eq!(scrollbar_thumb(10, 5, 20), Some((2, 5)));
+        assert_eq!(scrollbar_thumb(10, 10, 20), Some((5, 5)), "at the bottom");
+        assert_eq!(
+            scrollbar_thumb(10, 995, 1000),
+            Some((9, 1)),
+            "at least a row"
+        );
+    }
+
+    #[test]
+    fn ages_are_formatted_in_their_largest_unit() {
+        let day = 24 * 60 * 60;
+        for (secs, expected) in [
+            (0, "0s"),
+            (59, "59s"),
+            (60, "1m"),
+            (2 * 60 * 60 + 59, "2h"),
+            (3 * day, "3d"),
+            (364 * day, "364d"),
+            (2 * 365 * day + 100, "2y"),
+        ] {
+            assert_eq!(format_age(Duration::from_secs(secs)), expected);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::interactive::{widgets::Theme, CursorDirection};
+use crosstermion::{input::Key, input::Key::*};
+use dua::traverse::TraversalError;
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Clear, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// An overlay listing the IO errors of the traversal, to see which entries are missing from the sizes.
+#[derive(Default)]
+pub struct ErrorPane {
+    selected: usize,
+    list: List,
+}
+
+pub struct ErrorPaneProps<'a> {
+    pub errors: &'a [TraversalError],
+    /// The amount of errors, which includes those whose path isn't known
+    pub io_errors: u64,
+    pub border_style: Style,
+    pub theme: Theme,
+}
+
+impl ErrorPane {
+    /// Handle `key`, returning `None` if the pane was closed.
+    pub fn process_events(mut self, key: Key, num_errors: usize) -> Option<Self> {
+        let direction = match key {
+            Esc | Char('q') | Char('e') => return None,
+            Char('H') | Char('g') | Home => CursorDirection::ToTop,
+            Char('G') | End => CursorDirection::ToBottom,
+            Ctrl('u') | PageUp => CursorDirection::PageUp,
+            Char('k') | Up => CursorDirection::Up,
+            Char('j') | Down => CursorDirection::Down,
+            Ctrl('d') | PageDown => CursorDirection::PageDown,
+            _ => return Some(self),
+        };
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(num_errors.saturating_sub(1));
+        Some(self)
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<ErrorPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let ErrorPaneProps {
+            errors,
+            io_errors,
+            border_style,
+            theme,
+        } = props.borrow();
+        let title = match errors.len() as u64 {
+            n if n == *io_errors => format!(" IO errors ({}) ", n),
+            n => format!(" IO errors ({} of {} with known paths) ", n, io_errors),
+        };
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner_area = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let kind_width = errors
+            .iter()
+            .map(|err| format!("{:?}", err.kind).len())
+            .max()
+            .unwrap_or(0);
+        let selected = self.selected;
+        let lines = errors.iter().enumerate().map(|(idx, err)| {
+            let style = if idx == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            vec![
+                Span::styled(
+                    format!(
+                        " {:<width$} ",
+                        format!("{:?}", err.kind),
+                        width = kind_width
+                    ),
+                    style.fg(theme.error).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{:<width$}",
+                        format!("{}: {}", err.path.display(), err.message),
+                        width = (inner_area.width as usize).saturating_sub(kind_width + 2)
+                    ),
+                    style,
+                ),
+            ]
+        });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, lines, inner_area, buf);
+    }
+}
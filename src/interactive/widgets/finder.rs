@@ -0,0 +1,338 @@
+use crate::interactive::{
+    fit_string_graphemes_with_ellipsis,
+    widgets::{entry_color, Theme},
+    CursorDirection,
+};
+use crosstermion::{input::Key, input::Key::*};
+use dua::traverse::{Traversal, TreeIndex};
+use petgraph::Direction;
+use std::{borrow::Borrow, path::Path};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+use tui_react::{List, ListProps};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// An overlay to find any entry of the traversal by typing parts of its path, in the order they appear in.
+pub struct PathFinder {
+    query: String,
+    candidates: Vec<Candidate>,
+    /// Indices into `candidates` of those matching the query, best match first
+    matches: Vec<usize>,
+    selected: usize,
+    list: List,
+}
+
+struct Candidate {
+    index: TreeIndex,
+    path: String,
+    is_dir: bool,
+}
+
+pub struct PathFinderProps {
+    pub border_style: Style,
+    pub theme: Theme,
+}
+
+impl PathFinder {
+    pub fn new(traversal: &Traversal) -> Self {
+        let tree = &traversal.tree;
+        let mut candidates = Vec::with_capacity(tree.node_count());
+        let mut to_visit = vec![(traversal.root_index, None::<String>)];
+        while let Some((parent, parent_path)) = to_visit.pop() {
+            for index in tree.neighbors_directed(parent, Direction::Outgoing) {
+                let entry = &tree[index];
+                let name = tree.names[entry.name].to_string_lossy();
+                let path = match &parent_path {
+                    Some(parent) => Path::new(parent)
+                        .join(&*name)
+                        .to_string_lossy()
+                        .into_owned(),
+                    None => name.into_owned(),
+                };
+                if entry.is_dir {
+                    to_visit.push((index, Some(path.clone())));
+                }
+                candidates.push(Candidate {
+                    index,
+                    path,
+                    is_dir: entry.is_dir,
+                });
+            }
+        }
+        candidates.sort_by(|l, r| l.path.cmp(&r.path));
+        let mut finder = PathFinder {
+            query: String::new(),
+            candidates,
+            matches: Vec::new(),
+            selected: 0,
+            list: Default::default(),
+        };
+        finder.update_matches();
+        finder
+    }
+
+    #[cfg(test)]
+    pub fn matches(&self) -> impl Iterator<Item = &str> {
+        self.matches
+            .iter()
+            .map(move |&m| self.candidates[m].path.as_str())
+    }
+
+    /// Handle `key`, returning `None` if the finder was closed, along with the entry to jump to if one was chosen.
+    pub fn process_events(mut self, key: Key) -> Option<(Self, Option<TreeIndex>)> {
+        match key {
+            Esc => return None,
+            Char('\n') => {
+                let chosen = self.matches.get(self.selected)?;
+                let index = self.candidates[*chosen].index;
+                return Some((self, Some(index)));
+            }
+            Up | Ctrl('p') | Ctrl('k') => self.change_selection(CursorDirection::Up),
+            Down | Ctrl('n') | Ctrl('j') => self.change_selection(CursorDirection::Down),
+            PageUp | Ctrl('u') => self.change_selection(CursorDirection::PageUp),
+            PageDown | Ctrl('d') => self.change_selection(CursorDirection::PageDown),
+            Backspace => {
+                self.query.pop();
+                self.update_matches();
+            }
+            Char(c) if !c.is_control() => {
+                self.query.push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+        Some((self, None))
+    }
+
+    fn change_selection(&mut self, direction: CursorDirection) {
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(self.matches.len().saturating_sub(1));
+    }
+
+    fn update_matches(&mut self) {
+        let query: Vec<char> = self.query.chars().collect();
+        let case_sensitive = query.iter().any(|c| c.is_uppercase());
+        let mut scored: Vec<_> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, c)| {
+                fuzzy_match(&query, &c.path, case_sensitive).map(|(score, _)| (score, idx))
+            })
+            .collect();
+        // best score first, then the shortest path, then in order of paths
+        scored.sort_by(|(ls, li), (rs, ri)| {
+            rs.cmp(ls).then_with(|| {
+                let (l, r) = (&self.candidates[*li].path, &self.candidates[*ri].path);
+                l.len().cmp(&r.len()).then(li.cmp(ri))
+            })
+        });
+        self.matches = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.selected = 0;
+        self.list.offset = 0;
+    }
+
+    pub fn render(&mut self, props: impl Borrow<PathFinderProps>, area: Rect, buf: &mut Buffer) {
+        let PathFinderProps {
+            border_style,
+            theme,
+        } = props.borrow();
+        let title = format!(
+            " Find path ({} of {}) ",
+            self.matches.len(),
+            self.candidates.len()
+        );
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner_area = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+        if inner_area.height == 0 {
+            return;
+        }
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        Paragraph::new(Text::from(Spans::from(vec![
+            Span::styled("> ", bold.fg(theme.accent)),
+            Span::styled(self.query.as_str(), bold),
+            Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+        ])))
+        .render(
+            Rect {
+                height: 1,
+                ..inner_area
+            },
+            buf,
+        );
+
+        let list_area = Rect {
+            y: inner_area.y + 1,
+            height: inner_area.height - 1,
+            ..inner_area
+        };
+        let query: Vec<char> = self.query.chars().collect();
+        let case_sensitive = query.iter().any(|c| c.is_uppercase());
+        let (candidates, selected) = (&self.candidates, self.selected);
+        let width = list_area.width as usize;
+        let lines = self.matches.iter().enumerate().map(|(idx, &m)| {
+            let candidate = &candidates[m];
+            let base_style = if idx == selected {
+                Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let style = Style {
+                fg: entry_color(theme, !candidate.is_dir, false),
+                ..base_style
+            };
+            let path_len = candidate.path.graphemes(true).count();
+            if path_len + 1 > width {
+                let (path, _) = fit_string_graphemes_with_ellipsis(
+                    candidate.path.as_str(),
+                    path_len,
+                    width.saturating_sub(1),
+                );
+                return vec![Span::styled(
+                    format!(" {:<width$}", path, width = width - 1),
+                    style,
+                )];
+            }
+            let positions = fuzzy_match(&query, &candidate.path, case_sensitive)
+                .map(|(_, positions)| positions)
+                .unwrap_or_default();
+            let mut spans = vec![Span::styled(" ", style)];
+            for (char_idx, c) in candidate.path.chars().enumerate() {
+                let style = if positions.contains(&char_idx) {
+                    style.fg(theme.accent).add_modifier(Modifier::BOLD)
+                } else {
+                    style
+                };
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            spans.push(Span::styled(
+                format!("{:width$}", "", width = width - path_len - 1),
+                style,
+            ));
+            spans
+        });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, lines, list_area, buf);
+    }
+}
+
+/// Match the characters of `query` in order against `candidate` and return a score, higher is better, along with the
+/// positions of the matched characters within `candidate`, or `None` if not all characters are contained.
+///
+/// The shortest match ending at the first possible position is scored, with bonuses for consecutive characters and
+/// those at the start of path components or words.
+fn fuzzy_match(query: &[char], candidate: &str, case_sensitive: bool) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = candidate
+        .chars()
+        .map(|c| {
+            if case_sensitive {
+                c
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect();
+    let query: Vec<char> = query
+        .iter()
+        .map(|c| {
+            if case_sensitive {
+                *c
+            } else {
+                c.to_lowercase().next().unwrap_or(*c)
+            }
+        })
+        .collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    // find where the first match ends, then walk back to find the latest start of that match
+    let mut end = None;
+    let mut qi = 0;
+    for (ci, c) in chars.iter().enumerate() {
+        if *c == query[qi] {
+            qi += 1;
+            if qi == query.len() {
+                end = Some(ci);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut positions = Vec::with_capacity(query.len());
+    let mut qi = query.len();
+    for ci in (0..=end).rev() {
+        if chars[ci] == query[qi - 1] {
+            positions.push(ci);
+            qi -= 1;
+            if qi == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+
+    let mut score = 0i64;
+    for (i, &pos) in positions.iter().enumerate() {
+        score += 16;
+        if i > 0 && positions[i - 1] + 1 == pos {
+            score += 12;
+        }
+        match pos.checked_sub(1).map(|p| chars[p]) {
+            None | Some('/') | Some('\\') => score += 10,
+            Some('_' | '-' | '.' | ' ') => score += 6,
+            _ => {}
+        }
+    }
+    let span = (positions[positions.len() - 1] - positions[0] + 1) as i64;
+    score -= span - query.len() as i64;
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> Option<i64> {
+        let query: Vec<char> = query.chars().collect();
+        let case_sensitive = query.iter().any(|c| c.is_uppercase());
+        fuzzy_match(&query, candidate, case_sensitive).map(|(score, _)| score)
+    }
+
+    #[test]
+    fn fuzzy_match_requires_all_characters_in_order() {
+        assert!(score("abc", "a/b/c").is_some());
+        assert!(score("cba", "a/b/c").is_none());
+        assert!(
+            score("ABC", "a/b/c").is_none(),
+            "uppercase queries are case-sensitive"
+        );
+        assert!(score("abc", "A/B/C").is_some());
+        assert_eq!(
+            fuzzy_match(&['d', 'i', 'r'], "sub/dir", false).map(|(_, p)| p),
+            Some(vec![4, 5, 6])
+        );
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_consecutive_characters_and_starts_of_components() {
+        assert!(score("sub", "sub/file") > score("sub", "s/u/b"));
+        assert!(score("fb", "foo/bar") > score("fb", "fxxb"));
+        assert!(score("dir", "a/dir") > score("dir", "adir"));
+    }
+}
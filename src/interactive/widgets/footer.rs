@@ -1,5 +1,9 @@
-use crate::ByteFormat;
-use std::borrow::Borrow;
+use crate::{
+    interactive::{widgets::Theme, MarkPattern, Message, Search, Severity},
+    ByteFormat,
+};
+use dua::format_number;
+use std::{borrow::Borrow, time::Duration};
 use tui::{
     buffer::Buffer,
     layout::Rect,
@@ -13,41 +17,160 @@ pub struct Footer;
 
 pub struct FooterProps {
     pub total_bytes: Option<u128>,
+    pub apparent_size: bool,
+    /// If set, scanning was stopped before all entries were seen
+    pub is_incomplete: bool,
     pub entries_traversed: u64,
+    pub io_errors: u64,
+    /// If set, the traversal is still running and started that long ago
+    pub scanning_for: Option<Duration>,
+    /// The bytes available and in total on the filesystem of the directory shown, if they are to be shown
+    pub free_space: Option<(u128, u128)>,
+    /// How long the scan took, if it is to be shown
+    pub scanned_in: Option<Duration>,
+    /// The amount of marked entries along with their total size, if there are any
+    pub marked: Option<(usize, u128)>,
     pub format: ByteFormat,
-    pub message: Option<String>,
+    pub message: Option<Message>,
+    /// The search filtering the current entries, along with the amount of matches
+    pub search: Option<(Search, usize)>,
+    /// The pattern of entries to mark being typed
+    pub mark_pattern: Option<MarkPattern>,
+    pub theme: Theme,
 }
 
 impl Footer {
     pub fn render(&self, props: impl Borrow<FooterProps>, area: Rect, buf: &mut Buffer) {
         let FooterProps {
             total_bytes,
+            apparent_size,
+            is_incomplete,
             entries_traversed,
+            io_errors,
+            scanning_for,
+            free_space,
+            scanned_in,
+            marked,
             format,
             message,
+            search,
+            mark_pattern,
+            theme,
         } = props.borrow();
 
+        if let Some(pattern) = mark_pattern {
+            let spans = vec![
+                Span::styled(
+                    format!(" Mark {}", pattern.query),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+                Span::from(format!(
+                    "   {}   (enter = mark | tab = {} | esc = cancel)",
+                    if pattern.recursive {
+                        "in all directories below"
+                    } else {
+                        "in this directory"
+                    },
+                    if pattern.recursive {
+                        "this directory only"
+                    } else {
+                        "all directories below"
+                    },
+                )),
+            ];
+            Paragraph::new(Text::from(Spans::from(spans)))
+                .style(Style::default().add_modifier(Modifier::REVERSED))
+                .render(area, buf);
+            return;
+        }
+
+        if let Some((search, matches)) = search.as_ref().filter(|(s, _)| s.is_editing) {
+            let spans = vec![
+                Span::styled(
+                    format!(" /{}", search.query),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+                Span::from(format!(
+                    "   {} match{}   (enter = select first | esc = clear)",
+                    matches,
+                    if *matches == 1 { "" } else { "es" }
+                )),
+            ];
+            Paragraph::new(Text::from(Spans::from(spans)))
+                .style(Style::default().add_modifier(Modifier::REVERSED))
+                .render(area, buf);
+            return;
+        }
+
         let spans = vec![
+            scanning_for.map(|elapsed| {
+                Span::styled(
+                    scan_progress(elapsed, *entries_traversed),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            }),
+            search.as_ref().map(|(search, matches)| {
+                Span::styled(
+                    format!(" Filter: {} ({})  |", search.query, matches),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            }),
             Span::from(format!(
-                " Total disk usage: {}  Entries: {}   ",
+                " Total {}: {}{}  Entries: {}   ",
+                match (format, *apparent_size) {
+                    (ByteFormat::Inodes, _) => "inodes",
+                    (_, true) => "apparent size",
+                    (_, false) => "disk usage",
+                },
                 match total_bytes {
                     Some(b) => format!("{}", format.display(*b)),
                     None => "-".to_owned(),
                 },
-                entries_traversed,
+                if *is_incomplete { " (incomplete)" } else { "" },
+                format_number(entries_traversed),
             ))
             .into(),
-            message.as_ref().map(|m| {
+            marked.map(|(count, size)| {
                 Span::styled(
-                    m,
-                    Style {
-                        fg: Color::Red.into(),
-                        bg: Color::Reset.into(),
-                        add_modifier: Modifier::BOLD | Modifier::RAPID_BLINK,
-                        ..Style::default()
-                    },
+                    format!(
+                        "{} marked, {}   ",
+                        format_number(count),
+                        format.display(size)
+                    ),
+                    Style::default()
+                        .fg(theme.marked)
+                        .add_modifier(Modifier::BOLD),
+                )
+            }),
+            free_space.map(|(available, total)| {
+                Span::from(format!(
+                    "Free: {} of {}   ",
+                    format.display(available),
+                    format.display(total)
+                ))
+            }),
+            scanned_in.map(|duration| {
+                Span::from(format!(
+                    "Scanned in {:.1}s ({} entries/s)   ",
+                    duration.as_secs_f64(),
+                    throughput(duration, *entries_traversed)
+                ))
+            }),
+            (*io_errors > 0).then(|| {
+                Span::styled(
+                    format!(
+                        "{} IO error{} (e = show)  ",
+                        io_errors,
+                        if *io_errors == 1 { "" } else { "s" }
+                    ),
+                    Style::default().fg(theme.error),
                 )
             }),
+            message
+                .as_ref()
+                .map(|m| Span::styled(m.text.as_str(), message_style(m.severity, theme))),
         ];
         Paragraph::new(Text::from(Spans::from(
             spans.into_iter().flatten().collect::<Vec<_>>(),
@@ -56,3 +179,63 @@ impl Footer {
         .render(area, buf);
     }
 }
+
+/// Errors blink to be noticed, warnings stand out in their color and everything else is just bold.
+fn message_style(severity: Severity, theme: &Theme) -> Style {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    match severity {
+        Severity::Info => bold,
+        Severity::Warning => Style {
+            fg: theme.warning.into(),
+            bg: Color::Reset.into(),
+            ..bold
+        },
+        Severity::Error => Style {
+            fg: theme.error.into(),
+            bg: Color::Reset.into(),
+            add_modifier: Modifier::BOLD | Modifier::RAPID_BLINK,
+            ..Style::default()
+        },
+    }
+}
+
+/// A spinner turning with the time `elapsed` since the scan started, along with the amount of entries per second.
+fn scan_progress(elapsed: Duration, entries_traversed: u64) -> String {
+    const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let frame = SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()];
+    format!(
+        " {} Scanning… {} entries/s  |",
+        frame,
+        throughput(elapsed, entries_traversed)
+    )
+}
+
+/// The amount of entries seen per second within `elapsed`, in thousands once there are many.
+fn throughput(elapsed: Duration, entries_traversed: u64) -> String {
+    let entries_per_second = entries_traversed as f64 / elapsed.as_secs_f64().max(0.001);
+    if entries_per_second >= 10_000.0 {
+        format!(
+            "{}k",
+            format_number(format!("{:.0}", entries_per_second / 1000.0))
+        )
+    } else {
+        format_number(format!("{:.0}", entries_per_second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_progress_turns_the_spinner_and_shows_throughput() {
+        assert_eq!(
+            scan_progress(Duration::from_millis(50), 100),
+            " ⠋ Scanning… 2000 entries/s  |"
+        );
+        assert_eq!(
+            scan_progress(Duration::from_millis(2150), 43000),
+            " ⠙ Scanning… 20k entries/s  |"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::interactive::SortMode;
 use tui::{
     buffer::Buffer,
     layout::Rect,
@@ -5,17 +6,33 @@ use tui::{
     text::{Span, Spans, Text},
     widgets::{Paragraph, Widget},
 };
+use tui_react::{
+    draw_text_nowrap_fn,
+    util::{block_width, rect},
+};
 
 pub struct Header;
 
 impl Header {
-    pub fn render(&self, bg_color: Color, area: Rect, buf: &mut Buffer) {
-        let standard = Style {
-            fg: Color::Black.into(),
-            bg: bg_color.into(),
-            ..Default::default()
+    pub fn render(
+        &self,
+        bg_color: Color,
+        fg_color: Color,
+        sorting: SortMode,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        // Without colors, the header is reversed to stand out
+        let standard = if bg_color == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style {
+                fg: fg_color.into(),
+                bg: bg_color.into(),
+                ..Default::default()
+            }
         };
-        debug_assert_ne!(standard.bg, standard.fg);
+        debug_assert!(standard.bg.is_none() || standard.bg != standard.fg);
         let modified = |text: &'static str, modifier| {
             Span::styled(
                 text,
@@ -43,10 +60,18 @@ impl Header {
             italic(" for help)"),
         ];
         Paragraph::new(Text::from(Spans::from(spans)))
-            .style(Style {
-                bg: bg_color.into(),
-                ..Default::default()
-            })
+            .style(standard)
             .render(area, buf);
+
+        let sorting = format!("sorted {} ", sorting.description());
+        let sorting_width = block_width(&sorting);
+        if sorting_width + 50 <= area.width {
+            draw_text_nowrap_fn(
+                rect::snap_to_right(area, sorting_width),
+                buf,
+                sorting,
+                |_, _, _| standard,
+            );
+        }
     }
 }
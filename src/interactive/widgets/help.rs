@@ -1,12 +1,16 @@
-use crate::interactive::CursorDirection;
+use crate::interactive::{
+    key_label, Action, ByteVisualization, Column, CursorDirection, DisplayOptions, EntryFilter,
+    KeyMap, SortMode, ACTION_GROUPS, TRASH_SUPPORTED,
+};
 use crosstermion::{input::Key, input::Key::*};
+use dua::ByteFormat;
 use std::{borrow::Borrow, cell::RefCell};
 use tui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, Paragraph, Widget},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
 };
 use tui_react::{
     draw_text_nowrap_fn,
@@ -18,9 +22,35 @@ pub struct HelpPane {
     pub scroll: u16,
 }
 
-pub struct HelpPaneProps {
+pub struct HelpPaneProps<'a> {
     pub border_style: Style,
     pub has_focus: bool,
+    pub display: DisplayOptions,
+    pub sorting: SortMode,
+    pub filter: EntryFilter,
+    pub apparent_size: bool,
+    pub keymap: &'a KeyMap,
+}
+
+/// The width of the column with the keys, each line of which holds as many keys as fit
+const KEYS_COLUMN_WIDTH: usize = 11;
+
+/// Join the labels of `keys` with '/' into lines fitting the column of keys, or return `-` if there are none.
+fn key_lines(keys: &[Key]) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    for label in keys.iter().map(|key| key_label(*key)) {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + label.chars().count() <= KEYS_COLUMN_WIDTH => {
+                line.push('/');
+                line.push_str(&label);
+            }
+            _ => lines.push(label),
+        }
+    }
+    if lines.is_empty() {
+        lines.push("-".into());
+    }
+    lines
 }
 
 fn margin(r: Rect, margin: u16) -> Rect {
@@ -35,8 +65,8 @@ fn margin(r: Rect, margin: u16) -> Rect {
 impl HelpPane {
     pub fn process_events(&mut self, key: Key) {
         match key {
-            Char('H') => self.scroll_help(CursorDirection::ToTop),
-            Char('G') => self.scroll_help(CursorDirection::ToBottom),
+            Char('H') | Char('g') | Home => self.scroll_help(CursorDirection::ToTop),
+            Char('G') | End => self.scroll_help(CursorDirection::ToBottom),
             Ctrl('u') | PageUp => self.scroll_help(CursorDirection::PageUp),
             Char('k') | Up => self.scroll_help(CursorDirection::Up),
             Char('j') | Down => self.scroll_help(CursorDirection::Down),
@@ -48,7 +78,22 @@ impl HelpPane {
         self.scroll = direction.move_cursor(self.scroll as usize) as u16;
     }
 
-    pub fn render(&mut self, props: impl Borrow<HelpPaneProps>, area: Rect, buf: &mut Buffer) {
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<HelpPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let HelpPaneProps {
+            border_style,
+            has_focus,
+            display,
+            sorting,
+            filter,
+            apparent_size,
+            keymap,
+        } = props.borrow();
+
         let lines = {
             let lines = RefCell::new(Vec::<Spans>::with_capacity(30));
             let add_newlines = |n| {
@@ -68,9 +113,9 @@ impl HelpPane {
                 )));
                 add_newlines(1);
             };
-            let hotkey = |keys, description, other_line: Option<&str>| {
+            let hotkey = |keys: String, description: &str, other_line: Option<&str>| {
                 let separator_size = 3;
-                let column_size = 11 + separator_size;
+                let column_size = KEYS_COLUMN_WIDTH + separator_size;
                 lines.borrow_mut().push(Spans::from(vec![
                     Span::styled(
                         format!(
@@ -79,7 +124,7 @@ impl HelpPane {
                             column_size = column_size - separator_size
                         ),
                         Style {
-                            fg: Color::Green.into(),
+                            fg: display.theme.accent.into(),
                             ..Default::default()
                         },
                     ),
@@ -95,104 +140,181 @@ impl HelpPane {
                 }
             };
 
-            title("Keys for pane control");
+            let option = |name: &str, value: &str| {
+                lines.borrow_mut().push(Spans::from(vec![
+                    Span::styled(
+                        format!("{:>11}", name),
+                        Style {
+                            fg: display.theme.directory.into(),
+                            ..Default::default()
+                        },
+                    ),
+                    Span::from(format!(" :  {}", value)),
+                ]));
+            };
+
+            title("Current display options");
             {
-                hotkey(
-                    "q/<ESC>",
-                    "Close the current pane. Closes the program if no",
-                    Some("pane is open"),
+                option("sorting", sorting.description());
+                option(
+                    "size",
+                    match (display.byte_format, *apparent_size) {
+                        (ByteFormat::Inodes, _) => "inodes",
+                        (_, true) => "apparent size",
+                        (_, false) => "disk usage",
+                    },
                 );
-                hotkey("<tab>", "Cycle between all open panes", None);
-                hotkey("?", "Show or hide the help pane", None);
-                spacer();
-            }
-            title("Keys for Navigation");
-            {
-                hotkey("j/<down>", "move down an entry", None);
-                hotkey("k/<up>", "move up an entry", None);
-                hotkey("o/l/<enter>", "descent into the selected directory", None);
-                hotkey("<right>", "^", None);
-                hotkey(
-                    "u/h/<left>",
-                    "ascent one level into the parent directory",
-                    None,
+                option(
+                    "size unit",
+                    match display.byte_format {
+                        ByteFormat::Metric => "metric (1000 bytes per kB)",
+                        ByteFormat::Binary => "binary (1024 bytes per KiB)",
+                        ByteFormat::Bytes => "bytes",
+                        ByteFormat::Raw => "exact bytes without unit",
+                        ByteFormat::GB => "GB",
+                        ByteFormat::GiB => "GiB",
+                        ByteFormat::MB => "MB",
+                        ByteFormat::MiB => "MiB",
+                        ByteFormat::KB => "KB",
+                        ByteFormat::KiB => "KiB",
+                        ByteFormat::TB => "TB",
+                        ByteFormat::TiB => "TiB",
+                        ByteFormat::Inodes => "inodes",
+                    },
                 );
-                hotkey("<backspace>", "^", None);
-                hotkey("Ctrl + d", "move down 10 entries at once", None);
-                hotkey("<Page Down>", "^", None);
-                hotkey("Ctrl + u", "move up 10 entries at once", None);
-                hotkey("<Page Up>", "^", None);
-                hotkey("H", "Move to the top of the entries list", None);
-                hotkey("G", "Move to the bottom of the entries list", None);
-                spacer();
-            }
-            title("Keys for display");
-            {
-                hotkey("s", "toggle sort by size ascending/descending", None);
-                hotkey(
-                    "g",
-                    "cycle through percentage display and bar options",
-                    None,
+                option(
+                    "share",
+                    match display.byte_vis {
+                        ByteVisualization::Percentage => "percentage",
+                        ByteVisualization::Bar => "bar",
+                        ByteVisualization::LongBar => "long bar",
+                        ByteVisualization::PercentageAndBar => "percentage and bar",
+                    },
                 );
-                spacer();
-            }
-            title("Keys for entry operations");
-            {
-                hotkey(
-                    "Shift + o",
-                    "Open the entry with the associated program",
-                    None,
+                option("bar width", &format!("{} characters", display.bar_width));
+                option("percentages of", display.percentage_base.description());
+                option(
+                    "entry count",
+                    if display.columns.is_shown(Column::Count) {
+                        "shown"
+                    } else {
+                        "hidden"
+                    },
                 );
-                hotkey(
-                    "d",
-                    "Toggle the currently selected entry and move down",
-                    None,
+                option(
+                    "mtime",
+                    if display.columns.is_shown(Column::MTime) {
+                        "shown"
+                    } else {
+                        "hidden"
+                    },
                 );
-                hotkey(
-                    "x",
-                    "Mark for the currently selected entry for deletion and move down",
-                    None,
+                option(
+                    "other size",
+                    if display.columns.is_shown(Column::OtherSize) {
+                        "shown"
+                    } else {
+                        "hidden"
+                    },
+                );
+                option(
+                    "hidden",
+                    if filter.hide_hidden {
+                        "hidden"
+                    } else {
+                        "shown"
+                    },
+                );
+                option("min size", filter.min_size_label());
+                option("entry kinds", filter.kind.description());
+                option(
+                    "footer stats",
+                    if display.show_footer_stats {
+                        "shown"
+                    } else {
+                        "hidden"
+                    },
                 );
-                hotkey("<space bar>", "Toggle the currently selected entry", None);
+                spacer();
+            }
+            for (group, actions) in ACTION_GROUPS {
+                title(group);
+                for action in actions.iter() {
+                    let (description, other_line) = action.description();
+                    let mut lines = key_lines(keymap.keys(*action)).into_iter();
+                    hotkey(lines.next().unwrap_or_default(), description, other_line);
+                    for keys in lines {
+                        hotkey(keys, "^", None);
+                    }
+                    if *action == Action::ExitDirectory {
+                        hotkey(
+                            "1-9".into(),
+                            "ascent to the directory with that number in the",
+                            Some("path shown above the entries"),
+                        );
+                    }
+                }
+                spacer();
+            }
+            if !keymap.external_actions().is_empty() {
+                title("External actions, run with all marked entries or the selected one");
+                for action in keymap.external_actions() {
+                    let description = action
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| action.command.join(" "));
+                    let mut lines = key_lines(&action.keys).into_iter();
+                    hotkey(
+                        lines.next().unwrap_or_default(),
+                        &format!("{}: {}", action.name, description),
+                        None,
+                    );
+                    for keys in lines {
+                        hotkey(keys, "^", None);
+                    }
+                }
                 spacer();
             }
             title("Keys in the Mark pane");
             {
                 hotkey(
-                    "x/d/<space>",
+                    "x/d/<space>".into(),
                     "remove the selected entry from the list",
                     None,
                 );
                 hotkey(
-                    "Ctrl + r",
+                    "Ctrl + r".into(),
                     "Permanently delete all marked entries without prompt!",
                     Some("This operation cannot be undone!"),
                 );
+                if TRASH_SUPPORTED {
+                    hotkey(
+                        "Ctrl + t".into(),
+                        "Move all marked entries to the trash without prompt",
+                        None,
+                    );
+                }
                 spacer();
             }
             title("Keys for application control");
             {
                 hotkey(
-                    "Ctrl + c",
+                    "Ctrl + c".into(),
                     "close the application. No questions asked!",
-                    None,
+                    Some("While scanning, stop it and browse what was seen. While deleting, abort it"),
                 );
                 spacer();
             }
             lines.into_inner()
         };
 
-        let HelpPaneProps {
-            border_style,
-            has_focus,
-        } = props.borrow();
-
         let title = "Help";
         let block = Block::default()
             .title(title)
             .border_style(*border_style)
             .borders(Borders::ALL);
         let inner_block_area = block.inner(area);
+        Clear.render(area, buf);
         block.render(area, buf);
 
         if *has_focus {
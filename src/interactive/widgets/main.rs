@@ -1,9 +1,13 @@
 use crate::interactive::{
+    filtered_out,
     widgets::{
-        Entries, EntriesProps, Footer, FooterProps, Header, HelpPane, HelpPaneProps, MarkPane,
-        MarkPaneProps, COLOR_MARKED,
+        Breadcrumbs, BreadcrumbsProps, DeleteConfirmation, DeleteConfirmationProps,
+        DeletionProgress, DeletionProgressProps, DevicesPane, DevicesPaneProps, DuplicatesPane,
+        DuplicatesPaneProps, Entries, EntriesProps, ErrorPane, ErrorPaneProps, Footer, FooterProps,
+        Header, HelpPane, HelpPaneProps, MarkPane, MarkPaneProps, MessagesPane, MessagesPaneProps,
+        PathFinder, PathFinderProps, Treemap, TreemapProps,
     },
-    AppState, DisplayOptions, FocussedPane,
+    AppState, DisplayOptions, EntryFilter, FocussedPane, PercentageBase,
 };
 use dua::traverse::Traversal;
 use std::borrow::Borrow;
@@ -25,8 +29,17 @@ pub struct MainWindowProps<'a> {
 #[derive(Default)]
 pub struct MainWindow {
     pub help_pane: Option<HelpPane>,
+    pub breadcrumbs: Breadcrumbs,
     pub entries_pane: Entries,
+    /// If set, the entries are shown as treemap instead of a list
+    pub treemap: Option<Treemap>,
     pub mark_pane: Option<MarkPane>,
+    pub delete_confirmation: Option<DeleteConfirmation>,
+    pub path_finder: Option<PathFinder>,
+    pub error_pane: Option<ErrorPane>,
+    pub messages_pane: Option<MessagesPane>,
+    pub duplicates_pane: Option<DuplicatesPane>,
+    pub devices_pane: Option<DevicesPane>,
 }
 
 impl MainWindow {
@@ -41,22 +54,27 @@ impl MainWindow {
                 Traversal {
                     tree,
                     entries_traversed,
+                    io_errors,
+                    errors,
                     total_bytes,
+                    apparent_size,
+                    is_incomplete,
                     ..
                 },
             display,
             state,
         } = props.borrow();
 
+        let theme = display.theme;
         let (entries_style, help_style, mark_style) = {
             let grey = Style {
-                fg: Color::DarkGray.into(),
+                fg: theme.border.into(),
                 bg: Color::Reset.into(),
                 add_modifier: Modifier::empty(),
                 ..Style::default()
             };
             let bold = Style {
-                fg: Color::Rgb(230, 230, 230).into(),
+                fg: theme.focussed_border.into(),
                 add_modifier: Modifier::BOLD,
                 ..grey
             };
@@ -67,84 +85,226 @@ impl MainWindow {
             }
         };
 
-        let (header_area, entries_area, footer_area) = {
+        let (header_area, breadcrumbs_area, entries_area, footer_area) = {
             let regions = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Length(1), Max(256), Length(1)].as_ref())
+                .constraints([Length(1), Length(1), Max(256), Length(1)].as_ref())
                 .split(area);
-            (regions[0], regions[1], regions[2])
+            (regions[0], regions[1], regions[2], regions[3])
         };
         {
             let marked = self.mark_pane.as_ref().map(|p| p.marked());
-            let bg_color = match (marked.map_or(true, |m| m.is_empty()), state.focussed) {
-                (false, FocussedPane::Mark) => Color::LightRed,
-                (false, _) => COLOR_MARKED,
-                (_, _) => Color::White,
+            let bg_color = match (marked.is_none_or(|m| m.is_empty()), state.focussed) {
+                (false, FocussedPane::Mark) => theme.danger,
+                (false, _) => theme.marked,
+                (_, _) => theme.header,
             };
-            Header.render(bg_color, header_area, buf);
+            Header.render(bg_color, theme.header_text, state.sorting, header_area, buf);
         }
-        let (entries_area, help_pane, mark_pane) = {
-            let regions = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Percentage(50), Percentage(50)].as_ref())
-                .split(entries_area);
-            let (left_pane, right_pane) = (regions[0], regions[1]);
-            match (&mut self.help_pane, &mut self.mark_pane) {
-                (Some(ref mut pane), None) => (left_pane, Some((right_pane, pane)), None),
-                (None, Some(ref mut pane)) => (left_pane, None, Some((right_pane, pane))),
-                (Some(ref mut help), Some(ref mut mark)) => {
-                    let regions = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([Percentage(50), Percentage(50)].as_ref())
-                        .split(right_pane);
-                    (
-                        left_pane,
-                        Some((regions[0], help)),
-                        Some((regions[1], mark)),
-                    )
-                }
-                (None, None) => (entries_area, None, None),
+        self.breadcrumbs.render(
+            BreadcrumbsProps {
+                tree,
+                root: state.root,
+                theme,
+            },
+            breadcrumbs_area,
+            buf,
+        );
+        let help_area = overlay(entries_area);
+        let (entries_area, mark_pane) = match self.mark_pane {
+            Some(ref mut pane) => {
+                let regions = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Percentage(50), Percentage(50)].as_ref())
+                    .split(entries_area);
+                (regions[0], Some((regions[1], pane)))
             }
+            None => (entries_area, None),
         };
 
         if let Some((mark_area, pane)) = mark_pane {
             let props = MarkPaneProps {
                 border_style: mark_style,
                 format: display.byte_format,
+                theme,
             };
             pane.render(props, mark_area, buf);
         }
 
-        if let Some((help_area, pane)) = help_pane {
-            let props = HelpPaneProps {
-                border_style: help_style,
-                has_focus: matches!(state.focussed, Help),
-            };
-            pane.render(props, help_area, buf);
-        }
-
         let marked = self.mark_pane.as_ref().map(|p| p.marked());
-        let props = EntriesProps {
-            tree: &tree,
-            root: state.root,
-            display: *display,
-            entries: &state.entries,
-            marked,
-            selected: state.selected,
-            border_style: entries_style,
-            is_focussed: matches!(state.focussed, Main),
-        };
-        self.entries_pane.render(props, entries_area, buf);
+        match &mut self.treemap {
+            Some(treemap) => {
+                let props = TreemapProps {
+                    tree,
+                    root: state.root,
+                    display: *display,
+                    entries: &state.entries,
+                    marked,
+                    selected: state.selected,
+                    border_style: entries_style,
+                };
+                treemap.render(props, entries_area, buf);
+            }
+            None => {
+                let props = EntriesProps {
+                    tree,
+                    root: state.root,
+                    display: *display,
+                    entries: &state.entries,
+                    marked,
+                    selected: state.selected,
+                    border_style: entries_style,
+                    is_focussed: matches!(state.focussed, Main),
+                    keymap: &state.keymap,
+                    sorting: state.sorting,
+                    filtered_out: (state.filter != EntryFilter::default())
+                        .then(|| filtered_out(tree, state.root, state.filter)),
+                    percentage_total: match display.percentage_base {
+                        PercentageBase::Directory => None,
+                        PercentageBase::Scan => *total_bytes,
+                        PercentageBase::Filesystem => {
+                            state.device_of_root(tree).map(|device| device.total)
+                        }
+                    },
+                    is_remote: state.is_remote,
+                };
+                self.entries_pane.render(props, entries_area, buf);
+            }
+        }
 
         Footer.render(
             FooterProps {
                 total_bytes: *total_bytes,
+                apparent_size: *apparent_size,
+                is_incomplete: *is_incomplete,
                 format: display.byte_format,
                 entries_traversed: *entries_traversed,
+                io_errors: *io_errors,
+                scanning_for: state
+                    .scan_started
+                    .filter(|_| state.is_scanning)
+                    .map(|started| started.elapsed()),
+                free_space: display
+                    .show_footer_stats
+                    .then(|| state.device_of_root(tree))
+                    .flatten()
+                    .map(|device| (device.available, device.total)),
+                marked: self
+                    .mark_pane
+                    .as_ref()
+                    .map(|pane| (pane.marked().len(), pane.total_size())),
+                scanned_in: state
+                    .scan_duration
+                    .filter(|_| display.show_footer_stats && !state.is_scanning),
                 message: state.message.clone(),
+                search: state
+                    .search
+                    .clone()
+                    .map(|search| (search, state.entries.len())),
+                mark_pattern: state.mark_pattern.clone(),
+                theme,
             },
             footer_area,
             buf,
         );
+
+        if let Some(pane) = &mut self.help_pane {
+            let props = HelpPaneProps {
+                border_style: help_style,
+                has_focus: matches!(state.focussed, Help),
+                display: *display,
+                sorting: state.sorting,
+                filter: state.filter,
+                apparent_size: *apparent_size,
+                keymap: &state.keymap,
+            };
+            pane.render(props, help_area, buf);
+        }
+
+        if let Some(finder) = &mut self.path_finder {
+            let props = PathFinderProps {
+                border_style: entries_style,
+                theme,
+            };
+            finder.render(props, help_area, buf);
+        }
+
+        if let Some(pane) = &mut self.error_pane {
+            let props = ErrorPaneProps {
+                errors,
+                io_errors: *io_errors,
+                border_style: entries_style,
+                theme,
+            };
+            pane.render(props, help_area, buf);
+        }
+
+        if let Some(pane) = &mut self.messages_pane {
+            let props = MessagesPaneProps {
+                messages: &state.messages,
+                border_style: entries_style,
+                theme,
+            };
+            pane.render(props, help_area, buf);
+        }
+
+        if let Some(pane) = &mut self.duplicates_pane {
+            let props = DuplicatesPaneProps {
+                format: display.byte_format,
+                border_style: entries_style,
+                theme,
+            };
+            pane.render(props, help_area, buf);
+        }
+
+        if let Some(pane) = &mut self.devices_pane {
+            let props = DevicesPaneProps {
+                format: display.byte_format,
+                border_style: entries_style,
+                theme,
+            };
+            pane.render(props, help_area, buf);
+        }
+
+        if let Some(dialog) = &self.delete_confirmation {
+            dialog.render(
+                DeleteConfirmationProps {
+                    format: display.byte_format,
+                    theme,
+                },
+                area,
+                buf,
+            );
+        }
+
+        if let Some(deletion) = &state.deletion {
+            DeletionProgress.render(
+                DeletionProgressProps {
+                    deletion,
+                    format: display.byte_format,
+                    theme,
+                },
+                area,
+                buf,
+            );
+        }
+    }
+}
+
+/// The area of panes drawn on top of the entries, leaving some of them visible around it.
+fn overlay(area: Rect) -> Rect {
+    let (width, height) = (
+        area.width
+            .saturating_sub(area.width / 5)
+            .max(area.width.min(80)),
+        area.height
+            .saturating_sub(area.height / 10)
+            .max(area.height.min(20)),
+    );
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     }
 }
@@ -1,5 +1,7 @@
 use crate::interactive::{
-    fit_string_graphemes_with_ellipsis, path_of, widgets::entry_color, CursorDirection,
+    fit_string_graphemes_with_ellipsis, path_of,
+    widgets::{entry_color, Theme},
+    CursorDirection, DeletionMode, TRASH_SUPPORTED,
 };
 use crosstermion::{input::Key, input::Key::*};
 use dua::{
@@ -10,12 +12,12 @@ use itertools::Itertools;
 use std::{
     borrow::Borrow,
     collections::{btree_map::Entry, BTreeMap},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use tui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
     widgets::{Block, Borders, Paragraph, Widget},
 };
@@ -27,7 +29,7 @@ use tui_react::{
 use unicode_segmentation::UnicodeSegmentation;
 
 pub enum MarkMode {
-    Delete,
+    Delete(DeletionMode),
 }
 
 pub type EntryMarkMap = BTreeMap<TreeIndex, EntryMark>;
@@ -51,6 +53,7 @@ pub struct MarkPane {
 pub struct MarkPaneProps {
     pub border_style: Style,
     pub format: ByteFormat,
+    pub theme: Theme,
 }
 
 impl MarkPane {
@@ -79,7 +82,7 @@ impl MarkPane {
                     let sorting_index = self.last_sorting_index + 1;
                     self.last_sorting_index = sorting_index;
                     entry.insert(EntryMark {
-                        size: e.size,
+                        size: e.size.into(),
                         path: path_of(tree, index),
                         index: sorting_index,
                         num_errors_during_deletion: 0,
@@ -99,21 +102,60 @@ impl MarkPane {
             Some(self)
         }
     }
+    pub fn refresh_sizes(&mut self, tree: &Tree) {
+        for (index, mark) in self.marked.iter_mut() {
+            if let Some(e) = tree.node_weight(*index) {
+                mark.size = e.size.into();
+            }
+        }
+    }
+    /// Unmark all entries which are not part of the `tree` anymore, like after deleting them.
+    pub fn forget_deleted(mut self, tree: &Tree) -> Option<Self> {
+        self.marked
+            .retain(|index, _| tree.node_weight(*index).is_some());
+        if self.marked.is_empty() {
+            None
+        } else {
+            self.selected = self
+                .selected
+                .map(|selected| selected.min(self.marked.len() - 1));
+            Some(self)
+        }
+    }
     pub fn marked(&self) -> &EntryMarkMap {
         &self.marked
     }
+    /// The size of all marked entries, counting those within marked directories only once.
+    pub fn total_size(&self) -> u128 {
+        // Paths within a directory are sorted right after it
+        let mut marks: Vec<_> = self.marked.values().collect();
+        marks.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut total = 0;
+        let mut last_counted: Option<&Path> = None;
+        for mark in marks {
+            if last_counted.is_some_and(|dir| mark.path.starts_with(dir)) {
+                continue;
+            }
+            total += mark.size;
+            last_counted = Some(&mark.path);
+        }
+        total
+    }
     pub fn into_paths(self) -> impl Iterator<Item = PathBuf> {
-        self.marked.into_iter().map(|(_k, v)| v.path)
+        self.marked.into_values().map(|v| v.path)
     }
     pub fn process_events(mut self, key: Key) -> Option<(Self, Option<MarkMode>)> {
         let action = None;
         match key {
-            Ctrl('r') => return Some(self.prepare_deletion()),
+            Ctrl('r') => return Some(self.prepare_deletion(DeletionMode::Permanently)),
+            Ctrl('t') if TRASH_SUPPORTED => {
+                return Some(self.prepare_deletion(DeletionMode::ToTrash))
+            }
             Char('x') | Char('d') | Char(' ') => {
                 return self.remove_selected().map(|s| (s, action))
             }
-            Char('H') => self.change_selection(CursorDirection::ToTop),
-            Char('G') => self.change_selection(CursorDirection::ToBottom),
+            Char('H') | Char('g') | Home => self.change_selection(CursorDirection::ToTop),
+            Char('G') | End => self.change_selection(CursorDirection::ToBottom),
             Ctrl('u') | PageUp => self.change_selection(CursorDirection::PageUp),
             Char('k') | Up => self.change_selection(CursorDirection::Up),
             Char('j') | Down => self.change_selection(CursorDirection::Down),
@@ -123,66 +165,18 @@ impl MarkPane {
         Some((self, action))
     }
 
-    pub fn iterate_deletable_items(
-        mut self,
-        mut delete_fn: impl FnMut(Self, TreeIndex) -> Result<Self, (Self, usize)>,
-    ) -> Option<Self> {
-        loop {
-            match self.next_entry_for_deletion() {
-                Some(entry_to_delete) => match delete_fn(self, entry_to_delete) {
-                    Ok(pane) => {
-                        self = pane;
-                        match self.delete_entry() {
-                            Some(p) => self = p,
-                            None => return None,
-                        }
-                    }
-                    Err((pane, num_errors)) => {
-                        self = pane;
-                        self.set_error_on_marked_item(num_errors)
-                    }
-                },
-                None => return Some(self),
-            }
-        }
-    }
-
-    fn next_entry_for_deletion(&mut self) -> Option<TreeIndex> {
-        match self.selected.and_then(|selected| {
-            self.tree_index_by_list_position(selected)
-                .and_then(|idx| self.marked.get(&idx).map(|d| (selected, idx, d)))
-        }) {
-            Some((position, selected_index, data)) => match data.num_errors_during_deletion {
-                0 => Some(selected_index),
-                _ => {
-                    self.selected = match position + 1 {
-                        p if p < self.marked.len() => Some(p),
-                        _ => Some(self.marked.len().saturating_sub(1)),
-                    };
-                    self.tree_index_by_list_position(position + 1)
-                }
-            },
-            None => None,
-        }
-    }
-    fn delete_entry(self) -> Option<Self> {
-        self.remove_selected()
-    }
-    fn set_error_on_marked_item(&mut self, num_errors: usize) {
-        if let Some(d) = self
-            .selected
-            .and_then(|s| self.tree_index_by_list_position(s))
-            .and_then(|p| self.marked.get_mut(&p))
-        {
-            d.num_errors_during_deletion = num_errors;
+    /// Remember that `num_errors` occurred while deleting the marked entry at `index`, to show it.
+    pub fn set_errors_during_deletion(&mut self, index: TreeIndex, num_errors: usize) {
+        if let Some(mark) = self.marked.get_mut(&index) {
+            mark.num_errors_during_deletion = num_errors;
         }
     }
-    fn prepare_deletion(mut self) -> (Self, Option<MarkMode>) {
+    fn prepare_deletion(mut self, mode: DeletionMode) -> (Self, Option<MarkMode>) {
         for entry in self.marked.values_mut() {
             entry.num_errors_during_deletion = 0;
         }
         self.selected = Some(0);
-        (self, Some(MarkMode::Delete))
+        (self, Some(MarkMode::Delete(mode)))
     }
     fn remove_selected(mut self) -> Option<Self> {
         if let Some(mut selected) = self.selected {
@@ -211,7 +205,8 @@ impl MarkPane {
             .map(|(k, _)| *k.to_owned())
     }
 
-    fn marked_sorted_by_index(&self) -> Vec<(&TreeIndex, &EntryMark)> {
+    /// The marked entries in the order they were marked in.
+    pub fn marked_sorted_by_index(&self) -> Vec<(&TreeIndex, &EntryMark)> {
         self.marked
             .iter()
             .sorted_by_key(|(_, v)| &v.index)
@@ -230,13 +225,14 @@ impl MarkPane {
         let MarkPaneProps {
             border_style,
             format,
+            theme,
         } = props.borrow();
 
         let marked: &_ = &self.marked;
         let title = format!(
             "Marked {} items ({}) ",
             marked.len(),
-            format.display(marked.iter().map(|(_k, v)| v.size).sum::<u128>())
+            format.display(self.total_size())
         );
         let selected = self.selected;
         let has_focus = self.has_focus;
@@ -278,7 +274,7 @@ impl MarkPane {
                         _ => (path, num_path_graphemes),
                     }
                 };
-                let fg_path = entry_color(None, !v.is_dir, true);
+                let fg_path = entry_color(theme, !v.is_dir, true);
                 let path = Span::styled(
                     path,
                     Style {
@@ -293,7 +289,7 @@ impl MarkPane {
                         byte_column_width = format.width()
                     ),
                     Style {
-                        fg: Color::Green.into(),
+                        fg: theme.size.into(),
                         ..base_style
                     },
                 );
@@ -352,8 +348,8 @@ impl MarkPane {
             };
 
             let default_style = Style {
-                fg: Color::Black.into(),
-                bg: Color::Yellow.into(),
+                fg: theme.header_text.into(),
+                bg: theme.marked.into(),
                 add_modifier: Modifier::BOLD,
                 sub_modifier: Modifier::empty(),
             };
@@ -361,7 +357,7 @@ impl MarkPane {
                 Span::styled(
                     " Ctrl + r",
                     Style {
-                        fg: Color::LightRed.into(),
+                        fg: theme.danger.into(),
                         add_modifier: default_style.add_modifier | Modifier::RAPID_BLINK,
                         ..default_style
                     },
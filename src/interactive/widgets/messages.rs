@@ -0,0 +1,104 @@
+use crate::interactive::{
+    widgets::{format_age, Theme},
+    CursorDirection, MessageLog, Severity,
+};
+use crosstermion::{input::Key, input::Key::*};
+use std::{borrow::Borrow, time::SystemTime};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Clear, Widget},
+};
+use tui_react::{List, ListProps};
+
+/// An overlay listing the messages shown so far with the latest one first, to read those that were missed.
+#[derive(Default)]
+pub struct MessagesPane {
+    selected: usize,
+    list: List,
+}
+
+pub struct MessagesPaneProps<'a> {
+    pub messages: &'a MessageLog,
+    pub border_style: Style,
+    pub theme: Theme,
+}
+
+impl MessagesPane {
+    /// Handle `key`, returning `None` if the pane was closed.
+    pub fn process_events(mut self, key: Key, num_messages: usize) -> Option<Self> {
+        let direction = match key {
+            Esc | Char('q') | Char('E') => return None,
+            Char('H') | Char('g') | Home => CursorDirection::ToTop,
+            Char('G') | End => CursorDirection::ToBottom,
+            Ctrl('u') | PageUp => CursorDirection::PageUp,
+            Char('k') | Up => CursorDirection::Up,
+            Char('j') | Down => CursorDirection::Down,
+            Ctrl('d') | PageDown => CursorDirection::PageDown,
+            _ => return Some(self),
+        };
+        self.selected = direction
+            .move_cursor(self.selected)
+            .min(num_messages.saturating_sub(1));
+        Some(self)
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<MessagesPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let MessagesPaneProps {
+            messages,
+            border_style,
+            theme,
+        } = props.borrow();
+        let title = format!(" Messages ({}) ", messages.len());
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner_area = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let now = SystemTime::now();
+        let selected = self.selected;
+        let lines = messages.latest_first().enumerate().map(|(idx, message)| {
+            let style = if idx == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            let severity_style = match message.severity {
+                Severity::Info => style,
+                Severity::Warning => style.fg(theme.warning),
+                Severity::Error => style.fg(theme.error),
+            };
+            let age = format_age(now.duration_since(message.at).unwrap_or_default());
+            vec![
+                Span::styled(format!(" {:>4} ago ", age), style),
+                Span::styled(
+                    format!("{:<8}", message.severity.label()),
+                    severity_style.add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "{:<width$}",
+                        message.text,
+                        width = (inner_area.width as usize).saturating_sub(18)
+                    ),
+                    style,
+                ),
+            ]
+        });
+        let props = ListProps {
+            block: None,
+            entry_in_view: Some(selected),
+        };
+        self.list.render(props, lines, inner_area, buf);
+    }
+}
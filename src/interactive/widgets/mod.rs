@@ -1,27 +1,43 @@
+mod breadcrumbs;
+mod confirm;
+mod devices;
+mod duplicates;
 mod entries;
+mod errors;
+mod finder;
 mod footer;
 mod header;
 mod help;
 mod main;
 mod mark;
+mod messages;
+mod theme;
+mod treemap;
 
+pub use breadcrumbs::*;
+pub use confirm::*;
+pub use devices::*;
+pub use duplicates::*;
 pub use entries::*;
+pub use errors::*;
+pub use finder::*;
 pub use footer::*;
 pub use header::*;
 pub use help::*;
 pub use main::*;
 pub use mark::*;
+pub use messages::*;
+pub use theme::*;
+pub use treemap::*;
 
 use tui::style::Color;
 
-pub const COLOR_MARKED: Color = Color::Yellow;
-pub const COLOR_MARKED_DARK: Color = Color::Rgb(176, 126, 0);
-
-fn entry_color(fg: Option<Color>, is_file: bool, is_marked: bool) -> Option<Color> {
+fn entry_color(theme: &Theme, is_file: bool, is_marked: bool) -> Option<Color> {
     match (is_file, is_marked) {
-        (true, false) => fg,
-        (true, true) => COLOR_MARKED_DARK.into(),
-        (false, true) => COLOR_MARKED.into(),
-        (false, false) => Color::Cyan.into(),
+        (true, false) => theme.file,
+        (true, true) => theme.marked_file,
+        (false, true) => theme.marked,
+        (false, false) => theme.directory,
     }
+    .into()
 }
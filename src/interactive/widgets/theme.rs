@@ -0,0 +1,329 @@
+use crate::config::{Config, Table};
+use anyhow::{anyhow, bail, Context, Result};
+use tui::style::Color;
+
+/// The colors the terminal user interface is drawn with.
+///
+/// [`Color::Reset`] stands for the color the terminal uses by default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// The names of directories
+    pub directory: Color,
+    /// The names of files
+    pub file: Color,
+    /// The sizes of entries
+    pub size: Color,
+    /// The names of marked directories, and the background of the header while entries are marked
+    pub marked: Color,
+    /// The names of marked files
+    pub marked_file: Color,
+    /// Directories tagged as cache, whose contents are safe to delete
+    pub cache_dir: Color,
+    /// Symbolic links, which are followed by the path they point to
+    pub symlink: Color,
+    /// Symbolic links whose target doesn't exist
+    pub broken_link: Color,
+    /// Entries which don't exist anymore, IO errors and error messages
+    pub error: Color,
+    /// Messages telling why an action wasn't done
+    pub warning: Color,
+    /// What deletes entries without asking, like the mark pane while it's focussed
+    pub danger: Color,
+    /// The background of the header
+    pub header: Color,
+    /// The text of the header
+    pub header_text: Color,
+    /// The border of the focussed pane
+    pub focussed_border: Color,
+    /// The borders of all other panes, and separators
+    pub border: Color,
+    /// Keys in the help and in dialogs, and the parts of lines that matter most
+    pub accent: Color,
+    /// The numbers of the directories in the path above the entries
+    pub highlight: Color,
+    /// The cells of directories in the treemap, alternating to tell neighbours apart
+    pub treemap_directories: [Color; 2],
+    /// The cells of files in the treemap, alternating to tell neighbours apart
+    pub treemap_files: [Color; 2],
+}
+
+/// The names of the themes that are always available
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+impl Theme {
+    /// Colors for terminals with a dark background
+    pub const DARK: Theme = Theme {
+        directory: Color::Cyan,
+        file: Color::Reset,
+        size: Color::Green,
+        marked: Color::Yellow,
+        marked_file: Color::Rgb(176, 126, 0),
+        cache_dir: Color::Magenta,
+        symlink: Color::LightBlue,
+        broken_link: Color::LightMagenta,
+        error: Color::Red,
+        warning: Color::Yellow,
+        danger: Color::LightRed,
+        header: Color::White,
+        header_text: Color::Black,
+        focussed_border: Color::Rgb(230, 230, 230),
+        border: Color::DarkGray,
+        accent: Color::Green,
+        highlight: Color::Yellow,
+        treemap_directories: [Color::Cyan, Color::Blue],
+        treemap_files: [Color::Green, Color::DarkGray],
+    };
+
+    /// Colors for terminals with a light background
+    pub const LIGHT: Theme = Theme {
+        directory: Color::Blue,
+        file: Color::Reset,
+        size: Color::Rgb(0, 128, 0),
+        marked: Color::Rgb(176, 126, 0),
+        marked_file: Color::Rgb(128, 90, 0),
+        cache_dir: Color::Magenta,
+        symlink: Color::Rgb(0, 128, 128),
+        broken_link: Color::Rgb(160, 0, 160),
+        error: Color::Red,
+        warning: Color::Rgb(176, 126, 0),
+        danger: Color::Red,
+        header: Color::DarkGray,
+        header_text: Color::White,
+        focussed_border: Color::Black,
+        border: Color::Gray,
+        accent: Color::Rgb(0, 128, 0),
+        highlight: Color::Rgb(176, 126, 0),
+        treemap_directories: [Color::LightCyan, Color::LightBlue],
+        treemap_files: [Color::LightGreen, Color::Gray],
+    };
+
+    /// No colors at all, relying on text attributes like bold and reversed text instead
+    pub const MONOCHROME: Theme = Theme {
+        directory: Color::Reset,
+        file: Color::Reset,
+        size: Color::Reset,
+        marked: Color::Reset,
+        marked_file: Color::Reset,
+        cache_dir: Color::Reset,
+        symlink: Color::Reset,
+        broken_link: Color::Reset,
+        error: Color::Reset,
+        warning: Color::Reset,
+        danger: Color::Reset,
+        header: Color::Reset,
+        header_text: Color::Reset,
+        focussed_border: Color::Reset,
+        border: Color::Reset,
+        accent: Color::Reset,
+        highlight: Color::Reset,
+        treemap_directories: [Color::Reset, Color::Reset],
+        treemap_files: [Color::Reset, Color::Reset],
+    };
+
+    /// Return the built-in theme with the given `name`, if there is one.
+    pub fn builtin(name: &str) -> Option<Theme> {
+        Some(match name {
+            "dark" => Theme::DARK,
+            "light" => Theme::LIGHT,
+            "monochrome" | "no-color" => Theme::MONOCHROME,
+            _ => return None,
+        })
+    }
+
+    /// Return the theme with the given `name`, defined in a `[themes.<name>]` table of `config` or built in.
+    ///
+    /// Themes in the configuration start out with the colors of the theme named by their `base` key, which defaults
+    /// to `dark`, and change the colors of the keys they set.
+    pub fn from_config(name: &str, config: &Config) -> Result<Theme> {
+        Self::from_config_inner(name, config, &mut Vec::new())
+    }
+
+    fn from_config_inner<'a>(
+        name: &'a str,
+        config: &'a Config,
+        seen: &mut Vec<&'a str>,
+    ) -> Result<Theme> {
+        let table = match config.subtables("themes").find(|(n, _)| *n == name) {
+            Some((_, table)) => table,
+            None => {
+                return Theme::builtin(name).ok_or_else(|| {
+                    anyhow!(
+                    "Unknown theme '{}', it's not one of {} and not defined in the configuration",
+                    name,
+                    BUILTIN_THEMES.join(", ")
+                )
+                })
+            }
+        };
+        if seen.contains(&name) {
+            bail!("Theme '{}' is based on itself", name);
+        }
+        seen.push(name);
+        let base = match table.get("base") {
+            Some(base) => base.as_str("base")?,
+            None => "dark",
+        };
+        let base = if base == name {
+            Theme::builtin(base).ok_or_else(|| anyhow!("Theme '{}' is based on itself", name))?
+        } else {
+            Self::from_config_inner(base, config, seen)?
+        };
+        base.with_colors_of(table)
+            .with_context(|| format!("Invalid theme '{}'", name))
+    }
+
+    /// Change the colors named by the keys of `table` to their value.
+    fn with_colors_of(mut self, table: &Table) -> Result<Theme> {
+        for (key, value) in table {
+            let color = || value.as_str(key).and_then(parse_color);
+            let colors = || -> Result<[Color; 2]> {
+                match value.as_strs(key)?.as_slice() {
+                    [first, second] => Ok([parse_color(first)?, parse_color(second)?]),
+                    _ => bail!("'{}' must have two colors", key),
+                }
+            };
+            match key.as_str() {
+                "base" => {}
+                "directory" => self.directory = color()?,
+                "file" => self.file = color()?,
+                "size" => self.size = color()?,
+                "marked" => self.marked = color()?,
+                "marked_file" => self.marked_file = color()?,
+                "cache_dir" => self.cache_dir = color()?,
+                "symlink" => self.symlink = color()?,
+                "broken_link" => self.broken_link = color()?,
+                "error" => self.error = color()?,
+                "warning" => self.warning = color()?,
+                "danger" => self.danger = color()?,
+                "header" => self.header = color()?,
+                "header_text" => self.header_text = color()?,
+                "focussed_border" => self.focussed_border = color()?,
+                "border" => self.border = color()?,
+                "accent" => self.accent = color()?,
+                "highlight" => self.highlight = color()?,
+                "treemap_directories" => self.treemap_directories = colors()?,
+                "treemap_files" => self.treemap_files = colors()?,
+                _ => bail!("Unknown color '{}'", key),
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Parse a color by its name, like `light-red`, or as hexadecimal RGB value, like `#ff8800`.
+pub fn parse_color(input: &str) -> Result<Color> {
+    if let Some(hex) = input.strip_prefix('#') {
+        let component = |range: std::ops::Range<usize>| {
+            hex.get(range)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid color '{}', expected '#rrggbb'", input))
+        };
+        if hex.len() != 6 {
+            bail!("Invalid color '{}', expected '#rrggbb'", input);
+        }
+        return Ok(Color::Rgb(
+            component(0..2)?,
+            component(2..4)?,
+            component(4..6)?,
+        ));
+    }
+    let name: String = input
+        .chars()
+        .filter(|c| *c != '-' && *c != '_' && *c != ' ')
+        .flat_map(char::to_lowercase)
+        .collect();
+    Ok(match name.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => bail!("Invalid color '{}'", input),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse;
+
+    #[test]
+    fn colors_are_parsed_by_name_and_hex_value() {
+        assert_eq!(parse_color("light-red").unwrap(), Color::LightRed);
+        assert_eq!(parse_color("Dark_Gray").unwrap(), Color::DarkGray);
+        assert_eq!(parse_color("#ff8800").unwrap(), Color::Rgb(255, 136, 0));
+        assert!(parse_color("#ff88").is_err());
+        assert!(parse_color("#gg8800").is_err());
+        assert!(parse_color("purple").is_err());
+    }
+
+    #[test]
+    fn themes_of_the_configuration_are_based_on_other_themes() {
+        let config = parse(
+            r##"
+            [themes.mine]
+            base = "light"
+            directory = "#102030"
+            treemap_files = ["red", "blue"]
+
+            [themes.derived]
+            base = "mine"
+            size = "magenta"
+
+            [themes.dark]
+            base = "dark"
+            size = "yellow"
+
+            [themes.broken]
+            directory = "purple"
+
+            [themes.loop]
+            base = "loop2"
+            [themes.loop2]
+            base = "loop"
+            "##,
+        )
+        .unwrap();
+        let derived = Theme::from_config("derived", &config).unwrap();
+        assert_eq!(derived.directory, Color::Rgb(16, 32, 48));
+        assert_eq!(derived.size, Color::Magenta);
+        assert_eq!(derived.treemap_files, [Color::Red, Color::Blue]);
+        assert_eq!(derived.border, Theme::LIGHT.border);
+
+        let dark = Theme::from_config("dark", &config).unwrap();
+        assert_eq!(dark.size, Color::Yellow, "built-in themes can be changed");
+        assert_eq!(dark.directory, Theme::DARK.directory);
+
+        assert_eq!(
+            Theme::from_config("monochrome", &config).unwrap(),
+            Theme::MONOCHROME
+        );
+        assert_eq!(
+            format!("{:#}", Theme::from_config("broken", &config).unwrap_err()),
+            "Invalid theme 'broken': Invalid color 'purple'"
+        );
+        assert_eq!(
+            Theme::from_config("loop", &config).unwrap_err().to_string(),
+            "Theme 'loop' is based on itself"
+        );
+        assert!(Theme::from_config("unknown", &config).is_err());
+    }
+}
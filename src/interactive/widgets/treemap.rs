@@ -0,0 +1,261 @@
+use crate::interactive::{
+    path_of, top_label, widgets::EntryMarkMap, DisplayOptions, EntryDataBundle,
+};
+use dua::traverse::{Tree, TreeIndex};
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Widget},
+};
+use tui_react::{draw_text_nowrap_fn, util::block_width};
+
+pub struct TreemapProps<'a> {
+    pub tree: &'a Tree,
+    pub root: TreeIndex,
+    pub display: DisplayOptions,
+    pub selected: Option<TreeIndex>,
+    pub entries: &'a [EntryDataBundle],
+    pub marked: Option<&'a EntryMarkMap>,
+    pub border_style: Style,
+}
+
+/// The entries of the current directory as rectangles whose area is proportional to their size.
+#[derive(Default)]
+pub struct Treemap {
+    /// The area the treemap was last drawn in
+    pub area: Rect,
+    /// The entries drawn last along with their cell, to know which entry the mouse points at
+    pub cells: Vec<(TreeIndex, Rect)>,
+}
+
+impl Treemap {
+    /// Return the entry whose cell contains the given `column` and `row` of the terminal, if there is one.
+    pub fn entry_at(&self, column: u16, row: u16) -> Option<TreeIndex> {
+        self.cells
+            .iter()
+            .find(|(_, r)| {
+                column >= r.x && column < r.x + r.width && row >= r.y && row < r.y + r.height
+            })
+            .map(|(index, _)| *index)
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<TreemapProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let TreemapProps {
+            tree,
+            root,
+            display,
+            selected,
+            entries,
+            marked,
+            border_style,
+        } = props.borrow();
+        self.area = area;
+
+        let title = match path_of(tree, *root).to_string_lossy().to_string() {
+            ref p if p.is_empty() => top_label(tree, *root).unwrap_or(".").to_owned(),
+            p => p,
+        };
+        let title = format!(" {} (treemap) ", title);
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let mut by_size: Vec<_> = entries.iter().collect();
+        by_size.sort_by_key(|b| std::cmp::Reverse(b.data.size));
+        let sizes: Vec<_> = by_size.iter().map(|b| u128::from(b.data.size)).collect();
+        self.cells = layout(&sizes, inner)
+            .into_iter()
+            .map(|(pos, cell)| (by_size[pos].index, cell))
+            .collect();
+
+        let theme = &display.theme;
+        for (pos, (index, cell)) in self.cells.iter().enumerate() {
+            let entry = match entries.iter().find(|b| b.index == *index) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let is_selected = *selected == Some(*index);
+            let is_marked = marked.map(|m| m.contains_key(index)).unwrap_or(false);
+            let color = if is_selected {
+                theme.focussed_border
+            } else if is_marked {
+                if entry.is_dir {
+                    theme.marked
+                } else {
+                    theme.marked_file
+                }
+            } else if entry.data.is_cache_dir {
+                theme.cache_dir
+            } else if entry.is_dir {
+                theme.treemap_directories[pos % 2]
+            } else {
+                theme.treemap_files[pos % 2]
+            };
+            // Without colors, cells are blank and only the selected one stands out
+            let is_colorless = color == Color::Reset;
+            let fill = Style {
+                fg: color.into(),
+                bg: color.into(),
+                add_modifier: if is_selected && is_colorless {
+                    Modifier::REVERSED
+                } else {
+                    Modifier::empty()
+                },
+                ..Style::default()
+            };
+            let symbol = if is_colorless {
+                " "
+            } else {
+                tui::symbols::block::FULL
+            };
+            for y in cell.y..cell.y + cell.height {
+                for x in cell.x..cell.x + cell.width {
+                    buf.get_mut(x, y).set_symbol(symbol).set_style(fill);
+                }
+            }
+
+            let label_style = Style {
+                fg: if is_colorless { color } else { Color::Black }.into(),
+                add_modifier: if is_selected {
+                    fill.add_modifier | Modifier::BOLD
+                } else {
+                    fill.add_modifier
+                },
+                ..fill
+            };
+            let name = format!(
+                "{}{}",
+                if entry.is_dir { "/" } else { "" },
+                tree.names[entry.data.name].to_string_lossy()
+            );
+            let size = display
+                .byte_format
+                .display(entry.data.size.into())
+                .to_string();
+            for (line, text) in [name, size].iter().enumerate() {
+                let line = line as u16;
+                if line >= cell.height || block_width(text) > cell.width {
+                    break;
+                }
+                draw_text_nowrap_fn(
+                    Rect {
+                        y: cell.y + line,
+                        height: 1,
+                        ..*cell
+                    },
+                    buf,
+                    text,
+                    |_, _, _| label_style,
+                );
+            }
+        }
+    }
+}
+
+/// Divide `area` into one cell per entry of `sizes`, each with an area proportional to its size, and return them
+/// along with the position of their size.
+///
+/// The largest entries should come first. Entries too small to get a cell of their own are left out.
+pub fn layout(sizes: &[u128], area: Rect) -> Vec<(usize, Rect)> {
+    let mut cells = Vec::with_capacity(sizes.len());
+    let positions: Vec<_> = (0..sizes.len()).filter(|pos| sizes[*pos] > 0).collect();
+    split(sizes, &positions, area, &mut cells);
+    cells
+}
+
+/// Split `positions` into two groups of about the same size, and `area` along its longer side to match.
+fn split(sizes: &[u128], positions: &[usize], area: Rect, cells: &mut Vec<(usize, Rect)>) {
+    if positions.is_empty() || area.width == 0 || area.height == 0 {
+        return;
+    }
+    if let [pos] = positions {
+        cells.push((*pos, area));
+        return;
+    }
+    let total: u128 = positions.iter().map(|pos| sizes[*pos]).sum();
+    let mut first_total = 0;
+    let mut split_at = 1;
+    for (count, pos) in positions.iter().enumerate().take(positions.len() - 1) {
+        first_total += sizes[*pos];
+        split_at = count + 1;
+        if first_total * 2 >= total {
+            break;
+        }
+    }
+    let (first, second) = positions.split_at(split_at);
+
+    let share = |length: u16| ((length as u128 * first_total + total / 2) / total) as u16;
+    // Cells of the terminal are about twice as high as they are wide
+    let (first_area, second_area) = if area.width >= area.height * 2 {
+        let width = share(area.width);
+        (
+            Rect { width, ..area },
+            Rect {
+                x: area.x + width,
+                width: area.width - width,
+                ..area
+            },
+        )
+    } else {
+        let height = share(area.height);
+        (
+            Rect { height, ..area },
+            Rect {
+                y: area.y + height,
+                height: area.height - height,
+                ..area
+            },
+        )
+    };
+    split(sizes, first, first_area, cells);
+    split(sizes, second, second_area, cells);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(rects: &[(usize, Rect)]) -> Vec<(usize, u16)> {
+        rects
+            .iter()
+            .map(|(pos, r)| (*pos, r.width * r.height))
+            .collect()
+    }
+
+    #[test]
+    fn layout_makes_areas_proportional_to_sizes() {
+        let full = Rect::new(0, 0, 40, 10);
+        assert_eq!(
+            area(&layout(&[200, 100, 100], full)),
+            vec![(0, 200), (1, 100), (2, 100)]
+        );
+        assert_eq!(
+            layout(&[1, 1], full),
+            vec![(0, Rect::new(0, 0, 20, 10)), (1, Rect::new(20, 0, 20, 10))],
+            "wide areas are split into columns"
+        );
+        assert_eq!(
+            layout(&[1, 1], Rect::new(0, 0, 10, 10)),
+            vec![(0, Rect::new(0, 0, 10, 5)), (1, Rect::new(0, 5, 10, 5))],
+            "high areas are split into rows"
+        );
+    }
+
+    #[test]
+    fn layout_leaves_out_empty_and_tiny_entries() {
+        let full = Rect::new(0, 0, 4, 1);
+        assert_eq!(area(&layout(&[1000, 1, 0], full)), vec![(0, 4)]);
+        assert!(layout(&[], full).is_empty());
+        assert!(layout(&[1], Rect::new(0, 0, 0, 0)).is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{anyhow, Error, Result};
+use std::io;
+
+/// Write `s` as JSON string literal, including the surrounding quotes.
+pub(crate) fn write_str(out: &mut impl io::Write, s: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    let mut unescaped_from = 0;
+    for (idx, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            c if c < ' ' => None,
+            _ => continue,
+        };
+        out.write_all(&s.as_bytes()[unescaped_from..idx])?;
+        match escaped {
+            Some(escaped) => out.write_all(escaped.as_bytes())?,
+            None => write!(out, "\\u{:04x}", c as u32)?,
+        }
+        unescaped_from = idx + c.len_utf8();
+    }
+    out.write_all(&s.as_bytes()[unescaped_from..])?;
+    out.write_all(b"\"")
+}
+
+/// A pull-parser for JSON documents held in memory, for callers who know the structure they expect.
+///
+/// All methods skip whitespace before the value they are looking at.
+pub(crate) struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Reader { input, pos: 0 }
+    }
+
+    pub fn error(&self, message: impl AsRef<str>) -> Error {
+        anyhow!("{} at byte offset {}", message.as_ref(), self.pos)
+    }
+
+    /// Return the next non-whitespace byte without consuming it.
+    pub fn peek(&mut self) -> Option<u8> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+        self.input.get(self.pos).copied()
+    }
+
+    /// Consume the next byte if it is `byte` and return true, or return false otherwise.
+    pub fn next_if(&mut self, byte: u8) -> bool {
+        let matches = self.peek() == Some(byte);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    pub fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.next_if(byte) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{}'", byte as char)))
+        }
+    }
+
+    /// Consume the ',' between items of an array or object, returning false if `close` ends it instead.
+    pub fn has_next_item(&mut self, close: u8) -> Result<bool> {
+        if self.next_if(close) {
+            Ok(false)
+        } else {
+            self.expect(b',').map(|_| true)
+        }
+    }
+
+    /// Parse a string and return its raw bytes, which aren't necessarily valid UTF-8.
+    pub fn string(&mut self) -> Result<Vec<u8>> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let byte = *self
+                .input
+                .get(self.pos)
+                .ok_or_else(|| self.error("Unterminated string"))?;
+            self.pos += 1;
+            match byte {
+                b'"' => return Ok(out),
+                b'\\' => {
+                    let escaped = *self
+                        .input
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        b'"' | b'\\' | b'/' => out.push(escaped),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'u' => {
+                            let mut c = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&c)
+                                && self.input[self.pos..].starts_with(b"\\u")
+                            {
+                                self.pos += 2;
+                                c = 0x10000 + ((c - 0xd800) << 10) + (self.hex4()? - 0xdc00);
+                            }
+                            let c = char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+                            out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        _ => return Err(self.error("Invalid escape sequence")),
+                    }
+                }
+                byte => out.push(byte),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    /// Parse a non-negative integer, like sizes and counts.
+    pub fn unsigned(&mut self) -> Result<u128> {
+        self.peek();
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| self.error("Expected an unsigned integer"))
+    }
+
+    /// Parse a boolean.
+    pub fn boolean(&mut self) -> Result<bool> {
+        self.peek();
+        for (literal, value) in [(&b"true"[..], true), (&b"false"[..], false)] {
+            if self.input[self.pos..].starts_with(literal) {
+                self.pos += literal.len();
+                return Ok(value);
+            }
+        }
+        Err(self.error("Expected a boolean"))
+    }
+
+    /// Skip over the next value, whatever it is.
+    ///
+    /// Arrays and objects still to close are kept on a stack of their own, so values of any depth can be skipped.
+    pub fn skip_value(&mut self) -> Result<()> {
+        // The bytes closing the arrays and objects the next value is in
+        let mut open = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.string()?;
+                }
+                Some(open_byte @ (b'[' | b'{')) => {
+                    self.pos += 1;
+                    let close = if open_byte == b'[' { b']' } else { b'}' };
+                    if !self.next_if(close) {
+                        open.push(close);
+                        if close == b'}' {
+                            self.string()?;
+                            self.expect(b':')?;
+                        }
+                        continue;
+                    }
+                }
+                Some(_) => {
+                    let start = self.pos;
+                    while let Some(b'-' | b'+' | b'.' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z') =
+                        self.input.get(self.pos)
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("Expected a value"));
+                    }
+                }
+                None => return Err(self.error("Unexpected end of input")),
+            }
+            // The value is skipped, and so are all arrays and objects it ends
+            loop {
+                let close = match open.last() {
+                    Some(close) => *close,
+                    None => return Ok(()),
+                };
+                if self.has_next_item(close)? {
+                    if close == b'}' {
+                        self.string()?;
+                        self.expect(b':')?;
+                    }
+                    break;
+                }
+                open.pop();
+            }
+        }
+    }
+
+    /// Fail unless only whitespace is left.
+    pub fn finish(&mut self) -> Result<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("Unexpected trailing characters")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_json(s: &str) -> String {
+        let mut buf = Vec::new();
+        write_str(&mut buf, s).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn it_escapes_quotes_backslashes_and_control_characters() {
+        assert_eq!(to_json("plain"), r#""plain""#);
+        assert_eq!(to_json(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(to_json("line\nbreak\ttab"), r#""line\nbreak\ttab""#);
+        assert_eq!(to_json("\u{1}bell\u{7}"), r#""\u0001bell\u0007""#);
+        assert_eq!(to_json("🙂 ünïcode"), "\"🙂 ünïcode\"");
+    }
+
+    #[test]
+    fn it_reads_what_it_expects_and_skips_the_rest() -> Result<()> {
+        let mut r = Reader::new(
+            br#" [ 1, {"skip": [true, null, -1.5e3, {"a": "]"}]}, "a\"\u00fc\ud83d\ude42\n", false ] "#,
+        );
+        r.expect(b'[')?;
+        assert_eq!(r.unsigned()?, 1);
+        assert!(r.has_next_item(b']')?);
+        r.skip_value()?;
+        assert!(r.has_next_item(b']')?);
+        assert_eq!(r.string()?, "a\"ü🙂\n".as_bytes());
+        assert!(r.has_next_item(b']')?);
+        assert!(!r.boolean()?);
+        assert!(!r.has_next_item(b']')?);
+        r.finish()?;
+
+        let mut r = Reader::new(b"[1 2]");
+        r.expect(b'[')?;
+        r.unsigned()?;
+        assert_eq!(
+            r.has_next_item(b']').unwrap_err().to_string(),
+            "Expected ',' at byte offset 3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn values_of_any_depth_are_skipped() -> Result<()> {
+        let depth = 100_000;
+        let nested = format!("{}1{},2", r#"[{"a":"#.repeat(depth), "}]".repeat(depth));
+        let mut r = Reader::new(nested.as_bytes());
+        r.skip_value()?;
+        assert!(r.next_if(b','));
+        assert_eq!(r.unsigned()?, 2);
+        r.finish()?;
+        Ok(())
+    }
+}
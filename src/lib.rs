@@ -1,15 +1,94 @@
+//! Learn about the disk usage of directories, fast.
+//!
+//! This is the library behind the `dua` command-line tool, to embed its scanner without running `dua` itself.
+//! [`WalkOptions`] configure a walk of one or more paths, which results in a [`Traversal`][traverse::Traversal] with
+//! the tree of all entries and their aggregated sizes, to be queried for the entries within a directory, their paths
+//! and the largest of them. [`ByteFormat`] displays sizes like `dua` does.
+//!
+//! ```
+//! use dua::{traverse::Traversal, ByteFormat, WalkOptions};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let traversal = WalkOptions::default().threads(1).apparent_size(true).traverse(&["src"])?;
+//! let src = traversal.find("src".as_ref()).expect("the input path");
+//! for idx in traversal.children_by_size(src).into_iter().take(3) {
+//!     let entry = &traversal.tree[idx];
+//!     println!("{} {}", ByteFormat::Metric.display(entry.size.into()), traversal.path_of(idx).display());
+//! }
+//!
+//! // Be called back regularly while walking, to show progress or to stop early by returning true
+//! let traversal = Traversal::from_walk(WalkOptions::default(), vec!["src".into()], |t| {
+//!     eprint!("Enumerating {} entries\r", t.entries_traversed);
+//!     Ok(false)
+//! })?;
+//! assert!(!traversal.is_incomplete);
+//! # Ok(())
+//! # }
+//! ```
 #![cfg_attr(windows, feature(windows_by_handle))]
 #![forbid(unsafe_code)]
 
 extern crate jwalk;
 
 mod aggregate;
+pub mod archive;
+mod broken_links;
+mod by_age;
+#[cfg(unix)]
+mod by_owner;
+mod by_type;
+mod clean;
 mod common;
 mod crossdev;
+mod csv;
+mod devices;
+mod diff;
+mod duplicates;
+mod empties;
+mod gitignore;
+mod glob;
+mod gzip;
 mod inodefilter;
+mod json;
+mod names;
+mod progress;
+mod prometheus;
+mod repos;
+mod sqlite;
+#[cfg(test)]
+mod testing;
+mod top_files;
+mod walk;
 
+pub mod docker;
+pub mod filesystem;
+pub mod ncdu;
+pub mod remote;
+pub mod report;
+pub mod s3;
+pub mod snapshot;
+pub mod sql;
 pub mod traverse;
 
-pub use aggregate::aggregate;
+pub use aggregate::{
+    aggregate, aggregate_json, aggregate_ndjson, aggregate_prometheus, aggregate_table,
+    AggregateOptions,
+};
+pub use broken_links::broken_links;
+pub use by_age::{by_age, AgeOf};
+#[cfg(unix)]
+pub use by_owner::{by_owner, names_by_id};
+pub use by_type::{by_type, file_type_of, Usage, NO_EXTENSION};
+pub use clean::{clean, find_junk, junk_kind, Deletion, Junk};
 pub use common::*;
+pub use devices::{device_containing, devices, mounted_devices, Device};
+pub use diff::{check, diff, grown_beyond, size_changes, watch, SizeChange};
+pub use docker::docker;
+pub use duplicates::{duplicates, find_duplicates, DuplicateSet};
+pub use empties::empties;
+pub use gitignore::{is_ignored_by_git, GitignoreMode};
+pub use glob::Glob;
 pub(crate) use inodefilter::InodeFilter;
+pub(crate) use progress::Progress;
+pub use repos::{find_repos, git_dir_of, repos, Repo, GIT_DIR};
+pub use top_files::top_files;
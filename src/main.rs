@@ -1,16 +1,29 @@
 #![forbid(unsafe_code)]
 use anyhow::Result;
 use clap::Clap;
-use dua::{ByteFormat, TraversalSorting};
-use std::{fs, io, io::Write, path::PathBuf, process};
+use dua::{ByteFormat, GitignoreMode, SymlinkMode, TraversalSorting};
+use std::{
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::Ordering,
+};
 
+mod completions;
+mod config;
 mod crossdev;
 #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
 mod interactive;
 mod options;
+#[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+mod path;
+#[cfg(all(test, any(feature = "tui-unix", feature = "tui-crossplatform")))]
+mod testing;
 
-fn stderr_if_tty() -> Option<io::Stderr> {
-    if atty::is(atty::Stream::Stderr) {
+/// Return standard error to show how far scans got on, unless `no_progress` is set or it isn't a terminal.
+fn progress_output(no_progress: bool) -> Option<io::Stderr> {
+    if !no_progress && atty::is(atty::Stream::Stderr) {
         Some(io::stderr())
     } else {
         None
@@ -56,62 +69,348 @@ fn main() -> Result<()> {
     use options::Command::*;
 
     let opt: options::Args = options::Args::parse_from(wild::args_os());
-    let threads = derive_default_threads(opt.threads);
+    stop_walks_on_interrupt()?;
+    let config = if opt.no_config {
+        config::Config::default()
+    } else {
+        config::load()?
+    };
+    let settings = config::Settings::from_config(&config).map_err(|err| {
+        anyhow::anyhow!(
+            "Invalid configuration in '{}': {}",
+            config::path().unwrap_or_default().display(),
+            err
+        )
+    })?;
+    if let Some(number_format) = opt.number_format.or(settings.number_format) {
+        dua::set_number_format(number_format);
+    }
+    let threads = derive_default_threads(opt.threads.or(settings.threads).unwrap_or(0));
     let walk_options = dua::WalkOptions {
         threads,
-        byte_format: opt.format.map(Into::into).unwrap_or(ByteFormat::Metric),
-        apparent_size: opt.apparent_size,
-        count_hard_links: opt.count_hard_links,
+        byte_format: match opt.count {
+            Some(options::Count::Inodes) => ByteFormat::Inodes,
+            _ => opt
+                .format
+                .or(settings.format)
+                .map(Into::into)
+                .unwrap_or(ByteFormat::Metric),
+        },
+        apparent_size: opt.apparent_size || settings.apparent_size,
+        count_hard_links: opt.count_hard_links || settings.count_hard_links,
         sorting: TraversalSorting::None,
-        cross_filesystems: !opt.stay_on_filesystem,
+        cross_filesystems: !(opt.stay_on_filesystem || settings.stay_on_filesystem),
+        exclude: exclude_patterns(
+            settings.exclude.into_iter().chain(opt.exclude).collect(),
+            opt.exclude_from,
+        )?,
+        gitignore: match (opt.respect_gitignore, opt.only_ignored) {
+            (true, _) => GitignoreMode::Respect,
+            (false, true) => GitignoreMode::OnlyIgnored,
+            (false, false) if settings.respect_gitignore => GitignoreMode::Respect,
+            (false, false) if settings.only_ignored => GitignoreMode::OnlyIgnored,
+            (false, false) => GitignoreMode::Disregard,
+        },
+        skip_cache_dirs: opt.skip_cache_dirs || settings.skip_cache_dirs,
+        symlinks: if opt.follow_symlinks || settings.follow_symlinks {
+            SymlinkMode::Always
+        } else if opt.no_dereference || (settings.no_dereference && !opt.dereference_args) {
+            SymlinkMode::Never
+        } else {
+            SymlinkMode::InputPaths
+        },
+        archives: false,
+        count_inodes: opt.count == Some(options::Count::Inodes),
+        max_entries: opt.max_entries,
+        include_virtual: opt.include_virtual || settings.include_virtual,
     };
+    let listed_paths = listed_paths(opt.files0_from, opt.files_from)?;
+    if listed_paths.is_some()
+        && matches!(
+            opt.command,
+            Some(Diff { .. })
+                | Some(Check { .. })
+                | Some(Export { .. })
+                | Some(Devices)
+                | Some(Docker { .. })
+                | Some(Completions { .. })
+        )
+    {
+        return Err(anyhow::anyhow!(
+            "'--files0-from' and '--files-from' can't be used with 'dua diff', 'dua check', 'dua export', 'dua devices', 'dua docker' and 'dua completions'"
+        ));
+    }
+    // Snapshots and exports keep sizes in bytes, and the largest files all have one inode
+    if walk_options.count_inodes
+        && matches!(
+            opt.command,
+            Some(Snapshot { .. })
+                | Some(Diff { .. })
+                | Some(Check { .. })
+                | Some(Export { .. })
+                | Some(TopFiles { .. })
+        )
+    {
+        return Err(anyhow::anyhow!(
+            "'--count inodes' can't be used with 'dua snapshot', 'dua diff', 'dua check', 'dua export' and 'dua top-files'"
+        ));
+    }
     let res = match opt.command {
         #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
-        Some(Interactive { input }) => {
-            use crate::interactive::{Interaction, TerminalApp};
+        Some(Interactive {
+            input,
+            load,
+            theme,
+            poll,
+            confirm_deletions_above,
+            resume,
+            cache,
+            refresh,
+            archives,
+            docker,
+        }) => {
+            use crate::interactive::{
+                disable_mouse_capture, enable_mouse_capture, restore_terminal, widgets::Theme,
+                Cache, Columns, Interaction, KeyMap, Preferences, SessionFiles, TerminalApp,
+            };
             use anyhow::{anyhow, Context};
             use crosstermion::terminal::{tui::new_terminal, AlternateRawScreen};
 
+            let walk_options = dua::WalkOptions {
+                archives,
+                ..walk_options
+            };
+            // Paths like 'user@host:/var' are walked on that host, and those like 's3://bucket' in object storage
+            let remote = match docker {
+                Some(root) => Some((
+                    Remote::Docker(root),
+                    dua::docker::DockerFilesystem::PATHS
+                        .iter()
+                        .map(PathBuf::from)
+                        .collect(),
+                )),
+                None => remote_paths(&input)?,
+            };
+            if walk_options.count_inodes && (load.is_some() || resume) {
+                return Err(anyhow!(
+                    "'--count inodes' can't be used with '--load' and '--resume', as saved scans have sizes in bytes"
+                ));
+            }
             let no_tty_msg = "Interactive mode requires a connected terminal";
             if atty::isnt(atty::Stream::Stderr) {
-                return Err(anyhow!(no_tty_msg));
+                if load.is_some() || remote.is_some() {
+                    return Err(anyhow!(no_tty_msg));
+                }
+                // Like when run by cron or with its output redirected, where the totals are all that can be shown
+                writeln!(
+                    io::stderr(),
+                    "{}, writing the aggregate instead",
+                    no_tty_msg
+                )
+                .ok();
+                let input = paths_from(input, listed_paths, &walk_options)?;
+                let (res, _) = dua::aggregate(
+                    io::stdout().lock(),
+                    None::<io::Stderr>,
+                    walk_options,
+                    dua::AggregateOptions {
+                        compute_total: true,
+                        sort_by_size_in_bytes: true,
+                        ..Default::default()
+                    },
+                    input,
+                )?;
+                process::exit(res.to_exit_code());
             }
+            let preferences = Preferences {
+                theme: match theme.or(settings.theme) {
+                    Some(name) => Theme::from_config(&name, &config)?,
+                    None if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) => {
+                        Theme::MONOCHROME
+                    }
+                    None => Theme::DARK,
+                },
+                sorting: settings
+                    .sort
+                    .map(|sort| sort.parse())
+                    .transpose()
+                    .map_err(|err| anyhow!("Invalid 'sort' in the configuration: {}", err))?
+                    .unwrap_or_default(),
+                keymap: KeyMap::from_config(&config)
+                    .context("Invalid key bindings in the configuration")?,
+                columns: settings
+                    .columns
+                    .map(|names| {
+                        Columns::from_names(&names.iter().map(String::as_str).collect::<Vec<_>>())
+                    })
+                    .transpose()
+                    .map_err(|err| anyhow!("Invalid 'columns' in the configuration: {}", err))?
+                    .unwrap_or_default(),
+                poll_changes: poll,
+                confirm_deletions_above: confirm_deletions_above
+                    .or(settings.confirm_deletions_above),
+            };
+            // Remote entries can't be deleted from here, just like those of a scan loaded from a file
+            let is_loaded = load.is_some() || remote.is_some();
+            let input = match (&load, &remote) {
+                (Some(_), _) => Vec::new(),
+                (None, Some((_, paths))) => paths.clone(),
+                (None, None) => paths_from(input, listed_paths, &walk_options)?,
+            };
+            // Scans loaded from a file might be of another machine, and their sessions aren't saved
+            let session_files = if is_loaded {
+                None
+            } else {
+                SessionFiles::of(&input)
+            };
+            let (session, resumed) = match session_files.as_ref().filter(|_| resume) {
+                Some(files) => (files.load()?, files.load_snapshot(&walk_options)?),
+                None => (None, None),
+            };
+            let loaded = load
+                .map(|path| {
+                    fs::read(&path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|content| {
+                            if dua::snapshot::is_snapshot(&content) {
+                                dua::snapshot::load(&content, &walk_options)
+                            } else {
+                                dua::ncdu::import(&content, &walk_options)
+                            }
+                        })
+                        .with_context(|| format!("Could not load scan from '{}'", path.display()))
+                })
+                .transpose()?
+                .or(resumed);
+            let cache = if (cache || refresh) && remote.is_none() {
+                Cache::of(&input, &walk_options)
+            } else {
+                None
+            };
+            let cached = match &cache {
+                Some(cache) if !refresh && loaded.is_none() => cache.load(&walk_options)?,
+                _ => None,
+            };
+            let cached_at = cached.as_ref().map(|(_, cached_at)| *cached_at);
+            let loaded = loaded.or(cached.map(|(traversal, _)| traversal));
 
+            // Panics abort without unwinding in release builds, so the terminal must be restored before the message
+            // is written to be seen at all.
+            let default_panic_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                restore_terminal();
+                default_panic_hook(info);
+            }));
             let mut terminal = new_terminal(
                 AlternateRawScreen::try_from(io::stderr()).with_context(|| no_tty_msg)?,
             )
             .with_context(|| "Could not instantiate terminal")?;
-            let res = TerminalApp::initialize(
-                &mut terminal,
-                walk_options,
-                paths_from(input, !opt.stay_on_filesystem)?,
-                Interaction::Full,
-            )?
-            .map(|(keys_rx, mut app)| {
-                let res = app.process_events(&mut terminal, keys_rx.into_iter());
-
-                let res = res.map(|r| {
-                    (
-                        r,
-                        app.window
-                            .mark_pane
-                            .take()
-                            .map(|marked| marked.into_paths()),
-                    )
-                });
-                // Leak app memory to avoid having to wait for the hashmap to deallocate,
-                // which causes a noticeable delay shortly before the the program exits anyway.
-                std::mem::forget(app);
-                res
+            enable_mouse_capture().with_context(|| "Could not capture the mouse")?;
+            let app = match loaded {
+                Some(traversal) => TerminalApp::initialize_from_traversal(
+                    &mut terminal,
+                    walk_options,
+                    preferences,
+                    traversal,
+                    Interaction::Full,
+                )
+                .map(Some),
+                None => match &remote {
+                    Some((remote, _)) => TerminalApp::initialize_from_filesystem(
+                        &mut terminal,
+                        walk_options.clone(),
+                        preferences,
+                        &mut *remote.filesystem(walk_options)?,
+                        input,
+                        Interaction::Full,
+                    ),
+                    None => TerminalApp::initialize(
+                        &mut terminal,
+                        walk_options,
+                        preferences,
+                        input,
+                        Interaction::Full,
+                    ),
+                },
+            };
+            // Errors are returned only after the terminal was restored, for them to be seen
+            let res = app.map(|app| {
+                app.map(|(events_rx, mut app)| {
+                    // The scan saved with the session is of this machine, and its entries can be deleted
+                    app.state.is_loaded = is_loaded;
+                    if remote.is_some() {
+                        app.state.is_remote = true;
+                        app.state.walk_options = None;
+                    }
+                    if cached_at.is_some() {
+                        app.state.cached_at = cached_at;
+                        app.state
+                            .look_for_stale_directories(&mut app.window, &mut app.traversal);
+                        app.refresh_view(&mut terminal);
+                    }
+                    if resume {
+                        match &session {
+                            Some(session) => match session.restore(&mut app) {
+                                0 => app.state.inform("Resumed the last session"),
+                                n => app.state.warn(format!(
+                                    "Resumed the last session, {} of its entr{} couldn't be found",
+                                    n,
+                                    if n == 1 { "y" } else { "ies" }
+                                )),
+                            },
+                            None => app
+                                .state
+                                .warn("There is no session of these input paths to resume"),
+                        }
+                        app.refresh_view(&mut terminal);
+                    }
+                    let res = app.process_events(&mut terminal, events_rx.into_iter());
+                    let session_saved = match &session_files {
+                        Some(files) if !app.state.scans_device => files.save(&app, resume),
+                        _ => Ok(()),
+                    };
+                    // A scan loaded from the cache is up to date with the time changes were last looked for, if any
+                    let cache_saved =
+                        match (&cache, app.state.changes_checked_at.or(app.state.cached_at)) {
+                            (Some(cache), Some(scanned_at))
+                                if !app.traversal.is_incomplete && !app.state.scans_device =>
+                            {
+                                cache.save(&app.traversal, scanned_at)
+                            }
+                            _ => Ok(()),
+                        };
+
+                    let res = res.map(|r| {
+                        (
+                            r,
+                            app.window
+                                .mark_pane
+                                .take()
+                                .map(|marked| marked.into_paths()),
+                            vec![session_saved, cache_saved]
+                                .into_iter()
+                                .filter_map(Result::err)
+                                .collect::<Vec<_>>(),
+                        )
+                    });
+                    // Leak app memory to avoid having to wait for the hashmap to deallocate,
+                    // which causes a noticeable delay shortly before the the program exits anyway.
+                    std::mem::forget(app);
+                    res
+                })
             });
 
+            disable_mouse_capture().ok();
             drop(terminal);
             io::stderr().flush().ok();
 
             // Exit 'quickly' to avoid having to not have to deal with slightly different types in the other match branches
             std::process::exit(
-                res.transpose()?
-                    .map(|(walk_result, paths)| {
+                res?.transpose()?
+                    .map(|(walk_result, paths, errors)| {
+                        for err in errors {
+                            writeln!(io::stderr(), "{:#}", err).ok();
+                        }
                         if let Some(paths) = paths {
                             for path in paths {
                                 println!("{}", path.display())
@@ -127,61 +426,594 @@ fn main() -> Result<()> {
             no_total,
             no_sort,
             statistics,
+            format,
+            max_depth,
+            threshold,
+            fail_if_larger_than,
+            fail_per_path,
         }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let byte_format = walk_options.byte_format;
             let stdout = io::stdout();
             let stdout_locked = stdout.lock();
-            let (res, stats) = dua::aggregate(
-                stdout_locked,
-                stderr_if_tty(),
-                walk_options,
-                !no_total,
-                !no_sort,
-                paths_from(input, !opt.stay_on_filesystem)?,
-            )?;
+            let aggregate_options = dua::AggregateOptions {
+                compute_total: !no_total,
+                sort_by_size_in_bytes: !no_sort,
+                max_depth,
+                threshold,
+            };
+            let (res, stats) = match format {
+                options::OutputFormat::Human => dua::aggregate(
+                    stdout_locked,
+                    progress_output(opt.no_progress),
+                    walk_options,
+                    aggregate_options,
+                    input,
+                )?,
+                options::OutputFormat::Json => dua::aggregate_json(
+                    stdout_locked,
+                    progress_output(opt.no_progress),
+                    walk_options,
+                    !no_sort,
+                    max_depth,
+                    input,
+                )?,
+                options::OutputFormat::Csv | options::OutputFormat::Tsv => dua::aggregate_table(
+                    stdout_locked,
+                    progress_output(opt.no_progress),
+                    walk_options,
+                    aggregate_options,
+                    if format == options::OutputFormat::Csv {
+                        b','
+                    } else {
+                        b'\t'
+                    },
+                    input,
+                )?,
+                options::OutputFormat::Ndjson => {
+                    dua::aggregate_ndjson(stdout_locked, walk_options, input)?
+                }
+                options::OutputFormat::Prometheus => dua::aggregate_prometheus(
+                    stdout_locked,
+                    progress_output(opt.no_progress),
+                    walk_options,
+                    aggregate_options,
+                    input,
+                )?,
+            };
             if statistics {
                 writeln!(io::stderr(), "{:?}", stats).ok();
             }
+            if let Some(bytes) = fail_if_larger_than {
+                let limit = dua::SizeLimit {
+                    bytes,
+                    per_path: fail_per_path,
+                };
+                let exceeding = limit.exceeded_by(&stats.path_sizes);
+                for (path, size) in &exceeding {
+                    writeln!(
+                        io::stderr(),
+                        "{} is larger than {}: {}",
+                        path.display(),
+                        byte_format.display(bytes),
+                        byte_format.display(*size)
+                    )
+                    .ok();
+                }
+                if !exceeding.is_empty() && !dua::INTERRUPTED.load(Ordering::Relaxed) {
+                    process::exit(1);
+                }
+            }
             res
         }
+        Some(TopFiles { input, num_files }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::top_files(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                num_files,
+                input,
+            )?
+        }
+        Some(ByAge {
+            input,
+            accessed,
+            stale_days,
+            num_files,
+        }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::by_age(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                if accessed {
+                    dua::AgeOf::Access
+                } else {
+                    dua::AgeOf::Modification
+                },
+                stale_days,
+                num_files,
+                input,
+            )?
+        }
+        Some(ByType { input, per_path }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::by_type(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                per_path,
+                input,
+            )?
+        }
+        #[cfg(unix)]
+        Some(ByOwner { input }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::by_owner(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                input,
+            )?
+        }
+        Some(Duplicates { input }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::duplicates(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                input,
+            )?
+        }
+        Some(Empty { input }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::empties(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                input,
+            )?
+        }
+        Some(BrokenLinks { input }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::broken_links(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                input,
+            )?
+        }
+        Some(Clean {
+            yes,
+            dry_run,
+            input,
+        }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let deletion = if yes {
+                dua::Deletion::All
+            } else if dry_run || atty::isnt(atty::Stream::Stdin) {
+                dua::Deletion::None
+            } else {
+                dua::Deletion::Ask
+            };
+            let stdout = io::stdout();
+            let stdin = io::stdin();
+            dua::clean(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                stdin.lock(),
+                walk_options,
+                deletion,
+                input,
+            )?
+        }
+        Some(Repos { input }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            let stdout = io::stdout();
+            dua::repos(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                input,
+            )?
+        }
+        Some(Snapshot { input, output }) => {
+            use anyhow::Context;
+
+            let input = match listed_paths {
+                Some(listed) => input.into_iter().chain(listed).collect(),
+                None if input.is_empty() => vec![PathBuf::from(".")],
+                None => input,
+            };
+            // Absolute paths can be scanned again by 'dua diff' from any directory
+            let input = input
+                .iter()
+                .map(|path| {
+                    fs::canonicalize(path)
+                        .with_context(|| format!("Could not find '{}'", path.display()))
+                })
+                .collect::<Result<_>>()?;
+            let traversal =
+                dua::traverse::Traversal::from_walk(walk_options, input, |_| Ok(false))?;
+            if traversal.is_incomplete {
+                return Err(anyhow::anyhow!("The scan was interrupted and is not saved"));
+            }
+            dua::snapshot::save(
+                io::BufWriter::new(
+                    fs::File::create(&output)
+                        .with_context(|| format!("Could not create '{}'", output.display()))?,
+                ),
+                &traversal,
+            )?;
+            dua::WalkResult {
+                num_errors: traversal.io_errors,
+            }
+        }
+        Some(Report { input, html }) => {
+            use anyhow::Context;
+
+            let input = match listed_paths {
+                Some(listed) => input.into_iter().chain(listed).collect(),
+                None if input.is_empty() => vec![PathBuf::from(".")],
+                None => input,
+            };
+            let traversal =
+                dua::traverse::Traversal::from_walk(walk_options, input, |_| Ok(false))?;
+            if traversal.is_incomplete {
+                return Err(anyhow::anyhow!(
+                    "The scan was interrupted and no report is written"
+                ));
+            }
+            dua::report::html(
+                io::BufWriter::new(
+                    fs::File::create(&html)
+                        .with_context(|| format!("Could not create '{}'", html.display()))?,
+                ),
+                &traversal,
+            )?;
+            dua::WalkResult {
+                num_errors: traversal.io_errors,
+            }
+        }
+        Some(Watch {
+            input,
+            interval,
+            num_entries,
+        }) => {
+            let input = paths_from(input, listed_paths, &walk_options)?;
+            dua::watch(
+                io::stdout().lock(),
+                walk_options,
+                input,
+                std::time::Duration::from_secs(interval),
+                num_entries,
+            )?
+        }
+        Some(Diff {
+            before,
+            after,
+            num_entries,
+        }) => {
+            use anyhow::Context;
+
+            let load = |path: &Path| {
+                fs::read(path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| dua::snapshot::load(&content, &walk_options))
+                    .with_context(|| format!("Could not load snapshot from '{}'", path.display()))
+            };
+            let before = load(&before)?;
+            let after = match after {
+                Some(path) => load(&path)?,
+                None => dua::traverse::Traversal::from_walk(
+                    walk_options.clone(),
+                    before.root_paths(),
+                    |_| Ok(false),
+                )?,
+            };
+            dua::diff(
+                io::stdout().lock(),
+                &walk_options,
+                &before,
+                &after,
+                num_entries,
+            )?;
+            dua::WalkResult {
+                num_errors: after.io_errors,
+            }
+        }
+        Some(Check {
+            baseline,
+            grow_threshold,
+        }) => {
+            use anyhow::Context;
+
+            let before = fs::read(&baseline)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| dua::snapshot::load(&content, &walk_options))
+                .with_context(|| {
+                    format!("Could not load snapshot from '{}'", baseline.display())
+                })?;
+            let after = dua::traverse::Traversal::from_walk(
+                walk_options.clone(),
+                before.root_paths(),
+                |_| Ok(false),
+            )?;
+            if after.is_incomplete {
+                process::exit(130);
+            }
+            let num_grown = dua::check(
+                io::stdout().lock(),
+                &walk_options,
+                &before,
+                &after,
+                grow_threshold,
+            )?;
+            if num_grown > 0 {
+                process::exit(1);
+            }
+            dua::WalkResult {
+                num_errors: after.io_errors,
+            }
+        }
+        Some(Export {
+            input,
+            output,
+            format,
+        }) => {
+            use anyhow::Context;
+
+            let output = output.filter(|path| path != Path::new("-"));
+            if format == options::ExportFormat::Sqlite && output.is_none() {
+                return Err(anyhow::anyhow!(
+                    "'--format sqlite' writes a database file, which needs to be given with '-o'"
+                ));
+            }
+            let input = input.unwrap_or_else(|| PathBuf::from("."));
+            // ncdu refers to the root by its absolute path, which also makes it possible to browse it from elsewhere
+            let input = fs::canonicalize(&input)
+                .with_context(|| format!("Could not find '{}'", input.display()))?;
+            let traversal =
+                dua::traverse::Traversal::from_walk(walk_options, vec![input], |_| Ok(false))?;
+            if traversal.is_incomplete {
+                return Err(anyhow::anyhow!(
+                    "The scan was interrupted and is not exported"
+                ));
+            }
+            let file = match output {
+                Some(path) => Some(io::BufWriter::new(
+                    fs::File::create(&path)
+                        .with_context(|| format!("Could not create '{}'", path.display()))?,
+                )),
+                None => None,
+            };
+            let or_stdout = |file: Option<io::BufWriter<fs::File>>| -> Box<dyn io::Write> {
+                match file {
+                    Some(file) => Box::new(file),
+                    None => Box::new(io::stdout().lock()),
+                }
+            };
+            match format {
+                options::ExportFormat::Ncdu => dua::ncdu::export(or_stdout(file), &traversal)?,
+                options::ExportFormat::Sql => dua::sql::export(or_stdout(file), &traversal)?,
+                options::ExportFormat::Sqlite => dua::sql::export_database(
+                    file.expect("a file to write to, as checked before scanning"),
+                    &traversal,
+                )?,
+            }
+            dua::WalkResult {
+                num_errors: traversal.io_errors,
+            }
+        }
+        Some(Devices) => {
+            dua::devices(io::stdout().lock(), walk_options.byte_format)?;
+            dua::WalkResult::default()
+        }
+        Some(Docker { root }) => {
+            let stdout = io::stdout();
+            dua::docker(
+                stdout.lock(),
+                progress_output(opt.no_progress),
+                walk_options,
+                &root,
+            )?
+        }
+        Some(Completions { shell }) => {
+            use clap::IntoApp;
+            completions::write(io::stdout().lock(), shell, &options::Args::into_app())?;
+            dua::WalkResult::default()
+        }
         None => {
+            let input = paths_from(opt.input, listed_paths, &walk_options)?;
             let stdout = io::stdout();
             let stdout_locked = stdout.lock();
             dua::aggregate(
                 stdout_locked,
-                stderr_if_tty(),
+                progress_output(opt.no_progress),
                 walk_options,
-                true,
-                true,
-                paths_from(opt.input, !opt.stay_on_filesystem)?,
+                dua::AggregateOptions {
+                    compute_total: true,
+                    sort_by_size_in_bytes: true,
+                    ..Default::default()
+                },
+                input,
             )?
             .0
         }
     };
 
+    if dua::INTERRUPTED.load(Ordering::Relaxed) {
+        process::exit(130);
+    }
     process::exit(res.to_exit_code());
 }
 
-fn paths_from(paths: Vec<PathBuf>, cross_filesystems: bool) -> Result<Vec<PathBuf>, io::Error> {
+/// Make walks stop with what they have seen so far on the first interrupt, like Ctrl+C, and exit on the second one.
+#[cfg(unix)]
+fn stop_walks_on_interrupt() -> io::Result<()> {
+    let signals = signal_hook::iterator::Signals::new([signal_hook::SIGINT])?;
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            if dua::INTERRUPTED.swap(true, Ordering::SeqCst) {
+                process::exit(130);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn stop_walks_on_interrupt() -> io::Result<()> {
+    Ok(())
+}
+
+fn exclude_patterns(
+    mut patterns: Vec<dua::Glob>,
+    exclude_from: Option<PathBuf>,
+) -> Result<Vec<dua::Glob>> {
+    use anyhow::{anyhow, Context};
+
+    if let Some(path) = exclude_from {
+        let content = fs::read_to_string(&path).with_context(|| {
+            format!("Could not read exclude patterns from '{}'", path.display())
+        })?;
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            patterns.push(
+                line.parse()
+                    .map_err(|err| anyhow!("{}:{}: {}", path.display(), line_number + 1, err))?,
+            );
+        }
+    }
+    Ok(patterns)
+}
+
+/// Read the input paths listed in the file given to `--files0-from` or `--files-from`, if there is one.
+fn listed_paths(
+    files0_from: Option<PathBuf>,
+    files_from: Option<PathBuf>,
+) -> Result<Option<Vec<PathBuf>>> {
+    use anyhow::Context;
+
+    let (path, separator) = match (files0_from, files_from) {
+        (Some(path), _) => (path, b'\0'),
+        (None, Some(path)) => (path, b'\n'),
+        (None, None) => return Ok(None),
+    };
+    let paths = if path == Path::new("-") {
+        dua::read_paths(io::stdin(), separator)
+    } else {
+        fs::File::open(&path).and_then(|file| dua::read_paths(file, separator))
+    };
+    paths
+        .map(Some)
+        .with_context(|| format!("Could not read input paths from '{}'", path.display()))
+}
+
+fn paths_from(
+    mut paths: Vec<PathBuf>,
+    listed_paths: Option<Vec<PathBuf>>,
+    walk_options: &dua::WalkOptions,
+) -> Result<Vec<PathBuf>, io::Error> {
+    if let Some(listed_paths) = listed_paths {
+        paths.extend(listed_paths);
+        return Ok(paths);
+    }
     let device_id = std::env::current_dir()
         .ok()
         .and_then(|cwd| crossdev::init(&cwd).ok());
 
     if paths.is_empty() {
-        cwd_dirlist().map(|paths| match device_id {
-            Some(device_id) if !cross_filesystems => paths
+        cwd_dirlist().map(|paths| {
+            paths
                 .into_iter()
-                .filter(|p| match p.metadata() {
-                    Ok(meta) => crossdev::is_same_device(device_id, &meta),
-                    Err(_) => true,
+                .filter(|p| match device_id {
+                    Some(device_id) if !walk_options.cross_filesystems => match p.metadata() {
+                        Ok(meta) => crossdev::is_same_device(device_id, &meta),
+                        Err(_) => true,
+                    },
+                    _ => true,
+                })
+                .filter(|p| !walk_options.exclude.iter().any(|glob| glob.matches_path(p)))
+                .filter(|p| match walk_options.gitignore {
+                    GitignoreMode::Disregard => true,
+                    GitignoreMode::Respect => !dua::is_ignored_by_git(p),
+                    GitignoreMode::OnlyIgnored => p.is_dir() || dua::is_ignored_by_git(p),
                 })
-                .collect(),
-            _ => paths,
+                .filter(|p| !(walk_options.skip_cache_dirs && p.is_dir() && dua::is_cache_dir(p)))
+                .collect()
         })
     } else {
         Ok(paths)
     }
 }
 
+/// Where input paths which aren't directories of this machine are walked.
+#[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+#[derive(PartialEq, Eq)]
+enum Remote {
+    /// The host to run 'dua' on over 'ssh', for paths like 'user@host:/var'
+    Ssh(String),
+    /// Buckets of object storage, for paths like 's3://bucket/prefix'
+    S3,
+    /// The images, containers and volumes of Docker with the given data directory
+    Docker(PathBuf),
+}
+
+#[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+impl Remote {
+    fn filesystem(
+        &self,
+        walk_options: dua::WalkOptions,
+    ) -> Result<Box<dyn dua::filesystem::Filesystem>> {
+        Ok(match self {
+            Remote::Ssh(host) => Box::new(dua::remote::RemoteFilesystem::new(host, walk_options)),
+            Remote::S3 => Box::new(dua::s3::S3Filesystem::new(walk_options)),
+            Remote::Docker(root) => Box::new(dua::docker::DockerFilesystem::new(
+                dua::docker::Docker::read(root)?,
+                walk_options,
+            )?),
+        })
+    }
+}
+
+/// Where `input` paths are along with the paths to walk there, or `None` if they are on this machine.
+/// Paths existing on this machine are always local, and all paths must be in the same place.
+#[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+fn remote_paths(input: &[PathBuf]) -> Result<Option<(Remote, Vec<PathBuf>)>> {
+    let mut remote: Option<(Remote, Vec<PathBuf>)> = None;
+    for (idx, path) in input.iter().enumerate() {
+        let split =
+            path.to_str()
+                .filter(|_| !path.exists())
+                .and_then(|input| match dua::s3::split_s3_url(input) {
+                    Some(_) => Some((Remote::S3, input)),
+                    None => dua::remote::split_remote_path(input)
+                        .map(|(host, path)| (Remote::Ssh(host.to_owned()), path)),
+                });
+        match (split, &mut remote) {
+            (Some((place, path)), None) if idx == 0 => remote = Some((place, vec![path.into()])),
+            (Some((place, path)), Some((remote_place, paths))) if place == *remote_place => {
+                paths.push(path.into())
+            }
+            (None, None) => {}
+            _ => anyhow::bail!("All input paths must be on the same machine"),
+        }
+    }
+    Ok(remote)
+}
+
 fn cwd_dirlist() -> Result<Vec<PathBuf>, io::Error> {
     let mut v: Vec<_> = fs::read_dir(".")?
         .filter_map(|e| {
@@ -0,0 +1,163 @@
+//! The names of the entries of a tree, each kept once however many entries have it, as most names are repeated
+//! throughout a tree, like `index.js` or `mod.rs`.
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    convert::TryFrom,
+    ffi::OsStr,
+    fmt,
+    hash::BuildHasher,
+    ops::Index,
+    path::Path,
+};
+
+/// The name of an entry, as an index into the [`Names`] of its tree.
+///
+/// Within a tree, entries have the same name if and only if they have the same `Name`. The default is the empty name.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Name(u32);
+
+/// The names of all entries of a tree, each kept once.
+///
+/// Names are stored one after the other in a single string, so a name takes about 16 bytes more than its length, and
+/// entries having it again take none. Names which aren't valid unicode are rare, and are kept on their own.
+#[derive(Clone)]
+pub struct Names {
+    /// All names which are valid unicode, one after the other
+    text: String,
+    /// Where each name ends in `text`
+    ends: Vec<usize>,
+    /// The names which aren't valid unicode, which are empty in `text`
+    others: HashMap<Name, Box<Path>>,
+    /// All names by their hash, probed linearly. Free slots are 0, and others are one more than the name.
+    slots: Vec<u32>,
+    hasher: RandomState,
+}
+
+impl Default for Names {
+    fn default() -> Self {
+        let mut names = Names {
+            text: String::new(),
+            ends: Vec::new(),
+            others: HashMap::new(),
+            slots: vec![0; 16],
+            hasher: RandomState::new(),
+        };
+        names.intern(Path::new(""));
+        names
+    }
+}
+
+impl Names {
+    /// Return the name of `name`, adding it if no entry had it so far.
+    pub fn intern(&mut self, name: &Path) -> Name {
+        let slot = match self.slot_of(name.as_os_str()) {
+            Ok(interned) => return interned,
+            Err(slot) => slot,
+        };
+        let interned = Name(u32::try_from(self.ends.len()).expect("fewer than 4 billion names"));
+        match name.to_str() {
+            Some(text) => self.text.push_str(text),
+            None => {
+                self.others.insert(interned, name.into());
+            }
+        }
+        self.ends.push(self.text.len());
+        self.slots[slot] = interned.0 + 1;
+        if self.ends.len() * 2 > self.slots.len() {
+            self.grow();
+        }
+        interned
+    }
+
+    /// Return the name of `name` if an entry has it.
+    pub fn get(&self, name: &Path) -> Option<Name> {
+        self.slot_of(name.as_os_str()).ok()
+    }
+
+    fn text_of(&self, name: Name) -> &str {
+        let idx = name.0 as usize;
+        let start = if idx == 0 { 0 } else { self.ends[idx - 1] };
+        &self.text[start..self.ends[idx]]
+    }
+
+    /// Find `name`, or the free slot to add it in.
+    fn slot_of(&self, name: &OsStr) -> Result<Name, usize> {
+        let mask = self.slots.len() - 1;
+        let mut slot = self.hasher.hash_one(name) as usize & mask;
+        loop {
+            match self.slots[slot] {
+                0 => return Err(slot),
+                interned if self[Name(interned - 1)].as_os_str() == name => {
+                    return Ok(Name(interned - 1))
+                }
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+    }
+
+    /// Double the amount of slots, to keep at least half of them free.
+    fn grow(&mut self) {
+        self.slots = vec![0; self.slots.len() * 2];
+        for idx in 0..self.ends.len() {
+            let interned = Name(idx as u32);
+            let slot = self
+                .slot_of(self[interned].as_os_str())
+                .expect_err("names to be unique");
+            self.slots[slot] = interned.0 + 1;
+        }
+    }
+}
+
+impl Index<Name> for Names {
+    type Output = Path;
+
+    fn index(&self, name: Name) -> &Path {
+        match self.text_of(name) {
+            "" => self.others.get(&name).map_or(Path::new(""), |other| other),
+            text => Path::new(text),
+        }
+    }
+}
+
+impl fmt::Debug for Names {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries((0..self.ends.len()).map(|idx| &self[Name(idx as u32)]))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_kept_once_and_found_again() {
+        let mut names = Names::default();
+        assert_eq!(names.get(Path::new("")), Some(Name::default()));
+        assert_eq!(names.get(Path::new("a")), None);
+
+        let interned: Vec<_> = (0..1000)
+            .map(|idx| names.intern(Path::new(&format!("name-{}", idx % 100))))
+            .collect();
+        assert_eq!(interned[0], interned[100], "repeated names are the same");
+        assert_ne!(interned[0], interned[1]);
+        assert_eq!(&names[interned[42]], Path::new("name-42"));
+        assert_eq!(names.get(Path::new("name-99")), Some(interned[99]));
+        assert_eq!(names.text.len(), "name-0".len() * 10 + "name-10".len() * 90);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_which_are_not_unicode_are_kept_as_they_are() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut names = Names::default();
+        let name = Path::new(OsStr::from_bytes(b"not-\xff-unicode"));
+        let interned = names.intern(name);
+        assert_eq!(&names[interned], name);
+        assert_eq!(names.get(name), Some(interned));
+        assert_eq!(names.intern(name), interned);
+        assert_ne!(names.intern(Path::new("")), interned);
+    }
+}
@@ -0,0 +1,468 @@
+//! Reading and writing the [JSON export format of ncdu](https://dev.yorhel.nl/ncdu/jsonfmt).
+//!
+//! This allows to browse scans created with `ncdu -o`, and to look at scans done by `dua export` with `ncdu -f`.
+use crate::{
+    get_entry_or_panic, json,
+    traverse::{EntryData, Mtime, Totals, Traversal, Tree, TreeIndex},
+    WalkOptions,
+};
+use anyhow::{bail, Result};
+use petgraph::Direction;
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAJOR_VERSION: u128 = 1;
+const MINOR_VERSION: u128 = 2;
+
+/// Write `traversal` to `out` in the ncdu export format.
+///
+/// The format holds exactly one root, which is why traversals of multiple input paths can't be exported.
+/// Directories are written without a size of their own, as these aren't recorded during traversal.
+pub fn export(mut out: impl io::Write, traversal: &Traversal) -> Result<()> {
+    let roots = children_in_order(&traversal.tree, traversal.root_index);
+    let root = match roots.as_slice() {
+        [root] => *root,
+        _ => bail!(
+            "ncdu exports contain a single root, but the traversal has {}",
+            roots.len()
+        ),
+    };
+    write!(
+        out,
+        "[{},{},{{\"progname\":\"dua\",\"progver\":\"{}\",\"timestamp\":{}}},",
+        MAJOR_VERSION,
+        MINOR_VERSION,
+        env!("CARGO_PKG_VERSION"),
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    )?;
+    write_entries(&mut out, traversal, root)?;
+    out.write_all(b"]\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn children_in_order(tree: &Tree, node_idx: TreeIndex) -> Vec<TreeIndex> {
+    let mut children: Vec<_> = tree
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .collect();
+    // Neighbors are yielded in reverse order of insertion
+    children.reverse();
+    children
+}
+
+/// Write the entry at `node_idx` and all entries below it.
+///
+/// Entries still to write are kept on a stack of their own, along with the ends of the directories containing them,
+/// so trees of any depth can be written.
+fn write_entries(
+    out: &mut impl io::Write,
+    traversal: &Traversal,
+    node_idx: TreeIndex,
+) -> Result<()> {
+    // `None` ends the directory whose children were written last
+    let mut stack = vec![Some(node_idx)];
+    while let Some(next) = stack.pop() {
+        let idx = match next {
+            Some(idx) => idx,
+            None => {
+                out.write_all(b"]")?;
+                continue;
+            }
+        };
+        let entry = get_entry_or_panic(&traversal.tree, idx);
+        if idx != node_idx {
+            out.write_all(b",")?;
+        }
+        if entry.is_dir {
+            out.write_all(b"[")?;
+        }
+        out.write_all(b"{\"name\":")?;
+        json::write_str(out, &traversal.tree.names[entry.name].to_string_lossy())?;
+        if !entry.is_dir {
+            let (asize, dsize) = if traversal.apparent_size {
+                (entry.size, entry.alternate_size)
+            } else {
+                (entry.alternate_size, entry.size)
+            };
+            write!(out, ",\"asize\":{},\"dsize\":{}", asize, dsize)?;
+            if let Some(mtime) = entry.mtime {
+                write!(out, ",\"mtime\":{}", mtime.secs())?;
+            }
+        }
+        if entry.metadata_io_error {
+            out.write_all(b",\"read_error\":true")?;
+        }
+        out.write_all(b"}")?;
+        if entry.is_dir {
+            stack.push(None);
+            stack.extend(
+                children_in_order(&traversal.tree, idx)
+                    .into_iter()
+                    .rev()
+                    .map(Some),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Create a traversal from `input` in the ncdu export format, as if it was obtained by walking the filesystem with
+/// `walk_options`.
+///
+/// Just like during a traversal, the sizes of directories themselves are ignored, along with entries ncdu excluded.
+pub fn import(input: &[u8], walk_options: &WalkOptions) -> Result<Traversal> {
+    let mut r = json::Reader::new(input);
+    r.expect(b'[')?;
+    let major_version = r.unsigned()?;
+    if major_version != MAJOR_VERSION {
+        return Err(r.error(format!("Unsupported ncdu export version {}", major_version)));
+    }
+    r.expect(b',')?;
+    r.unsigned()?; // minor versions are backwards compatible
+    r.expect(b',')?;
+    r.skip_value()?; // metadata
+    r.expect(b',')?;
+
+    let mut tree = Tree::new();
+    let root_index = tree.add_node(EntryData::default());
+    let mut importer = Importer {
+        tree,
+        apparent_size: walk_options.apparent_size,
+        count_hard_links: walk_options.count_hard_links,
+        inodes: Default::default(),
+        entries_traversed: 0,
+        io_errors: 0,
+    };
+    let root_size = importer.read_entries(&mut r, root_index)?;
+    while r.has_next_item(b']')? {
+        r.skip_value()?;
+    }
+    r.finish()?;
+
+    let Importer {
+        mut tree,
+        entries_traversed,
+        io_errors,
+        ..
+    } = importer;
+    let root_size = root_size.unwrap_or_default();
+    let root = tree
+        .node_weight_mut(root_index)
+        .expect("root node we just added");
+    root.size = root_size.size;
+    root.alternate_size = root_size.alternate_size;
+    root.entry_count = root_size.entry_count;
+    root.mtime = root_size.mtime;
+    Ok(Traversal {
+        tree,
+        root_index,
+        entries_traversed,
+        io_errors,
+        errors: Vec::new(),
+        total_bytes: Some(root_size.size.into()),
+        apparent_size: walk_options.apparent_size,
+        is_incomplete: false,
+        collapsed_entries: 0,
+    })
+}
+
+#[derive(Default)]
+struct Info {
+    name: Option<PathBuf>,
+    asize: u64,
+    dsize: u64,
+    dev: Option<u128>,
+    ino: Option<u128>,
+    mtime: Option<Mtime>,
+    is_hard_link: bool,
+    read_error: bool,
+    excluded: bool,
+}
+
+struct Importer {
+    tree: Tree,
+    apparent_size: bool,
+    count_hard_links: bool,
+    inodes: HashSet<(u128, u128)>,
+    entries_traversed: u64,
+    io_errors: u64,
+}
+
+/// What was read of an entry
+enum Read {
+    /// The entry was excluded by ncdu, and isn't part of the tree
+    Excluded,
+    /// A file, with its sizes as seen by its parent
+    File(Totals),
+    /// A directory whose children are still to read
+    Dir(OpenDir),
+}
+
+/// A directory whose children are being read
+struct OpenDir {
+    node_idx: TreeIndex,
+    dev: u128,
+    sizes: Totals,
+}
+
+impl Importer {
+    /// Read a file or directory and all entries within it and add it to `parent_idx`, returning its sizes as seen by
+    /// the parent, or `None` if it was excluded.
+    ///
+    /// Directories still being read are kept on a stack of their own, so trees of any depth can be read.
+    fn read_entries(
+        &mut self,
+        r: &mut json::Reader,
+        parent_idx: TreeIndex,
+    ) -> Result<Option<Totals>> {
+        let mut open: Vec<OpenDir> = Vec::new();
+        loop {
+            let (parent_idx, parent_dev) = open
+                .last()
+                .map_or((parent_idx, 0), |dir| (dir.node_idx, dir.dev));
+            let mut finished = match self.read_entry(r, parent_idx, parent_dev)? {
+                Read::Excluded => None,
+                Read::File(sizes) => Some(sizes),
+                Read::Dir(dir) => {
+                    open.push(dir);
+                    None
+                }
+            };
+            loop {
+                if let Some(sizes) = finished.take() {
+                    match open.last_mut() {
+                        Some(parent) => parent.sizes += sizes,
+                        None => return Ok(Some(sizes)),
+                    }
+                }
+                if open.is_empty() {
+                    return Ok(None);
+                }
+                if r.has_next_item(b']')? {
+                    break;
+                }
+                let dir = open.pop().expect("an open directory");
+                finished = Some(self.set_sizes(dir.node_idx, dir.sizes));
+            }
+        }
+    }
+
+    /// Read a file or directory and add it to `parent_idx`, but none of the entries within it.
+    fn read_entry(
+        &mut self,
+        r: &mut json::Reader,
+        parent_idx: TreeIndex,
+        parent_dev: u128,
+    ) -> Result<Read> {
+        let is_dir = r.next_if(b'[');
+        let info = read_info(r)?;
+        let name = info.name.ok_or_else(|| r.error("Entry without \"name\""))?;
+        if info.excluded {
+            if is_dir {
+                while r.has_next_item(b']')? {
+                    r.skip_value()?;
+                }
+            }
+            return Ok(Read::Excluded);
+        }
+
+        self.entries_traversed += 1;
+        if info.read_error {
+            self.io_errors += 1;
+        }
+        let dev = info.dev.unwrap_or(parent_dev);
+        let name = self.tree.names.intern(&name);
+        let node_idx = self.tree.add_node(EntryData {
+            name,
+            is_dir,
+            metadata_io_error: info.read_error,
+            ..Default::default()
+        });
+        self.tree.add_edge(parent_idx, node_idx, ());
+
+        if is_dir {
+            return Ok(Read::Dir(OpenDir {
+                node_idx,
+                dev,
+                sizes: Totals::default(),
+            }));
+        }
+        let is_counted = match (info.is_hard_link, info.ino) {
+            (true, Some(ino)) => self.count_hard_links || self.inodes.insert((dev, ino)),
+            _ => true,
+        };
+        let mut sizes = Totals {
+            mtime: info.mtime,
+            ..Default::default()
+        };
+        if is_counted {
+            (sizes.size, sizes.alternate_size) = if self.apparent_size {
+                (info.asize, info.dsize)
+            } else {
+                (info.dsize, info.asize)
+            };
+        }
+        Ok(Read::File(self.set_sizes(node_idx, sizes)))
+    }
+
+    /// Set the sizes of the entry at `node_idx` to `sizes`, and return them as seen by its parent.
+    fn set_sizes(&mut self, node_idx: TreeIndex, mut sizes: Totals) -> Totals {
+        let entry = self
+            .tree
+            .node_weight_mut(node_idx)
+            .expect("node we just added");
+        entry.size = sizes.size;
+        entry.alternate_size = sizes.alternate_size;
+        entry.entry_count = sizes.entry_count;
+        entry.mtime = sizes.mtime;
+        sizes.entry_count += 1; // the entry itself, as seen by its parent
+        sizes
+    }
+}
+
+fn read_info(r: &mut json::Reader) -> Result<Info> {
+    let mut info = Info::default();
+    r.expect(b'{')?;
+    if r.next_if(b'}') {
+        return Ok(info);
+    }
+    loop {
+        let key = r.string()?;
+        r.expect(b':')?;
+        match key.as_slice() {
+            b"name" => info.name = Some(path_from_bytes(r.string()?)),
+            b"asize" | b"dsize" => {
+                let size = u64::try_from(r.unsigned()?).map_err(|_| r.error("Size too large"))?;
+                if key == b"asize" {
+                    info.asize = size;
+                } else {
+                    info.dsize = size;
+                }
+            }
+            b"dev" => info.dev = Some(r.unsigned()?),
+            b"ino" => info.ino = Some(r.unsigned()?),
+            b"hlnkc" => info.is_hard_link = r.boolean()?,
+            b"mtime" => info.mtime = u64::try_from(r.unsigned()?).ok().map(Mtime::from_secs),
+            b"read_error" => info.read_error = r.boolean()?,
+            b"excluded" => {
+                r.skip_value()?;
+                info.excluded = true;
+            }
+            _ => r.skip_value()?,
+        }
+        if !r.has_next_item(b'}')? {
+            return Ok(info);
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    std::ffi::OsString::from_vec(bytes).into()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCAN: &str = r#"[1,2,{"progname":"ncdu","progver":"1.15.1","timestamp":1623200000},[{"name":"/scan","asize":4096,"dsize":4096,"dev":42},{"name":"a","asize":256,"dsize":4096,"mtime":1623200000},[{"name":"sub","asize":4096,"dsize":4096},{"name":"link-1","asize":1000,"dsize":4096,"ino":7,"hlnkc":true},{"name":"link-2","asize":1000,"dsize":4096,"ino":7,"hlnkc":true}],{"name":"node_modules","excluded":"pattern"},[{"name":"locked","read_error":true}]]]"#;
+
+    fn options(apparent_size: bool, count_hard_links: bool) -> WalkOptions {
+        WalkOptions::default()
+            .threads(1)
+            .count_hard_links(count_hard_links)
+            .apparent_size(apparent_size)
+    }
+
+    fn root_of(t: &Traversal) -> &EntryData {
+        let root = t
+            .tree
+            .neighbors_directed(t.root_index, Direction::Outgoing)
+            .next()
+            .unwrap();
+        get_entry_or_panic(&t.tree, root)
+    }
+
+    #[test]
+    fn it_imports_entries_with_sizes_of_files_and_errors() -> Result<()> {
+        let t = import(SCAN.as_bytes(), &options(true, false))?;
+        assert_eq!(
+            t.tree.node_count(),
+            7,
+            "the virtual root, but not excluded entries"
+        );
+        assert_eq!(t.entries_traversed, 6);
+        assert_eq!(t.io_errors, 1);
+        assert_eq!(t.total_bytes, Some(256 + 1000));
+        let root = root_of(&t);
+        assert_eq!(&t.tree.names[root.name], std::path::Path::new("/scan"));
+        assert!(root.is_dir);
+        assert_eq!(root.entry_count, 5);
+        assert_eq!(root.alternate_size, 4096 * 2);
+        assert_eq!(
+            root.mtime,
+            Some(Mtime::from_secs(1623200000)),
+            "directories are as new as their newest entry"
+        );
+
+        let t = import(SCAN.as_bytes(), &options(false, true))?;
+        assert_eq!(
+            t.total_bytes,
+            Some(4096 * 3),
+            "disk usage, hard links counted"
+        );
+        assert!(!t.apparent_size);
+        Ok(())
+    }
+
+    #[test]
+    fn it_exports_what_it_imported() -> Result<()> {
+        let t = import(SCAN.as_bytes(), &options(false, true))?;
+        let mut out = Vec::new();
+        export(&mut out, &t)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.starts_with(r#"[1,2,{"progname":"dua","progver":""#));
+        assert!(out.ends_with(
+            r#"},[{"name":"/scan"},{"name":"a","asize":256,"dsize":4096,"mtime":1623200000},[{"name":"sub"},{"name":"link-1","asize":1000,"dsize":4096},{"name":"link-2","asize":1000,"dsize":4096}],[{"name":"locked","read_error":true}]]]
+"#
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_invalid_documents() {
+        for (input, message) in [
+            (
+                "[2,0,{},[]]",
+                "Unsupported ncdu export version 2 at byte offset 2",
+            ),
+            ("[1,0,{},[{}]]", "Entry without \"name\" at byte offset 11"),
+            (
+                "[1,0,{},{\"name\":\"a\"}] x",
+                "Unexpected trailing characters at byte offset 22",
+            ),
+        ] {
+            assert_eq!(
+                import(input.as_bytes(), &options(true, false))
+                    .map(|_| ())
+                    .unwrap_err()
+                    .to_string(),
+                message
+            );
+        }
+    }
+}
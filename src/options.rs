@@ -8,10 +8,22 @@ pub enum ByteFormat {
     Metric,
     Binary,
     Bytes,
+    Raw,
     GB,
     GiB,
     MB,
     MiB,
+    KB,
+    KiB,
+    TB,
+    TiB,
+}
+
+/// Parse a size like `100`, `10K`, `1.5MB` or `2GiB` into its amount of bytes.
+pub fn parse_size(s: &str) -> Result<u128, String> {
+    byte_unit::Byte::from_str(s)
+        .map(|size| size.get_bytes())
+        .map_err(|err| format!("Invalid size {:?}: {}", s, err))
 }
 
 impl FromStr for ByteFormat {
@@ -22,18 +34,24 @@ impl FromStr for ByteFormat {
             "metric" | "Metric" => ByteFormat::Metric,
             "binary" | "Binary" => ByteFormat::Binary,
             "bytes" | "Bytes" => ByteFormat::Bytes,
+            "raw" | "Raw" => ByteFormat::Raw,
             "GB" | "Gb" | "gb" => ByteFormat::GB,
             "GiB" | "gib" => ByteFormat::GiB,
             "MB" | "Mb" | "mb" => ByteFormat::MB,
             "MiB" | "mib" => ByteFormat::MiB,
+            "KB" | "kB" | "Kb" | "kb" => ByteFormat::KB,
+            "KiB" | "kib" => ByteFormat::KiB,
+            "TB" | "Tb" | "tb" => ByteFormat::TB,
+            "TiB" | "tib" => ByteFormat::TiB,
             _ => return Err(format!("Invalid byte format: {:?}", s)),
         })
     }
 }
 
 impl ByteFormat {
-    const VARIANTS: &'static [&'static str] =
-        &["metric", "binary", "bytes", "MB", "MiB", "GB", "GiB"];
+    const VARIANTS: &'static [&'static str] = &[
+        "metric", "binary", "bytes", "raw", "KB", "KiB", "MB", "MiB", "GB", "GiB", "TB", "TiB",
+    ];
 }
 
 impl From<ByteFormat> for LibraryByteFormat {
@@ -42,14 +60,126 @@ impl From<ByteFormat> for LibraryByteFormat {
             ByteFormat::Metric => LibraryByteFormat::Metric,
             ByteFormat::Binary => LibraryByteFormat::Binary,
             ByteFormat::Bytes => LibraryByteFormat::Bytes,
+            ByteFormat::Raw => LibraryByteFormat::Raw,
             ByteFormat::GB => LibraryByteFormat::GB,
             ByteFormat::GiB => LibraryByteFormat::GiB,
             ByteFormat::MB => LibraryByteFormat::MB,
             ByteFormat::MiB => LibraryByteFormat::MiB,
+            ByteFormat::KB => LibraryByteFormat::KB,
+            ByteFormat::KiB => LibraryByteFormat::KiB,
+            ByteFormat::TB => LibraryByteFormat::TB,
+            ByteFormat::TiB => LibraryByteFormat::TiB,
         }
     }
 }
 
+/// The way aggregate results are written to standard output
+#[derive(PartialEq, Debug)]
+pub enum OutputFormat {
+    Human,
+    Json,
+    Csv,
+    Tsv,
+    Ndjson,
+    Prometheus,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "human" | "Human" => OutputFormat::Human,
+            "json" | "Json" | "JSON" => OutputFormat::Json,
+            "csv" | "Csv" | "CSV" => OutputFormat::Csv,
+            "tsv" | "Tsv" | "TSV" => OutputFormat::Tsv,
+            "ndjson" | "Ndjson" | "NDJSON" => OutputFormat::Ndjson,
+            "prometheus" | "Prometheus" => OutputFormat::Prometheus,
+            _ => return Err(format!("Invalid output format: {:?}", s)),
+        })
+    }
+}
+
+impl OutputFormat {
+    const VARIANTS: &'static [&'static str] =
+        &["human", "json", "csv", "tsv", "ndjson", "prometheus"];
+}
+
+/// What sizes are made of
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Count {
+    Bytes,
+    Inodes,
+}
+
+impl FromStr for Count {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "bytes" | "Bytes" => Count::Bytes,
+            "inodes" | "Inodes" => Count::Inodes,
+            _ => return Err(format!("Invalid count: {:?}", s)),
+        })
+    }
+}
+
+impl Count {
+    const VARIANTS: &'static [&'static str] = &["bytes", "inodes"];
+}
+
+/// The format `dua export` writes scans in
+#[derive(PartialEq, Debug)]
+pub enum ExportFormat {
+    Ncdu,
+    Sql,
+    Sqlite,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ncdu" | "Ncdu" => ExportFormat::Ncdu,
+            "sql" | "Sql" | "SQL" => ExportFormat::Sql,
+            "sqlite" | "Sqlite" | "SQLite" => ExportFormat::Sqlite,
+            _ => return Err(format!("Invalid export format: {:?}", s)),
+        })
+    }
+}
+
+impl ExportFormat {
+    const VARIANTS: &'static [&'static str] = &["ncdu", "sql", "sqlite"];
+}
+
+/// The shells `dua completions` writes completion scripts for
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "bash" | "Bash" => Shell::Bash,
+            "zsh" | "Zsh" => Shell::Zsh,
+            "fish" | "Fish" => Shell::Fish,
+            "powershell" | "PowerShell" => Shell::Powershell,
+            _ => return Err(format!("Invalid shell: {:?}", s)),
+        })
+    }
+}
+
+impl Shell {
+    const VARIANTS: &'static [&'static str] = &["bash", "zsh", "fish", "powershell"];
+}
+
 #[derive(Debug, Clap)]
 #[clap(name = "dua", about = "A tool to learn about disk usage, fast!", version = clap::crate_version!())]
 #[clap(setting = clap::AppSettings::ColoredHelp)]
@@ -61,25 +191,54 @@ pub struct Args {
 
     /// The amount of threads to use. Defaults to 0, indicating the amount of logical processors.
     /// Set to 1 to use only a single thread.
-    #[clap(short = 't', long = "threads", default_value = "0")]
-    pub threads: usize,
+    #[clap(short = 't', long = "threads")]
+    pub threads: Option<usize>,
+
+    /// Don't read settings from the configuration file, which is '~/.config/dua/config.toml' by default.
+    /// All settings given on the command-line are used instead of those in the configuration file.
+    #[clap(long)]
+    pub no_config: bool,
 
     /// The format with which to print byte counts.
     /// Metric - uses 1000 as base (default)
     /// Binary - uses 1024 as base
     /// Bytes - plain bytes without any formatting
-    /// GB - only gigabytes
-    /// GiB - only gibibytes
-    /// MB - only megabytes
-    /// MiB - only mebibytes
+    /// Raw - the exact amount of bytes without a unit, for scripts
+    /// KB, MB, GB, TB - only kilobytes, megabytes, gigabytes or terabytes, with two decimals
+    /// KiB, MiB, GiB, TiB - only kibibytes, mebibytes, gibibytes or tebibytes, with two decimals
+    /// Sizes in a single unit line up and can be compared down a list at a glance.
     #[clap(short = 'f', long, case_insensitive = true, possible_values(&ByteFormat::VARIANTS))]
     pub format: Option<ByteFormat>,
 
+    /// How numbers are shown in sizes, amounts of entries and percentages.
+    /// plain - digits without separators, like 1234.5 (default)
+    /// locale - like numbers of the locale in LC_ALL, LC_NUMERIC or LANG
+    /// A decimal separator, '.' or ',', optionally preceded by a thousands separator, one of ',', '.', '_', an
+    /// apostrophe or a space, like ',.' for 1,234.5 or '.,' for 1.234,5.
+    /// Raw byte counts for scripts are never separated.
+    #[clap(long)]
+    pub number_format: Option<dua::NumberFormat>,
+
     /// Display apparent size instead of disk usage.
     #[clap(short = 'A', long)]
     pub apparent_size: bool,
 
-    /// Count hard-linked files each time they are seen
+    /// What sizes are made of.
+    /// Bytes - the space entries take (default)
+    /// Inodes - one for each file and directory, to see where inodes are used up when the filesystem is full but
+    /// 'df' shows free space. Sizes, sorting, percentages and totals are all amounts of inodes then.
+    #[clap(long, case_insensitive = true, possible_values(&Count::VARIANTS))]
+    pub count: Option<Count>,
+
+    /// Keep no more than about this amount of entries in memory when building a tree of them, like in interactive
+    /// mode. Whenever there are more, the smallest files of each directory are collapsed into one entry standing for
+    /// all of them, which keeps scanning huge trees from running out of memory. Sizes and totals stay exact.
+    #[clap(long)]
+    pub max_entries: Option<usize>,
+
+    /// Count hard-linked files each time they are seen.
+    /// Without it, they are counted once per device and inode. Copies sharing their blocks without being hard links,
+    /// like clones on APFS, are always counted in full.
     #[clap(short = 'l', long)]
     pub count_hard_links: bool,
 
@@ -87,6 +246,71 @@ pub struct Args {
     #[clap(short = 'x', long)]
     pub stay_on_filesystem: bool,
 
+    /// Descend into virtual filesystems like /proc, /sys, /dev and /run, as listed in /proc/mounts.
+    /// Without it, their mount points are shown as empty directories, as their files have sizes which don't take any
+    /// space and would make totals meaningless.
+    #[clap(long)]
+    pub include_virtual: bool,
+
+    /// Skip entries matching the given glob pattern, along with all of their children. Can be given multiple times.
+    /// Patterns without a '/' match file names, like '.cache' or '*.log', all others match the whole path,
+    /// like '*/node_modules'. These are used in addition to the patterns in the configuration file.
+    #[clap(
+        short = 'X',
+        long = "exclude",
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    pub exclude: Vec<dua::Glob>,
+
+    /// Read exclude patterns from the given file, one per line. Empty lines and lines starting with '#' are ignored.
+    #[clap(long, parse(from_os_str))]
+    pub exclude_from: Option<PathBuf>,
+
+    /// Skip entries ignored by the '.gitignore' files in the scanned directories and their parents, up to the root
+    /// of the git repository, along with all of their children. '.git/info/exclude' is read as well.
+    #[clap(long, conflicts_with = "only-ignored")]
+    pub respect_gitignore: bool,
+
+    /// Only count entries ignored by '.gitignore' files, like build artifacts, to see what can be deleted without
+    /// losing anything under version control. Directories are kept, as they may contain ignored entries.
+    #[clap(long)]
+    pub only_ignored: bool,
+
+    /// Skip directories tagged as cache by a 'CACHEDIR.TAG' file, see https://bford.info/cachedir.
+    /// Without it, they are highlighted in interactive mode instead.
+    #[clap(long)]
+    pub skip_cache_dirs: bool,
+
+    /// Follow all symbolic links, counting what they point to instead of the links themselves.
+    /// Directories reached by more than one link are counted once, and links looping back to their parents are not
+    /// followed.
+    #[clap(short = 'L', long)]
+    pub follow_symlinks: bool,
+
+    /// Follow input paths which are symbolic links, but no links within them.
+    /// This is the default, and only overrides 'no_dereference' in the configuration file.
+    #[clap(short = 'D', long)]
+    pub dereference_args: bool,
+
+    /// Count input paths which are symbolic links as links instead of following them, just like 'du' does by default.
+    #[clap(short = 'P', long, conflicts_with_all = &["follow-symlinks", "dereference-args"])]
+    pub no_dereference: bool,
+
+    /// Don't keep a line telling how far a scan got on standard error, which is only done if it's a terminal.
+    #[clap(long)]
+    pub no_progress: bool,
+
+    /// Read input paths from the given file, or from standard input if it's '-', each followed by a NUL character
+    /// like the output of 'find -print0'. They are scanned in addition to the input paths on the command-line, and
+    /// the current working directory isn't scanned if there are none.
+    #[clap(long, parse(from_os_str), conflicts_with = "files-from")]
+    pub files0_from: Option<PathBuf>,
+
+    /// Like '--files0-from', but with one input path per line.
+    #[clap(long, parse(from_os_str))]
+    pub files_from: Option<PathBuf>,
+
     /// One or more input files or directories. If unset, we will use all entries in the current working directory.
     #[clap(parse(from_os_str))]
     pub input: Vec<PathBuf>,
@@ -98,7 +322,56 @@ pub enum Command {
     #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
     #[clap(name = "interactive", visible_alias = "i")]
     Interactive {
+        /// Browse a scan previously written by 'dua snapshot', 'dua export' or 'ncdu -o' instead of scanning the input
+        /// paths.
+        /// Entries can't be deleted in this mode.
+        #[clap(long, parse(from_os_str), conflicts_with = "input")]
+        load: Option<PathBuf>,
+        /// The colors to use, one of 'dark', 'light' and 'monochrome', or the name of a theme defined in a
+        /// '[themes.<name>]' table of the configuration file. Defaults to 'monochrome' if the NO_COLOR environment
+        /// variable is set, and to 'dark' otherwise.
+        #[clap(long)]
+        theme: Option<String>,
+        /// If set, directories are polled for changes every two seconds once scanning is done, and those with
+        /// entries created or removed in them are scanned again.
+        /// Only the time of last modification of directories is looked at, without subscribing to filesystem events,
+        /// so files whose content changed aren't noticed. Only the directory that is shown and those within it are
+        /// checked, up to 1000 of them.
+        #[clap(long, conflicts_with = "load")]
+        poll: bool,
+        /// Deleting entries larger than the given size, like '10GB', needs the amount of entries they contain to be
+        /// typed to confirm it, and they can't be deleted from the list of marked entries.
+        #[clap(long, parse(try_from_str = parse_size))]
+        confirm_deletions_above: Option<u128>,
+        /// Show the directory, selected entry, sorting and marked entries of the last time the same input paths were
+        /// browsed, which are saved when quitting.
+        /// The scan is saved along with them when quitting after resuming, to be browsed again the next time instead
+        /// of scanning the input paths.
+        #[clap(long, conflicts_with = "load")]
+        resume: bool,
+        /// Show the scan cached the last time the same input paths were browsed with this flag instead of scanning them,
+        /// and look for directories changed since then, which can be rescanned with 'R'. Caches older than a week
+        /// aren't used.
+        /// The scan is cached when quitting if it's complete.
+        #[clap(long, conflicts_with = "load")]
+        cache: bool,
+        /// Scan the input paths even if there is a cached scan of them, and cache the new scan when quitting.
+        #[clap(long, conflicts_with = "load")]
+        refresh: bool,
+        /// Look into '.tar', '.tar.gz', '.tgz' and '.zip' files, and show what's in them with its uncompressed size.
+        /// Entries in archives are shown dimmed and can't be deleted, and they don't add to the size of the archive,
+        /// which is still the space it takes.
+        #[clap(long, conflicts_with = "load")]
+        archives: bool,
+        /// Browse the images, containers and volumes of Docker instead of the input paths, by reading its data directory,
+        /// like '/var/lib/docker'. Layers are shown within each image using them, and are only scanned in the first
+        /// one. Their entries can be browsed, but not deleted.
+        #[clap(long, parse(from_os_str), conflicts_with_all = &["load", "input"])]
+        docker: Option<PathBuf>,
         /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        /// Paths like 'user@host:/var' are scanned on that machine by running 'dua' there over 'ssh', which has to log
+        /// in without asking for a password. Paths like 's3://bucket/prefix' list objects of S3 or compatible storage
+        /// with the 'aws' tool, as configured for it. Their entries can be browsed, but not deleted.
         #[clap(parse(from_os_str))]
         input: Vec<PathBuf>,
     },
@@ -115,8 +388,230 @@ pub enum Command {
         /// If set, no total column will be computed for multiple inputs
         #[clap(long)]
         no_total: bool,
+        /// The format in which to write the results.
+        /// Human - one colored line per input path with its size (default)
+        /// Json - a JSON document with the tree of all scanned entries with their size, entry count and errors
+        /// Csv - a header followed by one row per line of the human format, with the path, size in bytes, entry count
+        /// and amount of errors, separated by commas
+        /// Tsv - like Csv, but separated by tabs
+        /// Ndjson - one JSON object per scanned entry with its path, size, depth and type, written as soon as its size
+        /// is known
+        /// Prometheus - gauges with the size, entry count and amount of errors of each input path, and the directories
+        /// within down to '--max-depth', for the textfile collector of the node exporter
+        #[clap(long, case_insensitive = true, default_value = "human", possible_values(&OutputFormat::VARIANTS))]
+        format: OutputFormat,
+        /// Also list the directories within each input down to the given depth, with their full path, like `du`.
+        /// Inputs are at depth 0. With the JSON format, entries below that depth are left out instead.
+        #[clap(short = 'd', long)]
+        max_depth: Option<usize>,
+        /// Only list entries at least as large as the given size, like '10M' or '1GiB'. If it's negative, only list
+        /// entries at most as large as its value, like `du` does. The total always includes all entries.
+        #[clap(long, allow_hyphen_values = true)]
+        threshold: Option<dua::Threshold>,
+        /// Exit with 1 if the total of all input paths is larger than the given size, like '10GB', and write it to
+        /// stderr, to be used as check in CI and monitoring scripts. The output is written as usual.
+        #[clap(long, parse(try_from_str = parse_size))]
+        fail_if_larger_than: Option<u128>,
+        /// Compare each input path with '--fail-if-larger-than' instead of their total, and write all that are larger.
+        #[clap(long, requires = "fail-if-larger-than")]
+        fail_per_path: bool,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List the largest files within one or more directories, the largest first, with their size, time of last
+    /// modification and path
+    #[clap(name = "top-files")]
+    TopFiles {
+        /// The amount of files to list
+        #[clap(short = 'n', long = "num-files", default_value = "50")]
+        num_files: usize,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Sum up the consumed space by the age of files, and list the largest files which weren't touched in a while,
+    /// to find candidates for archival
+    #[clap(name = "by-age")]
+    ByAge {
+        /// Use the time files were last accessed instead of the time they were last modified
+        #[clap(long)]
+        accessed: bool,
+        /// List files which weren't touched in the given amount of days
+        #[clap(long, default_value = "365")]
+        stale_days: u64,
+        /// The amount of files to list
+        #[clap(short = 'n', long = "num-files", default_value = "20")]
+        num_files: usize,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Sum up the consumed space by type of file, which is its extension, the largest first, to see what kind of
+    /// data fills a disk
+    #[clap(name = "by-type")]
+    ByType {
+        /// Write a table for each input path, instead of one for all of them
+        #[clap(long)]
+        per_path: bool,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Sum up the consumed space by the user and the group owning files, the largest first
+    #[cfg(unix)]
+    #[clap(name = "by-owner")]
+    ByOwner {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List sets of files with the same content within one or more directories, along with the space that could be
+    /// freed by keeping only one file of each set
+    #[clap(name = "duplicates")]
+    Duplicates {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List empty directories and files without content within one or more directories, to clean them up.
+    /// Press 'v' in interactive mode until only empty entries are shown to browse them.
+    #[clap(name = "empty")]
+    Empty {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List symbolic links whose target doesn't exist within one or more directories, like those left behind by
+    /// uninstalled programs. Press 'v' in interactive mode until only broken links are shown to browse them.
+    #[clap(name = "broken-links")]
+    BrokenLinks {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Find directories whose contents are disposable, like cargo's 'target', 'node_modules', '__pycache__',
+    /// '.gradle/caches', browser caches and directories tagged as cache, list them with the space they take and offer
+    /// to delete them one by one.
+    #[clap(name = "clean")]
+    Clean {
+        /// Delete all of them without asking
+        #[clap(short = 'y', long, conflicts_with = "dry-run")]
+        yes: bool,
+        /// Only list them. This is also done if there is no terminal to ask on.
+        #[clap(short = 'n', long)]
+        dry_run: bool,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// List the git repositories within one or more directories, like a home directory, with the space taken by their
+    /// '.git' directory and by their working tree, followed by the space taken by all '.git' directories together.
+    #[clap(name = "repos")]
+    Repos {
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Scan one or more directories and save the result as compact binary snapshot, to compare it with a later scan
+    /// with 'dua diff' or to browse it with 'dua interactive --load'
+    #[clap(name = "snapshot")]
+    Snapshot {
+        /// The file to write the snapshot to
+        #[clap(short = 'o', long, parse(from_os_str))]
+        output: PathBuf,
+        /// One or more input files or directories. If unset, we will use the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Scan one or more directories and write a report to share with others, like a single HTML page with a treemap
+    /// to zoom into directories
+    #[clap(name = "report")]
+    Report {
+        /// The file to write the HTML report to
+        #[clap(long, parse(from_os_str))]
+        html: PathBuf,
+        /// One or more input files or directories. If unset, we will use the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Vec<PathBuf>,
+    },
+    /// Show which directories grew or shrank the most between two snapshots, or between a snapshot and a fresh scan
+    /// of the paths it contains
+    #[clap(name = "diff")]
+    Diff {
+        /// The amount of directories to list
+        #[clap(short = 'n', long = "num-entries", default_value = "50")]
+        num_entries: usize,
+        /// The snapshot to compare with
+        #[clap(parse(from_os_str))]
+        before: PathBuf,
+        /// The snapshot with the newer state. If unset, the paths of the first snapshot are scanned again.
+        #[clap(parse(from_os_str))]
+        after: Option<PathBuf>,
+    },
+    /// Scan the paths of a snapshot again and list the directories which grew by more than a threshold since, exiting
+    /// with 1 if there are any. Nothing is written otherwise, to raise an alarm about growing disk usage when run
+    /// from cron or a systemd timer.
+    #[clap(name = "check")]
+    Check {
+        /// The snapshot written by 'dua snapshot' to compare with
+        #[clap(long, parse(from_os_str))]
+        baseline: PathBuf,
+        /// The amount of bytes a directory may grow by before it's listed, like '5GiB'. Directories which only grew
+        /// that much because a directory within them did aren't listed.
+        #[clap(long, parse(try_from_str = parse_size))]
+        grow_threshold: u128,
+    },
+    /// Write a script completing the subcommands, options and their values of dua in the given shell to stdout, like
+    /// 'dua completions bash > /etc/bash_completion.d/dua'
+    #[clap(name = "completions")]
+    Completions {
+        #[clap(case_insensitive = true, possible_values(&Shell::VARIANTS))]
+        shell: Shell,
+    },
+    /// Scan one or more directories again and again, and list the directories whose size changed the most since the
+    /// previous scan after each one, to find what is filling a disk right now. Stop it with Ctrl+C.
+    #[clap(name = "watch")]
+    Watch {
+        /// The amount of seconds from the start of one scan to the next
+        #[clap(short = 'i', long, default_value = "60")]
+        interval: u64,
+        /// The amount of directories to list after each scan
+        #[clap(short = 'n', long = "num-entries", default_value = "10")]
+        num_entries: usize,
         /// One or more input files or directories. If unset, we will use all entries in the current working directory.
         #[clap(parse(from_os_str))]
         input: Vec<PathBuf>,
     },
+    /// Scan a directory and write the result in the JSON export format of ncdu, to be viewed with
+    /// 'dua interactive --load' or 'ncdu -f', or as SQLite database to analyse with queries
+    #[clap(name = "export")]
+    Export {
+        /// The file to write the scan to, or '-' for standard output, which is also the default.
+        #[clap(short = 'o', long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// The format in which to write the scan.
+        /// Ncdu - the JSON export format of ncdu (default)
+        /// Sqlite - a SQLite database with the tables 'entries' and 'errors', written to the file given with '-o'.
+        /// Entries have an id, parent_id, name, path, type, disk_usage, apparent_size, entry_count, mtime and error.
+        /// Sql - a script of SQL statements creating and filling the same tables, to be run with 'sqlite3 scan.db'.
+        #[clap(long, case_insensitive = true, default_value = "ncdu", possible_values(&ExportFormat::VARIANTS))]
+        format: ExportFormat,
+        /// The directory to scan. If unset, we will use the current working directory.
+        #[clap(parse(from_os_str))]
+        input: Option<PathBuf>,
+    },
+    /// List the mounted filesystems with their size, used and free space, as reported by 'df'.
+    /// Press 'V' in interactive mode to pick one of them to scan.
+    #[clap(name = "devices")]
+    Devices,
+    /// List the images, containers and volumes of Docker with the space they take. Images are listed with the size of
+    /// all of their layers, and of the layers no other image uses, which is what removing them frees.
+    /// Only the 'overlay2' storage driver is supported, and reading its data directory usually requires root.
+    #[clap(name = "docker")]
+    Docker {
+        /// The data directory of Docker
+        #[clap(long, parse(from_os_str), default_value = dua::docker::DOCKER_ROOT)]
+        root: PathBuf,
+    },
 }
@@ -0,0 +1,33 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Make `path` absolute without resolving the entry it points to, as that could be a symlink which is meant itself.
+/// Only its parent is resolved, or `path` as a whole if it has no name, like `..`.
+pub fn absolute(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            parent.canonicalize().map(|parent| parent.join(name))
+        }
+        (_, Some(name)) => std::env::current_dir().map(|cwd| cwd.join(name)),
+        _ => path.canonicalize(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_made_absolute_without_resolving_them() -> io::Result<()> {
+        let cwd = std::env::current_dir()?;
+        assert_eq!(absolute(Path::new("a"))?, cwd.join("a"));
+        assert_eq!(
+            absolute(Path::new("tests/fixtures/sample-01/c.lnk"))?,
+            cwd.canonicalize()?.join("tests/fixtures/sample-01/c.lnk")
+        );
+        assert_eq!(absolute(Path::new("."))?, cwd.canonicalize()?);
+        Ok(())
+    }
+}
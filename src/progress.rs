@@ -0,0 +1,133 @@
+use crate::ByteFormat;
+use std::{
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// The longest line that is drawn, as lines wrapping on narrow terminals couldn't be cleared anymore.
+const MAX_WIDTH: usize = 79;
+/// How long to wait before drawing the first line, so quick walks finish without any.
+const DELAY: Duration = Duration::from_secs(1);
+/// How long to wait before drawing the line again.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// A line on a terminal telling how far a walk got, with the amount of entries and bytes seen so far and the
+/// directory being walked. Walks which don't count bytes leave them out.
+///
+/// It's meant to be updated for each entry, and is redrawn in place at most every [`INTERVAL`]. Clear it before
+/// writing anything else to the same terminal.
+pub(crate) struct Progress<W: io::Write> {
+    out: W,
+    byte_format: ByteFormat,
+    started: Instant,
+    drawn: Option<Instant>,
+    /// The amount of characters of the line drawn last, or 0 if there is none
+    width: usize,
+}
+
+impl<W: io::Write> Progress<W> {
+    pub fn new(out: W, byte_format: ByteFormat) -> Self {
+        Progress {
+            out,
+            byte_format,
+            started: Instant::now(),
+            drawn: None,
+            width: 0,
+        }
+    }
+
+    /// Redraw the line with the given amount of `entries` and `bytes` and the directory being walked, `dir`, unless
+    /// it was drawn just before.
+    pub fn update(&mut self, entries: u64, bytes: Option<u128>, dir: &Path) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.started);
+        if elapsed < DELAY
+            || self
+                .drawn
+                .is_some_and(|drawn| now.duration_since(drawn) < INTERVAL)
+        {
+            return;
+        }
+        self.drawn = Some(now);
+
+        let mut line = format!(
+            "{} entries ({}/s)",
+            entries,
+            (entries as f64 / elapsed.as_secs_f64()) as u64
+        );
+        if let Some(bytes) = bytes {
+            line.push_str(&format!(", {}", self.byte_format.display(bytes)));
+        }
+        line.push_str(" in ");
+        let dir = dir.to_string_lossy();
+        let available = MAX_WIDTH.saturating_sub(line.chars().count());
+        let dir_width = dir.chars().count();
+        if dir_width > available {
+            // The end of the path is what changes the most
+            line.push('…');
+            line.extend(dir.chars().skip(dir_width - available.saturating_sub(1)));
+        } else {
+            line.push_str(&dir);
+        }
+
+        self.clear();
+        write!(self.out, "{}\r", line).ok();
+        self.out.flush().ok();
+        self.width = line.chars().count();
+    }
+
+    /// Remove the line, if it was drawn.
+    pub fn clear(&mut self) {
+        if self.width > 0 {
+            write!(self.out, "{:1$}\r", "", self.width).ok();
+            self.out.flush().ok();
+            self.width = 0;
+        }
+    }
+}
+
+impl<W: io::Write> Drop for Progress<W> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_drawn_after_a_delay_at_most_once_per_interval() {
+        let mut out = Vec::new();
+        let mut progress = Progress::new(&mut out, ByteFormat::Bytes);
+        progress.update(10, Some(100), Path::new("a"));
+        assert_eq!(progress.width, 0, "nothing is drawn right away");
+
+        progress.started -= DELAY * 2;
+        progress.update(10, Some(100), Path::new("a"));
+        progress.update(20, None, Path::new("b"));
+        let first_width = progress.width;
+        progress.drawn = progress.drawn.map(|drawn| drawn - INTERVAL);
+        progress.update(30, Some(300), &Path::new("c").join("d".repeat(100)));
+        assert_eq!(progress.width, MAX_WIDTH, "long paths are shortened");
+        drop(progress);
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.split('\r').collect();
+        assert!(lines[0].starts_with("10 entries ("));
+        assert!(lines[0].ends_with("/s), 100 b in a"));
+        assert_eq!(
+            lines[1],
+            " ".repeat(first_width),
+            "lines are cleared before drawing the next one"
+        );
+        assert!(
+            lines[2].starts_with("30 entries (")
+                && lines[2].contains(" in …dd")
+                && lines[2].ends_with("dd")
+        );
+        assert_eq!(lines[3], " ".repeat(MAX_WIDTH), "dropping clears the line");
+        assert_eq!(lines.len(), 5);
+    }
+}
@@ -0,0 +1,35 @@
+use std::io;
+
+/// Write `value` to `out` as the value of a label in the text exposition format of Prometheus, surrounded by quotes
+/// and with backslashes, quotes and line feeds escaped.
+pub(crate) fn write_label_value(out: &mut impl io::Write, value: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in value.chars() {
+        match c {
+            '\\' => out.write_all(b"\\\\")?,
+            '"' => out.write_all(b"\\\"")?,
+            '\n' => out.write_all(b"\\n")?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label_value(s: &str) -> String {
+        let mut out = Vec::new();
+        write_label_value(&mut out, s).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn label_values_are_quoted_and_escaped() {
+        assert_eq!(label_value("dir/a b"), r#""dir/a b""#);
+        assert_eq!(label_value(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(label_value(r"C:\dir"), r#""C:\\dir""#);
+        assert_eq!(label_value("two\nlines"), r#""two\nlines""#);
+    }
+}
@@ -0,0 +1,525 @@
+//! Walk directories of other machines over `ssh`, by running `dua aggregate --format ndjson` on them.
+use crate::{
+    filesystem::{EntrySize, Filesystem, FsEntry, Walk},
+    json,
+    ncdu::path_from_bytes,
+    traverse::TraversalError,
+    GitignoreMode, Glob, SymlinkMode, WalkOptions,
+};
+use anyhow::Result;
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdout, Command, Stdio},
+    thread::JoinHandle,
+};
+
+/// Split `input` like `user@host:/var` into the host to log into and the path on it, like `scp` does.
+///
+/// It's a remote path if it has a `:` before the first `/`, after a host of more than one character, which leaves
+/// Windows drives like `C:\` alone. Without a path after the `:`, the home directory is meant.
+pub fn split_remote_path(input: &str) -> Option<(&str, &str)> {
+    let (host, path) = input.split_once(':')?;
+    if host.chars().count() < 2 || host.contains(['/', '\\']) {
+        return None;
+    }
+    Some((host, if path.is_empty() { "." } else { path }))
+}
+
+/// The directories of another machine, walked by running `dua` on it through `ssh`.
+///
+/// `dua` has to be installed there, and `ssh` must be able to log in without asking for a password, like with keys
+/// loaded into an agent. Sizes are those seen by the remote `dua`, which has no modification times to tell.
+pub struct RemoteFilesystem {
+    host: String,
+    walk_options: WalkOptions,
+}
+
+impl RemoteFilesystem {
+    /// Walk paths on `host`, which may be prefixed with the user to log in as, with `walk_options`.
+    pub fn new(host: impl Into<String>, walk_options: WalkOptions) -> Self {
+        RemoteFilesystem {
+            host: host.into(),
+            walk_options,
+        }
+    }
+
+    /// The command to run on the remote host to walk `path`, as `ssh` passes it to the shell there.
+    fn remote_command(&self, path: &Path) -> String {
+        let o = &self.walk_options;
+        let mut args = vec!["dua", "--no-config", "--no-progress"];
+        if o.apparent_size {
+            args.push("--apparent-size");
+        }
+        if o.count_hard_links {
+            args.push("--count-hard-links");
+        }
+        if o.count_inodes {
+            args.extend(["--count", "inodes"]);
+        }
+        if !o.cross_filesystems {
+            args.push("--stay-on-filesystem");
+        }
+        if o.include_virtual {
+            args.push("--include-virtual");
+        }
+        if o.skip_cache_dirs {
+            args.push("--skip-cache-dirs");
+        }
+        match o.gitignore {
+            GitignoreMode::Disregard => {}
+            GitignoreMode::Respect => args.push("--respect-gitignore"),
+            GitignoreMode::OnlyIgnored => args.push("--only-ignored"),
+        }
+        match o.symlinks {
+            SymlinkMode::Never => args.push("--no-dereference"),
+            SymlinkMode::InputPaths => args.push("--dereference-args"),
+            SymlinkMode::Always => args.push("--follow-symlinks"),
+        }
+        args.extend(["aggregate", "--format", "ndjson", "--"]);
+        args.into_iter()
+            .map(ToOwned::to_owned)
+            .chain(std::iter::once(quoted(&path.to_string_lossy())))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Quote `s` for a POSIX shell, to be passed as a single argument as is.
+fn quoted(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+impl Filesystem for RemoteFilesystem {
+    fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+        let name = PathBuf::from(format!("{}:{}", self.host, path.display()));
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes", "--", &self.host])
+            .arg(self.remote_command(path));
+        let (stdout, mut ssh) = Process::spawn(ssh, name.clone())?;
+        Ok(Box::new(
+            NdjsonWalk::new(
+                BufReader::new(stdout),
+                path.to_owned(),
+                name,
+                self.walk_options.exclude.clone(),
+            )
+            .chain(std::iter::once_with(move || ssh.finish()).flatten()),
+        ))
+    }
+}
+
+/// A running program whose output is read, which is stopped if it's dropped before it's done.
+pub(crate) struct Process {
+    child: Child,
+    program: String,
+    stderr: Option<JoinHandle<String>>,
+    /// The name of the walked path, for errors to be about
+    name: PathBuf,
+}
+
+impl Process {
+    /// Run `command` to walk the path named `name`, and return what it writes to standard output.
+    pub(crate) fn spawn(mut command: Command, name: PathBuf) -> io::Result<(ChildStdout, Process)> {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                io::Error::new(err.kind(), format!("Could not run '{}': {}", program, err))
+            })?;
+        let stdout = child.stdout.take().expect("piped stdout");
+        // Read on a thread of its own, as the program blocks once the pipe is full
+        let mut stderr = child.stderr.take().expect("piped stderr");
+        let stderr = std::thread::spawn(move || {
+            let mut message = String::new();
+            stderr.read_to_string(&mut message).ok();
+            message
+        });
+        Ok((
+            stdout,
+            Process {
+                child,
+                program,
+                stderr: Some(stderr),
+                name,
+            },
+        ))
+    }
+
+    /// Wait for the program to be done, and turn it failing into an error.
+    pub(crate) fn finish(&mut self) -> Option<Result<FsEntry, TraversalError>> {
+        let status = self.child.wait();
+        let message = self
+            .stderr
+            .take()
+            .and_then(|stderr| stderr.join().ok())
+            .unwrap_or_default();
+        match status {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(Err(TraversalError {
+                path: self.name.clone(),
+                kind: io::ErrorKind::Other,
+                message: match message.trim() {
+                    "" => format!("'{}' failed with {}", self.program, status),
+                    message => message.to_owned(),
+                },
+            })),
+            Err(err) => Some(Err(TraversalError::from_io_error(&err, self.name.clone()))),
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if self.stderr.is_some() {
+            self.child.kill().ok();
+            self.child.wait().ok();
+        }
+    }
+}
+
+/// Turns entries listed by their path into a walk of `root`, for listings without directories of their own or with
+/// directories after their entries.
+///
+/// All entries within a directory must be listed right after one another, so the directories leading to an entry
+/// are yielded when it is listed, and those which are listed on their own are only yielded if they weren't already.
+pub(crate) struct Listing {
+    /// The listed path, which paths of entries are relative to
+    root: PathBuf,
+    /// The name of the root entry, and what the paths of errors are relative to
+    name: PathBuf,
+    exclude: Vec<Glob>,
+    /// The names of the directories leading to the last entry, which were all yielded already
+    open: Vec<OsString>,
+    pending: VecDeque<Result<FsEntry, TraversalError>>,
+    is_root_yielded: bool,
+}
+
+impl Listing {
+    pub(crate) fn new(root: PathBuf, name: PathBuf, exclude: Vec<Glob>) -> Self {
+        Listing {
+            root,
+            name,
+            exclude,
+            open: Vec::new(),
+            pending: VecDeque::new(),
+            is_root_yielded: false,
+        }
+    }
+
+    /// An entry without sizes at `depth`
+    pub(crate) fn entry(depth: usize, name: PathBuf, is_dir: bool) -> FsEntry {
+        FsEntry {
+            depth,
+            name,
+            is_dir,
+            is_symlink: false,
+            is_broken_link: false,
+            is_cache_dir_tag: false,
+            is_archive: false,
+            mtime: None,
+            size: None,
+            metadata_error: None,
+        }
+    }
+
+    /// An error about the entry at `components` below the root
+    pub(crate) fn error(
+        &self,
+        components: &[OsString],
+        message: impl Into<String>,
+    ) -> TraversalError {
+        TraversalError {
+            path: components.iter().fold(self.name.clone(), |p, c| p.join(c)),
+            kind: io::ErrorKind::Other,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn is_root_yielded(&self) -> bool {
+        self.is_root_yielded
+    }
+
+    /// Yield the root itself, which must be done before any entries are added.
+    pub(crate) fn add_root(&mut self, root: Result<FsEntry, TraversalError>) {
+        self.is_root_yielded = true;
+        self.pending.push_back(root.map(|root| FsEntry {
+            depth: 0,
+            name: self.name.clone(),
+            ..root
+        }));
+    }
+
+    /// Yield `entry` at `components` below the root, along with the directories leading to it which weren't yet.
+    /// Entries within excluded directories are left out, and directories that were yielded already aren't again.
+    pub(crate) fn add(&mut self, components: &[OsString], entry: FsEntry) {
+        if !self.is_root_yielded {
+            self.add_root(Ok(Listing::entry(0, PathBuf::new(), true)));
+        }
+        let depth = components.len();
+        if depth == 0 || (entry.is_dir && self.open.starts_with(components)) {
+            return;
+        }
+        if (1..=depth).any(|depth| self.is_excluded(&components[..depth])) {
+            return;
+        }
+        let common = self
+            .open
+            .iter()
+            .zip(&components[..depth - 1])
+            .take_while(|(open, parent)| open == parent)
+            .count();
+        self.open.truncate(common);
+        for (parent_depth, name) in components[..depth - 1].iter().enumerate().skip(common) {
+            let dir = Listing::entry(parent_depth + 1, name.into(), true);
+            self.pending.push_back(Ok(dir));
+            self.open.push(name.clone());
+        }
+        if entry.is_dir {
+            self.open.push(components[depth - 1].clone());
+        }
+        self.pending.push_back(Ok(FsEntry {
+            depth,
+            name: components[depth - 1].clone().into(),
+            ..entry
+        }));
+    }
+
+    pub(crate) fn add_error(&mut self, err: TraversalError) {
+        self.pending.push_back(Err(err));
+    }
+
+    /// The next entry to yield, if there is one.
+    pub(crate) fn next(&mut self) -> Option<Result<FsEntry, TraversalError>> {
+        self.pending.pop_front()
+    }
+
+    fn is_excluded(&self, components: &[OsString]) -> bool {
+        let (name, parent) = components.split_last().expect("an entry below the root");
+        let parent = parent.iter().fold(self.root.clone(), |p, c| p.join(c));
+        self.exclude.iter().any(|glob| glob.matches(&parent, name))
+    }
+}
+
+/// A line written by `dua aggregate --format ndjson`
+struct Line {
+    path: PathBuf,
+    size: u128,
+    kind: Vec<u8>,
+    has_error: bool,
+}
+
+fn parse_line(line: &[u8]) -> Result<Line> {
+    let mut r = json::Reader::new(line);
+    let mut parsed = Line {
+        path: PathBuf::new(),
+        size: 0,
+        kind: Vec::new(),
+        has_error: false,
+    };
+    r.expect(b'{')?;
+    if !r.next_if(b'}') {
+        loop {
+            let key = r.string()?;
+            r.expect(b':')?;
+            match key.as_slice() {
+                b"path" => parsed.path = path_from_bytes(r.string()?),
+                b"size" => parsed.size = r.unsigned()?,
+                b"type" => parsed.kind = r.string()?,
+                b"error" => parsed.has_error = r.boolean()?,
+                _ => r.skip_value()?,
+            }
+            if !r.has_next_item(b'}')? {
+                break;
+            }
+        }
+    }
+    r.finish()?;
+    Ok(parsed)
+}
+
+/// The entries of a walk of `root`, read from the output of `dua aggregate --format ndjson` as it's written.
+///
+/// Directories are written after their entries there, and have to come before them in a walk.
+struct NdjsonWalk<R> {
+    lines: io::Split<R>,
+    listing: Listing,
+}
+
+impl<R: BufRead> NdjsonWalk<R> {
+    fn new(input: R, root: PathBuf, name: PathBuf, exclude: Vec<Glob>) -> Self {
+        NdjsonWalk {
+            lines: input.split(b'\n'),
+            listing: Listing::new(root, name, exclude),
+        }
+    }
+
+    /// Add the entries to yield for `line`.
+    fn read(&mut self, line: Line) {
+        let listing = &mut self.listing;
+        let components: Vec<OsString> = match line.path.strip_prefix(&listing.root) {
+            Ok(relative) => relative
+                .components()
+                .map(|c| c.as_os_str().to_owned())
+                .collect(),
+            Err(_) => {
+                let err = listing.error(&[], format!("'{}' is not within it", line.path.display()));
+                listing.add_error(err);
+                return;
+            }
+        };
+        let is_dir = line.kind == b"dir";
+        let mut entry = Listing::entry(0, PathBuf::new(), is_dir);
+        entry.is_symlink = line.kind == b"symlink";
+        if line.has_error {
+            entry.metadata_error =
+                Some(listing.error(&components, "Could not be read on the remote host"));
+        } else if !is_dir {
+            entry.size = Some(sizes(line.size));
+        }
+        if components.is_empty() && !is_dir && !listing.is_root_yielded() {
+            // The walked path isn't a directory, and this is all there is
+            listing.add_root(match entry.metadata_error {
+                Some(err) => Err(err),
+                None => Ok(entry),
+            });
+            return;
+        }
+        listing.add(&components, entry);
+    }
+}
+
+/// The sizes of an entry of which only one is known
+pub(crate) fn sizes(size: u128) -> EntrySize {
+    let size = u64::try_from(size).unwrap_or(u64::MAX);
+    EntrySize {
+        apparent: size,
+        disk_usage: Ok(size),
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonWalk<R> {
+    type Item = Result<FsEntry, TraversalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.listing.next() {
+                return Some(entry);
+            }
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => {
+                    let name = self.listing.name.clone();
+                    return Some(Err(TraversalError::from_io_error(&err, name)));
+                }
+            };
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match parse_line(&line) {
+                Ok(line) => self.read(line),
+                Err(err) => {
+                    let message = format!("Unexpected output of 'dua': {:#}", err);
+                    let err = self.listing.error(&[], message);
+                    self.listing.add_error(err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, traverse::Traversal};
+    use std::fs;
+
+    #[test]
+    fn paths_with_a_host_are_remote() {
+        assert_eq!(
+            split_remote_path("user@host:/var"),
+            Some(("user@host", "/var"))
+        );
+        assert_eq!(split_remote_path("host:"), Some(("host", ".")));
+        assert_eq!(split_remote_path("host:dir/sub"), Some(("host", "dir/sub")));
+        assert_eq!(split_remote_path("dir/a:b"), None);
+        assert_eq!(split_remote_path("C:\\Users"), None);
+        assert_eq!(split_remote_path("/var"), None);
+    }
+
+    #[test]
+    fn aggregated_entries_are_walked_like_the_directories_they_were_written_of() -> Result<()> {
+        let root = TempDir::new("remote")?;
+        fs::create_dir_all(root.join("a/b/empty"))?;
+        fs::create_dir_all(root.join("c"))?;
+        fs::create_dir_all(root.join("skipped"))?;
+        fs::write(root.join("a/b/file"), vec![0; 300])?;
+        fs::write(root.join("a/file"), vec![0; 20])?;
+        fs::write(root.join("c/file"), vec![0; 1])?;
+        fs::write(root.join("skipped/file"), vec![0; 4000])?;
+        fs::write(root.join("top"), vec![0; 5000])?;
+
+        let walk_options = WalkOptions::default()
+            .threads(1)
+            .apparent_size(true)
+            .sorting(crate::TraversalSorting::AlphabeticalByFileName);
+        let mut output = Vec::new();
+        crate::aggregate_ndjson(&mut output, walk_options.clone(), [&root])?;
+
+        let name = PathBuf::from(format!("host:{}", root.display()));
+        let entries: Vec<_> = NdjsonWalk::new(
+            &output[..],
+            root.to_path_buf(),
+            name.clone(),
+            vec!["skipped".parse().expect("valid glob")],
+        )
+        .map(|entry| {
+            let entry = entry.expect("no errors");
+            let name = entry.name.to_string_lossy().into_owned();
+            (entry.depth, name, entry.size.map(|size| size.apparent))
+        })
+        .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (0, name.to_string_lossy().into_owned(), None),
+                (1, "a".into(), None),
+                (2, "b".into(), None),
+                (3, "empty".into(), None),
+                (3, "file".into(), Some(300)),
+                (2, "file".into(), Some(20)),
+                (1, "c".into(), None),
+                (2, "file".into(), Some(1)),
+                (1, "top".into(), Some(5000)),
+            ]
+        );
+
+        struct Output(Vec<u8>, PathBuf);
+        impl Filesystem for Output {
+            fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+                Ok(Box::new(NdjsonWalk::new(
+                    &self.0[..],
+                    path.to_owned(),
+                    self.1.clone(),
+                    Vec::new(),
+                )))
+            }
+        }
+        let traversal = Traversal::from_filesystem(
+            &mut Output(output, name.clone()),
+            true,
+            vec![root.to_path_buf()],
+            |_| Ok(false),
+        )?;
+        let top = traversal.find(&name).expect("the walked path");
+        assert_eq!(traversal.tree[top].size, 9321);
+        assert_eq!(traversal.tree[top].entry_count, 10);
+        Ok(())
+    }
+}
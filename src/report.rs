@@ -0,0 +1,203 @@
+//! Self-contained HTML reports of a traversal, with a treemap to zoom into directories, to share findings with those
+//! who won't run a terminal tool.
+//!
+//! The entries are embedded as JSON, each entry being an array of its name and size, followed by the array of its
+//! children if it's a directory. Entries too small to be seen in the treemap are merged into a single entry per
+//! directory, which has the amount of entries it stands for as name.
+use crate::{
+    get_entry_or_panic, json,
+    traverse::{Traversal, TreeIndex},
+    utc_date_time,
+};
+use anyhow::Result;
+use petgraph::Direction;
+use std::{
+    io::{self, Write},
+    time::SystemTime,
+};
+
+const TEMPLATE: &str = include_str!("report.html");
+/// Where the entries go in [`TEMPLATE`]
+const DATA_MARKER: &str = "{{data}}";
+/// Entries smaller than this fraction of the total size are merged, to keep reports of large scans small
+const MIN_FRACTION_OF_TOTAL: u128 = 1000;
+
+/// Write `traversal` to `out` as a single HTML page which draws its entries as treemap.
+///
+/// With multiple input paths, the top of the treemap is a directory named `input paths` containing all of them.
+pub fn html(mut out: impl io::Write, traversal: &Traversal) -> Result<()> {
+    let tree = &traversal.tree;
+    let roots: Vec<_> = tree
+        .neighbors_directed(traversal.root_index, Direction::Outgoing)
+        .collect();
+    let total: u128 = roots
+        .iter()
+        .map(|&idx| u128::from(get_entry_or_panic(tree, idx).size))
+        .sum();
+    let min_size = total / MIN_FRACTION_OF_TOTAL;
+
+    let mut data = Vec::new();
+    write!(
+        data,
+        "{{\"generator\":\"dua {}\",\"date\":\"{} UTC\",\"apparent_size\":{},\"root\":",
+        env!("CARGO_PKG_VERSION"),
+        utc_date_time(SystemTime::now()).replace('T', " "),
+        traversal.apparent_size
+    )?;
+    match roots.as_slice() {
+        [root] => write_entries(
+            &mut data,
+            traversal,
+            vec![Step::Entry(*root, true)],
+            min_size,
+        )?,
+        _ => {
+            data.extend_from_slice(b"[\"input paths\",");
+            write!(data, "{},", total)?;
+            let mut stack = Vec::new();
+            push_children(
+                &mut data,
+                traversal,
+                traversal.root_index,
+                min_size,
+                &mut stack,
+            )?;
+            write_entries(&mut data, traversal, stack, min_size)?;
+        }
+    }
+    data.extend_from_slice(b"}");
+    // Names can't end the script they are embedded in this way, and '\/' is a valid escape in JSON strings
+    let data = String::from_utf8(data)?.replace("</", "<\\/");
+
+    let (before, after) = TEMPLATE
+        .split_once(DATA_MARKER)
+        .expect("the template has a marker for the data");
+    out.write_all(before.as_bytes())?;
+    out.write_all(data.as_bytes())?;
+    out.write_all(after.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// What is still to write of the entries
+enum Step {
+    /// An entry, and whether it's the first of its siblings
+    Entry(TreeIndex, bool),
+    /// The entries of a directory which are too small to be seen, merged into one
+    Smaller {
+        count: usize,
+        size: u128,
+        is_first: bool,
+    },
+    /// The end of the children of a directory, and of the directory itself
+    End,
+}
+
+/// Write the entries on `stack` and all entries below them, popping them in order.
+///
+/// Entries still to write are kept on this stack, so trees of any depth can be written.
+fn write_entries(
+    out: &mut impl io::Write,
+    traversal: &Traversal,
+    mut stack: Vec<Step>,
+    min_size: u128,
+) -> io::Result<()> {
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Entry(node_idx, is_first) => {
+                if !is_first {
+                    out.write_all(b",")?;
+                }
+                let entry = get_entry_or_panic(&traversal.tree, node_idx);
+                out.write_all(b"[")?;
+                json::write_str(out, &traversal.tree.names[entry.name].to_string_lossy())?;
+                write!(out, ",{}", entry.size)?;
+                if entry.is_dir {
+                    out.write_all(b",")?;
+                    push_children(out, traversal, node_idx, min_size, &mut stack)?;
+                } else {
+                    out.write_all(b"]")?;
+                }
+            }
+            Step::Smaller {
+                count,
+                size,
+                is_first,
+            } => {
+                if !is_first {
+                    out.write_all(b",")?;
+                }
+                write!(out, "[{},{}]", count, size)?;
+            }
+            Step::End => out.write_all(b"]]")?,
+        }
+    }
+    Ok(())
+}
+
+/// Start the children of the directory at `node_idx`, and push them onto `stack` along with their end.
+fn push_children(
+    out: &mut impl io::Write,
+    traversal: &Traversal,
+    node_idx: TreeIndex,
+    min_size: u128,
+    stack: &mut Vec<Step>,
+) -> io::Result<()> {
+    let tree = &traversal.tree;
+    let mut children: Vec<_> = tree
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .collect();
+    children.sort_by_key(|&idx| std::cmp::Reverse(get_entry_or_panic(tree, idx).size));
+    let num_shown = children
+        .iter()
+        .take_while(|&&idx| u128::from(get_entry_or_panic(tree, idx).size) >= min_size.max(1))
+        .count();
+
+    out.write_all(b"[")?;
+    stack.push(Step::End);
+    let smaller = &children[num_shown..];
+    if !smaller.is_empty() {
+        let size: u128 = smaller
+            .iter()
+            .map(|&idx| u128::from(get_entry_or_panic(tree, idx).size))
+            .sum();
+        stack.push(Step::Smaller {
+            count: smaller.len(),
+            size,
+            is_first: num_shown == 0,
+        });
+    }
+    stack.extend(
+        children[..num_shown]
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(pos, &child)| Step::Entry(child, pos == 0)),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WalkOptions;
+    use std::path::PathBuf;
+
+    #[test]
+    fn entries_are_embedded_largest_first_with_small_ones_merged() -> Result<()> {
+        let traversal = Traversal::from_walk(
+            WalkOptions::default().threads(1).apparent_size(true),
+            vec![PathBuf::from("tests/fixtures/sample-02")],
+            |_| Ok(false),
+        )?;
+        let mut out = Vec::new();
+        html(&mut out, &traversal)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.starts_with("<!DOCTYPE html>"));
+        assert!(!out.contains(DATA_MARKER));
+        assert!(out.contains(
+            r#""root":["tests/fixtures/sample-02",1540,[["dir",1283,[["sub",1024,[["e",1024]]],["c",257],["d",2],[1,0]]],["a",256],["b",1]]]}"#
+        ), "{}", out);
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! Finding git repositories and how much of their space is taken by the history and metadata in their `.git`
+//! directory.
+use crate::{
+    get_entry_or_panic,
+    traverse::{Traversal, Tree, TreeIndex},
+    WalkOptions, WalkResult,
+};
+use anyhow::Result;
+use colored::Colorize;
+use petgraph::Direction;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the directory git keeps the history and metadata of a repository in
+pub const GIT_DIR: &str = ".git";
+
+/// A git repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    /// The path of the working tree, which contains the `.git` directory
+    pub path: PathBuf,
+    /// The size of the `.git` directory in bytes
+    pub git_size: u128,
+    /// The size of everything else in bytes, including repositories within the working tree
+    pub working_tree_size: u128,
+}
+
+/// Return the `.git` directory of the repository at `node_idx` of `tree`, or `None` if it's not a repository.
+pub fn git_dir_of(tree: &Tree, node_idx: TreeIndex) -> Option<TreeIndex> {
+    if !get_entry_or_panic(tree, node_idx).is_git_repo {
+        return None;
+    }
+    tree.neighbors_directed(node_idx, Direction::Outgoing)
+        .find(|idx| tree[*idx].is_dir && tree.name_of(*idx) == Path::new(GIT_DIR))
+}
+
+/// Return all repositories in `traversal`, those with the largest `.git` directory first.
+pub fn find_repos(traversal: &Traversal) -> Vec<Repo> {
+    let tree = &traversal.tree;
+    let mut repos = Vec::new();
+    // The names of the entries below the root are the input paths
+    let mut stack: Vec<(TreeIndex, PathBuf)> = tree
+        .neighbors_directed(traversal.root_index, Direction::Outgoing)
+        .map(|idx| (idx, tree.name_of(idx).to_path_buf()))
+        .collect();
+    while let Some((idx, path)) = stack.pop() {
+        if let Some(git_idx) = git_dir_of(tree, idx) {
+            let git_size = tree[git_idx].size as u128;
+            repos.push(Repo {
+                path: path.clone(),
+                git_size,
+                working_tree_size: (tree[idx].size as u128).saturating_sub(git_size),
+            });
+        }
+        stack.extend(
+            tree.neighbors_directed(idx, Direction::Outgoing)
+                .filter(|child| tree[*child].is_dir && tree.name_of(*child) != Path::new(GIT_DIR))
+                .map(|child| (child, path.join(tree.name_of(child)))),
+        );
+    }
+    repos.sort_by(|a, b| {
+        b.git_size
+            .cmp(&a.git_size)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    repos
+}
+
+/// Walk the given `paths` and write a line for each git repository among them to `out`, the one with the largest
+/// `.git` directory first, with the size of its `.git` directory, of its working tree and its path. A line with the
+/// space taken by all `.git` directories together follows.
+///
+/// If `err` is set, the amount of entries seen so far is written to it while walking.
+/// If the walk is [interrupted][crate::INTERRUPTED], the repositories found so far are written along with a note that
+/// more may be missing.
+pub fn repos(
+    mut out: impl io::Write,
+    mut err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let byte_format = walk_options.byte_format;
+    let paths = paths.into_iter().map(|p| p.as_ref().to_owned()).collect();
+    let traversal = Traversal::from_walk(walk_options, paths, |t| {
+        if let Some(err) = err.as_mut() {
+            write!(err, "Enumerating {} entries\r", t.entries_traversed).ok();
+        }
+        Ok(false)
+    })?;
+    let repos = find_repos(&traversal);
+
+    let width = byte_format.width();
+    writeln!(
+        out,
+        "{:>width$} {:>width$} REPOSITORY",
+        ".GIT",
+        "WORKING TREE",
+        width = width
+    )?;
+    for repo in &repos {
+        writeln!(
+            out,
+            "{:>width$} {:>width$} {}",
+            byte_format
+                .display(repo.git_size)
+                .to_string()
+                .as_str()
+                .green(),
+            byte_format.display(repo.working_tree_size).to_string(),
+            repo.path.display(),
+            width = width
+        )?;
+    }
+    writeln!(
+        out,
+        "{} {} with {} in {}",
+        repos.len(),
+        if repos.len() == 1 {
+            "repository"
+        } else {
+            "repositories"
+        },
+        byte_format.display(repos.iter().map(|r| r.git_size).sum()),
+        GIT_DIR
+    )?;
+    if traversal.is_incomplete {
+        writeln!(out, "{}", "(incomplete, more may be missing)".red())?;
+    }
+    Ok(WalkResult {
+        num_errors: traversal.io_errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, ByteFormat};
+    use std::fs;
+
+    #[test]
+    fn repositories_are_listed_with_the_size_of_their_git_directory() -> Result<()> {
+        let root = TempDir::new("repos")?;
+        let write = |path: &str, len: usize| -> io::Result<()> {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().expect("a parent"))?;
+            fs::write(path, vec![0; len])
+        };
+        write("big/.git/objects/pack", 300)?;
+        write("big/src/main.rs", 100)?;
+        write("big/vendor/small/.git/HEAD", 20)?;
+        write("big/vendor/small/lib.rs", 10)?;
+        write("plain/.git", 5)?;
+
+        let mut out = Vec::new();
+        let res = repos(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            vec![&root],
+        )?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "        .GIT WORKING TREE REPOSITORY
+       300 b        130 b {r}/big
+        20 b         10 b {r}/big/vendor/small
+2 repositories with 320 b in .git
+",
+                r = root.display()
+            )
+        );
+        assert_eq!(res.num_errors, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+//! List buckets of S3 and compatible object storage like they were directories, with the `aws` command-line tool.
+use crate::{
+    filesystem::{Filesystem, FsEntry, Walk},
+    remote::{sizes, Listing, Process},
+    traverse::TraversalError,
+    WalkOptions,
+};
+use std::{
+    convert::TryFrom,
+    ffi::OsString,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Split `input` like `s3://bucket/some/prefix` into the bucket and the prefix of the keys within it, which is empty
+/// for all keys.
+pub fn split_s3_url(input: &str) -> Option<(&str, &str)> {
+    let rest = input.strip_prefix("s3://")?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        return None;
+    }
+    Some((bucket, prefix.trim_matches('/')))
+}
+
+/// Buckets of S3 or compatible object storage, walked from URLs like `s3://bucket/some/prefix`.
+///
+/// Keys are split into directories at each `/`. Objects are listed by running `aws s3api list-objects-v2`, so
+/// credentials, regions and endpoints of other providers are configured like for the `aws` tool, like with
+/// `AWS_PROFILE` and `AWS_ENDPOINT_URL`. Objects take up exactly their size.
+pub struct S3Filesystem {
+    walk_options: WalkOptions,
+}
+
+impl S3Filesystem {
+    /// List objects with `walk_options`, of which only excluded patterns apply.
+    pub fn new(walk_options: WalkOptions) -> Self {
+        S3Filesystem { walk_options }
+    }
+}
+
+impl Filesystem for S3Filesystem {
+    fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+        let (bucket, prefix) = path.to_str().and_then(split_s3_url).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' is not like 's3://bucket/prefix'", path.display()),
+            )
+        })?;
+        let mut aws = Command::new("aws");
+        aws.args(["s3api", "list-objects-v2", "--bucket", bucket])
+            .args(["--output", "text"])
+            .args(["--query", "Contents[].[Size,LastModified,Key]"]);
+        let key_prefix = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        };
+        if !key_prefix.is_empty() {
+            aws.args(["--prefix", &key_prefix]);
+        }
+        let name = PathBuf::from(format!("s3://{}/{}", bucket, prefix).trim_end_matches('/'));
+        let (stdout, mut aws) = Process::spawn(aws, name.clone())?;
+        Ok(Box::new(
+            ObjectsWalk {
+                lines: BufReader::new(stdout).split(b'\n'),
+                key_prefix,
+                count_objects: self.walk_options.count_inodes,
+                listing: Listing::new(name.clone(), name, self.walk_options.exclude.clone()),
+            }
+            .chain(std::iter::once_with(move || aws.finish()).flatten()),
+        ))
+    }
+}
+
+/// The entries of a walk of the objects listed by `aws s3api list-objects-v2` as text, one per line with their
+/// size, time of last modification and key.
+///
+/// Keys are listed in the order of their bytes, which keeps those starting with the same prefix together.
+struct ObjectsWalk<R> {
+    lines: io::Split<R>,
+    /// What all listed keys start with, and which isn't part of their path
+    key_prefix: String,
+    /// If set, each object takes one instead of its size, like the inode of a file
+    count_objects: bool,
+    listing: Listing,
+}
+
+impl<R: BufRead> ObjectsWalk<R> {
+    /// Add the entries to yield for a listed object.
+    fn read(&mut self, line: &str) {
+        let mut fields = line.splitn(3, '\t');
+        let (size, mtime, key) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(size), Some(mtime), Some(key)) => (size.trim().parse::<u128>().ok(), mtime, key),
+            _ if line.trim() == "None" => return, // there are no objects with the prefix
+            _ => (None, "", line),
+        };
+        let size = match size {
+            Some(size) => size,
+            None => {
+                let message = format!("Unexpected output of 'aws': '{}'", line);
+                let err = self.listing.error(&[], message);
+                self.listing.add_error(err);
+                return;
+            }
+        };
+        let relative = key.strip_prefix(&self.key_prefix).unwrap_or(key);
+        let components: Vec<OsString> = relative
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .map(OsString::from)
+            .collect();
+        // Keys ending with a '/' are placeholders for directories, like those created in web consoles
+        let is_dir = relative.is_empty() || relative.ends_with('/');
+        let mut entry = Listing::entry(0, PathBuf::new(), is_dir);
+        entry.mtime = parse_utc_date_time(mtime);
+        if !is_dir {
+            entry.size = Some(sizes(if self.count_objects { 1 } else { size }));
+        }
+        self.listing.add(&components, entry);
+    }
+}
+
+impl<R: BufRead> Iterator for ObjectsWalk<R> {
+    type Item = Result<FsEntry, TraversalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.listing.next() {
+                return Some(entry);
+            }
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    let line = String::from_utf8_lossy(&line);
+                    let line = line.trim_end_matches('\r');
+                    if !line.trim().is_empty() {
+                        self.read(line);
+                    }
+                }
+                Some(Err(err)) => {
+                    let err = self.listing.error(&[], err.to_string());
+                    self.listing.add_error(err);
+                }
+                // Buckets without objects with the prefix are empty directories
+                None if !self.listing.is_root_yielded() => self
+                    .listing
+                    .add(&[], Listing::entry(0, PathBuf::new(), true)),
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Parse a time like `2021-06-09T10:13:20.000Z` or `2021-06-09T10:13:20+00:00`.
+fn parse_utc_date_time(s: &str) -> Option<SystemTime> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> { s.get(range)?.parse().ok() };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    let zone = s[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone {
+        "Z" | "" => 0,
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let zone = zone.get(1..)?.replace(':', "");
+            sign * (zone.get(..2)?.parse::<i64>().ok()? * 3600
+                + zone.get(2..4)?.parse::<i64>().ok()? * 60)
+        }
+    };
+    // Convert a date of the proleptic Gregorian calendar into days since the epoch,
+    // see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(secs)
+        .ok()
+        .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{traverse::Traversal, utc_date_time};
+
+    #[test]
+    fn s3_urls_are_split_into_bucket_and_prefix() {
+        assert_eq!(split_s3_url("s3://bucket"), Some(("bucket", "")));
+        assert_eq!(split_s3_url("s3://bucket/"), Some(("bucket", "")));
+        assert_eq!(split_s3_url("s3://bucket/a/b/"), Some(("bucket", "a/b")));
+        assert_eq!(split_s3_url("s3://"), None);
+        assert_eq!(split_s3_url("/bucket"), None);
+    }
+
+    #[test]
+    fn times_of_objects_are_parsed_with_their_zone() {
+        let parsed = |s| parse_utc_date_time(s).map(utc_date_time);
+        assert_eq!(
+            parsed("2021-06-09T10:13:20.000Z").as_deref(),
+            Some("2021-06-09T10:13:20")
+        );
+        assert_eq!(
+            parsed("2000-02-29T23:00:00-02:00").as_deref(),
+            Some("2000-03-01T01:00:00")
+        );
+        assert_eq!(parsed("yesterday"), None);
+    }
+
+    #[test]
+    fn keys_are_split_into_directories_at_each_slash() -> anyhow::Result<()> {
+        struct Listed(&'static str);
+        impl Filesystem for Listed {
+            fn walk(&mut self, path: &Path) -> io::Result<Walk<'_>> {
+                Ok(Box::new(ObjectsWalk {
+                    lines: BufRead::split(self.0.as_bytes(), b'\n'),
+                    key_prefix: "logs/".into(),
+                    count_objects: false,
+                    listing: Listing::new(
+                        path.to_owned(),
+                        path.to_owned(),
+                        vec!["*.tmp".parse().expect("valid glob")],
+                    ),
+                }))
+            }
+        }
+        let listed = "10\t2021-06-09T10:13:20.000Z\tlogs/2021/01/a.log\n\
+                      0\t2021-06-09T10:13:20.000Z\tlogs/2021/02/\n\
+                      20\t2021-06-09T10:13:20.000Z\tlogs/2021/02/b.log\n\
+                      5\t2021-06-09T10:13:20.000Z\tlogs/2021/c.tmp\n\
+                      30\t2021-06-09T10:13:20.000Z\tlogs/index\n";
+        let root = PathBuf::from("s3://bucket/logs");
+        let traversal =
+            Traversal::from_filesystem(&mut Listed(listed), true, vec![root.clone()], |_| {
+                Ok(false)
+            })?;
+        let paths: Vec<_> = traversal
+            .descendants(traversal.root_index)
+            .map(|idx| {
+                let path = traversal.path_of(idx);
+                (
+                    path.to_string_lossy().into_owned(),
+                    traversal.tree[idx].size,
+                )
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("s3://bucket/logs".into(), 60),
+                ("s3://bucket/logs/2021".into(), 30),
+                ("s3://bucket/logs/2021/01".into(), 10),
+                ("s3://bucket/logs/2021/01/a.log".into(), 10),
+                ("s3://bucket/logs/2021/02".into(), 20),
+                ("s3://bucket/logs/2021/02/b.log".into(), 20),
+                ("s3://bucket/logs/index".into(), 30),
+            ]
+        );
+        assert_eq!(traversal.io_errors, 0);
+
+        let traversal =
+            Traversal::from_filesystem(&mut Listed("None\n"), true, vec![root.clone()], |_| {
+                Ok(false)
+            })?;
+        let top = traversal.find(&root).expect("the listed prefix");
+        assert!(traversal.tree[top].is_dir);
+        assert_eq!(traversal.children(top).len(), 0);
+        Ok(())
+    }
+}
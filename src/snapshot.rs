@@ -0,0 +1,335 @@
+//! A compact binary format to save a completed traversal, to browse it later or to compare it with another one.
+//!
+//! A snapshot starts with [`MAGIC`] and its version, followed by the totals of the traversal and all of its entries
+//! in depth-first order, each directory followed by its children. All numbers are written as LEB128, and names as
+//! their length followed by their bytes. Both sizes of each entry are kept, so it can be loaded with either of them.
+use crate::{
+    get_entry_or_panic,
+    ncdu::path_from_bytes,
+    traverse::{EntryData, Mtime, Traversal, Tree, TreeIndex},
+    WalkOptions,
+};
+use anyhow::{anyhow, bail, Result};
+use petgraph::Direction;
+use std::{convert::TryFrom, io, path::Path};
+
+/// The bytes every snapshot starts with
+pub const MAGIC: &[u8] = b"dua-snapshot\n";
+const VERSION: u64 = 1;
+
+const IS_DIR: u64 = 1;
+const METADATA_IO_ERROR: u64 = 1 << 1;
+const HAS_MTIME: u64 = 1 << 2;
+const IS_CACHE_DIR: u64 = 1 << 3;
+const IS_SYMLINK: u64 = 1 << 4;
+const IS_BROKEN_LINK: u64 = 1 << 5;
+const IS_GIT_REPO: u64 = 1 << 6;
+const IS_IN_ARCHIVE: u64 = 1 << 7;
+const IS_COLLAPSED: u64 = 1 << 8;
+
+/// Return true if `input` looks like a snapshot, and not like another format.
+pub fn is_snapshot(input: &[u8]) -> bool {
+    input.starts_with(MAGIC)
+}
+
+/// Write `traversal` to `out` as snapshot.
+///
+/// The IO errors of the traversal are not kept, only their amount and which entries couldn't be read.
+pub fn save(mut out: impl io::Write, traversal: &Traversal) -> Result<()> {
+    out.write_all(MAGIC)?;
+    write_number(&mut out, VERSION)?;
+    write_number(&mut out, traversal.apparent_size as u64)?;
+    write_number(&mut out, traversal.entries_traversed)?;
+    write_number(&mut out, traversal.io_errors)?;
+    write_entries(&mut out, &traversal.tree, traversal.root_index)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write the entry at `node_idx` and all entries below it, each directory followed by its children.
+///
+/// Entries still to write are kept on a stack of their own, so trees of any depth can be written.
+fn write_entries(out: &mut impl io::Write, tree: &Tree, node_idx: TreeIndex) -> io::Result<()> {
+    let mut stack = vec![node_idx];
+    while let Some(node_idx) = stack.pop() {
+        let entry = get_entry_or_panic(tree, node_idx);
+        let name = bytes_of(&tree.names[entry.name]);
+        write_number(out, name.len() as u64)?;
+        out.write_all(&name)?;
+        write_number(
+            out,
+            if entry.is_dir { IS_DIR } else { 0 }
+                | if entry.metadata_io_error {
+                    METADATA_IO_ERROR
+                } else {
+                    0
+                }
+                | if entry.mtime.is_some() { HAS_MTIME } else { 0 }
+                | if entry.is_cache_dir { IS_CACHE_DIR } else { 0 }
+                | if entry.is_symlink { IS_SYMLINK } else { 0 }
+                | if entry.is_broken_link {
+                    IS_BROKEN_LINK
+                } else {
+                    0
+                }
+                | if entry.is_git_repo { IS_GIT_REPO } else { 0 }
+                | if entry.is_in_archive {
+                    IS_IN_ARCHIVE
+                } else {
+                    0
+                }
+                | if entry.is_collapsed { IS_COLLAPSED } else { 0 },
+        )?;
+        write_number(out, entry.size)?;
+        write_number(out, entry.alternate_size)?;
+        write_number(out, entry.entry_count)?;
+        if let Some(mtime) = entry.mtime {
+            write_number(out, mtime.secs())?;
+        }
+        let children: Vec<_> = tree
+            .neighbors_directed(node_idx, Direction::Outgoing)
+            .collect();
+        write_number(out, children.len() as u64)?;
+        // Neighbors are yielded in reverse order of insertion, which is the order to pop them in
+        stack.extend(children);
+    }
+    Ok(())
+}
+
+fn write_number(out: &mut impl io::Write, n: impl Into<u128>) -> io::Result<()> {
+    let mut n = n.into();
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Create a traversal from the snapshot in `input`, with the sizes `walk_options` asks for.
+pub fn load(input: &[u8], walk_options: &WalkOptions) -> Result<Traversal> {
+    let mut r = Reader { input, pos: 0 };
+    if !is_snapshot(input) {
+        bail!("Not a dua snapshot");
+    }
+    r.pos = MAGIC.len();
+    let version = r.number()?;
+    if version != u128::from(VERSION) {
+        bail!("Unsupported snapshot version {}", version);
+    }
+    let apparent_size = r.number()? != 0;
+    let entries_traversed = r.u64()?;
+    let io_errors = r.u64()?;
+    let mut tree = Tree::new();
+    let root_index = r.entries(&mut tree)?;
+    if r.pos != input.len() {
+        return Err(r.error("Unexpected data after the last entry"));
+    }
+
+    let total_bytes = Some(get_entry_or_panic(&tree, root_index).size.into());
+    let mut traversal = Traversal {
+        tree,
+        root_index,
+        entries_traversed,
+        io_errors,
+        errors: Vec::new(),
+        total_bytes,
+        apparent_size,
+        is_incomplete: false,
+        collapsed_entries: 0,
+    };
+    if traversal.apparent_size != walk_options.apparent_size {
+        traversal.toggle_apparent_size();
+    }
+    Ok(traversal)
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow!("{} at byte {} of the snapshot", message, self.pos)
+    }
+
+    fn number(&mut self) -> Result<u128> {
+        let mut n = 0u128;
+        for shift in (0..128).step_by(7) {
+            let byte = *self
+                .input
+                .get(self.pos)
+                .ok_or_else(|| self.error("Unexpected end"))?;
+            self.pos += 1;
+            n |= u128::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(self.error("Number too large"))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let n = self.number()?;
+        u64::try_from(n).map_err(|_| self.error("Number too large"))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&[u8]> {
+        let bytes = self
+            .input
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or_else(|| self.error("Unexpected end"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Read an entry and all entries below it into `tree`, returning the index of the former.
+    ///
+    /// Directories still missing children are kept on a stack of their own, so trees of any depth can be read.
+    fn entries(&mut self, tree: &mut Tree) -> Result<TreeIndex> {
+        // Directories with the amount of their children which are still to read
+        let mut open: Vec<(TreeIndex, u64)> = Vec::new();
+        let mut root_idx = None;
+        loop {
+            let node_idx = self.entry(tree)?;
+            match open.last_mut() {
+                Some((parent_idx, num_missing)) => {
+                    tree.add_edge(*parent_idx, node_idx, ());
+                    *num_missing -= 1;
+                }
+                None => root_idx = Some(node_idx),
+            }
+            open.push((node_idx, self.u64()?));
+            while let Some((_, 0)) = open.last() {
+                open.pop();
+            }
+            if open.is_empty() {
+                return Ok(root_idx.expect("the first entry read is the root"));
+            }
+        }
+    }
+
+    /// Read an entry and add it to `tree`, without its children.
+    fn entry(&mut self, tree: &mut Tree) -> Result<TreeIndex> {
+        let name_len = usize::try_from(self.u64()?).map_err(|_| self.error("Name too long"))?;
+        let name = path_from_bytes(self.bytes(name_len)?.to_owned());
+        let flags = self.u64()?;
+        let size = self.u64()?;
+        let alternate_size = self.u64()?;
+        let entry_count = u32::try_from(self.u64()?).map_err(|_| self.error("Too many entries"))?;
+        let mtime = if flags & HAS_MTIME != 0 {
+            Some(Mtime::from_secs(self.u64()?))
+        } else {
+            None
+        };
+        let name = tree.names.intern(&name);
+        Ok(tree.add_node(EntryData {
+            name,
+            size,
+            alternate_size,
+            entry_count,
+            is_dir: flags & IS_DIR != 0,
+            is_symlink: flags & IS_SYMLINK != 0,
+            is_broken_link: flags & IS_BROKEN_LINK != 0,
+            mtime,
+            metadata_io_error: flags & METADATA_IO_ERROR != 0,
+            is_cache_dir: flags & IS_CACHE_DIR != 0,
+            is_git_repo: flags & IS_GIT_REPO != 0,
+            is_in_archive: flags & IS_IN_ARCHIVE != 0,
+            is_collapsed: flags & IS_COLLAPSED != 0,
+        }))
+    }
+}
+
+#[cfg(unix)]
+fn bytes_of(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_owned()
+}
+
+#[cfg(not(unix))]
+fn bytes_of(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TraversalSorting;
+    use std::path::PathBuf;
+
+    fn options(apparent_size: bool) -> WalkOptions {
+        WalkOptions::default()
+            .threads(1)
+            .apparent_size(apparent_size)
+            .sorting(TraversalSorting::AlphabeticalByFileName)
+    }
+
+    fn entries_of(t: &Traversal) -> Vec<(PathBuf, u64, u64, u32, bool)> {
+        let mut entries: Vec<_> = t
+            .tree
+            .node_indices()
+            .map(|idx| {
+                let e = get_entry_or_panic(&t.tree, idx);
+                (
+                    t.tree.names[e.name].to_path_buf(),
+                    e.size,
+                    e.alternate_size,
+                    e.entry_count,
+                    e.is_dir,
+                )
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn it_loads_what_it_saved_with_either_size() -> Result<()> {
+        let traversal = Traversal::from_walk(
+            options(true),
+            vec![PathBuf::from("tests/fixtures/sample-01")],
+            |_| Ok(false),
+        )?;
+        let mut snapshot = Vec::new();
+        save(&mut snapshot, &traversal)?;
+        assert!(is_snapshot(&snapshot));
+
+        let loaded = load(&snapshot, &options(true))?;
+        assert_eq!(entries_of(&loaded), entries_of(&traversal));
+        assert_eq!(loaded.entries_traversed, traversal.entries_traversed);
+        assert_eq!(loaded.total_bytes, traversal.total_bytes);
+
+        let mut resaved = Vec::new();
+        save(&mut resaved, &loaded)?;
+        assert_eq!(resaved, snapshot, "children keep their order");
+
+        let loaded = load(&snapshot, &options(false))?;
+        assert!(!loaded.apparent_size);
+        let mut toggled = traversal;
+        toggled.toggle_apparent_size();
+        assert_eq!(entries_of(&loaded), entries_of(&toggled));
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_other_and_truncated_input() -> Result<()> {
+        assert!(load(b"[1,2,{}]", &options(true)).is_err());
+        let traversal = Traversal::from_walk(
+            options(true),
+            vec![PathBuf::from("tests/fixtures/sample-02")],
+            |_| Ok(false),
+        )?;
+        let mut snapshot = Vec::new();
+        save(&mut snapshot, &traversal)?;
+        let err = load(&snapshot[..snapshot.len() - 1], &options(true))
+            .expect_err("truncated snapshots are rejected");
+        assert!(err.to_string().starts_with("Unexpected end at byte"));
+        snapshot.push(0);
+        assert!(load(&snapshot, &options(true)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,420 @@
+//! Writing a traversal into a SQLite database, or as SQL script which creates and fills its tables, to analyse a scan
+//! with queries, like with `dua export --format sqlite -o scan.db` or `dua export --format sql | sqlite3 scan.db`.
+//!
+//! The `entries` table has a row per entry with its `id`, the `parent_id` of the directory containing it, which is
+//! `NULL` for input paths, its `name`, full `path` and `type`, one of `dir`, `file` or `symlink`. It also has both
+//! sizes in bytes, `disk_usage` and `apparent_size`, the amount of entries within directories as `entry_count`, the time
+//! of last modification as `mtime` in seconds since the UNIX epoch, and `error`, which is 1 if the entry couldn't be
+//! read. The `errors` table holds the `path` and `message` of each error encountered while scanning.
+use crate::{
+    get_entry_or_panic,
+    sqlite::{Database, Value},
+    traverse::{Traversal, TreeIndex},
+};
+use anyhow::Result;
+use petgraph::Direction;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+const ENTRIES: &str = "\
+CREATE TABLE entries (
+  id INTEGER PRIMARY KEY,
+  parent_id INTEGER REFERENCES entries(id),
+  name TEXT NOT NULL,
+  path TEXT NOT NULL,
+  type TEXT NOT NULL,
+  disk_usage INTEGER NOT NULL,
+  apparent_size INTEGER NOT NULL,
+  entry_count INTEGER NOT NULL,
+  mtime INTEGER,
+  error INTEGER NOT NULL
+)";
+const ENTRIES_BY_PARENT: &str = "CREATE INDEX entries_by_parent ON entries(parent_id)";
+const ERRORS: &str = "\
+CREATE TABLE errors (
+  path TEXT NOT NULL,
+  message TEXT NOT NULL
+)";
+
+/// An entry as row of the `entries` table
+struct Row<'a> {
+    id: u64,
+    parent_id: Option<u64>,
+    name: &'a Path,
+    path: &'a Path,
+    kind: &'static str,
+    disk_usage: u64,
+    apparent_size: u64,
+    entry_count: u32,
+    mtime: Option<u64>,
+    error: bool,
+}
+
+/// Write `traversal` to `out` as SQLite database with the tables described in the module documentation.
+///
+/// The database is written from start to end, except for its first page which is written last, so `out` should be
+/// an empty file.
+pub fn export_database(out: impl io::Write + io::Seek, traversal: &Traversal) -> Result<()> {
+    let mut db = Database::new(out)?;
+    let mut parents = Vec::new();
+    let mut entries = db.table("entries", ENTRIES);
+    for_each_row(traversal, |row| {
+        parents.push((row.parent_id, row.id));
+        let (name, path) = (row.name.to_string_lossy(), row.path.to_string_lossy());
+        entries.insert(
+            row.id as i64,
+            &[
+                // The id is the rowid, which takes the place of the column
+                Value::Null,
+                row.parent_id
+                    .map_or(Value::Null, |id| Value::Integer(id as i64)),
+                Value::Text(&name),
+                Value::Text(&path),
+                Value::Text(row.kind),
+                Value::Integer(row.disk_usage as i64),
+                Value::Integer(row.apparent_size as i64),
+                Value::Integer(row.entry_count.into()),
+                row.mtime
+                    .map_or(Value::Null, |mtime| Value::Integer(mtime as i64)),
+                Value::Integer(row.error.into()),
+            ],
+        )
+    })?;
+    entries.finish()?;
+
+    // Input paths have no parent, and come first like NULL does in SQLite
+    parents.sort_unstable();
+    db.index(
+        "entries_by_parent",
+        "entries",
+        ENTRIES_BY_PARENT,
+        parents.into_iter().map(|(parent_id, id)| {
+            [
+                parent_id.map_or(Value::Null, |id| Value::Integer(id as i64)),
+                Value::Integer(id as i64),
+            ]
+        }),
+    )?;
+
+    let mut errors = db.table("errors", ERRORS);
+    for (rowid, err) in traversal.errors.iter().enumerate() {
+        errors.insert(
+            rowid as i64 + 1,
+            &[
+                Value::Text(&err.path.to_string_lossy()),
+                Value::Text(&err.message),
+            ],
+        )?;
+    }
+    errors.finish()?;
+    db.finish()?;
+    Ok(())
+}
+
+/// Write `traversal` to `out` as SQL script for SQLite, creating and filling the tables described in the module
+/// documentation within a single transaction.
+pub fn export(mut out: impl io::Write, traversal: &Traversal) -> Result<()> {
+    out.write_all(b"BEGIN TRANSACTION;\n")?;
+    for sql in &[ENTRIES, ENTRIES_BY_PARENT, ERRORS] {
+        writeln!(out, "{};", sql)?;
+    }
+    for_each_row(traversal, |row| write_row(&mut out, row))?;
+    for err in &traversal.errors {
+        out.write_all(b"INSERT INTO errors VALUES (")?;
+        write_str(&mut out, &err.path.to_string_lossy())?;
+        out.write_all(b",")?;
+        write_str(&mut out, &err.message)?;
+        out.write_all(b");\n")?;
+    }
+    out.write_all(b"COMMIT;\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn children_in_order(traversal: &Traversal, node_idx: TreeIndex) -> Vec<TreeIndex> {
+    let mut children: Vec<_> = traversal
+        .tree
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .collect();
+    // Neighbors are yielded in reverse order of insertion
+    children.reverse();
+    children
+}
+
+/// Call `f` with the row of each entry of `traversal`, in the order of their ids, which is the order of a walk
+/// through the tree with directories before their content.
+fn for_each_row(
+    traversal: &Traversal,
+    mut f: impl FnMut(&Row<'_>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut next_id = 1;
+    for root in children_in_order(traversal, traversal.root_index) {
+        for_each_row_below(traversal, root, &mut next_id, &mut f)?;
+    }
+    Ok(())
+}
+
+/// Call `f` with the rows of the entry at `node_idx` and all entries below it, with their paths being relative to that
+/// of the former.
+///
+/// Entries still to visit are kept on a stack of their own, so trees of any depth can be written.
+fn for_each_row_below(
+    traversal: &Traversal,
+    node_idx: TreeIndex,
+    next_id: &mut u64,
+    f: &mut impl FnMut(&Row<'_>) -> io::Result<()>,
+) -> io::Result<()> {
+    // `None` leaves the directory whose children were visited last
+    let mut stack = vec![Some(node_idx)];
+    // The ids and names of the directories containing the next entry
+    let mut open: Vec<(u64, &Path)> = Vec::new();
+    while let Some(next) = stack.pop() {
+        let idx = match next {
+            Some(idx) => idx,
+            None => {
+                open.pop();
+                continue;
+            }
+        };
+        let entry = get_entry_or_panic(&traversal.tree, idx);
+        let name = &traversal.tree.names[entry.name];
+        let id = *next_id;
+        *next_id += 1;
+        let path: PathBuf = open
+            .iter()
+            .map(|(_, name)| *name)
+            .chain(std::iter::once(name))
+            .collect();
+        let (disk_usage, apparent_size) = if traversal.apparent_size {
+            (entry.alternate_size, entry.size)
+        } else {
+            (entry.size, entry.alternate_size)
+        };
+        f(&Row {
+            id,
+            parent_id: open.last().map(|(parent_id, _)| *parent_id),
+            name,
+            path: &path,
+            kind: if entry.is_symlink {
+                "symlink"
+            } else if entry.is_dir {
+                "dir"
+            } else {
+                "file"
+            },
+            disk_usage,
+            apparent_size,
+            entry_count: entry.entry_count,
+            mtime: entry.mtime.map(|mtime| mtime.secs()),
+            error: entry.metadata_io_error,
+        })?;
+
+        open.push((id, name));
+        stack.push(None);
+        stack.extend(
+            children_in_order(traversal, idx)
+                .into_iter()
+                .rev()
+                .map(Some),
+        );
+    }
+    Ok(())
+}
+
+/// Write `row` as statement inserting it into the `entries` table.
+fn write_row(out: &mut impl io::Write, row: &Row<'_>) -> io::Result<()> {
+    write!(out, "INSERT INTO entries VALUES ({},", row.id)?;
+    match row.parent_id {
+        Some(parent_id) => write!(out, "{},", parent_id)?,
+        None => out.write_all(b"NULL,")?,
+    }
+    write_str(out, &row.name.to_string_lossy())?;
+    out.write_all(b",")?;
+    write_str(out, &row.path.to_string_lossy())?;
+    write!(
+        out,
+        ",'{}',{},{},{},",
+        row.kind, row.disk_usage, row.apparent_size, row.entry_count
+    )?;
+    match row.mtime {
+        Some(mtime) => write!(out, "{},", mtime)?,
+        None => out.write_all(b"NULL,")?,
+    }
+    writeln!(out, "{});", row.error as u8)
+}
+
+/// Write `s` as SQL string literal, with single quotes doubled.
+fn write_str(out: &mut impl io::Write, s: &str) -> io::Result<()> {
+    out.write_all(b"'")?;
+    out.write_all(s.replace('\'', "''").as_bytes())?;
+    out.write_all(b"'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TraversalSorting, WalkOptions};
+    use std::path::PathBuf;
+
+    #[test]
+    fn entries_are_inserted_with_the_id_of_their_parent() -> Result<()> {
+        let traversal = Traversal::from_walk(
+            WalkOptions::default()
+                .threads(1)
+                .apparent_size(true)
+                .sorting(TraversalSorting::AlphabeticalByFileName),
+            vec![PathBuf::from("tests/fixtures/sample-02/dir")],
+            |_| Ok(false),
+        )?;
+        let mut out = Vec::new();
+        export(&mut out, &traversal)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.starts_with("BEGIN TRANSACTION;\nCREATE TABLE entries ("));
+        assert!(out.ends_with("COMMIT;\n"));
+        let rows: Vec<_> = out
+            .lines()
+            .filter_map(|l| l.strip_prefix("INSERT INTO entries VALUES ("))
+            .map(|l| {
+                // Disk usage and times of last modification depend on the checkout
+                let fields: Vec<_> = l.split(',').collect();
+                [&fields[..5], &fields[6..8]].concat().join(",")
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                "1,NULL,'tests/fixtures/sample-02/dir','tests/fixtures/sample-02/dir','dir',1283,6",
+                "2,1,'c','tests/fixtures/sample-02/dir/c','file',257,0",
+                "3,1,'d','tests/fixtures/sample-02/dir/d','file',2,0",
+                "4,1,'empty-dir','tests/fixtures/sample-02/dir/empty-dir','dir',0,1",
+                "5,4,'.gitkeep','tests/fixtures/sample-02/dir/empty-dir/.gitkeep','file',0,0",
+                "6,1,'sub','tests/fixtures/sample-02/dir/sub','dir',1024,1",
+                "7,6,'e','tests/fixtures/sample-02/dir/sub/e','file',1024,0",
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_str(&mut out, "it's")?;
+        assert_eq!(out, b"'it''s'");
+        Ok(())
+    }
+
+    #[test]
+    fn entries_are_written_into_a_database_with_the_id_of_their_parent() -> Result<()> {
+        use crate::sqlite::read::{self, OwnedValue::*};
+
+        let traversal = Traversal::from_walk(
+            WalkOptions::default()
+                .threads(1)
+                .apparent_size(true)
+                .sorting(TraversalSorting::AlphabeticalByFileName),
+            vec![PathBuf::from("tests/fixtures/sample-02/dir")],
+            |_| Ok(false),
+        )?;
+        let mut db = io::Cursor::new(Vec::new());
+        export_database(&mut db, &traversal)?;
+        let db = db.into_inner();
+
+        let rows: Vec<_> = read::table(&db, "entries")
+            .into_iter()
+            .map(|(id, row)| {
+                // Disk usage and times of last modification depend on the checkout
+                let mut row = [&row[..5], &row[6..8]].concat();
+                row[0] = Integer(id);
+                row
+            })
+            .collect();
+        let text = |s: &str| Text(s.into());
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Integer(1),
+                    Null,
+                    text("tests/fixtures/sample-02/dir"),
+                    text("tests/fixtures/sample-02/dir"),
+                    text("dir"),
+                    Integer(1283),
+                    Integer(6)
+                ],
+                vec![
+                    Integer(2),
+                    Integer(1),
+                    text("c"),
+                    text("tests/fixtures/sample-02/dir/c"),
+                    text("file"),
+                    Integer(257),
+                    Integer(0)
+                ],
+                vec![
+                    Integer(3),
+                    Integer(1),
+                    text("d"),
+                    text("tests/fixtures/sample-02/dir/d"),
+                    text("file"),
+                    Integer(2),
+                    Integer(0)
+                ],
+                vec![
+                    Integer(4),
+                    Integer(1),
+                    text("empty-dir"),
+                    text("tests/fixtures/sample-02/dir/empty-dir"),
+                    text("dir"),
+                    Integer(0),
+                    Integer(1)
+                ],
+                vec![
+                    Integer(5),
+                    Integer(4),
+                    text(".gitkeep"),
+                    text("tests/fixtures/sample-02/dir/empty-dir/.gitkeep"),
+                    text("file"),
+                    Integer(0),
+                    Integer(0)
+                ],
+                vec![
+                    Integer(6),
+                    Integer(1),
+                    text("sub"),
+                    text("tests/fixtures/sample-02/dir/sub"),
+                    text("dir"),
+                    Integer(1024),
+                    Integer(1)
+                ],
+                vec![
+                    Integer(7),
+                    Integer(6),
+                    text("e"),
+                    text("tests/fixtures/sample-02/dir/sub/e"),
+                    text("file"),
+                    Integer(1024),
+                    Integer(0)
+                ],
+            ]
+        );
+        let by_parent: Vec<_> = read::table(&db, "entries_by_parent")
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect();
+        assert_eq!(
+            by_parent,
+            [
+                (None, 1),
+                (Some(1), 2),
+                (Some(1), 3),
+                (Some(1), 4),
+                (Some(1), 6),
+                (Some(4), 5),
+                (Some(6), 7)
+            ]
+            .iter()
+            .map(|(parent_id, id)| vec![parent_id.map_or(Null, Integer), Integer(*id)])
+            .collect::<Vec<_>>()
+        );
+        assert_eq!(read::table(&db, "errors"), vec![]);
+        Ok(())
+    }
+}
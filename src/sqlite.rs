@@ -0,0 +1,752 @@
+//! Writing SQLite database files, to be queried with `sqlite3` or any other program using SQLite.
+//!
+//! Only what's needed to write a database once from start to end is implemented: rows are added to tables in the order
+//! of their rowids, and indexes are written from keys sorted beforehand. Pages are written as soon as they are full,
+//! with the b-trees built from the bottom up, so only the page being filled is kept in memory. The schema is written
+//! last, into the first page, which is why the output needs to be seekable. There are no free pages and no journal.
+use std::io::{self, Seek, SeekFrom, Write};
+
+const PAGE_SIZE: usize = 4096;
+/// The size of the header of the database, at the start of the first page
+const FILE_HEADER_SIZE: usize = 100;
+const TABLE_LEAF: u8 = 0x0d;
+const TABLE_INTERIOR: u8 = 0x05;
+const INDEX_LEAF: u8 = 0x0a;
+const INDEX_INTERIOR: u8 = 0x02;
+/// The most bytes of a row kept on the page of a table, before the rest overflows onto pages of its own
+const MAX_LOCAL_TABLE: usize = PAGE_SIZE - 35;
+/// The most bytes of a key kept on the page of an index
+const MAX_LOCAL_INDEX: usize = (PAGE_SIZE - 12) * 64 / 255 - 23;
+/// The least bytes of a row kept on the page of a table if the rest of it overflows
+const MIN_LOCAL: usize = (PAGE_SIZE - 12) * 32 / 255 - 23;
+/// The version of SQLite said to have written the database, which is only shown by tools
+const SQLITE_VERSION_NUMBER: u32 = 3_008_000;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_owned())
+}
+
+/// The value of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value<'a> {
+    Null,
+    Integer(i64),
+    Text(&'a str),
+}
+
+/// A table or an index as row of the schema table
+struct SchemaEntry {
+    kind: &'static str,
+    name: String,
+    table: String,
+    root_page: u32,
+    sql: String,
+}
+
+/// A database being written to `out`, one table or index after another.
+pub struct Database<W: Write + Seek> {
+    out: W,
+    /// The number of the page written next, starting at 1
+    next_page: u32,
+    schema: Vec<SchemaEntry>,
+}
+
+impl<W: Write + Seek> Database<W> {
+    /// Start writing a database at the current position of `out`, which should be its start.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        // The first page holds the schema, which is only known once all tables are written
+        out.write_all(&[0; PAGE_SIZE])?;
+        Ok(Database {
+            out,
+            next_page: 2,
+            schema: Vec::new(),
+        })
+    }
+
+    /// Create the table `name` with the `CREATE TABLE` statement `sql`, to add its rows to.
+    pub fn table(&mut self, name: &str, sql: &str) -> Table<'_, W> {
+        Table {
+            name: name.to_owned(),
+            sql: sql.to_owned(),
+            page: Page::new(TABLE_LEAF, 0),
+            last_rowid: None,
+            leaves: Vec::new(),
+            db: self,
+        }
+    }
+
+    /// Create the index `name` on `table` with the `CREATE INDEX` statement `sql`, holding `keys`.
+    ///
+    /// Each key is made of the values of the indexed columns followed by the rowid of the row they belong to. Keys must
+    /// be sorted like SQLite compares them, with `NULL` before integers and integers before text.
+    pub fn index<'a>(
+        &mut self,
+        name: &str,
+        table: &str,
+        sql: &str,
+        keys: impl IntoIterator<Item = impl AsRef<[Value<'a>]>>,
+    ) -> io::Result<()> {
+        let mut keys = keys.into_iter().map(|key| record(key.as_ref())).peekable();
+        let mut page = Page::new(INDEX_LEAF, 0);
+        let mut last_key = Vec::new();
+        // Each leaf with the key separating it from the next one, which is in neither of them
+        let mut leaves = Vec::new();
+        while let Some(key) = keys.next() {
+            if key.len() > MAX_LOCAL_INDEX {
+                return Err(invalid("Keys of indexes must fit onto their page"));
+            }
+            let cell = index_cell(&key);
+            if page.fits(cell.len()) {
+                page.push(cell);
+                last_key = key;
+                continue;
+            }
+            if keys.peek().is_some() {
+                // The key which doesn't fit separates this leaf from the next one
+                let leaf = std::mem::replace(&mut page, Page::new(INDEX_LEAF, 0));
+                leaves.push((self.write_page(&leaf.finish(None))?, Some(key)));
+            } else {
+                // The last key can't separate two leaves, so the one before it does
+                page.pop();
+                let separator = std::mem::replace(&mut last_key, key);
+                let leaf = std::mem::replace(&mut page, Page::new(INDEX_LEAF, 0));
+                leaves.push((self.write_page(&leaf.finish(None))?, Some(separator)));
+                page.push(cell);
+            }
+        }
+        leaves.push((self.write_page(&page.finish(None))?, None));
+
+        let root_page = self.write_interior_pages(leaves, INDEX_INTERIOR, |child, key| {
+            let key = key
+                .as_ref()
+                .expect("only the last child to have no key after it");
+            let mut cell = child.to_be_bytes().to_vec();
+            cell.extend(index_cell(key));
+            cell
+        })?;
+        self.schema.push(SchemaEntry {
+            kind: "index",
+            name: name.to_owned(),
+            table: table.to_owned(),
+            root_page,
+            sql: sql.to_owned(),
+        });
+        Ok(())
+    }
+
+    /// Write the schema and the header of the database, and return what it was written to.
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut page = Page::new(TABLE_LEAF, FILE_HEADER_SIZE);
+        for (rowid, entry) in self.schema.iter().enumerate() {
+            let payload = record(&[
+                Value::Text(entry.kind),
+                Value::Text(&entry.name),
+                Value::Text(&entry.table),
+                Value::Integer(entry.root_page.into()),
+                Value::Text(&entry.sql),
+            ]);
+            let mut cell = Vec::new();
+            put_varint(&mut cell, payload.len() as u64);
+            put_varint(&mut cell, rowid as u64 + 1);
+            cell.extend(payload);
+            if !page.fits(cell.len()) {
+                return Err(invalid("The schema must fit onto the first page"));
+            }
+            page.push(cell);
+        }
+        let mut first_page = page.finish(None);
+        first_page[..FILE_HEADER_SIZE].copy_from_slice(&file_header(self.next_page - 1));
+        self.out.seek(SeekFrom::Start(0))?;
+        self.out.write_all(&first_page)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Write `page` after all pages written so far, and return its number.
+    fn write_page(&mut self, page: &[u8]) -> io::Result<u32> {
+        self.out.write_all(page)?;
+        self.next_page += 1;
+        Ok(self.next_page - 1)
+    }
+
+    /// Write the cell of a table leaf with `prefix` followed by `payload`, of which only `local` bytes are kept on the
+    /// page. The rest of it is written onto overflow pages right away.
+    fn table_cell(
+        &mut self,
+        mut prefix: Vec<u8>,
+        payload: &[u8],
+        local: usize,
+    ) -> io::Result<Vec<u8>> {
+        prefix.extend_from_slice(&payload[..local]);
+        let overflow = &payload[local..];
+        if overflow.is_empty() {
+            return Ok(prefix);
+        }
+        let first_page = self.next_page;
+        prefix.extend_from_slice(&first_page.to_be_bytes());
+        let num_pages = overflow.chunks(PAGE_SIZE - 4).len() as u32;
+        for (idx, chunk) in overflow.chunks(PAGE_SIZE - 4).enumerate() {
+            let next_page = match idx as u32 + 1 {
+                next if next < num_pages => first_page + next,
+                _ => 0,
+            };
+            let mut page = vec![0; PAGE_SIZE];
+            page[..4].copy_from_slice(&next_page.to_be_bytes());
+            page[4..4 + chunk.len()].copy_from_slice(chunk);
+            self.write_page(&page)?;
+        }
+        Ok(prefix)
+    }
+
+    /// Write the interior pages of a b-tree level by level, starting with the `children` of the lowest level along with
+    /// their key, until a single page is left, whose number is returned.
+    ///
+    /// The key of a child is what separates it from the next one, and is put into a cell with the child by `cell`. The
+    /// last child of each page is pointed to from its header instead, and its key goes up a level along with the page.
+    fn write_interior_pages<K: Clone>(
+        &mut self,
+        mut children: Vec<(u32, K)>,
+        kind: u8,
+        cell: impl Fn(u32, &K) -> Vec<u8>,
+    ) -> io::Result<u32> {
+        while children.len() > 1 {
+            let num_children = children.len();
+            let mut parents = Vec::new();
+            let mut first = 0;
+            while first < num_children {
+                let mut page = Page::new(kind, 0);
+                let mut last = first;
+                while last + 1 < num_children {
+                    let (child, key) = &children[last];
+                    let cell = cell(*child, key);
+                    if !page.fits(cell.len()) {
+                        break;
+                    }
+                    page.push(cell);
+                    last += 1;
+                }
+                // Pages need at least one cell, so the last one can't be left with a single child
+                if last + 2 == num_children && page.cells.len() > 1 {
+                    page.pop();
+                    last -= 1;
+                }
+                let (last_child, key) = children[last].clone();
+                parents.push((self.write_page(&page.finish(Some(last_child)))?, key));
+                first = last + 1;
+            }
+            children = parents;
+        }
+        Ok(children[0].0)
+    }
+}
+
+/// A table being filled with rows, in the order of their rowids.
+pub struct Table<'db, W: Write + Seek> {
+    db: &'db mut Database<W>,
+    name: String,
+    sql: String,
+    /// The leaf being filled
+    page: Page,
+    last_rowid: Option<i64>,
+    /// The leaves written so far along with the largest rowid in them
+    leaves: Vec<(u32, i64)>,
+}
+
+impl<W: Write + Seek> Table<'_, W> {
+    /// Add a row with `rowid` and the `values` of its columns, with `NULL` in place of an `INTEGER PRIMARY KEY`
+    /// column. The `rowid` must be larger than those of all rows added before.
+    pub fn insert(&mut self, rowid: i64, values: &[Value<'_>]) -> io::Result<()> {
+        if matches!(self.last_rowid, Some(last) if rowid <= last) {
+            return Err(invalid("Rows must be added in the order of their rowid"));
+        }
+        let payload = record(values);
+        let mut prefix = Vec::new();
+        put_varint(&mut prefix, payload.len() as u64);
+        put_varint(&mut prefix, rowid as u64);
+        let local = local_size(payload.len(), MAX_LOCAL_TABLE);
+        let cell_len = prefix.len() + local + if local < payload.len() { 4 } else { 0 };
+        if !self.page.fits(cell_len) {
+            self.write_leaf()?;
+        }
+        let cell = self.db.table_cell(prefix, &payload, local)?;
+        self.page.push(cell);
+        self.last_rowid = Some(rowid);
+        Ok(())
+    }
+
+    /// Write what's left of the table, after which it is part of the schema.
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.page.cells.is_empty() || self.leaves.is_empty() {
+            self.write_leaf()?;
+        }
+        let leaves = std::mem::take(&mut self.leaves);
+        let root_page = self
+            .db
+            .write_interior_pages(leaves, TABLE_INTERIOR, |child, rowid| {
+                let mut cell = child.to_be_bytes().to_vec();
+                put_varint(&mut cell, *rowid as u64);
+                cell
+            })?;
+        self.db.schema.push(SchemaEntry {
+            kind: "table",
+            name: self.name.clone(),
+            table: std::mem::take(&mut self.name),
+            root_page,
+            sql: std::mem::take(&mut self.sql),
+        });
+        Ok(())
+    }
+
+    fn write_leaf(&mut self) -> io::Result<()> {
+        let leaf = std::mem::replace(&mut self.page, Page::new(TABLE_LEAF, 0));
+        let page = self.db.write_page(&leaf.finish(None))?;
+        self.leaves
+            .push((page, self.last_rowid.unwrap_or_default()));
+        Ok(())
+    }
+}
+
+/// A page of a b-tree being filled with cells, in the order of their keys
+struct Page {
+    kind: u8,
+    /// Where the header of the page starts, which is after the header of the database on the first page
+    start: usize,
+    cells: Vec<Vec<u8>>,
+    /// The bytes taken by cells and the pointers to them
+    used: usize,
+}
+
+impl Page {
+    fn new(kind: u8, start: usize) -> Self {
+        Page {
+            kind,
+            start,
+            cells: Vec::new(),
+            used: 0,
+        }
+    }
+
+    fn header_size(&self) -> usize {
+        match self.kind {
+            TABLE_INTERIOR | INDEX_INTERIOR => 12,
+            _ => 8,
+        }
+    }
+
+    fn fits(&self, cell_len: usize) -> bool {
+        self.start + self.header_size() + self.used + 2 + cell_len <= PAGE_SIZE
+    }
+
+    fn push(&mut self, cell: Vec<u8>) {
+        self.used += 2 + cell.len();
+        self.cells.push(cell);
+    }
+
+    fn pop(&mut self) {
+        if let Some(cell) = self.cells.pop() {
+            self.used -= 2 + cell.len();
+        }
+    }
+
+    /// Lay out the page, with the pointers to its cells after its header and the cells at its end. Interior pages
+    /// point to their `right_child`.
+    fn finish(self, right_child: Option<u32>) -> Vec<u8> {
+        let mut page = vec![0; PAGE_SIZE];
+        let header = self.start;
+        let mut pointer = header + self.header_size();
+        let mut content = PAGE_SIZE;
+        for cell in &self.cells {
+            content -= cell.len();
+            page[content..content + cell.len()].copy_from_slice(cell);
+            page[pointer..pointer + 2].copy_from_slice(&(content as u16).to_be_bytes());
+            pointer += 2;
+        }
+        page[header] = self.kind;
+        page[header + 3..header + 5].copy_from_slice(&(self.cells.len() as u16).to_be_bytes());
+        page[header + 5..header + 7].copy_from_slice(&(content as u16).to_be_bytes());
+        if let Some(right_child) = right_child {
+            page[header + 8..header + 12].copy_from_slice(&right_child.to_be_bytes());
+        }
+        page
+    }
+}
+
+/// The header of a database of `num_pages` pages, with text in UTF-8.
+fn file_header(num_pages: u32) -> [u8; FILE_HEADER_SIZE] {
+    let mut header = [0; FILE_HEADER_SIZE];
+    header[..16].copy_from_slice(b"SQLite format 3\0");
+    header[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+    // Written and read with a rollback journal, without bytes reserved at the end of pages
+    header[18] = 1;
+    header[19] = 1;
+    // The fractions of pages payloads may take, which must be these
+    header[21] = 64;
+    header[22] = 32;
+    header[23] = 32;
+    let mut put = |offset: usize, value: u32| {
+        header[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    };
+    // The amount of changes, which the size of the database is valid for as it's the same
+    put(24, 1);
+    put(28, num_pages);
+    put(92, 1);
+    // The version of the schema, and that of its format
+    put(40, 1);
+    put(44, 4);
+    // UTF-8
+    put(56, 1);
+    put(96, SQLITE_VERSION_NUMBER);
+    header
+}
+
+/// The cell of an index leaf holding `key`, which is also the end of those of interior pages.
+fn index_cell(key: &[u8]) -> Vec<u8> {
+    let mut cell = Vec::with_capacity(key.len() + 2);
+    put_varint(&mut cell, key.len() as u64);
+    cell.extend_from_slice(key);
+    cell
+}
+
+/// The amount of bytes of a payload of `len` bytes that are kept on its page, if at most `max_local` can be.
+fn local_size(len: usize, max_local: usize) -> usize {
+    if len <= max_local {
+        return len;
+    }
+    // The overflow is made to fill its last page if that keeps enough on the page itself
+    match MIN_LOCAL + (len - MIN_LOCAL) % (PAGE_SIZE - 4) {
+        local if local <= max_local => local,
+        _ => MIN_LOCAL,
+    }
+}
+
+/// Encode `values` in the record format, a header with the type of each value followed by the values themselves.
+fn record(values: &[Value<'_>]) -> Vec<u8> {
+    let mut types = Vec::with_capacity(values.len());
+    let mut body = Vec::new();
+    for value in values {
+        let serial_type = match *value {
+            Value::Null => 0,
+            Value::Integer(0) => 8,
+            Value::Integer(1) => 9,
+            Value::Integer(n) => {
+                let (serial_type, len) = match n {
+                    -0x80..=0x7f => (1, 1),
+                    -0x8000..=0x7fff => (2, 2),
+                    -0x80_0000..=0x7f_ffff => (3, 3),
+                    -0x8000_0000..=0x7fff_ffff => (4, 4),
+                    -0x8000_0000_0000..=0x7fff_ffff_ffff => (5, 6),
+                    _ => (6, 8),
+                };
+                body.extend_from_slice(&n.to_be_bytes()[8 - len..]);
+                serial_type
+            }
+            Value::Text(text) => {
+                body.extend_from_slice(text.as_bytes());
+                13 + 2 * text.len() as u64
+            }
+        };
+        put_varint(&mut types, serial_type);
+    }
+    // The size of the header includes its own
+    let mut header_len = types.len() + 1;
+    while types.len() + varint_len(header_len as u64) != header_len {
+        header_len = types.len() + varint_len(header_len as u64);
+    }
+    let mut record = Vec::with_capacity(header_len + body.len());
+    put_varint(&mut record, header_len as u64);
+    record.extend(types);
+    record.extend(body);
+    record
+}
+
+fn varint_len(value: u64) -> usize {
+    let mut buf = Vec::new();
+    put_varint(&mut buf, value);
+    buf.len()
+}
+
+/// Append `value` to `out` with 7 bits per byte, the most significant first, and the high bit set on all bytes but the
+/// last. Values needing more than 8 such bytes use all 8 bits of the 9th.
+fn put_varint(out: &mut Vec<u8>, value: u64) {
+    if value >> 56 != 0 {
+        let mut rest = value >> 8;
+        let mut bytes = [0u8; 9];
+        bytes[8] = value as u8;
+        for byte in bytes[..8].iter_mut().rev() {
+            *byte = (rest & 0x7f) as u8 | 0x80;
+            rest >>= 7;
+        }
+        out.extend_from_slice(&bytes);
+        return;
+    }
+    let mut bytes = Vec::with_capacity(8);
+    let mut rest = value;
+    loop {
+        bytes.push((rest & 0x7f) as u8 | if bytes.is_empty() { 0 } else { 0x80 });
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    out.extend(bytes.iter().rev());
+}
+
+/// Reading databases back, as SQLite itself can't be used in tests.
+#[cfg(test)]
+pub(crate) mod read {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum OwnedValue {
+        Null,
+        Integer(i64),
+        Text(String),
+    }
+
+    impl From<Value<'_>> for OwnedValue {
+        fn from(value: Value<'_>) -> Self {
+            match value {
+                Value::Null => OwnedValue::Null,
+                Value::Integer(n) => OwnedValue::Integer(n),
+                Value::Text(text) => OwnedValue::Text(text.into()),
+            }
+        }
+    }
+
+    fn page(db: &[u8], number: u32) -> &[u8] {
+        &db[(number as usize - 1) * PAGE_SIZE..number as usize * PAGE_SIZE]
+    }
+
+    fn u16_at(bytes: &[u8], offset: usize) -> usize {
+        u16::from_be_bytes(bytes[offset..offset + 2].try_into().unwrap()) as usize
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    pub fn varint(bytes: &[u8]) -> (u64, usize) {
+        let mut value = 0;
+        for (idx, byte) in bytes.iter().enumerate().take(9) {
+            if idx == 8 {
+                return ((value << 8) | u64::from(*byte), 9);
+            }
+            value = (value << 7) | u64::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return (value, idx + 1);
+            }
+        }
+        unreachable!("varints to end")
+    }
+
+    fn values(record: &[u8]) -> Vec<OwnedValue> {
+        let (header_len, mut offset) = varint(record);
+        let mut body = header_len as usize;
+        let mut values = Vec::new();
+        while offset < header_len as usize {
+            let (serial_type, len) = varint(&record[offset..]);
+            offset += len;
+            let value = match serial_type {
+                0 => OwnedValue::Null,
+                8 => OwnedValue::Integer(0),
+                9 => OwnedValue::Integer(1),
+                1..=6 => {
+                    let len = [1, 2, 3, 4, 6, 8][serial_type as usize - 1];
+                    let bytes = &record[body..body + len];
+                    let mut n = if bytes[0] & 0x80 != 0 { -1i64 } else { 0 };
+                    for byte in bytes {
+                        n = (n << 8) | i64::from(*byte);
+                    }
+                    body += len;
+                    OwnedValue::Integer(n)
+                }
+                _ => {
+                    let len = (serial_type as usize - 13) / 2;
+                    let text = String::from_utf8(record[body..body + len].to_vec()).unwrap();
+                    body += len;
+                    OwnedValue::Text(text)
+                }
+            };
+            values.push(value);
+        }
+        values
+    }
+
+    /// The payload starting at `cell`, `len` bytes long, along with those on overflow pages.
+    fn payload(db: &[u8], cell: &[u8], len: usize, max_local: usize) -> Vec<u8> {
+        let local = local_size(len, max_local);
+        let mut payload = cell[..local].to_vec();
+        let mut next = if local < len { u32_at(cell, local) } else { 0 };
+        while next != 0 {
+            let overflow = page(db, next);
+            let take = (len - payload.len()).min(PAGE_SIZE - 4);
+            payload.extend_from_slice(&overflow[4..4 + take]);
+            next = u32_at(overflow, 0);
+        }
+        payload
+    }
+
+    /// Append all rows of the b-tree at page `number` to `out`, along with their rowid for tables, in order.
+    fn rows(db: &[u8], number: u32, out: &mut Vec<(i64, Vec<OwnedValue>)>) {
+        let page = page(db, number);
+        let header = if number == 1 { FILE_HEADER_SIZE } else { 0 };
+        let kind = page[header];
+        let is_interior = kind == TABLE_INTERIOR || kind == INDEX_INTERIOR;
+        let pointers = header + if is_interior { 12 } else { 8 };
+        for idx in 0..u16_at(page, header + 3) {
+            let mut cell = &page[u16_at(page, pointers + idx * 2)..];
+            if is_interior {
+                rows(db, u32_at(cell, 0), out);
+                cell = &cell[4..];
+                if kind == TABLE_INTERIOR {
+                    continue;
+                }
+            }
+            let (len, offset) = varint(cell);
+            let (rowid, cell) = if kind == TABLE_LEAF {
+                let (rowid, rowid_len) = varint(&cell[offset..]);
+                (rowid as i64, &cell[offset + rowid_len..])
+            } else {
+                (0, &cell[offset..])
+            };
+            let max_local = if kind == TABLE_LEAF {
+                MAX_LOCAL_TABLE
+            } else {
+                MAX_LOCAL_INDEX
+            };
+            out.push((rowid, values(&payload(db, cell, len as usize, max_local))));
+        }
+        if is_interior {
+            rows(db, u32_at(page, header + 8), out);
+        }
+    }
+
+    /// All rows of the table or index `name` in `db`, with their rowid for tables.
+    pub fn table(db: &[u8], name: &str) -> Vec<(i64, Vec<OwnedValue>)> {
+        assert_eq!(&db[..16], b"SQLite format 3\0");
+        assert_eq!(
+            u32_at(db, 28) as usize * PAGE_SIZE,
+            db.len(),
+            "the size in the header to be right"
+        );
+        let mut schema = Vec::new();
+        rows(db, 1, &mut schema);
+        let root = schema
+            .into_iter()
+            .find(|(_, row)| row[1] == OwnedValue::Text(name.into()))
+            .map(|(_, row)| match row[3] {
+                OwnedValue::Integer(root) => root as u32,
+                _ => panic!("root pages to be integers"),
+            })
+            .expect("the table to exist");
+        let mut table = Vec::new();
+        rows(db, root, &mut table);
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read::OwnedValue, *};
+
+    #[test]
+    fn varints_take_7_bits_per_byte_and_all_8_of_the_9th() {
+        let encoded = |value| {
+            let mut out = Vec::new();
+            put_varint(&mut out, value);
+            out
+        };
+        assert_eq!(encoded(0), [0]);
+        assert_eq!(encoded(127), [0x7f]);
+        assert_eq!(encoded(128), [0x81, 0]);
+        assert_eq!(encoded(300), [0x82, 0x2c]);
+        assert_eq!(encoded(u64::MAX), [0xff; 9]);
+        for value in &[
+            0,
+            1,
+            240,
+            2287,
+            67823,
+            1 << 40,
+            (1 << 56) - 1,
+            1 << 56,
+            u64::MAX,
+        ] {
+            assert_eq!(read::varint(&encoded(*value)), (*value, varint_len(*value)));
+        }
+    }
+
+    #[test]
+    fn tables_and_indexes_are_written_across_many_pages_and_read_back() -> io::Result<()> {
+        let long_text = "long ".repeat(3000);
+        let texts: Vec<_> = (0..20_000i64)
+            .map(|rowid| match rowid {
+                42 => long_text.clone(),
+                _ => format!("row {}", rowid),
+            })
+            .collect();
+        let row = |rowid: i64| {
+            vec![
+                Value::Null,
+                Value::Integer(rowid * 1_000_000_007 - 1_000_000),
+                Value::Text(&texts[rowid as usize]),
+                if rowid % 3 == 0 {
+                    Value::Null
+                } else {
+                    Value::Integer(rowid % 3)
+                },
+            ]
+        };
+
+        let mut db = Database::new(io::Cursor::new(Vec::new()))?;
+        let mut table = db.table(
+            "rows",
+            "CREATE TABLE rows (id INTEGER PRIMARY KEY, n, text, parity)",
+        );
+        for rowid in 1..20_000 {
+            table.insert(rowid, &row(rowid))?;
+        }
+        assert!(table.insert(10, &row(10)).is_err(), "rowids must grow");
+        table.finish()?;
+        db.table("empty", "CREATE TABLE empty (a)").finish()?;
+        let mut keys: Vec<_> = (1..20_000i64)
+            .map(|rowid| (Some(rowid % 3).filter(|parity| *parity != 0), rowid))
+            .collect();
+        keys.sort();
+        let keys: Vec<_> = keys
+            .into_iter()
+            .map(|(parity, rowid)| {
+                [
+                    parity.map_or(Value::Null, Value::Integer),
+                    Value::Integer(rowid),
+                ]
+            })
+            .collect();
+        db.index(
+            "rows_by_parity",
+            "rows",
+            "CREATE INDEX rows_by_parity ON rows(parity)",
+            &keys,
+        )?;
+        let db = db.finish()?.into_inner();
+
+        let rows = read::table(&db, "rows");
+        assert_eq!(rows.len(), 19_999);
+        for (rowid, values) in rows {
+            let expected: Vec<OwnedValue> = row(rowid).into_iter().map(Into::into).collect();
+            assert_eq!(values, expected);
+        }
+        assert_eq!(read::table(&db, "empty"), vec![]);
+        let index: Vec<_> = read::table(&db, "rows_by_parity")
+            .into_iter()
+            .map(|(_, values)| values)
+            .collect();
+        let expected: Vec<Vec<OwnedValue>> = keys
+            .iter()
+            .map(|key| key.iter().copied().map(Into::into).collect())
+            .collect();
+        assert_eq!(index, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Helpers for tests of the library and the binary alike.
+use std::{
+    fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// An empty directory for a single test, removed with all its content when dropped, even if the test fails.
+pub struct TempDir {
+    pub root: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty directory named after `name`, the process and the directories created before by it, so tests
+    /// running at the same time never share one.
+    pub fn new(name: &str) -> io::Result<TempDir> {
+        static CREATED: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "dua-{}-{}-{}",
+            name,
+            std::process::id(),
+            CREATED.fetch_add(1, Ordering::Relaxed)
+        ));
+        if root.exists() {
+            fs::remove_dir_all(&root)?;
+        }
+        fs::create_dir_all(&root)?;
+        Ok(TempDir { root })
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.root).ok();
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.root
+    }
+}
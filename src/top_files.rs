@@ -0,0 +1,178 @@
+use crate::{crossdev, utc_date_time, walk::Walk, InodeFilter, WalkOptions, WalkResult};
+use anyhow::Result;
+use colored::Colorize;
+use filesize::PathExt;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// A file along with what we know about it, ordered by size first.
+type Candidate = (u128, PathBuf, Option<SystemTime>);
+
+/// The largest files seen so far, up to a given amount of them
+pub(crate) struct LargestFiles {
+    files: BinaryHeap<Reverse<Candidate>>,
+    capacity: usize,
+}
+
+impl LargestFiles {
+    pub(crate) fn new(capacity: usize) -> Self {
+        LargestFiles {
+            files: BinaryHeap::with_capacity(capacity + 1),
+            capacity,
+        }
+    }
+
+    /// Keep the file at `path` if it's among the largest, along with `time`, which is written with it.
+    pub(crate) fn push(&mut self, size: u128, path: PathBuf, time: Option<SystemTime>) {
+        self.files.push(Reverse((size, path, time)));
+        if self.files.len() > self.capacity {
+            self.files.pop();
+        }
+    }
+
+    /// Write a line for each file to `out`, the largest first, with its size, time in UTC, and path.
+    pub(crate) fn write(
+        self,
+        out: &mut impl io::Write,
+        walk_options: &WalkOptions,
+    ) -> io::Result<()> {
+        // Sorting the reversed candidates puts the largest file first
+        for Reverse((num_bytes, path, time)) in self.files.into_sorted_vec() {
+            writeln!(
+                out,
+                "{:>byte_column_width$} {:<19} {}",
+                walk_options
+                    .byte_format
+                    .display(num_bytes)
+                    .to_string()
+                    .as_str()
+                    .green(),
+                time.map(utc_date_time).unwrap_or_else(|| "-".into()),
+                path.display(),
+                byte_column_width = walk_options.byte_format.width()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Walk the given `paths` and write the `num_files` largest files among them to `out`, the largest first, each
+/// with its size, time of last modification in UTC, and path.
+///
+/// Only the largest files seen so far are kept in memory, not the tree of all entries, so this works on trees too
+/// large to aggregate. If `err` is set, it shows the entries and bytes of files seen so far while walking.
+/// If the walk is [interrupted][crate::INTERRUPTED], the files seen so far are written along with a note that larger
+/// ones may be missing.
+pub fn top_files(
+    mut out: impl io::Write,
+    err: Option<impl io::Write>,
+    walk_options: WalkOptions,
+    num_files: usize,
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Result<WalkResult> {
+    let mut res = WalkResult::default();
+    let mut largest = LargestFiles::new(num_files);
+    let mut inodes = InodeFilter::default();
+    let mut walk = Walk::new(&walk_options, err)?;
+
+    for path in paths {
+        walk.path(path.as_ref(), |entry, device_id| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            let m = match entry.client_state {
+                Some(Ok(ref m))
+                    if !m.is_dir()
+                        && (walk_options.count_hard_links || inodes.add(m))
+                        && (walk_options.cross_filesystems
+                            || crossdev::is_same_device(device_id, m)) =>
+                {
+                    m
+                }
+                Some(Ok(_)) | None => return None,
+                Some(Err(_)) => {
+                    res.num_errors += 1;
+                    return None;
+                }
+            };
+            let path = entry.path();
+            let file_size = if walk_options.apparent_size {
+                m.len()
+            } else {
+                match path.size_on_disk_fast(m) {
+                    Ok(size) => size,
+                    Err(_) => {
+                        res.num_errors += 1;
+                        0
+                    }
+                }
+            } as u128;
+            largest.push(file_size, path, m.modified().ok());
+            Some(file_size)
+        })?;
+    }
+
+    walk.clear_progress();
+    largest.write(&mut out, &walk_options)?;
+    if walk.is_incomplete {
+        writeln!(out, "{}", "(incomplete, larger files may be missing)".red())?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ByteFormat;
+
+    #[test]
+    fn it_writes_the_largest_files_first() -> Result<()> {
+        let mut out = Vec::new();
+        let res = top_files(
+            &mut out,
+            None::<io::Sink>,
+            WalkOptions::default()
+                .threads(1)
+                .byte_format(ByteFormat::Bytes)
+                .apparent_size(true),
+            2,
+            vec![
+                Path::new("tests/fixtures/sample-01"),
+                Path::new("tests/fixtures/sample-02"),
+            ],
+        )?;
+        let out = String::from_utf8(out)?;
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(
+            lines.len(),
+            2,
+            "only the requested amount of files is listed"
+        );
+        for (line, (size, path)) in lines.iter().zip(&[
+            ("1000000 b", "tests/fixtures/sample-01/dir/dir-a.1mb"),
+            (
+                "256000 b",
+                "tests/fixtures/sample-01/dir/sub/dir-sub-a.256kb",
+            ),
+        ]) {
+            assert!(line.contains(size), "{:?} should contain {:?}", line, size);
+            assert!(
+                line.ends_with(path),
+                "{:?} should end with {:?}",
+                line,
+                path
+            );
+        }
+        assert_eq!(res.num_errors, 0);
+        Ok(())
+    }
+}
@@ -1,24 +1,166 @@
-use crate::{crossdev, get_size_or_panic, InodeFilter, WalkOptions};
-use anyhow::Result;
-use filesize::PathExt;
-use petgraph::{graph::NodeIndex, stable_graph::StableGraph, Directed, Direction};
+use crate::{
+    filesystem::{EntrySize, Filesystem, LocalFilesystem},
+    get_entry_or_panic, ByteFormat, WalkOptions,
+};
+use anyhow::{bail, Result};
+use petgraph::{
+    graph::NodeIndex,
+    stable_graph::StableGraph,
+    visit::{Bfs, Dfs},
+    Directed, Direction,
+};
 use std::{
-    fs::Metadata,
+    convert::TryFrom,
     io,
+    num::NonZeroU32,
+    ops::{AddAssign, Deref, DerefMut},
     path::{Path, PathBuf},
-    time::{Duration, Instant},
+    sync::atomic::Ordering,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+pub use crate::names::{Name, Names};
+
 pub type TreeIndex = NodeIndex;
-pub type Tree = StableGraph<EntryData, (), Directed>;
 
-#[derive(Eq, PartialEq, Debug, Default, Clone)]
+/// The entries of a traversal, each directory linked to the entries within it, along with their names.
+///
+/// It's used like the graph it dereferences to, and names of entries are looked up in `names`.
+#[derive(Default, Clone, Debug)]
+pub struct Tree {
+    graph: StableGraph<EntryData, (), Directed>,
+    /// The names of all entries, each kept once however many entries have it
+    pub names: Names,
+}
+
+impl Tree {
+    pub fn new() -> Tree {
+        Tree::default()
+    }
+
+    /// The name of the entry at `index`.
+    pub fn name_of(&self, index: TreeIndex) -> &Path {
+        &self.names[self.graph[index].name]
+    }
+}
+
+impl Deref for Tree {
+    type Target = StableGraph<EntryData, (), Directed>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl DerefMut for Tree {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.graph
+    }
+}
+
+/// A time of last modification in whole seconds since the UNIX epoch, which takes 4 bytes instead of the 16 of a
+/// [`SystemTime`], also when it's optional.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Mtime(NonZeroU32);
+
+impl Mtime {
+    /// The time `secs` seconds after the UNIX epoch, or the latest time there is room for, in 2106, if it's later.
+    pub fn from_secs(secs: u64) -> Mtime {
+        let secs = u32::try_from(secs).map_or(u32::MAX - 1, |secs| secs.min(u32::MAX - 1));
+        Mtime(NonZeroU32::MIN.saturating_add(secs))
+    }
+
+    /// The seconds since the UNIX epoch.
+    pub fn secs(self) -> u64 {
+        u64::from(self.0.get() - 1)
+    }
+}
+
+/// Times before the UNIX epoch are kept as the epoch.
+impl From<SystemTime> for Mtime {
+    fn from(time: SystemTime) -> Self {
+        Mtime::from_secs(
+            time.duration_since(UNIX_EPOCH)
+                .map_or(0, |age| age.as_secs()),
+        )
+    }
+}
+
+impl From<Mtime> for SystemTime {
+    fn from(mtime: Mtime) -> Self {
+        UNIX_EPOCH + Duration::from_secs(mtime.secs())
+    }
+}
+
+/// An entry of a traversal, as kept in its tree.
+///
+/// It's kept small, as there is one per entry: names are kept once in [`Tree::names`], sizes of 64 bits are enough
+/// for 16 exabytes, and amounts of entries of 32 bits for 4 billion of them.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
 pub struct EntryData {
-    pub name: PathBuf,
+    /// The entry's name within its directory, or the path it was walked from for entries at the top, to look up in
+    /// [`Tree::names`].
+    pub name: Name,
     /// The entry's size in bytes. If it's a directory, the size is the aggregated file size of all children
-    pub size: u128,
+    pub size: u64,
+    /// The entry's size in bytes, measured the other way: disk usage if `size` is the apparent size, and vice versa.
+    pub alternate_size: u64,
+    /// The amount of entries within this directory and all of its subdirectories. Always 0 for files.
+    pub entry_count: u32,
+    /// If set, the entry is a directory, even if it is empty.
+    pub is_dir: bool,
+    /// If set, the entry is a symbolic link. It's a directory as well if the link was followed to one.
+    pub is_symlink: bool,
+    /// If set, the entry is a symbolic link whose target doesn't exist.
+    pub is_broken_link: bool,
+    /// The time the entry was last modified at, if known. For directories, it's the latest modification time of all
+    /// entries within them, which is unknown if they are empty.
+    pub mtime: Option<Mtime>,
     /// If set, the item meta-data could not be obtained
     pub metadata_io_error: bool,
+    /// If set, the directory contains a valid [`CACHEDIR_TAG`][crate::CACHEDIR_TAG], so its contents can be recreated.
+    pub is_cache_dir: bool,
+    /// If set, the directory contains a [`.git`][crate::GIT_DIR] directory, so it's the working tree of a git
+    /// repository.
+    pub is_git_repo: bool,
+    /// If set, the entry only exists within an archive, and its size is the one it has once unpacked.
+    pub is_in_archive: bool,
+    /// If set, the entry stands for `entry_count + 1` of the smallest files of its directory, which were left out to
+    /// keep fewer entries than [`WalkOptions::max_entries`] in memory. Its name tells how many there were.
+    pub is_collapsed: bool,
+}
+
+/// An error encountered while walking the filesystem, like a directory that couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraversalError {
+    /// The path of the entry the error is about
+    pub path: PathBuf,
+    pub kind: io::ErrorKind,
+    /// A description of the error, for humans
+    pub message: String,
+}
+
+impl TraversalError {
+    pub(crate) fn from_walk_error(err: &jwalk::Error, fallback_path: &Path) -> Self {
+        TraversalError {
+            path: err.path().unwrap_or(fallback_path).to_owned(),
+            kind: err
+                .io_error()
+                .map_or(io::ErrorKind::Other, |err| err.kind()),
+            message: match err.io_error() {
+                Some(err) => err.to_string(),
+                None => err.to_string(),
+            },
+        }
+    }
+
+    pub(crate) fn from_io_error(err: &io::Error, path: PathBuf) -> Self {
+        TraversalError {
+            path,
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
 }
 
 const REFRESH_RATE: Duration = Duration::from_millis(100);
@@ -34,27 +176,117 @@ pub struct Traversal {
     pub entries_traversed: u64,
     /// Total amount of IO errors encountered when traversing the filesystem
     pub io_errors: u64,
+    /// The IO errors encountered when traversing the filesystem. Might be fewer than `io_errors` if the traversal was
+    /// loaded from a file, which only knows which entries had errors.
+    pub errors: Vec<TraversalError>,
     /// Total amount of bytes seen during the traversal
     pub total_bytes: Option<u128>,
+    /// If set, `EntryData::size` is the apparent size of entries, otherwise it's their disk usage.
+    pub apparent_size: bool,
+    /// If set, the walk was stopped before all entries were seen, and sizes are lower than they really are.
+    pub is_incomplete: bool,
+    /// The amount of files which were collapsed into entries standing for several of them, to keep fewer entries
+    /// than [`WalkOptions::max_entries`].
+    pub collapsed_entries: u64,
+}
+
+/// The sizes and amount of entries, either of a single entry or summed up for all entries within a directory.
+#[derive(Default, Clone, Copy)]
+pub(crate) struct Totals {
+    pub(crate) size: u64,
+    pub(crate) alternate_size: u64,
+    pub(crate) entry_count: u32,
+    /// The latest modification time seen
+    pub(crate) mtime: Option<Mtime>,
+}
+
+impl AddAssign for Totals {
+    fn add_assign(&mut self, rhs: Self) {
+        self.size += rhs.size;
+        self.alternate_size += rhs.alternate_size;
+        self.entry_count += rhs.entry_count;
+        self.mtime = self.mtime.max(rhs.mtime);
+    }
+}
+
+/// Return the path of the entry at `node_idx` of `tree`, starting with the input path it was found in.
+pub fn path_of(tree: &Tree, mut node_idx: TreeIndex) -> PathBuf {
+    const THE_ROOT: usize = 1;
+    let mut entries = Vec::new();
+
+    while let Some(parent_idx) = tree
+        .neighbors_directed(node_idx, Direction::Incoming)
+        .next()
+    {
+        entries.push(get_entry_or_panic(tree, node_idx));
+        node_idx = parent_idx;
+    }
+    entries.push(get_entry_or_panic(tree, node_idx));
+    entries
+        .iter()
+        .rev()
+        .skip(THE_ROOT)
+        .fold(PathBuf::new(), |mut acc, entry| {
+            acc.push(&tree.names[entry.name]);
+            acc
+        })
 }
 
 impl Traversal {
+    /// Walk all `input` paths and build a tree of their entries, calling `update` regularly with what was seen so far.
+    ///
+    /// If `update` returns true or [`INTERRUPTED`][crate::INTERRUPTED] is set, the walk stops and the traversal
+    /// of all entries seen until then is returned, marked as incomplete.
     pub fn from_walk(
-        mut walk_options: WalkOptions,
+        walk_options: WalkOptions,
+        input: Vec<PathBuf>,
+        update: impl FnMut(&mut Traversal) -> Result<bool>,
+    ) -> Result<Traversal> {
+        let options = walk_options.clone();
+        let mut filesystem = LocalFilesystem::new(walk_options)?;
+        Traversal::from_filesystem_with_options(&mut filesystem, &options, input, update)
+    }
+
+    /// Walk all `input` paths of `filesystem` and build a tree of their entries, like [`Traversal::from_walk()`]
+    /// does for the local filesystem. Sizes are apparent sizes if `apparent_size` is set, and disk usage otherwise.
+    pub fn from_filesystem(
+        filesystem: &mut dyn Filesystem,
+        apparent_size: bool,
+        input: Vec<PathBuf>,
+        update: impl FnMut(&mut Traversal) -> Result<bool>,
+    ) -> Result<Traversal> {
+        let options = WalkOptions::default().apparent_size(apparent_size);
+        Traversal::from_filesystem_with_options(filesystem, &options, input, update)
+    }
+
+    /// Like [`Traversal::from_filesystem()`], with the sizes and the limit of entries of `walk_options`.
+    ///
+    /// Once there are more than [`WalkOptions::max_entries`], the smallest files are collapsed into one entry per
+    /// directory, named with their amount and size as formatted by [`WalkOptions::byte_format`].
+    pub fn from_filesystem_with_options(
+        filesystem: &mut dyn Filesystem,
+        walk_options: &WalkOptions,
         input: Vec<PathBuf>,
         mut update: impl FnMut(&mut Traversal) -> Result<bool>,
-    ) -> Result<Option<Traversal>> {
-        fn set_size_or_panic(tree: &mut Tree, node_idx: TreeIndex, current_size_at_depth: u128) {
-            tree.node_weight_mut(node_idx)
-                .expect("node for parent index we just retrieved")
-                .size = current_size_at_depth;
+    ) -> Result<Traversal> {
+        let apparent_size = walk_options.apparent_size;
+        // Collapsing stops freeing entries if there are mostly directories, so it's not tried again right away
+        let mut collapse_above = walk_options.max_entries;
+        fn set_size_or_panic(tree: &mut Tree, node_idx: TreeIndex, current_size_at_depth: Totals) {
+            let entry = tree
+                .node_weight_mut(node_idx)
+                .expect("node for parent index we just retrieved");
+            entry.size = current_size_at_depth.size;
+            entry.alternate_size = current_size_at_depth.alternate_size;
+            entry.entry_count = current_size_at_depth.entry_count;
+            entry.mtime = current_size_at_depth.mtime;
         }
         fn parent_or_panic(tree: &mut Tree, parent_node_idx: TreeIndex) -> TreeIndex {
             tree.neighbors_directed(parent_node_idx, Direction::Incoming)
                 .next()
                 .expect("every node in the iteration has a parent")
         }
-        fn pop_or_panic(v: &mut Vec<u128>) -> u128 {
+        fn pop_or_panic(v: &mut Vec<Totals>) -> Totals {
             v.pop().expect("sizes per level to be in sync with graph")
         }
 
@@ -64,84 +296,87 @@ impl Traversal {
             Traversal {
                 tree,
                 root_index,
+                apparent_size,
                 ..Default::default()
             }
         };
 
         let (mut previous_node_idx, mut parent_node_idx) = (t.root_index, t.root_index);
         let mut sizes_per_depth_level = Vec::new();
-        let mut current_size_at_depth: u128 = 0;
+        let mut current_size_at_depth = Totals::default();
         let mut previous_depth = 0;
-        let mut inodes = InodeFilter::default();
 
         let mut last_checked = Instant::now();
 
         const INITIAL_CHECK_INTERVAL: usize = 500;
         let mut check_instant_every = INITIAL_CHECK_INTERVAL;
-        if walk_options.threads == 0 {
-            // avoid using the global rayon pool, as it will keep a lot of threads alive after we are done.
-            // Also means that we will spin up a bunch of threads per root path, instead of reusing them.
-            walk_options.threads = num_cpus::get();
-        }
-
-        #[cfg(not(windows))]
-        fn size_on_disk(_parent: &Path, name: &Path, meta: &Metadata) -> io::Result<u64> {
-            name.size_on_disk_fast(meta)
-        }
-        #[cfg(windows)]
-        fn size_on_disk(parent: &Path, name: &Path, meta: &Metadata) -> io::Result<u64> {
-            parent.join(name).size_on_disk_fast(meta)
-        }
 
-        for path in input.into_iter() {
+        'walk: for path in input.into_iter() {
             let mut last_seen_eid = 0;
-            let device_id = crossdev::init(path.as_ref())?;
-            for (eid, entry) in walk_options
-                .iter_from_path(path.as_ref())
-                .into_iter()
-                .enumerate()
-            {
+            for (eid, entry) in filesystem.walk(&path)?.enumerate() {
                 t.entries_traversed += 1;
                 let mut data = EntryData::default();
                 match entry {
                     Ok(entry) => {
-                        data.name = if entry.depth < 1 {
-                            path.clone()
-                        } else {
-                            entry.file_name.into()
-                        };
-                        let file_size = match &entry.client_state {
-                            Some(Ok(ref m))
-                                if !m.is_dir()
-                                    && (walk_options.count_hard_links || inodes.add(m))
-                                    && (walk_options.cross_filesystems
-                                        || crossdev::is_same_device(device_id, m)) =>
-                            {
-                                if walk_options.apparent_size {
-                                    m.len()
-                                } else {
-                                    size_on_disk(&entry.parent_path, &data.name, m).unwrap_or_else(
-                                        |_| {
-                                            t.io_errors += 1;
-                                            data.metadata_io_error = true;
-                                            0
-                                        },
-                                    )
+                        data.is_dir = entry.is_dir;
+                        data.is_symlink = entry.is_symlink;
+                        data.is_broken_link = entry.is_broken_link;
+                        data.name = t.tree.names.intern(&entry.name);
+                        if let Some(err) = entry.metadata_error {
+                            t.io_errors += 1;
+                            t.errors.push(err);
+                            data.metadata_io_error = true;
+                        }
+                        let file_size = match entry.size {
+                            Some(EntrySize {
+                                apparent,
+                                disk_usage,
+                            }) => match (apparent_size, disk_usage) {
+                                (true, disk_usage) => Totals {
+                                    size: apparent,
+                                    alternate_size: disk_usage.unwrap_or(0),
+                                    ..Default::default()
+                                },
+                                (false, Ok(disk_usage)) => Totals {
+                                    size: disk_usage,
+                                    alternate_size: apparent,
+                                    ..Default::default()
+                                },
+                                (false, Err(err)) => {
+                                    t.io_errors += 1;
+                                    t.errors.push(err);
+                                    data.metadata_io_error = true;
+                                    Totals {
+                                        size: 0,
+                                        alternate_size: apparent,
+                                        ..Default::default()
+                                    }
                                 }
-                            }
-                            Some(Ok(_)) => 0,
-                            Some(Err(_)) => {
-                                t.io_errors += 1;
-                                data.metadata_io_error = true;
-                                0
-                            }
-                            None => 0, // a directory
-                        } as u128;
+                            },
+                            None => Totals::default(),
+                        };
+                        data.mtime = entry.mtime.map(Mtime::from);
+                        let file_size = Totals {
+                            entry_count: 1, // the entry itself, as seen by its parent
+                            mtime: data.mtime,
+                            ..file_size
+                        };
 
                         match (entry.depth, previous_depth) {
                             (n, p) if n > p => {
+                                // Directories include their own size, like the inode they take, which moves from
+                                // their parent's level to theirs
+                                let own = &t.tree[previous_node_idx];
+                                let own = Totals {
+                                    size: own.size,
+                                    alternate_size: own.alternate_size,
+                                    ..Default::default()
+                                };
+                                current_size_at_depth.size -= own.size;
+                                current_size_at_depth.alternate_size -= own.alternate_size;
                                 sizes_per_depth_level.push(current_size_at_depth);
-                                current_size_at_depth = file_size;
+                                current_size_at_depth = own;
+                                current_size_at_depth += file_size;
                                 parent_node_idx = previous_node_idx;
                             }
                             (n, p) if n < p => {
@@ -167,24 +402,59 @@ impl Traversal {
                             }
                         };
 
-                        data.size = file_size;
+                        if entry.is_cache_dir_tag {
+                            t.tree[parent_node_idx].is_cache_dir = true;
+                        }
+                        if entry.depth > 0
+                            && data.is_dir
+                            && !data.is_symlink
+                            && t.tree.names[data.name] == *Path::new(crate::GIT_DIR)
+                        {
+                            t.tree[parent_node_idx].is_git_repo = true;
+                        }
+                        data.size = file_size.size;
+                        data.alternate_size = file_size.alternate_size;
                         let entry_index = t.tree.add_node(data);
 
                         t.tree.add_edge(parent_node_idx, entry_index, ());
                         previous_node_idx = entry_index;
                         previous_depth = entry.depth;
+
+                        if entry.is_archive {
+                            let path = path_of(&t.tree, entry_index);
+                            if let Err(err) = t.add_archive_contents(entry_index, &path) {
+                                t.io_errors += 1;
+                                t.errors.push(TraversalError::from_io_error(&err, path));
+                                t.tree[entry_index].metadata_io_error = true;
+                            }
+                        }
+                        if let Some(max_entries) = walk_options.max_entries {
+                            if collapse_above.is_some_and(|limit| t.tree.node_count() > limit) {
+                                t.collapse_smallest_files(
+                                    max_entries / 2,
+                                    walk_options.byte_format,
+                                );
+                                collapse_above =
+                                    Some(max_entries.max(t.tree.node_count() + max_entries / 2));
+                            }
+                        }
                     }
-                    Err(_) => {
+                    Err(err) => {
                         if previous_depth == 0 {
-                            data.name = path.clone();
+                            data.name = t.tree.names.intern(&path);
                             let entry_index = t.tree.add_node(data);
                             t.tree.add_edge(parent_node_idx, entry_index, ());
                         }
 
-                        t.io_errors += 1
+                        t.io_errors += 1;
+                        t.errors.push(err);
                     }
                 }
 
+                if crate::INTERRUPTED.load(Ordering::Relaxed) {
+                    t.is_incomplete = true;
+                    break 'walk;
+                }
                 if eid != 0
                     && eid % check_instant_every == 0
                     && last_checked.elapsed() >= REFRESH_RATE
@@ -199,14 +469,15 @@ impl Traversal {
                     last_checked = now;
 
                     if update(&mut t)? {
-                        return Ok(None);
+                        t.is_incomplete = true;
+                        break 'walk;
                     }
                 }
             }
         }
 
         sizes_per_depth_level.push(current_size_at_depth);
-        current_size_at_depth = 0;
+        current_size_at_depth = Totals::default();
         for _ in 0..previous_depth {
             current_size_at_depth += pop_or_panic(&mut sizes_per_depth_level);
             set_size_or_panic(&mut t.tree, parent_node_idx, current_size_at_depth);
@@ -214,15 +485,454 @@ impl Traversal {
         }
         let root_size = t.recompute_root_size();
         set_size_or_panic(&mut t.tree, t.root_index, root_size);
-        t.total_bytes = Some(root_size);
+        t.total_bytes = Some(root_size.size.into());
+
+        Ok(t)
+    }
+
+    /// Walk the directory at `index` again, found at `path`, and replace all entries within it with those found now.
+    ///
+    /// The sizes of `index` and its parents are not recomputed. Hard links are only counted once within `path`, as
+    /// other parts of the tree are unknown to the new walk.
+    pub fn rescan(
+        &mut self,
+        index: TreeIndex,
+        path: PathBuf,
+        walk_options: WalkOptions,
+    ) -> Result<()> {
+        let walk_options = WalkOptions {
+            apparent_size: self.apparent_size,
+            ..walk_options
+        };
+        let scan = Traversal::from_walk(walk_options, vec![path.clone()], |_| Ok(false))?;
+        self.replace_directory(index, &path, scan)
+    }
+
+    /// Replace all entries within the directory at `index`, found at `path`, with those of `scan`, a walk of `path`.
+    ///
+    /// The sizes of `index` and its parents are not recomputed.
+    pub fn replace_directory(
+        &mut self,
+        index: TreeIndex,
+        path: &Path,
+        mut scan: Traversal,
+    ) -> Result<()> {
+        if scan.is_incomplete {
+            bail!("the walk was interrupted");
+        }
+        let scanned_root = match scan
+            .tree
+            .neighbors_directed(scan.root_index, Direction::Outgoing)
+            .next()
+        {
+            Some(idx) if scan.tree[idx].is_dir => idx,
+            _ => bail!("'{}' is not a directory anymore", path.display()),
+        };
+        if scan.apparent_size != self.apparent_size {
+            scan.toggle_apparent_size();
+        }
+
+        let previous_entries: Vec<_> = {
+            let tree = &*self.tree;
+            let mut bfs = Bfs::new(tree, index);
+            bfs.next(tree);
+            std::iter::from_fn(|| bfs.next(tree)).collect()
+        };
+        // Add the new entries first to not reuse the indices of the previous ones, which may still be referred to
+        self.copy_entries_below(index, &scan.tree, scanned_root);
+        for idx in &previous_entries {
+            self.tree.remove_node(*idx);
+        }
+        self.tree[index].metadata_io_error = scan.tree[scanned_root].metadata_io_error;
+        let errors_before = self.errors.len();
+        self.errors.retain(|err| !err.path.starts_with(path));
+        self.io_errors = (self.io_errors + scan.io_errors)
+            .saturating_sub((errors_before - self.errors.len()) as u64);
+        self.errors.extend(scan.errors);
+        self.entries_traversed = (self.entries_traversed + scan.entries_traversed)
+            .saturating_sub(previous_entries.len() as u64 + 1);
+        Ok(())
+    }
 
-        Ok(Some(t))
+    /// Add the entries within the archive at `index`, found at `path`, below it. The size of the archive stays the
+    /// space it takes.
+    fn add_archive_contents(&mut self, index: TreeIndex, path: &Path) -> io::Result<()> {
+        let mut contents = crate::archive::contents(path)?;
+        let scan = Traversal::from_filesystem(
+            &mut contents,
+            self.apparent_size,
+            vec![path.to_owned()],
+            |_| Ok(false),
+        )
+        .map_err(io::Error::other)?;
+        let scanned_root = scan.children(scan.root_index)[0];
+        self.copy_entries_below(index, &scan.tree, scanned_root);
+        let entries: Vec<_> = self.descendants(index).collect();
+        for idx in entries {
+            self.tree[idx].is_in_archive = true;
+        }
+        Ok(())
+    }
+
+    /// Add copies of all entries below `scanned_root` in `tree` below `index`, in the same order.
+    fn copy_entries_below(&mut self, index: TreeIndex, tree: &Tree, scanned_root: TreeIndex) {
+        let mut to_copy = vec![(scanned_root, index)];
+        while let Some((scanned_parent, parent)) = to_copy.pop() {
+            let mut children: Vec<_> = tree
+                .neighbors_directed(scanned_parent, Direction::Outgoing)
+                .collect();
+            children.reverse(); // neighbors come in the reverse order they were added in
+            for scanned_idx in children {
+                let name = self.tree.names.intern(tree.name_of(scanned_idx));
+                let idx = self.tree.add_node(EntryData {
+                    name,
+                    ..tree[scanned_idx].clone()
+                });
+                self.tree.add_edge(parent, idx, ());
+                to_copy.push((scanned_idx, idx));
+            }
+        }
+    }
+
+    /// The paths that were walked, in the order they were given.
+    pub fn root_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = self
+            .tree
+            .neighbors_directed(self.root_index, Direction::Outgoing)
+            .map(|idx| self.tree.name_of(idx).to_path_buf())
+            .collect();
+        // Neighbors are yielded in reverse order of insertion
+        paths.reverse();
+        paths
+    }
+
+    /// The entries directly within the directory at `index`, in the order they were walked in.
+    pub fn children(&self, index: TreeIndex) -> Vec<TreeIndex> {
+        let mut children: Vec<_> = self
+            .tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .collect();
+        children.reverse();
+        children
+    }
+
+    /// The entries directly within the directory at `index`, the largest first.
+    pub fn children_by_size(&self, index: TreeIndex) -> Vec<TreeIndex> {
+        let mut children = self.children(index);
+        children.sort_by(|a, b| self.tree[*b].size.cmp(&self.tree[*a].size));
+        children
+    }
+
+    /// All entries within the directory at `index` and its subdirectories, each directory before its entries.
+    pub fn descendants(&self, index: TreeIndex) -> impl Iterator<Item = TreeIndex> + '_ {
+        let tree = &*self.tree;
+        let mut dfs = Dfs::new(tree, index);
+        dfs.next(tree);
+        std::iter::from_fn(move || dfs.next(tree))
     }
 
-    fn recompute_root_size(&self) -> u128 {
+    /// The directory containing the entry at `index`, or `None` if it's the root.
+    pub fn parent(&self, index: TreeIndex) -> Option<TreeIndex> {
         self.tree
+            .neighbors_directed(index, Direction::Incoming)
+            .next()
+    }
+
+    /// The path of the entry at `index`, starting with the input path it was found in.
+    pub fn path_of(&self, index: TreeIndex) -> PathBuf {
+        path_of(&self.tree, index)
+    }
+
+    /// The entry at `path`, which has to start with one of the input paths as it was given, or `None` if there is
+    /// no such entry.
+    pub fn find(&self, path: &Path) -> Option<TreeIndex> {
+        self.children(self.root_index)
+            .into_iter()
+            .find_map(|input| {
+                let rest = path.strip_prefix(self.tree.name_of(input)).ok()?;
+                rest.components().try_fold(input, |parent, name| {
+                    let name = self.tree.names.get(Path::new(name.as_os_str()))?;
+                    self.tree
+                        .neighbors_directed(parent, Direction::Outgoing)
+                        .find(|idx| self.tree[*idx].name == name)
+                })
+            })
+    }
+
+    /// Collapse the smallest files into one entry per directory standing for all of them, until there are no more than
+    /// `max_entries` or no more files to collapse. Input paths and entries within archives are kept.
+    fn collapse_smallest_files(&mut self, max_entries: usize, byte_format: ByteFormat) {
+        let tree = &self.tree;
+        let mut files: Vec<_> = tree
+            .node_indices()
+            .filter(|idx| {
+                let entry = &tree[*idx];
+                !entry.is_dir
+                    && !entry.is_collapsed
+                    && !entry.is_in_archive
+                    && tree
+                        .neighbors_directed(*idx, Direction::Outgoing)
+                        .next()
+                        .is_none()
+            })
+            .filter_map(|idx| {
+                let parent = tree.neighbors_directed(idx, Direction::Incoming).next()?;
+                (parent != self.root_index).then_some((parent, idx))
+            })
+            .collect();
+        files.sort_by_key(|(_, idx)| tree[*idx].size);
+        files.truncate(tree.node_count().saturating_sub(max_entries));
+        files.sort();
+
+        for files in files.chunk_by(|(l, _), (r, _)| l == r) {
+            let parent = files[0].0;
+            let collapsed = self
+                .tree
+                .neighbors_directed(parent, Direction::Outgoing)
+                .find(|idx| self.tree[*idx].is_collapsed);
+            let (collapsed, mut amount) = match collapsed {
+                Some(idx) => (idx, self.tree[idx].entry_count + 1),
+                None => {
+                    let idx = self.tree.add_node(EntryData {
+                        is_collapsed: true,
+                        ..Default::default()
+                    });
+                    self.tree.add_edge(parent, idx, ());
+                    (idx, 0)
+                }
+            };
+            for (_, idx) in files {
+                let file = self
+                    .tree
+                    .remove_node(*idx)
+                    .expect("files to collapse to exist");
+                let entry = &mut self.tree[collapsed];
+                entry.size += file.size;
+                entry.alternate_size += file.alternate_size;
+                entry.mtime = entry.mtime.max(file.mtime);
+                entry.metadata_io_error |= file.metadata_io_error;
+                amount += 1;
+            }
+            let name = format!(
+                "(… {} more entr{}, {})",
+                amount,
+                if amount == 1 { "y" } else { "ies" },
+                byte_format.display(self.tree[collapsed].size.into())
+            );
+            let name = self.tree.names.intern(Path::new(&name));
+            let entry = &mut self.tree[collapsed];
+            entry.entry_count = amount - 1;
+            entry.name = name;
+            self.collapsed_entries += files.len() as u64;
+        }
+    }
+
+    /// Swap the size of all entries between their apparent size and their disk usage.
+    pub fn toggle_apparent_size(&mut self) {
+        for entry in self.tree.node_weights_mut() {
+            std::mem::swap(&mut entry.size, &mut entry.alternate_size);
+        }
+        self.apparent_size = !self.apparent_size;
+        if self.total_bytes.is_some() {
+            self.total_bytes = self
+                .tree
+                .node_weight(self.root_index)
+                .map(|w| w.size.into());
+        }
+    }
+
+    fn recompute_root_size(&self) -> Totals {
+        let mut sizes = Totals::default();
+        for idx in self
+            .tree
             .neighbors_directed(self.root_index, Direction::Outgoing)
-            .map(|idx| get_size_or_panic(&self.tree, idx))
-            .sum()
+        {
+            let entry = get_entry_or_panic(&self.tree, idx);
+            sizes += Totals {
+                size: entry.size,
+                alternate_size: entry.alternate_size,
+                entry_count: entry.entry_count + 1,
+                mtime: entry.mtime,
+            };
+        }
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::fs;
+
+    #[test]
+    fn entries_are_found_by_path_and_listed_by_size() -> Result<()> {
+        let root = TempDir::new("traverse")?;
+        fs::create_dir_all(root.join("dir/sub"))?;
+        fs::write(root.join("small"), vec![0; 10])?;
+        fs::write(root.join("dir/big"), vec![0; 100])?;
+        fs::write(root.join("dir/sub/medium"), vec![0; 50])?;
+
+        let traversal = WalkOptions::default()
+            .threads(1)
+            .apparent_size(true)
+            .traverse(vec![&root])?;
+        let top = traversal.find(&root).expect("the input path");
+        let dir = traversal.find(&root.join("dir")).expect("a directory");
+        let medium = traversal
+            .find(&root.join("dir/sub/medium"))
+            .expect("a file");
+        assert!(traversal.find(&root.join("dir/missing")).is_none());
+        assert_eq!(traversal.path_of(medium), root.join("dir/sub/medium"));
+        assert_eq!(traversal.parent(dir), Some(top));
+        assert_eq!(traversal.parent(traversal.root_index), None);
+
+        let names = |indices: Vec<TreeIndex>| -> Vec<_> {
+            indices
+                .into_iter()
+                .map(|idx| traversal.tree.name_of(idx).to_path_buf())
+                .collect()
+        };
+        assert_eq!(
+            names(traversal.children_by_size(dir)),
+            vec![PathBuf::from("big"), PathBuf::from("sub")]
+        );
+        assert_eq!(traversal.tree[dir].size, 150);
+        assert_eq!(traversal.descendants(top).count(), 5);
+        assert_eq!(traversal.children(top).len(), 2);
+        assert_eq!(
+            names(traversal.children_by_size(top)),
+            vec![PathBuf::from("dir"), PathBuf::from("small")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn modification_times_keep_whole_seconds_within_their_range() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_623_200_000_500);
+        assert_eq!(Mtime::from(time).secs(), 1_623_200_000);
+        assert_eq!(
+            SystemTime::from(Mtime::from(time)),
+            UNIX_EPOCH + Duration::from_secs(1_623_200_000)
+        );
+        assert_eq!(Mtime::from(UNIX_EPOCH - Duration::from_secs(1)).secs(), 0);
+        assert_eq!(Mtime::from_secs(u64::MAX).secs(), u64::from(u32::MAX - 1));
+        assert!(
+            Some(Mtime::from_secs(0)) > None,
+            "known times are the latest"
+        );
+        assert_eq!(std::mem::size_of::<Option<Mtime>>(), 4);
+    }
+
+    #[test]
+    fn the_smallest_files_are_collapsed_to_keep_fewer_entries() -> Result<()> {
+        let mut filesystem = crate::filesystem::MemoryFilesystem::default()
+            .block_size(1)
+            .file("root/big", 1000)
+            .dir("root/empty");
+        for idx in 0..20 {
+            filesystem = filesystem
+                .file(format!("root/logs/{:02}.log", idx), idx + 1)
+                .file(format!("root/tmp/{:02}", idx), 1);
+        }
+        let options = WalkOptions::default()
+            .apparent_size(true)
+            .byte_format(ByteFormat::Bytes)
+            .max_entries(Some(20));
+        let traversal = Traversal::from_filesystem_with_options(
+            &mut filesystem,
+            &options,
+            vec!["root".into()],
+            |_| Ok(false),
+        )?;
+        assert!(traversal.tree.node_count() <= 20);
+        assert!(traversal.collapsed_entries > 0);
+        let root = traversal.find(Path::new("root")).expect("the input path");
+        assert_eq!(
+            traversal.tree[root].size,
+            1000 + 210 + 20,
+            "sizes stay exact"
+        );
+        assert_eq!(
+            traversal.tree[root].entry_count, 44,
+            "and so do amounts of entries"
+        );
+        assert!(traversal.find(Path::new("root/big")).is_some());
+        assert!(traversal.find(Path::new("root/empty")).is_some());
+
+        let tmp = traversal.find(Path::new("root/tmp")).expect("a directory");
+        let collapsed: Vec<_> = traversal
+            .children(tmp)
+            .into_iter()
+            .filter(|idx| traversal.tree[*idx].is_collapsed)
+            .collect();
+        assert_eq!(
+            collapsed.len(),
+            1,
+            "there is one collapsed entry per directory"
+        );
+        let amount = traversal.tree[collapsed[0]].entry_count + 1;
+        assert_eq!(
+            traversal.tree.name_of(collapsed[0]),
+            Path::new(&format!("(… {} more entries, {} b)", amount, amount))
+        );
+        assert_eq!(traversal.children(tmp).len() as u32, 20 - amount + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn trees_of_any_depth_are_walked_saved_and_loaded() -> Result<()> {
+        const DEPTH: usize = 100_000;
+        /// A chain of directories with a single file at the bottom
+        struct Chain;
+        impl Filesystem for Chain {
+            fn walk(&mut self, path: &Path) -> io::Result<crate::filesystem::Walk<'_>> {
+                let path = path.to_owned();
+                Ok(Box::new((0..=DEPTH).map(move |depth| {
+                    Ok(crate::filesystem::FsEntry {
+                        depth,
+                        name: if depth == 0 {
+                            path.clone()
+                        } else {
+                            PathBuf::from("d")
+                        },
+                        is_dir: depth < DEPTH,
+                        is_symlink: false,
+                        is_broken_link: false,
+                        is_cache_dir_tag: false,
+                        is_archive: false,
+                        mtime: None,
+                        size: (depth == DEPTH).then_some(EntrySize {
+                            apparent: 10,
+                            disk_usage: Ok(10),
+                        }),
+                        metadata_error: None,
+                    })
+                })))
+            }
+        }
+        let root = PathBuf::from("chain");
+        let traversal =
+            Traversal::from_filesystem(&mut Chain, true, vec![root.clone()], |_| Ok(false))?;
+        let top = traversal.find(&root).expect("the walked path");
+        assert_eq!(traversal.tree[top].size, 10);
+        assert_eq!(traversal.tree[top].entry_count, DEPTH as u32);
+
+        let mut snapshot = Vec::new();
+        crate::snapshot::save(&mut snapshot, &traversal)?;
+        let loaded = crate::snapshot::load(&snapshot, &WalkOptions::default().apparent_size(true))?;
+        assert_eq!(loaded.tree.node_count(), traversal.tree.node_count());
+        let top = loaded.find(&root).expect("the loaded path");
+        assert_eq!(loaded.tree[top].entry_count, DEPTH as u32);
+
+        let mut export = Vec::new();
+        crate::ncdu::export(&mut export, &traversal)?;
+        let imported = crate::ncdu::import(&export, &WalkOptions::default().apparent_size(true))?;
+        assert_eq!(imported.tree.node_count(), traversal.tree.node_count());
+        let top = imported.find(&root).expect("the imported path");
+        assert_eq!(imported.tree[top].size, 10);
+
+        crate::report::html(std::io::sink(), &traversal)?;
+        Ok(())
     }
 }
@@ -0,0 +1,69 @@
+use crate::{crossdev, DirEntry, Progress, WalkOptions, INTERRUPTED};
+use std::{io, path::Path, sync::atomic::Ordering};
+
+/// A walk through input paths one after another, looking at each entry once without keeping a tree of them, and
+/// telling how far it got on a [progress line][Progress] meanwhile.
+pub(crate) struct Walk<'a, W: io::Write> {
+    walk_options: &'a WalkOptions,
+    parallelism: jwalk::Parallelism,
+    progress: Option<Progress<W>>,
+    entries_traversed: u64,
+    /// The bytes counted so far, if any are counted
+    bytes: Option<u128>,
+    /// If set, the walk was [interrupted][INTERRUPTED], and no more entries are visited.
+    pub is_incomplete: bool,
+}
+
+impl<'a, W: io::Write> Walk<'a, W> {
+    /// Prepare a walk with `walk_options`, drawing the progress line to `err` if set.
+    pub fn new(walk_options: &'a WalkOptions, err: Option<W>) -> io::Result<Self> {
+        Ok(Walk {
+            walk_options,
+            parallelism: walk_options.parallelism()?,
+            progress: err.map(|err| Progress::new(err, walk_options.byte_format)),
+            entries_traversed: 0,
+            bytes: None,
+            is_incomplete: false,
+        })
+    }
+
+    /// Walk `path` and call `visit` with each entry, or the error reading it, along with the device `path` is on.
+    /// `visit` returns the bytes it counts for the entry, if any, which add up to those on the progress line.
+    ///
+    /// Nothing is visited once the walk is incomplete.
+    pub fn path(
+        &mut self,
+        path: &Path,
+        mut visit: impl FnMut(Result<DirEntry, jwalk::Error>, u64) -> Option<u128>,
+    ) -> io::Result<()> {
+        if self.is_incomplete {
+            return Ok(());
+        }
+        let device_id = crossdev::init(path)?;
+        for entry in self
+            .walk_options
+            .iter_from_path(path, device_id, self.parallelism.clone())
+        {
+            if INTERRUPTED.load(Ordering::Relaxed) {
+                self.is_incomplete = true;
+                break;
+            }
+            self.entries_traversed += 1;
+            if let (Some(progress), Ok(entry)) = (self.progress.as_mut(), entry.as_ref()) {
+                progress.update(self.entries_traversed, self.bytes, &entry.parent_path);
+            }
+            if let Some(bytes) = visit(entry, device_id) {
+                *self.bytes.get_or_insert(0) += bytes;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the progress line, to write something else to the same terminal. It's removed when the walk is dropped
+    /// as well.
+    pub fn clear_progress(&mut self) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress.clear();
+        }
+    }
+}
@@ -81,6 +81,13 @@ WITH_FAILURE=1
       )
     )
 
+    (with "the json output format"
+      it "produces a JSON document with the tree of all entries, with children sorted by name" && {
+        WITH_SNAPSHOT="$snapshot/success-json-apparent-size" \
+        expect_run ${SUCCESSFULLY} "$exe" --apparent-size aggregate --format json dir
+      }
+    )
+
     (with "the byte format set"
       for format in binary bytes metric gb gib mb mib; do
         (with $format
@@ -92,6 +99,12 @@ WITH_FAILURE=1
       done
     )
   )
+  (with "top files counted by inodes"
+    it "fails as all files have one inode" && {
+      WITH_SNAPSHOT="$snapshot/failure-top-files-counting-inodes" \
+      expect_run ${WITH_FAILURE} "$exe" --count inodes top-files
+    }
+  )
   (with "interactive mode"
     it "fails as there is no TTY connected" && {
       WITH_SNAPSHOT="$snapshot/failure-interactive-without-tty" \